@@ -26,6 +26,14 @@ use subxt::{
     substitute_type(
         path = "entropy_shared::types::ValidatorInfo",
         with = "::subxt::utils::Static<::entropy_shared::ValidatorInfo>",
+    ),
+    substitute_type(
+        path = "entropy_shared::types::SignatureScheme",
+        with = "::subxt::utils::Static<::entropy_shared::SignatureScheme>",
+    ),
+    substitute_type(
+        path = "entropy_shared::types::RateLimit",
+        with = "::subxt::utils::Static<::entropy_shared::RateLimit>",
     )
 )]
 pub mod entropy {}
@@ -20,9 +20,16 @@ pub use crate::{
     errors::ClientError,
 };
 use anyhow::anyhow;
-pub use entropy_protocol::{sign_and_encrypt::EncryptedSignedMessage, KeyParams};
-use std::str::FromStr;
-pub use synedrion::KeyShare;
+pub use entropy_protocol::{
+    sign_and_encrypt::EncryptedSignedMessage, KeyParams, PartyId, SigningMode, ThresholdSignature,
+};
+pub use entropy_shared::{HashingAlgorithm, RateLimit, SignatureScheme};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+    time::Duration,
+};
+pub use synedrion::{ecdsa::SigningKey, KeyShare, ThresholdKeyShare};
 
 use crate::{
     chain_api::{
@@ -30,39 +37,113 @@ use crate::{
             self,
             runtime_types::{
                 bounded_collections::bounded_vec::BoundedVec,
+                pallet_parameters::module::SignersSize,
                 pallet_programs::pallet::ProgramInfo,
-                pallet_registry::pallet::{ProgramInstance, RegisteredInfo},
+                pallet_registry::pallet::{
+                    ProgramInstance, RegisteredInfo, RegistrationFailure, RegistrationStatus,
+                },
             },
         },
         EntropyConfig,
     },
     client::entropy::staking_extension::events::{EndpointChanged, ThresholdAccountChanged},
+    program_validation::{check_program_bytecode, check_program_config, check_schema},
     substrate::{query_chain, submit_transaction_with_pair},
-    user::{get_signers_from_chain, UserSignatureRequest},
+    user::{get_signers_from_chain, get_validators_info, ProgramEvaluation, UserSignatureRequest},
     Hasher,
 };
 
 use base64::prelude::{Engine, BASE64_STANDARD};
 use entropy_protocol::RecoverableSignature;
-use entropy_shared::HashingAlgorithm;
+use entropy_shared::{
+    user::ValidatorInfo, X25519PublicKey, ED25519_VERIFICATION_KEY_LENGTH, MESSAGE_HASH_HEADER,
+};
 use futures::{future, stream::StreamExt};
+use k256::schnorr;
+use rand_core::OsRng;
 use sp_core::{sr25519, Pair};
 use subxt::{
     backend::legacy::LegacyRpcMethods,
-    events::EventsClient,
-    utils::{AccountId32 as SubxtAccountId32, H256},
+    ext::codec::{Decode, Encode},
+    utils::{AccountId32 as SubxtAccountId32, Static, H256},
     Config, OnlineClient,
 };
-use synedrion::k256::ecdsa::{RecoveryId, Signature as k256Signature, VerifyingKey};
+use synedrion::{
+    bip32::DerivationPath,
+    k256::{
+        ecdsa::{RecoveryId, Signature as k256Signature, VerifyingKey},
+        Scalar,
+    },
+    signature::hazmat::PrehashVerifier,
+    DeriveChildKey,
+};
+use x25519_dalek::StaticSecret;
 
 pub const VERIFYING_KEY_LENGTH: usize = entropy_shared::VERIFICATION_KEY_LENGTH as usize;
 
-/// Register an account.
+/// The length of a `Registered` storage key before the SCALE encoded verifying key, which is the
+/// hashes of the pallet and storage item names followed by the Blake2-128 hash of the key
+const REGISTERED_KEY_PREFIX_LENGTH: usize = 48;
+
+/// How long to wait for each TSS server to respond to a signature request
+pub const SIGNING_RESPONSE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// The verifying key of an account, which determines how signatures made with it are checked
+enum AccountVerifyingKey {
+    Secp256k1(VerifyingKey),
+    Ed25519(frost_ed25519::VerifyingKey),
+}
+
+/// The outcome of a signature request, from the responses of all TSS servers in the signing
+/// committee
+#[derive(Debug, Clone)]
+pub struct SigningReport {
+    /// The signature returned by the most TSS servers, which has been verified against the
+    /// verifying key
+    pub signature: ThresholdSignature,
+    /// The TSS account of each server in the signing committee, with what it responded
+    pub responses: Vec<(SubxtAccountId32, SignerResponse)>,
+}
+
+impl SigningReport {
+    /// Whether every TSS server responded with the same valid signature
+    pub fn is_unanimous(&self) -> bool {
+        self.responses.iter().all(|(_, response)| *response == SignerResponse::Agreed)
+    }
+
+    /// The TSS accounts of the servers which did not respond with the agreed signature
+    pub fn dissenting_signers(&self) -> Vec<&SubxtAccountId32> {
+        self.responses
+            .iter()
+            .filter(|(_, response)| *response != SignerResponse::Agreed)
+            .map(|(tss_account, _)| tss_account)
+            .collect()
+    }
+}
+
+/// The response of a single TSS server to a signature request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerResponse {
+    /// Responded with the agreed signature
+    Agreed,
+    /// Responded with a valid signature, but not the one returned by most of the TSS servers
+    Disagreed,
+    /// Responded with an error, a bad attestation, or a signature which does not verify
+    Failed(String),
+    /// Did not respond within [SIGNING_RESPONSE_TIMEOUT]
+    TimedOut,
+}
+
+/// Register an account, with a key of the given signature scheme generated by the TSS servers.
 ///
-/// If successful, returns registration info including verfiying key.
+/// If successful, returns registration info including verfiying key, which is a 33 byte
+/// compressed secp256k1 key for ECDSA or a 32 byte Ed25519 key.
 ///
 /// If registering in private mode, a keyshare is also returned.
-#[allow(clippy::type_complexity)]
+///
+/// The key is shared amongst a signing committee of `total_signers` validators, `threshold` of
+/// which are needed to sign. These must be within the bounds of the `SignersInfo` parameters.
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(
     skip_all,
     fields(
@@ -76,7 +157,10 @@ pub async fn register(
     signature_request_keypair: sr25519::Pair,
     program_account: SubxtAccountId32,
     programs_data: BoundedVec<ProgramInstance>,
-) -> Result<([u8; VERIFYING_KEY_LENGTH], RegisteredInfo), ClientError> {
+    signature_scheme: SignatureScheme,
+    threshold: u8,
+    total_signers: u8,
+) -> Result<(Vec<u8>, RegisteredInfo), ClientError> {
     // Send register transaction
     put_register_request_on_chain(
         api,
@@ -84,34 +168,168 @@ pub async fn register(
         signature_request_keypair.clone(),
         program_account,
         programs_data,
+        signature_scheme,
+        threshold,
+        total_signers,
     )
     .await?;
 
     let account_id: SubxtAccountId32 = signature_request_keypair.public().into();
+    wait_for_registration(api, rpc, account_id).await
+}
 
+/// Registers an account with an existing ECDSA signing key, rather than having the threshold
+/// servers generate a new one
+///
+/// The key is split into a share for each validator in a signing committee of `total_signers`, and
+/// each share is sent encrypted to its TSS server. The TSS servers then reshare the key amongst
+/// themselves with the given `threshold`, so the shares made here can no longer be used to sign,
+/// and confirm the registration. The signing key itself should be discarded once this returns.
+#[allow(clippy::type_complexity)]
+#[tracing::instrument(
+    skip_all,
+    fields(
+        signature_request_account = ?signature_request_keypair.public(),
+        program_account,
+    )
+)]
+pub async fn import_key(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    signature_request_keypair: sr25519::Pair,
+    program_account: SubxtAccountId32,
+    programs_data: BoundedVec<ProgramInstance>,
+    signing_key: SigningKey,
+    threshold: u8,
+    total_signers: u8,
+) -> Result<(Vec<u8>, RegisteredInfo), ClientError> {
+    check_program_instances(api, rpc, &programs_data).await?;
+
+    let verifying_key = signing_key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+    let register_tx = entropy::tx().registry().register_with_imported_key(
+        program_account,
+        programs_data,
+        BoundedVec(verifying_key),
+        threshold,
+        total_signers,
+    );
+    submit_transaction_with_pair(api, rpc, &signature_request_keypair, &register_tx, None).await?;
+
+    // The signing committee is chosen by the chain when the registration is submitted
+    let account_id: SubxtAccountId32 = signature_request_keypair.public().into();
+    let registering_query = entropy::storage().registry().registering(&account_id);
+    let registering_details =
+        query_chain(api, rpc, registering_query, None).await?.ok_or(ClientError::NotRegistering)?;
+    let validators_info = get_validators_info(api, rpc, registering_details.committee).await?;
+
+    let party_ids: BTreeSet<PartyId> = validators_info
+        .iter()
+        .map(|validator_info| PartyId::new(validator_info.tss_account.clone()))
+        .collect();
+    let key_shares =
+        KeyShare::<KeyParams, PartyId>::new_centralized(&mut OsRng, &party_ids, Some(&signing_key));
+
+    let client = reqwest::Client::new();
+    for validator_info in validators_info {
+        let party_id = PartyId::new(validator_info.tss_account.clone());
+        let key_share = ThresholdKeyShare::from_key_share(&key_shares[&party_id]);
+        let encrypted_message = EncryptedSignedMessage::new(
+            &signature_request_keypair,
+            serde_json::to_vec(&key_share)?,
+            &validator_info.x25519_public_key,
+            &[],
+        )?;
+
+        let response = client
+            .post(format!("http://{}/user/import_key", validator_info.ip_address))
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&encrypted_message)?)
+            .send()
+            .await?;
+        if response.status() != 200 {
+            return Err(ClientError::KeyImportFailed(response.text().await?));
+        }
+    }
+
+    wait_for_registration(api, rpc, account_id).await
+}
+
+/// Polls the registration status of an account until it has either registered or failed
+/// registering, and returns the verifying key and registration info if it succeeded
+async fn wait_for_registration(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    account_id: SubxtAccountId32,
+) -> Result<(Vec<u8>, RegisteredInfo), ClientError> {
+    let mut registration_status = None;
     for _ in 0..50 {
-        let block_hash = rpc.chain_get_block_hash(None).await?;
-        let events =
-            EventsClient::new(api.clone()).at(block_hash.ok_or(ClientError::BlockHash)?).await?;
-        let registered_event = events.find::<entropy::registry::events::AccountRegistered>();
-        for event in registered_event.flatten() {
-            // check if the event belongs to this user
-            if event.0 == account_id {
-                let registered_query = entropy::storage().registry().registered(&event.1);
-                let registered_status = query_chain(api, rpc, registered_query, block_hash).await?;
-                if let Some(status) = registered_status {
-                    let verifying_key =
-                        event.1 .0.try_into().map_err(|_| ClientError::BadVerifyingKeyLength)?;
-                    return Ok((verifying_key, status));
-                }
-            }
+        registration_status = get_registration_status(api, rpc, account_id.clone()).await?;
+        match &registration_status {
+            Some(RegistrationStatus::Done(verifying_key)) => {
+                let registered_query = entropy::storage().registry().registered(verifying_key);
+                let registered_info = query_chain(api, rpc, registered_query, None)
+                    .await?
+                    .ok_or(ClientError::NotRegistered)?;
+                return Ok((verifying_key.0.clone(), registered_info));
+            },
+            Some(RegistrationStatus::Failed(failure)) => {
+                let reason = match failure {
+                    RegistrationFailure::MismatchedVerifyingKeys => {
+                        "threshold servers generated different verifying keys"
+                    },
+                    RegistrationFailure::DkgTimedOut => {
+                        "not every threshold server confirmed distributed key generation in time"
+                    },
+                    RegistrationFailure::InvalidProgramConfig => {
+                        "a program configuration does not match its configuration schema"
+                    },
+                };
+                return Err(ClientError::RegistrationFailed(reason.to_string()));
+            },
+            _ => {},
         }
         std::thread::sleep(std::time::Duration::from_millis(1000));
     }
-    Err(ClientError::RegistrationTimeout)
+    Err(ClientError::RegistrationTimeout(format!("{:?}", registration_status)))
+}
+
+/// Get the status of the most recent registration of an account, if it has registered
+pub async fn get_registration_status(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    account_id: SubxtAccountId32,
+) -> Result<Option<RegistrationStatus>, ClientError> {
+    let block_hash = rpc.chain_get_block_hash(None).await?.ok_or(ClientError::BlockHash)?;
+    let status_call = entropy::apis().registry_api().registration_status(account_id);
+    Ok(api.runtime_api().at(block_hash).call(status_call).await?)
+}
+
+/// Get the bounds on the signing committee an account can register with, which are the minimum
+/// `threshold` and the maximum `total_signers`
+pub async fn get_signers_info(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+) -> Result<SignersSize, ClientError> {
+    let signers_info_query = entropy::storage().parameters().signers_info();
+    query_chain(api, rpc, signers_info_query, None).await?.ok_or(ClientError::NoSignersInfo)
 }
 
 /// Request to sign a message
+///
+/// The request is sent to every TSS server in the signing committee. Each response is checked
+/// against the attestation of the server's TSS account, and the signature is checked against the
+/// verifying key - for BIP-340 using its x-only form. The returned [SigningReport] contains the
+/// signature given by the most servers, and what each server responded with.
+///
+/// The message is hashed with the given hashing algorithm. Where the client can compute the hash
+/// itself, signatures are checked against that. For custom hashes, which are computed by one of the
+/// account's programs, each signature is checked against the hash given by the TSS server.
+///
+/// If a BIP-32 derivation path is given, the message is signed with that child of the verifying key.
+/// This requires every program on the account to accept derivation paths.
+///
+/// Accounts with a 32 byte Ed25519 verifying key sign the message itself rather than its hash, and
+/// can only be used with [SigningMode::Ecdsa] and no derivation path.
 #[tracing::instrument(
     skip_all,
     fields(
@@ -126,93 +344,315 @@ pub async fn sign(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
     user_keypair: sr25519::Pair,
-    signature_verifying_key: [u8; VERIFYING_KEY_LENGTH],
+    signature_verifying_key: Vec<u8>,
     message: Vec<u8>,
+    hashing_algorithm: HashingAlgorithm,
     auxilary_data: Option<Vec<u8>>,
-) -> Result<RecoverableSignature, ClientError> {
-    let message_hash = Hasher::keccak(&message);
-    let validators_info = get_signers_from_chain(api, rpc).await?;
-    tracing::debug!("Validators info {:?}", validators_info);
+    signing_mode: SigningMode,
+    derivation_path: Option<String>,
+) -> Result<SigningReport, ClientError> {
+    if hashing_algorithm == HashingAlgorithm::Sha1 {
+        return Err(ClientError::Sha1NotSupported);
+    }
+    let message_hash = Hasher::hash(&hashing_algorithm, &message);
+    let verifying_key = if signature_verifying_key.len() == ED25519_VERIFICATION_KEY_LENGTH as usize
+    {
+        // The TSS servers reject derivation paths and BIP-340 for Ed25519 keys
+        AccountVerifyingKey::Ed25519(
+            frost_ed25519::VerifyingKey::deserialize(&signature_verifying_key)
+                .map_err(|_| ClientError::BadEd25519VerifyingKey)?,
+        )
+    } else {
+        let mut verifying_key = VerifyingKey::from_sec1_bytes(&signature_verifying_key)?;
+        if let Some(derivation_path) = &derivation_path {
+            verifying_key = derive_verifying_key(&verifying_key, derivation_path)?;
+        }
+        AccountVerifyingKey::Secp256k1(verifying_key)
+    };
     let block_number = rpc.chain_get_header(None).await?.ok_or(ClientError::BlockNumber)?.number;
+    let validators_info =
+        get_signers_from_chain(api, rpc, signature_verifying_key.clone(), block_number).await?;
+    tracing::debug!("Validators info {:?}", validators_info);
     let signature_request = UserSignatureRequest {
-        message: hex::encode(message),
+        message: hex::encode(&message),
         auxilary_data: Some(vec![auxilary_data.map(hex::encode)]),
         validators_info: validators_info.clone(),
         block_number,
-        hash: HashingAlgorithm::Keccak,
-        signature_verifying_key: signature_verifying_key.to_vec(),
+        hash: hashing_algorithm,
+        signature_verifying_key,
+        signing_mode,
+        derivation_path,
+        callback_url: None,
     };
 
     let signature_request_vec = serde_json::to_vec(&signature_request)?;
     let client = reqwest::Client::new();
 
     // Make http requests to TSS servers
-    let submit_transaction_requests = validators_info
+    let responses = future::join_all(validators_info.iter().map(|validator_info| {
+        request_signature(&client, validator_info, &user_keypair, signature_request_vec.clone())
+    }))
+    .await;
+
+    // Check each signature, keeping both its serialized form for comparison and the parsed
+    // signature to return
+    let outcomes = responses
+        .into_iter()
+        .map(|response| {
+            response.and_then(|(signature, server_message_hash)| {
+                let threshold_signature = match &verifying_key {
+                    AccountVerifyingKey::Secp256k1(verifying_key) => {
+                        let message_hash = message_hash
+                            .or(server_message_hash)
+                            .ok_or(ClientError::NoMessageHash)?;
+                        verify_threshold_signature(
+                            signature.clone(),
+                            &message_hash,
+                            verifying_key,
+                            signing_mode,
+                        )?
+                    },
+                    AccountVerifyingKey::Ed25519(verifying_key) => {
+                        verify_ed25519_signature(&signature, &message, verifying_key)?
+                    },
+                };
+                Ok((signature, threshold_signature))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // The agreed signature is the one returned by the most TSS servers
+    let agreed_signature = outcomes
         .iter()
-        .map(|validator_info| async {
-            let encrypted_message = EncryptedSignedMessage::new(
-                &user_keypair,
-                signature_request_vec.clone(),
-                &validator_info.x25519_public_key,
-                &[],
-            )?;
-            let message_json = serde_json::to_string(&encrypted_message)?;
-
-            let url = format!("http://{}/user/sign_tx", validator_info.ip_address);
-
-            let res = client
-                .post(url)
-                .header("Content-Type", "application/json")
-                .body(message_json)
-                .send()
-                .await;
-            Ok::<_, ClientError>(res)
+        .filter_map(|outcome| outcome.as_ref().ok())
+        .max_by_key(|(signature, _)| {
+            outcomes
+                .iter()
+                .filter(|outcome| matches!(outcome, Ok((other, _)) if other == signature))
+                .count()
+        })
+        .cloned();
+
+    let responses = validators_info
+        .iter()
+        .zip(outcomes)
+        .map(|(validator_info, outcome)| {
+            let signer_response = match (outcome, &agreed_signature) {
+                (Ok((signature, _)), Some((agreed, _))) if signature == *agreed => {
+                    SignerResponse::Agreed
+                },
+                (Ok(_), _) => SignerResponse::Disagreed,
+                (Err(ClientError::HttpRequest(error)), _) if error.is_timeout() => {
+                    SignerResponse::TimedOut
+                },
+                (Err(error), _) => SignerResponse::Failed(error.to_string()),
+            };
+            if signer_response != SignerResponse::Agreed {
+                tracing::warn!(
+                    "TSS server {} did not respond with the agreed signature: {:?}",
+                    validator_info.tss_account,
+                    signer_response
+                );
+            }
+            (validator_info.tss_account.clone(), signer_response)
         })
         .collect::<Vec<_>>();
 
-    // If we have a keyshare, connect to TSS servers
-    let results = future::try_join_all(submit_transaction_requests).await?;
+    let Some((_, signature)) = agreed_signature else {
+        // Give the reason the first TSS server failed, if it did not just time out
+        return Err(responses
+            .into_iter()
+            .find_map(|(_, response)| match response {
+                SignerResponse::Failed(error) => Some(ClientError::SigningFailed(error)),
+                _ => None,
+            })
+            .unwrap_or(ClientError::NoResponse));
+    };
 
-    // Get the first result
-    if let Some(res) = results.into_iter().next() {
-        let output = res?;
-        if output.status() != 200 {
-            return Err(ClientError::SigningFailed(output.text().await?));
-        }
+    Ok(SigningReport { signature, responses })
+}
 
-        let mut bytes_stream = output.bytes_stream();
-        let chunk = bytes_stream.next().await.ok_or(ClientError::NoResponse)??;
-        let signing_result: Result<(String, sr25519::Signature), String> =
-            serde_json::from_slice(&chunk)?;
-        let (signature_base64, signature_of_signature) =
-            signing_result.map_err(ClientError::SigningFailed)?;
-        tracing::debug!("Signature: {}", signature_base64);
-        let mut decoded_sig = BASE64_STANDARD.decode(signature_base64)?;
-
-        // Verify the response signature from the TSS client
-        if !sr25519::Pair::verify(
-            &signature_of_signature,
-            &decoded_sig,
-            &sr25519::Public(validators_info[0].tss_account.0),
-        ) {
-            return Err(ClientError::BadSignature);
-        }
+/// Checks whether a message would pass the programs of a registered account, without signing it
+///
+/// The request is evaluated by one of the account's signers, and the outcome for each program is
+/// returned in the same order as the account's programs.
+#[tracing::instrument(
+    skip_all,
+    fields(
+        user_account = ?user_keypair.public(),
+        signature_verifying_key,
+        message,
+    )
+)]
+pub async fn evaluate_programs(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    user_keypair: sr25519::Pair,
+    signature_verifying_key: Vec<u8>,
+    message: Vec<u8>,
+    hashing_algorithm: HashingAlgorithm,
+    auxilary_data: Option<Vec<u8>>,
+) -> Result<Vec<ProgramEvaluation>, ClientError> {
+    let block_number = rpc.chain_get_header(None).await?.ok_or(ClientError::BlockNumber)?.number;
+    let validators_info =
+        get_signers_from_chain(api, rpc, signature_verifying_key.clone(), block_number).await?;
+    let validator_info = validators_info.first().ok_or(ClientError::NoSyncedValidators)?.clone();
+    let signature_request = UserSignatureRequest {
+        message: hex::encode(message),
+        auxilary_data: Some(vec![auxilary_data.map(hex::encode)]),
+        validators_info,
+        block_number,
+        hash: hashing_algorithm,
+        signature_verifying_key,
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
+        callback_url: None,
+    };
+
+    let encrypted_message = EncryptedSignedMessage::new(
+        &user_keypair,
+        serde_json::to_vec(&signature_request)?,
+        &validator_info.x25519_public_key,
+        &[],
+    )?;
+
+    let response = reqwest::Client::new()
+        .post(format!("http://{}/user/evaluate", validator_info.ip_address))
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string(&encrypted_message)?)
+        .send()
+        .await?;
+    if response.status() != 200 {
+        return Err(ClientError::EvaluationFailed(response.text().await?));
+    }
+    Ok(response.json().await?)
+}
+
+/// Sends a signature request to a single TSS server, and returns the signature from its response
+/// once the attestation from the server's TSS account has been checked, along with the message
+/// hash given by the server
+async fn request_signature(
+    client: &reqwest::Client,
+    validator_info: &ValidatorInfo,
+    user_keypair: &sr25519::Pair,
+    signature_request_vec: Vec<u8>,
+) -> Result<(Vec<u8>, Option<[u8; 32]>), ClientError> {
+    let encrypted_message = EncryptedSignedMessage::new(
+        user_keypair,
+        signature_request_vec,
+        &validator_info.x25519_public_key,
+        &[],
+    )?;
+    let message_json = serde_json::to_string(&encrypted_message)?;
+
+    let url = format!("http://{}/user/sign_tx", validator_info.ip_address);
+
+    let output = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .body(message_json)
+        .timeout(SIGNING_RESPONSE_TIMEOUT)
+        .send()
+        .await?;
+    if output.status() != 200 {
+        return Err(ClientError::SigningFailed(output.text().await?));
+    }
 
-        let recovery_digit = decoded_sig.pop().ok_or(ClientError::NoRecoveryId)?;
-        let signature = k256Signature::from_slice(&decoded_sig)?;
-        let recovery_id =
-            RecoveryId::from_byte(recovery_digit).ok_or(ClientError::BadRecoveryId)?;
+    let message_hash = output
+        .headers()
+        .get(MESSAGE_HASH_HEADER)
+        .and_then(|header| hex::decode(header.as_bytes()).ok())
+        .and_then(|message_hash| message_hash.try_into().ok());
 
-        let verifying_key_of_signature =
-            VerifyingKey::recover_from_prehash(&message_hash, &signature, recovery_id)?;
-        tracing::debug!("Verifying Key {:?}", verifying_key_of_signature);
+    let mut bytes_stream = output.bytes_stream();
+    let chunk = bytes_stream.next().await.ok_or(ClientError::NoResponse)??;
+    let signing_result: Result<(String, sr25519::Signature), String> =
+        serde_json::from_slice(&chunk)?;
+    let (signature_base64, signature_of_signature) =
+        signing_result.map_err(ClientError::SigningFailed)?;
+    tracing::debug!("Signature from {}: {}", validator_info.tss_account, signature_base64);
+    let decoded_sig = BASE64_STANDARD.decode(signature_base64)?;
 
-        return Ok(RecoverableSignature { signature, recovery_id });
+    // Verify the response signature from the TSS client
+    if !sr25519::Pair::verify(
+        &signature_of_signature,
+        &decoded_sig,
+        &sr25519::Public(validator_info.tss_account.0),
+    ) {
+        return Err(ClientError::BadSignature);
     }
-    Err(ClientError::NoResponse)
+    Ok((decoded_sig, message_hash))
+}
+
+/// Parses a signature returned by a TSS server and checks that it is valid for the message hash
+/// and verifying key
+pub(crate) fn verify_threshold_signature(
+    mut decoded_sig: Vec<u8>,
+    message_hash: &[u8; 32],
+    verifying_key: &VerifyingKey,
+    signing_mode: SigningMode,
+) -> Result<ThresholdSignature, ClientError> {
+    if signing_mode == SigningMode::Bip340 {
+        let signature = schnorr::Signature::try_from(decoded_sig.as_slice())
+            .map_err(|_| ClientError::BadSignature)?;
+        // The x-only key is the compressed key without its first byte, which only gives the
+        // parity of the y coordinate
+        let x_only_verifying_key = schnorr::VerifyingKey::from_bytes(
+            &verifying_key.to_encoded_point(true).as_bytes()[1..],
+        )
+        .map_err(|_| ClientError::BadSignature)?;
+        x_only_verifying_key
+            .verify_prehash(message_hash, &signature)
+            .map_err(|_| ClientError::BadSignature)?;
+
+        return Ok(ThresholdSignature::Bip340(signature));
+    }
+
+    let recovery_digit = decoded_sig.pop().ok_or(ClientError::NoRecoveryId)?;
+    let signature = k256Signature::from_slice(&decoded_sig)?;
+    let recovery_id = RecoveryId::from_byte(recovery_digit).ok_or(ClientError::BadRecoveryId)?;
+
+    let verifying_key_of_signature =
+        VerifyingKey::recover_from_prehash(message_hash, &signature, recovery_id)?;
+    if verifying_key_of_signature != *verifying_key {
+        return Err(ClientError::BadSignature);
+    }
+
+    Ok(ThresholdSignature::Ecdsa(RecoverableSignature { signature, recovery_id }))
+}
+
+/// Parses an Ed25519 signature returned by a TSS server and checks that it is valid for the
+/// message and verifying key
+pub(crate) fn verify_ed25519_signature(
+    decoded_sig: &[u8],
+    message: &[u8],
+    verifying_key: &frost_ed25519::VerifyingKey,
+) -> Result<ThresholdSignature, ClientError> {
+    let signature = frost_ed25519::Signature::deserialize(decoded_sig)
+        .map_err(|_| ClientError::BadSignature)?;
+    verifying_key.verify(message, &signature).map_err(|_| ClientError::BadSignature)?;
+    Ok(ThresholdSignature::Ed25519(signature))
+}
+
+/// Derive the verifying key of a child key using a non-hardened BIP-32 derivation path, eg. `m/0/1`
+///
+/// This needs no contact with the TSS servers, so can be used to get an address for a child key
+/// before ever signing with it.
+pub fn derive_verifying_key(
+    verifying_key: &VerifyingKey,
+    derivation_path: &str,
+) -> Result<VerifyingKey, ClientError> {
+    let derivation_path = DerivationPath::from_str(derivation_path).map_err(ClientError::Bip32)?;
+    verifying_key.derive_verifying_key_bip32(&derivation_path).map_err(ClientError::Bip32)
 }
 
 /// Store a program on chain and return it's hash
+///
+/// `accepts_prehashed` declares that the program can evaluate prehashed messages, see
+/// [HashingAlgorithm::Prehashed], and `accepts_derivation_path` that it can evaluate requests to
+/// sign with a child key. Programs which accept derivation paths are always given their auxiliary
+/// data as a SCALE encoded [entropy_shared::VersionedAuxilaryData].
+#[allow(clippy::too_many_arguments)]
 #[tracing::instrument(
     skip_all,
     fields(
@@ -228,12 +668,20 @@ pub async fn store_program(
     configuration_interface: Vec<u8>,
     auxiliary_data_interface: Vec<u8>,
     oracle_data_pointer: Vec<u8>,
+    accepts_prehashed: bool,
+    accepts_derivation_path: bool,
 ) -> Result<<EntropyConfig as Config>::Hash, ClientError> {
+    check_program_bytecode(&program)?;
+    check_schema(&configuration_interface)?;
+    check_schema(&auxiliary_data_interface)?;
+
     let update_program_tx = entropy::tx().programs().set_program(
         program,
         configuration_interface,
         auxiliary_data_interface,
         oracle_data_pointer,
+        accepts_prehashed,
+        accepts_derivation_path,
     );
     let in_block =
         submit_transaction_with_pair(api, rpc, deployer_pair, &update_program_tx, None).await?;
@@ -242,31 +690,206 @@ pub async fn store_program(
 }
 
 /// Update the program pointers associated with a given entropy account
+///
+/// The change only takes effect once the account's signing committee have checked the program
+/// configurations against their schemas, so this waits until it has been applied or dropped.
 pub async fn update_programs(
     entropy_api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
-    verifying_key: [u8; VERIFYING_KEY_LENGTH],
+    verifying_key: Vec<u8>,
     deployer_pair: &sr25519::Pair,
     program_instance: BoundedVec<ProgramInstance>,
 ) -> Result<(), ClientError> {
+    check_program_instances(entropy_api, rpc, &program_instance).await?;
+    let verifying_key = BoundedVec(verifying_key);
     let update_pointer_tx = entropy::tx()
         .registry()
-        .change_program_instance(BoundedVec(verifying_key.to_vec()), program_instance);
+        .change_program_instance(verifying_key.clone(), program_instance.clone());
     submit_transaction_with_pair(entropy_api, rpc, deployer_pair, &update_pointer_tx, None).await?;
+
+    for _ in 0..50 {
+        let program_change_query =
+            entropy::storage().registry().pending_program_changes(&verifying_key);
+        if query_chain(entropy_api, rpc, program_change_query, None).await?.is_none() {
+            let registered_query = entropy::storage().registry().registered(&verifying_key);
+            let registered_info = query_chain(entropy_api, rpc, registered_query, None)
+                .await?
+                .ok_or(ClientError::NotRegistered)?;
+            // A refused or expired change leaves the programs as they were
+            if registered_info.programs_data.encode() != program_instance.encode() {
+                return Err(ClientError::ProgramChangeFailed);
+            }
+            return Ok(());
+        }
+        std::thread::sleep(std::time::Duration::from_millis(1000));
+    }
+    Err(ClientError::ProgramChangeTimeout)
+}
+
+/// Deregister an account, using its program modification account.
+///
+/// The threshold servers then delete their keyshares for the account's verifying key, which can
+/// be followed with the `KeyshareDeletionConfirmed` and `KeysharesDeleted` registry events.
+pub async fn deregister(
+    entropy_api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    verifying_key: Vec<u8>,
+    program_modification_pair: &sr25519::Pair,
+) -> Result<(), ClientError> {
+    let deregister_tx = entropy::tx().registry().deregister(BoundedVec(verifying_key));
+    submit_transaction_with_pair(entropy_api, rpc, program_modification_pair, &deregister_tx, None)
+        .await?;
+    Ok(())
+}
+
+/// Set or remove the signature request rate limit of an account, using its program modification
+/// account
+pub async fn set_rate_limit(
+    entropy_api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    verifying_key: Vec<u8>,
+    program_modification_pair: &sr25519::Pair,
+    rate_limit: Option<RateLimit>,
+) -> Result<(), ClientError> {
+    let set_rate_limit_tx =
+        entropy::tx().registry().set_rate_limit(BoundedVec(verifying_key), rate_limit.map(Static));
+    submit_transaction_with_pair(
+        entropy_api,
+        rpc,
+        program_modification_pair,
+        &set_rate_limit_tx,
+        None,
+    )
+    .await?;
     Ok(())
 }
+
+/// Set or remove the X25519 public key which TSS servers encrypt their keyshares of an account to
+/// when exporting them, using the account's program modification account
+///
+/// A new recovery key can only be used once the network's recovery key delay has passed.
+pub async fn set_recovery_key(
+    entropy_api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    verifying_key: Vec<u8>,
+    program_modification_pair: &sr25519::Pair,
+    recovery_key: Option<X25519PublicKey>,
+) -> Result<(), ClientError> {
+    let set_recovery_key_tx =
+        entropy::tx().registry().set_recovery_key(BoundedVec(verifying_key), recovery_key);
+    submit_transaction_with_pair(
+        entropy_api,
+        rpc,
+        program_modification_pair,
+        &set_recovery_key_tx,
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+/// Asks every TSS server in an account's signing committee to export its keyshare, using the
+/// account's program modification account
+///
+/// The exported keyshares are encrypted to the recovery key set with [set_recovery_key], so they
+/// can be kept as a backup and only decrypted with [decrypt_exported_keyshares] when needed.
+pub async fn export_keyshares(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    verifying_key: Vec<u8>,
+    program_modification_pair: &sr25519::Pair,
+) -> Result<Vec<EncryptedSignedMessage>, ClientError> {
+    let registered_query =
+        entropy::storage().registry().registered(BoundedVec(verifying_key.clone()));
+    let registered_info =
+        query_chain(api, rpc, registered_query, None).await?.ok_or(ClientError::NotRegistered)?;
+    let validators_info = get_validators_info(api, rpc, registered_info.committee).await?;
+    let client = reqwest::Client::new();
+
+    let mut exported_keyshares = Vec::new();
+    for validator_info in validators_info {
+        let encrypted_message = EncryptedSignedMessage::new(
+            program_modification_pair,
+            verifying_key.clone(),
+            &validator_info.x25519_public_key,
+            &[],
+        )?;
+
+        let response = client
+            .post(format!("http://{}/user/export_keyshare", validator_info.ip_address))
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&encrypted_message)?)
+            .send()
+            .await?;
+        if response.status() != 200 {
+            return Err(ClientError::KeyShareExportFailed(response.text().await?));
+        }
+        exported_keyshares.push(response.json().await?);
+    }
+    Ok(exported_keyshares)
+}
+
+/// Decrypts keyshares exported with [export_keyshares], giving each one with the party it
+/// belongs to, which is the TSS account that signed it
+pub fn decrypt_exported_keyshares(
+    exported_keyshares: &[EncryptedSignedMessage],
+    recovery_secret_key: &StaticSecret,
+) -> Result<BTreeMap<PartyId, ThresholdKeyShare<KeyParams, PartyId>>, ClientError> {
+    exported_keyshares
+        .iter()
+        .map(|exported_keyshare| {
+            let signed_message = exported_keyshare.decrypt(recovery_secret_key, &[])?;
+            let party_id = PartyId::new(SubxtAccountId32(*signed_message.account_id().as_ref()));
+            let key_share = serde_json::from_slice(&signed_message.message.0)?;
+            Ok((party_id, key_share))
+        })
+        .collect()
+}
+
+/// Combines exported keyshares into the private key they are shares of, for disaster recovery
+///
+/// At least as many keyshares as the threshold of the key must be given. Any that many are
+/// converted to additive shares, which sum to the private key, and the result is checked against
+/// the verifying key of the keyshares.
+pub fn recover_signing_key(
+    key_shares: &BTreeMap<PartyId, ThresholdKeyShare<KeyParams, PartyId>>,
+) -> Result<SigningKey, ClientError> {
+    let first_key_share = key_shares.values().next().ok_or(ClientError::NotEnoughKeyShares)?;
+    let verifying_key = first_key_share.verifying_key();
+    let threshold = first_key_share.threshold();
+    if key_shares.values().any(|key_share| key_share.verifying_key() != verifying_key) {
+        return Err(ClientError::MismatchedKeyShares);
+    }
+    if key_shares.len() < threshold {
+        return Err(ClientError::NotEnoughKeyShares);
+    }
+
+    let parties: BTreeSet<PartyId> = key_shares.keys().take(threshold).cloned().collect();
+    let secret = parties
+        .iter()
+        .map(|party_id| key_shares[party_id].to_key_share(&parties).secret_share())
+        .fold(Scalar::ZERO, |sum, secret_share| sum + secret_share);
+
+    let signing_key =
+        SigningKey::from_bytes(&secret.to_bytes()).map_err(|_| ClientError::BadRecoveredKey)?;
+    if signing_key.verifying_key() != &verifying_key {
+        return Err(ClientError::BadRecoveredKey);
+    }
+    Ok(signing_key)
+}
+
 /// Get info on all registered accounts
 pub async fn get_accounts(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
-) -> Result<Vec<([u8; VERIFYING_KEY_LENGTH], RegisteredInfo)>, ClientError> {
+) -> Result<Vec<(Vec<u8>, RegisteredInfo)>, ClientError> {
     let block_hash = rpc.chain_get_block_hash(None).await?.ok_or(ClientError::BlockHash)?;
     let storage_address = entropy::storage().registry().registered_iter();
     let mut iter = api.storage().at(block_hash).iter(storage_address).await?;
     let mut accounts = Vec::new();
     while let Some(Ok(kv)) = iter.next().await {
-        let key: [u8; VERIFYING_KEY_LENGTH] =
-            kv.key_bytes[kv.key_bytes.len() - VERIFYING_KEY_LENGTH..].try_into()?;
+        let key = Vec::<u8>::decode(&mut &kv.key_bytes[REGISTERED_KEY_PREFIX_LENGTH..])
+            .map_err(|_| ClientError::BadVerifyingKeyLength)?;
         accounts.push((key, kv.value))
     }
     Ok(accounts)
@@ -289,15 +912,62 @@ pub async fn get_programs(
     Ok(programs)
 }
 
+/// Checks that the programs given for an account exist, and that each configuration matches the
+/// configuration schema of its program
+pub async fn check_program_instances(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    program_instances: &BoundedVec<ProgramInstance>,
+) -> Result<(), ClientError> {
+    for program_instance in program_instances.0.iter() {
+        let program_info = get_program_info(api, rpc, &program_instance.program_pointer).await?;
+        check_program_config(&program_info.configuration_schema, &program_instance.program_config)?;
+    }
+    Ok(())
+}
+
+/// Gets a program by its pointer, which is either the hash of the program or a program family
+async fn get_program_info(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    program_pointer: &H256,
+) -> Result<ProgramInfo<<EntropyConfig as Config>::AccountId>, ClientError> {
+    let program_info_query = entropy::storage().programs().programs(program_pointer);
+    if let Some(program_info) = query_chain(api, rpc, program_info_query, None).await? {
+        return Ok(program_info);
+    }
+
+    let program_family_query = entropy::storage().programs().program_families(program_pointer);
+    let program_family = query_chain(api, rpc, program_family_query, None)
+        .await?
+        .ok_or(ClientError::NoProgramDefined(program_pointer.to_string()))?;
+
+    let program_info_query = entropy::storage().programs().programs(program_family.program_hash);
+    query_chain(api, rpc, program_info_query, None)
+        .await?
+        .ok_or(ClientError::NoProgramDefined(program_family.program_hash.to_string()))
+}
+
 /// Submit a register transaction
+#[allow(clippy::too_many_arguments)]
 pub async fn put_register_request_on_chain(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
     signature_request_keypair: sr25519::Pair,
     deployer: SubxtAccountId32,
     program_instance: BoundedVec<ProgramInstance>,
+    signature_scheme: SignatureScheme,
+    threshold: u8,
+    total_signers: u8,
 ) -> Result<(), ClientError> {
-    let registering_tx = entropy::tx().registry().register(deployer, program_instance);
+    check_program_instances(api, rpc, &program_instance).await?;
+    let registering_tx = entropy::tx().registry().register(
+        deployer,
+        program_instance,
+        Static(signature_scheme),
+        threshold,
+        total_signers,
+    );
 
     submit_transaction_with_pair(api, rpc, &signature_request_keypair, &registering_tx, None)
         .await?;
@@ -47,6 +47,23 @@ pub enum SubgroupGetError {
     JoinError(#[from] tokio::task::JoinError),
 }
 
+/// An error when checking a program or its configuration
+#[derive(Debug, Error)]
+pub enum ProgramValidationError {
+    #[error("Program bytecode is not valid WebAssembly: {0}")]
+    InvalidBytecode(String),
+    #[error("Program bytecode is a WebAssembly module rather than a component")]
+    NotAComponent,
+    #[error("Program does not export the function {0}")]
+    MissingExport(&'static str),
+    #[error("Schema is not a valid JSON schema: {0}")]
+    InvalidSchema(String),
+    #[error("Program configuration is not valid JSON: {0}")]
+    ConfigNotJson(String),
+    #[error("Program configuration does not match the configuration schema: {0}")]
+    ConfigMismatch(String),
+}
+
 #[cfg(feature = "full-client")]
 #[derive(Debug, Error)]
 pub enum ClientError {
@@ -64,8 +81,14 @@ pub enum ClientError {
     Utf8(#[from] std::str::Utf8Error),
     #[error("Subxt: {0}")]
     Subxt(#[from] subxt::Error),
-    #[error("Timed out waiting for register confirmation")]
-    RegistrationTimeout,
+    #[error("Timed out waiting for register confirmation, last status: {0}")]
+    RegistrationTimeout(String),
+    #[error("Registration failed: {0}")]
+    RegistrationFailed(String),
+    #[error("The program change was refused by the signing committee, or not confirmed in time")]
+    ProgramChangeFailed,
+    #[error("Timed out waiting for the signing committee to check the program change")]
+    ProgramChangeTimeout,
     #[error("Cannot get subgroup: {0}")]
     SubgroupGet(#[from] SubgroupGetError),
     #[error("JSON: {0}")]
@@ -92,6 +115,8 @@ pub enum ClientError {
     TryFromSlice(#[from] std::array::TryFromSliceError),
     #[error("User not registered")]
     NotRegistered,
+    #[error("User is not registering")]
+    NotRegistering,
     #[error("No synced validators")]
     NoSyncedValidators,
     #[error("Cannot confirm program was created")]
@@ -102,4 +127,30 @@ pub enum ClientError {
     CannotQuerySynced,
     #[error("Verifying key has incorrect length")]
     BadVerifyingKeyLength,
+    #[error("Verifying key is not a valid Ed25519 key")]
+    BadEd25519VerifyingKey,
+    #[error("BIP-32 error: {0}")]
+    Bip32(synedrion::bip32::Error),
+    #[error("TSS server did not give the hash of the message it signed")]
+    NoMessageHash,
+    #[error("SHA-1 gives a 20 byte hash, which cannot be signed")]
+    Sha1NotSupported,
+    #[error("TSS server rejected the imported keyshare: {0}")]
+    KeyImportFailed(String),
+    #[error("TSS server could not evaluate programs: {0}")]
+    EvaluationFailed(String),
+    #[error("No program defined at: {0}")]
+    NoProgramDefined(String),
+    #[error("Program validation: {0}")]
+    ProgramValidation(#[from] ProgramValidationError),
+    #[error("TSS server refused to export its keyshare: {0}")]
+    KeyShareExportFailed(String),
+    #[error("Fewer keyshares were given than the threshold of the key")]
+    NotEnoughKeyShares,
+    #[error("The keyshares do not all belong to the same key")]
+    MismatchedKeyShares,
+    #[error("The recovered private key does not match the verifying key of the keyshares")]
+    BadRecoveredKey,
+    #[error("Cannot get the signing committee bounds from chain")]
+    NoSignersInfo,
 }
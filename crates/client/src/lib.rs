@@ -16,6 +16,8 @@
 //! Since the TSS server communicates with the chain node, this is also a dependency of entropy-tss.
 pub mod chain_api;
 pub mod errors;
+#[cfg(feature = "program-validation")]
+pub mod program_validation;
 pub mod substrate;
 pub mod user;
 pub mod util;
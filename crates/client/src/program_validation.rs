@@ -0,0 +1,111 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Checks for programs and their configuration.
+//!
+//! The chain only checks that schemas, and the configuration of programs which have a schema, are
+//! well-formed JSON. The rest of these checks are made off-chain before uploading a program or
+//! choosing the programs of an account, as well as by the threshold servers before evaluating a
+//! program.
+use crate::errors::ProgramValidationError;
+use jsonschema::JSONSchema;
+use wasmparser::{Encoding, Parser, Payload, Validator, WasmFeatures};
+
+/// The functions which a program component must export
+pub const PROGRAM_EXPORTS: [&str; 2] = ["evaluate", "custom-hash"];
+
+/// Checks that program bytecode is a valid WebAssembly component with the functions which the
+/// program runtime calls
+pub fn check_program_bytecode(bytecode: &[u8]) -> Result<(), ProgramValidationError> {
+    let mut features = WasmFeatures::default();
+    features.set(WasmFeatures::COMPONENT_MODEL, true);
+    Validator::new_with_features(features)
+        .validate_all(bytecode)
+        .map_err(|error| ProgramValidationError::InvalidBytecode(error.to_string()))?;
+
+    // Nested modules and components are parsed too, so keep track of how deep we are in order to
+    // only look at the exports of the program itself
+    let mut depth = 0usize;
+    let mut exports = Vec::new();
+    for payload in Parser::new(0).parse_all(bytecode) {
+        match payload.map_err(|error| ProgramValidationError::InvalidBytecode(error.to_string()))? {
+            Payload::Version { encoding, .. } => {
+                if depth == 0 && encoding != Encoding::Component {
+                    return Err(ProgramValidationError::NotAComponent);
+                }
+                depth += 1;
+            },
+            Payload::End(_) => depth = depth.saturating_sub(1),
+            Payload::ComponentExportSection(reader) if depth == 1 => {
+                for export in reader {
+                    let export = export.map_err(|error| {
+                        ProgramValidationError::InvalidBytecode(error.to_string())
+                    })?;
+                    exports.push(export.name.0.to_string());
+                }
+            },
+            _ => {},
+        }
+    }
+
+    for name in PROGRAM_EXPORTS {
+        if !exports.iter().any(|export| export == name) {
+            return Err(ProgramValidationError::MissingExport(name));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that a configuration or auxiliary data schema is a valid JSON schema
+///
+/// An empty schema means that the program does not describe its interface, and is allowed.
+pub fn check_schema(schema: &[u8]) -> Result<(), ProgramValidationError> {
+    if schema.is_empty() {
+        return Ok(());
+    }
+    compile_schema(schema)?;
+    Ok(())
+}
+
+/// Checks that a program configuration matches the program's configuration schema
+///
+/// If the program has no configuration schema, any configuration is allowed.
+pub fn check_program_config(
+    configuration_schema: &[u8],
+    program_config: &[u8],
+) -> Result<(), ProgramValidationError> {
+    if configuration_schema.is_empty() {
+        return Ok(());
+    }
+    let schema = compile_schema(configuration_schema)?;
+    let config: serde_json::Value = serde_json::from_slice(program_config)
+        .map_err(|error| ProgramValidationError::ConfigNotJson(error.to_string()))?;
+
+    if let Err(errors) = schema.validate(&config) {
+        let messages = errors
+            .map(|error| format!("{} at '{}'", error, error.instance_path))
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(ProgramValidationError::ConfigMismatch(messages));
+    }
+    Ok(())
+}
+
+fn compile_schema(schema: &[u8]) -> Result<JSONSchema, ProgramValidationError> {
+    let schema: serde_json::Value = serde_json::from_slice(schema)
+        .map_err(|error| ProgramValidationError::InvalidSchema(error.to_string()))?;
+    JSONSchema::compile(&schema)
+        .map_err(|error| ProgramValidationError::InvalidSchema(error.to_string()))
+}
@@ -6,12 +6,25 @@ use crate::{
         get_api, get_rpc,
     },
     change_endpoint, change_threshold_accounts,
+    client::{verify_ed25519_signature, verify_threshold_signature},
+    derive_verifying_key,
+    errors::ProgramValidationError,
+    program_validation::{check_program_bytecode, check_program_config, check_schema},
+    recover_signing_key, sign, ClientError, Hasher, HashingAlgorithm, KeyParams, KeyShare, PartyId,
+    SigningMode, ThresholdKeyShare, ThresholdSignature,
 };
-use entropy_testing_utils::substrate_context::test_context_stationary;
+use entropy_protocol::decode_verifying_key;
+use entropy_shared::EVE_VERIFYING_KEY;
+use entropy_testing_utils::{
+    constants::TEST_PROGRAM_WASM_BYTECODE, substrate_context::test_context_stationary,
+};
+use rand_core::OsRng;
 use serial_test::serial;
 use sp_core::Pair;
 use sp_keyring::AccountKeyring;
+use std::collections::{BTreeMap, BTreeSet};
 use subxt::utils::AccountId32;
+use synedrion::k256::ecdsa::SigningKey;
 
 #[tokio::test]
 #[serial]
@@ -68,3 +81,171 @@ async fn test_change_threshold_accounts() {
         )
     );
 }
+
+#[test]
+fn test_derive_verifying_key() {
+    let verifying_key = decode_verifying_key(&EVE_VERIFYING_KEY).unwrap();
+
+    // Deriving in one go is the same as deriving one level at a time
+    let child = derive_verifying_key(&verifying_key, "m/0").unwrap();
+    let grandchild = derive_verifying_key(&verifying_key, "m/0/1").unwrap();
+    assert_eq!(derive_verifying_key(&child, "m/1").unwrap(), grandchild);
+    assert_ne!(child, verifying_key);
+    assert_ne!(child, grandchild);
+
+    // Hardened derivation needs the secret key
+    assert!(derive_verifying_key(&verifying_key, "m/0'").is_err());
+}
+
+#[test]
+fn test_verify_threshold_signature() {
+    let signing_key = SigningKey::from_slice(&[1; 32]).unwrap();
+    let other_verifying_key = *SigningKey::from_slice(&[2; 32]).unwrap().verifying_key();
+    let message_hash = Hasher::keccak(b"a message");
+
+    let (signature, recovery_id) = signing_key.sign_prehash_recoverable(&message_hash).unwrap();
+    let mut signature_bytes = signature.to_vec();
+    signature_bytes.push(recovery_id.to_byte());
+
+    let threshold_signature = verify_threshold_signature(
+        signature_bytes.clone(),
+        &message_hash,
+        signing_key.verifying_key(),
+        SigningMode::Ecdsa,
+    )
+    .unwrap();
+    assert!(matches!(threshold_signature, ThresholdSignature::Ecdsa(_)));
+
+    // A valid signature made with some other key is rejected
+    assert!(verify_threshold_signature(
+        signature_bytes,
+        &message_hash,
+        &other_verifying_key,
+        SigningMode::Ecdsa
+    )
+    .is_err());
+}
+
+#[test]
+fn test_verify_ed25519_signature() {
+    let signing_key = frost_ed25519::SigningKey::new(&mut OsRng);
+    let verifying_key = frost_ed25519::VerifyingKey::from(&signing_key);
+    let other_verifying_key =
+        frost_ed25519::VerifyingKey::from(&frost_ed25519::SigningKey::new(&mut OsRng));
+    let message = b"a message";
+
+    // Ed25519 signs the message itself rather than a hash of it
+    let signature_bytes = signing_key.sign(OsRng, message).serialize().unwrap();
+    let threshold_signature =
+        verify_ed25519_signature(&signature_bytes, message, &verifying_key).unwrap();
+    assert!(matches!(threshold_signature, ThresholdSignature::Ed25519(_)));
+
+    assert!(verify_ed25519_signature(&signature_bytes, b"another message", &verifying_key).is_err());
+    assert!(verify_ed25519_signature(&signature_bytes, message, &other_verifying_key).is_err());
+}
+
+#[test]
+fn test_hash() {
+    let message = b"a message";
+    assert_eq!(Hasher::hash(&HashingAlgorithm::Keccak, message), Some(Hasher::keccak(message)));
+    assert_eq!(
+        Hasher::hash(&HashingAlgorithm::Blake2_256, message),
+        Some(sp_core::hashing::blake2_256(message))
+    );
+    // Custom hashes can only be computed by the TSS server
+    assert_eq!(Hasher::hash(&HashingAlgorithm::Custom(0), message), None);
+    // SHA-1 does not give a 32 byte hash
+    assert_eq!(Hasher::hash(&HashingAlgorithm::Sha1, message), None);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_sign_sha1_fails() {
+    let substrate_context = test_context_stationary().await;
+    let api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+
+    let result = sign(
+        &api,
+        &rpc,
+        AccountKeyring::Dave.pair(),
+        EVE_VERIFYING_KEY.to_vec(),
+        b"a message".to_vec(),
+        HashingAlgorithm::Sha1,
+        None,
+        SigningMode::Ecdsa,
+        None,
+    )
+    .await;
+    assert!(matches!(result, Err(ClientError::Sha1NotSupported)));
+}
+
+#[test]
+fn test_recover_signing_key() {
+    let signing_key = SigningKey::from_slice(&[3; 32]).unwrap();
+    let party_ids: BTreeSet<PartyId> = (0..3).map(|i| PartyId::new(AccountId32([i; 32]))).collect();
+    let key_shares =
+        KeyShare::<KeyParams, PartyId>::new_centralized(&mut OsRng, &party_ids, Some(&signing_key));
+    let mut threshold_key_shares: BTreeMap<PartyId, ThresholdKeyShare<KeyParams, PartyId>> =
+        key_shares
+            .iter()
+            .map(|(party_id, key_share)| {
+                (party_id.clone(), ThresholdKeyShare::from_key_share(key_share))
+            })
+            .collect();
+
+    let recovered_key = recover_signing_key(&threshold_key_shares).unwrap();
+    assert_eq!(recovered_key, signing_key);
+
+    // Without enough keyshares the key cannot be recovered
+    threshold_key_shares.pop_first();
+    assert!(matches!(
+        recover_signing_key(&threshold_key_shares),
+        Err(ClientError::NotEnoughKeyShares)
+    ));
+}
+
+#[test]
+fn test_check_program_bytecode() {
+    assert!(check_program_bytecode(TEST_PROGRAM_WASM_BYTECODE).is_ok());
+
+    assert!(matches!(
+        check_program_bytecode(&[1, 2, 3]),
+        Err(ProgramValidationError::InvalidBytecode(_))
+    ));
+
+    // An empty core WebAssembly module
+    assert!(matches!(
+        check_program_bytecode(b"\0asm\x01\0\0\0"),
+        Err(ProgramValidationError::NotAComponent)
+    ));
+}
+
+#[test]
+fn test_check_program_config() {
+    let schema = br#"{
+        "type": "object",
+        "properties": { "allowed_addresses": { "type": "array", "items": { "type": "string" } } },
+        "required": ["allowed_addresses"]
+    }"#;
+    assert!(check_schema(schema).is_ok());
+    assert!(check_schema(&[]).is_ok());
+    assert!(matches!(check_schema(b"not json"), Err(ProgramValidationError::InvalidSchema(_))));
+    assert!(matches!(
+        check_schema(br#"{ "type": 5 }"#),
+        Err(ProgramValidationError::InvalidSchema(_))
+    ));
+
+    assert!(check_program_config(schema, br#"{ "allowed_addresses": ["abcd"] }"#).is_ok());
+    assert!(matches!(
+        check_program_config(schema, br#"{ "allowed_addresses": [5] }"#),
+        Err(ProgramValidationError::ConfigMismatch(_))
+    ));
+    assert!(matches!(
+        check_program_config(schema, b"not json"),
+        Err(ProgramValidationError::ConfigNotJson(_))
+    ));
+
+    // Without a schema any configuration is allowed
+    assert!(check_program_config(&[], b"not json").is_ok());
+}
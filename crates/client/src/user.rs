@@ -17,9 +17,9 @@ use crate::{
     chain_api::{entropy, EntropyConfig},
     substrate::query_chain,
 };
-use entropy_shared::{user::ValidatorInfo, BlockNumber, HashingAlgorithm};
+use entropy_shared::{user::ValidatorInfo, BlockNumber, HashingAlgorithm, SigningMode};
 use serde::{Deserialize, Serialize};
-use subxt::{backend::legacy::LegacyRpcMethods, OnlineClient};
+use subxt::{backend::legacy::LegacyRpcMethods, utils::AccountId32, OnlineClient};
 
 pub use crate::errors::SubgroupGetError;
 
@@ -38,25 +38,120 @@ pub struct UserSignatureRequest {
     pub hash: HashingAlgorithm,
     /// The veryfying key for the signature requested
     pub signature_verifying_key: Vec<u8>,
+    /// The kind of signature to produce, only used for secp256k1 keys. Defaults to ECDSA
+    #[serde(default)]
+    pub signing_mode: SigningMode,
+    /// An optional non-hardened BIP-32 derivation path (eg. `m/0/1`), to sign with a child key of
+    /// the verifying key rather than the verifying key itself. Programs receive the path along with
+    /// the auxilary data as a SCALE encoded [entropy_shared::VersionedAuxilaryData], so every
+    /// program on the account must accept derivation paths
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+    /// An optional URL which the result will be POSTed to when signing asynchronously with
+    /// `/user/sign_tx_async`. Ignored by other endpoints
+    #[serde(default)]
+    pub callback_url: Option<String>,
 }
 
+/// Represents an unparsed request to sign several messages with the same verifying key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserSignatureBatchRequest {
+    /// The messages to be signed, each evaluated against the account's programs separately
+    pub messages: Vec<BatchMessage>,
+    /// Information from the validators in signing party
+    pub validators_info: Vec<ValidatorInfo>,
+    /// When the message was created and signed
+    pub block_number: BlockNumber,
+    /// The veryfying key for the signatures requested
+    pub signature_verifying_key: Vec<u8>,
+    /// The kind of signatures to produce, only used for secp256k1 keys. Defaults to ECDSA
+    #[serde(default)]
+    pub signing_mode: SigningMode,
+    /// An optional non-hardened BIP-32 derivation path, used for all messages in the batch
+    #[serde(default)]
+    pub derivation_path: Option<String>,
+}
+
+/// A single message to be signed as part of a [UserSignatureBatchRequest]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchMessage {
+    /// Hex-encoded raw data to be signed
+    pub message: String,
+    /// Hex-encoded auxilary data for program evaluation, will not be signed
+    pub auxilary_data: Option<Vec<Option<String>>>,
+    /// Hashing algorithm to be used for signing
+    pub hash: HashingAlgorithm,
+}
+
+impl UserSignatureBatchRequest {
+    /// Split the batch into a [UserSignatureRequest] for each message, in order
+    pub fn into_requests(self) -> Vec<UserSignatureRequest> {
+        self.messages
+            .into_iter()
+            .map(|batch_message| UserSignatureRequest {
+                message: batch_message.message,
+                auxilary_data: batch_message.auxilary_data,
+                validators_info: self.validators_info.clone(),
+                block_number: self.block_number,
+                hash: batch_message.hash,
+                signature_verifying_key: self.signature_verifying_key.clone(),
+                signing_mode: self.signing_mode,
+                derivation_path: self.derivation_path.clone(),
+                callback_url: None,
+            })
+            .collect()
+    }
+}
+
+/// The outcome of evaluating one of an account's programs with `/user/evaluate`, which runs the
+/// programs without signing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramEvaluation {
+    /// The pointer of the program, as given in the account's programs
+    pub program_pointer: String,
+    /// Whether the program allowed the message to be signed
+    pub passed: bool,
+    /// The reason the program failed, if it did
+    pub error: Option<String>,
+    /// The amount of fuel used when evaluating the program
+    pub fuel_used: u64,
+}
+
+/// Gets the details of the validators which should sign a message with a registered verifying key,
+/// for a signature request made at the given block.
+///
+/// The signers are selected by the chain from the account's signing committee, so the client and
+/// the threshold servers always agree on who signs, and in which order.
 pub async fn get_signers_from_chain(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
+    verifying_key: Vec<u8>,
+    block_number: BlockNumber,
 ) -> Result<Vec<ValidatorInfo>, SubgroupGetError> {
-    let all_validators_query = entropy::storage().session().validators();
-    let mut validators = query_chain(api, rpc, all_validators_query, None)
+    let block_hash = rpc
+        .chain_get_block_hash(Some(block_number.into()))
+        .await?
+        .ok_or_else(|| SubgroupGetError::ChainFetch("Error getting block hash"))?;
+    let signers_call = entropy::apis().registry_api().signers(verifying_key);
+    let validators = api
+        .runtime_api()
+        .at(block_hash)
+        .call(signers_call)
         .await?
-        .ok_or_else(|| SubgroupGetError::ChainFetch("Get all validators error"))?;
+        .ok_or_else(|| SubgroupGetError::ChainFetch("Not registered"))?;
+
+    get_validators_info(api, rpc, validators).await
+}
+
+/// Gets the threshold server details of the given validators
+pub async fn get_validators_info(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    validators: Vec<AccountId32>,
+) -> Result<Vec<ValidatorInfo>, SubgroupGetError> {
     let block_hash = rpc.chain_get_block_hash(None).await?;
     let mut handles = Vec::new();
 
-    // TODO #898 For now we use a fix proportion of the number of validators as the threshold
-    let threshold = (validators.len() as f32 * 0.75) as usize;
-    // TODO #899 For now we just take the first t validators as the ones to perform signing
-    validators.sort();
-    validators.truncate(threshold);
-
     for validator in validators {
         let handle: tokio::task::JoinHandle<Result<ValidatorInfo, SubgroupGetError>> =
             tokio::task::spawn({
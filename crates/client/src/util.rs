@@ -14,7 +14,10 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 //! Utilities
 
-use sha3::{Digest, Keccak256};
+use entropy_shared::HashingAlgorithm;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256, Sha3_256};
+use sp_core::hashing::blake2_256;
 
 /// Produces a specific hash on a given message
 pub struct Hasher;
@@ -29,4 +32,21 @@ impl Hasher {
         keccak.update(data);
         keccak.finalize().into()
     }
+
+    /// Produces the hash of a given message in the same way as the TSS server does for the given
+    /// hashing algorithm.
+    ///
+    /// Returns `None` for custom hashes, which are computed by one of the account's programs, and
+    /// for which the hash given by the TSS server must be used. Also returns `None` for SHA-1, which
+    /// does not give a 32 byte hash and so cannot be signed.
+    pub fn hash(hashing_algorithm: &HashingAlgorithm, data: &[u8]) -> Option<[u8; 32]> {
+        match hashing_algorithm {
+            HashingAlgorithm::Sha2 => Some(Sha256::digest(data).into()),
+            HashingAlgorithm::Sha3 => Some(Sha3_256::digest(data).into()),
+            HashingAlgorithm::Keccak => Some(Self::keccak(data)),
+            HashingAlgorithm::Blake2_256 => Some(blake2_256(data)),
+            HashingAlgorithm::Prehashed => data.try_into().ok(),
+            _ => None,
+        }
+    }
 }
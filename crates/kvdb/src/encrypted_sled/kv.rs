@@ -192,6 +192,14 @@ impl EncryptedDb {
         self.decrypt(prev_val)
     }
 
+    /// Returns the keys which start with the given prefix, in order.
+    pub fn keys_with_prefix<P>(&self, prefix: P) -> EncryptedDbResult<Vec<IVec>>
+    where
+        P: AsRef<[u8]>,
+    {
+        Ok(self.kv.scan_prefix(prefix).keys().collect::<Result<_, sled::Error>>()?)
+    }
+
     /// Returns true if the database was recovered from a previous process.
     pub fn was_recovered(&self) -> bool {
         self.kv.was_recovered()
@@ -36,6 +36,8 @@ pub enum KvError {
     DeleteErr(InnerKvError),
     #[error("Exits Error: {0}")]
     ExistsErr(InnerKvError),
+    #[error("Keys Error: {0}")]
+    KeysErr(InnerKvError),
 }
 pub type KvResult<Success> = Result<Success, KvError>;
 
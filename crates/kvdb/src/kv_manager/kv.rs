@@ -23,7 +23,10 @@ use tokio::sync::{mpsc, oneshot};
 
 use super::{
     error::{InnerKvError, KvError::*, KvResult},
-    sled_bindings::{handle_delete, handle_exists, handle_get, handle_put, handle_reserve},
+    sled_bindings::{
+        handle_delete, handle_exists, handle_get, handle_keys_with_prefix, handle_put,
+        handle_reserve,
+    },
     types::{
         Command::{self, *},
         KeyReservation, DEFAULT_KV_NAME, DEFAULT_KV_PATH,
@@ -119,6 +122,16 @@ where
             .map_err(|e| SendErr(e.to_string()))?;
         resp_rx.await?.map_err(ExistsErr)
     }
+
+    /// Gets every key which starts with the given prefix, including reserved keys
+    /// Returns [KeysErr] or [SendErr] on failure.
+    pub async fn keys_with_prefix(&self, prefix: &str) -> KvResult<Vec<String>> {
+        let (resp_tx, resp_rx) = oneshot::channel();
+        self.sender
+            .send(KeysWithPrefix { prefix: prefix.to_string(), resp: resp_tx })
+            .map_err(|e| SendErr(e.to_string()))?;
+        resp_rx.await?.map_err(KeysErr)
+    }
 }
 
 /// Returns the db with name `db_name`, or creates a new if such DB does not exist
@@ -175,6 +188,9 @@ where
             Delete { key, resp } => {
                 handle_response(handle_delete(&kv, key), resp);
             },
+            KeysWithPrefix { prefix, resp } => {
+                handle_response(handle_keys_with_prefix(&kv, &prefix), resp);
+            },
         }
     }
 }
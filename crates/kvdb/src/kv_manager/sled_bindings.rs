@@ -109,6 +109,19 @@ where
     Ok(value)
 }
 
+/// Gets the keys which start with the given prefix.
+/// Returns [SledErr] on failure.
+pub(super) fn handle_keys_with_prefix(
+    kv: &encrypted_sled::Db,
+    prefix: &str,
+) -> InnerKvResult<Vec<String>> {
+    Ok(kv
+        .keys_with_prefix(prefix)?
+        .iter()
+        .map(|key| String::from_utf8_lossy(key).into_owned())
+        .collect())
+}
+
 /// Checks if a key exists in the kvstore.
 /// Returns [SledErr] of [LogicalErr] on failure.
 pub(super) fn handle_exists(kv: &encrypted_sled::Db, key: &str) -> InnerKvResult<bool> {
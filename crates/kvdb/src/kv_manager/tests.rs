@@ -25,7 +25,9 @@ use serial_test::serial;
 use super::{
     error::InnerKvError::LogicalErr,
     helpers::deserialize,
-    sled_bindings::{handle_exists, handle_get, handle_put, handle_reserve},
+    sled_bindings::{
+        handle_exists, handle_get, handle_keys_with_prefix, handle_put, handle_reserve,
+    },
     types::{KeyReservation, DEFAULT_RESERVE},
 };
 use crate::{
@@ -192,3 +194,21 @@ fn test_exists() {
     assert!(!exists.unwrap()); // check that the result is false
     clean_tests();
 }
+
+#[test]
+#[serial]
+fn test_keys_with_prefix() {
+    let kv = open_with_test_password().unwrap();
+
+    // no keys have been added yet
+    assert!(handle_keys_with_prefix(&kv, "JOB").unwrap().is_empty());
+
+    for key in ["JOB_2", "OTHER_1", "JOB_1"] {
+        let reservation = handle_reserve(&kv, key.to_string()).unwrap();
+        handle_put(&kv, reservation, "value".to_string()).unwrap();
+    }
+
+    // only keys with the prefix are returned, in order
+    assert_eq!(handle_keys_with_prefix(&kv, "JOB").unwrap(), vec!["JOB_1", "JOB_2"]);
+    clean_tests();
+}
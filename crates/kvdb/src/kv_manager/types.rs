@@ -69,4 +69,8 @@ pub(super) enum Command<V> {
         key: String,
         resp: Responder<()>,
     },
+    KeysWithPrefix {
+        prefix: String,
+        resp: Responder<Vec<String>>,
+    },
 }
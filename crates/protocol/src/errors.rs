@@ -136,6 +136,22 @@ pub enum ProtocolExecutionErr {
     BadVerifyingKey(String),
     #[error("Expected verifying key but got a protocol message")]
     UnexpectedMessage,
+    #[error("FROST error: {0}")]
+    Frost(#[from] frost_ed25519::Error),
+    #[error("Missing FROST package for party {0}")]
+    MissingFrostPackage(PartyId),
+    #[error("Party {0} got different FROST DKG commitments from us")]
+    FrostDkgEchoMismatch(PartyId),
+    #[error("BIP-340 signature error: {0}")]
+    Bip340(#[from] synedrion::signature::Error),
+    #[error("Nonce from party {0} does not match their commitment")]
+    Bip340NonceCommitmentMismatch(PartyId),
+    #[error("Bad BIP-340 signing message from party {0}")]
+    BadBip340Message(PartyId),
+    #[error("BIP-340 public key shares do not add up to the public key")]
+    Bip340PublicSharesMismatch,
+    #[error("Bad BIP-340 signature share from party {0}")]
+    BadBip340SignatureShare(PartyId),
 }
 
 #[derive(Debug, Error)]
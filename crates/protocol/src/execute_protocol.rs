@@ -15,10 +15,21 @@
 
 //! A wrapper for the threshold signing library to handle sending and receiving messages.
 
+use blake2::Blake2s256;
+use k256::{
+    elliptic_curve::{
+        ops::Reduce,
+        point::AffineCoordinates,
+        sec1::{FromEncodedPoint, ToEncodedPoint},
+        Field, PrimeField,
+    },
+    schnorr, AffinePoint, FieldBytes, ProjectivePoint, Scalar, U256,
+};
 use num::bigint::BigUint;
 use rand_core::{CryptoRngCore, OsRng};
+use sha2::{Digest, Sha256};
 use sp_core::{sr25519, Pair};
-use std::collections::VecDeque;
+use std::{collections::VecDeque, future::Future};
 use subxt::utils::AccountId32;
 use synedrion::{
     ecdsa::VerifyingKey,
@@ -26,20 +37,25 @@ use synedrion::{
     make_aux_gen_session, make_interactive_signing_session, make_key_init_session,
     make_key_resharing_session,
     sessions::{FinalizeOutcome, Session, SessionId as SynedrionSessionId},
-    signature::{self, hazmat::RandomizedPrehashSigner},
+    signature::{
+        self,
+        hazmat::{PrehashVerifier, RandomizedPrehashSigner},
+    },
     AuxInfo, KeyResharingInputs, KeyShare, NewHolder, OldHolder, PrehashedMessage,
     RecoverableSignature, ThresholdKeyShare,
 };
 use tokio::sync::mpsc;
+use zeroize::Zeroizing;
 
 use crate::{
     errors::{GenericProtocolError, ProtocolExecutionErr},
-    protocol_message::{ProtocolMessage, ProtocolMessagePayload},
+    protocol_message::{FrostMessage, FrostRound, ProtocolMessage, ProtocolMessagePayload},
     protocol_transport::Broadcaster,
-    DkgSubsession, KeyParams, KeyShareWithAuxInfo, PartyId, SessionId,
+    Bip340Presignature, DkgSubsession, Ed25519KeyShare, KeyParams, KeyShareWithAuxInfo, PartyId,
+    SessionId,
 };
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 
 pub type ChannelIn = mpsc::Receiver<ProtocolMessage>;
 pub type ChannelOut = Broadcaster;
@@ -156,6 +172,9 @@ pub async fn execute_protocol_generic<Res: synedrion::ProtocolResult>(
 }
 
 /// Execute threshold signing protocol.
+///
+/// The incoming message channel is given back along with the signature, so that another signing
+/// session can be run over the same connections.
 #[tracing::instrument(
     skip_all,
     fields(prehashed_message, threshold_accounts),
@@ -169,7 +188,7 @@ pub async fn execute_signing_protocol(
     prehashed_message: &PrehashedMessage,
     threshold_pair: &sr25519::Pair,
     threshold_accounts: Vec<AccountId32>,
-) -> Result<RecoverableSignature, ProtocolExecutionErr> {
+) -> Result<(RecoverableSignature, ChannelIn), ProtocolExecutionErr> {
     tracing::debug!("Executing signing protocol");
     tracing::trace!("Using key share with verifying key {:?}", &key_share.verifying_key());
 
@@ -191,7 +210,7 @@ pub async fn execute_signing_protocol(
     )
     .map_err(ProtocolExecutionErr::SessionCreation)?;
 
-    Ok(execute_protocol_generic(chans, session, session_id_hash).await?.0)
+    Ok(execute_protocol_generic(chans, session, session_id_hash).await?)
 }
 
 /// Execute dkg.
@@ -326,6 +345,75 @@ pub async fn execute_dkg(
     Ok((new_key_share, aux_info))
 }
 
+/// Execute the import of an existing ECDSA key.
+///
+/// Each party starts with its share of an n-of-n sharing of the key, made by the user. These are
+/// reshared to give a `threshold`-of-n key, so that the shares known to the user can no longer be
+/// used, and then aux info is generated.
+#[tracing::instrument(
+    skip_all,
+    fields(threshold_accounts, session_id, threshold),
+    level = tracing::Level::DEBUG
+)]
+pub async fn execute_key_import(
+    session_id: SessionId,
+    chans: Channels,
+    threshold_pair: &sr25519::Pair,
+    threshold_accounts: Vec<AccountId32>,
+    imported_key_share: ThresholdKeyShare<KeyParams, PartyId>,
+    threshold: usize,
+) -> Result<KeyShareWithAuxInfo, ProtocolExecutionErr> {
+    tracing::debug!("Executing key import");
+    let broadcaster = chans.0.clone();
+
+    let party_ids: BTreeSet<PartyId> =
+        threshold_accounts.iter().cloned().map(PartyId::new).collect();
+    let pair = PairWrapper(threshold_pair.clone());
+    let verifying_key = imported_key_share.verifying_key();
+
+    let inputs = KeyResharingInputs {
+        old_holder: Some(OldHolder { key_share: imported_key_share }),
+        new_holder: Some(NewHolder {
+            verifying_key,
+            old_threshold: party_ids.len(),
+            old_holders: party_ids.clone(),
+        }),
+        new_holders: party_ids.clone(),
+        new_threshold: threshold,
+    };
+
+    let session_id_hash = session_id.blake2(Some(DkgSubsession::Reshare))?;
+    let session = make_key_resharing_session(
+        &mut OsRng,
+        SynedrionSessionId::from_seed(session_id_hash.as_slice()),
+        pair.clone(),
+        &party_ids,
+        inputs,
+    )
+    .map_err(ProtocolExecutionErr::SessionCreation)?;
+    let (new_key_share_option, rx) =
+        execute_protocol_generic(chans, session, session_id_hash).await?;
+    let new_key_share =
+        new_key_share_option.ok_or(ProtocolExecutionErr::NoOutputFromReshareProtocol)?;
+    tracing::info!("Finished reshare protocol");
+
+    // Setup channels for the next session
+    let chans = Channels(broadcaster, rx);
+
+    let session_id_hash = session_id.blake2(Some(DkgSubsession::AuxGen))?;
+    let session = make_aux_gen_session(
+        &mut OsRng,
+        SynedrionSessionId::from_seed(session_id_hash.as_slice()),
+        pair,
+        &party_ids,
+    )
+    .map_err(ProtocolExecutionErr::SessionCreation)?;
+    let aux_info = execute_protocol_generic(chans, session, session_id_hash).await?.0;
+    tracing::info!("Finished aux gen protocol");
+
+    Ok((new_key_share, aux_info))
+}
+
 /// Execute proactive refresh.
 #[tracing::instrument(
     skip_all,
@@ -374,6 +462,828 @@ pub async fn execute_proactive_refresh(
     new_key_share.ok_or(ProtocolExecutionErr::NoOutputFromReshareProtocol)
 }
 
+/// Execute distributed key generation for an Ed25519 key using FROST.
+#[tracing::instrument(
+    skip_all,
+    fields(threshold_accounts, session_id, threshold),
+    level = tracing::Level::DEBUG
+)]
+pub async fn execute_frost_dkg(
+    session_id: SessionId,
+    mut chans: Channels,
+    threshold_pair: &sr25519::Pair,
+    threshold_accounts: Vec<AccountId32>,
+    threshold: usize,
+) -> Result<Ed25519KeyShare, ProtocolExecutionErr> {
+    tracing::debug!("Executing FROST DKG");
+    let session_id_hash = session_id.blake2(None)?;
+    let my_party_id = PartyId::new(AccountId32(threshold_pair.public().0));
+    let identifiers = frost_identifiers(threshold_accounts)?;
+    let my_identifier = *identifiers
+        .get(&my_party_id)
+        .ok_or_else(|| ProtocolExecutionErr::MissingFrostPackage(my_party_id.clone()))?;
+
+    let max_signers: u16 =
+        identifiers.len().try_into().map_err(|_| frost_ed25519::Error::InvalidMaxSigners)?;
+    let min_signers: u16 =
+        threshold.try_into().map_err(|_| frost_ed25519::Error::InvalidMinSigners)?;
+    let mut cached_messages = Vec::new();
+
+    // Round 1 - everyone gets the same commitment package
+    let (round1_secret_package, round1_package) =
+        frost_ed25519::keys::dkg::part1(my_identifier, max_signers, min_signers, OsRng)?;
+    let round1_package_bytes = bincode::serialize(&round1_package)?;
+    let outgoing = identifiers
+        .keys()
+        .filter(|party_id| **party_id != my_party_id)
+        .map(|party_id| (party_id.clone(), round1_package_bytes.clone()))
+        .collect();
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        outgoing,
+        session_id_hash,
+        FrostRound::DkgRound1,
+        &mut cached_messages,
+    )
+    .await?;
+
+    // Echo round - the commitment packages are sent to each party separately, so check that
+    // everyone got the same ones by comparing a hash of all of them
+    let mut hasher = Blake2s256::new();
+    for (party_id, package_bytes) in
+        received.iter().chain([(&my_party_id, &round1_package_bytes)]).collect::<BTreeMap<_, _>>()
+    {
+        hasher.update(party_id.0 .0);
+        hasher.update((package_bytes.len() as u64).to_le_bytes());
+        hasher.update(package_bytes);
+    }
+    let round1_packages_hash = hasher.finalize().to_vec();
+    let outgoing = identifiers
+        .keys()
+        .filter(|party_id| **party_id != my_party_id)
+        .map(|party_id| (party_id.clone(), round1_packages_hash.clone()))
+        .collect();
+    let echoed = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        outgoing,
+        session_id_hash,
+        FrostRound::DkgRound1Echo,
+        &mut cached_messages,
+    )
+    .await?;
+    if let Some((party_id, _)) = echoed.into_iter().find(|(_, hash)| *hash != round1_packages_hash)
+    {
+        return Err(ProtocolExecutionErr::FrostDkgEchoMismatch(party_id));
+    }
+
+    let round1_packages: BTreeMap<_, frost_ed25519::keys::dkg::round1::Package> =
+        from_frost_payloads(received, &identifiers)?;
+
+    // Round 2 - each party gets their own secret share
+    let (round2_secret_package, round2_packages_out) =
+        frost_ed25519::keys::dkg::part2(round1_secret_package, &round1_packages)?;
+    let mut outgoing = BTreeMap::new();
+    for (party_id, identifier) in
+        identifiers.iter().filter(|(party_id, _)| **party_id != my_party_id)
+    {
+        let package = round2_packages_out
+            .get(identifier)
+            .ok_or_else(|| ProtocolExecutionErr::MissingFrostPackage(party_id.clone()))?;
+        outgoing.insert(party_id.clone(), bincode::serialize(package)?);
+    }
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        outgoing,
+        session_id_hash,
+        FrostRound::DkgRound2,
+        &mut cached_messages,
+    )
+    .await?;
+    let round2_packages: BTreeMap<_, frost_ed25519::keys::dkg::round2::Package> =
+        from_frost_payloads(received, &identifiers)?;
+
+    let (key_package, public_key_package) = frost_ed25519::keys::dkg::part3(
+        &round2_secret_package,
+        &round1_packages,
+        &round2_packages,
+    )?;
+    tracing::info!("Finished FROST DKG");
+
+    Ok((key_package, public_key_package))
+}
+
+/// Execute threshold signing with an Ed25519 key using FROST.
+///
+/// Unlike ECDSA, the message itself is signed rather than a hash of it, since EdDSA does its own
+/// hashing. As with ECDSA, the incoming message channel is given back along with the signature.
+#[tracing::instrument(
+    skip_all,
+    fields(threshold_accounts),
+    level = tracing::Level::DEBUG
+)]
+pub async fn execute_frost_signing(
+    session_id: SessionId,
+    mut chans: Channels,
+    key_share: &Ed25519KeyShare,
+    message: &[u8],
+    threshold_pair: &sr25519::Pair,
+    threshold_accounts: Vec<AccountId32>,
+) -> Result<(frost_ed25519::Signature, ChannelIn), ProtocolExecutionErr> {
+    tracing::debug!("Executing FROST signing protocol");
+    let (key_package, public_key_package) = key_share;
+    let session_id_hash = session_id.blake2(None)?;
+    let my_party_id = PartyId::new(AccountId32(threshold_pair.public().0));
+    let identifiers = frost_identifiers(threshold_accounts)?;
+    let my_identifier = *identifiers
+        .get(&my_party_id)
+        .ok_or_else(|| ProtocolExecutionErr::MissingFrostPackage(my_party_id.clone()))?;
+    let mut cached_messages = Vec::new();
+
+    // Round 1 - share nonce commitments
+    let (nonces, commitments) =
+        frost_ed25519::round1::commit(key_package.signing_share(), &mut OsRng);
+    let commitments_bytes = bincode::serialize(&commitments)?;
+    let outgoing = identifiers
+        .keys()
+        .filter(|party_id| **party_id != my_party_id)
+        .map(|party_id| (party_id.clone(), commitments_bytes.clone()))
+        .collect();
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        outgoing,
+        session_id_hash,
+        FrostRound::SigningCommitments,
+        &mut cached_messages,
+    )
+    .await?;
+    let mut all_commitments: BTreeMap<_, frost_ed25519::round1::SigningCommitments> =
+        from_frost_payloads(received, &identifiers)?;
+    all_commitments.insert(my_identifier, commitments);
+
+    // Round 2 - share signature shares, so that every party can aggregate the signature
+    let signing_package = frost_ed25519::SigningPackage::new(all_commitments, message);
+    let signature_share = frost_ed25519::round2::sign(&signing_package, &nonces, key_package)?;
+    let signature_share_bytes = bincode::serialize(&signature_share)?;
+    let outgoing = identifiers
+        .keys()
+        .filter(|party_id| **party_id != my_party_id)
+        .map(|party_id| (party_id.clone(), signature_share_bytes.clone()))
+        .collect();
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        outgoing,
+        session_id_hash,
+        FrostRound::SignatureShares,
+        &mut cached_messages,
+    )
+    .await?;
+    let mut signature_shares: BTreeMap<_, frost_ed25519::round2::SignatureShare> =
+        from_frost_payloads(received, &identifiers)?;
+    signature_shares.insert(my_identifier, signature_share);
+
+    let signature =
+        frost_ed25519::aggregate(&signing_package, &signature_shares, public_key_package)?;
+    Ok((signature, chans.1))
+}
+
+/// Execute threshold signing with a secp256k1 key share, giving a BIP-340 Schnorr signature.
+///
+/// This uses the same key shares as ECDSA signing, converted to additive shares for the signing
+/// parties.
+///
+/// Each party first gives the IDs of the presignatures made by [execute_bip340_presigning] which
+/// it holds for these parties. If every party holds some of the same ones, they all pick the same
+/// one, and only then is it taken out of wherever it is stored with `take_presignature`. Signing
+/// with it then takes one more round, rather than three. The presignature is used up whether or
+/// not signing succeeds.
+///
+/// Otherwise each party commits to a nonce before revealing it, so that nobody can choose their
+/// nonce based on the others. Parties also give their public key share, so that each signature
+/// share can be checked against the nonce and key share of whoever sent it.
+///
+/// As with ECDSA, the incoming message channel is given back along with the signature.
+#[tracing::instrument(
+    skip_all,
+    fields(threshold_accounts),
+    level = tracing::Level::DEBUG
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn execute_bip340_signing<TakePresignature, Taken>(
+    session_id: SessionId,
+    mut chans: Channels,
+    key_share: &KeyShare<KeyParams, PartyId>,
+    message_hash: &[u8; 32],
+    presignature_ids: Vec<[u8; 32]>,
+    take_presignature: TakePresignature,
+    threshold_pair: &sr25519::Pair,
+    threshold_accounts: Vec<AccountId32>,
+) -> Result<(schnorr::Signature, ChannelIn), ProtocolExecutionErr>
+where
+    TakePresignature: FnOnce([u8; 32]) -> Taken,
+    Taken: Future<Output = Option<Bip340Presignature>>,
+{
+    tracing::debug!("Executing BIP-340 signing protocol");
+    let session_id_hash = session_id.blake2(None)?;
+    let my_party_id = PartyId::new(AccountId32(threshold_pair.public().0));
+    let other_parties: Vec<PartyId> = threshold_accounts
+        .into_iter()
+        .map(PartyId::new)
+        .filter(|party_id| *party_id != my_party_id)
+        .collect();
+    let to_other_parties = |payload: Vec<u8>| -> BTreeMap<PartyId, Vec<u8>> {
+        other_parties.iter().map(|party_id| (party_id.clone(), payload.clone())).collect()
+    };
+    let mut cached_messages = Vec::new();
+
+    // BIP-340 public keys always have an even y coordinate, so if ours is odd we sign with the
+    // negated key
+    let public_key = *key_share.verifying_key().as_affine();
+    let negate_key = bool::from(public_key.y_is_odd());
+    let mut secret_share = Zeroizing::new(key_share.secret_share());
+    let public_share = (ProjectivePoint::GENERATOR * *secret_share).to_affine();
+    if negate_key {
+        *secret_share = -*secret_share;
+    }
+
+    // Presignature choice round - give the IDs of the presignatures we hold, and pick the one
+    // every party holds whose ID has the lowest hash together with the session ID hash. As every
+    // party sees the same IDs they all pick the same one, and sessions signing at the same time
+    // are unlikely to pick the same one.
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        to_other_parties(presignature_ids.concat()),
+        session_id_hash,
+        FrostRound::Bip340PresignatureChoice,
+        &mut cached_messages,
+    )
+    .await?;
+    let chosen_id =
+        common_bip340_presignature_ids(presignature_ids, received)?.into_iter().min_by_key(|id| {
+            let mut hasher = Blake2s256::new();
+            hasher.update(id);
+            hasher.update(session_id_hash);
+            <[u8; 32]>::from(hasher.finalize())
+        });
+
+    // Presigned round - with the chosen presignature, give its ID along with our public key share
+    // and signature share. If we could not take it out of the pool, for example as another
+    // session used it first, an empty message is sent. Without a presignature every party knows to
+    // skip this round.
+    if let Some(chosen_id) = chosen_id {
+        let signing_parties: BTreeSet<&PartyId> =
+            other_parties.iter().chain([&my_party_id]).collect();
+        let presignature = take_presignature(chosen_id).await.filter(|presignature| {
+            let matches = presignature.id == chosen_id
+                && presignature.parties().eq(signing_parties.iter().copied());
+            if !matches {
+                tracing::warn!(
+                    "Presignature does not match the one chosen - signing interactively"
+                );
+            }
+            matches
+        });
+        let mut presigned = None;
+        let mut presigned_payload = Vec::new();
+        if let Some(presignature) = presignature {
+            let (mut nonce, nonce_points) = bip340_presigned_nonces(
+                &presignature,
+                &my_party_id,
+                &public_key.x(),
+                message_hash,
+            )?;
+            let aggregate_nonce = nonce_points
+                .values()
+                .fold(ProjectivePoint::IDENTITY, |sum, nonce_point| sum + nonce_point)
+                .to_affine();
+            let negate_nonce = bool::from(aggregate_nonce.y_is_odd());
+            if negate_nonce {
+                *nonce = -*nonce;
+            }
+            let challenge = bip340_challenge(&aggregate_nonce.x(), &public_key.x(), message_hash);
+            let signature_share = *nonce + challenge * *secret_share;
+
+            presigned_payload.extend_from_slice(&presignature.id);
+            presigned_payload.extend_from_slice(public_share.to_encoded_point(true).as_bytes());
+            presigned_payload.extend_from_slice(&signature_share.to_bytes());
+            presigned =
+                Some((nonce_points, aggregate_nonce, negate_nonce, challenge, signature_share));
+        }
+        let received = execute_frost_round(
+            &mut chans,
+            &my_party_id,
+            to_other_parties(presigned_payload),
+            session_id_hash,
+            FrostRound::Bip340PresignedSignatureShares,
+            &mut cached_messages,
+        )
+        .await?;
+
+        // Every party sees the presignature ID given by each of the others, so if anyone could not
+        // use the chosen presignature, every party goes on to sign interactively
+        if let Some((nonce_points, aggregate_nonce, negate_nonce, challenge, signature_share)) =
+            presigned
+        {
+            if received.values().all(|payload| payload.len() > 32 && payload[..32] == chosen_id) {
+                let mut public_share_sum = ProjectivePoint::from(public_share);
+                let mut s = signature_share;
+                for (party_id, payload) in received {
+                    if payload.len() != 32 + 33 + 32 {
+                        return Err(ProtocolExecutionErr::BadBip340Message(party_id));
+                    }
+                    let (public_share_bytes, signature_share_bytes) = payload[32..].split_at(33);
+                    let public_share =
+                        ProjectivePoint::from(bip340_point(&party_id, public_share_bytes)?);
+                    public_share_sum += public_share;
+                    let nonce_point = nonce_points
+                        .get(&party_id)
+                        .ok_or_else(|| ProtocolExecutionErr::BadBip340Message(party_id.clone()))?;
+                    s += bip340_signature_share(
+                        &party_id,
+                        signature_share_bytes,
+                        if negate_nonce { -*nonce_point } else { *nonce_point },
+                        if negate_key { -public_share } else { public_share },
+                        challenge,
+                    )?;
+                }
+                if public_share_sum.to_affine() != public_key {
+                    return Err(ProtocolExecutionErr::Bip340PublicSharesMismatch);
+                }
+
+                let signature = bip340_signature(&aggregate_nonce, &s, &public_key, message_hash)?;
+                tracing::info!("Finished BIP-340 signing with a presignature");
+                return Ok((signature, chans.1));
+            }
+        }
+        tracing::warn!("Not every party has the chosen presignature - signing interactively");
+    }
+
+    // Round 1 - commit to a nonce, and give our public key share so that everyone can check our
+    // signature share
+    let mut nonce = Zeroizing::new(Scalar::random(&mut OsRng));
+    let nonce_point = (ProjectivePoint::GENERATOR * *nonce).to_affine();
+    let nonce_point_bytes = nonce_point.to_encoded_point(true).as_bytes().to_vec();
+    let mut commitment_and_public_share =
+        bip340_nonce_commitment(&session_id_hash, &nonce_point_bytes).to_vec();
+    commitment_and_public_share.extend_from_slice(public_share.to_encoded_point(true).as_bytes());
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        to_other_parties(commitment_and_public_share),
+        session_id_hash,
+        FrostRound::Bip340NonceCommitments,
+        &mut cached_messages,
+    )
+    .await?;
+    let mut commitments = BTreeMap::new();
+    let mut public_shares = BTreeMap::new();
+    let mut public_share_sum = ProjectivePoint::from(public_share);
+    for (party_id, payload) in received {
+        if payload.len() <= 32 {
+            return Err(ProtocolExecutionErr::BadBip340Message(party_id));
+        }
+        let (commitment, public_share_bytes) = payload.split_at(32);
+        let public_share = bip340_point(&party_id, public_share_bytes)?;
+        public_share_sum += public_share;
+        commitments.insert(party_id.clone(), commitment.to_vec());
+        public_shares.insert(party_id, public_share);
+    }
+    // The public key shares must add up to the public key, so that a bad signature share can be
+    // pinned on the party who sent it
+    if public_share_sum.to_affine() != public_key {
+        return Err(ProtocolExecutionErr::Bip340PublicSharesMismatch);
+    }
+
+    // Round 2 - reveal the nonce, and check everyone else's against their commitment
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        to_other_parties(nonce_point_bytes),
+        session_id_hash,
+        FrostRound::Bip340Nonces,
+        &mut cached_messages,
+    )
+    .await?;
+    let mut nonce_points = BTreeMap::new();
+    let mut aggregate_nonce = ProjectivePoint::from(nonce_point);
+    for (party_id, nonce_point_bytes) in received {
+        let commitment = bip340_nonce_commitment(&session_id_hash, &nonce_point_bytes);
+        if commitments.get(&party_id) != Some(&commitment.to_vec()) {
+            return Err(ProtocolExecutionErr::Bip340NonceCommitmentMismatch(party_id));
+        }
+        let point = bip340_point(&party_id, &nonce_point_bytes)?;
+        aggregate_nonce += point;
+        nonce_points.insert(party_id, point);
+    }
+    // As with the public key, the nonce point must have an even y coordinate
+    let aggregate_nonce = aggregate_nonce.to_affine();
+    let negate_nonce = bool::from(aggregate_nonce.y_is_odd());
+    if negate_nonce {
+        *nonce = -*nonce;
+    }
+
+    // Round 3 - share signature shares, which add up to the signature
+    let challenge = bip340_challenge(&aggregate_nonce.x(), &public_key.x(), message_hash);
+    let signature_share = *nonce + challenge * *secret_share;
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        to_other_parties(signature_share.to_bytes().to_vec()),
+        session_id_hash,
+        FrostRound::Bip340SignatureShares,
+        &mut cached_messages,
+    )
+    .await?;
+    let mut s = signature_share;
+    for (party_id, signature_share_bytes) in received {
+        let (Some(nonce_point), Some(public_share)) =
+            (nonce_points.get(&party_id), public_shares.get(&party_id))
+        else {
+            return Err(ProtocolExecutionErr::BadBip340Message(party_id));
+        };
+        let nonce_point = ProjectivePoint::from(*nonce_point);
+        let public_share = ProjectivePoint::from(*public_share);
+        s += bip340_signature_share(
+            &party_id,
+            &signature_share_bytes,
+            if negate_nonce { -nonce_point } else { nonce_point },
+            if negate_key { -public_share } else { public_share },
+            challenge,
+        )?;
+    }
+
+    let signature = bip340_signature(&aggregate_nonce, &s, &public_key, message_hash)?;
+    tracing::info!("Finished BIP-340 signing");
+
+    Ok((signature, chans.1))
+}
+
+/// Execute BIP-340 presigning, topping up the pool of presignatures held by the signing parties.
+///
+/// Each party first gives the IDs of the presignatures it holds for these parties. Only those held
+/// by every party can ever be used, so the others should be dropped, and enough new ones are made
+/// to bring the pool up to `pool_target`. The IDs held by every party are given back with the new
+/// presignatures. Presignatures do not depend on the message or the key, so this can run over the
+/// connections of a finished signing session, or in a session of its own, and the presignatures
+/// can be used by [execute_bip340_signing] with any key share these parties hold.
+///
+/// Each presignature holds two nonces from each party, and the nonce used to sign is a
+/// combination of these bound to the message and key. This stops anyone steering the nonce by
+/// choosing which message is signed with which presignature.
+///
+/// As with signing, the incoming message channel is given back along with the presignatures.
+#[tracing::instrument(
+    skip_all,
+    fields(threshold_accounts, pool_target),
+    level = tracing::Level::DEBUG
+)]
+pub async fn execute_bip340_presigning(
+    session_id: &SessionId,
+    mut chans: Channels,
+    threshold_pair: &sr25519::Pair,
+    threshold_accounts: Vec<AccountId32>,
+    presignature_ids: Vec<[u8; 32]>,
+    pool_target: usize,
+) -> Result<(Vec<Bip340Presignature>, BTreeSet<[u8; 32]>, ChannelIn), ProtocolExecutionErr> {
+    tracing::debug!("Executing BIP-340 presigning protocol");
+    let mut hasher = Blake2s256::new();
+    hasher.update(session_id.blake2(None)?);
+    hasher.update(b"presigning");
+    let session_id_hash: [u8; 32] = hasher.finalize().into();
+    let my_party_id = PartyId::new(AccountId32(threshold_pair.public().0));
+    let other_parties: Vec<PartyId> = threshold_accounts
+        .into_iter()
+        .map(PartyId::new)
+        .filter(|party_id| *party_id != my_party_id)
+        .collect();
+    let to_other_parties = |payload: Vec<u8>| -> BTreeMap<PartyId, Vec<u8>> {
+        other_parties.iter().map(|party_id| (party_id.clone(), payload.clone())).collect()
+    };
+    let mut cached_messages = Vec::new();
+
+    // Round 1 - give the IDs of the presignatures we hold, so that everyone agrees on which to
+    // keep and how many to make
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        to_other_parties(presignature_ids.concat()),
+        session_id_hash,
+        FrostRound::Bip340PresignatureIds,
+        &mut cached_messages,
+    )
+    .await?;
+    let common_ids = common_bip340_presignature_ids(presignature_ids, received)?;
+    let count = pool_target.saturating_sub(common_ids.len());
+    if count == 0 {
+        return Ok((Vec::new(), common_ids, chans.1));
+    }
+
+    // Round 2 - commit to two nonces for each presignature
+    let nonces: Vec<[Zeroizing<Scalar>; 2]> = (0..count)
+        .map(|_| {
+            [Zeroizing::new(Scalar::random(&mut OsRng)), Zeroizing::new(Scalar::random(&mut OsRng))]
+        })
+        .collect();
+    let mut my_commitments = Vec::with_capacity(count);
+    let mut commitments_payload = Vec::with_capacity(count * 66);
+    for [first_nonce, second_nonce] in nonces.iter() {
+        let first_commitment = (ProjectivePoint::GENERATOR * **first_nonce).to_affine();
+        let second_commitment = (ProjectivePoint::GENERATOR * **second_nonce).to_affine();
+        let first_commitment = first_commitment.to_encoded_point(true).as_bytes().to_vec();
+        let second_commitment = second_commitment.to_encoded_point(true).as_bytes().to_vec();
+        commitments_payload.extend_from_slice(&first_commitment);
+        commitments_payload.extend_from_slice(&second_commitment);
+        my_commitments.push((first_commitment, second_commitment));
+    }
+    let received = execute_frost_round(
+        &mut chans,
+        &my_party_id,
+        to_other_parties(commitments_payload),
+        session_id_hash,
+        FrostRound::Bip340PresignatureCommitments,
+        &mut cached_messages,
+    )
+    .await?;
+    let mut commitments: Vec<BTreeMap<PartyId, (Vec<u8>, Vec<u8>)>> =
+        my_commitments.into_iter().map(|c| BTreeMap::from([(my_party_id.clone(), c)])).collect();
+    for (party_id, payload) in received {
+        if payload.len() != count * 66 {
+            return Err(ProtocolExecutionErr::BadBip340Message(party_id));
+        }
+        for (index, party_commitments) in payload.chunks_exact(66).enumerate() {
+            let (first_commitment, second_commitment) = party_commitments.split_at(33);
+            bip340_point(&party_id, first_commitment)?;
+            bip340_point(&party_id, second_commitment)?;
+            commitments[index]
+                .insert(party_id.clone(), (first_commitment.to_vec(), second_commitment.to_vec()));
+        }
+    }
+
+    // The ID of each presignature is a hash of everyone's commitments to it
+    let presignatures = nonces
+        .into_iter()
+        .zip(commitments)
+        .enumerate()
+        .map(|(index, ([first_nonce, second_nonce], commitments))| {
+            let mut hasher = Blake2s256::new();
+            hasher.update(session_id_hash);
+            hasher.update((index as u64).to_le_bytes());
+            for (party_id, (first_commitment, second_commitment)) in commitments.iter() {
+                hasher.update(party_id.0 .0);
+                hasher.update(first_commitment);
+                hasher.update(second_commitment);
+            }
+            Bip340Presignature {
+                id: hasher.finalize().into(),
+                nonces: [first_nonce.to_bytes().into(), second_nonce.to_bytes().into()],
+                commitments,
+            }
+        })
+        .collect();
+    tracing::info!("Finished BIP-340 presigning");
+
+    Ok((presignatures, common_ids, chans.1))
+}
+
+/// The IDs of the BIP-340 presignatures held by every party, from our own IDs and the
+/// concatenated IDs received from each of the others
+fn common_bip340_presignature_ids(
+    presignature_ids: Vec<[u8; 32]>,
+    received: BTreeMap<PartyId, Vec<u8>>,
+) -> Result<BTreeSet<[u8; 32]>, ProtocolExecutionErr> {
+    let mut common_ids: BTreeSet<[u8; 32]> = presignature_ids.into_iter().collect();
+    for (party_id, payload) in received {
+        if payload.len() % 32 != 0 {
+            return Err(ProtocolExecutionErr::BadBip340Message(party_id));
+        }
+        let party_ids: BTreeSet<[u8; 32]> =
+            payload.chunks_exact(32).filter_map(|id| <[u8; 32]>::try_from(id).ok()).collect();
+        common_ids.retain(|id| party_ids.contains(id));
+    }
+    Ok(common_ids)
+}
+
+/// Commit to a BIP-340 signing nonce, so that it can be revealed in a later round
+fn bip340_nonce_commitment(session_id_hash: &[u8; 32], nonce_point_bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2s256::new();
+    hasher.update(session_id_hash);
+    hasher.update(nonce_point_bytes);
+    hasher.finalize().into()
+}
+
+/// Decode a point sent by another party during BIP-340 signing
+fn bip340_point(party_id: &PartyId, bytes: &[u8]) -> Result<AffinePoint, ProtocolExecutionErr> {
+    let encoded_point = EncodedPoint::from_bytes(bytes)
+        .map_err(|_| ProtocolExecutionErr::BadBip340Message(party_id.clone()))?;
+    Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded_point))
+        .ok_or_else(|| ProtocolExecutionErr::BadBip340Message(party_id.clone()))
+}
+
+/// The BIP-340 challenge, a tagged hash of the nonce point, public key and message
+fn bip340_challenge(nonce_x: &FieldBytes, public_key_x: &FieldBytes, message: &[u8]) -> Scalar {
+    let tag = Sha256::digest(b"BIP0340/challenge");
+    let hash = Sha256::new()
+        .chain_update(tag)
+        .chain_update(tag)
+        .chain_update(nonce_x)
+        .chain_update(public_key_x)
+        .chain_update(message)
+        .finalize();
+    <Scalar as Reduce<U256>>::reduce_bytes(&hash)
+}
+
+/// Each party's nonce point for a presignature, bound to the message and public key, along with
+/// our own nonce
+fn bip340_presigned_nonces(
+    presignature: &Bip340Presignature,
+    my_party_id: &PartyId,
+    public_key_x: &FieldBytes,
+    message_hash: &[u8; 32],
+) -> Result<(Zeroizing<Scalar>, BTreeMap<PartyId, ProjectivePoint>), ProtocolExecutionErr> {
+    let mut nonce_points = BTreeMap::new();
+    for (party_id, (first_commitment, second_commitment)) in presignature.commitments.iter() {
+        let binding_factor =
+            bip340_binding_factor(&presignature.id, party_id, public_key_x, message_hash);
+        let nonce_point = ProjectivePoint::from(bip340_point(party_id, first_commitment)?)
+            + ProjectivePoint::from(bip340_point(party_id, second_commitment)?) * binding_factor;
+        nonce_points.insert(party_id.clone(), nonce_point);
+    }
+
+    let [first_nonce, second_nonce] = presignature.nonces.map(|nonce| {
+        Zeroizing::new(Option::<Scalar>::from(Scalar::from_repr(nonce.into())).unwrap_or_default())
+    });
+    let binding_factor =
+        bip340_binding_factor(&presignature.id, my_party_id, public_key_x, message_hash);
+    let nonce = Zeroizing::new(*first_nonce + *second_nonce * binding_factor);
+    if nonce_points.get(my_party_id) != Some(&(ProjectivePoint::GENERATOR * *nonce)) {
+        return Err(ProtocolExecutionErr::BadBip340Message(my_party_id.clone()));
+    }
+    Ok((nonce, nonce_points))
+}
+
+/// The factor binding a party's presignature nonces to what is being signed, so that nobody can
+/// steer the nonce point by choosing which message is signed with the presignature
+fn bip340_binding_factor(
+    presignature_id: &[u8; 32],
+    party_id: &PartyId,
+    public_key_x: &FieldBytes,
+    message_hash: &[u8; 32],
+) -> Scalar {
+    let hash = Sha256::new()
+        .chain_update(b"entropy/bip340/presignature")
+        .chain_update(presignature_id)
+        .chain_update(party_id.0 .0)
+        .chain_update(public_key_x)
+        .chain_update(message_hash)
+        .finalize();
+    <Scalar as Reduce<U256>>::reduce_bytes(&hash)
+}
+
+/// Decode a BIP-340 signature share from another party, and check it satisfies
+/// s_i * G = R_i + e * X_i with their nonce point and public key share, each already negated
+/// wherever the aggregate nonce or public key were
+fn bip340_signature_share(
+    party_id: &PartyId,
+    signature_share_bytes: &[u8],
+    nonce_point: ProjectivePoint,
+    public_share: ProjectivePoint,
+    challenge: Scalar,
+) -> Result<Scalar, ProtocolExecutionErr> {
+    let signature_share_bytes: [u8; 32] = signature_share_bytes
+        .try_into()
+        .map_err(|_| ProtocolExecutionErr::BadBip340Message(party_id.clone()))?;
+    let signature_share =
+        Option::<Scalar>::from(Scalar::from_repr(signature_share_bytes.into()))
+            .ok_or_else(|| ProtocolExecutionErr::BadBip340Message(party_id.clone()))?;
+    if ProjectivePoint::GENERATOR * signature_share != nonce_point + public_share * challenge {
+        return Err(ProtocolExecutionErr::BadBip340SignatureShare(party_id.clone()));
+    }
+    Ok(signature_share)
+}
+
+/// Put together a BIP-340 signature from the aggregate nonce point and the sum of the signature
+/// shares, and check it against the public key
+fn bip340_signature(
+    aggregate_nonce: &AffinePoint,
+    s: &Scalar,
+    public_key: &AffinePoint,
+    message_hash: &[u8; 32],
+) -> Result<schnorr::Signature, ProtocolExecutionErr> {
+    let mut signature_bytes = aggregate_nonce.x().to_vec();
+    signature_bytes.extend_from_slice(&s.to_bytes());
+    let signature = schnorr::Signature::try_from(signature_bytes.as_slice())?;
+
+    let verifying_key = schnorr::VerifyingKey::from_bytes(&public_key.x())?;
+    verifying_key.verify_prehash(message_hash, &signature)?;
+    Ok(signature)
+}
+
+/// Send a message to each of the given parties for a round of a FROST protocol, and wait to receive
+/// one from each of them.
+///
+/// Messages for a later round of the same session are kept in `cached_messages`, and messages from
+/// other sessions are put back on the incoming message channel.
+async fn execute_frost_round(
+    chans: &mut Channels,
+    my_party_id: &PartyId,
+    outgoing: BTreeMap<PartyId, Vec<u8>>,
+    session_id_hash: [u8; 32],
+    round: FrostRound,
+    cached_messages: &mut Vec<(PartyId, FrostMessage)>,
+) -> Result<BTreeMap<PartyId, Vec<u8>>, ProtocolExecutionErr> {
+    for (party_id, payload) in outgoing.iter() {
+        chans.0.send(ProtocolMessage {
+            from: my_party_id.clone(),
+            to: party_id.clone(),
+            payload: ProtocolMessagePayload::Frost(FrostMessage {
+                session_id_hash,
+                round,
+                payload: payload.clone(),
+            }),
+        })?;
+    }
+
+    let mut received = BTreeMap::new();
+    let (this_round, later_rounds): (Vec<_>, Vec<_>) =
+        cached_messages.drain(..).partition(|(_, message)| message.round == round);
+    *cached_messages = later_rounds;
+    for (from, message) in this_round {
+        received.insert(from, message.payload);
+    }
+
+    let mut messages_for_later = VecDeque::new();
+    while received.len() < outgoing.len() {
+        let message = chans
+            .1
+            .recv()
+            .await
+            .ok_or_else(|| ProtocolExecutionErr::IncomingStream(format!("{:?}", round)))?;
+
+        match message.payload {
+            ProtocolMessagePayload::Frost(frost_message)
+                if frost_message.session_id_hash == session_id_hash =>
+            {
+                if !outgoing.contains_key(&message.from) {
+                    tracing::warn!("Got FROST message from unexpected party - ignoring");
+                } else if frost_message.round == round {
+                    received.insert(message.from, frost_message.payload);
+                } else {
+                    cached_messages.push((message.from, frost_message));
+                }
+            },
+            ProtocolMessagePayload::VerifyingKey(_) => {
+                tracing::warn!("Got verifying key during protocol - ignoring");
+            },
+            payload => {
+                tracing::warn!(
+                    "Got protocol message with incorrect session ID - putting back in queue"
+                );
+                messages_for_later.push_back(ProtocolMessage { payload, ..message });
+            },
+        }
+    }
+    // Put messages which were not for this session back onto the incoming message channel
+    for message in messages_for_later.into_iter() {
+        chans.0.incoming_sender.send(message).await?;
+    }
+
+    Ok(received)
+}
+
+/// Derive a FROST identifier for each party from their account ID
+fn frost_identifiers(
+    threshold_accounts: Vec<AccountId32>,
+) -> Result<BTreeMap<PartyId, frost_ed25519::Identifier>, ProtocolExecutionErr> {
+    threshold_accounts
+        .into_iter()
+        .map(|account| {
+            let identifier = frost_ed25519::Identifier::derive(&account.0)?;
+            Ok((PartyId::new(account), identifier))
+        })
+        .collect()
+}
+
+/// Deserialize the packages received in a FROST round and key them by FROST identifier
+fn from_frost_payloads<T: serde::de::DeserializeOwned>(
+    received: BTreeMap<PartyId, Vec<u8>>,
+    identifiers: &BTreeMap<PartyId, frost_ed25519::Identifier>,
+) -> Result<BTreeMap<frost_ed25519::Identifier, T>, ProtocolExecutionErr> {
+    received
+        .into_iter()
+        .map(|(party_id, payload)| {
+            let identifier = *identifiers
+                .get(&party_id)
+                .ok_or_else(|| ProtocolExecutionErr::MissingFrostPackage(party_id.clone()))?;
+            Ok((identifier, bincode::deserialize(&payload)?))
+        })
+        .collect()
+}
+
 /// Psuedo-randomly select a subset of the parties of size `threshold`
 fn get_key_init_parties(
     my_party_id: &PartyId,
@@ -21,12 +21,13 @@ mod protocol_message;
 pub mod protocol_transport;
 pub mod sign_and_encrypt;
 
-pub use entropy_shared::user::ValidatorInfo;
+pub use entropy_shared::{user::ValidatorInfo, SignatureScheme, SigningMode};
 pub use listener::Listener;
 pub use protocol_message::ProtocolMessage;
 
 extern crate alloc;
 use std::{
+    collections::BTreeMap,
     fmt,
     hash::{Hash, Hasher},
 };
@@ -45,6 +46,7 @@ use synedrion::{
     signature::{self, hazmat::PrehashVerifier},
     AuxInfo, ThresholdKeyShare,
 };
+use zeroize::Zeroize;
 
 /// Identifies a party participating in a protocol session
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
@@ -134,6 +136,37 @@ pub use synedrion::KeyShare;
 /// This is the keyshare payload which gets stored by entropy-tss
 pub type KeyShareWithAuxInfo = (ThresholdKeyShare<KeyParams, PartyId>, AuxInfo<KeyParams, PartyId>);
 
+/// This is the keyshare payload which gets stored by entropy-tss for accounts using Ed25519
+pub type Ed25519KeyShare = (frost_ed25519::keys::KeyPackage, frost_ed25519::keys::PublicKeyPackage);
+
+/// A message independent part of a BIP-340 signature, made ahead of time by the parties who will
+/// sign with it so that signing only takes two rounds, one of which agrees on the presignature.
+///
+/// It holds our own two secret nonces and every party's commitments to theirs. A presignature must
+/// never be used for more than one signature, as that would give away our key share.
+#[derive(Serialize, Deserialize)]
+pub struct Bip340Presignature {
+    /// Identifies the presignature, and is the same for each of the parties holding it
+    pub id: [u8; 32],
+    /// Our two secret nonces
+    nonces: [[u8; 32]; 2],
+    /// Each party's commitments to their two nonces, as compressed points
+    commitments: BTreeMap<PartyId, (Vec<u8>, Vec<u8>)>,
+}
+
+impl Bip340Presignature {
+    /// The parties who made the presignature, all of whom must sign with it
+    pub fn parties(&self) -> impl Iterator<Item = &PartyId> {
+        self.commitments.keys()
+    }
+}
+
+impl Drop for Bip340Presignature {
+    fn drop(&mut self) {
+        self.nonces.zeroize();
+    }
+}
+
 /// A secp256k1 signature from which we can recover the public key of the keypair used to create it
 #[derive(Clone, Debug)]
 pub struct RecoverableSignature {
@@ -154,15 +187,43 @@ impl RecoverableSignature {
     }
 }
 
+/// A signature produced by one of the threshold signing protocols
+#[derive(Clone, Debug)]
+pub enum ThresholdSignature {
+    /// A secp256k1 ECDSA signature
+    Ecdsa(RecoverableSignature),
+    /// An Ed25519 signature produced with FROST
+    Ed25519(frost_ed25519::Signature),
+    /// A secp256k1 BIP-340 Schnorr signature
+    Bip340(k256::schnorr::Signature),
+}
+
+impl ThresholdSignature {
+    /// Serialize the signature. For ECDSA this is 65 bytes (r, s and the recovery ID), for Ed25519
+    /// and BIP-340 it is the standard 64 byte encoding.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ProtocolExecutionErr> {
+        match self {
+            ThresholdSignature::Ecdsa(signature) => Ok(signature.to_rsv_bytes().to_vec()),
+            ThresholdSignature::Ed25519(signature) => Ok(signature.serialize()?),
+            ThresholdSignature::Bip340(signature) => Ok(signature.to_bytes().to_vec()),
+        }
+    }
+}
+
 /// An identifier to specify and particular protocol session
 #[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 pub enum SessionId {
     /// A distributed key generation protocol session for registering
-    Dkg { user: AccountId32, block_number: u32 },
+    Dkg { user: AccountId32, block_number: u32, signature_scheme: SignatureScheme },
+    /// A session for registering with an existing ECDSA key, which the user has split into shares
+    KeyImport { user: AccountId32, block_number: u32 },
     /// A proactive refresh session
     Reshare { verifying_key: Vec<u8>, block_number: u32 },
     /// A signing session
     Sign(SigningSessionInfo),
+    /// A session for topping up the BIP-340 presignatures of a set of signing parties, which the
+    /// parties start on their own at the given block
+    Presign { parties: Vec<AccountId32>, block_number: u32 },
 }
 
 /// Information to identify a particular signing protocol session
@@ -175,13 +236,24 @@ pub struct SigningSessionInfo {
     /// Account ID of the request author (in public access mode this may differ from the signature
     /// request account)
     pub request_author: AccountId32,
+    /// The signature scheme of the key being used
+    pub signature_scheme: SignatureScheme,
+    /// The kind of signature to produce with a secp256k1 key
+    pub signing_mode: SigningMode,
+    /// The BIP-32 derivation path of the child key to sign with, if any
+    pub derivation_path: Option<String>,
 }
 
 // This is needed because subxt's AccountId32 does not implement Hash
 impl Hash for SessionId {
     fn hash<H: Hasher>(&self, state: &mut H) {
         match self {
-            SessionId::Dkg { user, block_number } => {
+            SessionId::Dkg { user, block_number, signature_scheme } => {
+                user.0.hash(state);
+                block_number.hash(state);
+                signature_scheme.hash(state);
+            },
+            SessionId::KeyImport { user, block_number } => {
                 user.0.hash(state);
                 block_number.hash(state);
             },
@@ -193,6 +265,15 @@ impl Hash for SessionId {
                 signing_session_info.signature_verifying_key.hash(state);
                 signing_session_info.message_hash.hash(state);
                 signing_session_info.request_author.0.hash(state);
+                signing_session_info.signature_scheme.hash(state);
+                signing_session_info.signing_mode.hash(state);
+                signing_session_info.derivation_path.hash(state);
+            },
+            SessionId::Presign { parties, block_number } => {
+                for party in parties {
+                    party.0.hash(state);
+                }
+                block_number.hash(state);
             },
         }
     }
@@ -42,6 +42,51 @@ pub enum ProtocolMessagePayload {
     MessageBundle(Box<MessageBundle<sr25519::Signature>>),
     /// A verifying key for parties who were not present in the key init session
     VerifyingKey(Vec<u8>),
+    /// A message from one of the FROST Ed25519 protocols, or from BIP-340 signing
+    Frost(FrostMessage),
+}
+
+/// A message sent during the FROST Ed25519 key generation or signing protocols, or during BIP-340
+/// Schnorr signing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrostMessage {
+    /// Hash of the session ID, so that messages from other sessions can be put aside
+    pub session_id_hash: [u8; 32],
+    /// The protocol round this message belongs to
+    pub round: FrostRound,
+    /// The bincode serialized FROST package, or the raw bytes for BIP-340 signing
+    pub payload: Vec<u8>,
+}
+
+/// The rounds of the FROST Ed25519 protocols and of BIP-340 signing
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum FrostRound {
+    /// DKG round one - commitments, broadcast to all parties
+    DkgRound1,
+    /// DKG round one echo - a hash of every party's round one commitments, so that parties can
+    /// check they all got the same ones
+    DkgRound1Echo,
+    /// DKG round two - secret shares, sent to each party individually
+    DkgRound2,
+    /// Signing round one - nonce commitments
+    SigningCommitments,
+    /// Signing round two - signature shares
+    SignatureShares,
+    /// BIP-340 signing round one - commitments to nonces
+    Bip340NonceCommitments,
+    /// BIP-340 signing round two - nonces
+    Bip340Nonces,
+    /// BIP-340 signing round three - signature shares
+    Bip340SignatureShares,
+    /// BIP-340 signing with a presignature - the IDs of the presignatures each party holds, so
+    /// that they agree on which one to use
+    Bip340PresignatureChoice,
+    /// BIP-340 signing with a presignature - the presignature ID and signature shares
+    Bip340PresignedSignatureShares,
+    /// BIP-340 presigning round one - the IDs of the presignatures each party holds
+    Bip340PresignatureIds,
+    /// BIP-340 presigning round two - commitments to nonces
+    Bip340PresignatureCommitments,
 }
 
 impl TryFrom<&[u8]> for ProtocolMessage {
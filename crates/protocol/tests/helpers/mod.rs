@@ -17,15 +17,17 @@
 use anyhow::{anyhow, ensure};
 use entropy_protocol::{
     execute_protocol::{
-        execute_dkg, execute_proactive_refresh, execute_signing_protocol, Channels,
+        execute_bip340_presigning, execute_bip340_signing, execute_dkg, execute_frost_dkg,
+        execute_frost_signing, execute_key_import, execute_proactive_refresh,
+        execute_signing_protocol, Channels,
     },
     protocol_transport::{
         errors::WsError,
         noise::{noise_handshake_initiator, noise_handshake_responder},
         ws_to_channels, SubscribeMessage, WsChannels,
     },
-    KeyParams, KeyShareWithAuxInfo, Listener, PartyId, RecoverableSignature, SessionId,
-    ValidatorInfo,
+    Ed25519KeyShare, KeyParams, KeyShareWithAuxInfo, Listener, PartyId, RecoverableSignature,
+    SessionId, SignatureScheme, SigningMode, ValidatorInfo,
 };
 use entropy_shared::X25519PublicKey;
 use futures::future;
@@ -57,6 +59,9 @@ pub enum ProtocolOutput {
     Sign(RecoverableSignature),
     Reshare(ThresholdKeyShare<KeyParams, PartyId>),
     Dkg(KeyShareWithAuxInfo),
+    Ed25519Sign(frost_ed25519::Signature),
+    Ed25519Dkg(Ed25519KeyShare),
+    Bip340Sign(k256::schnorr::Signature, k256::schnorr::Signature),
 }
 
 impl fmt::Debug for ProtocolOutput {
@@ -75,6 +80,7 @@ pub async fn server(
     keyshare: Option<KeyShare<KeyParams, PartyId>>,
     threshold_keyshare: Option<ThresholdKeyShare<KeyParams, PartyId>>,
     aux_info: Option<AuxInfo<KeyParams, PartyId>>,
+    ed25519_keyshare: Option<Ed25519KeyShare>,
     threshold: usize,
 ) -> anyhow::Result<ProtocolOutput> {
     let account_id = AccountId32(pair.public().0);
@@ -115,8 +121,65 @@ pub async fn server(
         validators_info.iter().map(|validator_info| validator_info.tss_account.clone()).collect();
 
     match session_id.clone() {
+        SessionId::Sign(session_info)
+            if session_info.signature_scheme == SignatureScheme::Ed25519 =>
+        {
+            let (signature, _) = execute_frost_signing(
+                session_id,
+                channels,
+                &ed25519_keyshare.unwrap(),
+                &session_info.message_hash,
+                &pair,
+                tss_accounts,
+            )
+            .await?;
+            Ok(ProtocolOutput::Ed25519Sign(signature))
+        },
+        SessionId::Sign(session_info) if session_info.signing_mode == SigningMode::Bip340 => {
+            // Sign interactively, then make a presignature and sign again with it over the same
+            // connections
+            let keyshare = keyshare.unwrap();
+            let broadcaster = channels.0.clone();
+            let (signature, rx) = execute_bip340_signing(
+                session_id.clone(),
+                channels,
+                &keyshare,
+                &session_info.message_hash,
+                Vec::new(),
+                |_| async { None },
+                &pair,
+                tss_accounts.clone(),
+            )
+            .await?;
+            let (mut presignatures, common_ids, rx) = execute_bip340_presigning(
+                &session_id,
+                Channels(broadcaster.clone(), rx),
+                &pair,
+                tss_accounts.clone(),
+                Vec::new(),
+                1,
+            )
+            .await?;
+            ensure!(common_ids.is_empty(), "Expected no presignatures to be held already");
+            ensure!(presignatures.len() == 1, "Expected one presignature");
+            let presignature = presignatures.pop();
+            let presignature_ids =
+                presignature.iter().map(|presignature| presignature.id).collect();
+            let (presigned_signature, _) = execute_bip340_signing(
+                session_id,
+                Channels(broadcaster, rx),
+                &keyshare,
+                &session_info.message_hash,
+                presignature_ids,
+                |_| async move { presignature },
+                &pair,
+                tss_accounts,
+            )
+            .await?;
+            Ok(ProtocolOutput::Bip340Sign(signature, presigned_signature))
+        },
         SessionId::Sign(session_info) => {
-            let rsig = execute_signing_protocol(
+            let (rsig, _) = execute_signing_protocol(
                 session_id,
                 channels,
                 &keyshare.unwrap(),
@@ -141,11 +204,29 @@ pub async fn server(
             .await?;
             Ok(ProtocolOutput::Reshare(new_keyshare))
         },
+        SessionId::KeyImport { .. } => {
+            let keyshare_and_aux_info = execute_key_import(
+                session_id,
+                channels,
+                &pair,
+                tss_accounts,
+                threshold_keyshare.unwrap(),
+                threshold,
+            )
+            .await?;
+            Ok(ProtocolOutput::Dkg(keyshare_and_aux_info))
+        },
+        SessionId::Dkg { signature_scheme: SignatureScheme::Ed25519, .. } => {
+            let keyshare =
+                execute_frost_dkg(session_id, channels, &pair, tss_accounts, threshold).await?;
+            Ok(ProtocolOutput::Ed25519Dkg(keyshare))
+        },
         SessionId::Dkg { .. } => {
             let keyshare_and_aux_info =
                 execute_dkg(session_id, channels, &pair, tss_accounts, threshold).await?;
             Ok(ProtocolOutput::Dkg(keyshare_and_aux_info))
         },
+        SessionId::Presign { .. } => Err(anyhow!("Presigning is tested with BIP-340 signing")),
     }
 }
 
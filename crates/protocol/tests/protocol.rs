@@ -17,14 +17,21 @@
 //! to the number of cpus available. Note that these should be run in release mode to get a realistic
 //! idea of how long things take in production.
 
-use entropy_protocol::{KeyParams, PartyId, SessionId, SigningSessionInfo, ValidatorInfo};
+use entropy_protocol::{
+    Ed25519KeyShare, KeyParams, PartyId, SessionId, SignatureScheme, SigningMode,
+    SigningSessionInfo, ValidatorInfo,
+};
 use futures::future;
 use rand_core::OsRng;
 use serial_test::serial;
 use sp_core::{sr25519, Pair};
 use std::time::Instant;
 use subxt::utils::AccountId32;
-use synedrion::{ecdsa::VerifyingKey, AuxInfo, KeyShare, ThresholdKeyShare};
+use synedrion::{
+    ecdsa::{SigningKey, VerifyingKey},
+    signature::hazmat::PrehashVerifier,
+    AuxInfo, KeyShare, ThresholdKeyShare,
+};
 use tokio::{net::TcpListener, runtime::Runtime, sync::oneshot};
 use x25519_dalek::StaticSecret;
 
@@ -42,6 +49,15 @@ fn sign_protocol_with_time_logged() {
     })
 }
 
+#[test]
+#[serial]
+fn bip340_sign_protocol_with_time_logged() {
+    let cpus = num_cpus::get();
+    get_tokio_runtime(cpus).block_on(async {
+        test_bip340_sign_with_parties(cpus).await;
+    })
+}
+
 #[test]
 #[serial]
 fn refresh_protocol_with_time_logged() {
@@ -60,6 +76,17 @@ fn dkg_protocol_with_time_logged() {
     })
 }
 
+#[test]
+#[serial]
+fn key_import_protocol_with_time_logged() {
+    let cpus = num_cpus::get();
+    // The imported key is reshared t-of-n, so we need at least 3 parties
+    let parties = cpus.max(3);
+    get_tokio_runtime(cpus).block_on(async {
+        test_key_import_with_parties(parties).await;
+    })
+}
+
 #[test]
 #[serial]
 fn t_of_n_dkg_and_sign() {
@@ -71,6 +98,17 @@ fn t_of_n_dkg_and_sign() {
     })
 }
 
+#[test]
+#[serial]
+fn t_of_n_ed25519_dkg_and_sign() {
+    let cpus = num_cpus::get();
+    // For this test we need at least 3 parties
+    let parties = 3;
+    get_tokio_runtime(cpus).block_on(async {
+        test_ed25519_dkg_and_sign_with_parties(parties).await;
+    })
+}
+
 async fn test_sign_with_parties(num_parties: usize) {
     let (pairs, ids) = get_keypairs_and_ids(num_parties);
     let keyshares = KeyShare::<KeyParams, PartyId>::new_centralized(&mut OsRng, &ids, None);
@@ -84,6 +122,7 @@ async fn test_sign_with_parties(num_parties: usize) {
             keyshare: Some(keyshares[&PartyId::from(pair.public())].clone()),
             threshold_keyshare: None,
             aux_info: Some(aux_infos[&PartyId::from(pair.public())].clone()),
+            ed25519_keyshare: None,
         })
         .collect();
     let message_hash = [0u8; 32];
@@ -91,6 +130,9 @@ async fn test_sign_with_parties(num_parties: usize) {
         signature_verifying_key: verifying_key.to_encoded_point(true).as_bytes().to_vec(),
         message_hash,
         request_author: AccountId32([0u8; 32]),
+        signature_scheme: SignatureScheme::Ecdsa,
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
     });
     let threshold = parties.len();
     let mut outputs = test_protocol_with_parties(parties, session_id, threshold).await;
@@ -108,6 +150,45 @@ async fn test_sign_with_parties(num_parties: usize) {
     }
 }
 
+async fn test_bip340_sign_with_parties(num_parties: usize) {
+    let (pairs, ids) = get_keypairs_and_ids(num_parties);
+    let keyshares = KeyShare::<KeyParams, PartyId>::new_centralized(&mut OsRng, &ids, None);
+    let verifying_key = keyshares[&PartyId::from(pairs[0].public())].verifying_key();
+
+    let parties: Vec<_> = pairs
+        .iter()
+        .map(|pair| ValidatorSecretInfo {
+            pair: pair.clone(),
+            keyshare: Some(keyshares[&PartyId::from(pair.public())].clone()),
+            threshold_keyshare: None,
+            aux_info: None,
+            ed25519_keyshare: None,
+        })
+        .collect();
+    let message_hash = [1u8; 32];
+    let session_id = SessionId::Sign(SigningSessionInfo {
+        signature_verifying_key: verifying_key.to_encoded_point(true).as_bytes().to_vec(),
+        message_hash,
+        request_author: AccountId32([0u8; 32]),
+        signature_scheme: SignatureScheme::Ecdsa,
+        signing_mode: SigningMode::Bip340,
+        derivation_path: None,
+    });
+    let threshold = parties.len();
+    let mut outputs = test_protocol_with_parties(parties, session_id, threshold).await;
+    if let ProtocolOutput::Bip340Sign(signature, presigned_signature) = outputs.pop().unwrap() {
+        // Check both signatures against the x-only form of the verifying key
+        let x_only_verifying_key = k256::schnorr::VerifyingKey::from_bytes(
+            &verifying_key.to_encoded_point(true).x().unwrap(),
+        )
+        .unwrap();
+        assert!(x_only_verifying_key.verify_prehash(&message_hash, &signature).is_ok());
+        assert!(x_only_verifying_key.verify_prehash(&message_hash, &presigned_signature).is_ok());
+    } else {
+        panic!("Unexpected protocol output");
+    }
+}
+
 async fn test_refresh_with_parties(num_parties: usize) {
     let (pairs, ids) = get_keypairs_and_ids(num_parties);
     let keyshares = KeyShare::<KeyParams, PartyId>::new_centralized(&mut OsRng, &ids, None);
@@ -127,6 +208,7 @@ async fn test_refresh_with_parties(num_parties: usize) {
                 &keyshares[&PartyId::from(pair.public())],
             )),
             aux_info: None,
+            ed25519_keyshare: None,
         })
         .collect();
     let threshold = parties.len();
@@ -138,12 +220,53 @@ async fn test_refresh_with_parties(num_parties: usize) {
     }
 }
 
+async fn test_key_import_with_parties(num_parties: usize) {
+    let threshold = num_parties - 1;
+    if threshold < 2 {
+        panic!("Not enought parties to test threshold signing");
+    }
+    let (pairs, ids) = get_keypairs_and_ids(num_parties);
+    let signing_key = SigningKey::random(&mut OsRng);
+    // The user splits their key into an n-of-n sharing
+    let keyshares =
+        KeyShare::<KeyParams, PartyId>::new_centralized(&mut OsRng, &ids, Some(&signing_key));
+
+    let parties: Vec<_> = pairs
+        .iter()
+        .map(|pair| ValidatorSecretInfo {
+            pair: pair.clone(),
+            keyshare: None,
+            threshold_keyshare: Some(ThresholdKeyShare::from_key_share(
+                &keyshares[&PartyId::from(pair.public())],
+            )),
+            aux_info: None,
+            ed25519_keyshare: None,
+        })
+        .collect();
+    let session_id = SessionId::KeyImport { user: AccountId32([0; 32]), block_number: 0 };
+    let outputs = test_protocol_with_parties(parties, session_id, threshold).await;
+    for output in outputs.iter() {
+        if let ProtocolOutput::Dkg((keyshare, _aux_info)) = output {
+            assert_eq!(keyshare.verifying_key(), *signing_key.verifying_key());
+        } else {
+            panic!("Unexpected protocol output");
+        }
+    }
+
+    // The imported key is now held t-of-n, so `threshold` of the parties can sign with it
+    test_sign_with_threshold_keyshares(outputs, &pairs, &ids, threshold).await;
+}
+
 async fn test_dkg_with_parties(num_parties: usize) {
     let (pairs, _ids) = get_keypairs_and_ids(num_parties);
     let parties: Vec<_> =
         pairs.iter().map(|pair| ValidatorSecretInfo::pair_only(pair.clone())).collect();
     let threshold = parties.len();
-    let session_id = SessionId::Dkg { user: AccountId32([0; 32]), block_number: 0 };
+    let session_id = SessionId::Dkg {
+        user: AccountId32([0; 32]),
+        block_number: 0,
+        signature_scheme: SignatureScheme::Ecdsa,
+    };
     let mut outputs = test_protocol_with_parties(parties, session_id, threshold).await;
     if let ProtocolOutput::Dkg(_keyshare) = outputs.pop().unwrap() {
     } else {
@@ -159,9 +282,24 @@ async fn test_dkg_and_sign_with_parties(num_parties: usize) {
     let (pairs, ids) = get_keypairs_and_ids(num_parties);
     let dkg_parties =
         pairs.iter().map(|pair| ValidatorSecretInfo::pair_only(pair.clone())).collect();
-    let session_id = SessionId::Dkg { user: AccountId32([0; 32]), block_number: 0 };
+    let session_id = SessionId::Dkg {
+        user: AccountId32([0; 32]),
+        block_number: 0,
+        signature_scheme: SignatureScheme::Ecdsa,
+    };
     let outputs = test_protocol_with_parties(dkg_parties, session_id, threshold).await;
 
+    test_sign_with_threshold_keyshares(outputs, &pairs, &ids, threshold).await;
+}
+
+/// Sign with the first `threshold` of the given parties using the threshold keyshares output by
+/// DKG or key import, and check the signature against the shared verifying key
+async fn test_sign_with_threshold_keyshares(
+    outputs: Vec<ProtocolOutput>,
+    pairs: &[sr25519::Pair],
+    ids: &BTreeSet<PartyId>,
+    threshold: usize,
+) {
     let signing_committee = (0..threshold)
         .into_iter()
         .map(|i| pairs[i].clone())
@@ -170,7 +308,6 @@ async fn test_dkg_and_sign_with_parties(num_parties: usize) {
         .collect::<BTreeSet<_>>();
 
     let parties: Vec<ValidatorSecretInfo> = outputs
-        .clone()
         .into_iter()
         .filter_map(|output| {
             if let ProtocolOutput::Dkg((threshold_keyshare, aux_info)) = output {
@@ -185,6 +322,7 @@ async fn test_dkg_and_sign_with_parties(num_parties: usize) {
                         keyshare: Some(keyshare),
                         threshold_keyshare: None,
                         aux_info: Some(aux_info),
+                        ed25519_keyshare: None,
                     })
                 } else {
                     None
@@ -202,6 +340,9 @@ async fn test_dkg_and_sign_with_parties(num_parties: usize) {
         signature_verifying_key: verifying_key.to_encoded_point(true).as_bytes().to_vec(),
         message_hash,
         request_author: AccountId32([0u8; 32]),
+        signature_scheme: SignatureScheme::Ecdsa,
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
     });
     let mut outputs =
         test_protocol_with_parties(parties[..threshold].to_vec(), session_id, threshold).await;
@@ -219,6 +360,74 @@ async fn test_dkg_and_sign_with_parties(num_parties: usize) {
     }
 }
 
+async fn test_ed25519_dkg_and_sign_with_parties(num_parties: usize) {
+    let threshold = num_parties - 1;
+    if threshold < 2 {
+        panic!("Not enought parties to test threshold signing");
+    }
+    let (pairs, _ids) = get_keypairs_and_ids(num_parties);
+    let dkg_parties =
+        pairs.iter().map(|pair| ValidatorSecretInfo::pair_only(pair.clone())).collect();
+    let session_id = SessionId::Dkg {
+        user: AccountId32([0; 32]),
+        block_number: 0,
+        signature_scheme: SignatureScheme::Ed25519,
+    };
+    let outputs = test_protocol_with_parties(dkg_parties, session_id, threshold).await;
+
+    let keyshares: Vec<Ed25519KeyShare> = outputs
+        .into_iter()
+        .map(|output| {
+            if let ProtocolOutput::Ed25519Dkg(keyshare) = output {
+                keyshare
+            } else {
+                panic!("Unexpected protocol output");
+            }
+        })
+        .collect();
+    let verifying_key = *keyshares[0].1.verifying_key();
+    // Everyone should have the same public key
+    assert!(keyshares
+        .iter()
+        .all(|(_, public_key_package)| { *public_key_package.verifying_key() == verifying_key }));
+
+    // Any `threshold` of the parties can sign
+    let parties: Vec<ValidatorSecretInfo> = pairs
+        .iter()
+        .map(|pair| {
+            let identifier = frost_ed25519::Identifier::derive(&pair.public().0).unwrap();
+            let keyshare = keyshares
+                .iter()
+                .find(|(key_package, _)| *key_package.identifier() == identifier)
+                .unwrap();
+            ValidatorSecretInfo {
+                pair: pair.clone(),
+                keyshare: None,
+                threshold_keyshare: None,
+                aux_info: None,
+                ed25519_keyshare: Some(keyshare.clone()),
+            }
+        })
+        .take(threshold)
+        .collect();
+
+    let message = [1u8; 32];
+    let session_id = SessionId::Sign(SigningSessionInfo {
+        signature_verifying_key: verifying_key.serialize().unwrap(),
+        message_hash: message,
+        request_author: AccountId32([0u8; 32]),
+        signature_scheme: SignatureScheme::Ed25519,
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
+    });
+    let mut outputs = test_protocol_with_parties(parties, session_id, threshold).await;
+    if let ProtocolOutput::Ed25519Sign(signature) = outputs.pop().unwrap() {
+        assert!(verifying_key.verify(&message, &signature).is_ok());
+    } else {
+        panic!("Unexpected protocol output");
+    }
+}
+
 /// Generic test for any of the 3 protocols
 async fn test_protocol_with_parties(
     parties: Vec<ValidatorSecretInfo>,
@@ -270,6 +479,7 @@ async fn test_protocol_with_parties(
                 secret.keyshare,
                 secret.threshold_keyshare,
                 secret.aux_info,
+                secret.ed25519_keyshare,
                 threshold,
             )
             .await;
@@ -292,11 +502,18 @@ struct ValidatorSecretInfo {
     keyshare: Option<KeyShare<KeyParams, PartyId>>,
     threshold_keyshare: Option<ThresholdKeyShare<KeyParams, PartyId>>,
     aux_info: Option<AuxInfo<KeyParams, PartyId>>,
+    ed25519_keyshare: Option<Ed25519KeyShare>,
 }
 
 impl ValidatorSecretInfo {
     fn pair_only(pair: sr25519::Pair) -> Self {
-        ValidatorSecretInfo { pair, keyshare: None, threshold_keyshare: None, aux_info: None }
+        ValidatorSecretInfo {
+            pair,
+            keyshare: None,
+            threshold_keyshare: None,
+            aux_info: None,
+            ed25519_keyshare: None,
+        }
     }
 }
 
@@ -306,6 +523,7 @@ struct ValidatorSecretInfoWithSocket {
     keyshare: Option<KeyShare<KeyParams, PartyId>>,
     threshold_keyshare: Option<ThresholdKeyShare<KeyParams, PartyId>>,
     aux_info: Option<AuxInfo<KeyParams, PartyId>>,
+    ed25519_keyshare: Option<Ed25519KeyShare>,
     x25519_secret_key: StaticSecret,
     socket: TcpListener,
 }
@@ -321,6 +539,7 @@ impl ValidatorSecretInfoWithSocket {
             keyshare: secret_info.keyshare,
             threshold_keyshare: secret_info.threshold_keyshare,
             aux_info: secret_info.aux_info,
+            ed25519_keyshare: secret_info.ed25519_keyshare,
             x25519_secret_key,
             socket,
         }
@@ -51,20 +51,45 @@ pub const REFRESHES_PER_SESSION: u32 = 10;
 /// Max instructions per wasm program
 pub const INITIAL_MAX_INSTRUCTIONS_PER_PROGRAM: u64 = 100_000_000;
 
+/// Max signature requests an account's own rate limit may allow in a window
+pub const INITIAL_MAX_RATE_LIMIT_REQUESTS: u32 = 10_000;
+
+/// Blocks after a recovery key is set before keyshares can be exported to it, one day with six
+/// second blocks
+pub const INITIAL_RECOVERY_KEY_DELAY: u32 = 14_400;
+
 /// Blocks a transaction is valid for
 pub const MORTALITY_BLOCKS: u64 = 32;
 
 /// Size of the verification key
 pub const VERIFICATION_KEY_LENGTH: u32 = 33;
 
+/// Size of an Ed25519 verification key
+pub const ED25519_VERIFICATION_KEY_LENGTH: u32 = 32;
+
 /// `device_key_proxy.wasm` from the `programs` repo.
 pub const DEVICE_KEY_PROXY: &[u8] = include_bytes!("../device_key_proxy.wasm");
 
 /// Network parent key specific size to fit into [u8; 32] to save extra code
 pub const NETWORK_PARENT_KEY: &str = "NETWORK_PARENT_KEY_FOR_ENTROPY_";
 
+/// HTTP response header in which a TSS server gives the hex encoded hash of the message it is
+/// about to sign
+pub const MESSAGE_HASH_HEADER: &str = "x-entropy-message-hash";
+
+/// HTTP request header in which the propagation pallet's offchain worker gives the hex encoded
+/// signature of its message, made with the validator's propagation session key
+pub const OCW_SIGNATURE_HEADER: &str = "x-entropy-ocw-signature";
+
+/// The block in which the network parent key is reshared when signer rotation is mocked at
+/// genesis, for testing
+pub const TEST_RESHARE_BLOCK_NUMBER: u32 = 10;
+
 /// Total signers on the network with the parent key
 pub const TOTAL_SIGNERS: u8 = 3;
 
 /// Threshold for those signers
 pub const SIGNER_THRESHOLD: u8 = 2;
+
+/// Oracle key under which the oracle pallet publishes the current block number
+pub const BLOCK_NUMBER_ORACLE_KEY: &[u8] = b"block_number_entropy";
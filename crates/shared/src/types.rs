@@ -13,7 +13,7 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 #![allow(dead_code)]
-use super::constants::VERIFICATION_KEY_LENGTH;
+use super::constants::{ED25519_VERIFICATION_KEY_LENGTH, VERIFICATION_KEY_LENGTH};
 #[cfg(not(feature = "wasm"))]
 use codec::alloc::vec::Vec;
 use codec::{Decode, Encode};
@@ -60,6 +60,36 @@ pub struct OcwMessageReshare {
     pub block_number: BlockNumber,
 }
 
+/// Offchain worker message for deleting the keyshares of deregistered accounts
+#[cfg(not(feature = "wasm"))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone, Encode, Decode, Debug, Eq, PartialEq, TypeInfo)]
+pub struct OcwMessageDeregister {
+    pub block_number: BlockNumber,
+    /// The verifying keys which were deregistered in this block
+    pub verifying_keys: Vec<Vec<u8>>,
+}
+
+/// Offchain worker message for checking changes to the programs of registered accounts
+#[cfg(not(feature = "wasm"))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone, Encode, Decode, Debug, Eq, PartialEq, TypeInfo)]
+pub struct OcwMessageProgramChange {
+    pub block_number: BlockNumber,
+    /// The verifying keys whose programs were changed in this block
+    pub verifying_keys: Vec<Vec<u8>>,
+}
+
+/// Offchain worker message telling the threshold servers how a signer rotation finished
+#[cfg(not(feature = "wasm"))]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Clone, Encode, Decode, Debug, Eq, PartialEq, TypeInfo)]
+pub struct OcwMessageSignerRotation {
+    pub block_number: BlockNumber,
+    /// Whether the next signers took over, rather than the rotation being rolled back
+    pub rotated: bool,
+}
+
 /// Offchain worker message for initiating a proactive refresh
 #[cfg(not(feature = "wasm"))]
 #[derive(
@@ -95,6 +125,88 @@ pub enum HashingAlgorithm {
     Keccak,
     Blake2_256,
     Custom(usize),
+    /// The message is already a 32 byte hash and is signed as it is. This can only be used if
+    /// every program on the account accepts prehashed messages
+    Prehashed,
+}
+
+/// The signature scheme of an account's threshold key, chosen when registering.
+#[cfg_attr(any(feature = "wasm", feature = "std"), derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", derive(EnumIter))]
+#[cfg_attr(any(feature = "wasm", feature = "std"), serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Encode, Decode, Debug, Default, Eq, PartialEq, Hash, TypeInfo)]
+pub enum SignatureScheme {
+    /// ECDSA over secp256k1, as used by Bitcoin and Ethereum
+    #[default]
+    Ecdsa,
+    /// EdDSA over Curve25519, as used by Solana and Cosmos-SDK chains
+    Ed25519,
+}
+
+impl SignatureScheme {
+    /// The length in bytes of a serialized verifying key for this scheme
+    pub fn verifying_key_length(&self) -> u32 {
+        match self {
+            SignatureScheme::Ecdsa => VERIFICATION_KEY_LENGTH,
+            SignatureScheme::Ed25519 => ED25519_VERIFICATION_KEY_LENGTH,
+        }
+    }
+}
+
+/// The kind of signature to produce with a secp256k1 key, chosen for each signature request.
+#[cfg_attr(any(feature = "wasm", feature = "std"), derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", derive(EnumIter))]
+#[cfg_attr(any(feature = "wasm", feature = "std"), serde(rename_all = "lowercase"))]
+#[derive(Clone, Copy, Encode, Decode, Debug, Default, Eq, PartialEq, Hash, TypeInfo)]
+pub enum SigningMode {
+    /// A 65 byte recoverable ECDSA signature
+    #[default]
+    Ecdsa,
+    /// A 64 byte BIP-340 Schnorr signature, as used for Bitcoin Taproot outputs
+    Bip340,
+}
+
+/// A limit on how many signature requests can be made with a verifying key over a window of
+/// blocks.
+///
+/// Windows are aligned to multiples of `window_blocks` so that all threshold servers agree on
+/// which window a given block falls in.
+#[cfg_attr(any(feature = "wasm", feature = "std"), derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Encode, Decode, Debug, Eq, PartialEq, TypeInfo)]
+pub struct RateLimit {
+    /// The maximum number of signature requests in a single window
+    pub max_requests: u32,
+    /// The length of a window in blocks. Must be greater than zero
+    pub window_blocks: BlockNumber,
+}
+
+impl RateLimit {
+    /// The first block of the window which contains the given block
+    pub fn window_start(&self, block_number: BlockNumber) -> BlockNumber {
+        block_number - block_number % self.window_blocks.max(1)
+    }
+}
+
+/// The auxilary data given to programs which accept derivation paths.
+///
+/// These programs always receive this SCALE encoded as their auxilary data, whether or not the
+/// request gives a derivation path, so they can tell the auxilary data from the request apart from
+/// the derivation path without guessing at its format. Programs which do not accept derivation paths
+/// are given the auxilary data from the request unchanged, and a request with a derivation path is
+/// refused for any account with such a program.
+///
+/// The leading variant index is the version of the format, so that further fields can be given to
+/// programs in a new version without changing what existing programs decode.
+#[derive(Clone, Encode, Decode, Debug, Eq, PartialEq, TypeInfo)]
+pub enum VersionedAuxilaryData {
+    /// The derivation path and the auxilary data from the request
+    V1 {
+        /// The non-hardened BIP-32 derivation path of the child key signing, eg. `m/0/1`, or
+        /// `None` when signing with the account's verifying key itself
+        derivation_path: Option<codec::alloc::string::String>,
+        /// The auxilary data given for the program in the signature request
+        auxilary_data: Option<codec::alloc::vec::Vec<u8>>,
+    },
 }
 
 /// A compressed, serialized [synedrion::ecdsa::VerifyingKey<k256::Secp256k1>]
@@ -25,8 +25,11 @@ use entropy_client::{
         EntropyConfig,
     },
     client::{
-        change_endpoint, change_threshold_accounts, get_accounts, get_api, get_programs, get_rpc,
-        register, sign, store_program, update_programs, VERIFYING_KEY_LENGTH,
+        change_endpoint, change_threshold_accounts, decrypt_exported_keyshares, deregister,
+        export_keyshares, get_accounts, get_api, get_programs, get_rpc, get_signers_info,
+        import_key, recover_signing_key, register, set_rate_limit, set_recovery_key, sign,
+        store_program, update_programs, EncryptedSignedMessage, HashingAlgorithm, RateLimit,
+        SignatureScheme, SigningKey, SigningMode,
     },
 };
 use sp_core::{sr25519, Hasher, Pair};
@@ -37,6 +40,7 @@ use subxt::{
     utils::{AccountId32 as SubxtAccountId32, H256},
     OnlineClient,
 };
+use x25519_dalek::StaticSecret;
 
 #[derive(Parser, Debug, Clone)]
 #[clap(
@@ -79,6 +83,34 @@ enum CliCommand {
         /// If giving a mnemonic it must be enclosed in quotes, eg: "--mnemonic-option "alarm mutual concert...""  
         #[arg(short, long)]
         mnemonic_option: Option<String>,
+        /// The number of keyshares needed to sign. Defaults to the network's minimum threshold
+        #[arg(long)]
+        threshold: Option<u8>,
+        /// The number of validators which will hold a keyshare. Defaults to the network's maximum
+        #[arg(long)]
+        total_signers: Option<u8>,
+        /// Register with an Ed25519 key rather than an ECDSA (secp256k1) key
+        #[arg(long)]
+        ed25519: bool,
+    },
+    /// Register with Entropy using an existing ECDSA signing key, which is split into keyshares
+    /// for the TSS servers
+    ImportKey {
+        /// The secp256k1 secret key to import, given as hex
+        signing_key: String,
+        /// Either hex-encoded hashes of existing programs, or paths to wasm files to store, given
+        /// in the same way as for `register`
+        programs: Vec<String>,
+        /// A name or mnemonic from which to derive a program modification keypair.
+        /// This is used to send the register extrinsic so it must be funded
+        #[arg(short, long)]
+        mnemonic_option: Option<String>,
+        /// The number of keyshares needed to sign. Defaults to the network's minimum threshold
+        #[arg(long)]
+        threshold: Option<u8>,
+        /// The number of validators which will hold a keyshare. Defaults to the network's maximum
+        #[arg(long)]
+        total_signers: Option<u8>,
     },
     /// Ask the network to sign a given message
     Sign {
@@ -90,6 +122,17 @@ enum CliCommand {
         auxilary_data: Option<String>,
         /// The mnemonic to use for the call
         mnemonic_option: Option<String>,
+        /// Make a BIP-340 Schnorr signature rather than an ECDSA signature
+        #[arg(long)]
+        bip340: bool,
+        /// A non-hardened BIP-32 derivation path, eg: "m/0/1", to sign with a child key
+        #[arg(long)]
+        derivation_path: Option<String>,
+        /// The hashing algorithm to use, one of "sha2", "sha3", "keccak", "blake2_256", "prehashed"
+        /// to sign a hex encoded 32 byte hash given as the message, or eg: "custom:0" to use the
+        /// custom hash from the account's first program
+        #[arg(long, default_value = "keccak", value_parser = parse_hashing_algorithm)]
+        hash: HashingAlgorithm,
     },
     /// Update the program for a particular account
     UpdatePrograms {
@@ -111,6 +154,59 @@ enum CliCommand {
         #[arg(short, long)]
         mnemonic_option: Option<String>,
     },
+    /// Deregister an account, so that its threshold keyshares are deleted
+    Deregister {
+        /// The verifying key of the account to deregister, given as hex
+        signature_verifying_key: String,
+        /// The mnemonic of the program modification account
+        #[arg(short, long)]
+        mnemonic_option: Option<String>,
+    },
+    /// Set or remove the signature request rate limit for a particular account
+    SetRateLimit {
+        /// The verifying key of the account, given as hex
+        signature_verifying_key: String,
+        /// The maximum number of signature requests per window. If not given, the account's rate
+        /// limit is removed and the network wide request limit applies
+        #[arg(long)]
+        max_requests: Option<u32>,
+        /// The length of a window in blocks
+        #[arg(long, default_value = "1")]
+        window_blocks: u32,
+        /// The mnemonic of the program modification account
+        #[arg(short, long)]
+        mnemonic_option: Option<String>,
+    },
+    /// Set or remove the X25519 key which exported keyshares of an account are encrypted to
+    SetRecoveryKey {
+        /// The verifying key of the account, given as hex
+        signature_verifying_key: String,
+        /// The X25519 public key to encrypt exported keyshares to, given as hex. If not given, the
+        /// recovery key is removed and keyshares can no longer be exported
+        #[arg(long)]
+        recovery_public_key: Option<String>,
+        /// The mnemonic of the program modification account
+        #[arg(short, long)]
+        mnemonic_option: Option<String>,
+    },
+    /// Export every TSS server's keyshare of an account, encrypted to its recovery key, and save
+    /// them to a file
+    ExportKeyshares {
+        /// The verifying key of the account, given as hex
+        signature_verifying_key: String,
+        /// The file to save the encrypted keyshares to
+        output_file: PathBuf,
+        /// The mnemonic of the program modification account
+        #[arg(short, long)]
+        mnemonic_option: Option<String>,
+    },
+    /// Recover the private key of an account from keyshares saved with `export-keyshares`
+    RecoverKey {
+        /// The file the encrypted keyshares were saved to
+        keyshares_file: PathBuf,
+        /// The X25519 secret key matching the account's recovery key, given as hex
+        recovery_secret_key: String,
+    },
     /// Store a given program on chain
     StoreProgram {
         /// The path to a .wasm file containing the program (defaults to a test program)
@@ -122,6 +218,12 @@ enum CliCommand {
         /// The mnemonic to use for the call
         #[arg(short, long)]
         mnemonic_option: Option<String>,
+        /// Declare that the program can evaluate prehashed messages
+        #[arg(long)]
+        accepts_prehashed: bool,
+        /// Declare that the program can evaluate requests to sign with a child key
+        #[arg(long)]
+        accepts_derivation_path: bool,
     },
     /// Allows a validator to change their endpoint
     ChangeEndpoint {
@@ -162,7 +264,7 @@ pub async fn run_command(
     let rpc = get_rpc(&endpoint_addr).await?;
 
     match cli.command {
-        CliCommand::Register { mnemonic_option, programs } => {
+        CliCommand::Register { mnemonic_option, programs, threshold, total_signers, ed25519 } => {
             let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
                 mnemonic_option
             } else {
@@ -181,18 +283,72 @@ pub async fn run_command(
                 );
             }
 
+            let signers_info = get_signers_info(&api, &rpc).await?;
             let (verifying_key, registered_info) = register(
                 &api,
                 &rpc,
                 program_keypair.clone(),
                 program_account,
                 BoundedVec(programs_info),
+                if ed25519 { SignatureScheme::Ed25519 } else { SignatureScheme::Ecdsa },
+                threshold.unwrap_or(signers_info.threshold),
+                total_signers.unwrap_or(signers_info.total_signers),
             )
             .await?;
 
             Ok(format!("Verfiying key: {},\n{:?}", hex::encode(verifying_key), registered_info))
         },
-        CliCommand::Sign { signature_verifying_key, message, auxilary_data, mnemonic_option } => {
+        CliCommand::ImportKey {
+            signing_key,
+            programs,
+            mnemonic_option,
+            threshold,
+            total_signers,
+        } => {
+            let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
+                mnemonic_option
+            } else {
+                passed_mnemonic.expect("No mnemonic set")
+            };
+
+            let program_keypair = <sr25519::Pair as Pair>::from_string(&mnemonic, None)?;
+            let program_account = SubxtAccountId32(program_keypair.public().0);
+            println!("Program account: {}", program_keypair.public());
+
+            let signing_key = SigningKey::from_slice(&hex::decode(signing_key)?)?;
+
+            let mut programs_info = vec![];
+
+            for program in programs {
+                programs_info.push(
+                    Program::from_hash_or_filename(&api, &rpc, &program_keypair, program).await?.0,
+                );
+            }
+
+            let signers_info = get_signers_info(&api, &rpc).await?;
+            let (verifying_key, registered_info) = import_key(
+                &api,
+                &rpc,
+                program_keypair.clone(),
+                program_account,
+                BoundedVec(programs_info),
+                signing_key,
+                threshold.unwrap_or(signers_info.threshold),
+                total_signers.unwrap_or(signers_info.total_signers),
+            )
+            .await?;
+
+            Ok(format!("Verfiying key: {},\n{:?}", hex::encode(verifying_key), registered_info))
+        },
+        CliCommand::Sign {
+            signature_verifying_key,
+            message,
+            auxilary_data,
+            mnemonic_option,
+            bip340,
+            derivation_path,
+            hash,
+        } => {
             let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
                 mnemonic_option
             } else {
@@ -206,27 +362,41 @@ pub async fn run_command(
             let auxilary_data =
                 if let Some(data) = auxilary_data { Some(hex::decode(data)?) } else { None };
 
-            let signature_verifying_key: [u8; VERIFYING_KEY_LENGTH] =
-                hex::decode(signature_verifying_key)?
-                    .try_into()
-                    .map_err(|_| anyhow!("Verifying key must be 33 bytes"))?;
+            let signature_verifying_key = hex::decode(signature_verifying_key)?;
+
+            let signing_mode = if bip340 { SigningMode::Bip340 } else { SigningMode::Ecdsa };
 
-            let recoverable_signature = sign(
+            // A prehashed message is given as hex
+            let message = if hash == HashingAlgorithm::Prehashed {
+                hex::decode(message)?
+            } else {
+                message.as_bytes().to_vec()
+            };
+
+            let signing_report = sign(
                 &api,
                 &rpc,
                 user_keypair,
                 signature_verifying_key,
-                message.as_bytes().to_vec(),
+                message,
+                hash,
                 auxilary_data,
+                signing_mode,
+                derivation_path,
             )
             .await?;
-            Ok(format!("Message signed: {:?}", recoverable_signature))
+            for tss_account in signing_report.dissenting_signers() {
+                println!("TSS server {} did not give the agreed signature", tss_account);
+            }
+            Ok(format!("Message signed: {:?}", signing_report.signature))
         },
         CliCommand::StoreProgram {
             mnemonic_option,
             program_file,
             config_interface_file,
             aux_data_interface_file,
+            accepts_prehashed,
+            accepts_derivation_path,
         } => {
             let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
                 mnemonic_option
@@ -263,6 +433,8 @@ pub async fn run_command(
                 config_interface,
                 aux_data_interface,
                 vec![],
+                accepts_prehashed,
+                accepts_derivation_path,
             )
             .await?;
             Ok(format!("Program stored {hash}"))
@@ -283,15 +455,112 @@ pub async fn run_command(
                 );
             }
 
-            let verifying_key: [u8; VERIFYING_KEY_LENGTH] = hex::decode(signature_verifying_key)?
-                .try_into()
-                .map_err(|_| anyhow!("Verifying key must be 33 bytes"))?;
+            let verifying_key = hex::decode(signature_verifying_key)?;
 
             update_programs(&api, &rpc, verifying_key, &program_keypair, BoundedVec(programs_info))
                 .await?;
 
             Ok("Programs updated".to_string())
         },
+        CliCommand::Deregister { signature_verifying_key, mnemonic_option } => {
+            let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
+                mnemonic_option
+            } else {
+                passed_mnemonic.expect("No Mnemonic set")
+            };
+            let program_keypair = <sr25519::Pair as Pair>::from_string(&mnemonic, None)?;
+            println!("Program modification account: {}", program_keypair.public());
+
+            let verifying_key = hex::decode(signature_verifying_key)?;
+
+            deregister(&api, &rpc, verifying_key, &program_keypair).await?;
+
+            Ok("Account deregistered".to_string())
+        },
+        CliCommand::SetRateLimit {
+            signature_verifying_key,
+            max_requests,
+            window_blocks,
+            mnemonic_option,
+        } => {
+            let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
+                mnemonic_option
+            } else {
+                passed_mnemonic.expect("No Mnemonic set")
+            };
+            let program_keypair = <sr25519::Pair as Pair>::from_string(&mnemonic, None)?;
+            println!("Program modification account: {}", program_keypair.public());
+
+            let verifying_key = hex::decode(signature_verifying_key)?;
+
+            let rate_limit =
+                max_requests.map(|max_requests| RateLimit { max_requests, window_blocks });
+            set_rate_limit(&api, &rpc, verifying_key, &program_keypair, rate_limit).await?;
+
+            Ok("Rate limit updated".to_string())
+        },
+        CliCommand::SetRecoveryKey {
+            signature_verifying_key,
+            recovery_public_key,
+            mnemonic_option,
+        } => {
+            let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
+                mnemonic_option
+            } else {
+                passed_mnemonic.expect("No Mnemonic set")
+            };
+            let program_keypair = <sr25519::Pair as Pair>::from_string(&mnemonic, None)?;
+            println!("Program modification account: {}", program_keypair.public());
+
+            let verifying_key = hex::decode(signature_verifying_key)?;
+
+            let recovery_key = recovery_public_key
+                .map(|recovery_public_key| -> anyhow::Result<[u8; 32]> {
+                    hex::decode(recovery_public_key)?
+                        .try_into()
+                        .map_err(|_| anyhow!("Recovery key must be 32 bytes"))
+                })
+                .transpose()?;
+            set_recovery_key(&api, &rpc, verifying_key, &program_keypair, recovery_key).await?;
+
+            Ok("Recovery key updated".to_string())
+        },
+        CliCommand::ExportKeyshares { signature_verifying_key, output_file, mnemonic_option } => {
+            let mnemonic = if let Some(mnemonic_option) = mnemonic_option {
+                mnemonic_option
+            } else {
+                passed_mnemonic.expect("No Mnemonic set")
+            };
+            let program_keypair = <sr25519::Pair as Pair>::from_string(&mnemonic, None)?;
+            println!("Program modification account: {}", program_keypair.public());
+
+            let verifying_key = hex::decode(signature_verifying_key)?;
+
+            let exported_keyshares =
+                export_keyshares(&api, &rpc, verifying_key, &program_keypair).await?;
+            fs::write(&output_file, serde_json::to_vec(&exported_keyshares)?)?;
+
+            Ok(format!(
+                "Saved {} encrypted keyshares to {}",
+                exported_keyshares.len(),
+                output_file.display()
+            ))
+        },
+        CliCommand::RecoverKey { keyshares_file, recovery_secret_key } => {
+            let exported_keyshares: Vec<EncryptedSignedMessage> =
+                serde_json::from_slice(&fs::read(keyshares_file)?)?;
+            let recovery_secret_key: [u8; 32] = hex::decode(recovery_secret_key)?
+                .try_into()
+                .map_err(|_| anyhow!("Recovery secret key must be 32 bytes"))?;
+
+            let key_shares = decrypt_exported_keyshares(
+                &exported_keyshares,
+                &StaticSecret::from(recovery_secret_key),
+            )?;
+            let signing_key = recover_signing_key(&key_shares)?;
+
+            Ok(format!("Private key: {}", hex::encode(signing_key.to_bytes())))
+        },
         CliCommand::Status => {
             let accounts = get_accounts(&api, &rpc).await?;
             println!(
@@ -399,7 +668,7 @@ struct Program(ProgramInstance);
 
 impl Program {
     fn new(program_pointer: H256, program_config: Vec<u8>) -> Self {
-        Self(ProgramInstance { program_pointer, program_config })
+        Self(ProgramInstance { program_pointer, program_config, fuel: None })
     }
 
     async fn from_hash_or_filename(
@@ -474,6 +743,8 @@ impl Program {
             config_description,
             auxiliary_data_schema,
             vec![],
+            false,
+            false,
         )
         .await
         {
@@ -490,3 +761,21 @@ impl Program {
         }
     }
 }
+
+/// Parse a hashing algorithm given on the command line
+fn parse_hashing_algorithm(hash: &str) -> Result<HashingAlgorithm, String> {
+    match hash {
+        "sha2" => Ok(HashingAlgorithm::Sha2),
+        "sha3" => Ok(HashingAlgorithm::Sha3),
+        "keccak" => Ok(HashingAlgorithm::Keccak),
+        "blake2_256" => Ok(HashingAlgorithm::Blake2_256),
+        "prehashed" => Ok(HashingAlgorithm::Prehashed),
+        _ => {
+            let index = hash
+                .strip_prefix("custom:")
+                .ok_or_else(|| format!("Unknown hashing algorithm: {hash}"))?;
+            let index = index.parse().map_err(|_| format!("Bad custom hash index: {index}"))?;
+            Ok(HashingAlgorithm::Custom(index))
+        },
+    }
+}
@@ -109,6 +109,35 @@ where
     output
 }
 
+/// Runs the reshare protocol between local parties, each given with its signing keypair and its
+/// inputs to the protocol. Old holders which are not new holders get no new keyshare.
+pub async fn reshare_test_keyshares<Params>(
+    parties: Vec<(sr25519::Pair, KeyResharingInputs<Params, PartyId>)>,
+) -> Vec<Option<ThresholdKeyShare<Params, PartyId>>>
+where
+    Params: SchemeParams,
+{
+    let session_id = SessionId::from_seed(b"reshare".as_slice());
+    let all_parties =
+        parties.iter().map(|(pair, _)| PartyId::from(pair.public())).collect::<BTreeSet<_>>();
+
+    let sessions = parties
+        .into_iter()
+        .map(|(pair, inputs)| {
+            make_key_resharing_session(
+                &mut OsRng,
+                session_id,
+                PairWrapper(pair),
+                &all_parties,
+                inputs,
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    run_nodes(sessions).await
+}
+
 /// This is used to run the synedrion protocols - it is mostly copied from the synedrion integration
 /// tests
 mod synedrion_test_environment {
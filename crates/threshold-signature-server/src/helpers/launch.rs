@@ -46,15 +46,19 @@ pub const DEFAULT_EVE_MNEMONIC: &str =
     "impact federal dish number fun crisp various wedding radio immense whisper glue";
 pub const LATEST_BLOCK_NUMBER_NEW_USER: &str = "LATEST_BLOCK_NUMBER_NEW_USER";
 pub const LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH: &str = "LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH";
+pub const LATEST_BLOCK_NUMBER_RESHARE: &str = "LATEST_BLOCK_NUMBER_RESHARE";
+/// Where a network parent key share from a reshare is kept until the signer rotation is final
+pub const NEXT_NETWORK_PARENT_KEY: &str = "NEXT_NETWORK_PARENT_KEY";
 
 #[cfg(any(test, feature = "test_helpers"))]
 pub const DEFAULT_ENDPOINT: &str = "ws://localhost:9944";
 
-pub const FORBIDDEN_KEYS: [&str; 4] = [
+pub const FORBIDDEN_KEYS: [&str; 5] = [
     FORBIDDEN_KEY_MNEMONIC,
     FORBIDDEN_KEY_SHARED_SECRET,
     FORBIDDEN_KEY_DIFFIE_HELLMAN_PUBLIC,
     NETWORK_PARENT_KEY,
+    NEXT_NETWORK_PARENT_KEY,
 ];
 
 pub const FORBIDDEN_KEY_MNEMONIC: &str = "MNEMONIC";
@@ -82,11 +86,15 @@ pub struct SetupOnlyOutput {
 #[derive(Deserialize, Debug, Clone)]
 pub struct Configuration {
     pub endpoint: String,
+    /// Whether the callback URL of an asynchronous signing request may resolve to a private,
+    /// loopback or otherwise non-public address. This should only be set for testing.
+    #[serde(default)]
+    pub allow_private_callback_urls: bool,
 }
 
 impl Configuration {
     pub fn new(endpoint: String) -> Configuration {
-        Configuration { endpoint }
+        Configuration { endpoint, allow_private_callback_urls: false }
     }
 }
 
@@ -185,6 +193,11 @@ pub struct StartupArgs {
     #[arg(short = 'd', long = "dev")]
     pub dev: bool,
 
+    /// Allow the callback URLs of asynchronous signing requests to be private or loopback
+    /// addresses. This should only be used for local testing.
+    #[arg(long = "allow-private-callback-urls")]
+    pub allow_private_callback_urls: bool,
+
     /// Whether or not to execute a specific test
     #[cfg(test)]
     #[arg(long = "test", required = false, default_value = "*")]
@@ -373,6 +386,19 @@ pub async fn setup_latest_block_number(kv: &KvManager) -> Result<(), KvError> {
             .await
             .expect("failed to update latest block number");
     }
+    let exists_result_reshare =
+        kv.kv().exists(LATEST_BLOCK_NUMBER_RESHARE).await.expect("issue querying DB");
+    if !exists_result_reshare {
+        let reservation = kv
+            .kv()
+            .reserve_key(LATEST_BLOCK_NUMBER_RESHARE.to_string())
+            .await
+            .expect("Issue reserving latest block number");
+        kv.kv()
+            .put(reservation, 0u32.to_be_bytes().to_vec())
+            .await
+            .expect("failed to update latest block number");
+    }
     Ok(())
 }
 
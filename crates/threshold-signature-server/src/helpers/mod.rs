@@ -16,6 +16,7 @@
 //! Helper modules for various components of the TSS server
 pub mod launch;
 pub mod logger;
+pub mod program_runtime;
 pub mod signing;
 pub mod substrate;
 pub mod user;
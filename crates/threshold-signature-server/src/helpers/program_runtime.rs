@@ -0,0 +1,122 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A Wasm runtime for evaluating programs which keeps track of the fuel each program uses
+//!
+//! This evaluates programs in the same way as [entropy_programs_runtime::Runtime], which keeps
+//! its store to itself, so the fuel a program used can't be read from it, or reset for the next
+//! program.
+use entropy_programs_runtime::{Program, ProgramError, RuntimeError, SignatureRequest};
+use wasmtime::{
+    component::{Component, Linker},
+    Config as WasmtimeConfig, Engine, Store, Trap,
+};
+
+/// Evaluates programs, each with its own fuel. Give a program its fuel with
+/// [ProgramRuntime::set_fuel] before evaluating it.
+pub struct ProgramRuntime {
+    engine: Engine,
+    linker: Linker<()>,
+    store: Store<()>,
+}
+
+impl Default for ProgramRuntime {
+    fn default() -> Self {
+        let mut wasmtime_config = WasmtimeConfig::new();
+        wasmtime_config.wasm_component_model(true).consume_fuel(true);
+
+        let engine = Engine::new(&wasmtime_config).expect("Wasmtime config is valid");
+        let linker = Linker::new(&engine);
+        let store = Store::new(&engine, ());
+        Self { engine, linker, store }
+    }
+}
+
+impl ProgramRuntime {
+    /// Gives the runtime `fuel` to use for the next program, discarding any left over from the
+    /// last one
+    pub fn set_fuel(&mut self, fuel: u64) {
+        self.store = Store::new(&self.engine, ());
+        self.store.add_fuel(fuel).expect("Fuel is enabled in the wasmtime config");
+    }
+
+    /// The fuel used since it was last set
+    pub fn fuel_consumed(&self) -> u64 {
+        self.store.fuel_consumed().unwrap_or_default()
+    }
+
+    /// Evaluates a program against a signature request with the program's configuration and
+    /// oracle data
+    pub fn evaluate(
+        &mut self,
+        program: &[u8],
+        signature_request: &SignatureRequest,
+        config: Option<&[u8]>,
+        oracle_data: Option<&[u8]>,
+    ) -> Result<(), RuntimeError> {
+        let bindings = self.instantiate(program)?;
+        bindings
+            .call_evaluate(&mut self.store, signature_request, config, oracle_data)
+            .map_err(trap_error)?
+            .map_err(RuntimeError::Runtime)
+    }
+
+    /// Computes the `custom-hash` of a `message` using a program
+    pub fn custom_hash(
+        &mut self,
+        program: &[u8],
+        message: &[u8],
+    ) -> Result<[u8; 32], RuntimeError> {
+        let bindings = self.instantiate(program)?;
+        let hash = bindings
+            .call_custom_hash(&mut self.store, message)
+            .map_err(trap_error)?
+            .ok_or_else(|| {
+                RuntimeError::Runtime(ProgramError::InvalidSignatureRequest(
+                    "`custom-hash` returns `None`. Implement the hash function in your program, \
+                     or select a predefined `hash` in your signature request."
+                        .to_string(),
+                ))
+            })?;
+        hash.as_slice().try_into().map_err(|_| {
+            RuntimeError::Runtime(ProgramError::InvalidSignatureRequest(format!(
+                "`custom-hash` must returns a Vec<u8> of length 32, not {}.",
+                hash.len()
+            )))
+        })
+    }
+
+    fn instantiate(&mut self, program: &[u8]) -> Result<Program, RuntimeError> {
+        if program.is_empty() {
+            return Err(RuntimeError::EmptyBytecode);
+        }
+        let component = Component::from_binary(&self.engine, program)
+            .map_err(|_| RuntimeError::InvalidBytecode)?;
+        let (bindings, _) = Program::instantiate(&mut self.store, &component, &self.linker)
+            .map_err(|_| RuntimeError::InvalidBytecode)?;
+        Ok(bindings)
+    }
+}
+
+/// Gives [RuntimeError::OutOfFuel] if a program ran out of fuel, or a runtime error for any other
+/// trap, such as a program panicking
+fn trap_error(error: wasmtime::Error) -> RuntimeError {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => RuntimeError::OutOfFuel,
+        _ => RuntimeError::Runtime(ProgramError::Evaluation(format!(
+            "Program stopped with an error: {error}"
+        ))),
+    }
+}
@@ -18,20 +18,25 @@ pub use entropy_client::Hasher;
 use std::time::Duration;
 
 use entropy_client::user::UserSignatureRequest;
-use entropy_protocol::{Listener, RecoverableSignature, SessionId, SigningSessionInfo};
-use entropy_shared::SETUP_TIMEOUT_SECONDS;
-use sp_core::Pair;
+use entropy_protocol::{
+    Listener, SessionId, SignatureScheme, SigningMode, SigningSessionInfo, ThresholdSignature,
+    ValidatorInfo,
+};
+use entropy_shared::{RateLimit, SETUP_TIMEOUT_SECONDS};
+use sp_core::{sr25519, Pair};
 use subxt::{backend::legacy::LegacyRpcMethods, utils::AccountId32};
 use tokio::time::timeout;
+use x25519_dalek::StaticSecret;
 
 use crate::{
     chain_api::EntropyConfig,
     get_signer_and_x25519_secret,
     sign_init::SignInit,
     signing_client::{
+        presignatures::replenish_presignatures,
         protocol_execution::{Channels, ThresholdSigningService},
         protocol_transport::open_protocol_connections,
-        ProtocolErr,
+        ListenerState, ProtocolErr,
     },
     user::api::increment_or_wipe_request_limit,
     AppState,
@@ -44,44 +49,34 @@ pub async fn do_signing(
     user_signature_request: UserSignatureRequest,
     app_state: &AppState,
     signing_session_info: SigningSessionInfo,
-    request_limit: u32,
-) -> Result<RecoverableSignature, ProtocolErr> {
+    rate_limit: RateLimit,
+) -> Result<ThresholdSignature, ProtocolErr> {
     tracing::debug!("Preparing to perform signing");
 
     let state = &app_state.listener_state;
     let kv_manager = &app_state.kv_store;
 
     let info = SignInit::new(user_signature_request.clone(), signing_session_info.clone());
-    let signing_service = ThresholdSigningService::new(state, kv_manager);
+    let signing_service = ThresholdSigningService::new(state, kv_manager, &app_state.presignatures);
     let (pair_signer, x25519_secret_key) = get_signer_and_x25519_secret(kv_manager)
         .await
         .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
     let signer = pair_signer.signer();
 
-    let account_id = AccountId32(signer.public().0);
-
     // set up context for signing protocol execution
     let sign_context = signing_service.get_sign_context(info.clone()).await?;
 
+    let message = hex::decode(&user_signature_request.message)?;
+
     let tss_accounts: Vec<AccountId32> = user_signature_request
         .validators_info
         .iter()
         .map(|validator_info| validator_info.tss_account.clone())
         .collect();
 
-    // subscribe to all other participating parties. Listener waits for other subscribers.
-    let (rx_ready, rx_from_others, listener) =
-        Listener::new(user_signature_request.validators_info, &account_id);
-
     let session_id = SessionId::Sign(sign_context.sign_init.signing_session_info.clone());
 
-    state
-        .listeners
-        .lock()
-        .map_err(|_| ProtocolErr::SessionError("Error getting lock".to_string()))?
-        .insert(session_id.clone(), listener);
-
-    open_protocol_connections(
+    let Channels(broadcaster, rx) = open_signing_channels(
         &sign_context.sign_init.validators_info,
         &session_id,
         signer,
@@ -89,30 +84,181 @@ pub async fn do_signing(
         &x25519_secret_key,
     )
     .await?;
-    let channels = {
-        let ready = timeout(Duration::from_secs(SETUP_TIMEOUT_SECONDS), rx_ready).await?;
-        let broadcast_out = ready??;
-        Channels(broadcast_out, rx_from_others)
-    };
 
-    let result = signing_service
+    let (result, rx) = signing_service
         .execute_sign(
-            session_id,
+            session_id.clone(),
             &sign_context.key_share,
-            &sign_context.aux_info,
-            channels,
+            &message,
+            Channels(broadcaster.clone(), rx),
             signer,
-            tss_accounts,
+            tss_accounts.clone(),
         )
         .await?;
+    if uses_presignatures(&info.signing_session_info) {
+        tokio::spawn(replenish_presignatures(
+            app_state.presignatures.clone(),
+            kv_manager.clone(),
+            session_id,
+            Channels(broadcaster, rx),
+            signer.clone(),
+            tss_accounts,
+        ));
+    }
     increment_or_wipe_request_limit(
         rpc,
         kv_manager,
         hex::encode(info.signing_session_info.signature_verifying_key),
-        request_limit,
+        rate_limit,
     )
     .await
     .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
 
     Ok(result)
 }
+
+/// Sign several messages with the same key, using one set of connections to the rest of the
+/// signing committee
+///
+/// The connections are made once, under the session ID of the first message. Each message then
+/// has its own signing session over those connections, one after another. As the session ID
+/// includes the message hash, messages from a session other than the current one are held back
+/// until it starts.
+///
+/// `send_result` is called with the result for each message, in order, and should return `false`
+/// when the results are no longer wanted. If a signing session fails the connections cannot be
+/// used again, so each remaining message gets an error. An error is only returned if the
+/// connections could not be made, in which case no message was signed.
+#[tracing::instrument(skip_all, level = tracing::Level::DEBUG)]
+pub async fn do_batch_signing(
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    signing_requests: Vec<(UserSignatureRequest, SigningSessionInfo)>,
+    app_state: &AppState,
+    rate_limit: RateLimit,
+    mut send_result: impl FnMut(Result<ThresholdSignature, ProtocolErr>) -> bool,
+) -> Result<(), ProtocolErr> {
+    tracing::debug!("Preparing to perform batch signing");
+
+    let state = &app_state.listener_state;
+    let kv_manager = &app_state.kv_store;
+
+    let (first_request, first_session_info) = signing_requests
+        .first()
+        .cloned()
+        .ok_or_else(|| ProtocolErr::UserError("No messages to sign".to_string()))?;
+
+    let signing_service = ThresholdSigningService::new(state, kv_manager, &app_state.presignatures);
+    let (pair_signer, x25519_secret_key) = get_signer_and_x25519_secret(kv_manager)
+        .await
+        .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
+    let signer = pair_signer.signer();
+
+    // All messages are signed with the same key share, since they share a verifying key,
+    // signature scheme and derivation path
+    let sign_context =
+        signing_service.get_sign_context(SignInit::new(first_request, first_session_info)).await?;
+
+    let tss_accounts: Vec<AccountId32> = sign_context
+        .sign_init
+        .validators_info
+        .iter()
+        .map(|validator_info| validator_info.tss_account.clone())
+        .collect();
+
+    let Channels(broadcaster, rx) = open_signing_channels(
+        &sign_context.sign_init.validators_info,
+        &SessionId::Sign(sign_context.sign_init.signing_session_info.clone()),
+        signer,
+        state,
+        &x25519_secret_key,
+    )
+    .await?;
+    let mut rx = Some(rx);
+
+    for (user_signature_request, signing_session_info) in signing_requests {
+        let result = match rx.take() {
+            Some(channel_in) => {
+                async {
+                    let message = hex::decode(&user_signature_request.message)?;
+                    let (signature, channel_in) = signing_service
+                        .execute_sign(
+                            SessionId::Sign(signing_session_info.clone()),
+                            &sign_context.key_share,
+                            &message,
+                            Channels(broadcaster.clone(), channel_in),
+                            signer,
+                            tss_accounts.clone(),
+                        )
+                        .await?;
+                    rx = Some(channel_in);
+                    increment_or_wipe_request_limit(
+                        rpc,
+                        kv_manager,
+                        hex::encode(&signing_session_info.signature_verifying_key),
+                        rate_limit,
+                    )
+                    .await
+                    .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
+                    Ok::<_, ProtocolErr>(signature)
+                }
+                .await
+            },
+            None => Err(ProtocolErr::SessionError(
+                "Signing session for an earlier message in the batch failed".to_string(),
+            )),
+        };
+        if !send_result(result) {
+            break;
+        }
+    }
+
+    if let Some(rx) = rx {
+        if uses_presignatures(&sign_context.sign_init.signing_session_info) {
+            tokio::spawn(replenish_presignatures(
+                app_state.presignatures.clone(),
+                kv_manager.clone(),
+                SessionId::Sign(sign_context.sign_init.signing_session_info.clone()),
+                Channels(broadcaster, rx),
+                signer.clone(),
+                tss_accounts,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether signing uses BIP-340 presignatures, in which case the signing parties top up their
+/// pool of them once they have signed
+fn uses_presignatures(signing_session_info: &SigningSessionInfo) -> bool {
+    signing_session_info.signature_scheme == SignatureScheme::Ecdsa
+        && signing_session_info.signing_mode == SigningMode::Bip340
+}
+
+/// Subscribe to the other members of the signing committee and connect to them, giving channels
+/// for the signing protocol once everyone is connected
+pub(crate) async fn open_signing_channels(
+    validators_info: &[ValidatorInfo],
+    session_id: &SessionId,
+    signer: &sr25519::Pair,
+    state: &ListenerState,
+    x25519_secret_key: &StaticSecret,
+) -> Result<Channels, ProtocolErr> {
+    let account_id = AccountId32(signer.public().0);
+
+    // subscribe to all other participating parties. Listener waits for other subscribers.
+    let (rx_ready, rx_from_others, listener) = Listener::new(validators_info.to_vec(), &account_id);
+
+    state
+        .listeners
+        .lock()
+        .map_err(|_| ProtocolErr::SessionError("Error getting lock".to_string()))?
+        .insert(session_id.clone(), listener);
+
+    open_protocol_connections(validators_info, session_id, signer, state, x25519_secret_key)
+        .await?;
+
+    let ready = timeout(Duration::from_secs(SETUP_TIMEOUT_SECONDS), rx_ready).await?;
+    let broadcast_out = ready??;
+    Ok(Channels(broadcast_out, rx_from_others))
+}
@@ -19,7 +19,7 @@ use crate::{
         entropy::{
             self,
             runtime_types::{
-                bounded_collections::bounded_vec::BoundedVec,
+                bounded_collections::bounded_vec::BoundedVec, pallet_programs::pallet::ProgramInfo,
                 pallet_registry::pallet::RegisteredInfo,
             },
         },
@@ -27,8 +27,10 @@ use crate::{
     },
     user::UserErr,
 };
+use axum::http::HeaderMap;
 pub use entropy_client::substrate::{query_chain, submit_transaction};
-use entropy_shared::user::ValidatorInfo;
+use entropy_shared::{user::ValidatorInfo, RateLimit, OCW_SIGNATURE_HEADER};
+use sp_core::{sr25519, Pair};
 use subxt::{backend::legacy::LegacyRpcMethods, utils::AccountId32, Config, OnlineClient};
 
 /// Given a threshold server's account ID, return its corresponding stash (validator) address.
@@ -47,18 +49,96 @@ pub async fn get_stash_address(
     Ok(stash_address)
 }
 
-/// Queries the user's program from the chain
+/// Checks that a message from the off-chain worker (propagation pallet) was signed with the
+/// propagation session key of this threshold server's validator, so that it came from our own node.
+pub async fn verify_ocw_signature(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    threshold_account_id: &AccountId32,
+    headers: &HeaderMap,
+    message: &[u8],
+) -> Result<(), UserErr> {
+    let signature = headers
+        .get(OCW_SIGNATURE_HEADER)
+        .ok_or(UserErr::InvalidSignature("No off-chain worker signature"))?;
+    let signature: [u8; 64] = hex::decode(signature.as_bytes())?.try_into().map_err(|_| {
+        UserErr::InvalidSignature("Off-chain worker signature has the wrong length")
+    })?;
+
+    let stash_address = get_stash_address(api, rpc, threshold_account_id).await?;
+    let session_keys_query = entropy::storage().session().next_keys(&stash_address);
+    let session_keys = query_chain(api, rpc, session_keys_query, None)
+        .await?
+        .ok_or_else(|| UserErr::ChainFetch("Session keys fetch error"))?;
+    let propagation_key = sr25519::Public::from_raw(session_keys.propagation.0 .0);
+
+    if !sr25519::Pair::verify(&sr25519::Signature::from_raw(signature), message, &propagation_key) {
+        return Err(UserErr::InvalidSignature(
+            "Off-chain worker signature is not from our validator",
+        ));
+    }
+    Ok(())
+}
+
+/// Queries the user's program from the chain, as it was at the given block hash if one is given
 pub async fn get_program(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
     program_pointer: &<EntropyConfig as Config>::Hash,
+    block_hash: Option<<EntropyConfig as Config>::Hash>,
 ) -> Result<Vec<u8>, UserErr> {
-    let bytecode_address = entropy::storage().programs().programs(program_pointer);
+    Ok(get_program_info(api, rpc, program_pointer, block_hash).await?.bytecode)
+}
+
+/// Gets all the stored information about a program, including its bytecode
+///
+/// The program pointer may also be the id of a program family, in which case the version of the
+/// program which was current at the given block hash is returned, or the current version if no
+/// block hash is given.
+pub async fn get_program_info(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    program_pointer: &<EntropyConfig as Config>::Hash,
+    block_hash: Option<<EntropyConfig as Config>::Hash>,
+) -> Result<ProgramInfo<AccountId32>, UserErr> {
+    let program_info_query = entropy::storage().programs().programs(program_pointer);
+    if let Some(program_info) = query_chain(api, rpc, program_info_query, block_hash).await? {
+        return Ok(program_info);
+    }
 
-    Ok(query_chain(api, rpc, bytecode_address, None)
+    let program_family_query = entropy::storage().programs().program_families(program_pointer);
+    let program_family = query_chain(api, rpc, program_family_query, block_hash)
         .await?
-        .ok_or(UserErr::NoProgramDefined(program_pointer.to_string()))?
-        .bytecode)
+        .ok_or(UserErr::NoProgramDefined(program_pointer.to_string()))?;
+
+    let program_info_query = entropy::storage().programs().programs(program_family.program_hash);
+    query_chain(api, rpc, program_info_query, block_hash)
+        .await?
+        .ok_or(UserErr::NoProgramDefined(program_family.program_hash.to_string()))
+}
+
+/// Gets the oracle data which a program needs, as it was at the given block
+///
+/// Returns `None` if the program does not use oracle data.
+pub async fn get_oracle_data(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    oracle_data_pointer: &[u8],
+    block_number: u32,
+) -> Result<Option<Vec<u8>>, UserErr> {
+    if oracle_data_pointer.is_empty() {
+        return Ok(None);
+    }
+    let block_hash = rpc.chain_get_block_hash(Some(block_number.into())).await?;
+    let oracle_data_query =
+        entropy::storage().oracle().oracle_data(BoundedVec(oracle_data_pointer.to_vec()));
+    let oracle_data =
+        query_chain(api, rpc, oracle_data_query, block_hash).await?.ok_or_else(|| {
+            UserErr::NoOracleDataForPointer(
+                String::from_utf8_lossy(oracle_data_pointer).to_string(),
+            )
+        })?;
+    Ok(Some(oracle_data.0))
 }
 
 /// Returns a registered user's key visibility
@@ -74,6 +154,25 @@ pub async fn get_registered_details(
     Ok(result)
 }
 
+/// Returns the signature request rate limit of a verifying key, falling back to the network wide
+/// request limit, per block, if the key does not have its own
+pub async fn get_rate_limit(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    verifying_key: Vec<u8>,
+) -> Result<RateLimit, UserErr> {
+    let rate_limit_query = entropy::storage().registry().rate_limits(BoundedVec(verifying_key));
+    if let Some(rate_limit) = query_chain(api, rpc, rate_limit_query, None).await? {
+        return Ok(rate_limit.0);
+    }
+
+    let request_limit_query = entropy::storage().parameters().request_limit();
+    let max_requests = query_chain(api, rpc, request_limit_query, None)
+        .await?
+        .ok_or_else(|| UserErr::ChainFetch("Failed to get request limit"))?;
+    Ok(RateLimit { max_requests, window_blocks: 1 })
+}
+
 /// Takes Stash keys and returns validator info from chain
 pub async fn get_validators_info(
     api: &OnlineClient<EntropyConfig>,
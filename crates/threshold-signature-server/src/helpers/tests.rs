@@ -22,7 +22,7 @@ use crate::{
     app,
     chain_api::{
         entropy::{self, runtime_types::bounded_collections::bounded_vec::BoundedVec},
-        EntropyConfig,
+        get_api, get_rpc, EntropyConfig,
     },
     get_signer,
     helpers::{
@@ -33,17 +33,23 @@ use crate::{
         logger::{Instrumentation, Logger},
         substrate::{query_chain, submit_transaction},
     },
-    signing_client::ListenerState,
     AppState,
 };
 use axum::{routing::IntoMakeService, Router};
 use entropy_kvdb::{encrypted_sled::PasswordMethod, get_db_path, kv_manager::KvManager};
 use entropy_protocol::PartyId;
-use entropy_shared::{DAVE_VERIFYING_KEY, EVE_VERIFYING_KEY, NETWORK_PARENT_KEY};
+use entropy_shared::{
+    OcwMessageProgramChange, DAVE_VERIFYING_KEY, EVE_VERIFYING_KEY, NETWORK_PARENT_KEY,
+    OCW_SIGNATURE_HEADER,
+};
+use parity_scale_codec::Encode;
 use std::time::Duration;
 use subxt::{
-    backend::legacy::LegacyRpcMethods, ext::sp_core::sr25519, tx::PairSigner,
-    utils::AccountId32 as SubxtAccountId32, Config, OnlineClient,
+    backend::legacy::LegacyRpcMethods,
+    ext::sp_core::{sr25519, Pair},
+    tx::PairSigner,
+    utils::AccountId32 as SubxtAccountId32,
+    Config, OnlineClient,
 };
 use tokio::sync::OnceCell;
 
@@ -61,6 +67,14 @@ pub async fn initialize_test_logger() {
     *LOGGER.get_or_init(|| instrumentation.setup()).await
 }
 
+/// The configuration of the threshold servers run by tests, which receive signing job callbacks on
+/// the loopback address
+pub fn test_configuration() -> Configuration {
+    let mut configuration = Configuration::new(DEFAULT_ENDPOINT.to_string());
+    configuration.allow_private_callback_urls = true;
+    configuration
+}
+
 pub async fn setup_client() -> KvManager {
     let kv_store =
         KvManager::new(get_db_path(true).into(), PasswordMethod::NoPassword.execute().unwrap())
@@ -70,9 +84,8 @@ pub async fn setup_client() -> KvManager {
     setup_mnemonic(&kv_store, mnemonic).await;
 
     let _ = setup_latest_block_number(&kv_store).await;
-    let listener_state = ListenerState::default();
-    let configuration = Configuration::new(DEFAULT_ENDPOINT.to_string());
-    let app_state = AppState { listener_state, configuration, kv_store: kv_store.clone() };
+    let configuration = test_configuration();
+    let app_state = AppState::new(configuration, kv_store.clone());
     let app = app(app_state).into_make_service();
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3001")
@@ -91,8 +104,7 @@ pub async fn create_clients(
     keys: Vec<String>,
     validator_name: &Option<ValidatorName>,
 ) -> (IntoMakeService<Router>, KvManager) {
-    let listener_state = ListenerState::default();
-    let configuration = Configuration::new(DEFAULT_ENDPOINT.to_string());
+    let configuration = test_configuration();
 
     let path = format!(".entropy/testing/test_db_{key_number}");
     let _ = std::fs::remove_dir_all(path.clone());
@@ -110,7 +122,7 @@ pub async fn create_clients(
         let _ = kv_store.clone().kv().put(reservation, value).await;
     }
 
-    let app_state = AppState { listener_state, configuration, kv_store: kv_store.clone() };
+    let app_state = AppState::new(configuration, kv_store.clone());
 
     let app = app(app_state).into_make_service();
 
@@ -168,12 +180,64 @@ pub async fn spawn_testing_validators(add_parent_key: bool) -> (Vec<String>, Vec
         axum::serve(listener_charlie, charlie_axum).await.unwrap();
     });
 
+    tokio::spawn(relay_program_changes());
+
     tokio::time::sleep(Duration::from_secs(1)).await;
 
     let ips = ports.iter().map(|port| format!("127.0.0.1:{port}")).collect();
     (ips, ids)
 }
 
+/// Passes program changes on to Bob's and Charlie's threshold servers, as their validators'
+/// off-chain workers would.
+///
+/// Tests only run Alice's node, whose off-chain worker only calls Alice's threshold server, but
+/// `threshold` of an account's signing committee must confirm a change to its programs.
+async fn relay_program_changes() {
+    let (api, rpc) = loop {
+        match (get_api(DEFAULT_ENDPOINT).await, get_rpc(DEFAULT_ENDPOINT).await) {
+            (Ok(api), Ok(rpc)) => break (api, rpc),
+            _ => tokio::time::sleep(Duration::from_secs(1)).await,
+        }
+    };
+    let client = reqwest::Client::new();
+    let mut relayed_block_number = 0;
+    loop {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let Ok(Some(header)) = rpc.chain_get_header(None).await else {
+            continue;
+        };
+        // Like the off-chain worker, pass on the changes requested in the previous block
+        let block_number = header.number.saturating_sub(1);
+        if block_number <= relayed_block_number {
+            continue;
+        }
+        relayed_block_number = block_number;
+
+        let program_changes_query = entropy::storage().registry().program_changes(block_number);
+        let verifying_keys = match query_chain(&api, &rpc, program_changes_query, None).await {
+            Ok(Some(verifying_keys)) if !verifying_keys.is_empty() => verifying_keys,
+            _ => continue,
+        };
+        let message = OcwMessageProgramChange {
+            block_number,
+            verifying_keys: verifying_keys
+                .into_iter()
+                .map(|verifying_key| verifying_key.0)
+                .collect(),
+        }
+        .encode();
+        for port in [3002, 3003] {
+            let _ = client
+                .post(format!("http://127.0.0.1:{port}/user/check_program_change"))
+                .header(OCW_SIGNATURE_HEADER, sign_ocw_message(port, &message))
+                .body(message.clone())
+                .send()
+                .await;
+        }
+    }
+}
+
 /// Add the pre-generated test keyshares to a kvdb
 async fn put_keyshares_in_db(holder_name: &str, kvdb: KvManager, add_parent_key: bool) {
     let mut user_names_and_verifying_keys =
@@ -263,3 +327,17 @@ pub async fn unsafe_get(client: &reqwest::Client, query_key: String, port: u32)
 
     get_result.bytes().await.unwrap().into()
 }
+
+/// Sign a message to the threshold server on the given port as its validator's off-chain worker
+/// would, giving the hex encoded signature to put in the [entropy_shared::OCW_SIGNATURE_HEADER]
+/// header
+pub fn sign_ocw_message(port: u32, message: &[u8]) -> String {
+    let seed = match port {
+        3001 => "//Alice",
+        3002 => "//Bob",
+        3003 => "//Charlie",
+        _ => panic!("No validator for port {port}"),
+    };
+    let pair = sr25519::Pair::from_string(seed, None).expect("Dev seeds are valid");
+    hex::encode(pair.sign(message))
+}
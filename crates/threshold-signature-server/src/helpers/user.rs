@@ -16,28 +16,62 @@
 //! Utilities relating to the user
 use std::time::Duration;
 
-use entropy_programs_runtime::Runtime;
+use entropy_kvdb::kv_manager::helpers::serialize as key_serialize;
 use entropy_protocol::{
-    execute_protocol::{execute_dkg, Channels},
-    KeyShareWithAuxInfo, Listener, SessionId, ValidatorInfo,
+    errors::ProtocolExecutionErr,
+    execute_protocol::{execute_dkg, execute_frost_dkg, execute_key_import, Channels},
+    Ed25519KeyShare, KeyParams, KeyShareWithAuxInfo, Listener, PartyId, SessionId, ValidatorInfo,
 };
-use entropy_shared::{HashingAlgorithm, SETUP_TIMEOUT_SECONDS};
+use entropy_shared::{HashingAlgorithm, SignatureScheme, SETUP_TIMEOUT_SECONDS};
 
-use sha1::{Digest as Sha1Digest, Sha1};
 use sha2::{Digest as Sha256Digest, Sha256};
 use sha3::{Digest as Sha3Digest, Keccak256, Sha3_256};
 use sp_core::{hashing::blake2_256, sr25519, Pair};
-use subxt::{backend::legacy::LegacyRpcMethods, tx::PairSigner, utils::AccountId32, OnlineClient};
+use subxt::{
+    backend::legacy::LegacyRpcMethods, tx::PairSigner, utils::AccountId32, Config, OnlineClient,
+};
+use synedrion::ThresholdKeyShare;
 use tokio::time::timeout;
 use x25519_dalek::StaticSecret;
 
 use crate::{
     chain_api::{entropy::runtime_types::pallet_registry::pallet::ProgramInstance, EntropyConfig},
-    helpers::substrate::get_program,
+    helpers::{program_runtime::ProgramRuntime, substrate::get_program},
     signing_client::{protocol_transport::open_protocol_connections, ListenerState},
     user::errors::UserErr,
 };
-/// complete the dkg process for a new user
+/// The keyshare resulting from a DKG, which depends on the signature scheme of the account
+pub enum DkgOutput {
+    Ecdsa(KeyShareWithAuxInfo),
+    Ed25519(Ed25519KeyShare),
+}
+
+impl DkgOutput {
+    /// The serialized verifying key of the newly generated key
+    pub fn verifying_key(&self) -> Result<Vec<u8>, UserErr> {
+        match self {
+            DkgOutput::Ecdsa((key_share, _)) => {
+                Ok(key_share.verifying_key().to_encoded_point(true).as_bytes().to_vec())
+            },
+            DkgOutput::Ed25519((_, public_key_package)) => Ok(public_key_package
+                .verifying_key()
+                .serialize()
+                .map_err(ProtocolExecutionErr::from)?),
+        }
+    }
+
+    /// Serialize the keyshare so that it can be stored in the kvdb
+    pub fn serialize(&self) -> Result<Vec<u8>, UserErr> {
+        match self {
+            DkgOutput::Ecdsa(key_share_with_aux_info) => key_serialize(key_share_with_aux_info),
+            DkgOutput::Ed25519(key_share) => key_serialize(key_share),
+        }
+        .map_err(|_| UserErr::KvSerialize("Kv Serialize Error".to_string()))
+    }
+}
+
+/// complete the dkg process for a new user, amongst the given validators with the given threshold
+#[allow(clippy::too_many_arguments)]
 pub async fn do_dkg(
     validators_info: &Vec<entropy_shared::ValidatorInfo>,
     signer: &PairSigner<EntropyConfig, sr25519::Pair>,
@@ -45,9 +79,11 @@ pub async fn do_dkg(
     state: &ListenerState,
     sig_request_account: AccountId32,
     block_number: u32,
-) -> Result<KeyShareWithAuxInfo, UserErr> {
-    let session_id = SessionId::Dkg { user: sig_request_account.clone(), block_number };
-    let account_id = AccountId32(signer.signer().public().0);
+    signature_scheme: SignatureScheme,
+    threshold: usize,
+) -> Result<DkgOutput, UserErr> {
+    let session_id =
+        SessionId::Dkg { user: sig_request_account.clone(), block_number, signature_scheme };
     let mut converted_validator_info = vec![];
     let mut tss_accounts = vec![];
     for validator_info in validators_info {
@@ -66,9 +102,68 @@ pub async fn do_dkg(
         tss_accounts.push(tss_account);
     }
 
+    let channels =
+        open_channels(&converted_validator_info, &session_id, signer, x25519_secret_key, state)
+            .await?;
+
+    let result = match signature_scheme {
+        SignatureScheme::Ecdsa => DkgOutput::Ecdsa(
+            execute_dkg(session_id, channels, signer.signer(), tss_accounts, threshold).await?,
+        ),
+        SignatureScheme::Ed25519 => DkgOutput::Ed25519(
+            execute_frost_dkg(session_id, channels, signer.signer(), tss_accounts, threshold)
+                .await?,
+        ),
+    };
+    Ok(result)
+}
+
+/// Import an existing ECDSA key for a user, given this server's share of an n-of-n sharing of it
+///
+/// The shares are reshared amongst the given validators with the given threshold, so the verifying
+/// key stays the same but the shares made by the user are no longer useful.
+#[allow(clippy::too_many_arguments)]
+pub async fn do_key_import(
+    validators_info: &[ValidatorInfo],
+    signer: &PairSigner<EntropyConfig, sr25519::Pair>,
+    x25519_secret_key: &StaticSecret,
+    state: &ListenerState,
+    sig_request_account: AccountId32,
+    block_number: u32,
+    imported_key_share: ThresholdKeyShare<KeyParams, PartyId>,
+    threshold: usize,
+) -> Result<DkgOutput, UserErr> {
+    let session_id = SessionId::KeyImport { user: sig_request_account, block_number };
+    let tss_accounts: Vec<AccountId32> =
+        validators_info.iter().map(|validator_info| validator_info.tss_account.clone()).collect();
+
+    let channels =
+        open_channels(validators_info, &session_id, signer, x25519_secret_key, state).await?;
+
+    let key_share_with_aux_info = execute_key_import(
+        session_id,
+        channels,
+        signer.signer(),
+        tss_accounts,
+        imported_key_share,
+        threshold,
+    )
+    .await?;
+    Ok(DkgOutput::Ecdsa(key_share_with_aux_info))
+}
+
+/// Subscribe to all other parties in a protocol session, and wait until connected to them all
+async fn open_channels(
+    validators_info: &[ValidatorInfo],
+    session_id: &SessionId,
+    signer: &PairSigner<EntropyConfig, sr25519::Pair>,
+    x25519_secret_key: &StaticSecret,
+    state: &ListenerState,
+) -> Result<Channels, UserErr> {
+    let account_id = AccountId32(signer.signer().public().0);
+
     // subscribe to all other participating parties. Listener waits for other subscribers.
-    let (rx_ready, rx_from_others, listener) =
-        Listener::new(converted_validator_info.clone(), &account_id);
+    let (rx_ready, rx_from_others, listener) = Listener::new(validators_info.to_vec(), &account_id);
     state
         .listeners
         .lock()
@@ -76,24 +171,17 @@ pub async fn do_dkg(
         .insert(session_id.clone(), listener);
 
     open_protocol_connections(
-        &converted_validator_info,
-        &session_id,
+        validators_info,
+        session_id,
         signer.signer(),
         state,
         x25519_secret_key,
     )
     .await?;
-    let channels = {
-        let ready = timeout(Duration::from_secs(SETUP_TIMEOUT_SECONDS), rx_ready).await?;
-        let broadcast_out = ready??;
-        Channels(broadcast_out, rx_from_others)
-    };
 
-    // TODO #898 For now we use a fix proportion of the number of validators as the threshold
-    let threshold = (tss_accounts.len() as f32 * 0.75) as usize;
-    let result =
-        execute_dkg(session_id, channels, signer.signer(), tss_accounts, threshold).await?;
-    Ok(result)
+    let ready = timeout(Duration::from_secs(SETUP_TIMEOUT_SECONDS), rx_ready).await?;
+    let broadcast_out = ready??;
+    Ok(Channels(broadcast_out, rx_from_others))
 }
 
 /// Checks if a validator is in the current selected registration committee
@@ -111,23 +199,20 @@ pub fn check_in_registration_group(
 }
 
 /// Generate the a hash of `message` to be signed based on the `hash` algorithm
+///
+/// A custom hash uses the program as it was at the given block hash, so that it is the same version
+/// which was evaluated for the request.
 pub async fn compute_hash(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
     hashing_algorithm: &HashingAlgorithm,
-    runtime: &mut Runtime,
+    runtime: &mut ProgramRuntime,
     programs_data: &[ProgramInstance],
     message: &[u8],
+    block_hash: Option<<EntropyConfig as Config>::Hash>,
 ) -> Result<[u8; 32], UserErr> {
     match hashing_algorithm {
-        HashingAlgorithm::Sha1 => {
-            let mut hasher = <Sha1 as Sha1Digest>::new();
-            hasher.update(message);
-            let result = hasher.finalize();
-            let mut hash = [0u8; 32];
-            hash.copy_from_slice(&result);
-            Ok(hash)
-        },
+        HashingAlgorithm::Sha1 => Err(UserErr::Sha1NotSupported),
         HashingAlgorithm::Sha2 => {
             let mut hasher = <Sha256 as Sha256Digest>::new();
             hasher.update(message);
@@ -153,8 +238,10 @@ pub async fn compute_hash(
             Ok(hash)
         },
         HashingAlgorithm::Blake2_256 => Ok(blake2_256(message)),
+        HashingAlgorithm::Prehashed => message.try_into().map_err(|_| UserErr::BadPrehashLength),
         HashingAlgorithm::Custom(i) => {
-            let program = get_program(api, rpc, &programs_data[*i].program_pointer).await?;
+            let program =
+                get_program(api, rpc, &programs_data[*i].program_pointer, block_hash).await?;
             runtime.custom_hash(program.as_slice(), message).map_err(|e| e.into())
         },
         _ => Err(UserErr::UnknownHashingAlgorithm),
@@ -40,7 +40,8 @@
 //! [UserSignatureRequest] encrypted in a [SignedMessage](crate::validation::SignedMessage).
 //!
 //! The response is chunked response stream. If the `UserSignatureRequest` could be processed, a
-//! success response header is sent.  Then the signing protocol runs. When the it finishes, a single
+//! success response header is sent, with the hex encoded hash of the message to be signed in the
+//! `x-entropy-message-hash` header.  Then the signing protocol runs. When the it finishes, a single
 //! message will be sent on the response stream with the result.
 //!
 //! If everything went well, the message will be a JSON object with a signle property "Ok"
@@ -70,6 +71,79 @@
 //!   http://127.0.0.1:3001/user/sign_tx
 //! ```
 //!
+//! #### `/user/sign_batch` - POST
+//!
+//! [crate::user::api::sign_batch()]
+//!
+//! Called by a user to sign several messages with the same verifying key in one request. Takes a
+//! [UserSignatureBatchRequest] encrypted in a [SignedMessage](crate::validation::SignedMessage),
+//! where each message has its own hashing algorithm and auxilary data.
+//!
+//! All messages are evaluated against the account's programs before signing begins. The response
+//! is a chunked response stream with one message per signature, in the same order as the request
+//! and in the same format as the result from `/user/sign_tx`. Each message in the batch counts
+//! towards the account's request limit.
+//!
+//! This is a convenience endpoint which saves making a request per message. Each message is still
+//! signed in its own signing protocol session, so a batch takes as long as signing its messages one
+//! after another.
+//!
+//! #### `/user/sign_tx_async` - POST
+//!
+//! [crate::user::api::sign_tx_async()]
+//!
+//! Takes the same input as `/user/sign_tx`, but rather than keeping the connection open until the
+//! signing protocol finishes, responds straight away with `202 Accepted` and a hex encoded job ID
+//! in the body. The request is then checked, evaluated and signed in the background, and the job's
+//! status can be polled with `/user/sign_status/{job_id}` for an hour. A job which had not finished
+//! when the server restarted is marked as failed.
+//!
+//! If the [UserSignatureRequest] has a `callback_url`, the finished job is also POSTed there as a
+//! JSON object with a `job_id` and a `status`. The callback URL must not resolve to a private,
+//! loopback or link-local address.
+//!
+//! #### `/user/sign_status/{job_id}` - GET
+//!
+//! [crate::user::api::sign_status()]
+//!
+//! Returns the status of a signing job as the JSON string `"signing"`, or once the job finishes
+//! either `{"done":[signature, tss_signature]}` with the same two strings as the result from
+//! `/user/sign_tx`, or `{"failed":error}`. Responds with `404 Not Found` for an unknown or expired
+//! job ID.
+//!
+//! #### `/user/evaluate` - POST
+//!
+//! [crate::user::api::evaluate()]
+//!
+//! Takes the same input as `/user/sign_tx`, and evaluates the message against each of the
+//! account's programs without signing it, for example to test a program against a registered
+//! account. Responds with a JSON array containing, for each program, its hex encoded
+//! `program_pointer`, whether it `passed`, the `error` if it failed, and the `fuel_used`. Every
+//! program is evaluated even if an earlier one fails. The request does not count towards the
+//! account's request limit, but each request author may only make as many evaluation requests per
+//! block as the network wide request limit, and stale requests are rejected.
+//!
+//! #### `/user/import_key` - POST
+//!
+//! [crate::user::api::import_key()]
+//!
+//! Called by a user who has registered on chain with `register_with_imported_key`, to give this
+//! server its share of the imported ECDSA key. Takes a JSON serialized keyshare encrypted in a
+//! [SignedMessage](crate::validation::SignedMessage). Responds straight away, and once all
+//! validators have been given their share they reshare the key, store the new keyshares and
+//! confirm the registration on chain.
+//!
+//! #### `/user/export_keyshare` - POST
+//!
+//! [crate::user::api::export_keyshare()]
+//!
+//! Called by the program modification account of a verifying key to export this server's keyshare
+//! for recovery. Takes the verifying key encrypted in a
+//! [SignedMessage](crate::validation::SignedMessage), and responds with the JSON serialized
+//! keyshare in a [SignedMessage](crate::validation::SignedMessage) from this server, encrypted to
+//! the recovery key which was set on chain beforehand with `set_recovery_key`, once the parameters
+//! pallet's `RecoveryKeyDelay` has passed.
+//!
 //! ### For the blockchain node
 //!
 //! #### `/user/new` - POST
@@ -80,6 +154,43 @@
 //! This takes a parity scale encoded [entropy_shared::types::OcwMessageDkg] which tells us which
 //! validators are in the registration group and will perform a DKG.
 //!
+//! #### `/user/deregister` - POST
+//!
+//! [crate::user::api::deregister()]
+//!
+//! Called by the off-chain worker (propagation pallet) when accounts have been deregistered.
+//! This takes a parity scale encoded [entropy_shared::types::OcwMessageDeregister]. The keyshares
+//! of the deregistered accounts are deleted, and the deletion is confirmed on chain.
+//!
+//! #### `/user/check_program_change` - POST
+//!
+//! [crate::user::api::check_program_change()]
+//!
+//! Called by the off-chain worker (propagation pallet) when the programs of accounts have been
+//! changed. This takes a parity scale encoded [entropy_shared::types::OcwMessageProgramChange]. The
+//! new program configurations are checked against their programs' configuration schemas, and the
+//! change is confirmed or refused on chain.
+//!
+//! #### `/validator/reshare` - POST
+//!
+//! [crate::validator::api::new_reshare()]
+//!
+//! Called by the off-chain worker (propagation pallet) when the signers are being rotated.
+//! This takes a parity scale encoded [entropy_shared::types::OcwMessageReshare]. The network parent
+//! key is reshared between the next signers, who each keep their new key share aside and confirm
+//! the reshare on chain.
+//!
+//! #### `/validator/rotate_network_key` - POST
+//!
+//! [crate::validator::api::rotate_network_key()]
+//!
+//! Called by the off-chain worker (propagation pallet) once a signer rotation has either been
+//! confirmed by every next signer or rolled back. This takes a parity scale encoded
+//! [entropy_shared::types::OcwMessageSignerRotation]. The threshold server then reconciles its
+//! network parent key shares with the rotations recorded on chain: the new key share replaces the
+//! old one only if the rotation went through, and signers which have left delete their old share.
+//! The same reconciliation is done before each reshare, in case this message was missed.
+//!
 //! ### For other instances of the threshold server
 //!
 //!   Takes a [UserRegistrationInfo] containing the users account ID and associated keyshare, wrapped
@@ -99,6 +210,8 @@
 //! - [`/version`](crate::node_info::api::version()) - Get - get the node version info
 //! - [`/heathlz`](crate::health::api::healthz()) - Get - get if the node is running
 //! - [`/hashes`](crate::node_info::api::hashes()) - Get - get the hashes supported by the node
+//! - [`/metrics`](crate::metrics::api::metrics()) - Get - get metrics about this node in the
+//!   Prometheus text format, including the fuel used when evaluating programs
 
 //! ### For testing / development
 //!
@@ -124,6 +237,7 @@
 pub use entropy_client::chain_api;
 pub(crate) mod health;
 pub mod helpers;
+pub(crate) mod metrics;
 pub(crate) mod node_info;
 pub(crate) mod sign_init;
 pub(crate) mod signing_client;
@@ -144,43 +258,70 @@ use tower_http::{
 };
 use tracing::Level;
 
-pub use crate::helpers::{
-    launch,
-    validator::{get_signer, get_signer_and_x25519_secret},
-};
 use crate::{
     health::api::healthz,
     launch::Configuration,
+    metrics::{api::metrics as get_metrics, setup_metrics},
     node_info::api::{hashes, version as get_version},
     r#unsafe::api::{delete, put, remove_keys, unsafe_get},
-    signing_client::{api::*, ListenerState},
+    signing_client::{api::*, presignatures::Presignatures, ListenerState},
     user::api::*,
-    validator::api::new_reshare,
+    validator::api::{new_reshare, rotate_network_key},
+};
+pub use crate::{
+    helpers::{
+        launch,
+        validator::{get_signer, get_signer_and_x25519_secret},
+    },
+    signing_client::presignatures::presign_periodically,
 };
 
 #[derive(Clone)]
 pub struct AppState {
     listener_state: ListenerState,
+    pub signing_jobs: SigningJobs,
+    presignatures: Presignatures,
     pub configuration: Configuration,
     pub kv_store: KvManager,
 }
 
 impl AppState {
     pub fn new(configuration: Configuration, kv_store: KvManager) -> Self {
-        Self { listener_state: ListenerState::default(), configuration, kv_store }
+        Self {
+            listener_state: ListenerState::default(),
+            signing_jobs: SigningJobs::default(),
+            presignatures: Presignatures::default(),
+            configuration,
+            kv_store,
+        }
     }
 }
 
 pub fn app(app_state: AppState) -> Router {
+    // Metrics are only recorded once the recorder is installed, but the server runs without them
+    if let Err(error) = setup_metrics() {
+        tracing::error!("Unable to set up metrics: {}", error);
+    }
+
     let mut routes = Router::new()
         .route("/generate_network_key", post(generate_network_key))
         .route("/user/new", post(new_user))
+        .route("/user/deregister", post(deregister))
+        .route("/user/check_program_change", post(check_program_change))
         .route("/user/sign_tx", post(sign_tx))
+        .route("/user/sign_batch", post(sign_batch))
+        .route("/user/sign_tx_async", post(sign_tx_async))
+        .route("/user/sign_status/:job_id", get(sign_status))
+        .route("/user/evaluate", post(evaluate))
+        .route("/user/import_key", post(import_key))
+        .route("/user/export_keyshare", post(export_keyshare))
         .route("/signer/proactive_refresh", post(proactive_refresh))
         .route("/validator/reshare", post(new_reshare))
+        .route("/validator/rotate_network_key", post(rotate_network_key))
         .route("/healthz", get(healthz))
         .route("/version", get(get_version))
         .route("/hashes", get(hashes))
+        .route("/metrics", get(get_metrics))
         .route("/ws", get(ws_handler));
 
     // Unsafe routes are for testing purposes only
@@ -23,6 +23,8 @@ use entropy_tss::{
         development_mnemonic, load_kv_store, setup_latest_block_number, setup_mnemonic, setup_only,
         Configuration, StartupArgs, ValidatorName,
     },
+    presign_periodically,
+    user::api::sweep_signing_jobs,
     AppState,
 };
 
@@ -40,7 +42,8 @@ async fn main() {
         tracing::info!("Sending logs to Loki server at `{}`", &args.logger.loki_endpoint);
     }
 
-    let configuration = Configuration::new(args.chain_endpoint);
+    let mut configuration = Configuration::new(args.chain_endpoint);
+    configuration.allow_private_callback_urls = args.allow_private_callback_urls;
     if !args.setup_only {
         tracing::info!("Connecting to Substrate node at: `{}`", &configuration.endpoint);
     }
@@ -115,6 +118,11 @@ async fn main() {
         )
         .await;
 
+        // Expired signing jobs are removed by one task, rather than one for each job
+        tokio::spawn(sweep_signing_jobs(app_state.signing_jobs.clone(), kv_store.clone()));
+        // Presignature pools are topped up every few blocks, as well as after signing
+        tokio::spawn(presign_periodically(app_state.clone()));
+
         let listener = tokio::net::TcpListener::bind(&addr)
             .await
             .expect("Unable to bind to given server address.");
@@ -0,0 +1,39 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use thiserror::Error;
+
+/// Errors for setting up and rendering metrics
+#[derive(Debug, Error)]
+pub enum MetricsErr {
+    #[error("Cannot install metrics recorder: {0}")]
+    Install(#[from] metrics_exporter_prometheus::BuildError),
+    #[error("Cannot spawn metrics upkeep thread: {0}")]
+    UpkeepThread(#[from] std::io::Error),
+    #[error("Metrics recorder is not installed")]
+    NotInstalled,
+}
+
+impl IntoResponse for MetricsErr {
+    fn into_response(self) -> Response {
+        tracing::error!("{:?}", format!("{self}"));
+        let body = format!("{self}").into_bytes();
+        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+    }
+}
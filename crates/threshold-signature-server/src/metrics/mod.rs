@@ -0,0 +1,103 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Metrics about this instance of `entropy-tss`, in the Prometheus text format
+pub mod api;
+pub mod errors;
+
+#[cfg(test)]
+mod tests;
+
+use errors::MetricsErr;
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::{
+    collections::BTreeSet,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+/// How often histogram samples are drained into the summaries which are rendered
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The most programs which get their own label value, as anyone can store a program and each
+/// label value is kept for the life of the process
+pub const MAX_LABELLED_PROGRAMS: usize = 100;
+
+/// The label value for programs evaluated once [MAX_LABELLED_PROGRAMS] have their own
+pub const OTHER_PROGRAMS_LABEL: &str = "other";
+
+static PROMETHEUS_HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Stops two callers from installing the recorder at once
+static SETUP_LOCK: Mutex<()> = Mutex::new(());
+
+static LABELLED_PROGRAMS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Installs the metrics recorder, if it is not already installed
+///
+/// Without the HTTP listener, the recorder does not run its own upkeep, so it is run on a
+/// separate thread to stop histogram samples building up between scrapes.
+pub fn setup_metrics() -> Result<&'static PrometheusHandle, MetricsErr> {
+    let _guard = SETUP_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(handle) = PROMETHEUS_HANDLE.get() {
+        return Ok(handle);
+    }
+
+    let handle = PrometheusBuilder::new().install_recorder()?;
+    let upkeep_handle = handle.clone();
+    std::thread::Builder::new().name("metrics-upkeep".to_string()).spawn(move || loop {
+        std::thread::sleep(UPKEEP_INTERVAL);
+        upkeep_handle.run_upkeep();
+    })?;
+    Ok(PROMETHEUS_HANDLE.get_or_init(|| handle))
+}
+
+/// Returns a handle for rendering the recorded metrics, once the recorder is installed
+pub fn prometheus_handle() -> Result<&'static PrometheusHandle, MetricsErr> {
+    PROMETHEUS_HANDLE.get().ok_or(MetricsErr::NotInstalled)
+}
+
+/// Records the fuel used when evaluating a program, and whether it passed
+///
+/// Metrics are labelled by program hash for the first [MAX_LABELLED_PROGRAMS] programs
+/// evaluated, and programs after that share the [OTHER_PROGRAMS_LABEL] label.
+pub fn record_program_evaluation(program_hash: &str, fuel_used: u64, passed: bool) {
+    let outcome = if passed { "passed" } else { "failed" };
+    let program = program_label(program_hash);
+    ::metrics::counter!(
+        "entropy_program_evaluations_total",
+        "program" => program.clone(),
+        "outcome" => outcome
+    )
+    .increment(1);
+    ::metrics::counter!("entropy_program_fuel_used_total", "program" => program.clone())
+        .increment(fuel_used);
+    ::metrics::histogram!("entropy_program_fuel_used", "program" => program)
+        .record(fuel_used as f64);
+}
+
+/// Gets the label value for a program, adding it to the labelled programs if there is room
+fn program_label(program_hash: &str) -> String {
+    let mut labelled_programs =
+        LABELLED_PROGRAMS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if labelled_programs.contains(program_hash) {
+        return program_hash.to_string();
+    }
+    if labelled_programs.len() < MAX_LABELLED_PROGRAMS {
+        labelled_programs.insert(program_hash.to_string());
+        return program_hash.to_string();
+    }
+    OTHER_PROGRAMS_LABEL.to_string()
+}
@@ -0,0 +1,66 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{record_program_evaluation, MAX_LABELLED_PROGRAMS, OTHER_PROGRAMS_LABEL};
+use crate::helpers::tests::{initialize_test_logger, setup_client};
+use entropy_kvdb::clean_tests;
+use serial_test::serial;
+
+#[tokio::test]
+#[serial]
+async fn metrics_test() {
+    clean_tests();
+    initialize_test_logger().await;
+    setup_client().await;
+
+    let metrics_before =
+        reqwest::get("http://127.0.0.1:3001/metrics").await.unwrap().text().await.unwrap();
+    let fuel_used_before = fuel_used_total(&metrics_before, "metrics_test_program");
+
+    record_program_evaluation("metrics_test_program", 1000, true);
+    record_program_evaluation("metrics_test_program", 500, false);
+
+    let metrics =
+        reqwest::get("http://127.0.0.1:3001/metrics").await.unwrap().text().await.unwrap();
+    assert!(metrics.contains(
+        "entropy_program_evaluations_total{program=\"metrics_test_program\",outcome=\"passed\"}"
+    ));
+    assert!(metrics.contains(
+        "entropy_program_evaluations_total{program=\"metrics_test_program\",outcome=\"failed\"}"
+    ));
+    assert_eq!(fuel_used_total(&metrics, "metrics_test_program"), fuel_used_before + 1500);
+
+    // Once the limit is reached, further programs share one label
+    for i in 0..MAX_LABELLED_PROGRAMS {
+        record_program_evaluation(&format!("metrics_test_program_{i}"), 1, true);
+    }
+    let metrics =
+        reqwest::get("http://127.0.0.1:3001/metrics").await.unwrap().text().await.unwrap();
+    assert!(!metrics
+        .contains(&format!("program=\"metrics_test_program_{}\"", MAX_LABELLED_PROGRAMS - 1)));
+    assert!(metrics.contains(&format!("program=\"{OTHER_PROGRAMS_LABEL}\"")));
+    clean_tests();
+}
+
+/// Gets the total fuel used by a program from rendered metrics, as other tests may also have
+/// evaluated programs
+fn fuel_used_total(metrics: &str, program: &str) -> u64 {
+    metrics
+        .lines()
+        .find_map(|line| {
+            line.strip_prefix(&format!("entropy_program_fuel_used_total{{program=\"{program}\"}} "))
+        })
+        .map_or(0, |total| total.parse().unwrap())
+}
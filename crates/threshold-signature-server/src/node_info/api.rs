@@ -22,8 +22,12 @@ pub async fn version() -> String {
     format!("{}-{}", env!("CARGO_PKG_VERSION"), env!("VERGEN_GIT_DESCRIBE"))
 }
 
+/// Returns the hashing algorithms which can be used to sign a message
 #[tracing::instrument]
 pub async fn hashes() -> Json<Vec<HashingAlgorithm>> {
-    let hashing_algos = HashingAlgorithm::iter().collect::<Vec<_>>();
+    // SHA-1 does not give a 32 byte hash, so cannot be used to sign
+    let hashing_algos = HashingAlgorithm::iter()
+        .filter(|hashing_algorithm| *hashing_algorithm != HashingAlgorithm::Sha1)
+        .collect::<Vec<_>>();
     Json(hashing_algos)
 }
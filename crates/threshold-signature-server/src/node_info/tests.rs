@@ -45,12 +45,12 @@ async fn hashes_test() {
     assert_eq!(
         algorithms,
         vec![
-            HashingAlgorithm::Sha1,
             HashingAlgorithm::Sha2,
             HashingAlgorithm::Sha3,
             HashingAlgorithm::Keccak,
             HashingAlgorithm::Blake2_256,
             HashingAlgorithm::Custom(0),
+            HashingAlgorithm::Prehashed,
         ]
     );
     clean_tests();
@@ -21,7 +21,7 @@ use axum::{
         ws::{WebSocket, WebSocketUpgrade},
         State,
     },
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
 };
 use blake2::{Blake2s256, Digest};
@@ -35,7 +35,7 @@ use entropy_kvdb::kv_manager::{
     helpers::{deserialize, serialize as key_serialize},
     KvManager,
 };
-use entropy_shared::{OcwMessageProactiveRefresh, SETUP_TIMEOUT_SECONDS};
+use entropy_shared::{OcwMessageProactiveRefresh, SignatureScheme, SETUP_TIMEOUT_SECONDS};
 use parity_scale_codec::Decode;
 use sp_core::Pair;
 use subxt::{
@@ -51,12 +51,21 @@ use x25519_dalek::StaticSecret;
 
 use crate::{
     chain_api::{
-        entropy::{self, runtime_types::pallet_staking_extension::pallet::RefreshInfo},
+        entropy::{
+            self,
+            runtime_types::{
+                bounded_collections::bounded_vec::BoundedVec,
+                pallet_registry::pallet::RegisteredInfo,
+                pallet_staking_extension::pallet::RefreshInfo,
+            },
+        },
         get_api, get_rpc, EntropyConfig,
     },
     helpers::{
-        launch::LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH, substrate::query_chain,
-        user::check_in_registration_group, validator::get_signer_and_x25519_secret,
+        launch::LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH,
+        substrate::{get_validators_info, query_chain, verify_ocw_signature},
+        user::check_in_registration_group,
+        validator::get_signer_and_x25519_secret,
     },
     signing_client::{
         protocol_transport::{handle_socket, open_protocol_connections},
@@ -73,10 +82,12 @@ pub const SUBSCRIBE_TIMEOUT_SECONDS: u64 = 10;
 /// initiates this request.
 ///
 /// The HTTP request takes a Parity SCALE encoded [ValidatorInfo] which indicates which validators
-/// are in the registration group and will perform a proactive refresh.
+/// are in the registration group and will perform a proactive refresh. The message must be signed
+/// with our validator's propagation session key.
 #[tracing::instrument(skip_all)]
 pub async fn proactive_refresh(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     encoded_data: Bytes,
 ) -> Result<StatusCode, ProtocolErr> {
     let ocw_data = OcwMessageProactiveRefresh::decode(&mut encoded_data.as_ref())?;
@@ -85,6 +96,9 @@ pub async fn proactive_refresh(
     let (signer, x25519_secret_key) = get_signer_and_x25519_secret(&app_state.kv_store)
         .await
         .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
+    verify_ocw_signature(&api, &rpc, signer.account_id(), &headers, &encoded_data)
+        .await
+        .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
 
     check_in_registration_group(&ocw_data.validators_info, signer.account_id())
         .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
@@ -95,6 +109,17 @@ pub async fn proactive_refresh(
         // key should always exist, figure out how to handle
         let exists_result = app_state.kv_store.kv().exists(&key).await?;
         if exists_result {
+            let registered_query =
+                entropy::storage().registry().registered(BoundedVec(encoded_key.clone()));
+            let registered_info = query_chain(&api, &rpc, registered_query, None).await?;
+            // Proactive refresh is only supported for ECDSA keys, so Ed25519 keys are left as
+            // they are. Keys which are not registered, such as the network parent key, are ECDSA.
+            if let Some(registered_info) = &registered_info {
+                if registered_info.signature_scheme.0 != SignatureScheme::Ecdsa {
+                    tracing::debug!("Skipping proactive refresh of Ed25519 key {}", key);
+                    continue;
+                }
+            }
             let old_key_share = app_state.kv_store.kv().get(&key).await?;
             let (deserialized_old_key, _aux_info): (
                 ThresholdKeyShare<KeyParams, PartyId>,
@@ -102,8 +127,11 @@ pub async fn proactive_refresh(
             ) = deserialize(&old_key_share)
                 .ok_or_else(|| ProtocolErr::Deserialization("Failed to load KeyShare".into()))?;
 
+            let refresh_committee =
+                get_refresh_committee(&api, &rpc, &ocw_data.validators_info, &registered_info)
+                    .await?;
             let new_key_share = do_proactive_refresh(
-                &ocw_data.validators_info,
+                &refresh_committee,
                 &signer,
                 &x25519_secret_key,
                 &app_state.listener_state,
@@ -205,11 +233,39 @@ pub async fn do_proactive_refresh(
     Ok(result)
 }
 
+/// The validators which take part in the proactive refresh of a key.
+///
+/// Keys of registered accounts are only held by the account's signing committee, which is stored
+/// with the account when it registers. Other keys, such as the network parent key, are held by
+/// every validator.
+async fn get_refresh_committee(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    validators_info: &[entropy_shared::ValidatorInfo],
+    registered_info: &Option<RegisteredInfo>,
+) -> Result<Vec<entropy_shared::ValidatorInfo>, ProtocolErr> {
+    let Some(registered_info) = registered_info else {
+        return Ok(validators_info.to_vec());
+    };
+    let committee = get_validators_info(api, rpc, registered_info.committee.clone())
+        .await
+        .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
+    Ok(validators_info
+        .iter()
+        .filter(|validator_info| {
+            committee
+                .iter()
+                .any(|member| member.tss_account.0.as_slice() == validator_info.tss_account)
+        })
+        .cloned()
+        .collect())
+}
+
 ///
 /// Validates proactive refresh call.
 ///
 /// It checks that:
-/// - the data matches what is on-chain
+/// - the data matches what is on-chain as of the block the message is from
 /// - the data is not repeated on-chain
 pub async fn validate_proactive_refresh(
     api: &OnlineClient<EntropyConfig>,
@@ -236,8 +292,14 @@ pub async fn validate_proactive_refresh(
         return Err(ProtocolErr::RepeatedData);
     }
 
+    // we subtract 1 as the message info is coming from the previous block
+    if latest_block_number.saturating_sub(1) != ocw_data.block_number {
+        return Err(ProtocolErr::StaleData);
+    }
+
+    let block_hash = rpc.chain_get_block_hash(Some(ocw_data.block_number.into())).await?;
     let proactive_info_query = entropy::storage().staking_extension().proactive_refresh();
-    let proactive_info = query_chain(api, rpc, proactive_info_query, None)
+    let proactive_info = query_chain(api, rpc, proactive_info_query, block_hash)
         .await?
         .ok_or_else(|| ProtocolErr::ChainFetch("Error getting Proactive Refresh data"))?;
     let mut hasher_chain_data = Blake2s256::new();
@@ -100,6 +100,8 @@ pub enum ProtocolErr {
     InvalidData,
     #[error("Data is repeated")]
     RepeatedData,
+    #[error("Data is stale")]
+    StaleData,
     #[error("Chain Fetch: {0}")]
     ChainFetch(&'static str),
     #[error("The session ID is not for a signing session")]
@@ -108,6 +110,8 @@ pub enum ProtocolErr {
     SubstrateClient(#[from] entropy_client::substrate::SubstrateError),
     #[error("Listener: {0}")]
     Listener(#[from] entropy_protocol::errors::ListenerErr),
+    #[error("BIP-32 error: {0}")]
+    Bip32(synedrion::bip32::Error),
 }
 
 impl IntoResponse for ProtocolErr {
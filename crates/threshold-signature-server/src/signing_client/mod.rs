@@ -16,6 +16,7 @@
 //! Communicate with other threshold servers and carry out the signing and DKG protocols
 pub mod api;
 mod errors;
+pub(crate) mod presignatures;
 pub(crate) mod protocol_execution;
 pub(crate) mod protocol_transport;
 
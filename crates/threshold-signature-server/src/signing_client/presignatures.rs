@@ -0,0 +1,348 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A pool of BIP-340 presignatures for each set of signing parties, so that BIP-340 signing only
+//! takes two rounds.
+//!
+//! ECDSA signing does not use presignatures, as the version of synedrion we use does not expose
+//! presigning on its own.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::Duration,
+};
+
+use entropy_client::user::get_signers_from_chain;
+use entropy_kvdb::kv_manager::{
+    helpers::{deserialize, serialize as key_serialize},
+    KvManager,
+};
+use entropy_protocol::{
+    execute_protocol::{execute_bip340_presigning, Channels},
+    Bip340Presignature, PartyId, SessionId, SignatureScheme, ValidatorInfo,
+};
+use entropy_shared::SETUP_TIMEOUT_SECONDS;
+use futures::future::join_all;
+use parity_scale_codec::Decode;
+use sp_core::{hashing::blake2_256, sr25519, Pair};
+use subxt::utils::AccountId32;
+use tokio::{sync::Mutex, time::timeout};
+
+use crate::{
+    chain_api::{entropy, get_api, get_rpc},
+    helpers::{
+        signing::open_signing_channels, substrate::query_chain,
+        validator::get_signer_and_x25519_secret,
+    },
+    signing_client::ProtocolErr,
+    AppState,
+};
+
+/// The number of presignatures to keep for each set of signing parties
+pub const PRESIGNATURE_POOL_SIZE: usize = 10;
+
+/// Prefix of the key-value store keys under which presignatures are kept
+pub const PRESIGNATURES_KEY_HEADER: &str = "BIP340_PRESIGNATURES";
+
+/// Signing committees top up their presignature pools in a session of their own at every block
+/// number which is a multiple of this
+pub const PRESIGNING_INTERVAL_BLOCKS: u32 = 50;
+
+/// How often to check whether a presigning session is due
+const PRESIGNING_POLL_SECONDS: u64 = 5;
+
+/// The length of the storage key prefix of the registry pallet's `Registered` map, before the
+/// verifying key
+const REGISTERED_KEY_PREFIX_LENGTH: usize = 48;
+
+/// The BIP-340 presignatures held by this server.
+///
+/// The presignatures for a set of signing parties are kept together in one entry of the encrypted
+/// key-value store. A lock is held whenever the pool changes, so that two signing sessions can
+/// never take the same presignature.
+#[derive(Default, Debug, Clone)]
+pub struct Presignatures(Arc<Mutex<()>>);
+
+impl Presignatures {
+    /// The IDs of the presignatures in the pool for the given parties
+    pub async fn ids(
+        &self,
+        kv_store: &KvManager,
+        parties: &BTreeSet<PartyId>,
+    ) -> Result<Vec<[u8; 32]>, ProtocolErr> {
+        let _lock = self.0.lock().await;
+        Ok(get_presignatures(kv_store, parties)
+            .await?
+            .iter()
+            .map(|presignature| presignature.id)
+            .collect())
+    }
+
+    /// Takes the presignature with the given ID out of the pool for the given parties. It is
+    /// removed from the key-value store before being given back, so that it cannot be used again
+    /// even if signing with it fails.
+    ///
+    /// This should only be called once all parties have agreed on the ID, so that no party
+    /// removes a presignature which the others go on to keep.
+    pub async fn take(
+        &self,
+        kv_store: &KvManager,
+        parties: &BTreeSet<PartyId>,
+        id: [u8; 32],
+    ) -> Result<Option<Bip340Presignature>, ProtocolErr> {
+        let _lock = self.0.lock().await;
+        let mut presignatures = get_presignatures(kv_store, parties).await?;
+        let Some(index) = presignatures.iter().position(|presignature| presignature.id == id)
+        else {
+            return Ok(None);
+        };
+        let presignature = presignatures.swap_remove(index);
+        put_presignatures(kv_store, parties, &presignatures).await?;
+        Ok(Some(presignature))
+    }
+
+    /// Updates the pool for the given parties after presigning. Only presignatures which all
+    /// parties still hold are kept, the new ones are added, and the pool is cut down to
+    /// [PRESIGNATURE_POOL_SIZE].
+    pub async fn update(
+        &self,
+        kv_store: &KvManager,
+        parties: &BTreeSet<PartyId>,
+        common_ids: &BTreeSet<[u8; 32]>,
+        new_presignatures: Vec<Bip340Presignature>,
+    ) -> Result<(), ProtocolErr> {
+        let _lock = self.0.lock().await;
+        let mut presignatures = get_presignatures(kv_store, parties).await?;
+        presignatures.retain(|presignature| common_ids.contains(&presignature.id));
+        presignatures.extend(new_presignatures);
+        presignatures.truncate(PRESIGNATURE_POOL_SIZE);
+        put_presignatures(kv_store, parties, &presignatures).await
+    }
+}
+
+/// Tops up the pool of presignatures for the given signing parties, over the connections of a
+/// signing session which has just finished.
+///
+/// Every party does this after a BIP-340 signing session, so that they make presignatures
+/// together. This is meant to be run in its own task, so that the signature can be given back
+/// without waiting for it.
+#[tracing::instrument(skip_all, level = tracing::Level::DEBUG)]
+pub async fn replenish_presignatures(
+    presignatures: Presignatures,
+    kv_store: KvManager,
+    session_id: SessionId,
+    channels: Channels,
+    signer: sr25519::Pair,
+    tss_accounts: Vec<AccountId32>,
+) {
+    let result =
+        presign(&presignatures, &kv_store, &session_id, channels, &signer, tss_accounts).await;
+
+    match result {
+        Ok(count) => tracing::debug!("Made {} BIP-340 presignatures", count),
+        Err(error) => tracing::warn!("Could not make BIP-340 presignatures: {}", error),
+    }
+}
+
+/// Tops up the presignature pools of every signing committee this server is in, at every
+/// [PRESIGNING_INTERVAL_BLOCKS] blocks.
+///
+/// Pools are otherwise only topped up after signing, so this keeps them full for committees which
+/// have not signed for a while, or whose last top up failed. Each committee opens a session of its
+/// own, identified by its members and the block number, so every member knows when to connect
+/// without being asked. This should be run once, when the server starts.
+pub async fn presign_periodically(app_state: AppState) {
+    let mut last_block_number = None;
+    let mut interval = tokio::time::interval(Duration::from_secs(PRESIGNING_POLL_SECONDS));
+    loop {
+        interval.tick().await;
+        let block_number = match presigning_block_number(&app_state).await {
+            Ok(block_number) => block_number,
+            Err(error) => {
+                tracing::warn!("Cannot get block number for presigning: {}", error);
+                continue;
+            },
+        };
+        // Sessions already held by the other committee members when this server started are
+        // not joined
+        if last_block_number.replace(block_number).unwrap_or(block_number) == block_number {
+            continue;
+        }
+        if let Err(error) = presign_for_committees(&app_state, block_number).await {
+            tracing::warn!("Could not make BIP-340 presignatures: {}", error);
+        }
+    }
+}
+
+/// The latest block number at which presigning sessions are held
+async fn presigning_block_number(app_state: &AppState) -> Result<u32, ProtocolErr> {
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let block_number = rpc
+        .chain_get_header(None)
+        .await?
+        .ok_or_else(|| ProtocolErr::OptionUnwrapError("Error Getting Block Number".to_string()))?
+        .number;
+    Ok(block_number - block_number % PRESIGNING_INTERVAL_BLOCKS)
+}
+
+/// Holds a presigning session for each signing committee this server is in, as chosen by the
+/// chain at the given block, all at the same time
+#[tracing::instrument(skip(app_state), level = tracing::Level::DEBUG)]
+async fn presign_for_committees(
+    app_state: &AppState,
+    block_number: u32,
+) -> Result<(), ProtocolErr> {
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let (pair_signer, x25519_secret_key) = get_signer_and_x25519_secret(&app_state.kv_store)
+        .await
+        .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
+    let signer = pair_signer.signer();
+    let account_id = AccountId32(signer.public().0);
+
+    let block_hash = rpc
+        .chain_get_block_hash(Some(block_number.into()))
+        .await?
+        .ok_or_else(|| ProtocolErr::ChainFetch("Error getting block hash"))?;
+    let stash_address_query =
+        entropy::storage().staking_extension().threshold_to_stash(&account_id);
+    let Some(stash_address) =
+        query_chain(&api, &rpc, stash_address_query, Some(block_hash)).await?
+    else {
+        return Ok(());
+    };
+
+    // Only ECDSA keys can sign with BIP-340, and so use presignatures
+    let mut verifying_keys = Vec::new();
+    let mut registered =
+        api.storage().at(block_hash).iter(entropy::storage().registry().registered_iter()).await?;
+    while let Some(Ok(kv)) = registered.next().await {
+        if kv.value.signature_scheme.0 == SignatureScheme::Ecdsa
+            && kv.value.committee.contains(&stash_address)
+        {
+            let verifying_key =
+                Vec::<u8>::decode(&mut &kv.key_bytes[REGISTERED_KEY_PREFIX_LENGTH..])?;
+            verifying_keys.push(verifying_key);
+        }
+    }
+
+    // Accounts with the same signers share a pool, so they only need one session
+    let mut committees: BTreeMap<Vec<AccountId32>, Vec<ValidatorInfo>> = BTreeMap::new();
+    for verifying_key in verifying_keys {
+        let signers = get_signers_from_chain(&api, &rpc, verifying_key, block_number)
+            .await
+            .map_err(|e| ProtocolErr::UserError(e.to_string()))?;
+        let mut tss_accounts: Vec<AccountId32> =
+            signers.iter().map(|signer| signer.tss_account.clone()).collect();
+        if tss_accounts.contains(&account_id) {
+            tss_accounts.sort();
+            committees.insert(tss_accounts, signers);
+        }
+    }
+
+    let x25519_secret_key = &x25519_secret_key;
+    let sessions = committees.into_iter().map(|(tss_accounts, validators_info)| async move {
+        let session_id = SessionId::Presign { parties: tss_accounts.clone(), block_number };
+        let channels = open_signing_channels(
+            &validators_info,
+            &session_id,
+            signer,
+            &app_state.listener_state,
+            x25519_secret_key,
+        )
+        .await?;
+        presign(
+            &app_state.presignatures,
+            &app_state.kv_store,
+            &session_id,
+            channels,
+            signer,
+            tss_accounts,
+        )
+        .await
+    });
+    for result in join_all(sessions).await {
+        match result {
+            Ok(count) => tracing::debug!("Made {} BIP-340 presignatures", count),
+            Err(error) => tracing::warn!("Could not make BIP-340 presignatures: {}", error),
+        }
+    }
+    Ok(())
+}
+
+/// Runs presigning over the given channels and updates the pool with the result, giving the
+/// number of presignatures made
+async fn presign(
+    presignatures: &Presignatures,
+    kv_store: &KvManager,
+    session_id: &SessionId,
+    channels: Channels,
+    signer: &sr25519::Pair,
+    tss_accounts: Vec<AccountId32>,
+) -> Result<usize, ProtocolErr> {
+    let parties: BTreeSet<PartyId> = tss_accounts.iter().cloned().map(PartyId::new).collect();
+    let presignature_ids = presignatures.ids(kv_store, &parties).await?;
+    let (new_presignatures, common_ids, _) = timeout(
+        Duration::from_secs(SETUP_TIMEOUT_SECONDS),
+        execute_bip340_presigning(
+            session_id,
+            channels,
+            signer,
+            tss_accounts,
+            presignature_ids,
+            PRESIGNATURE_POOL_SIZE,
+        ),
+    )
+    .await??;
+    let count = new_presignatures.len();
+    presignatures.update(kv_store, &parties, &common_ids, new_presignatures).await?;
+    Ok(count)
+}
+
+/// The key-value store key for the presignatures of a set of signing parties
+fn presignatures_key(parties: &BTreeSet<PartyId>) -> String {
+    let parties: String = parties.iter().cloned().map(String::from).collect();
+    format!("{PRESIGNATURES_KEY_HEADER}_{}", hex::encode(blake2_256(parties.as_bytes())))
+}
+
+/// Gets the presignatures held for a set of signing parties
+async fn get_presignatures(
+    kv_store: &KvManager,
+    parties: &BTreeSet<PartyId>,
+) -> Result<Vec<Bip340Presignature>, ProtocolErr> {
+    let key = presignatures_key(parties);
+    if !kv_store.kv().exists(&key).await? {
+        return Ok(Vec::new());
+    }
+    deserialize(&kv_store.kv().get(&key).await?)
+        .ok_or_else(|| ProtocolErr::Deserialization("Failed to load presignatures".into()))
+}
+
+/// Replaces the presignatures held for a set of signing parties
+async fn put_presignatures(
+    kv_store: &KvManager,
+    parties: &BTreeSet<PartyId>,
+    presignatures: &[Bip340Presignature],
+) -> Result<(), ProtocolErr> {
+    let key = presignatures_key(parties);
+    let serialized_presignatures = key_serialize(presignatures)
+        .map_err(|_| ProtocolErr::KvSerialize("Kv Serialize Error".to_string()))?;
+    if kv_store.kv().exists(&key).await? {
+        kv_store.kv().delete(&key).await?;
+    }
+    let reservation = kv_store.kv().reserve_key(key).await?;
+    kv_store.kv().put(reservation, serialized_presignatures).await?;
+    Ok(())
+}
@@ -13,7 +13,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use entropy_protocol::{KeyParams, PartyId};
+use entropy_protocol::{Ed25519KeyShare, KeyParams, PartyId};
 use synedrion::{AuxInfo, ThresholdKeyShare};
 
 use crate::sign_init::SignInit;
@@ -24,16 +24,21 @@ pub struct SignContext {
     /// Party context from block proposer
     pub sign_init: SignInit,
     /// Signing key share
-    pub key_share: ThresholdKeyShare<KeyParams, PartyId>,
-    pub aux_info: AuxInfo<KeyParams, PartyId>,
+    pub key_share: SigningKeyShare,
 }
 
 impl SignContext {
-    pub fn new(
-        sign_init: SignInit,
+    pub fn new(sign_init: SignInit, key_share: SigningKeyShare) -> Self {
+        Self { sign_init, key_share }
+    }
+}
+
+/// A signing key share, which depends on the signature scheme of the account
+#[derive(Clone)]
+pub enum SigningKeyShare {
+    Ecdsa {
         key_share: ThresholdKeyShare<KeyParams, PartyId>,
         aux_info: AuxInfo<KeyParams, PartyId>,
-    ) -> Self {
-        Self { sign_init, key_share, aux_info }
-    }
+    },
+    Ed25519(Ed25519KeyShare),
 }
@@ -18,28 +18,32 @@
 mod context;
 
 use entropy_kvdb::kv_manager::KvManager;
-use entropy_protocol::PartyId;
 pub use entropy_protocol::{
-    execute_protocol::{execute_signing_protocol, Channels},
-    KeyParams, ProtocolMessage, RecoverableSignature, SessionId,
+    execute_protocol::{
+        execute_bip340_signing, execute_frost_signing, execute_signing_protocol, ChannelIn,
+        Channels,
+    },
+    KeyParams, ProtocolMessage, RecoverableSignature, SessionId, ThresholdSignature,
 };
+use entropy_protocol::{Ed25519KeyShare, PartyId, SignatureScheme, SigningMode};
 use sp_core::sr25519;
 use subxt::utils::AccountId32;
-use synedrion::{AuxInfo, ThresholdKeyShare};
+use synedrion::{bip32::DerivationPath, AuxInfo, ThresholdKeyShare};
 
-pub use self::context::SignContext;
+pub use self::context::{SignContext, SigningKeyShare};
 use crate::{
     sign_init::SignInit,
-    signing_client::{ListenerState, ProtocolErr},
+    signing_client::{presignatures::Presignatures, ListenerState, ProtocolErr},
 };
 
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, str::FromStr};
 
 /// Thin wrapper around [ListenerState], manages execution of a signing party.
 #[derive(Clone)]
 pub struct ThresholdSigningService<'a> {
     pub state: &'a ListenerState,
     pub kv_manager: &'a KvManager,
+    pub presignatures: &'a Presignatures,
 }
 
 impl std::fmt::Debug for ThresholdSigningService<'_> {
@@ -50,9 +54,13 @@ impl std::fmt::Debug for ThresholdSigningService<'_> {
 }
 
 impl<'a> ThresholdSigningService<'a> {
-    pub fn new(state: &'a ListenerState, kv_manager: &'a KvManager) -> Self {
+    pub fn new(
+        state: &'a ListenerState,
+        kv_manager: &'a KvManager,
+        presignatures: &'a Presignatures,
+    ) -> Self {
         {
-            Self { state, kv_manager }
+            Self { state, kv_manager, presignatures }
         }
     }
 
@@ -70,15 +78,44 @@ impl<'a> ThresholdSigningService<'a> {
             .kv()
             .get(&hex::encode(sign_init.signing_session_info.signature_verifying_key.clone()))
             .await?;
-        let (key_share, aux_info): (
-            ThresholdKeyShare<KeyParams, PartyId>,
-            AuxInfo<KeyParams, PartyId>,
-        ) = entropy_kvdb::kv_manager::helpers::deserialize(&key_share_and_aux_info_vec)
-            .ok_or_else(|| ProtocolErr::Deserialization("Failed to load KeyShare".into()))?;
-        Ok(SignContext::new(sign_init, key_share, aux_info))
+        let key_share = match sign_init.signing_session_info.signature_scheme {
+            SignatureScheme::Ecdsa => {
+                let (mut key_share, aux_info): (
+                    ThresholdKeyShare<KeyParams, PartyId>,
+                    AuxInfo<KeyParams, PartyId>,
+                ) = entropy_kvdb::kv_manager::helpers::deserialize(&key_share_and_aux_info_vec)
+                    .ok_or_else(|| {
+                        ProtocolErr::Deserialization("Failed to load KeyShare".into())
+                    })?;
+                // Sign with a child key by tweaking our share of the parent key
+                if let Some(derivation_path) = &sign_init.signing_session_info.derivation_path {
+                    let derivation_path =
+                        DerivationPath::from_str(derivation_path).map_err(ProtocolErr::Bip32)?;
+                    key_share =
+                        key_share.derive_bip32(&derivation_path).map_err(ProtocolErr::Bip32)?;
+                }
+                SigningKeyShare::Ecdsa { key_share, aux_info }
+            },
+            SignatureScheme::Ed25519 => {
+                let key_share: Ed25519KeyShare =
+                    entropy_kvdb::kv_manager::helpers::deserialize(&key_share_and_aux_info_vec)
+                        .ok_or_else(|| {
+                            ProtocolErr::Deserialization("Failed to load KeyShare".into())
+                        })?;
+                SigningKeyShare::Ed25519(key_share)
+            },
+        };
+        Ok(SignContext::new(sign_init, key_share))
     }
 
     /// handle signing protocol execution.
+    ///
+    /// For ECDSA and BIP-340 the message hash from the session ID is signed, for Ed25519 the given
+    /// message is signed directly. BIP-340 signing uses a presignature from the pool if there is
+    /// one which all parties hold, in which case it only takes two rounds.
+    ///
+    /// The incoming message channel is given back with the signature, so that the connections to
+    /// the rest of the committee can be used to sign another message.
     #[tracing::instrument(
         skip_all,
         level = tracing::Level::DEBUG
@@ -86,24 +123,66 @@ impl<'a> ThresholdSigningService<'a> {
     pub async fn execute_sign(
         &self,
         session_id: SessionId,
-        key_share: &ThresholdKeyShare<KeyParams, PartyId>,
-        aux_info: &AuxInfo<KeyParams, PartyId>,
+        key_share: &SigningKeyShare,
+        message: &[u8],
         channels: Channels,
         threshold_signer: &sr25519::Pair,
         threshold_accounts: Vec<AccountId32>,
-    ) -> Result<RecoverableSignature, ProtocolErr> {
+    ) -> Result<(ThresholdSignature, ChannelIn), ProtocolErr> {
         tracing::trace!("Signing info {session_id:?}");
 
-        let message_hash = if let SessionId::Sign(session_info) = &session_id {
-            session_info.message_hash
+        let (message_hash, signing_mode) = if let SessionId::Sign(session_info) = &session_id {
+            (session_info.message_hash, session_info.signing_mode)
         } else {
             return Err(ProtocolErr::BadSessionId);
         };
 
+        let (key_share, aux_info) = match key_share {
+            SigningKeyShare::Ecdsa { key_share, aux_info } => (key_share, aux_info),
+            SigningKeyShare::Ed25519(key_share) => {
+                let (signature, rx) = execute_frost_signing(
+                    session_id,
+                    channels,
+                    key_share,
+                    message,
+                    threshold_signer,
+                    threshold_accounts,
+                )
+                .await?;
+                return Ok((ThresholdSignature::Ed25519(signature), rx));
+            },
+        };
+
         let parties: BTreeSet<PartyId> =
             threshold_accounts.iter().map(|t| PartyId::new(t.clone())).collect();
 
-        let rsig = execute_signing_protocol(
+        if signing_mode == SigningMode::Bip340 {
+            let presignature_ids = self.presignatures.ids(self.kv_manager, &parties).await?;
+            let presigning_parties = &parties;
+            let take_presignature = |id| async move {
+                self.presignatures
+                    .take(self.kv_manager, presigning_parties, id)
+                    .await
+                    .unwrap_or_else(|error| {
+                        tracing::warn!("Could not take BIP-340 presignature: {}", error);
+                        None
+                    })
+            };
+            let (signature, rx) = execute_bip340_signing(
+                session_id,
+                channels,
+                &key_share.to_key_share(&parties),
+                &message_hash,
+                presignature_ids,
+                take_presignature,
+                threshold_signer,
+                threshold_accounts,
+            )
+            .await?;
+            return Ok((ThresholdSignature::Bip340(signature), rx));
+        }
+
+        let (rsig, rx) = execute_signing_protocol(
             session_id,
             channels,
             &key_share.to_key_share(&parties),
@@ -115,6 +194,6 @@ impl<'a> ThresholdSigningService<'a> {
         .await?;
 
         let (signature, recovery_id) = rsig.to_backend();
-        Ok(RecoverableSignature { signature, recovery_id })
+        Ok((ThresholdSignature::Ecdsa(RecoverableSignature { signature, recovery_id }), rx))
     }
 }
@@ -19,15 +19,15 @@ use crate::{
     helpers::{
         launch::LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH,
         tests::{
-            initialize_test_logger, run_to_block, setup_client, spawn_testing_validators,
-            unsafe_get,
+            initialize_test_logger, run_to_block, setup_client, sign_ocw_message,
+            spawn_testing_validators, unsafe_get,
         },
     },
 };
 use entropy_kvdb::clean_tests;
 use entropy_shared::{
     constants::{DAVE_VERIFYING_KEY, EVE_VERIFYING_KEY},
-    OcwMessageProactiveRefresh,
+    OcwMessageProactiveRefresh, OCW_SIGNATURE_HEADER,
 };
 use entropy_testing_utils::{
     constants::{TSS_ACCOUNTS, X25519_PUBLIC_KEYS},
@@ -125,9 +125,11 @@ pub async fn submit_transaction_requests(
             .iter()
             .map(|ip| async {
                 let url = format!("http://{}/signer/proactive_refresh", ip.clone());
+                let port = ip.rsplit(':').next().unwrap().parse().unwrap();
                 mock_client
                     .post(url)
                     .header("Content-Type", "application/json")
+                    .header(OCW_SIGNATURE_HEADER, sign_ocw_message(port, &ocw_message.encode()))
                     .body(ocw_message.clone().encode())
                     .send()
                     .await
@@ -176,8 +178,24 @@ async fn test_proactive_refresh_validation_fail() {
         kv.kv().reserve_key(LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH.to_string()).await.unwrap();
     kv.kv().put(reservation, (block_number + 5).to_be_bytes().to_vec()).await.unwrap();
 
-    let err_stale_data =
+    let err_repeated_data =
         validate_proactive_refresh(&api, &rpc, &kv, &ocw_message).await.map_err(|e| e.to_string());
-    assert_eq!(err_stale_data, Err("Data is repeated".to_string()));
+    assert_eq!(err_repeated_data, Err("Data is repeated".to_string()));
+
+    kv.kv().delete(LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH).await.unwrap();
+    let reservation =
+        kv.kv().reserve_key(LATEST_BLOCK_NUMBER_PROACTIVE_REFRESH.to_string()).await.unwrap();
+    kv.kv().put(reservation, 0u32.to_be_bytes().to_vec()).await.unwrap();
+
+    // a message which is not from the previous block is rejected
+    let err_stale_data = validate_proactive_refresh(
+        &api,
+        &rpc,
+        &kv,
+        &OcwMessageProactiveRefresh { block_number: block_number + 5, ..ocw_message },
+    )
+    .await
+    .map_err(|e| e.to_string());
+    assert_eq!(err_stale_data, Err("Data is stale".to_string()));
     clean_tests();
 }
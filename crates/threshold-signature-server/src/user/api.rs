@@ -13,12 +13,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use std::{str::FromStr, sync::Arc, time::SystemTime};
+use std::{
+    collections::VecDeque,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use axum::{
     body::{Body, Bytes},
-    extract::State,
-    http::StatusCode,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
@@ -26,16 +32,21 @@ use axum::{
 use base64::prelude::{Engine, BASE64_STANDARD};
 use bip39::{Language, Mnemonic};
 use blake2::{Blake2s256, Digest};
+use entropy_client::program_validation::check_program_config;
 use entropy_kvdb::kv_manager::{
     error::{InnerKvError, KvError},
-    helpers::serialize as key_serialize,
+    helpers::deserialize,
     value::PartyInfo,
     KvManager,
 };
-use entropy_programs_runtime::{Config as ProgramConfig, Runtime, SignatureRequest};
-use entropy_protocol::{KeyParams, PartyId, SigningSessionInfo, ValidatorInfo};
+use entropy_programs_runtime::SignatureRequest;
+use entropy_protocol::{
+    KeyParams, KeyShareWithAuxInfo, PartyId, SigningSessionInfo, ValidatorInfo,
+};
 use entropy_shared::{
-    HashingAlgorithm, OcwMessageDkg, X25519PublicKey, NETWORK_PARENT_KEY, SIGNING_PARTY_SIZE,
+    HashingAlgorithm, OcwMessageDeregister, OcwMessageDkg, OcwMessageProgramChange, RateLimit,
+    SignatureScheme, SigningMode, VersionedAuxilaryData, X25519PublicKey, MESSAGE_HASH_HEADER,
+    NETWORK_PARENT_KEY, SIGNING_PARTY_SIZE,
 };
 use futures::{
     channel::mpsc,
@@ -44,6 +55,7 @@ use futures::{
 };
 use num::{bigint::BigInt, FromPrimitive, Num, ToPrimitive};
 use parity_scale_codec::{Decode, DecodeAll, Encode};
+use rand_core::{OsRng, RngCore};
 use serde::{Deserialize, Serialize};
 use sp_core::{crypto::AccountId32, H256};
 use subxt::{
@@ -53,7 +65,7 @@ use subxt::{
     utils::{AccountId32 as SubxtAccountId32, MultiAddress},
     Config, OnlineClient,
 };
-use synedrion::ThresholdKeyShare;
+use synedrion::{bip32::DerivationPath, ThresholdKeyShare};
 use tracing::instrument;
 use x25519_dalek::StaticSecret;
 use zeroize::Zeroize;
@@ -61,26 +73,48 @@ use zeroize::Zeroize;
 use super::{ParsedUserInputPartyInfo, ProgramError, UserErr, UserInputPartyInfo};
 use crate::{
     chain_api::{
-        entropy::{self, runtime_types::pallet_registry::pallet::RegisteringDetails},
+        entropy::{
+            self,
+            runtime_types::pallet_registry::pallet::{
+                ProgramInstance, RegisteredInfo, RegisteringDetails, RegistrationStatus,
+            },
+        },
         get_api, get_rpc, EntropyConfig,
     },
     helpers::{
         launch::LATEST_BLOCK_NUMBER_NEW_USER,
-        signing::{do_signing, Hasher},
+        program_runtime::ProgramRuntime,
+        signing::{do_batch_signing, do_signing, Hasher},
         substrate::{
-            get_program, get_registered_details, get_stash_address, query_chain, submit_transaction,
+            get_oracle_data, get_program_info, get_rate_limit, get_registered_details,
+            get_stash_address, get_validators_info, query_chain, submit_transaction,
+            verify_ocw_signature,
         },
-        user::{check_in_registration_group, compute_hash, do_dkg},
+        user::{check_in_registration_group, compute_hash, do_dkg, do_key_import, DkgOutput},
         validator::{get_signer, get_signer_and_x25519_secret},
     },
+    metrics,
     signing_client::{ListenerState, ProtocolErr},
     validation::{check_stale, EncryptedSignedMessage},
     validator::api::check_forbidden_key,
     AppState, Configuration,
 };
 
-pub use entropy_client::user::{get_signers_from_chain, UserSignatureRequest};
+pub use entropy_client::user::{
+    get_signers_from_chain, BatchMessage, ProgramEvaluation, UserSignatureBatchRequest,
+    UserSignatureRequest,
+};
 pub const REQUEST_KEY_HEADER: &str = "REQUESTS";
+/// Distinguishes the request limits of program evaluations, which are counted per request author,
+/// from the signature request limits of accounts
+pub const EVALUATE_KEY_HEADER: &str = "EVALUATE";
+pub const SIGNING_JOB_KEY_HEADER: &str = "SIGNING_JOB";
+/// How long the result of a signing job is kept for after the job is started
+pub const SIGNING_JOB_TTL_SECONDS: u64 = 60 * 60;
+/// How long to wait for a response when sending the result of a signing job to a callback URL
+pub const CALLBACK_TIMEOUT_SECONDS: u64 = 10;
+/// How often expired signing jobs are removed from the key-value store
+pub const SIGNING_JOB_SWEEP_INTERVAL_SECONDS: u64 = 60;
 
 /// Used to differentiate different flows which perform distributed key generation.
 enum DkgFlow {
@@ -105,18 +139,97 @@ pub struct UserRegistrationInfo {
 /// Type that gets stored for request limit checks
 #[derive(Debug, PartialEq, Serialize, Deserialize, Encode, Decode)]
 pub struct RequestLimitStorage {
+    /// The first block of the rate limit window the requests were made in
     pub block_number: u32,
     pub request_amount: u32,
 }
 
+/// The state of a signing job started with [sign_tx_async], which gets stored in the key-value
+/// store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningJobStatus {
+    /// The job has been accepted, but its request has not been checked yet
+    Pending,
+    /// The request is being checked against the request limit and evaluated against the
+    /// account's programs
+    Evaluating,
+    /// The signing protocol is running
+    Signing,
+    /// Signing succeeded. Contains the base64 encoded signature and the TSS server's signature of
+    /// it, as in the response from [sign_tx]
+    Done(String, Signature),
+    /// Evaluation or signing failed with the given error
+    Failed(String),
+}
+
+/// Sent to the callback URL of a signing request when the job finishes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigningJob {
+    pub job_id: String,
+    pub status: SigningJobStatus,
+}
+
+/// The status of a signing job as it is kept in the key-value store, along with when it expires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct StoredSigningJob {
+    status: SigningJobStatus,
+    /// Seconds since the unix epoch after which the job is forgotten
+    expires_at: u64,
+}
+
+/// The signing jobs started on this server, in the order they expire, so that a single task can
+/// remove them from the key-value store once they have expired
+#[derive(Default, Debug, Clone)]
+pub struct SigningJobs(Arc<Mutex<VecDeque<(u64, String)>>>);
+
+impl SigningJobs {
+    /// Keeps track of a job which expires at the given time. Jobs are almost always added in the
+    /// order they expire, except for those left in the key-value store by a previous run.
+    pub fn push(&self, expires_at: u64, job_id: String) {
+        match self.0.lock() {
+            Ok(mut jobs) => {
+                let index =
+                    jobs.partition_point(|(other_expires_at, _)| *other_expires_at <= expires_at);
+                jobs.insert(index, (expires_at, job_id))
+            },
+            Err(error) => tracing::error!("Cannot keep track of signing job {}: {}", job_id, error),
+        }
+    }
+
+    /// Stops keeping track of the jobs which have expired by the given time, and returns them
+    pub fn take_expired(&self, now: u64) -> Vec<String> {
+        let mut expired = Vec::new();
+        if let Ok(mut jobs) = self.0.lock() {
+            while jobs.front().is_some_and(|(expires_at, _)| *expires_at <= now) {
+                if let Some((_, job_id)) = jobs.pop_front() {
+                    expired.push(job_id);
+                }
+            }
+        }
+        expired
+    }
+}
+
+/// A callback URL which has been checked with [check_callback_url], along with the addresses its
+/// host resolved to, which are the only addresses the result will be sent to
+#[derive(Debug, Clone)]
+pub struct CallbackUrl {
+    pub url: reqwest::Url,
+    pub addresses: Vec<SocketAddr>,
+}
+
 /// Called by a user to initiate the signing process for a message
 ///
 /// Takes an [EncryptedSignedMessage] containing a JSON serialized [UserSignatureRequest]
+///
+/// The hash of the message which will be signed is given in the [MESSAGE_HASH_HEADER] response
+/// header, so that a signature made with a custom hash can be checked by the user.
 #[tracing::instrument(skip_all, fields(request_author))]
 pub async fn sign_tx(
     State(app_state): State<AppState>,
     Json(encrypted_msg): Json<EncryptedSignedMessage>,
-) -> Result<(StatusCode, Body), UserErr> {
+) -> Result<(StatusCode, [(&'static str, String); 1], Body), UserErr> {
     let (signer, x25519_secret) = get_signer_and_x25519_secret(&app_state.kv_store).await?;
 
     let api = get_api(&app_state.configuration.endpoint).await?;
@@ -127,16 +240,186 @@ pub async fn sign_tx(
     let request_author = SubxtAccountId32(*signed_message.account_id().as_ref());
     tracing::Span::current().record("request_author", signed_message.account_id().to_string());
 
-    let request_limit_query = entropy::storage().parameters().request_limit();
-    let request_limit = query_chain(&api, &rpc, request_limit_query, None)
-        .await?
-        .ok_or_else(|| UserErr::ChainFetch("Failed to get request limit"))?;
+    let user_sig_req: UserSignatureRequest = serde_json::from_slice(&signed_message.message.0)?;
 
-    let mut user_sig_req: UserSignatureRequest = serde_json::from_slice(&signed_message.message.0)?;
+    let rate_limit =
+        get_rate_limit(&api, &rpc, user_sig_req.signature_verifying_key.clone()).await?;
 
-    let string_verifying_key = hex::encode(user_sig_req.signature_verifying_key.clone());
-    request_limit_check(&rpc, &app_state.kv_store, string_verifying_key.clone(), request_limit)
+    let (user_sig_req, signing_session_id) =
+        prepare_signing(&api, &rpc, &app_state.kv_store, request_author, user_sig_req, rate_limit)
+            .await?;
+
+    let message_hash_header = hex::encode(signing_session_id.message_hash);
+
+    let (mut response_tx, response_rx) = mpsc::channel(1);
+
+    // Do the signing protocol in another task, so we can already respond
+    tokio::spawn(async move {
+        let signing_protocol_output =
+            do_signing(&rpc, user_sig_req, &app_state, signing_session_id, rate_limit)
+                .await
+                .and_then(|signature| signature.to_bytes().map_err(ProtocolErr::from))
+                .map(|signature| {
+                    (BASE64_STANDARD.encode(&signature), signer.signer().sign(&signature))
+                })
+                .map_err(|error| error.to_string());
+
+        // This response chunk is sent later with the result of the signing protocol
+        if response_tx.try_send(serde_json::to_string(&signing_protocol_output)).is_err() {
+            tracing::warn!("Cannot send signing protocol output - connection is closed")
+        };
+    });
+
+    // This indicates that the signing protocol is starting successfully
+    Ok((
+        StatusCode::OK,
+        [(MESSAGE_HASH_HEADER, message_hash_header)],
+        Body::from_stream(response_rx),
+    ))
+}
+
+/// Called by a user to initiate the signing process for a message, without waiting for the result
+///
+/// Takes an [EncryptedSignedMessage] containing a JSON serialized [UserSignatureRequest]. Once the
+/// request has been decrypted the response is the ID of a signing job. The request is then checked
+/// and evaluated against the account's programs as with [sign_tx], and signed, in the background.
+/// The state of the job is stored in the key-value store for [SIGNING_JOB_TTL_SECONDS] so that it
+/// can be polled with [sign_status]. If the request has a `callback_url`, the finished job is also
+/// POSTed there as a JSON serialized [SigningJob].
+#[tracing::instrument(skip_all, fields(request_author, job_id))]
+pub async fn sign_tx_async(
+    State(app_state): State<AppState>,
+    Json(encrypted_msg): Json<EncryptedSignedMessage>,
+) -> Result<(StatusCode, String), UserErr> {
+    let (signer, x25519_secret) = get_signer_and_x25519_secret(&app_state.kv_store).await?;
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+
+    let signed_message = encrypted_msg.decrypt(&x25519_secret, &[])?;
+
+    let request_author = SubxtAccountId32(*signed_message.account_id().as_ref());
+    tracing::Span::current().record("request_author", signed_message.account_id().to_string());
+
+    let user_sig_req: UserSignatureRequest = serde_json::from_slice(&signed_message.message.0)?;
+    let callback_url = match &user_sig_req.callback_url {
+        Some(callback_url) => Some(
+            check_callback_url(callback_url, app_state.configuration.allow_private_callback_urls)
+                .await?,
+        ),
+        None => None,
+    };
+
+    let mut job_id_bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut job_id_bytes);
+    let job_id = hex::encode(job_id_bytes);
+    tracing::Span::current().record("job_id", job_id.as_str());
+
+    let expires_at = unix_time() + SIGNING_JOB_TTL_SECONDS;
+    update_signing_job(&app_state.kv_store, &job_id, &SigningJobStatus::Pending, expires_at)
         .await?;
+    // The job is forgotten once it has expired, in case it is never polled again
+    app_state.signing_jobs.push(expires_at, job_id.clone());
+
+    // Evaluate and sign in another task, so we can already respond with the job ID
+    tokio::spawn({
+        let job_id = job_id.clone();
+        async move {
+            let status = evaluate_and_sign(
+                &app_state,
+                &job_id,
+                expires_at,
+                &api,
+                &rpc,
+                request_author,
+                user_sig_req,
+            )
+            .await
+            .map(|signature| {
+                SigningJobStatus::Done(
+                    BASE64_STANDARD.encode(&signature),
+                    signer.signer().sign(&signature),
+                )
+            })
+            .unwrap_or_else(SigningJobStatus::Failed);
+            set_signing_job_status(&app_state.kv_store, &job_id, &status, expires_at).await;
+
+            if let Some(callback_url) = callback_url {
+                let signing_job = SigningJob { job_id: job_id.clone(), status };
+                if let Err(error) = send_to_callback_url(callback_url, &signing_job).await {
+                    tracing::warn!(
+                        "Cannot send result of signing job {} to callback URL: {}",
+                        job_id,
+                        error
+                    );
+                }
+            }
+        }
+    });
+
+    Ok((StatusCode::ACCEPTED, job_id))
+}
+
+/// Checks and evaluates the request of a signing job, and then signs it, storing the status of the
+/// job as it moves from one stage to the next. Returns the signature, or the error the job failed
+/// with.
+async fn evaluate_and_sign(
+    app_state: &AppState,
+    job_id: &str,
+    expires_at: u64,
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    request_author: SubxtAccountId32,
+    user_sig_req: UserSignatureRequest,
+) -> Result<Vec<u8>, String> {
+    let kv_store = &app_state.kv_store;
+    set_signing_job_status(kv_store, job_id, &SigningJobStatus::Evaluating, expires_at).await;
+    let rate_limit = get_rate_limit(api, rpc, user_sig_req.signature_verifying_key.clone())
+        .await
+        .map_err(|error| error.to_string())?;
+    let (user_sig_req, signing_session_id) =
+        prepare_signing(api, rpc, kv_store, request_author, user_sig_req, rate_limit)
+            .await
+            .map_err(|error| error.to_string())?;
+
+    set_signing_job_status(kv_store, job_id, &SigningJobStatus::Signing, expires_at).await;
+    do_signing(rpc, user_sig_req, app_state, signing_session_id, rate_limit)
+        .await
+        .and_then(|signature| signature.to_bytes().map_err(ProtocolErr::from))
+        .map_err(|error| error.to_string())
+}
+
+/// Returns the current [SigningJobStatus] of a signing job started with [sign_tx_async]
+#[tracing::instrument(skip_all)]
+pub async fn sign_status(
+    State(app_state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Json<SigningJobStatus>, UserErr> {
+    let key = signing_job_key(&job_id);
+    if !app_state.kv_store.kv().exists(&key).await? {
+        return Err(UserErr::UnknownSigningJob);
+    }
+    let stored_job: StoredSigningJob =
+        serde_json::from_slice(&app_state.kv_store.kv().get(&key).await?)?;
+    if stored_job.expires_at <= unix_time() {
+        remove_signing_job(&app_state.kv_store, &job_id).await?;
+        return Err(UserErr::UnknownSigningJob);
+    }
+    Ok(Json(stored_job.status))
+}
+
+/// Checks a signature request against the request limit, the account's registration and programs,
+/// and returns the request along with the info needed to start a signing session for it.
+async fn prepare_signing(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    kv_store: &KvManager,
+    request_author: SubxtAccountId32,
+    mut user_sig_req: UserSignatureRequest,
+    rate_limit: RateLimit,
+) -> Result<(UserSignatureRequest, SigningSessionInfo), UserErr> {
+    let string_verifying_key = hex::encode(user_sig_req.signature_verifying_key.clone());
+    request_limit_check(rpc, kv_store, string_verifying_key.clone(), rate_limit, 1).await?;
 
     let block_number = rpc
         .chain_get_header(None)
@@ -150,123 +433,876 @@ pub async fn sign_tx(
         return Err(UserErr::NoSigningFromParentKey);
     }
 
+    let user_details =
+        get_registered_details(api, rpc, user_sig_req.signature_verifying_key.clone()).await?;
+    check_signing_mode(&user_details.signature_scheme.0, &user_sig_req.signing_mode)?;
+    check_derivation_path(&user_details.signature_scheme.0, &user_sig_req.derivation_path)?;
+
+    // gets fuel from chain
+    let max_instructions_per_programs_query =
+        entropy::storage().parameters().max_instructions_per_programs();
+    let fuel = query_chain(api, rpc, max_instructions_per_programs_query, None)
+        .await?
+        .ok_or_else(|| UserErr::ChainFetch("Max instructions per program error"))?;
+
+    let message_hash =
+        evaluate_programs_and_hash(api, rpc, &user_sig_req, &user_details, fuel).await?;
+
+    // The signers are selected by the chain at the block the request was made, so that the user
+    // and every signer agree on them
+    let signers = get_signers_from_chain(
+        api,
+        rpc,
+        user_sig_req.signature_verifying_key.clone(),
+        user_sig_req.block_number,
+    )
+    .await?;
+    // Use the validator info from chain as we can be sure it is in the correct order and the
+    // details are correct
+    user_sig_req.validators_info = signers;
+
+    let signing_session_id = SigningSessionInfo {
+        signature_verifying_key: user_sig_req.signature_verifying_key.clone(),
+        message_hash,
+        request_author,
+        signature_scheme: user_details.signature_scheme.0,
+        signing_mode: user_sig_req.signing_mode,
+        derivation_path: user_sig_req.derivation_path.clone(),
+    };
+
+    let _has_key = check_for_key(&string_verifying_key, kv_store).await?;
+
+    Ok((user_sig_req, signing_session_id))
+}
+
+/// Called by a user to initiate the signing process for several messages with the same verifying
+/// key
+///
+/// Takes an [EncryptedSignedMessage] containing a JSON serialized [UserSignatureBatchRequest].
+///
+/// Every message is evaluated against the account's programs before any signing starts, so if one
+/// message fails evaluation the whole batch is rejected. The signing committee then connects once
+/// for the whole batch, and signs the messages one after another over those connections. Each
+/// result is sent as its own chunk on the response stream in the order the messages were given.
+#[tracing::instrument(skip_all, fields(request_author, batch_size))]
+pub async fn sign_batch(
+    State(app_state): State<AppState>,
+    Json(encrypted_msg): Json<EncryptedSignedMessage>,
+) -> Result<(StatusCode, Body), UserErr> {
+    let (signer, x25519_secret) = get_signer_and_x25519_secret(&app_state.kv_store).await?;
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+
+    let signed_message = encrypted_msg.decrypt(&x25519_secret, &[])?;
+
+    let request_author = SubxtAccountId32(*signed_message.account_id().as_ref());
+    tracing::Span::current().record("request_author", signed_message.account_id().to_string());
+
+    let batch_request: UserSignatureBatchRequest =
+        serde_json::from_slice(&signed_message.message.0)?;
+    if batch_request.messages.is_empty() {
+        return Err(UserErr::EmptyBatch);
+    }
+    let batch_size: u32 =
+        batch_request.messages.len().try_into().map_err(|_| UserErr::Conversion("Batch size"))?;
+    tracing::Span::current().record("batch_size", batch_size);
+
+    // Each message in the batch counts towards the request limit
+    let rate_limit =
+        get_rate_limit(&api, &rpc, batch_request.signature_verifying_key.clone()).await?;
+    let string_verifying_key = hex::encode(batch_request.signature_verifying_key.clone());
+    request_limit_check(
+        &rpc,
+        &app_state.kv_store,
+        string_verifying_key.clone(),
+        rate_limit,
+        batch_size,
+    )
+    .await?;
+
+    let block_number = rpc
+        .chain_get_header(None)
+        .await?
+        .ok_or_else(|| UserErr::OptionUnwrapError("Error Getting Block Number".to_string()))?
+        .number;
+
+    check_stale(batch_request.block_number, block_number).await?;
+    if string_verifying_key == hex::encode(NETWORK_PARENT_KEY) {
+        return Err(UserErr::NoSigningFromParentKey);
+    }
+
+    let user_details =
+        get_registered_details(&api, &rpc, batch_request.signature_verifying_key.clone()).await?;
+    check_signing_mode(&user_details.signature_scheme.0, &batch_request.signing_mode)?;
+    check_derivation_path(&user_details.signature_scheme.0, &batch_request.derivation_path)?;
+
+    let max_instructions_per_programs_query =
+        entropy::storage().parameters().max_instructions_per_programs();
+    let fuel = query_chain(&api, &rpc, max_instructions_per_programs_query, None)
+        .await?
+        .ok_or_else(|| UserErr::ChainFetch("Max instructions per program error"))?;
+
+    let signers = get_signers_from_chain(
+        &api,
+        &rpc,
+        batch_request.signature_verifying_key.clone(),
+        batch_request.block_number,
+    )
+    .await?;
+
+    let mut signing_requests = Vec::with_capacity(batch_request.messages.len());
+    for mut user_sig_req in batch_request.into_requests() {
+        let message_hash =
+            evaluate_programs_and_hash(&api, &rpc, &user_sig_req, &user_details, fuel).await?;
+        user_sig_req.validators_info = signers.clone();
+
+        let signing_session_id = SigningSessionInfo {
+            signature_verifying_key: user_sig_req.signature_verifying_key.clone(),
+            message_hash,
+            request_author: request_author.clone(),
+            signature_scheme: user_details.signature_scheme.0,
+            signing_mode: user_sig_req.signing_mode,
+            derivation_path: user_sig_req.derivation_path.clone(),
+        };
+        signing_requests.push((user_sig_req, signing_session_id));
+    }
+
+    let _has_key = check_for_key(&string_verifying_key, &app_state.kv_store).await?;
+
+    let (mut response_tx, response_rx) = mpsc::channel(signing_requests.len());
+
+    // Do the signing protocols in another task, so we can already respond
+    tokio::spawn(async move {
+        let batch_size = signing_requests.len();
+        let mut send_output = |signing_protocol_output: Result<(String, _), String>| {
+            if response_tx.try_send(serde_json::to_string(&signing_protocol_output)).is_err() {
+                tracing::warn!("Cannot send signing protocol output - connection is closed");
+                return false;
+            };
+            true
+        };
+        let batch_result =
+            do_batch_signing(&rpc, signing_requests, &app_state, rate_limit, |result| {
+                send_output(
+                    result
+                        .and_then(|signature| signature.to_bytes().map_err(ProtocolErr::from))
+                        .map(|signature| {
+                            (BASE64_STANDARD.encode(&signature), signer.signer().sign(&signature))
+                        })
+                        .map_err(|error| error.to_string()),
+                )
+            })
+            .await;
+        // If the committee could not connect, every message gets the error
+        if let Err(error) = batch_result {
+            for _ in 0..batch_size {
+                if !send_output(Err(error.to_string())) {
+                    break;
+                }
+            }
+        }
+    });
+
+    Ok((StatusCode::OK, Body::from_stream(response_rx)))
+}
+
+/// Called by a user to check whether a message would pass the programs of an account, without
+/// signing it
+///
+/// Takes an [EncryptedSignedMessage] containing a JSON serialized [UserSignatureRequest]. The
+/// message is evaluated against each of the account's programs with their on-chain configuration
+/// and fuel limit, as when signing, but every program is evaluated even if an earlier one fails.
+/// The request does not count towards the account's request limit. Instead, each request author
+/// may make as many evaluation requests per block as the network wide request limit.
+///
+/// Responds with a JSON serialized [ProgramEvaluation] for each program, in order.
+#[tracing::instrument(skip_all, fields(request_author))]
+pub async fn evaluate(
+    State(app_state): State<AppState>,
+    Json(encrypted_msg): Json<EncryptedSignedMessage>,
+) -> Result<Json<Vec<ProgramEvaluation>>, UserErr> {
+    let (_signer, x25519_secret) = get_signer_and_x25519_secret(&app_state.kv_store).await?;
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+
+    let signed_message = encrypted_msg.decrypt(&x25519_secret, &[])?;
+    tracing::Span::current().record("request_author", signed_message.account_id().to_string());
+
+    let user_sig_req: UserSignatureRequest = serde_json::from_slice(&signed_message.message.0)?;
+
+    let block_number = rpc
+        .chain_get_header(None)
+        .await?
+        .ok_or_else(|| UserErr::OptionUnwrapError("Error Getting Block Number".to_string()))?
+        .number;
+    check_stale(user_sig_req.block_number, block_number).await?;
+
+    let request_limit_query = entropy::storage().parameters().request_limit();
+    let max_requests = query_chain(&api, &rpc, request_limit_query, None)
+        .await?
+        .ok_or_else(|| UserErr::ChainFetch("Failed to get request limit"))?;
+    let rate_limit = RateLimit { max_requests, window_blocks: 1 };
+    let limit_key = evaluate_limit_key(&signed_message.account_id().to_string());
+    request_limit_check(&rpc, &app_state.kv_store, limit_key.clone(), rate_limit, 1).await?;
+    increment_or_wipe_request_limit(&rpc, &app_state.kv_store, limit_key, rate_limit).await?;
+
     let user_details =
         get_registered_details(&api, &rpc, user_sig_req.signature_verifying_key.clone()).await?;
     check_hash_pointer_out_of_bounds(&user_sig_req.hash, user_details.programs_data.0.len())?;
 
+    let max_instructions_per_programs_query =
+        entropy::storage().parameters().max_instructions_per_programs();
+    let max_fuel = query_chain(&api, &rpc, max_instructions_per_programs_query, None)
+        .await?
+        .ok_or_else(|| UserErr::ChainFetch("Max instructions per program error"))?;
+
+    let message = hex::decode(&user_sig_req.message)?;
+    let auxilary_data_vec = get_auxilary_data(&user_sig_req, &user_details)?;
+
+    let mut runtime = ProgramRuntime::default();
+    let mut evaluations = Vec::with_capacity(user_details.programs_data.0.len());
+    for (program_info, auxilary_data) in user_details.programs_data.0.iter().zip(auxilary_data_vec)
+    {
+        let (result, fuel_used) = evaluate_program(
+            &api,
+            &rpc,
+            &mut runtime,
+            &user_sig_req,
+            program_info,
+            message.clone(),
+            auxilary_data,
+            max_fuel,
+        )
+        .await;
+
+        evaluations.push(ProgramEvaluation {
+            program_pointer: hex::encode(program_info.program_pointer),
+            passed: result.is_ok(),
+            error: result.err().map(|error| error.to_string()),
+            fuel_used,
+        });
+    }
+
+    Ok(Json(evaluations))
+}
+
+/// Evaluates a signature request against each of the programs registered to the account, and if
+/// they all pass returns the hash of the message which should be signed.
+///
+/// Each program is evaluated with the fuel set for it, limited to `max_fuel`, in a single runtime
+/// which has its fuel reset for each program.
+async fn evaluate_programs_and_hash(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    user_sig_req: &UserSignatureRequest,
+    user_details: &RegisteredInfo,
+    max_fuel: u64,
+) -> Result<[u8; 32], UserErr> {
+    check_hash_pointer_out_of_bounds(&user_sig_req.hash, user_details.programs_data.0.len())?;
+
     let message = hex::decode(&user_sig_req.message)?;
+    let auxilary_data_vec = get_auxilary_data(user_sig_req, user_details)?;
+
+    let mut runtime = ProgramRuntime::default();
+    for (program_info, auxilary_data) in user_details.programs_data.0.iter().zip(auxilary_data_vec)
+    {
+        let (result, _fuel_used) = evaluate_program(
+            api,
+            rpc,
+            &mut runtime,
+            user_sig_req,
+            program_info,
+            message.clone(),
+            auxilary_data,
+            max_fuel,
+        )
+        .await;
+        result?;
+    }
+
+    // A custom hash is limited to the fuel of the program which provides it
+    let hash_fuel = match user_sig_req.hash {
+        HashingAlgorithm::Custom(i) => program_fuel(&user_details.programs_data.0[i], max_fuel),
+        _ => max_fuel,
+    };
+    runtime.set_fuel(hash_fuel);
 
+    // As with evaluation, a custom hash program is resolved as of the block the request was made in
+    let block_hash = rpc.chain_get_block_hash(Some(user_sig_req.block_number.into())).await?;
+    compute_hash(
+        api,
+        rpc,
+        &user_sig_req.hash,
+        &mut runtime,
+        &user_details.programs_data.0,
+        message.as_slice(),
+        block_hash,
+    )
+    .await
+}
+
+/// Gets the fuel a program may use, which is the fuel set for the program if it is lower than the
+/// network's maximum
+fn program_fuel(program_info: &ProgramInstance, max_fuel: u64) -> u64 {
+    program_info.fuel.map_or(max_fuel, |fuel| fuel.min(max_fuel))
+}
+
+/// Gets the auxilary data for each of the account's programs from a signature request
+///
+/// If no auxilary data is given, none is given to any of the programs.
+fn get_auxilary_data(
+    user_sig_req: &UserSignatureRequest,
+    user_details: &RegisteredInfo,
+) -> Result<Vec<Option<String>>, UserErr> {
     if user_details.programs_data.0.is_empty() {
         return Err(UserErr::NoProgramPointerDefined());
     }
     // handle aux data padding, if it is not explicit by client for ease send through None, error if incorrect length
-    let auxilary_data_vec;
-    if let Some(auxilary_data) = user_sig_req.clone().auxilary_data {
-        if auxilary_data.len() < user_details.programs_data.0.len() {
-            return Err(UserErr::MismatchAuxData);
-        } else {
-            auxilary_data_vec = auxilary_data;
+    match user_sig_req.auxilary_data.clone() {
+        Some(auxilary_data) if auxilary_data.len() < user_details.programs_data.0.len() => {
+            Err(UserErr::MismatchAuxData)
+        },
+        Some(auxilary_data) => Ok(auxilary_data),
+        None => Ok(vec![None; user_details.programs_data.0.len()]),
+    }
+}
+
+/// Evaluates a message against one of the account's programs, and returns the outcome along with
+/// the fuel used, which is also recorded in the server's metrics
+///
+/// The runtime's fuel is reset to the program's fuel first, so that one runtime can be used for
+/// each of an account's programs.
+#[allow(clippy::too_many_arguments)]
+async fn evaluate_program(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    runtime: &mut ProgramRuntime,
+    user_sig_req: &UserSignatureRequest,
+    program_info: &ProgramInstance,
+    message: Vec<u8>,
+    auxilary_data: Option<String>,
+    max_fuel: u64,
+) -> (Result<(), UserErr>, u64) {
+    let fuel = program_fuel(program_info, max_fuel);
+    runtime.set_fuel(fuel);
+    let result =
+        run_program(api, rpc, runtime, user_sig_req, program_info, message, auxilary_data).await;
+
+    let fuel_used = runtime.fuel_consumed();
+    let program_pointer = hex::encode(program_info.program_pointer);
+    tracing::debug!(
+        "Program {} used {} of {} fuel, passed: {}",
+        program_pointer,
+        fuel_used,
+        fuel,
+        result.is_ok()
+    );
+    metrics::record_program_evaluation(&program_pointer, fuel_used, result.is_ok());
+
+    (result, fuel_used)
+}
+
+/// Runs a program against a message, with the program's configuration and any oracle data it
+/// uses
+async fn run_program(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    runtime: &mut ProgramRuntime,
+    user_sig_req: &UserSignatureRequest,
+    program_info: &ProgramInstance,
+    message: Vec<u8>,
+    auxilary_data: Option<String>,
+) -> Result<(), UserErr> {
+    // A program family is resolved as of the block the request was made in, so that the version
+    // which is evaluated does not depend on when each threshold server gets to it
+    let block_hash = rpc.chain_get_block_hash(Some(user_sig_req.block_number.into())).await?;
+    let program = get_program_info(api, rpc, &program_info.program_pointer, block_hash).await?;
+    // When prehashed, the message given to the program is the hash itself, so the program
+    // must say that it can handle that
+    if user_sig_req.hash == HashingAlgorithm::Prehashed && !program.accepts_prehashed {
+        return Err(UserErr::PrehashedNotAccepted(program_info.program_pointer.to_string()));
+    }
+    // Likewise a program must say that it can handle being given a derivation path, which comes
+    // with the auxilary data in a versioned format
+    if user_sig_req.derivation_path.is_some() && !program.accepts_derivation_path {
+        return Err(UserErr::DerivationPathNotAccepted(program_info.program_pointer.to_string()));
+    }
+    check_program_config(&program.configuration_schema, &program_info.program_config).map_err(
+        |error| UserErr::InvalidProgramConfig(program_info.program_pointer.to_string(), error),
+    )?;
+    let oracle_data =
+        get_oracle_data(api, rpc, &program.oracle_data_pointer, user_sig_req.block_number).await?;
+    let mut auxilary_data = auxilary_data.map(hex::decode).transpose()?;
+    // Programs must be able to tell which child key is signing, so that a policy for the
+    // verifying key cannot be side-stepped by signing with a child key. Those which can are always
+    // given the versioned format, so what they decode does not depend on the request
+    if program.accepts_derivation_path {
+        auxilary_data = Some(
+            VersionedAuxilaryData::V1 {
+                derivation_path: user_sig_req.derivation_path.clone(),
+                auxilary_data,
+            }
+            .encode(),
+        );
+    }
+    let signature_request = SignatureRequest { message, auxilary_data };
+    runtime.evaluate(
+        &program.bytecode,
+        &signature_request,
+        Some(&program_info.program_config),
+        oracle_data.as_deref(),
+    )?;
+    Ok(())
+}
+
+/// HTTP POST endpoint called by the off-chain worker (Propagation pallet) during the network
+/// jumpstart.
+///
+/// The HTTP request takes a Parity SCALE encoded [OcwMessageDkg] which indicates which validators
+/// are in the validator group. The message must be signed with our validator's propagation session
+/// key.
+///
+/// This will trigger the Distributed Key Generation (DKG) process.
+#[tracing::instrument(skip_all, fields(block_number))]
+pub async fn generate_network_key(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    encoded_data: Bytes,
+) -> Result<StatusCode, UserErr> {
+    let data = OcwMessageDkg::decode(&mut encoded_data.as_ref())?;
+    tracing::Span::current().record("block_number", data.block_number);
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let signer = get_signer(&app_state.kv_store).await?;
+    verify_ocw_signature(&api, &rpc, signer.account_id(), &headers, &encoded_data).await?;
+
+    distributed_key_generation(app_state, data, DkgFlow::Jumpstart).await
+}
+
+/// HTTP POST endpoint called by the off-chain worker (Propagation pallet) during user registration.
+///
+/// The HTTP request takes a Parity SCALE encoded [OcwMessageDkg] which indicates which validators
+/// are in the validator group. The message must be signed with our validator's propagation session
+/// key.
+///
+/// This will trigger the Distributed Key Generation (DKG) process.
+#[tracing::instrument(skip_all, fields(block_number))]
+pub async fn new_user(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    encoded_data: Bytes,
+) -> Result<StatusCode, UserErr> {
+    let data = OcwMessageDkg::decode(&mut encoded_data.as_ref())?;
+    tracing::Span::current().record("block_number", data.block_number);
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let signer = get_signer(&app_state.kv_store).await?;
+    verify_ocw_signature(&api, &rpc, signer.account_id(), &headers, &encoded_data).await?;
+
+    distributed_key_generation(app_state, data, DkgFlow::Registration).await
+}
+
+/// Called by a user who has registered with `register_with_imported_key`, to give this server its
+/// share of the imported key.
+///
+/// Takes an [EncryptedSignedMessage] containing a JSON serialized [ThresholdKeyShare], which is
+/// this server's share of an n-of-n sharing of the key amongst the account's signing committee.
+/// The shares are then reshared with the threshold the user chose when registering, and the
+/// registration is confirmed on chain with the verifying key the user gave when registering.
+#[tracing::instrument(skip_all, fields(request_author))]
+pub async fn import_key(
+    State(app_state): State<AppState>,
+    Json(encrypted_msg): Json<EncryptedSignedMessage>,
+) -> Result<StatusCode, UserErr> {
+    let (signer, x25519_secret_key) = get_signer_and_x25519_secret(&app_state.kv_store).await?;
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+
+    let signed_message = encrypted_msg.decrypt(&x25519_secret_key, &[])?;
+
+    let request_author = SubxtAccountId32(*signed_message.account_id().as_ref());
+    tracing::Span::current().record("request_author", signed_message.account_id().to_string());
+
+    let imported_key_share: ThresholdKeyShare<KeyParams, PartyId> =
+        serde_json::from_slice(&signed_message.message.0)?;
+
+    let registering_details = get_registering_user_details(&api, &request_author, &rpc).await?;
+    if !registering_details.imported_key {
+        return Err(UserErr::NotImportingKey);
+    }
+    if matches!(registering_details.status, RegistrationStatus::Failed(_)) {
+        return Err(UserErr::NotRegistering("Registration has failed"));
+    }
+
+    let imported_verifying_key =
+        imported_key_share.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+    if registering_details.verifying_key.map(|verifying_key| verifying_key.0)
+        != Some(imported_verifying_key)
+    {
+        return Err(UserErr::MismatchedImportedKey);
+    }
+
+    // The key is shared amongst the account's signing committee, as with a distributed key
+    // generation
+    let validators_info = get_validators_info(&api, &rpc, registering_details.committee).await?;
+
+    if !validators_info
+        .iter()
+        .any(|validator_info| &validator_info.tss_account == signer.account_id())
+    {
+        return Err(UserErr::InvalidSigner("Invalid Signer in key import group"));
+    }
+
+    if let Err(err) = check_program_configs(&api, &rpc, &registering_details.programs_data.0).await
+    {
+        if let UserErr::InvalidProgramConfig(..) = err {
+            refuse_registration(&api, &rpc, &signer, request_author).await;
         }
-    } else {
-        auxilary_data_vec = vec![None; user_details.programs_data.0.len()];
+        return Err(err);
     }
-    // gets fuel from chain
-    let max_instructions_per_programs_query =
-        entropy::storage().parameters().max_instructions_per_programs();
-    let fuel = query_chain(&api, &rpc, max_instructions_per_programs_query, None)
+
+    // Do the key import protocol in another task, so we can already respond
+    tokio::spawn(async move {
+        let result = async {
+            let dkg_output = do_key_import(
+                &validators_info,
+                &signer,
+                &x25519_secret_key,
+                &app_state.listener_state,
+                request_author.clone(),
+                registering_details.dkg_deadline,
+                imported_key_share,
+                registering_details.threshold as usize,
+            )
+            .await?;
+            store_key_share_and_confirm(
+                &api,
+                &rpc,
+                &signer,
+                &app_state.kv_store,
+                request_author,
+                dkg_output,
+            )
+            .await
+        };
+        if let Err(err) = result.await {
+            tracing::error!("Key import failed {:?}", err);
+        }
+    });
+
+    Ok(StatusCode::OK)
+}
+
+/// Called by a user's program modification account to export this server's keyshare of one of
+/// their verifying keys, so that the private key can be recovered should the network become
+/// unavailable.
+///
+/// Takes an [EncryptedSignedMessage] containing the verifying key. The response is a JSON
+/// serialized [EncryptedSignedMessage] from this server's TSS account, encrypted to the recovery
+/// key set on chain with `set_recovery_key`, which contains the JSON serialized
+/// [ThresholdKeyShare]. Only ECDSA keyshares can be exported, and only once the recovery key's
+/// delay has passed.
+#[tracing::instrument(skip_all, fields(request_author))]
+pub async fn export_keyshare(
+    State(app_state): State<AppState>,
+    Json(encrypted_msg): Json<EncryptedSignedMessage>,
+) -> Result<Json<EncryptedSignedMessage>, UserErr> {
+    let (signer, x25519_secret_key) = get_signer_and_x25519_secret(&app_state.kv_store).await?;
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+
+    let signed_message = encrypted_msg.decrypt(&x25519_secret_key, &[])?;
+
+    let request_author = SubxtAccountId32(*signed_message.account_id().as_ref());
+    tracing::Span::current().record("request_author", signed_message.account_id().to_string());
+
+    let verifying_key = signed_message.message.0;
+    let registered_info = get_registered_details(&api, &rpc, verifying_key.clone()).await?;
+    if registered_info.program_modification_account != request_author {
+        return Err(UserErr::ExportNotAuthorized);
+    }
+    if registered_info.signature_scheme.0 != SignatureScheme::Ecdsa {
+        return Err(UserErr::ExportNotSupported);
+    }
+
+    let recovery_key_query = entropy::storage().registry().recovery_keys(
+        entropy::runtime_types::bounded_collections::bounded_vec::BoundedVec(verifying_key.clone()),
+    );
+    let recovery_key_info =
+        query_chain(&api, &rpc, recovery_key_query, None).await?.ok_or(UserErr::NoRecoveryKey)?;
+
+    // A new recovery key can only be used after a delay, giving the user the chance to remove one
+    // they did not set themselves
+    let block_number = rpc
+        .chain_get_header(None)
         .await?
-        .ok_or_else(|| UserErr::ChainFetch("Max instructions per program error"))?;
+        .ok_or_else(|| UserErr::OptionUnwrapError("Failed to get block number".to_string()))?
+        .number;
+    if block_number < recovery_key_info.active_from {
+        return Err(UserErr::RecoveryKeyNotActive(recovery_key_info.active_from));
+    }
+
+    let string_verifying_key = hex::encode(&verifying_key);
+    if !app_state.kv_store.kv().exists(&string_verifying_key).await? {
+        return Err(UserErr::UserDoesNotExist);
+    }
+    let key_share = app_state.kv_store.kv().get(&string_verifying_key).await?;
+    let (threshold_key_share, _aux_info): KeyShareWithAuxInfo = deserialize(&key_share)
+        .ok_or_else(|| UserErr::KvSerialize("Failed to load KeyShare".to_string()))?;
+
+    let exported_key_share = EncryptedSignedMessage::new(
+        signer.signer(),
+        serde_json::to_vec(&threshold_key_share)?,
+        &recovery_key_info.recovery_key,
+        &[],
+    )?;
+    Ok(Json(exported_key_share))
+}
+
+/// HTTP POST endpoint called by the off-chain worker (propagation pallet) when accounts have been
+/// deregistered.
+///
+/// The HTTP request takes a Parity SCALE encoded [OcwMessageDeregister], which must be signed with
+/// our validator's propagation session key. The keyshares of the deregistered verifying keys are
+/// deleted from the key-value store, and if this server is in the account's signing committee the
+/// deletion is then confirmed on chain with `confirm_deregistration`.
+#[tracing::instrument(skip_all, fields(block_number))]
+pub async fn deregister(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    encoded_data: Bytes,
+) -> Result<StatusCode, UserErr> {
+    let data = OcwMessageDeregister::decode(&mut encoded_data.as_ref())?;
+    tracing::Span::current().record("block_number", data.block_number);
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let signer = get_signer(&app_state.kv_store).await?;
+    verify_ocw_signature(&api, &rpc, signer.account_id(), &headers, &encoded_data).await?;
+
+    if data.verifying_keys.is_empty() {
+        return Ok(StatusCode::NO_CONTENT);
+    }
 
-    let mut runtime = Runtime::new(ProgramConfig { fuel });
+    validate_deregistration(&data, &api, &rpc).await?;
 
-    for (i, program_info) in user_details.programs_data.0.iter().enumerate() {
-        let program = get_program(&api, &rpc, &program_info.program_pointer).await?;
-        let auxilary_data = auxilary_data_vec[i].as_ref().map(hex::decode).transpose()?;
-        let signature_request = SignatureRequest { message: message.clone(), auxilary_data };
-        runtime.evaluate(&program, &signature_request, Some(&program_info.program_config), None)?;
+    for verifying_key in &data.verifying_keys {
+        let string_verifying_key = hex::encode(verifying_key);
+        for key in [string_verifying_key.clone(), request_limit_key(string_verifying_key)] {
+            if app_state.kv_store.kv().exists(&key).await? {
+                app_state.kv_store.kv().delete(&key).await?;
+            }
+        }
     }
 
-    let signers = get_signers_from_chain(&api, &rpc).await?;
-    // Use the validator info from chain as we can be sure it is in the correct order and the
-    // details are correct
-    user_sig_req.validators_info = signers;
-
-    let message_hash = compute_hash(
-        &api,
-        &rpc,
-        &user_sig_req.hash,
-        &mut runtime,
-        &user_details.programs_data.0,
-        message.as_slice(),
-    )
-    .await?;
+    // Confirm the deletions in another task, so we can already respond
+    tokio::spawn(async move {
+        let stash_address = match get_stash_address(&api, &rpc, signer.account_id()).await {
+            Ok(stash_address) => stash_address,
+            Err(err) => {
+                tracing::error!("Failed to get stash address {:?}", err);
+                return;
+            },
+        };
+        for verifying_key in data.verifying_keys {
+            let verifying_key =
+                entropy::runtime_types::bounded_collections::bounded_vec::BoundedVec(verifying_key);
+
+            // Only the account's signing committee held a keyshare, and each member only needs to
+            // confirm once, even if the deletion is requested again
+            let deregistering_query = entropy::storage().registry().deregistering(&verifying_key);
+            match query_chain(&api, &rpc, deregistering_query, None).await {
+                Ok(Some(deregistering_details))
+                    if deregistering_details.committee.contains(&stash_address)
+                        && !deregistering_details.confirmations.contains(signer.account_id()) => {},
+                Ok(_) => continue,
+                Err(err) => {
+                    tracing::error!("Failed to get deregistration details {:?}", err);
+                    continue;
+                },
+            }
 
-    let signing_session_id = SigningSessionInfo {
-        signature_verifying_key: user_sig_req.signature_verifying_key.clone(),
-        message_hash,
-        request_author,
-    };
+            let confirm_deregistration_request =
+                entropy::tx().registry().confirm_deregistration(verifying_key);
+            if let Err(err) =
+                submit_transaction(&api, &rpc, &signer, &confirm_deregistration_request, None).await
+            {
+                tracing::error!("Failed to confirm keyshare deletion {:?}", err);
+            }
+        }
+    });
 
-    let _has_key = check_for_key(&string_verifying_key, &app_state.kv_store).await?;
+    Ok(StatusCode::OK)
+}
 
-    let (mut response_tx, response_rx) = mpsc::channel(1);
+/// Checks that a deregistration message from the off-chain worker is for the previous block, and
+/// matches the verifying keys which the chain has recorded as deregistered in that block
+async fn validate_deregistration(
+    chain_data: &OcwMessageDeregister,
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+) -> Result<(), UserErr> {
+    let latest_block_number = rpc
+        .chain_get_header(None)
+        .await?
+        .ok_or_else(|| UserErr::OptionUnwrapError("Failed to get block number".to_string()))?
+        .number;
 
-    // Do the signing protocol in another task, so we can already respond
-    tokio::spawn(async move {
-        let signing_protocol_output =
-            do_signing(&rpc, user_sig_req, &app_state, signing_session_id, request_limit)
-                .await
-                .map(|signature| {
-                    (
-                        BASE64_STANDARD.encode(signature.to_rsv_bytes()),
-                        signer.signer().sign(&signature.to_rsv_bytes()),
-                    )
-                })
-                .map_err(|error| error.to_string());
+    // we subtract 1 as the message info is coming from the previous block
+    if latest_block_number.saturating_sub(1) != chain_data.block_number {
+        return Err(UserErr::StaleData);
+    }
 
-        // This response chunk is sent later with the result of the signing protocol
-        if response_tx.try_send(serde_json::to_string(&signing_protocol_output)).is_err() {
-            tracing::warn!("Cannot send signing protocol output - connection is closed")
-        };
-    });
+    let deregistrations_query =
+        entropy::storage().registry().deregistrations(chain_data.block_number);
+    let deregistered_keys: Vec<Vec<u8>> = query_chain(api, rpc, deregistrations_query, None)
+        .await?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|verifying_key| verifying_key.0)
+        .collect();
 
-    // This indicates that the signing protocol is starting successfully
-    Ok((StatusCode::OK, Body::from_stream(response_rx)))
+    if deregistered_keys != chain_data.verifying_keys {
+        return Err(UserErr::InvalidData);
+    }
+    Ok(())
 }
 
-/// HTTP POST endpoint called by the off-chain worker (Propagation pallet) during the network
-/// jumpstart.
-///
-/// The HTTP request takes a Parity SCALE encoded [OcwMessageDkg] which indicates which validators
-/// are in the validator group.
+/// HTTP POST endpoint called by the off-chain worker (propagation pallet) when the programs of
+/// registered verifying keys have been changed.
 ///
-/// This will trigger the Distributed Key Generation (DKG) process.
+/// The HTTP request takes a Parity SCALE encoded [OcwMessageProgramChange], which must be signed
+/// with our validator's propagation session key. For each verifying key whose signing committee
+/// this server is in, the new program configurations are checked against the configuration schemas
+/// of their programs, and the change is confirmed or refused on chain.
 #[tracing::instrument(skip_all, fields(block_number))]
-pub async fn generate_network_key(
+pub async fn check_program_change(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     encoded_data: Bytes,
 ) -> Result<StatusCode, UserErr> {
-    let data = OcwMessageDkg::decode(&mut encoded_data.as_ref())?;
+    let data = OcwMessageProgramChange::decode(&mut encoded_data.as_ref())?;
     tracing::Span::current().record("block_number", data.block_number);
 
-    distributed_key_generation(app_state, data, DkgFlow::Jumpstart).await
+    if data.verifying_keys.is_empty() {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let signer = get_signer(&app_state.kv_store).await?;
+    verify_ocw_signature(&api, &rpc, signer.account_id(), &headers, &encoded_data).await?;
+
+    validate_program_change(&data, &api, &rpc).await?;
+
+    // Check the programs in another task, so we can already respond
+    tokio::spawn(async move {
+        let stash_address = match get_stash_address(&api, &rpc, signer.account_id()).await {
+            Ok(stash_address) => stash_address,
+            Err(err) => {
+                tracing::error!("Failed to get stash address {:?}", err);
+                return;
+            },
+        };
+        for verifying_key in data.verifying_keys {
+            if let Err(err) =
+                respond_to_program_change(&api, &rpc, &signer, &stash_address, verifying_key).await
+            {
+                tracing::error!("Failed to check program change {:?}", err);
+            }
+        }
+    });
+
+    Ok(StatusCode::OK)
 }
 
-/// HTTP POST endpoint called by the off-chain worker (Propagation pallet) during user registration.
-///
-/// The HTTP request takes a Parity SCALE encoded [OcwMessageDkg] which indicates which validators
-/// are in the validator group.
+/// Checks that a program change message from the off-chain worker is for the previous block, and
+/// matches the verifying keys whose programs the chain has recorded as changed in that block
+async fn validate_program_change(
+    chain_data: &OcwMessageProgramChange,
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+) -> Result<(), UserErr> {
+    let latest_block_number = rpc
+        .chain_get_header(None)
+        .await?
+        .ok_or_else(|| UserErr::OptionUnwrapError("Failed to get block number".to_string()))?
+        .number;
+
+    // we subtract 1 as the message info is coming from the previous block
+    if latest_block_number.saturating_sub(1) != chain_data.block_number {
+        return Err(UserErr::StaleData);
+    }
+
+    let program_changes_query =
+        entropy::storage().registry().program_changes(chain_data.block_number);
+    let changed_keys: Vec<Vec<u8>> = query_chain(api, rpc, program_changes_query, None)
+        .await?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|verifying_key| verifying_key.0)
+        .collect();
+
+    if changed_keys != chain_data.verifying_keys {
+        return Err(UserErr::InvalidData);
+    }
+    Ok(())
+}
+
+/// Confirms a pending change to the programs of a verifying key if its program configurations
+/// match their schemas, or refuses it if not.
 ///
-/// This will trigger the Distributed Key Generation (DKG) process.
-#[tracing::instrument(skip_all, fields(block_number))]
-pub async fn new_user(
-    State(app_state): State<AppState>,
-    encoded_data: Bytes,
-) -> Result<StatusCode, UserErr> {
-    let data = OcwMessageDkg::decode(&mut encoded_data.as_ref())?;
-    tracing::Span::current().record("block_number", data.block_number);
+/// Only the verifying key's signing committee respond, and each member only once.
+async fn respond_to_program_change(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    signer: &PairSigner<EntropyConfig, sr25519::Pair>,
+    stash_address: &SubxtAccountId32,
+    verifying_key: Vec<u8>,
+) -> Result<(), UserErr> {
+    let verifying_key =
+        entropy::runtime_types::bounded_collections::bounded_vec::BoundedVec(verifying_key);
 
-    distributed_key_generation(app_state, data, DkgFlow::Registration).await
+    let registered_query = entropy::storage().registry().registered(&verifying_key);
+    match query_chain(api, rpc, registered_query, None).await? {
+        Some(registered_info) if registered_info.committee.contains(stash_address) => {},
+        _ => return Ok(()),
+    }
+    let program_change_query =
+        entropy::storage().registry().pending_program_changes(&verifying_key);
+    let Some(program_change) = query_chain(api, rpc, program_change_query, None).await? else {
+        return Ok(());
+    };
+    if program_change.confirmations.contains(signer.account_id())
+        || program_change.refusals.contains(signer.account_id())
+    {
+        return Ok(());
+    }
+
+    match check_program_configs(api, rpc, &program_change.programs_data.0).await {
+        Ok(()) => {
+            let confirm_program_change_request =
+                entropy::tx().registry().confirm_program_change(verifying_key);
+            submit_transaction(api, rpc, signer, &confirm_program_change_request, None).await?;
+        },
+        Err(err @ UserErr::InvalidProgramConfig(..)) => {
+            tracing::warn!(
+                "Refusing the program change of {}: {}",
+                hex::encode(&verifying_key.0),
+                err
+            );
+            let refuse_program_change_request =
+                entropy::tx().registry().refuse_program_change(verifying_key);
+            submit_transaction(api, rpc, signer, &refuse_program_change_request, None).await?;
+        },
+        Err(err) => return Err(err),
+    }
+    Ok(())
 }
 
 /// An internal helper which kicks off the distributed key generation (DKG) process.
@@ -336,45 +1372,145 @@ async fn setup_dkg(
             .map_err(|_| UserErr::AddressConversionError("Invalid Length".to_string()))?;
         let sig_request_address = SubxtAccountId32(*address_slice);
 
-        let (key_share, aux_info) = do_dkg(
-            &data.validators_info,
+        // The network parent key is always ECDSA and is held by every validator with the
+        // network's threshold. Otherwise the user chose these when registering
+        let (signature_scheme, committee, threshold, programs_data) = if sig_request_account
+            == NETWORK_PARENT_KEY.encode()
+        {
+            let signers_info_query = entropy::storage().parameters().signers_info();
+            let signers_info = query_chain(&api, rpc, signers_info_query, None)
+                .await?
+                .ok_or_else(|| UserErr::ChainFetch("Error getting signers info"))?;
+            (SignatureScheme::Ecdsa, data.validators_info.clone(), signers_info.threshold, vec![])
+        } else {
+            let registering_details =
+                get_registering_user_details(&api, &sig_request_address, rpc).await?;
+            let committee = get_validators_info(&api, rpc, registering_details.committee)
+                .await?
+                .into_iter()
+                .map(|validator_info| entropy_shared::ValidatorInfo {
+                    x25519_public_key: validator_info.x25519_public_key,
+                    ip_address: validator_info.ip_address.into_bytes(),
+                    tss_account: validator_info.tss_account.encode(),
+                })
+                .collect();
+            (
+                registering_details.signature_scheme.0,
+                committee,
+                registering_details.threshold,
+                registering_details.programs_data.0,
+            )
+        };
+        if check_in_registration_group(&committee, signer.account_id()).is_err() {
+            tracing::debug!("Not in the signing committee of {:?}", sig_request_address);
+            continue;
+        }
+
+        match check_program_configs(&api, rpc, &programs_data).await {
+            Err(err @ UserErr::InvalidProgramConfig(..)) => {
+                tracing::warn!("Refusing the registration of {:?}: {}", sig_request_address, err);
+                refuse_registration(&api, rpc, &signer, sig_request_address).await;
+                continue;
+            },
+            result => result?,
+        }
+
+        let dkg_output = do_dkg(
+            &committee,
             &signer,
             x25519_secret_key,
             &app_state.listener_state,
             sig_request_address.clone(),
             data.block_number,
+            signature_scheme,
+            threshold as usize,
         )
         .await?;
 
-        let verifying_key = key_share.verifying_key().to_encoded_point(true).as_bytes().to_vec();
-        let string_verifying_key = if sig_request_account == NETWORK_PARENT_KEY.encode() {
-            hex::encode(NETWORK_PARENT_KEY)
-        } else {
-            hex::encode(verifying_key.clone())
-        }
-        .to_string();
+        store_key_share_and_confirm(
+            &api,
+            rpc,
+            &signer,
+            &app_state.kv_store,
+            sig_request_address,
+            dkg_output,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Stores a newly generated or imported keyshare, and confirms the registration on chain.
+async fn store_key_share_and_confirm(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    signer: &PairSigner<EntropyConfig, sr25519::Pair>,
+    kv_store: &KvManager,
+    sig_request_address: SubxtAccountId32,
+    dkg_output: DkgOutput,
+) -> Result<(), UserErr> {
+    let verifying_key = dkg_output.verifying_key()?;
+    let string_verifying_key = if sig_request_address.encode() == NETWORK_PARENT_KEY.encode() {
+        hex::encode(NETWORK_PARENT_KEY)
+    } else {
+        hex::encode(verifying_key.clone())
+    }
+    .to_string();
 
-        let serialized_key_share = key_serialize(&(key_share, aux_info))
-            .map_err(|_| UserErr::KvSerialize("Kv Serialize Error".to_string()))?;
+    let serialized_key_share = dkg_output.serialize()?;
 
-        let reservation = app_state.kv_store.kv().reserve_key(string_verifying_key.clone()).await?;
-        app_state.kv_store.kv().put(reservation, serialized_key_share.clone()).await?;
+    let reservation = kv_store.kv().reserve_key(string_verifying_key.clone()).await?;
+    kv_store.kv().put(reservation, serialized_key_share.clone()).await?;
 
-        let block_hash = rpc
-            .chain_get_block_hash(None)
-            .await?
-            .ok_or_else(|| UserErr::OptionUnwrapError("Error getting block hash".to_string()))?;
+    let block_hash = rpc
+        .chain_get_block_hash(None)
+        .await?
+        .ok_or_else(|| UserErr::OptionUnwrapError("Error getting block hash".to_string()))?;
+
+    let nonce_call = entropy::apis().account_nonce_api().account_nonce(signer.account_id().clone());
+    let nonce = api.runtime_api().at(block_hash).call(nonce_call).await?;
 
-        let nonce_call =
-            entropy::apis().account_nonce_api().account_nonce(signer.account_id().clone());
-        let nonce = api.runtime_api().at(block_hash).call(nonce_call).await?;
+    // TODO: Error handling really complex needs to be thought about.
+    confirm_registered(api, rpc, sig_request_address, signer, verifying_key, nonce).await
+}
 
-        // TODO: Error handling really complex needs to be thought about.
-        confirm_registered(&api, rpc, sig_request_address, &signer, verifying_key, nonce).await?;
+/// Checks that each program configuration of a registering account, or of a change to the programs
+/// of a registered account, matches the configuration schema of its program.
+///
+/// The chain only checks that configurations are well-formed JSON, so this is checked by the
+/// signing committee before a key is generated or imported for the account, and before a change to
+/// its programs is applied.
+async fn check_program_configs(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    programs_data: &[ProgramInstance],
+) -> Result<(), UserErr> {
+    for program_instance in programs_data {
+        let program = get_program_info(api, rpc, &program_instance.program_pointer, None).await?;
+        check_program_config(&program.configuration_schema, &program_instance.program_config)
+            .map_err(|error| {
+                UserErr::InvalidProgramConfig(program_instance.program_pointer.to_string(), error)
+            })?;
     }
     Ok(())
 }
 
+/// Refuses a registration on chain, as its program configurations do not match their schemas.
+///
+/// The registration only fails once `threshold` members of the signing committee have refused
+/// it, after which further refusals are rejected, so failing to submit is only logged.
+async fn refuse_registration(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    signer: &PairSigner<EntropyConfig, sr25519::Pair>,
+    who: SubxtAccountId32,
+) {
+    let refuse_register_request = entropy::tx().registry().refuse_register(who.clone());
+    if let Err(err) = submit_transaction(api, rpc, signer, &refuse_register_request, None).await {
+        tracing::warn!("Could not refuse the registration of {:?}: {:?}", who, err);
+    }
+}
+
 /// Returns details of a given registering user including key key visibility and X25519 public key.
 #[tracing::instrument(
     skip_all,
@@ -393,7 +1529,8 @@ pub async fn get_registering_user_details(
     Ok(register_info)
 }
 
-/// Returns `true` if the given account is in a "registering" state.
+/// Returns `true` if the given account is in a "registering" state, and has not failed
+/// registering.
 pub async fn is_registering(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
@@ -402,7 +1539,7 @@ pub async fn is_registering(
     let registering_info_query = entropy::storage().registry().registering(who);
     let register_info = query_chain(api, rpc, registering_info_query, None).await?;
 
-    Ok(register_info.is_some())
+    Ok(register_info.is_some_and(|info| !matches!(info.status, RegistrationStatus::Failed(_))))
 }
 
 /// Confirms that a address has finished registering on chain.
@@ -435,7 +1572,8 @@ pub async fn confirm_registered(
 }
 
 /// Validates new user endpoint
-/// Checks the chain for validity of data and block number of data matches current block
+/// Checks the chain for validity of data and block number of data matches current block, and that
+/// the validators given are the ones which were validating at that block
 async fn validate_new_user(
     chain_data: &OcwMessageDkg,
     api: &OnlineClient<EntropyConfig>,
@@ -474,13 +1612,39 @@ async fn validate_new_user(
         DkgFlow::Registration => entropy::storage().registry().dkg(chain_data.block_number),
     };
 
-    let verifying_data = query_chain(api, rpc, verifying_data_query, None).await?;
+    let block_hash = rpc.chain_get_block_hash(Some(chain_data.block_number.into())).await?;
+    let verifying_data = query_chain(api, rpc, verifying_data_query, block_hash).await?;
     hasher_verifying_data.update(verifying_data.encode());
 
     let verifying_data_hash = hasher_verifying_data.finalize();
     if verifying_data_hash != chain_data_hash {
         return Err(UserErr::InvalidData);
     }
+
+    // The validators taking part must be the ones the chain had at that block, in any order
+    let validators_query = entropy::storage().session().validators();
+    let validators = query_chain(api, rpc, validators_query, block_hash)
+        .await?
+        .ok_or_else(|| UserErr::ChainFetch("Error getting validators"))?;
+    if validators.len() != chain_data.validators_info.len() {
+        return Err(UserErr::InvalidData);
+    }
+    for validator in validators {
+        let threshold_server_query =
+            entropy::storage().staking_extension().threshold_servers(validator);
+        let server_info = query_chain(api, rpc, threshold_server_query, block_hash)
+            .await?
+            .ok_or_else(|| UserErr::ChainFetch("Error getting threshold server info"))?;
+        let validator_info = entropy_shared::ValidatorInfo {
+            x25519_public_key: server_info.x25519_public_key,
+            ip_address: server_info.endpoint,
+            tss_account: server_info.tss_account.encode(),
+        };
+        if !chain_data.validators_info.contains(&validator_info) {
+            return Err(UserErr::InvalidData);
+        }
+    }
+
     kv_manager.kv().delete(LATEST_BLOCK_NUMBER_NEW_USER).await?;
     let reservation = kv_manager.kv().reserve_key(LATEST_BLOCK_NUMBER_NEW_USER.to_string()).await?;
     kv_manager.kv().put(reservation, chain_data.block_number.to_be_bytes().to_vec()).await?;
@@ -494,11 +1658,15 @@ pub async fn check_for_key(account: &str, kv: &KvManager) -> Result<bool, UserEr
 }
 
 /// Checks the request limit
+///
+/// `requests` is the number of signatures about to be requested, so that a batch of messages can
+/// be checked against the limit as a whole.
 pub async fn request_limit_check(
     rpc: &LegacyRpcMethods<EntropyConfig>,
     kv_store: &KvManager,
     verifying_key: String,
-    request_limit: u32,
+    rate_limit: RateLimit,
+    requests: u32,
 ) -> Result<(), UserErr> {
     let key = request_limit_key(verifying_key);
     let block_number = rpc
@@ -506,26 +1674,31 @@ pub async fn request_limit_check(
         .await?
         .ok_or_else(|| UserErr::OptionUnwrapError("Failed to get block number".to_string()))?
         .number;
+    let window_start = rate_limit.window_start(block_number);
 
+    let mut request_amount = 0;
     if kv_store.kv().exists(&key).await? {
         let serialized_request_amount = kv_store.kv().get(&key).await?;
         let request_info: RequestLimitStorage =
             RequestLimitStorage::decode(&mut serialized_request_amount.as_ref())?;
-        if request_info.block_number == block_number && request_info.request_amount >= request_limit
-        {
-            return Err(UserErr::TooManyRequests);
+        if request_info.block_number == window_start {
+            request_amount = request_info.request_amount;
         }
     }
 
+    if request_amount.saturating_add(requests) > rate_limit.max_requests {
+        return Err(UserErr::TooManyRequests);
+    }
+
     Ok(())
 }
 
-/// Increments or restarts request count if a new block has been created
+/// Increments or restarts request count if a new rate limit window has started
 pub async fn increment_or_wipe_request_limit(
     rpc: &LegacyRpcMethods<EntropyConfig>,
     kv_store: &KvManager,
     verifying_key: String,
-    request_limit: u32,
+    rate_limit: RateLimit,
 ) -> Result<(), UserErr> {
     let key = request_limit_key(verifying_key);
     let block_number = rpc
@@ -533,13 +1706,14 @@ pub async fn increment_or_wipe_request_limit(
         .await?
         .ok_or_else(|| UserErr::OptionUnwrapError("Failed to get block number".to_string()))?
         .number;
+    let block_number = rate_limit.window_start(block_number);
 
     if kv_store.kv().exists(&key).await? {
         let serialized_request_amount = kv_store.kv().get(&key).await?;
         let request_info: RequestLimitStorage =
             RequestLimitStorage::decode(&mut serialized_request_amount.as_ref())?;
 
-        // Previous block wipe request amount to new block
+        // Previous window wipe request amount to new window
         if request_info.block_number != block_number {
             kv_store.kv().delete(&key).await?;
             let reservation = kv_store.kv().reserve_key(key).await?;
@@ -550,8 +1724,8 @@ pub async fn increment_or_wipe_request_limit(
             return Ok(());
         }
 
-        // same block incrememnt request amount
-        if request_info.request_amount <= request_limit {
+        // same window incrememnt request amount
+        if request_info.request_amount <= rate_limit.max_requests {
             kv_store.kv().delete(&key).await?;
             let reservation = kv_store.kv().reserve_key(key).await?;
             kv_store
@@ -582,6 +1756,245 @@ pub fn request_limit_key(signing_address: String) -> String {
     format!("{REQUEST_KEY_HEADER}_{signing_address}")
 }
 
+/// Creates the key passed to the request limit checks for the program evaluations of a request
+/// author
+pub fn evaluate_limit_key(request_author: &str) -> String {
+    format!("{EVALUATE_KEY_HEADER}_{request_author}")
+}
+
+/// Creates the key under which the status of a signing job is stored
+pub fn signing_job_key(job_id: &str) -> String {
+    format!("{SIGNING_JOB_KEY_HEADER}_{job_id}")
+}
+
+/// Stores the status of a signing job, replacing any previous status
+pub async fn update_signing_job(
+    kv_store: &KvManager,
+    job_id: &str,
+    status: &SigningJobStatus,
+    expires_at: u64,
+) -> Result<(), UserErr> {
+    remove_signing_job(kv_store, job_id).await?;
+    let reservation = kv_store.kv().reserve_key(signing_job_key(job_id)).await?;
+    let stored_job = StoredSigningJob { status: status.clone(), expires_at };
+    kv_store.kv().put(reservation, serde_json::to_vec(&stored_job)?).await?;
+    Ok(())
+}
+
+/// Stores the status of a signing job which is running in the background, where there is no one
+/// to return an error to
+async fn set_signing_job_status(
+    kv_store: &KvManager,
+    job_id: &str,
+    status: &SigningJobStatus,
+    expires_at: u64,
+) {
+    if let Err(error) = update_signing_job(kv_store, job_id, status, expires_at).await {
+        tracing::error!("Cannot store status of signing job {}: {}", job_id, error);
+    }
+}
+
+/// Removes signing jobs from the key-value store once they have expired, checking every
+/// [SIGNING_JOB_SWEEP_INTERVAL_SECONDS].
+///
+/// This should be run once, when the server starts. The jobs left in the key-value store by a
+/// previous run are taken over first, see [recover_signing_jobs].
+pub async fn sweep_signing_jobs(signing_jobs: SigningJobs, kv_store: KvManager) {
+    if let Err(error) = recover_signing_jobs(&signing_jobs, &kv_store).await {
+        tracing::error!("Cannot recover signing jobs from a previous run: {}", error);
+    }
+    let mut interval =
+        tokio::time::interval(Duration::from_secs(SIGNING_JOB_SWEEP_INTERVAL_SECONDS));
+    loop {
+        interval.tick().await;
+        for job_id in signing_jobs.take_expired(unix_time()) {
+            if let Err(error) = remove_signing_job(&kv_store, &job_id).await {
+                tracing::warn!("Cannot remove expired signing job {}: {}", job_id, error);
+            }
+        }
+    }
+}
+
+/// Takes over the signing jobs left in the key-value store by a previous run of the server.
+///
+/// Expired jobs are removed. The others are kept until they expire, but any which had not finished
+/// are marked as failed, since their signing sessions ended when the server stopped.
+pub async fn recover_signing_jobs(
+    signing_jobs: &SigningJobs,
+    kv_store: &KvManager,
+) -> Result<(), UserErr> {
+    let now = unix_time();
+    let prefix = signing_job_key("");
+    for key in kv_store.kv().keys_with_prefix(&prefix).await? {
+        let job_id = &key[prefix.len()..];
+        if let Err(error) = recover_signing_job(signing_jobs, kv_store, job_id, now).await {
+            tracing::warn!("Cannot recover signing job {}: {}", job_id, error);
+        }
+    }
+    Ok(())
+}
+
+/// Takes over a single signing job left by a previous run, see [recover_signing_jobs]
+async fn recover_signing_job(
+    signing_jobs: &SigningJobs,
+    kv_store: &KvManager,
+    job_id: &str,
+    now: u64,
+) -> Result<(), UserErr> {
+    let stored_job: StoredSigningJob =
+        serde_json::from_slice(&kv_store.kv().get(&signing_job_key(job_id)).await?)?;
+    if stored_job.expires_at <= now {
+        return remove_signing_job(kv_store, job_id).await;
+    }
+    if !matches!(stored_job.status, SigningJobStatus::Done(..) | SigningJobStatus::Failed(_)) {
+        let status = SigningJobStatus::Failed(
+            "The threshold server restarted before the signing job finished".to_string(),
+        );
+        update_signing_job(kv_store, job_id, &status, stored_job.expires_at).await?;
+    }
+    signing_jobs.push(stored_job.expires_at, job_id.to_string());
+    Ok(())
+}
+
+/// Removes the stored status of a signing job, if there is one
+async fn remove_signing_job(kv_store: &KvManager, job_id: &str) -> Result<(), UserErr> {
+    let key = signing_job_key(job_id);
+    if kv_store.kv().exists(&key).await? {
+        kv_store.kv().delete(&key).await?;
+    }
+    Ok(())
+}
+
+/// The current time in seconds since the unix epoch
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+/// Checks that a signing request's callback URL is a valid HTTP or HTTPS URL, and resolves its
+/// host.
+///
+/// Unless `allow_private` is set, every address the host resolves to must be publicly routable, so
+/// that callbacks cannot be used to reach services on the threshold server's own network.
+pub async fn check_callback_url(
+    callback_url: &str,
+    allow_private: bool,
+) -> Result<CallbackUrl, UserErr> {
+    let url = reqwest::Url::parse(callback_url).map_err(|_| UserErr::BadCallbackUrl)?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(UserErr::BadCallbackUrl);
+    }
+    let port = url.port_or_known_default().ok_or(UserErr::BadCallbackUrl)?;
+    let host = url.host_str().ok_or(UserErr::BadCallbackUrl)?;
+    let addresses: Vec<SocketAddr> = match host_ip_address(host) {
+        Some(ip) => vec![SocketAddr::new(ip, port)],
+        None => tokio::net::lookup_host((host, port))
+            .await
+            .map_err(|_| UserErr::BadCallbackUrl)?
+            .collect(),
+    };
+    if addresses.is_empty() {
+        return Err(UserErr::BadCallbackUrl);
+    }
+    if !allow_private && !addresses.iter().all(|address| is_public_address(address.ip())) {
+        return Err(UserErr::CallbackUrlNotPublic);
+    }
+    Ok(CallbackUrl { url, addresses })
+}
+
+/// The IP address of a URL's host, if the host is given as an address rather than a domain name
+fn host_ip_address(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+/// Whether an IP address is publicly routable, rather than private, loopback, link-local or
+/// otherwise reserved
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Shared address space used for carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && b & 0xc0 == 64))
+        },
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_address(ip.into()),
+            None => {
+                let first_segment = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local addresses, fc00::/7
+                    || first_segment & 0xfe00 == 0xfc00
+                    // Link-local addresses, fe80::/10
+                    || first_segment & 0xffc0 == 0xfe80)
+            },
+        },
+    }
+}
+
+/// Sends the result of a signing job to its callback URL.
+///
+/// The request only goes to the addresses found by [check_callback_url], so the host cannot be
+/// made to resolve to a different address later, and redirects are not followed.
+async fn send_to_callback_url(
+    callback_url: CallbackUrl,
+    signing_job: &SigningJob,
+) -> Result<(), reqwest::Error> {
+    let mut client_builder = reqwest::Client::builder()
+        .timeout(Duration::from_secs(CALLBACK_TIMEOUT_SECONDS))
+        .redirect(reqwest::redirect::Policy::none());
+    if let Some(host) = callback_url.url.host_str() {
+        if host_ip_address(host).is_none() {
+            client_builder = client_builder.resolve_to_addrs(host, &callback_url.addresses);
+        }
+    }
+    client_builder
+        .build()?
+        .post(callback_url.url)
+        .json(signing_job)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Checks that the requested kind of signature can be made with the account's key
+pub fn check_signing_mode(
+    signature_scheme: &SignatureScheme,
+    signing_mode: &SigningMode,
+) -> Result<(), UserErr> {
+    match (signature_scheme, signing_mode) {
+        (SignatureScheme::Ecdsa, _) | (_, SigningMode::Ecdsa) => Ok(()),
+        _ => Err(UserErr::SigningModeNotSupported),
+    }
+}
+
+/// Checks that a requested BIP-32 derivation path is valid and can be used with the account's key.
+///
+/// Only non-hardened derivation is possible, since hardened derivation needs the private key.
+pub fn check_derivation_path(
+    signature_scheme: &SignatureScheme,
+    derivation_path: &Option<String>,
+) -> Result<(), UserErr> {
+    let Some(derivation_path) = derivation_path else {
+        return Ok(());
+    };
+    if *signature_scheme != SignatureScheme::Ecdsa {
+        return Err(UserErr::DerivationNotSupported);
+    }
+    let derivation_path = DerivationPath::from_str(derivation_path).map_err(UserErr::Bip32)?;
+    if derivation_path.iter().any(|child_number| child_number.is_hardened()) {
+        return Err(UserErr::HardenedDerivation);
+    }
+    Ok(())
+}
+
 pub fn check_hash_pointer_out_of_bounds(
     hashing_algorithm: &HashingAlgorithm,
     program_info_len: usize,
@@ -167,6 +167,46 @@ pub enum UserErr {
     SubgroupGet(#[from] entropy_client::user::SubgroupGetError),
     #[error("Unknown hashing algorthim - user is using a newer version than us")]
     UnknownHashingAlgorithm,
+    #[error("SHA-1 gives a 20 byte hash, which cannot be signed")]
+    Sha1NotSupported,
+    #[error("Signature batch request contains no messages")]
+    EmptyBatch,
+    #[error("BIP-340 signatures can only be made with ECDSA (secp256k1) accounts")]
+    SigningModeNotSupported,
+    #[error("Key derivation can only be used with ECDSA (secp256k1) accounts")]
+    DerivationNotSupported,
+    #[error("Hardened derivation paths cannot be used with a threshold key")]
+    HardenedDerivation,
+    #[error("BIP-32 error: {0}")]
+    Bip32(synedrion::bip32::Error),
+    #[error("No signing job with this ID")]
+    UnknownSigningJob,
+    #[error("Callback URL must be a valid HTTP or HTTPS URL")]
+    BadCallbackUrl,
+    #[error("Callback URL must not resolve to a private, loopback or link-local address")]
+    CallbackUrlNotPublic,
+    #[error("Prehashed messages must be 32 bytes long")]
+    BadPrehashLength,
+    #[error("Program {0} does not accept prehashed messages")]
+    PrehashedNotAccepted(String),
+    #[error("Program {0} does not accept derivation paths")]
+    DerivationPathNotAccepted(String),
+    #[error("This account is not registering with an imported key")]
+    NotImportingKey,
+    #[error("The imported keyshare does not match the verifying key given when registering")]
+    MismatchedImportedKey,
+    #[error("Only the program modification account of a verifying key can export its keyshare")]
+    ExportNotAuthorized,
+    #[error("No recovery key has been set for this verifying key")]
+    NoRecoveryKey,
+    #[error("The recovery key can not be used until block {0}")]
+    RecoveryKeyNotActive(u32),
+    #[error("Only ECDSA keyshares can be exported")]
+    ExportNotSupported,
+    #[error("No oracle data published at: {0}")]
+    NoOracleDataForPointer(String),
+    #[error("Program {0} cannot be evaluated with this configuration: {1}")]
+    InvalidProgramConfig(String, entropy_client::errors::ProgramValidationError),
 }
 
 impl From<hkdf::InvalidLength> for UserErr {
@@ -179,6 +219,10 @@ impl IntoResponse for UserErr {
     fn into_response(self) -> Response {
         tracing::error!("{:?}", format!("{self}"));
         let body = format!("{self}").into_bytes();
-        (StatusCode::INTERNAL_SERVER_ERROR, body).into_response()
+        let status = match self {
+            UserErr::UnknownSigningJob => StatusCode::NOT_FOUND,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, body).into_response()
     }
 }
@@ -18,24 +18,28 @@ use base64::prelude::{Engine, BASE64_STANDARD};
 use bip39::{Language, Mnemonic};
 use blake3::hash;
 use entropy_client::{
-    client::{sign, store_program, update_programs},
+    client::{deregister, register, set_rate_limit, sign, store_program, update_programs},
     user::get_signers_from_chain,
 };
 use entropy_kvdb::{
     clean_tests,
     encrypted_sled::PasswordMethod,
+    get_db_path,
     kv_manager::{helpers::deserialize as keyshare_deserialize, value::KvManager},
 };
-use entropy_programs_runtime::{Runtime, SignatureRequest};
+use entropy_programs_runtime::SignatureRequest;
 use entropy_protocol::{
     decode_verifying_key,
     protocol_transport::{noise::noise_handshake_initiator, SubscribeMessage, WsConnection},
-    KeyParams, KeyShareWithAuxInfo, PartyId, SessionId, SigningSessionInfo, ValidatorInfo,
+    Ed25519KeyShare, KeyParams, KeyShareWithAuxInfo, PartyId, SessionId, SigningSessionInfo,
+    ThresholdSignature, ValidatorInfo,
 };
 use entropy_shared::{
-    HashingAlgorithm, OcwMessageDkg, DAVE_VERIFYING_KEY, DEFAULT_VERIFYING_KEY,
-    DEFAULT_VERIFYING_KEY_NOT_REGISTERED, DEVICE_KEY_HASH, EVE_VERIFYING_KEY, FERDIE_VERIFYING_KEY,
-    NETWORK_PARENT_KEY,
+    HashingAlgorithm, OcwMessageDeregister, OcwMessageDkg, RateLimit, SignatureScheme, SigningMode,
+    BLOCK_NUMBER_ORACLE_KEY, DAVE_VERIFYING_KEY, DEFAULT_VERIFYING_KEY,
+    DEFAULT_VERIFYING_KEY_NOT_REGISTERED, DEVICE_KEY_HASH, ED25519_VERIFICATION_KEY_LENGTH,
+    EVE_VERIFYING_KEY, FERDIE_VERIFYING_KEY, NETWORK_PARENT_KEY, OCW_SIGNATURE_HEADER,
+    SIGNER_THRESHOLD, TOTAL_SIGNERS,
 };
 use entropy_testing_utils::{
     chain_api::{
@@ -102,11 +106,14 @@ use x25519_dalek::{PublicKey, StaticSecret};
 use super::UserInputPartyInfo;
 use crate::{
     chain_api::{
-        entropy, entropy::runtime_types::bounded_collections::bounded_vec::BoundedVec,
+        entropy,
+        entropy::runtime_types::bounded_collections::bounded_vec::BoundedVec,
         entropy::runtime_types::entropy_runtime::RuntimeCall,
         entropy::runtime_types::pallet_balances::pallet::Call as BalancesCall,
-        entropy::runtime_types::pallet_registry::pallet::ProgramInstance, get_api, get_rpc,
-        EntropyConfig,
+        entropy::runtime_types::pallet_registry::pallet::{
+            ProgramInstance, RegistrationFailure, RegistrationStatus,
+        },
+        get_api, get_rpc, EntropyConfig,
     },
     get_signer,
     helpers::{
@@ -115,11 +122,13 @@ use crate::{
             Configuration, ValidatorName, DEFAULT_BOB_MNEMONIC, DEFAULT_CHARLIE_MNEMONIC,
             DEFAULT_ENDPOINT, DEFAULT_MNEMONIC,
         },
+        program_runtime::ProgramRuntime,
         signing::Hasher,
-        substrate::{query_chain, submit_transaction},
+        substrate::{get_oracle_data, get_rate_limit, query_chain, submit_transaction},
         tests::{
             check_has_confirmation, check_if_confirmation, create_clients, initialize_test_logger,
-            remove_program, run_to_block, setup_client, spawn_testing_validators, unsafe_get,
+            remove_program, run_to_block, setup_client, sign_ocw_message, spawn_testing_validators,
+            unsafe_get,
         },
         user::compute_hash,
         validator::get_signer_and_x25519_secret_from_mnemonic,
@@ -129,8 +138,11 @@ use crate::{
     signing_client::ListenerState,
     user::{
         api::{
-            check_hash_pointer_out_of_bounds, confirm_registered, increment_or_wipe_request_limit,
-            request_limit_check, request_limit_key, RequestLimitStorage, UserRegistrationInfo,
+            check_callback_url, check_derivation_path, check_hash_pointer_out_of_bounds,
+            confirm_registered, evaluate_limit_key, increment_or_wipe_request_limit,
+            recover_signing_jobs, request_limit_check, request_limit_key, signing_job_key,
+            update_signing_job, BatchMessage, ProgramEvaluation, RequestLimitStorage, SigningJob,
+            SigningJobStatus, SigningJobs, UserRegistrationInfo, UserSignatureBatchRequest,
             UserSignatureRequest,
         },
         UserErr,
@@ -179,6 +191,8 @@ async fn test_sign_tx_no_chain() {
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
@@ -189,6 +203,9 @@ async fn test_sign_tx_no_chain() {
         signature_verifying_key: DAVE_VERIFYING_KEY.to_vec(),
         message_hash,
         request_author: signature_request_account.clone(),
+        signature_scheme: SignatureScheme::Ecdsa,
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
     });
 
     let (validators_info, mut generic_msg, validator_ips_and_keys) =
@@ -205,11 +222,19 @@ async fn test_sign_tx_no_chain() {
     update_programs(
         &entropy_api,
         &rpc,
-        DAVE_VERIFYING_KEY,
+        DAVE_VERIFYING_KEY.to_vec(),
         &one.pair(),
         OtherBoundedVec(vec![
-            OtherProgramInstance { program_pointer: program_hash, program_config: vec![] },
-            OtherProgramInstance { program_pointer: program_hash, program_config: vec![] },
+            OtherProgramInstance {
+                program_pointer: program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
+            OtherProgramInstance {
+                program_pointer: program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
         ]),
     )
     .await
@@ -436,17 +461,27 @@ async fn test_sign_tx_no_chain_fail() {
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
     update_programs(
         &entropy_api,
         &rpc,
-        DAVE_VERIFYING_KEY,
+        DAVE_VERIFYING_KEY.to_vec(),
         &one.pair(),
         OtherBoundedVec(vec![
-            OtherProgramInstance { program_pointer: program_hash, program_config: vec![] },
-            OtherProgramInstance { program_pointer: program_hash, program_config: vec![] },
+            OtherProgramInstance {
+                program_pointer: program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
+            OtherProgramInstance {
+                program_pointer: program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
         ]),
     )
     .await
@@ -506,6 +541,8 @@ async fn test_program_with_config() {
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
@@ -529,11 +566,19 @@ async fn test_program_with_config() {
     update_programs(
         &entropy_api,
         &rpc,
-        DAVE_VERIFYING_KEY,
+        DAVE_VERIFYING_KEY.to_vec(),
         &one.pair(),
         OtherBoundedVec(vec![
-            OtherProgramInstance { program_pointer: program_hash, program_config: config.to_vec() },
-            OtherProgramInstance { program_pointer: program_hash, program_config: config.to_vec() },
+            OtherProgramInstance {
+                program_pointer: program_hash,
+                program_config: config.to_vec(),
+                fuel: None,
+            },
+            OtherProgramInstance {
+                program_pointer: program_hash,
+                program_config: config.to_vec(),
+                fuel: None,
+            },
         ]),
     )
     .await
@@ -573,6 +618,8 @@ async fn test_store_share() {
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
@@ -607,7 +654,12 @@ async fn test_store_share() {
         &rpc,
         &alice,
         alice_program.to_account_id().into(),
-        BoundedVec(vec![ProgramInstance { program_pointer: program_hash, program_config: vec![] }]),
+        BoundedVec(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
+        SignatureScheme::Ecdsa,
     )
     .await;
 
@@ -619,6 +671,10 @@ async fn test_store_share() {
             .map(|port| {
                 client
                     .post(format!("http://127.0.0.1:{}/user/new", port))
+                    .header(
+                        OCW_SIGNATURE_HEADER,
+                        sign_ocw_message(*port, &onchain_user_request.encode()),
+                    )
                     .body(onchain_user_request.clone().encode())
                     .send()
             })
@@ -658,9 +714,22 @@ async fn test_store_share() {
         entropy_kvdb::kv_manager::helpers::deserialize(&response_key);
     assert_eq!(key_share.is_some(), true);
 
+    // fails without a signature from the validator's off-chain worker
+    let response_unsigned = client
+        .post("http://127.0.0.1:3001/user/new")
+        .body(onchain_user_request.clone().encode())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response_unsigned.text().await.unwrap(),
+        "Invalid Signature: No off-chain worker signature"
+    );
+
     // fails repeated data
     let response_repeated_data = client
         .post("http://127.0.0.1:3001/user/new")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3001, &onchain_user_request.encode()))
         .body(onchain_user_request.clone().encode())
         .send()
         .await
@@ -674,6 +743,7 @@ async fn test_store_share() {
     // fails stale data
     let response_stale = client
         .post("http://127.0.0.1:3001/user/new")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3001, &onchain_user_request.encode()))
         .body(onchain_user_request.clone().encode())
         .send()
         .await
@@ -688,7 +758,12 @@ async fn test_store_share() {
         &rpc,
         &alice_program,
         alice_program.to_account_id().into(),
-        BoundedVec(vec![ProgramInstance { program_pointer: program_hash, program_config: vec![] }]),
+        BoundedVec(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
+        SignatureScheme::Ecdsa,
     )
     .await;
     onchain_user_request.block_number = block_number;
@@ -697,6 +772,7 @@ async fn test_store_share() {
     // fails not verified data
     let response_not_verified = client
         .post("http://127.0.0.1:3001/user/new")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3001, &onchain_user_request.encode()))
         .body(onchain_user_request.clone().encode())
         .send()
         .await
@@ -705,10 +781,26 @@ async fn test_store_share() {
     assert_eq!(response_not_verified.status(), StatusCode::INTERNAL_SERVER_ERROR);
     assert_eq!(response_not_verified.text().await.unwrap(), "Data is not verifiable");
 
+    // fails with validators which are not the ones on chain
+    let mut wrong_validators_request = onchain_user_request.clone();
+    wrong_validators_request.sig_request_accounts = vec![alice_program.public().encode()];
+    wrong_validators_request.validators_info[2].ip_address = b"127.0.0.1:3004".to_vec();
+    let response_wrong_validators = client
+        .post("http://127.0.0.1:3001/user/new")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3001, &wrong_validators_request.encode()))
+        .body(wrong_validators_request.encode())
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response_wrong_validators.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response_wrong_validators.text().await.unwrap(), "Data is not verifiable");
+
     onchain_user_request.validators_info[0].tss_account = TSS_ACCOUNTS[1].clone().encode();
     // fails not in validator group data
     let response_not_validator = client
         .post("http://127.0.0.1:3001/user/new")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3001, &onchain_user_request.encode()))
         .body(onchain_user_request.clone().encode())
         .send()
         .await
@@ -723,11 +815,13 @@ async fn test_store_share() {
 
 #[tokio::test]
 #[serial]
-async fn test_jumpstart_network() {
+async fn test_register_refused_with_invalid_program_config() {
     initialize_test_logger().await;
     clean_tests();
 
     let alice = AccountKeyring::Alice;
+    let alice_program = AccountKeyring::Charlie;
+    let program_manager = AccountKeyring::Dave;
 
     let cxt = test_context_stationary().await;
     let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
@@ -736,6 +830,32 @@ async fn test_jumpstart_network() {
 
     let client = reqwest::Client::new();
 
+    let configuration_schema = r#"
+        {
+            "type": "object",
+            "properties": {
+                "allowlisted_addresses": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["allowlisted_addresses"]
+        }
+    "#
+    .as_bytes()
+    .to_vec();
+
+    let program_hash = store_program(
+        &api,
+        &rpc,
+        &program_manager.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        configuration_schema,
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
     let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number + 1;
 
     let validators_info = vec![
@@ -756,22 +876,38 @@ async fn test_jumpstart_network() {
         },
     ];
     let onchain_user_request = OcwMessageDkg {
-        sig_request_accounts: vec![NETWORK_PARENT_KEY.encode()],
+        sig_request_accounts: vec![alice.public().encode()],
         block_number,
         validators_info,
     };
 
-    put_jumpstart_request_on_chain(&api, &rpc, &alice).await;
+    // Well formed JSON, but the addresses are not an array as the schema requires
+    put_register_request_on_chain(
+        &api,
+        &rpc,
+        &alice,
+        alice_program.to_account_id().into(),
+        BoundedVec(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: r#"{ "allowlisted_addresses": 5 }"#.as_bytes().to_vec(),
+            fuel: None,
+        }]),
+        SignatureScheme::Ecdsa,
+    )
+    .await;
 
     run_to_block(&rpc, block_number + 1).await;
 
-    // succeeds
     let response_results = join_all(
         vec![3002, 3003]
             .iter()
             .map(|port| {
                 client
-                    .post(format!("http://127.0.0.1:{}/generate_network_key", port))
+                    .post(format!("http://127.0.0.1:{}/user/new", port))
+                    .header(
+                        OCW_SIGNATURE_HEADER,
+                        sign_ocw_message(*port, &onchain_user_request.encode()),
+                    )
                     .body(onchain_user_request.clone().encode())
                     .send()
             })
@@ -783,587 +919,1770 @@ async fn test_jumpstart_network() {
         assert_eq!(response_result.unwrap().text().await.unwrap(), "");
     }
 
-    // wait for jump start event check that key exists in kvdb
+    let mut refused = false;
+    // wait for the committee to refuse the registration
     for _ in 0..45 {
         std::thread::sleep(std::time::Duration::from_millis(1000));
         let block_hash = rpc.chain_get_block_hash(None).await.unwrap();
         let events = EventsClient::new(api.clone()).at(block_hash.unwrap()).await.unwrap();
-        let jump_start_event = events.find::<entropy::registry::events::FinishedNetworkJumpStart>();
-        for _event in jump_start_event.flatten() {
+        let failed_event = events.find::<entropy::registry::events::FailedRegistration>();
+        for event in failed_event.flatten() {
+            if event.0 == alice.to_account_id().into() {
+                assert!(matches!(event.1, RegistrationFailure::InvalidProgramConfig));
+                refused = true;
+            }
+        }
+        if refused {
             break;
         }
     }
-
-    let response_key = unsafe_get(&client, hex::encode(NETWORK_PARENT_KEY), 3001).await;
-
-    // check to make sure keyshare is correct
-    let key_share: Option<KeyShareWithAuxInfo> =
-        entropy_kvdb::kv_manager::helpers::deserialize(&response_key);
-    assert_eq!(key_share.is_some(), true);
-    let jump_start_progress_query = entropy::storage().registry().jump_start_progress();
-    let jump_start_progress =
-        query_chain(&api, &rpc, jump_start_progress_query, None).await.unwrap().unwrap();
-    let verifying_key =
-        key_share.unwrap().0.verifying_key().to_encoded_point(true).as_bytes().to_vec();
-
-    assert_eq!(jump_start_progress.verifying_key.unwrap().0, verifying_key);
+    // Check that the timeout was not reached
+    assert!(refused);
+
+    // No key was generated for the refused registration
+    let alice_account: subxtAccountId32 = alice.to_account_id().into();
+    let registering_query = entropy::storage().registry().registering(&alice_account);
+    let registering_info = query_chain(&api, &rpc, registering_query, None).await.unwrap().unwrap();
+    assert!(matches!(
+        registering_info.status,
+        RegistrationStatus::Failed(RegistrationFailure::InvalidProgramConfig)
+    ));
     clean_tests();
 }
 
-pub async fn put_register_request_on_chain(
-    api: &OnlineClient<EntropyConfig>,
-    rpc: &LegacyRpcMethods<EntropyConfig>,
-    sig_req_keyring: &Sr25519Keyring,
-    program_modification_account: subxtAccountId32,
-    program_instance: BoundedVec<ProgramInstance>,
-) {
-    let sig_req_account =
-        PairSigner::<EntropyConfig, sp_core::sr25519::Pair>::new(sig_req_keyring.pair());
-
-    let registering_tx =
-        entropy::tx().registry().register(program_modification_account, program_instance);
-    submit_transaction(api, rpc, &sig_req_account, &registering_tx, None).await.unwrap();
-}
-
-pub async fn put_jumpstart_request_on_chain(
-    api: &OnlineClient<EntropyConfig>,
-    rpc: &LegacyRpcMethods<EntropyConfig>,
-    sig_req_keyring: &Sr25519Keyring,
-) {
-    let sig_req_account =
-        PairSigner::<EntropyConfig, sp_core::sr25519::Pair>::new(sig_req_keyring.pair());
-
-    let registering_tx = entropy::tx().registry().jump_start_network();
-    submit_transaction(api, rpc, &sig_req_account, &registering_tx, None).await.unwrap();
-}
-
 #[tokio::test]
-async fn test_compute_hash() {
+#[serial]
+async fn test_program_change_refused_with_invalid_program_config() {
     initialize_test_logger().await;
     clean_tests();
-    let one = AccountKeyring::Dave;
-    let substrate_context = testing_context().await;
-    let api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
-    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
 
-    let mut runtime = Runtime::default();
+    let dave = AccountKeyring::Dave;
+    let program_manager = AccountKeyring::Two;
+
+    let cxt = test_context_stationary().await;
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let api = get_api(&cxt.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&cxt.node_proc.ws_url).await.unwrap();
+
+    let configuration_schema = r#"
+        {
+            "type": "object",
+            "properties": {
+                "allowlisted_addresses": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["allowlisted_addresses"]
+        }
+    "#
+    .as_bytes()
+    .to_vec();
+
     let program_hash = store_program(
         &api,
         &rpc,
-        &one.pair(),
-        TEST_PROGRAM_CUSTOM_HASH.to_owned(),
-        vec![],
+        &program_manager.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        configuration_schema,
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
 
-    let message_hash = compute_hash(
-        &api,
-        &rpc,
-        &HashingAlgorithm::Custom(0),
-        &mut runtime,
-        &vec![ProgramInstance { program_pointer: program_hash, program_config: vec![] }],
-        PREIMAGE_SHOULD_SUCCEED,
-    )
-    .await
-    .unwrap();
-    // custom hash program uses blake 3 to hash
-    let expected_hash = blake3::hash(PREIMAGE_SHOULD_SUCCEED).as_bytes().to_vec();
-    assert_eq!(message_hash.to_vec(), expected_hash);
-}
-
-#[tokio::test]
-async fn test_check_hash_pointer_out_of_bounds() {
-    assert!(check_hash_pointer_out_of_bounds(&HashingAlgorithm::Custom(2), 5).is_ok());
-    assert_eq!(
-        check_hash_pointer_out_of_bounds(&HashingAlgorithm::Custom(5), 5).unwrap_err().to_string(),
-        "Custom hash choice out of bounds".to_string()
+    let verifying_key = BoundedVec(DAVE_VERIFYING_KEY.to_vec());
+    let registered_query = entropy::storage().registry().registered(&verifying_key);
+    let programs_before = query_chain(&api, &rpc, registered_query.clone(), None)
+        .await
+        .unwrap()
+        .unwrap()
+        .programs_data;
+
+    // Well formed JSON, but the addresses are not an array as the schema requires. This goes
+    // straight to the chain, skipping the check `update_programs` makes
+    let dave_signer = PairSigner::<EntropyConfig, sp_core::sr25519::Pair>::new(dave.pair());
+    let change_program_tx = entropy::tx().registry().change_program_instance(
+        verifying_key.clone(),
+        BoundedVec(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: r#"{ "allowlisted_addresses": 5 }"#.as_bytes().to_vec(),
+            fuel: None,
+        }]),
     );
-}
+    submit_transaction(&api, &rpc, &dave_signer, &change_program_tx, None).await.unwrap();
 
-pub async fn verify_signature(
-    test_user_res: Vec<Result<reqwest::Response, reqwest::Error>>,
-    message_should_succeed_hash: [u8; 32],
-    verifying_key: &VerifyingKey,
-    validators_info: &Vec<ValidatorInfo>,
-) {
-    let mut i = 0;
-    for res in test_user_res {
-        let mut res = res.unwrap();
-        assert_eq!(res.status(), 200);
-        let chunk = res.chunk().await.unwrap().unwrap();
-        let signing_result: Result<(String, Signature), String> =
-            serde_json::from_slice(&chunk).unwrap();
-        assert_eq!(signing_result.clone().unwrap().0.len(), 88);
-        let mut decoded_sig = BASE64_STANDARD.decode(signing_result.clone().unwrap().0).unwrap();
-        let recovery_digit = decoded_sig.pop().unwrap();
-        let signature = k256Signature::from_slice(&decoded_sig).unwrap();
-        let recover_id = RecoveryId::from_byte(recovery_digit).unwrap();
-        let recovery_key_from_sig = VerifyingKey::recover_from_prehash(
-            &message_should_succeed_hash,
-            &signature,
-            recover_id,
-        )
-        .unwrap();
-        assert_eq!(verifying_key, &recovery_key_from_sig);
-        let sig_recovery = <sr25519::Pair as Pair>::verify(
-            &signing_result.clone().unwrap().1,
-            BASE64_STANDARD.decode(signing_result.unwrap().0).unwrap(),
-            &sr25519::Public(validators_info[i].tss_account.0),
-        );
-        assert!(sig_recovery);
-        i += 1;
+    let mut refused = false;
+    // wait for the committee to refuse the program change
+    for _ in 0..45 {
+        std::thread::sleep(std::time::Duration::from_millis(1000));
+        let block_hash = rpc.chain_get_block_hash(None).await.unwrap();
+        let events = EventsClient::new(api.clone()).at(block_hash.unwrap()).await.unwrap();
+        let refused_event = events.find::<entropy::registry::events::ProgramChangeRefused>();
+        for event in refused_event.flatten() {
+            if event.0 .0 == DAVE_VERIFYING_KEY.to_vec() {
+                refused = true;
+            }
+        }
+        if refused {
+            break;
+        }
     }
+    // Check that the timeout was not reached
+    assert!(refused);
+
+    // The refused change is dropped and the account keeps its programs
+    let pending_query = entropy::storage().registry().pending_program_changes(&verifying_key);
+    assert!(query_chain(&api, &rpc, pending_query, None).await.unwrap().is_none());
+    let programs_after =
+        query_chain(&api, &rpc, registered_query, None).await.unwrap().unwrap().programs_data;
+    assert_eq!(programs_after.encode(), programs_before.encode());
+    clean_tests();
 }
 
 #[tokio::test]
 #[serial]
-async fn test_fail_infinite_program() {
+async fn test_register_and_sign_with_ed25519() {
     initialize_test_logger().await;
     clean_tests();
 
-    let one = AccountKeyring::Dave;
-    let two = AccountKeyring::Two;
+    let alice = AccountKeyring::Alice;
+    let alice_program = AccountKeyring::Charlie;
+    let program_manager = AccountKeyring::Dave;
 
-    let (validator_ips, _validator_ids) = spawn_testing_validators(false).await;
-    let substrate_context = test_context_stationary().await;
-    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
-    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+    let cxt = test_context_stationary().await;
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let api = get_api(&cxt.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&cxt.node_proc.ws_url).await.unwrap();
+
+    let client = reqwest::Client::new();
 
     let program_hash = store_program(
-        &entropy_api,
+        &api,
         &rpc,
-        &two.pair(),
-        TEST_INFINITE_LOOP_BYTECODE.to_owned(),
+        &program_manager.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
 
-    update_programs(
-        &entropy_api,
-        &rpc,
-        DAVE_VERIFYING_KEY,
-        &one.pair(),
-        OtherBoundedVec(vec![OtherProgramInstance {
-            program_pointer: program_hash,
-            program_config: vec![],
-        }]),
-    )
-    .await
-    .unwrap();
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number + 1;
 
     let validators_info = vec![
-        ValidatorInfo {
-            ip_address: "localhost:3001".to_string(),
+        entropy_shared::ValidatorInfo {
+            ip_address: b"127.0.0.1:3001".to_vec(),
             x25519_public_key: X25519_PUBLIC_KEYS[0],
-            tss_account: TSS_ACCOUNTS[0].clone(),
+            tss_account: TSS_ACCOUNTS[0].clone().encode(),
         },
-        ValidatorInfo {
-            ip_address: "127.0.0.1:3002".to_string(),
+        entropy_shared::ValidatorInfo {
+            ip_address: b"127.0.0.1:3002".to_vec(),
             x25519_public_key: X25519_PUBLIC_KEYS[1],
-            tss_account: TSS_ACCOUNTS[1].clone(),
+            tss_account: TSS_ACCOUNTS[1].clone().encode(),
+        },
+        entropy_shared::ValidatorInfo {
+            ip_address: b"127.0.0.1:3003".to_vec(),
+            x25519_public_key: X25519_PUBLIC_KEYS[2],
+            tss_account: TSS_ACCOUNTS[2].clone().encode(),
         },
     ];
+    let onchain_user_request = OcwMessageDkg {
+        sig_request_accounts: vec![alice.public().encode()],
+        block_number,
+        validators_info,
+    };
 
-    let mut generic_msg = UserSignatureRequest {
-        message: hex::encode(PREIMAGE_SHOULD_SUCCEED),
-        auxilary_data: Some(vec![
-            Some(hex::encode(AUXILARY_DATA_SHOULD_SUCCEED)),
+    // Register with the client, which waits until the TSS servers have confirmed the registration
+    let register_task = tokio::spawn({
+        let (api, rpc) = (api.clone(), rpc.clone());
+        async move {
+            register(
+                &api,
+                &rpc,
+                alice.pair(),
+                alice_program.to_account_id().into(),
+                BoundedVec(vec![ProgramInstance {
+                    program_pointer: program_hash,
+                    program_config: vec![],
+                    fuel: None,
+                }]),
+                SignatureScheme::Ed25519,
+                SIGNER_THRESHOLD,
+                TOTAL_SIGNERS,
+            )
+            .await
+        }
+    });
+
+    run_to_block(&rpc, block_number + 1).await;
+
+    // The chain only tells the first TSS server about the registration
+    let response_results = join_all(
+        vec![3002, 3003]
+            .iter()
+            .map(|port| {
+                client
+                    .post(format!("http://127.0.0.1:{}/user/new", port))
+                    .header(
+                        OCW_SIGNATURE_HEADER,
+                        sign_ocw_message(*port, &onchain_user_request.encode()),
+                    )
+                    .body(onchain_user_request.clone().encode())
+                    .send()
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await;
+
+    for response_result in response_results {
+        assert_eq!(response_result.unwrap().text().await.unwrap(), "");
+    }
+
+    let (new_verifying_key, registered_info) = register_task.await.unwrap().unwrap();
+    assert_eq!(new_verifying_key.len(), ED25519_VERIFICATION_KEY_LENGTH as usize);
+    assert_eq!(registered_info.signature_scheme.0, SignatureScheme::Ed25519);
+
+    let response_key = unsafe_get(&client, hex::encode(&new_verifying_key), 3001).await;
+    let key_share: Option<Ed25519KeyShare> = keyshare_deserialize(&response_key);
+    let verifying_key = key_share.unwrap().1.verifying_key().clone();
+    assert_eq!(verifying_key.serialize().unwrap(), new_verifying_key);
+
+    // The client checks each signature against the message itself, since Ed25519 does not sign
+    // its hash
+    let signing_report = sign(
+        &api,
+        &rpc,
+        alice.pair(),
+        new_verifying_key.clone(),
+        PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Keccak,
+        Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Ecdsa,
+        None,
+    )
+    .await
+    .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Ed25519(frost_signature) = signing_report.signature else {
+        panic!("Expected an Ed25519 signature");
+    };
+    assert!(verifying_key.verify(PREIMAGE_SHOULD_SUCCEED, &frost_signature).is_ok());
+
+    // Ed25519 keys cannot be used for BIP-340 signatures
+    assert!(sign(
+        &api,
+        &rpc,
+        alice.pair(),
+        new_verifying_key,
+        PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Keccak,
+        Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Bip340,
+        None,
+    )
+    .await
+    .is_err());
+    clean_tests();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_jumpstart_network() {
+    initialize_test_logger().await;
+    clean_tests();
+
+    let alice = AccountKeyring::Alice;
+
+    let cxt = test_context_stationary().await;
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let api = get_api(&cxt.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&cxt.node_proc.ws_url).await.unwrap();
+
+    let client = reqwest::Client::new();
+
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number + 1;
+
+    let validators_info = vec![
+        entropy_shared::ValidatorInfo {
+            ip_address: b"127.0.0.1:3001".to_vec(),
+            x25519_public_key: X25519_PUBLIC_KEYS[0],
+            tss_account: TSS_ACCOUNTS[0].clone().encode(),
+        },
+        entropy_shared::ValidatorInfo {
+            ip_address: b"127.0.0.1:3002".to_vec(),
+            x25519_public_key: X25519_PUBLIC_KEYS[1],
+            tss_account: TSS_ACCOUNTS[1].clone().encode(),
+        },
+        entropy_shared::ValidatorInfo {
+            ip_address: b"127.0.0.1:3003".to_vec(),
+            x25519_public_key: X25519_PUBLIC_KEYS[2],
+            tss_account: TSS_ACCOUNTS[2].clone().encode(),
+        },
+    ];
+    let onchain_user_request = OcwMessageDkg {
+        sig_request_accounts: vec![NETWORK_PARENT_KEY.encode()],
+        block_number,
+        validators_info,
+    };
+
+    put_jumpstart_request_on_chain(&api, &rpc, &alice).await;
+
+    run_to_block(&rpc, block_number + 1).await;
+
+    // succeeds
+    let response_results = join_all(
+        vec![3002, 3003]
+            .iter()
+            .map(|port| {
+                client
+                    .post(format!("http://127.0.0.1:{}/generate_network_key", port))
+                    .header(
+                        OCW_SIGNATURE_HEADER,
+                        sign_ocw_message(*port, &onchain_user_request.encode()),
+                    )
+                    .body(onchain_user_request.clone().encode())
+                    .send()
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await;
+
+    for response_result in response_results {
+        assert_eq!(response_result.unwrap().text().await.unwrap(), "");
+    }
+
+    // wait for jump start event check that key exists in kvdb
+    for _ in 0..45 {
+        std::thread::sleep(std::time::Duration::from_millis(1000));
+        let block_hash = rpc.chain_get_block_hash(None).await.unwrap();
+        let events = EventsClient::new(api.clone()).at(block_hash.unwrap()).await.unwrap();
+        let jump_start_event = events.find::<entropy::registry::events::FinishedNetworkJumpStart>();
+        for _event in jump_start_event.flatten() {
+            break;
+        }
+    }
+
+    let response_key = unsafe_get(&client, hex::encode(NETWORK_PARENT_KEY), 3001).await;
+
+    // check to make sure keyshare is correct
+    let key_share: Option<KeyShareWithAuxInfo> =
+        entropy_kvdb::kv_manager::helpers::deserialize(&response_key);
+    assert_eq!(key_share.is_some(), true);
+    let jump_start_progress_query = entropy::storage().registry().jump_start_progress();
+    let jump_start_progress =
+        query_chain(&api, &rpc, jump_start_progress_query, None).await.unwrap().unwrap();
+    let verifying_key =
+        key_share.unwrap().0.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+
+    assert_eq!(jump_start_progress.verifying_key.unwrap().0, verifying_key);
+    clean_tests();
+}
+
+pub async fn put_register_request_on_chain(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    sig_req_keyring: &Sr25519Keyring,
+    program_modification_account: subxtAccountId32,
+    program_instance: BoundedVec<ProgramInstance>,
+    signature_scheme: SignatureScheme,
+) {
+    let sig_req_account =
+        PairSigner::<EntropyConfig, sp_core::sr25519::Pair>::new(sig_req_keyring.pair());
+
+    let registering_tx = entropy::tx().registry().register(
+        program_modification_account,
+        program_instance,
+        Static(signature_scheme),
+        SIGNER_THRESHOLD,
+        TOTAL_SIGNERS,
+    );
+    submit_transaction(api, rpc, &sig_req_account, &registering_tx, None).await.unwrap();
+}
+
+pub async fn put_jumpstart_request_on_chain(
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    sig_req_keyring: &Sr25519Keyring,
+) {
+    let sig_req_account =
+        PairSigner::<EntropyConfig, sp_core::sr25519::Pair>::new(sig_req_keyring.pair());
+
+    let registering_tx = entropy::tx().registry().jump_start_network();
+    submit_transaction(api, rpc, &sig_req_account, &registering_tx, None).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_compute_hash() {
+    initialize_test_logger().await;
+    clean_tests();
+    let one = AccountKeyring::Dave;
+    let substrate_context = testing_context().await;
+    let api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+
+    let mut runtime = ProgramRuntime::default();
+    let program_hash = store_program(
+        &api,
+        &rpc,
+        &one.pair(),
+        TEST_PROGRAM_CUSTOM_HASH.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let message_hash = compute_hash(
+        &api,
+        &rpc,
+        &HashingAlgorithm::Custom(0),
+        &mut runtime,
+        &vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }],
+        PREIMAGE_SHOULD_SUCCEED,
+        None,
+    )
+    .await
+    .unwrap();
+    // custom hash program uses blake 3 to hash
+    let expected_hash = blake3::hash(PREIMAGE_SHOULD_SUCCEED).as_bytes().to_vec();
+    assert_eq!(message_hash.to_vec(), expected_hash);
+}
+
+#[tokio::test]
+async fn test_get_oracle_data() {
+    initialize_test_logger().await;
+    clean_tests();
+    let substrate_context = testing_context().await;
+    let api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+    run_to_block(&rpc, 3).await;
+
+    // Programs without an oracle data pointer get no oracle data
+    assert_eq!(get_oracle_data(&api, &rpc, &[], 2).await.unwrap(), None);
+
+    // The oracle data is read at the given block
+    let oracle_data = get_oracle_data(&api, &rpc, BLOCK_NUMBER_ORACLE_KEY, 2).await.unwrap();
+    assert_eq!(oracle_data, Some(2u32.encode()));
+
+    assert_eq!(
+        get_oracle_data(&api, &rpc, b"not published", 2).await.unwrap_err().to_string(),
+        "No oracle data published at: not published"
+    );
+}
+
+#[tokio::test]
+async fn test_check_hash_pointer_out_of_bounds() {
+    assert!(check_hash_pointer_out_of_bounds(&HashingAlgorithm::Custom(2), 5).is_ok());
+    assert_eq!(
+        check_hash_pointer_out_of_bounds(&HashingAlgorithm::Custom(5), 5).unwrap_err().to_string(),
+        "Custom hash choice out of bounds".to_string()
+    );
+}
+
+#[tokio::test]
+async fn test_check_derivation_path() {
+    assert!(check_derivation_path(&SignatureScheme::Ecdsa, &None).is_ok());
+    assert!(check_derivation_path(&SignatureScheme::Ecdsa, &Some("m/0/1".to_string())).is_ok());
+    assert_eq!(
+        check_derivation_path(&SignatureScheme::Ecdsa, &Some("m/0'/1".to_string()))
+            .unwrap_err()
+            .to_string(),
+        "Hardened derivation paths cannot be used with a threshold key".to_string()
+    );
+    assert_eq!(
+        check_derivation_path(&SignatureScheme::Ed25519, &Some("m/0".to_string()))
+            .unwrap_err()
+            .to_string(),
+        "Key derivation can only be used with ECDSA (secp256k1) accounts".to_string()
+    );
+    assert!(
+        check_derivation_path(&SignatureScheme::Ecdsa, &Some("not a path".to_string())).is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_check_callback_url() {
+    assert!(check_callback_url("https://1.1.1.1/callback", false).await.is_ok());
+    for private_url in [
+        "http://127.0.0.1:3001/callback",
+        "http://localhost/callback",
+        "http://10.0.0.1/callback",
+        "http://192.168.1.1/callback",
+        "http://169.254.169.254/latest/meta-data",
+        "http://100.64.0.1/callback",
+        "http://0.0.0.0/callback",
+        "http://[::1]/callback",
+        "http://[fe80::1]/callback",
+        "http://[fd00::1]/callback",
+        "http://[::ffff:127.0.0.1]/callback",
+    ] {
+        assert_eq!(
+            check_callback_url(private_url, false).await.unwrap_err().to_string(),
+            "Callback URL must not resolve to a private, loopback or link-local address"
+                .to_string()
+        );
+    }
+    assert!(check_callback_url("http://127.0.0.1:3001/callback", true).await.is_ok());
+    assert_eq!(
+        check_callback_url("ftp://1.1.1.1/callback", false).await.unwrap_err().to_string(),
+        "Callback URL must be a valid HTTP or HTTPS URL".to_string()
+    );
+}
+
+#[test]
+fn test_signing_jobs_expire_in_order() {
+    let signing_jobs = SigningJobs::default();
+    signing_jobs.push(10, "first".to_string());
+    signing_jobs.push(20, "second".to_string());
+    signing_jobs.push(30, "third".to_string());
+
+    assert!(signing_jobs.take_expired(9).is_empty());
+    assert_eq!(signing_jobs.take_expired(20), vec!["first".to_string(), "second".to_string()]);
+    assert!(signing_jobs.take_expired(20).is_empty());
+    assert_eq!(signing_jobs.take_expired(100), vec!["third".to_string()]);
+
+    // a job added out of order still expires in order
+    signing_jobs.push(200, "fifth".to_string());
+    signing_jobs.push(150, "fourth".to_string());
+    assert_eq!(signing_jobs.take_expired(200), vec!["fourth".to_string(), "fifth".to_string()]);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_recover_signing_jobs() {
+    initialize_test_logger().await;
+    clean_tests();
+
+    let kv_store =
+        KvManager::new(get_db_path(true).into(), PasswordMethod::NoPassword.execute().unwrap())
+            .unwrap();
+    let now = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
+    let done = SigningJobStatus::Failed("Program evaluation failed".to_string());
+    update_signing_job(&kv_store, "expired", &SigningJobStatus::Signing, now - 1).await.unwrap();
+    update_signing_job(&kv_store, "unfinished", &SigningJobStatus::Evaluating, now + 100)
+        .await
+        .unwrap();
+    update_signing_job(&kv_store, "finished", &done, now + 50).await.unwrap();
+
+    let signing_jobs = SigningJobs::default();
+    recover_signing_jobs(&signing_jobs, &kv_store).await.unwrap();
+
+    // the expired job is removed straight away
+    assert!(!kv_store.kv().exists(&signing_job_key("expired")).await.unwrap());
+
+    // an unfinished job is failed, as its signing session ended with the previous run
+    let unfinished = kv_store.kv().get(&signing_job_key("unfinished")).await.unwrap();
+    assert!(String::from_utf8(unfinished).unwrap().contains("restarted"));
+    let finished = kv_store.kv().get(&signing_job_key("finished")).await.unwrap();
+    assert!(String::from_utf8(finished).unwrap().contains("Program evaluation failed"));
+
+    // both are still removed once they expire
+    assert_eq!(
+        signing_jobs.take_expired(now + 100),
+        vec!["finished".to_string(), "unfinished".to_string()]
+    );
+    clean_tests();
+}
+
+pub async fn verify_signature(
+    test_user_res: Vec<Result<reqwest::Response, reqwest::Error>>,
+    message_should_succeed_hash: [u8; 32],
+    verifying_key: &VerifyingKey,
+    validators_info: &Vec<ValidatorInfo>,
+) {
+    let mut i = 0;
+    for res in test_user_res {
+        let mut res = res.unwrap();
+        assert_eq!(res.status(), 200);
+        let chunk = res.chunk().await.unwrap().unwrap();
+        let signing_result: Result<(String, Signature), String> =
+            serde_json::from_slice(&chunk).unwrap();
+        assert_eq!(signing_result.clone().unwrap().0.len(), 88);
+        let mut decoded_sig = BASE64_STANDARD.decode(signing_result.clone().unwrap().0).unwrap();
+        let recovery_digit = decoded_sig.pop().unwrap();
+        let signature = k256Signature::from_slice(&decoded_sig).unwrap();
+        let recover_id = RecoveryId::from_byte(recovery_digit).unwrap();
+        let recovery_key_from_sig = VerifyingKey::recover_from_prehash(
+            &message_should_succeed_hash,
+            &signature,
+            recover_id,
+        )
+        .unwrap();
+        assert_eq!(verifying_key, &recovery_key_from_sig);
+        let sig_recovery = <sr25519::Pair as Pair>::verify(
+            &signing_result.clone().unwrap().1,
+            BASE64_STANDARD.decode(signing_result.unwrap().0).unwrap(),
+            &sr25519::Public(validators_info[i].tss_account.0),
+        );
+        assert!(sig_recovery);
+        i += 1;
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_fail_infinite_program() {
+    initialize_test_logger().await;
+    clean_tests();
+
+    let one = AccountKeyring::Dave;
+    let two = AccountKeyring::Two;
+
+    let (validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_context_stationary().await;
+    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+
+    let program_hash = store_program(
+        &entropy_api,
+        &rpc,
+        &two.pair(),
+        TEST_INFINITE_LOOP_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    update_programs(
+        &entropy_api,
+        &rpc,
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
+    )
+    .await
+    .unwrap();
+
+    let validators_info = vec![
+        ValidatorInfo {
+            ip_address: "localhost:3001".to_string(),
+            x25519_public_key: X25519_PUBLIC_KEYS[0],
+            tss_account: TSS_ACCOUNTS[0].clone(),
+        },
+        ValidatorInfo {
+            ip_address: "127.0.0.1:3002".to_string(),
+            x25519_public_key: X25519_PUBLIC_KEYS[1],
+            tss_account: TSS_ACCOUNTS[1].clone(),
+        },
+    ];
+
+    let mut generic_msg = UserSignatureRequest {
+        message: hex::encode(PREIMAGE_SHOULD_SUCCEED),
+        auxilary_data: Some(vec![
+            Some(hex::encode(AUXILARY_DATA_SHOULD_SUCCEED)),
             Some(hex::encode(AUXILARY_DATA_SHOULD_SUCCEED)),
         ]),
         validators_info,
         block_number: rpc.chain_get_header(None).await.unwrap().unwrap().number,
-        hash: HashingAlgorithm::Keccak,
+        hash: HashingAlgorithm::Keccak,
+        signature_verifying_key: DAVE_VERIFYING_KEY.to_vec(),
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
+        callback_url: None,
+    };
+
+    let validator_ips_and_keys = vec![
+        (validator_ips[0].clone(), X25519_PUBLIC_KEYS[0]),
+        (validator_ips[1].clone(), X25519_PUBLIC_KEYS[1]),
+    ];
+
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+
+    let test_infinite_loop =
+        submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one).await;
+    for res in test_infinite_loop {
+        assert_eq!(res.unwrap().text().await.unwrap(), "Runtime error: OutOfFuel");
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_device_key_proxy() {
+    initialize_test_logger().await;
+    clean_tests();
+    /// JSON-deserializable struct that will be used to derive the program-JSON interface.
+    /// Note how this uses JSON-native types only.
+    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
+    pub struct UserConfig {
+        /// base64-encoded compressed point (33-byte) ECDSA public keys, (eg. "A572dqoue5OywY/48dtytQimL9WO0dpSObaFbAxoEWW9")
+        pub ecdsa_public_keys: Option<Vec<String>>,
+        pub sr25519_public_keys: Option<Vec<String>>,
+        pub ed25519_public_keys: Option<Vec<String>>,
+    }
+    /// JSON representation of the auxiliary data
+    #[cfg_attr(feature = "std", derive(schemars::JsonSchema))]
+    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+    pub struct AuxData {
+        /// "ecdsa", "ed25519", "sr25519"
+        pub public_key_type: String,
+        /// base64-encoded public key
+        pub public_key: String,
+        /// base64-encoded signature
+        pub signature: String,
+        /// The context for the signature only needed in sr25519 signature type
+        pub context: String,
+    }
+
+    let one = AccountKeyring::Dave;
+
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_context_stationary().await;
+    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+    let keypair = Sr25519Keypair::generate();
+    let public_key = BASE64_STANDARD.encode(keypair.public);
+
+    let device_key_user_config = UserConfig {
+        ecdsa_public_keys: None,
+        sr25519_public_keys: Some(vec![public_key.clone()]),
+        ed25519_public_keys: None,
+    };
+    // check to make sure config data stored properly
+    let program_query = entropy::storage().programs().programs(*DEVICE_KEY_HASH);
+    let program_data = query_chain(&entropy_api, &rpc, program_query, None).await.unwrap().unwrap();
+    let schema_config_device_key_proxy = schema_for!(UserConfig);
+    let schema_aux_data_device_key_proxy = schema_for!(AuxData);
+
+    assert_eq!(
+        serde_json::to_vec(&schema_config_device_key_proxy).unwrap(),
+        program_data.configuration_schema,
+        "configuration interface recoverable through schemars"
+    );
+    assert_eq!(
+        serde_json::to_vec(&schema_aux_data_device_key_proxy).unwrap(),
+        program_data.auxiliary_data_schema,
+        "aux data interface recoverable through schemers"
+    );
+    update_programs(
+        &entropy_api,
+        &rpc,
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: *DEVICE_KEY_HASH,
+            program_config: serde_json::to_vec(&device_key_user_config).unwrap(),
+            fuel: None,
+        }]),
+    )
+    .await
+    .unwrap();
+
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let validators_info =
+        get_signers_from_chain(&entropy_api, &rpc, DAVE_VERIFYING_KEY.to_vec(), block_number)
+            .await
+            .unwrap();
+    let context = signing_context(b"");
+
+    let sr25519_signature: Sr25519Signature = keypair.sign(context.bytes(PREIMAGE_SHOULD_SUCCEED));
+
+    let aux_data_json_sr25519 = AuxData {
+        public_key_type: "sr25519".to_string(),
+        public_key,
+        signature: BASE64_STANDARD.encode(sr25519_signature.to_bytes()),
+        context: "".to_string(),
+    };
+    let mut generic_msg = UserSignatureRequest {
+        message: hex::encode(PREIMAGE_SHOULD_SUCCEED),
+        auxilary_data: Some(vec![Some(hex::encode(
+            &serde_json::to_string(&aux_data_json_sr25519.clone()).unwrap(),
+        ))]),
+        validators_info: validators_info.clone(),
+        block_number,
+        hash: HashingAlgorithm::Keccak,
+        signature_verifying_key: DAVE_VERIFYING_KEY.to_vec(),
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
+        callback_url: None,
+    };
+
+    let validator_ips_and_keys: Vec<_> = validators_info
+        .iter()
+        .map(|validator_info| {
+            (validator_info.ip_address.clone(), validator_info.x25519_public_key.clone())
+        })
+        .collect();
+
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let message_hash = Hasher::keccak(PREIMAGE_SHOULD_SUCCEED);
+    let test_user_res =
+        submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one).await;
+
+    let verifying_key = decode_verifying_key(&DAVE_VERIFYING_KEY).unwrap();
+    verify_signature(test_user_res, message_hash, &verifying_key, &validators_info).await;
+}
+
+/// FIXME (#909): Ignored due to block number changing message causing signing selection to be the incorrect nodes
+#[ignore]
+#[tokio::test]
+#[serial]
+async fn test_faucet() {
+    initialize_test_logger().await;
+    clean_tests();
+    /// JSON representation of the auxiliary data
+    #[cfg_attr(feature = "std", derive(schemars::JsonSchema))]
+    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+    pub struct AuxData {
+        pub spec_version: u32,
+        pub transaction_version: u32,
+        pub string_account_id: String,
+        pub amount: u128,
+    }
+
+    /// JSON-deserializable struct that will be used to derive the program-JSON interface.
+    #[cfg_attr(feature = "std", derive(schemars::JsonSchema))]
+    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+    pub struct UserConfig {
+        max_transfer_amount: u128,
+        genesis_hash: String,
+    }
+
+    let one = AccountKeyring::Dave;
+    let two = AccountKeyring::Eve;
+    let alice = AccountKeyring::Alice;
+
+    let (validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_node_process_testing_state(true).await;
+    let entropy_api = get_api(&substrate_context.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.ws_url).await.unwrap();
+
+    let verifying_key = EVE_VERIFYING_KEY;
+    let verfiying_key_account_hash = blake2_256(&verifying_key);
+    let verfiying_key_account = subxtAccountId32(verfiying_key_account_hash);
+
+    // Add funds to faucet
+    let call = RuntimeCall::Balances(BalancesCall::force_set_balance {
+        who: verfiying_key_account.clone().into(),
+        new_free: 10000000000000000000000u128,
+    });
+    let add_balance_tx = entropy::tx().sudo().sudo(call);
+
+    let signature_request_pair_signer =
+        PairSigner::<EntropyConfig, sp_core::sr25519::Pair>::new(alice.into());
+
+    let tx_params_balance = Params::new().build();
+    entropy_api
+        .tx()
+        .create_signed(&add_balance_tx, &signature_request_pair_signer, tx_params_balance)
+        .await
+        .unwrap()
+        .submit_and_watch()
+        .await
+        .unwrap();
+
+    let program_hash = store_program(
+        &entropy_api,
+        &rpc,
+        &two.pair(),
+        FAUCET_PROGRAM.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let amount_to_send = 200000001;
+    let genesis_hash = &entropy_api.genesis_hash();
+
+    let faucet_user_config = UserConfig {
+        max_transfer_amount: amount_to_send,
+        genesis_hash: hex::encode(genesis_hash.encode()),
+    };
+
+    update_programs(
+        &entropy_api,
+        &rpc,
+        verifying_key.to_vec(),
+        &two.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: program_hash,
+            program_config: serde_json::to_vec(&faucet_user_config).unwrap(),
+            fuel: None,
+        }]),
+    )
+    .await
+    .unwrap();
+
+    let validators_info = vec![
+        ValidatorInfo {
+            ip_address: "localhost:3001".to_string(),
+            x25519_public_key: X25519_PUBLIC_KEYS[0],
+            tss_account: TSS_ACCOUNTS[0].clone(),
+        },
+        ValidatorInfo {
+            ip_address: "127.0.0.1:3002".to_string(),
+            x25519_public_key: X25519_PUBLIC_KEYS[1],
+            tss_account: TSS_ACCOUNTS[1].clone(),
+        },
+    ];
+    // get tx data for aux data
+    let spec_version = entropy_api.runtime_version().spec_version;
+    let transaction_version = entropy_api.runtime_version().transaction_version;
+
+    let aux_data = AuxData {
+        spec_version,
+        transaction_version,
+        string_account_id: one.to_account_id().to_string(),
+        amount: amount_to_send,
+    };
+    // create a partial tx to sign
+    let tx_params = Params::new().build();
+    let balance_transfer_tx =
+        entropy::tx().balances().transfer_allow_death(one.to_account_id().into(), aux_data.amount);
+    let partial =
+        entropy_api.tx().create_partial_signed_offline(&balance_transfer_tx, tx_params).unwrap();
+
+    let mut generic_msg = UserSignatureRequest {
+        message: hex::encode(partial.signer_payload()),
+        auxilary_data: Some(vec![Some(hex::encode(
+            &serde_json::to_string(&aux_data.clone()).unwrap(),
+        ))]),
+        validators_info,
+        block_number: rpc.chain_get_header(None).await.unwrap().unwrap().number,
+        hash: HashingAlgorithm::Blake2_256,
+        signature_verifying_key: verifying_key.clone().to_vec(),
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
+        callback_url: None,
+    };
+
+    let validator_ips_and_keys = vec![
+        (validator_ips[0].clone(), X25519_PUBLIC_KEYS[0]),
+        (validator_ips[1].clone(), X25519_PUBLIC_KEYS[1]),
+    ];
+
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let test_user_res =
+        submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one).await;
+    let mut decoded_sig: Vec<u8> = vec![];
+    for res in test_user_res {
+        let chunk = res.unwrap().chunk().await.unwrap().unwrap();
+        let signing_result: Result<(String, Signature), String> =
+            serde_json::from_slice(&chunk).unwrap();
+        decoded_sig = BASE64_STANDARD.decode(signing_result.clone().unwrap().0).unwrap();
+    }
+    // take signed tx and repack it into a submitable tx
+    let submittable_extrinsic = partial.sign_with_address_and_signature(
+        &MultiAddress::Id(verfiying_key_account.clone().into()),
+        &MultiSignature::Ecdsa(decoded_sig.try_into().unwrap()),
+    );
+    let account = subxtAccountId32::from_str(&aux_data.string_account_id).unwrap();
+    // get balance before for checking if succeful
+    let balance_query = entropy::storage().system().account(account.clone());
+    let account_info = query_chain(&entropy_api, &rpc, balance_query, None).await.unwrap().unwrap();
+    let balance_before = account_info.data.free;
+    // submit then wait for tx
+    let mut tx = submittable_extrinsic.submit_and_watch().await.unwrap();
+
+    while let Some(status) = tx.next().await {
+        match status.unwrap() {
+            TxStatus::InBestBlock(tx_in_block) | TxStatus::InFinalizedBlock(tx_in_block) => {
+                assert!(tx_in_block.wait_for_success().await.is_ok());
+                break;
+            },
+            TxStatus::Error { message } => {
+                panic!("{}", message);
+            },
+            TxStatus::Invalid { message } => {
+                panic!("{}", message);
+            },
+            TxStatus::Dropped { message } => {
+                panic!("{}", message);
+            },
+            // Continue otherwise:
+            _ => continue,
+        };
+    }
+
+    // balance after
+    let balance_after_query = entropy::storage().system().account(account);
+    let account_info =
+        query_chain(&entropy_api, &rpc, balance_after_query, None).await.unwrap().unwrap();
+    let balance_after = account_info.data.free;
+    // make sure funds were transfered
+    ma::assert_gt!(balance_after, balance_before);
+    clean_tests();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_mutiple_confirm_done() {
+    initialize_test_logger().await;
+    clean_tests();
+
+    let alice = AccountKeyring::Alice;
+    let bob = AccountKeyring::Bob;
+
+    let alice_program = AccountKeyring::Charlie;
+    let program_manager = AccountKeyring::Dave;
+
+    let cxt = test_context_stationary().await;
+    let api = get_api(&cxt.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&cxt.node_proc.ws_url).await.unwrap();
+
+    let program_hash = store_program(
+        &api,
+        &rpc,
+        &program_manager.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    put_register_request_on_chain(
+        &api,
+        &rpc,
+        &alice,
+        alice_program.to_account_id().into(),
+        BoundedVec(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
+        SignatureScheme::Ecdsa,
+    )
+    .await;
+
+    put_register_request_on_chain(
+        &api,
+        &rpc,
+        &bob,
+        alice_program.to_account_id().into(),
+        BoundedVec(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
+        SignatureScheme::Ecdsa,
+    )
+    .await;
+
+    let (signer_alice, _) = get_signer_and_x25519_secret_from_mnemonic(DEFAULT_MNEMONIC).unwrap();
+
+    confirm_registered(
+        &api,
+        &rpc,
+        alice.to_account_id().into(),
+        &signer_alice,
+        DEFAULT_VERIFYING_KEY.to_vec(),
+        0u32,
+    )
+    .await
+    .unwrap();
+    confirm_registered(
+        &api,
+        &rpc,
+        bob.to_account_id().into(),
+        &signer_alice,
+        DEFAULT_VERIFYING_KEY.to_vec(),
+        1u32,
+    )
+    .await
+    .unwrap();
+    check_has_confirmation(&api, &rpc, &alice.pair()).await;
+    check_has_confirmation(&api, &rpc, &bob.pair()).await;
+    clean_tests();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_sign_batch() {
+    initialize_test_logger().await;
+    clean_tests();
+
+    let one = AccountKeyring::Dave;
+    let two = AccountKeyring::Two;
+
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_context_stationary().await;
+    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+    let program_hash = store_program(
+        &entropy_api,
+        &rpc,
+        &two.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    update_programs(
+        &entropy_api,
+        &rpc,
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
+    )
+    .await
+    .unwrap();
+
+    let (validators_info, generic_msg, validator_ips_and_keys) =
+        get_sign_tx_data(&entropy_api, &rpc, hex::encode(PREIMAGE_SHOULD_SUCCEED)).await;
+
+    let mut batch_request = UserSignatureBatchRequest {
+        messages: vec![
+            BatchMessage {
+                message: hex::encode(PREIMAGE_SHOULD_SUCCEED),
+                auxilary_data: Some(vec![Some(hex::encode(AUXILARY_DATA_SHOULD_SUCCEED))]),
+                hash: HashingAlgorithm::Keccak,
+            },
+            BatchMessage {
+                message: hex::encode(PREIMAGE_SHOULD_SUCCEED),
+                auxilary_data: Some(vec![Some(hex::encode(AUXILARY_DATA_SHOULD_SUCCEED))]),
+                hash: HashingAlgorithm::Sha2,
+            },
+        ],
+        validators_info: generic_msg.validators_info,
+        block_number: rpc.chain_get_header(None).await.unwrap().unwrap().number,
         signature_verifying_key: DAVE_VERIFYING_KEY.to_vec(),
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
     };
 
-    let validator_ips_and_keys = vec![
-        (validator_ips[0].clone(), X25519_PUBLIC_KEYS[0]),
-        (validator_ips[1].clone(), X25519_PUBLIC_KEYS[1]),
+    let expected_hashes = [
+        Hasher::keccak(PREIMAGE_SHOULD_SUCCEED),
+        <sha2::Sha256 as sha2::Digest>::digest(PREIMAGE_SHOULD_SUCCEED).into(),
     ];
+    let verifying_key = decode_verifying_key(&DAVE_VERIFYING_KEY).unwrap();
 
-    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let test_batch_res =
+        submit_batch_requests(validator_ips_and_keys.clone(), batch_request.clone(), one).await;
 
-    let test_infinite_loop =
-        submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one).await;
-    for res in test_infinite_loop {
-        assert_eq!(res.unwrap().text().await.unwrap(), "Runtime error: OutOfFuel");
+    for (i, res) in test_batch_res.into_iter().enumerate() {
+        let mut res = res.unwrap();
+        assert_eq!(res.status(), 200);
+        for expected_hash in expected_hashes.iter() {
+            let chunk = res.chunk().await.unwrap().unwrap();
+            let signing_result: Result<(String, Signature), String> =
+                serde_json::from_slice(&chunk).unwrap();
+            let (signature_base64, signature_of_signature) = signing_result.unwrap();
+            let mut decoded_sig = BASE64_STANDARD.decode(signature_base64).unwrap();
+            assert!(<sr25519::Pair as Pair>::verify(
+                &signature_of_signature,
+                &decoded_sig,
+                &sr25519::Public(validators_info[i].tss_account.0),
+            ));
+            let recovery_digit = decoded_sig.pop().unwrap();
+            let signature = k256Signature::from_slice(&decoded_sig).unwrap();
+            let recover_id = RecoveryId::from_byte(recovery_digit).unwrap();
+            let recovery_key_from_sig =
+                VerifyingKey::recover_from_prehash(expected_hash, &signature, recover_id).unwrap();
+            assert_eq!(verifying_key, recovery_key_from_sig);
+        }
+    }
+
+    // If one message fails program evaluation, the whole batch is rejected
+    batch_request.messages[1].message = hex::encode(PREIMAGE_SHOULD_FAIL);
+    batch_request.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let test_batch_failed_program =
+        submit_batch_requests(validator_ips_and_keys.clone(), batch_request.clone(), one).await;
+    for res in test_batch_failed_program {
+        assert_eq!(
+            res.unwrap().text().await.unwrap(),
+            "Runtime error: Runtime(Error::Evaluation(\"Length of data is too short.\"))"
+        );
+    }
+
+    batch_request.messages = vec![];
+    let test_empty_batch =
+        submit_batch_requests(validator_ips_and_keys.clone(), batch_request, one).await;
+    for res in test_empty_batch {
+        assert_eq!(
+            res.unwrap().text().await.unwrap(),
+            "Signature batch request contains no messages"
+        );
     }
+    clean_tests();
 }
 
 #[tokio::test]
 #[serial]
-async fn test_device_key_proxy() {
+async fn test_evaluate() {
     initialize_test_logger().await;
     clean_tests();
-    /// JSON-deserializable struct that will be used to derive the program-JSON interface.
-    /// Note how this uses JSON-native types only.
-    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, JsonSchema)]
-    pub struct UserConfig {
-        /// base64-encoded compressed point (33-byte) ECDSA public keys, (eg. "A572dqoue5OywY/48dtytQimL9WO0dpSObaFbAxoEWW9")
-        pub ecdsa_public_keys: Option<Vec<String>>,
-        pub sr25519_public_keys: Option<Vec<String>>,
-        pub ed25519_public_keys: Option<Vec<String>>,
-    }
-    /// JSON representation of the auxiliary data
-    #[cfg_attr(feature = "std", derive(schemars::JsonSchema))]
-    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
-    pub struct AuxData {
-        /// "ecdsa", "ed25519", "sr25519"
-        pub public_key_type: String,
-        /// base64-encoded public key
-        pub public_key: String,
-        /// base64-encoded signature
-        pub signature: String,
-        /// The context for the signature only needed in sr25519 signature type
-        pub context: String,
-    }
 
     let one = AccountKeyring::Dave;
+    let two = AccountKeyring::Two;
 
     let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
     let substrate_context = test_context_stationary().await;
     let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
     let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
-    let keypair = Sr25519Keypair::generate();
-    let public_key = BASE64_STANDARD.encode(keypair.public);
 
-    let device_key_user_config = UserConfig {
-        ecdsa_public_keys: None,
-        sr25519_public_keys: Some(vec![public_key.clone()]),
-        ed25519_public_keys: None,
+    let program_hash = store_program(
+        &entropy_api,
+        &rpc,
+        &two.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+    let infinite_program_hash = store_program(
+        &entropy_api,
+        &rpc,
+        &two.pair(),
+        TEST_INFINITE_LOOP_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    update_programs(
+        &entropy_api,
+        &rpc,
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![
+            OtherProgramInstance {
+                program_pointer: program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
+            OtherProgramInstance {
+                program_pointer: infinite_program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
+        ]),
+    )
+    .await
+    .unwrap();
+
+    let (_validators_info, mut generic_msg, _validator_ips_and_keys) =
+        get_sign_tx_data(&entropy_api, &rpc, hex::encode(PREIMAGE_SHOULD_SUCCEED)).await;
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+
+    let submit_evaluate = |signature_request: UserSignatureRequest| async move {
+        let signed_message = EncryptedSignedMessage::new(
+            &one.pair(),
+            serde_json::to_vec(&signature_request).unwrap(),
+            &X25519_PUBLIC_KEYS[1],
+            &[],
+        )
+        .unwrap();
+        reqwest::Client::new()
+            .post("http://127.0.0.1:3002/user/evaluate")
+            .header("Content-Type", "application/json")
+            .body(serde_json::to_string(&signed_message).unwrap())
+            .send()
+            .await
+            .unwrap()
+    };
+    let evaluate = |signature_request: UserSignatureRequest| async move {
+        let response = submit_evaluate(signature_request).await;
+        assert_eq!(response.status(), 200);
+        response.json::<Vec<ProgramEvaluation>>().await.unwrap()
     };
-    // check to make sure config data stored properly
-    let program_query = entropy::storage().programs().programs(*DEVICE_KEY_HASH);
-    let program_data = query_chain(&entropy_api, &rpc, program_query, None).await.unwrap().unwrap();
-    let schema_config_device_key_proxy = schema_for!(UserConfig);
-    let schema_aux_data_device_key_proxy = schema_for!(AuxData);
 
+    // Every program is evaluated, even after one fails
+    let evaluations = evaluate(generic_msg.clone()).await;
+    let passing_fuel_used = evaluations[0].fuel_used;
+    assert_eq!(evaluations.len(), 2);
+    assert_eq!(evaluations[0].program_pointer, hex::encode(program_hash));
+    assert!(evaluations[0].passed);
+    assert_eq!(evaluations[0].error, None);
+    assert!(evaluations[0].fuel_used > 0);
+    assert_eq!(evaluations[1].program_pointer, hex::encode(infinite_program_hash));
+    assert!(!evaluations[1].passed);
+    assert_eq!(evaluations[1].error, Some("Runtime error: OutOfFuel".to_string()));
+
+    generic_msg.message = hex::encode(PREIMAGE_SHOULD_FAIL);
+    let evaluations = evaluate(generic_msg.clone()).await;
+    assert_eq!(evaluations.len(), 2);
+    assert!(!evaluations[0].passed);
     assert_eq!(
-        serde_json::to_vec(&schema_config_device_key_proxy).unwrap(),
-        program_data.configuration_schema,
-        "configuration interface recoverable through schemars"
-    );
-    assert_eq!(
-        serde_json::to_vec(&schema_aux_data_device_key_proxy).unwrap(),
-        program_data.auxiliary_data_schema,
-        "aux data interface recoverable through schemers"
+        evaluations[0].error,
+        Some(
+            "Runtime error: Runtime(Error::Evaluation(\"Length of data is too short.\"))"
+                .to_string()
+        )
     );
+    assert!(!evaluations[1].passed);
+
+    // A program can be given less fuel than the network's maximum
+    update_programs(
+        &entropy_api,
+        &rpc,
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: Some(passing_fuel_used - 1),
+        }]),
+    )
+    .await
+    .unwrap();
+    generic_msg.message = hex::encode(PREIMAGE_SHOULD_SUCCEED);
+    generic_msg.auxilary_data = Some(vec![Some(hex::encode(AUXILARY_DATA_SHOULD_SUCCEED))]);
+    let fuel_limited_evaluations = evaluate(generic_msg.clone()).await;
+    assert_eq!(fuel_limited_evaluations.len(), 1);
+    assert_eq!(fuel_limited_evaluations[0].error, Some("Runtime error: OutOfFuel".to_string()));
+
+    // Evaluating still works once the request limit has been reached
+    let request_limit_query = entropy::storage().parameters().request_limit();
+    let request_limit =
+        query_chain(&entropy_api, &rpc, request_limit_query, None).await.unwrap().unwrap();
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let unsafe_put = UnsafeQuery::new(
+        request_limit_key(hex::encode(DAVE_VERIFYING_KEY.to_vec())),
+        RequestLimitStorage { request_amount: request_limit + 1, block_number }.encode(),
+    )
+    .to_json();
+    reqwest::Client::new()
+        .post("http://127.0.0.1:3002/unsafe/put")
+        .header("Content-Type", "application/json")
+        .body(unsafe_put)
+        .send()
+        .await
+        .unwrap();
+
+    generic_msg.block_number = block_number;
+    assert_eq!(evaluate(generic_msg.clone()).await.len(), 1);
+
+    // Stale requests are rejected
+    let mut stale_msg = generic_msg.clone();
+    stale_msg.block_number = block_number + 100;
+    let response_stale = submit_evaluate(stale_msg).await;
+    assert_eq!(response_stale.status(), 500);
+    assert_eq!(response_stale.text().await.unwrap(), "Validation Error: Message is too old");
+
+    // A custom hash must come from one of the account's programs
+    let mut out_of_bounds_msg = generic_msg.clone();
+    out_of_bounds_msg.hash = HashingAlgorithm::Custom(5);
+    let response_out_of_bounds = submit_evaluate(out_of_bounds_msg).await;
+    assert_eq!(response_out_of_bounds.status(), 500);
+    assert_eq!(response_out_of_bounds.text().await.unwrap(), "Custom hash choice out of bounds");
+
+    // Evaluations are limited per request author
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let unsafe_put = UnsafeQuery::new(
+        request_limit_key(evaluate_limit_key(&one.to_account_id().to_string())),
+        RequestLimitStorage { request_amount: request_limit, block_number }.encode(),
+    )
+    .to_json();
+    reqwest::Client::new()
+        .post("http://127.0.0.1:3002/unsafe/put")
+        .header("Content-Type", "application/json")
+        .body(unsafe_put)
+        .send()
+        .await
+        .unwrap();
+
+    generic_msg.block_number = block_number;
+    let response_too_many = submit_evaluate(generic_msg).await;
+    assert_eq!(response_too_many.status(), 500);
+    assert_eq!(response_too_many.text().await.unwrap(), "Too many requests - wait a block");
+    clean_tests();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_sign_tx_async() {
+    initialize_test_logger().await;
+    clean_tests();
+
+    let one = AccountKeyring::Dave;
+    let two = AccountKeyring::Two;
+
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_context_stationary().await;
+    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+    let program_hash = store_program(
+        &entropy_api,
+        &rpc,
+        &two.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
     update_programs(
         &entropy_api,
         &rpc,
-        DAVE_VERIFYING_KEY,
+        DAVE_VERIFYING_KEY.to_vec(),
         &one.pair(),
         OtherBoundedVec(vec![OtherProgramInstance {
-            program_pointer: *DEVICE_KEY_HASH,
-            program_config: serde_json::to_vec(&device_key_user_config).unwrap(),
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
         }]),
     )
     .await
     .unwrap();
 
-    let validators_info = get_signers_from_chain(&entropy_api, &rpc).await.unwrap();
-    let context = signing_context(b"");
+    // A server to receive the results sent to the callback URL
+    let (callback_tx, mut callback_rx) = futures::channel::mpsc::unbounded();
+    let callback_app = axum::Router::new().route(
+        "/callback",
+        axum::routing::post(move |axum::Json(signing_job): axum::Json<SigningJob>| {
+            let callback_tx = callback_tx.clone();
+            async move { callback_tx.unbounded_send(signing_job).unwrap() }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let callback_url = format!("http://{}/callback", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, callback_app).await.unwrap() });
 
-    let sr25519_signature: Sr25519Signature = keypair.sign(context.bytes(PREIMAGE_SHOULD_SUCCEED));
+    let (validators_info, mut generic_msg, validator_ips_and_keys) =
+        get_sign_tx_data(&entropy_api, &rpc, hex::encode(PREIMAGE_SHOULD_SUCCEED)).await;
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    generic_msg.callback_url = Some(callback_url);
+
+    let test_async_res =
+        submit_async_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one)
+            .await;
+    let mut job_ids = Vec::new();
+    for res in test_async_res {
+        let res = res.unwrap();
+        assert_eq!(res.status(), 202);
+        job_ids.push(res.text().await.unwrap());
+    }
 
-    let aux_data_json_sr25519 = AuxData {
-        public_key_type: "sr25519".to_string(),
-        public_key,
-        signature: BASE64_STANDARD.encode(sr25519_signature.to_bytes()),
-        context: "".to_string(),
-    };
-    let mut generic_msg = UserSignatureRequest {
-        message: hex::encode(PREIMAGE_SHOULD_SUCCEED),
-        auxilary_data: Some(vec![Some(hex::encode(
-            &serde_json::to_string(&aux_data_json_sr25519.clone()).unwrap(),
-        ))]),
-        validators_info: validators_info.clone(),
-        block_number: rpc.chain_get_header(None).await.unwrap().unwrap().number,
-        hash: HashingAlgorithm::Keccak,
-        signature_verifying_key: DAVE_VERIFYING_KEY.to_vec(),
-    };
+    // Poll each server until its job has finished
+    let mock_client = reqwest::Client::new();
+    let message_hash = Hasher::keccak(PREIMAGE_SHOULD_SUCCEED);
+    let verifying_key = decode_verifying_key(&DAVE_VERIFYING_KEY).unwrap();
+    for (i, ((ip_address, _), job_id)) in validator_ips_and_keys.iter().zip(&job_ids).enumerate() {
+        let url = format!("http://{}/user/sign_status/{}", ip_address, job_id);
+        let status = loop {
+            let status: SigningJobStatus =
+                mock_client.get(&url).send().await.unwrap().json().await.unwrap();
+            match status {
+                SigningJobStatus::Done(..) | SigningJobStatus::Failed(_) => break status,
+                _ => tokio::time::sleep(Duration::from_millis(500)).await,
+            }
+        };
+        let SigningJobStatus::Done(signature, tss_signature) = status else {
+            panic!("Signing job failed: {:?}", status);
+        };
 
-    let validator_ips_and_keys: Vec<_> = validators_info
-        .iter()
-        .map(|validator_info| {
-            (validator_info.ip_address.clone(), validator_info.x25519_public_key.clone())
-        })
-        .collect();
+        let mut decoded_sig = BASE64_STANDARD.decode(&signature).unwrap();
+        let recovery_id = RecoveryId::from_byte(decoded_sig.pop().unwrap()).unwrap();
+        let k256_signature = k256Signature::from_slice(&decoded_sig).unwrap();
+        let recovered_key =
+            VerifyingKey::recover_from_prehash(&message_hash, &k256_signature, recovery_id)
+                .unwrap();
+        assert_eq!(verifying_key, recovered_key);
+        assert!(<sr25519::Pair as Pair>::verify(
+            &tss_signature,
+            BASE64_STANDARD.decode(&signature).unwrap(),
+            &sr25519::Public(validators_info[i].tss_account.0),
+        ));
+    }
 
-    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
-    let message_hash = Hasher::keccak(PREIMAGE_SHOULD_SUCCEED);
-    let test_user_res =
-        submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one).await;
+    // Each server also sent its result to the callback URL
+    for _ in 0..job_ids.len() {
+        let signing_job = callback_rx.next().await.unwrap();
+        assert!(job_ids.contains(&signing_job.job_id));
+        assert!(matches!(signing_job.status, SigningJobStatus::Done(..)));
+    }
 
-    let verifying_key = decode_verifying_key(&DAVE_VERIFYING_KEY).unwrap();
-    verify_signature(test_user_res, message_hash, &verifying_key, &validators_info).await;
+    // A request which fails evaluation is still accepted, and the job then fails
+    let mut failing_msg = generic_msg.clone();
+    failing_msg.message = hex::encode(PREIMAGE_SHOULD_FAIL);
+    failing_msg.callback_url = None;
+    let test_async_fail =
+        submit_async_transaction_requests(validator_ips_and_keys.clone(), failing_msg, one).await;
+    for ((ip_address, _), res) in validator_ips_and_keys.iter().zip(test_async_fail) {
+        let res = res.unwrap();
+        assert_eq!(res.status(), 202);
+        let url = format!("http://{}/user/sign_status/{}", ip_address, res.text().await.unwrap());
+        let status = loop {
+            let status: SigningJobStatus =
+                mock_client.get(&url).send().await.unwrap().json().await.unwrap();
+            match status {
+                SigningJobStatus::Done(..) | SigningJobStatus::Failed(_) => break status,
+                _ => tokio::time::sleep(Duration::from_millis(500)).await,
+            }
+        };
+        assert!(matches!(status, SigningJobStatus::Failed(_)));
+    }
+
+    let unknown_job = mock_client
+        .get(format!("http://{}/user/sign_status/{}", validator_ips_and_keys[0].0, "00"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(unknown_job.status(), 404);
+
+    generic_msg.callback_url = Some("ftp://127.0.0.1/callback".to_string());
+    let test_bad_callback_url =
+        submit_async_transaction_requests(validator_ips_and_keys.clone(), generic_msg, one).await;
+    for res in test_bad_callback_url {
+        assert_eq!(
+            res.unwrap().text().await.unwrap(),
+            "Callback URL must be a valid HTTP or HTTPS URL"
+        );
+    }
+    clean_tests();
 }
 
-/// FIXME (#909): Ignored due to block number changing message causing signing selection to be the incorrect nodes
-#[ignore]
 #[tokio::test]
 #[serial]
-async fn test_faucet() {
+async fn test_sign_prehashed() {
     initialize_test_logger().await;
     clean_tests();
-    /// JSON representation of the auxiliary data
-    #[cfg_attr(feature = "std", derive(schemars::JsonSchema))]
-    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
-    pub struct AuxData {
-        pub spec_version: u32,
-        pub transaction_version: u32,
-        pub string_account_id: String,
-        pub amount: u128,
-    }
-
-    /// JSON-deserializable struct that will be used to derive the program-JSON interface.
-    #[cfg_attr(feature = "std", derive(schemars::JsonSchema))]
-    #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
-    pub struct UserConfig {
-        max_transfer_amount: u128,
-        genesis_hash: String,
-    }
 
     let one = AccountKeyring::Dave;
-    let two = AccountKeyring::Eve;
-    let alice = AccountKeyring::Alice;
-
-    let (validator_ips, _validator_ids) = spawn_testing_validators(false).await;
-    let substrate_context = test_node_process_testing_state(true).await;
-    let entropy_api = get_api(&substrate_context.ws_url).await.unwrap();
-    let rpc = get_rpc(&substrate_context.ws_url).await.unwrap();
-
-    let verifying_key = EVE_VERIFYING_KEY;
-    let verfiying_key_account_hash = blake2_256(&verifying_key);
-    let verfiying_key_account = subxtAccountId32(verfiying_key_account_hash);
-
-    // Add funds to faucet
-    let call = RuntimeCall::Balances(BalancesCall::force_set_balance {
-        who: verfiying_key_account.clone().into(),
-        new_free: 10000000000000000000000u128,
-    });
-    let add_balance_tx = entropy::tx().sudo().sudo(call);
+    let two = AccountKeyring::Two;
 
-    let signature_request_pair_signer =
-        PairSigner::<EntropyConfig, sp_core::sr25519::Pair>::new(alice.into());
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_context_stationary().await;
+    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
 
-    let tx_params_balance = Params::new().build();
-    entropy_api
-        .tx()
-        .create_signed(&add_balance_tx, &signature_request_pair_signer, tx_params_balance)
-        .await
-        .unwrap()
-        .submit_and_watch()
-        .await
-        .unwrap();
+    let message_hash = Hasher::keccak(PREIMAGE_SHOULD_SUCCEED);
+    let verifying_key = decode_verifying_key(&DAVE_VERIFYING_KEY).unwrap();
 
+    // A program which does not accept prehashed messages
     let program_hash = store_program(
         &entropy_api,
         &rpc,
         &two.pair(),
-        FAUCET_PROGRAM.to_owned(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
-
-    let amount_to_send = 200000001;
-    let genesis_hash = &entropy_api.genesis_hash();
-
-    let faucet_user_config = UserConfig {
-        max_transfer_amount: amount_to_send,
-        genesis_hash: hex::encode(genesis_hash.encode()),
-    };
-
     update_programs(
         &entropy_api,
         &rpc,
-        verifying_key.clone().try_into().unwrap(),
-        &two.pair(),
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
         OtherBoundedVec(vec![OtherProgramInstance {
             program_pointer: program_hash,
-            program_config: serde_json::to_vec(&faucet_user_config).unwrap(),
+            program_config: vec![],
+            fuel: None,
         }]),
     )
     .await
     .unwrap();
 
-    let validators_info = vec![
-        ValidatorInfo {
-            ip_address: "localhost:3001".to_string(),
-            x25519_public_key: X25519_PUBLIC_KEYS[0],
-            tss_account: TSS_ACCOUNTS[0].clone(),
-        },
-        ValidatorInfo {
-            ip_address: "127.0.0.1:3002".to_string(),
-            x25519_public_key: X25519_PUBLIC_KEYS[1],
-            tss_account: TSS_ACCOUNTS[1].clone(),
-        },
-    ];
-    // get tx data for aux data
-    let spec_version = entropy_api.runtime_version().spec_version;
-    let transaction_version = entropy_api.runtime_version().transaction_version;
-
-    let aux_data = AuxData {
-        spec_version,
-        transaction_version,
-        string_account_id: one.to_account_id().to_string(),
-        amount: amount_to_send,
-    };
-    // create a partial tx to sign
-    let tx_params = Params::new().build();
-    let balance_transfer_tx =
-        entropy::tx().balances().transfer_allow_death(one.to_account_id().into(), aux_data.amount);
-    let partial =
-        entropy_api.tx().create_partial_signed_offline(&balance_transfer_tx, tx_params).unwrap();
-
-    let mut generic_msg = UserSignatureRequest {
-        message: hex::encode(partial.signer_payload()),
-        auxilary_data: Some(vec![Some(hex::encode(
-            &serde_json::to_string(&aux_data.clone()).unwrap(),
-        ))]),
-        validators_info,
-        block_number: rpc.chain_get_header(None).await.unwrap().unwrap().number,
-        hash: HashingAlgorithm::Blake2_256,
-        signature_verifying_key: verifying_key.clone().to_vec(),
-    };
-
-    let validator_ips_and_keys = vec![
-        (validator_ips[0].clone(), X25519_PUBLIC_KEYS[0]),
-        (validator_ips[1].clone(), X25519_PUBLIC_KEYS[1]),
-    ];
-
+    let (validators_info, mut generic_msg, validator_ips_and_keys) =
+        get_sign_tx_data(&entropy_api, &rpc, hex::encode(message_hash)).await;
+    generic_msg.hash = HashingAlgorithm::Prehashed;
     generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
-    let test_user_res =
+
+    let test_not_accepted =
         submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one).await;
-    let mut decoded_sig: Vec<u8> = vec![];
-    for res in test_user_res {
-        let chunk = res.unwrap().chunk().await.unwrap().unwrap();
-        let signing_result: Result<(String, Signature), String> =
-            serde_json::from_slice(&chunk).unwrap();
-        decoded_sig = BASE64_STANDARD.decode(signing_result.clone().unwrap().0).unwrap();
+    for res in test_not_accepted {
+        assert_eq!(
+            res.unwrap().text().await.unwrap(),
+            format!("Program {} does not accept prehashed messages", program_hash)
+        );
     }
-    // take signed tx and repack it into a submitable tx
-    let submittable_extrinsic = partial.sign_with_address_and_signature(
-        &MultiAddress::Id(verfiying_key_account.clone().into()),
-        &MultiSignature::Ecdsa(decoded_sig.try_into().unwrap()),
-    );
-    let account = subxtAccountId32::from_str(&aux_data.string_account_id).unwrap();
-    // get balance before for checking if succeful
-    let balance_query = entropy::storage().system().account(account.clone());
-    let account_info = query_chain(&entropy_api, &rpc, balance_query, None).await.unwrap().unwrap();
-    let balance_before = account_info.data.free;
-    // submit then wait for tx
-    let mut tx = submittable_extrinsic.submit_and_watch().await.unwrap();
 
-    while let Some(status) = tx.next().await {
-        match status.unwrap() {
-            TxStatus::InBestBlock(tx_in_block) | TxStatus::InFinalizedBlock(tx_in_block) => {
-                assert!(tx_in_block.wait_for_success().await.is_ok());
-                break;
-            },
-            TxStatus::Error { message } => {
-                panic!("{}", message);
-            },
-            TxStatus::Invalid { message } => {
-                panic!("{}", message);
-            },
-            TxStatus::Dropped { message } => {
-                panic!("{}", message);
-            },
-            // Continue otherwise:
-            _ => continue,
-        };
-    }
+    // The same program, declared as accepting prehashed messages
+    let program_hash = store_program(
+        &entropy_api,
+        &rpc,
+        &two.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        true,
+        false,
+    )
+    .await
+    .unwrap();
+    update_programs(
+        &entropy_api,
+        &rpc,
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
+    )
+    .await
+    .unwrap();
 
-    // balance after
-    let balance_after_query = entropy::storage().system().account(account);
-    let account_info =
-        query_chain(&entropy_api, &rpc, balance_after_query, None).await.unwrap().unwrap();
-    let balance_after = account_info.data.free;
-    // make sure funds were transfered
-    ma::assert_gt!(balance_after, balance_before);
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let test_prehashed =
+        submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg.clone(), one).await;
+    verify_signature(test_prehashed, message_hash, &verifying_key, &validators_info).await;
+
+    // A prehashed message must be a 32 byte hash
+    generic_msg.message = hex::encode(PREIMAGE_SHOULD_SUCCEED);
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let test_bad_length =
+        submit_transaction_requests(validator_ips_and_keys.clone(), generic_msg, one).await;
+    for res in test_bad_length {
+        assert_eq!(res.unwrap().text().await.unwrap(), "Prehashed messages must be 32 bytes long");
+    }
     clean_tests();
 }
 
 #[tokio::test]
 #[serial]
-async fn test_mutiple_confirm_done() {
+async fn test_sign_derivation_path_not_accepted() {
     initialize_test_logger().await;
     clean_tests();
 
-    let alice = AccountKeyring::Alice;
-    let bob = AccountKeyring::Bob;
-
-    let alice_program = AccountKeyring::Charlie;
-    let program_manager = AccountKeyring::Dave;
+    let one = AccountKeyring::Dave;
+    let two = AccountKeyring::Two;
 
-    let cxt = test_context_stationary().await;
-    let api = get_api(&cxt.node_proc.ws_url).await.unwrap();
-    let rpc = get_rpc(&cxt.node_proc.ws_url).await.unwrap();
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_context_stationary().await;
+    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
 
+    // A program which does not accept derivation paths
     let program_hash = store_program(
-        &api,
+        &entropy_api,
         &rpc,
-        &program_manager.pair(),
+        &two.pair(),
         TEST_PROGRAM_WASM_BYTECODE.to_owned(),
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
-
-    put_register_request_on_chain(
-        &api,
+    update_programs(
+        &entropy_api,
         &rpc,
-        &alice,
-        alice_program.to_account_id().into(),
-        BoundedVec(vec![ProgramInstance { program_pointer: program_hash, program_config: vec![] }]),
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
     )
-    .await;
+    .await
+    .unwrap();
 
-    put_register_request_on_chain(
-        &api,
-        &rpc,
-        &bob,
-        alice_program.to_account_id().into(),
-        BoundedVec(vec![ProgramInstance { program_pointer: program_hash, program_config: vec![] }]),
-    )
-    .await;
+    let (_validators_info, mut generic_msg, validator_ips_and_keys) =
+        get_sign_tx_data(&entropy_api, &rpc, hex::encode(PREIMAGE_SHOULD_SUCCEED)).await;
+    generic_msg.derivation_path = Some("m/0/1".to_string());
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
 
-    let (signer_alice, _) = get_signer_and_x25519_secret_from_mnemonic(DEFAULT_MNEMONIC).unwrap();
+    let test_not_accepted =
+        submit_transaction_requests(validator_ips_and_keys, generic_msg, one).await;
+    for res in test_not_accepted {
+        assert_eq!(
+            res.unwrap().text().await.unwrap(),
+            format!("Program {} does not accept derivation paths", program_hash)
+        );
+    }
+    clean_tests();
+}
 
-    confirm_registered(
-        &api,
+#[tokio::test]
+#[serial]
+async fn test_sign_sha1_fails() {
+    initialize_test_logger().await;
+    clean_tests();
+
+    let one = AccountKeyring::Dave;
+    let two = AccountKeyring::Two;
+
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let substrate_context = test_context_stationary().await;
+    let entropy_api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+
+    let program_hash = store_program(
+        &entropy_api,
         &rpc,
-        alice.to_account_id().into(),
-        &signer_alice,
-        DEFAULT_VERIFYING_KEY.to_vec(),
-        0u32,
+        &two.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
-    confirm_registered(
-        &api,
+    update_programs(
+        &entropy_api,
         &rpc,
-        bob.to_account_id().into(),
-        &signer_alice,
-        DEFAULT_VERIFYING_KEY.to_vec(),
-        1u32,
+        DAVE_VERIFYING_KEY.to_vec(),
+        &one.pair(),
+        OtherBoundedVec(vec![OtherProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }]),
     )
     .await
     .unwrap();
-    check_has_confirmation(&api, &rpc, &alice.pair()).await;
-    check_has_confirmation(&api, &rpc, &bob.pair()).await;
+
+    let (_validators_info, mut generic_msg, validator_ips_and_keys) =
+        get_sign_tx_data(&entropy_api, &rpc, hex::encode(PREIMAGE_SHOULD_SUCCEED)).await;
+    generic_msg.hash = HashingAlgorithm::Sha1;
+    generic_msg.block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+
+    // SHA-1 gives a 20 byte hash, so the request is rejected rather than signing a padded hash
+    let test_sha1 = submit_transaction_requests(validator_ips_and_keys, generic_msg, one).await;
+    for res in test_sha1 {
+        assert_eq!(
+            res.unwrap().text().await.unwrap(),
+            "SHA-1 gives a 20 byte hash, which cannot be signed"
+        );
+    }
     clean_tests();
 }
 
@@ -1379,24 +2698,38 @@ async fn test_increment_or_wipe_request_limit() {
 
     let request_limit_query = entropy::storage().parameters().request_limit();
     let request_limit = query_chain(&api, &rpc, request_limit_query, None).await.unwrap().unwrap();
+    let rate_limit = RateLimit { max_requests: request_limit, window_blocks: 1 };
 
     // no error
     assert!(request_limit_check(
         &rpc,
         &kv_store,
         hex::encode(DAVE_VERIFYING_KEY.to_vec()),
-        request_limit
+        rate_limit,
+        1
     )
     .await
     .is_ok());
 
+    // a batch bigger than the request limit is rejected outright
+    let err_batch_too_big = request_limit_check(
+        &rpc,
+        &kv_store,
+        hex::encode(DAVE_VERIFYING_KEY.to_vec()),
+        rate_limit,
+        request_limit + 1,
+    )
+    .await
+    .map_err(|e| e.to_string());
+    assert_eq!(err_batch_too_big, Err("Too many requests - wait a block".to_string()));
+
     // run up the request check to one less then max (to check integration)
     for _ in 0..request_limit {
         increment_or_wipe_request_limit(
             &rpc,
             &kv_store,
             hex::encode(DAVE_VERIFYING_KEY.to_vec()),
-            request_limit,
+            rate_limit,
         )
         .await
         .unwrap();
@@ -1406,7 +2739,8 @@ async fn test_increment_or_wipe_request_limit() {
         &rpc,
         &kv_store,
         hex::encode(DAVE_VERIFYING_KEY.to_vec()),
-        request_limit,
+        rate_limit,
+        1,
     )
     .await
     .map_err(|e| e.to_string());
@@ -1415,6 +2749,178 @@ async fn test_increment_or_wipe_request_limit() {
     clean_tests();
 }
 
+#[tokio::test]
+#[serial]
+async fn test_per_account_rate_limit() {
+    initialize_test_logger().await;
+    clean_tests();
+    let one = AccountKeyring::Dave;
+    let substrate_context = test_context_stationary().await;
+    let api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+    let kv_store = load_kv_store(&None, None).await;
+
+    // Accounts without a rate limit of their own fall back to the request limit, per block
+    let request_limit_query = entropy::storage().parameters().request_limit();
+    let request_limit = query_chain(&api, &rpc, request_limit_query, None).await.unwrap().unwrap();
+    assert_eq!(
+        get_rate_limit(&api, &rpc, DAVE_VERIFYING_KEY.to_vec()).await.unwrap(),
+        RateLimit { max_requests: request_limit, window_blocks: 1 }
+    );
+
+    let rate_limit = RateLimit { max_requests: 2, window_blocks: 3 };
+    set_rate_limit(&api, &rpc, DAVE_VERIFYING_KEY.to_vec(), &one.pair(), Some(rate_limit))
+        .await
+        .unwrap();
+    assert_eq!(get_rate_limit(&api, &rpc, DAVE_VERIFYING_KEY.to_vec()).await.unwrap(), rate_limit);
+
+    // Start at the beginning of a window so that it does not end part way through the test
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let next_window = rate_limit.window_start(block_number) + rate_limit.window_blocks;
+    run_to_block(&rpc, next_window).await;
+
+    for _ in 0..rate_limit.max_requests {
+        request_limit_check(&rpc, &kv_store, hex::encode(DAVE_VERIFYING_KEY), rate_limit, 1)
+            .await
+            .unwrap();
+        increment_or_wipe_request_limit(
+            &rpc,
+            &kv_store,
+            hex::encode(DAVE_VERIFYING_KEY),
+            rate_limit,
+        )
+        .await
+        .unwrap();
+    }
+
+    // The limit holds across blocks within the window
+    run_to_block(&rpc, next_window + 1).await;
+    let err_too_many_requests =
+        request_limit_check(&rpc, &kv_store, hex::encode(DAVE_VERIFYING_KEY), rate_limit, 1)
+            .await
+            .map_err(|e| e.to_string());
+    assert_eq!(err_too_many_requests, Err("Too many requests - wait a block".to_string()));
+
+    // And is reset once the next window starts
+    run_to_block(&rpc, next_window + rate_limit.window_blocks).await;
+    assert!(request_limit_check(&rpc, &kv_store, hex::encode(DAVE_VERIFYING_KEY), rate_limit, 1)
+        .await
+        .is_ok());
+
+    // Removing the rate limit falls back to the request limit again
+    set_rate_limit(&api, &rpc, DAVE_VERIFYING_KEY.to_vec(), &one.pair(), None).await.unwrap();
+    assert_eq!(
+        get_rate_limit(&api, &rpc, DAVE_VERIFYING_KEY.to_vec()).await.unwrap(),
+        RateLimit { max_requests: request_limit, window_blocks: 1 }
+    );
+
+    clean_tests();
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deregister() {
+    initialize_test_logger().await;
+    clean_tests();
+    let one = AccountKeyring::Dave;
+    let cxt = test_context_stationary().await;
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+    let api = get_api(&cxt.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&cxt.node_proc.ws_url).await.unwrap();
+    let client = reqwest::Client::new();
+
+    // A message which does not match what the chain has recorded is rejected
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let invalid_message = OcwMessageDeregister {
+        block_number: block_number.saturating_sub(1),
+        verifying_keys: vec![DAVE_VERIFYING_KEY.to_vec()],
+    };
+    let response = client
+        .post("http://127.0.0.1:3002/user/deregister")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3002, &invalid_message.encode()))
+        .body(invalid_message.encode())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.text().await.unwrap(), "Data is not verifiable");
+
+    deregister(&api, &rpc, DAVE_VERIFYING_KEY.to_vec(), &one.pair()).await.unwrap();
+    let deregistered_query =
+        entropy::storage().registry().registered(BoundedVec(DAVE_VERIFYING_KEY.to_vec()));
+    assert!(query_chain(&api, &rpc, deregistered_query, None).await.unwrap().is_none());
+
+    // Find the block the account was deregistered in
+    let mut deregistration_block = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    loop {
+        let deregistrations_query =
+            entropy::storage().registry().deregistrations(deregistration_block);
+        let deregistrations =
+            query_chain(&api, &rpc, deregistrations_query, None).await.unwrap().unwrap_or_default();
+        if !deregistrations.is_empty() {
+            break;
+        }
+        deregistration_block -= 1;
+    }
+    let onchain_message = OcwMessageDeregister {
+        block_number: deregistration_block,
+        verifying_keys: vec![DAVE_VERIFYING_KEY.to_vec()],
+    };
+
+    // Alice's threshold server gets the message from the propagation pallet
+    run_to_block(&rpc, deregistration_block + 1).await;
+    let response_results = join_all(
+        vec![3002, 3003]
+            .iter()
+            .map(|port| {
+                client
+                    .post(format!("http://127.0.0.1:{}/user/deregister", port))
+                    .header(
+                        OCW_SIGNATURE_HEADER,
+                        sign_ocw_message(*port, &onchain_message.encode()),
+                    )
+                    .body(onchain_message.clone().encode())
+                    .send()
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await;
+    for response_result in response_results {
+        assert_eq!(response_result.unwrap().text().await.unwrap(), "");
+    }
+
+    // The keyshare is gone, so there is nothing to read back
+    let get_query = UnsafeQuery::new(hex::encode(DAVE_VERIFYING_KEY.to_vec()), vec![]).to_json();
+    let get_result = client
+        .post("http://127.0.0.1:3002/unsafe/get")
+        .header("Content-Type", "application/json")
+        .body(get_query)
+        .send()
+        .await;
+    assert!(get_result.map(|response| !response.status().is_success()).unwrap_or(true));
+
+    // wait for the deletions to be confirmed on chain
+    let mut confirmed = false;
+    for _ in 0..45 {
+        std::thread::sleep(std::time::Duration::from_millis(1000));
+        let deregistering_query =
+            entropy::storage().registry().deregistering(BoundedVec(DAVE_VERIFYING_KEY.to_vec()));
+        let deregistering = query_chain(&api, &rpc, deregistering_query, None).await.unwrap();
+        confirmed = match deregistering {
+            Some(deregistering_details) => {
+                deregistering_details.confirmations.contains(&TSS_ACCOUNTS[1])
+                    && deregistering_details.confirmations.contains(&TSS_ACCOUNTS[2])
+            },
+            None => true,
+        };
+        if confirmed {
+            break;
+        }
+    }
+    assert!(confirmed, "Keyshare deletion was not confirmed on chain");
+
+    clean_tests();
+}
+
 pub async fn submit_transaction_requests(
     validator_urls_and_keys: Vec<(String, [u8; 32])>,
     signature_request: UserSignatureRequest,
@@ -1445,12 +2951,74 @@ pub async fn submit_transaction_requests(
     .await
 }
 
+pub async fn submit_async_transaction_requests(
+    validator_urls_and_keys: Vec<(String, [u8; 32])>,
+    signature_request: UserSignatureRequest,
+    keyring: Sr25519Keyring,
+) -> Vec<std::result::Result<reqwest::Response, reqwest::Error>> {
+    let mock_client = reqwest::Client::new();
+    join_all(
+        validator_urls_and_keys
+            .iter()
+            .map(|validator_tuple| async {
+                let signed_message = EncryptedSignedMessage::new(
+                    &keyring.pair(),
+                    serde_json::to_vec(&signature_request.clone()).unwrap(),
+                    &validator_tuple.1,
+                    &[],
+                )
+                .unwrap();
+                let url = format!("http://{}/user/sign_tx_async", validator_tuple.0.clone());
+                mock_client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(serde_json::to_string(&signed_message).unwrap())
+                    .send()
+                    .await
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await
+}
+
+pub async fn submit_batch_requests(
+    validator_urls_and_keys: Vec<(String, [u8; 32])>,
+    batch_request: UserSignatureBatchRequest,
+    keyring: Sr25519Keyring,
+) -> Vec<std::result::Result<reqwest::Response, reqwest::Error>> {
+    let mock_client = reqwest::Client::new();
+    join_all(
+        validator_urls_and_keys
+            .iter()
+            .map(|validator_tuple| async {
+                let signed_message = EncryptedSignedMessage::new(
+                    &keyring.pair(),
+                    serde_json::to_vec(&batch_request.clone()).unwrap(),
+                    &validator_tuple.1,
+                    &[],
+                )
+                .unwrap();
+                let url = format!("http://{}/user/sign_batch", validator_tuple.0.clone());
+                mock_client
+                    .post(url)
+                    .header("Content-Type", "application/json")
+                    .body(serde_json::to_string(&signed_message).unwrap())
+                    .send()
+                    .await
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await
+}
+
 pub async fn get_sign_tx_data(
     api: &OnlineClient<EntropyConfig>,
     rpc: &LegacyRpcMethods<EntropyConfig>,
     message: String,
 ) -> (Vec<ValidatorInfo>, UserSignatureRequest, Vec<(String, [u8; 32])>) {
-    let validators_info = get_signers_from_chain(api, rpc).await.unwrap();
+    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number;
+    let validators_info =
+        get_signers_from_chain(api, rpc, DAVE_VERIFYING_KEY.to_vec(), block_number).await.unwrap();
     let generic_msg = UserSignatureRequest {
         message,
         auxilary_data: Some(vec![
@@ -1461,6 +3029,9 @@ pub async fn get_sign_tx_data(
         block_number: 0,
         hash: HashingAlgorithm::Keccak,
         signature_verifying_key: DAVE_VERIFYING_KEY.to_vec(),
+        signing_mode: SigningMode::Ecdsa,
+        derivation_path: None,
+        callback_url: None,
     };
 
     let validator_ips_and_keys =
@@ -15,27 +15,39 @@
 
 use crate::{
     chain_api::{
-        entropy::{self},
+        entropy::{
+            self,
+            runtime_types::pallet_staking_extension::pallet::{ReshareInfo, RotationOutcome},
+        },
         get_api, get_rpc, EntropyConfig,
     },
-    get_signer_and_x25519_secret,
+    get_signer, get_signer_and_x25519_secret,
     helpers::{
-        launch::FORBIDDEN_KEYS,
-        substrate::{get_stash_address, get_validators_info, query_chain, submit_transaction},
+        launch::{FORBIDDEN_KEYS, LATEST_BLOCK_NUMBER_RESHARE, NEXT_NETWORK_PARENT_KEY},
+        substrate::{
+            get_stash_address, get_validators_info, query_chain, submit_transaction,
+            verify_ocw_signature,
+        },
     },
     signing_client::{protocol_transport::open_protocol_connections, ProtocolErr},
     validator::errors::ValidatorErr,
     AppState,
 };
-use axum::{body::Bytes, extract::State, http::StatusCode};
-use entropy_kvdb::kv_manager::helpers::serialize as key_serialize;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use entropy_kvdb::kv_manager::{helpers::serialize as key_serialize, KvManager};
 pub use entropy_protocol::{
     decode_verifying_key,
     errors::ProtocolExecutionErr,
     execute_protocol::{execute_protocol_generic, Channels, PairWrapper},
     KeyParams, KeyShareWithAuxInfo, Listener, PartyId, SessionId, ValidatorInfo,
 };
-use entropy_shared::{OcwMessageReshare, NETWORK_PARENT_KEY, SETUP_TIMEOUT_SECONDS};
+use entropy_shared::{
+    OcwMessageReshare, OcwMessageSignerRotation, NETWORK_PARENT_KEY, SETUP_TIMEOUT_SECONDS,
+};
 use parity_scale_codec::{Decode, Encode};
 use rand_core::OsRng;
 use sp_core::Pair;
@@ -45,8 +57,8 @@ use subxt::{
     OnlineClient,
 };
 use synedrion::{
-    make_key_resharing_session, sessions::SessionId as SynedrionSessionId, KeyResharingInputs,
-    NewHolder, OldHolder,
+    ecdsa::VerifyingKey, make_key_resharing_session, sessions::SessionId as SynedrionSessionId,
+    KeyResharingInputs, NewHolder, OldHolder, ThresholdKeyShare,
 };
 use tokio::time::timeout;
 
@@ -54,32 +66,32 @@ use tokio::time::timeout;
 ///
 /// The HTTP request takes a Parity SCALE encoded [OcwMessageReshare] which indicates which validator is joining
 ///
-/// This will trigger the key reshare process.
+/// The message must be signed with our validator's propagation session key.
+///
+/// This will trigger the key reshare process. The new key share is kept aside until the signer
+/// rotation is final, see [rotate_network_key].
 #[tracing::instrument(skip_all)]
 pub async fn new_reshare(
     State(app_state): State<AppState>,
+    headers: HeaderMap,
     encoded_data: Bytes,
 ) -> Result<StatusCode, ValidatorErr> {
     let data = OcwMessageReshare::decode(&mut encoded_data.as_ref())?;
-    // TODO: validate message came from chain (check reshare block # against current block number) see #941
 
     let api = get_api(&app_state.configuration.endpoint).await?;
     let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let (signer, x25519_secret_key) = get_signer_and_x25519_secret(&app_state.kv_store)
+        .await
+        .map_err(|e| ValidatorErr::UserError(e.to_string()))?;
+    verify_ocw_signature(&api, &rpc, signer.account_id(), &headers, &encoded_data).await?;
 
-    let signers_query = entropy::storage().staking_extension().signers();
-    let signers = query_chain(&api, &rpc, signers_query, None)
-        .await?
-        .ok_or_else(|| ValidatorErr::ChainFetch("Error getting signers"))?;
-
-    let next_signers_query = entropy::storage().staking_extension().signers();
-    let next_signers = query_chain(&api, &rpc, next_signers_query, None)
-        .await?
-        .ok_or_else(|| ValidatorErr::ChainFetch("Error getting next signers"))?;
+    let reshare_data = validate_new_reshare(&data, &api, &rpc, &app_state.kv_store).await?;
+    let reshare_block_number = reshare_data.block_number;
 
-    let validators_info = get_validators_info(&api, &rpc, next_signers)
+    let old_holders_info = get_validators_info(&api, &rpc, reshare_data.old_signers)
         .await
         .map_err(|e| ValidatorErr::UserError(e.to_string()))?;
-    let (signer, x25519_secret_key) = get_signer_and_x25519_secret(&app_state.kv_store)
+    let new_holders_info = get_validators_info(&api, &rpc, reshare_data.new_signers)
         .await
         .map_err(|e| ValidatorErr::UserError(e.to_string()))?;
 
@@ -99,54 +111,47 @@ pub async fn new_reshare(
     )
     .map_err(|e| ValidatorErr::VerifyingKeyError(e.to_string()))?;
 
-    let is_proper_signer = &validators_info
-        .iter()
-        .any(|validator_info| validator_info.tss_account == *signer.account_id());
+    let old_holders: BTreeSet<PartyId> =
+        old_holders_info.iter().cloned().map(|x| PartyId::new(x.tss_account)).collect();
+    let new_holders: BTreeSet<PartyId> =
+        new_holders_info.iter().cloned().map(|x| PartyId::new(x.tss_account)).collect();
 
-    if !is_proper_signer {
+    // Both the outgoing and the incoming signers take part in the reshare
+    let mut validators_info = old_holders_info;
+    for validator_info in new_holders_info {
+        if !validators_info.iter().any(|v| v.tss_account == validator_info.tss_account) {
+            validators_info.push(validator_info);
+        }
+    }
+    let party_ids: BTreeSet<PartyId> = old_holders.union(&new_holders).cloned().collect();
+
+    // A key share from an earlier rotation must be kept or discarded before it is reshared
+    reconcile_network_key(&app_state.kv_store, &api, &rpc, &signer).await?;
+
+    let my_party_id = PartyId::new(signer.account_id().clone());
+    if !party_ids.contains(&my_party_id) {
         return Ok(StatusCode::MISDIRECTED_REQUEST);
     }
-    // get old key if have it
-    let my_stash_address = get_stash_address(&api, &rpc, signer.account_id())
-        .await
-        .map_err(|e| ValidatorErr::UserError(e.to_string()))?;
-    let old_holder: Option<OldHolder<KeyParams, PartyId>> =
-        if data.new_signer == my_stash_address.encode() {
-            None
-        } else {
-            let kvdb_result = app_state.kv_store.kv().get(&hex::encode(NETWORK_PARENT_KEY)).await?;
-            let key_share: KeyShareWithAuxInfo =
-                entropy_kvdb::kv_manager::helpers::deserialize(&kvdb_result)
-                    .ok_or_else(|| ValidatorErr::KvDeserialize("Failed to load KeyShare".into()))?;
-            Some(OldHolder { key_share: key_share.0 })
-        };
-    let party_ids: BTreeSet<PartyId> =
-        validators_info.iter().cloned().map(|x| PartyId::new(x.tss_account)).collect();
 
-    let old_holders_info = get_validators_info(&api, &rpc, signers)
-        .await
-        .map_err(|e| ValidatorErr::UserError(e.to_string()))?;
-    let old_holders: BTreeSet<PartyId> =
-        old_holders_info.iter().cloned().map(|x| PartyId::new(x.tss_account)).collect();
+    let old_key_share = if old_holders.contains(&my_party_id) {
+        let kvdb_result = app_state.kv_store.kv().get(&hex::encode(NETWORK_PARENT_KEY)).await?;
+        let key_share: KeyShareWithAuxInfo =
+            entropy_kvdb::kv_manager::helpers::deserialize(&kvdb_result)
+                .ok_or_else(|| ValidatorErr::KvDeserialize("Failed to load KeyShare".into()))?;
+        Some(key_share.0)
+    } else {
+        None
+    };
 
-    let new_holder = NewHolder {
-        verifying_key: decoded_verifying_key,
-        // TODO: get from chain see #941
-        old_threshold: party_ids.len(),
+    let inputs = make_reshare_inputs(
+        &my_party_id,
+        old_key_share,
+        decoded_verifying_key,
         old_holders,
-    };
-    let key_info_query = entropy::storage().parameters().signers_info();
-    let threshold = query_chain(&api, &rpc, key_info_query, None)
-        .await?
-        .ok_or_else(|| ValidatorErr::ChainFetch("Failed to get signers info"))?
-        .threshold;
-
-    let inputs = KeyResharingInputs {
-        old_holder,
-        new_holder: Some(new_holder),
-        new_holders: party_ids.clone(),
-        new_threshold: threshold as usize,
-    };
+        reshare_data.old_threshold as usize,
+        new_holders,
+        reshare_data.new_threshold as usize,
+    );
 
     let session_id = SessionId::Reshare { verifying_key, block_number: data.block_number };
     let account_id = AccountId32(signer.signer().public().0);
@@ -201,24 +206,227 @@ pub async fn new_reshare(
     let new_key_share = execute_protocol_generic(channels, session, session_id_hash)
         .await
         .map_err(|_| ValidatorErr::ProtocolError("Error executing protocol".to_string()))?
-        .0
-        .ok_or(ValidatorErr::NoOutputFromReshareProtocol)?;
+        .0;
+    // An old holder which is leaving the signers gets no new key share. It keeps its old one until
+    // the rotation is final.
+    let Some(new_key_share) = new_key_share else {
+        return Ok(StatusCode::OK);
+    };
     let serialized_key_share = key_serialize(&new_key_share)
         .map_err(|_| ProtocolErr::KvSerialize("Kv Serialize Error".to_string()))?;
-    let network_parent_key = hex::encode(NETWORK_PARENT_KEY);
-    // TODO: should this be a two step process? see # https://github.com/entropyxyz/entropy-core/issues/968
-    if app_state.kv_store.kv().exists(&network_parent_key).await? {
-        app_state.kv_store.kv().delete(&network_parent_key).await?
-    };
 
-    let reservation = app_state.kv_store.kv().reserve_key(network_parent_key).await?;
-    app_state.kv_store.kv().put(reservation, serialized_key_share.clone()).await?;
+    // The old key share is still needed if the rotation gets rolled back, so it is only replaced
+    // once the rotation is final. It is stored with the block the reshare started in, which is how
+    // the chain records the outcome of the rotation.
+    if app_state.kv_store.kv().exists(NEXT_NETWORK_PARENT_KEY).await? {
+        app_state.kv_store.kv().delete(NEXT_NETWORK_PARENT_KEY).await?
+    };
+    let reservation =
+        app_state.kv_store.kv().reserve_key(NEXT_NETWORK_PARENT_KEY.to_string()).await?;
+    app_state
+        .kv_store
+        .kv()
+        .put(reservation, (reshare_block_number, serialized_key_share).encode())
+        .await?;
 
     // TODO: Error handling really complex needs to be thought about.
     confirm_key_reshare(&api, &rpc, &signer).await?;
     Ok(StatusCode::OK)
 }
 
+/// Validates a reshare message from the off-chain worker.
+///
+/// It checks that:
+/// - the message is from the previous block and has not been seen before
+/// - the data matches the reshare recorded on-chain as of that block
+pub async fn validate_new_reshare(
+    chain_data: &OcwMessageReshare,
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    kv_manager: &KvManager,
+) -> Result<ReshareInfo<AccountId32, u32>, ValidatorErr> {
+    let last_block_number_recorded = kv_manager.kv().get(LATEST_BLOCK_NUMBER_RESHARE).await?;
+    if u32::from_be_bytes(
+        last_block_number_recorded
+            .try_into()
+            .map_err(|_| ValidatorErr::Conversion("Block number conversion"))?,
+    ) >= chain_data.block_number
+    {
+        return Err(ValidatorErr::RepeatedData);
+    }
+
+    let latest_block_number = rpc
+        .chain_get_header(None)
+        .await?
+        .ok_or_else(|| ValidatorErr::OptionUnwrapError("Failed to get block number".to_string()))?
+        .number;
+
+    // we subtract 1 as the message info is coming from the previous block
+    if latest_block_number.saturating_sub(1) != chain_data.block_number {
+        return Err(ValidatorErr::StaleData);
+    }
+
+    let block_hash = rpc.chain_get_block_hash(Some(chain_data.block_number.into())).await?;
+    let reshare_data_query = entropy::storage().staking_extension().reshare_data();
+    let reshare_data = query_chain(api, rpc, reshare_data_query, block_hash)
+        .await?
+        .ok_or_else(|| ValidatorErr::ChainFetch("Error getting reshare data"))?;
+
+    // The reshare is started in the block after the one it was scheduled in
+    if reshare_data.block_number != chain_data.block_number.saturating_add(1)
+        || reshare_data.new_signer != chain_data.new_signer
+    {
+        return Err(ValidatorErr::InvalidData);
+    }
+
+    kv_manager.kv().delete(LATEST_BLOCK_NUMBER_RESHARE).await?;
+    let reservation = kv_manager.kv().reserve_key(LATEST_BLOCK_NUMBER_RESHARE.to_string()).await?;
+    kv_manager.kv().put(reservation, chain_data.block_number.to_be_bytes().to_vec()).await?;
+    Ok(reshare_data)
+}
+
+/// Builds the inputs to the key resharing protocol for one party.
+///
+/// `old_key_share` must be given if the party is one of the `old_holders`. A party which is only
+/// in `old_holders` gets no new key share from the reshare.
+pub fn make_reshare_inputs(
+    party_id: &PartyId,
+    old_key_share: Option<ThresholdKeyShare<KeyParams, PartyId>>,
+    verifying_key: VerifyingKey,
+    old_holders: BTreeSet<PartyId>,
+    old_threshold: usize,
+    new_holders: BTreeSet<PartyId>,
+    new_threshold: usize,
+) -> KeyResharingInputs<KeyParams, PartyId> {
+    let new_holder = if new_holders.contains(party_id) {
+        Some(NewHolder { verifying_key, old_threshold, old_holders })
+    } else {
+        None
+    };
+    KeyResharingInputs {
+        old_holder: old_key_share.map(|key_share| OldHolder { key_share }),
+        new_holder,
+        new_holders,
+        new_threshold,
+    }
+}
+
+/// HTTP POST endpoint called by the off-chain worker (propagation pallet) once a signer rotation has
+/// finished.
+///
+/// The HTTP request takes a Parity SCALE encoded [OcwMessageSignerRotation], which must be signed
+/// with our validator's propagation session key. The message only prompts the threshold server to
+/// reconcile its network parent key shares with the chain, see [reconcile_network_key].
+#[tracing::instrument(skip_all, fields(block_number))]
+pub async fn rotate_network_key(
+    State(app_state): State<AppState>,
+    headers: HeaderMap,
+    encoded_data: Bytes,
+) -> Result<StatusCode, ValidatorErr> {
+    let data = OcwMessageSignerRotation::decode(&mut encoded_data.as_ref())?;
+    tracing::Span::current().record("block_number", data.block_number);
+
+    let api = get_api(&app_state.configuration.endpoint).await?;
+    let rpc = get_rpc(&app_state.configuration.endpoint).await?;
+    let signer = get_signer(&app_state.kv_store).await?;
+    verify_ocw_signature(&api, &rpc, signer.account_id(), &headers, &encoded_data).await?;
+
+    validate_signer_rotation(&data, &api, &rpc).await?;
+    reconcile_network_key(&app_state.kv_store, &api, &rpc, &signer).await?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Checks that a signer rotation message from the off-chain worker matches how the chain recorded
+/// the latest rotation finishing
+async fn validate_signer_rotation(
+    chain_data: &OcwMessageSignerRotation,
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+) -> Result<(), ValidatorErr> {
+    let reshare_data_query = entropy::storage().staking_extension().reshare_data();
+    let reshare_data = query_chain(api, rpc, reshare_data_query, None)
+        .await?
+        .ok_or_else(|| ValidatorErr::ChainFetch("Error getting reshare data"))?;
+    let finished_rotation_query =
+        entropy::storage().staking_extension().finished_rotations(reshare_data.block_number);
+    let finished_rotation = query_chain(api, rpc, finished_rotation_query, None)
+        .await?
+        .ok_or_else(|| ValidatorErr::ChainFetch("No signer rotation has finished"))?;
+
+    if finished_rotation.block_number != chain_data.block_number
+        || matches!(finished_rotation.outcome, RotationOutcome::Rotated) != chain_data.rotated
+    {
+        return Err(ValidatorErr::InvalidData);
+    }
+    Ok(())
+}
+
+/// Brings the network parent key shares held by this threshold server in line with the signer
+/// rotations recorded on chain.
+///
+/// A key share from a reshare replaces the current one if its rotation went through, and is
+/// discarded if the rotation was rolled back or has been superseded. A validator which holds a key
+/// share but is no longer a signer deletes it once no rotation is in progress.
+///
+/// This is done both when told about a rotation finishing and before taking part in a reshare, so
+/// a threshold server which misses a message still ends up with the right key share.
+pub async fn reconcile_network_key(
+    kv_manager: &KvManager,
+    api: &OnlineClient<EntropyConfig>,
+    rpc: &LegacyRpcMethods<EntropyConfig>,
+    signer: &PairSigner<EntropyConfig, sr25519::Pair>,
+) -> Result<(), ValidatorErr> {
+    let kv = kv_manager.kv();
+    let network_parent_key = hex::encode(NETWORK_PARENT_KEY);
+
+    let next_signers_query = entropy::storage().staking_extension().next_signers();
+    let rotation_in_progress = query_chain(api, rpc, next_signers_query, None).await?.is_some();
+
+    if kv.exists(NEXT_NETWORK_PARENT_KEY).await? {
+        let (reshare_block_number, new_key_share) =
+            <(u32, Vec<u8>)>::decode(&mut kv.get(NEXT_NETWORK_PARENT_KEY).await?.as_ref())?;
+        let finished_rotation_query =
+            entropy::storage().staking_extension().finished_rotations(reshare_block_number);
+        match query_chain(api, rpc, finished_rotation_query, None).await? {
+            Some(finished_rotation) => {
+                if matches!(finished_rotation.outcome, RotationOutcome::Rotated) {
+                    if kv.exists(&network_parent_key).await? {
+                        kv.delete(&network_parent_key).await?;
+                    }
+                    let reservation = kv.reserve_key(network_parent_key.clone()).await?;
+                    kv.put(reservation, new_key_share).await?;
+                }
+                kv.delete(NEXT_NETWORK_PARENT_KEY).await?;
+            },
+            None => {
+                let reshare_data_query = entropy::storage().staking_extension().reshare_data();
+                let reshare_data = query_chain(api, rpc, reshare_data_query, None)
+                    .await?
+                    .ok_or_else(|| ValidatorErr::ChainFetch("Error getting reshare data"))?;
+                // Unless the rotation is still in progress its outcome has been pruned, meaning
+                // a later rotation went through without us
+                if !rotation_in_progress || reshare_data.block_number != reshare_block_number {
+                    kv.delete(NEXT_NETWORK_PARENT_KEY).await?;
+                }
+            },
+        }
+    }
+
+    if !rotation_in_progress && kv.exists(&network_parent_key).await? {
+        let stash_address = get_stash_address(api, rpc, signer.account_id()).await?;
+        let signers_query = entropy::storage().staking_extension().signers();
+        let signers = query_chain(api, rpc, signers_query, None)
+            .await?
+            .ok_or_else(|| ValidatorErr::ChainFetch("Error getting signers"))?;
+        if !signers.contains(&stash_address) {
+            kv.delete(&network_parent_key).await?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Confirms that a validator has succefully reshared.
 pub async fn confirm_key_reshare(
     api: &OnlineClient<EntropyConfig>,
@@ -12,32 +12,44 @@
 //
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
-use super::api::{check_balance_for_fees, check_forbidden_key};
+use super::api::{check_balance_for_fees, check_forbidden_key, make_reshare_inputs};
 use crate::{
     chain_api::{
         entropy::{self, runtime_types::bounded_collections::bounded_vec},
         get_api, get_rpc, EntropyConfig,
     },
     helpers::{
-        launch::{development_mnemonic, ValidatorName, FORBIDDEN_KEYS},
-        substrate::submit_transaction,
-        tests::{initialize_test_logger, spawn_testing_validators, unsafe_get},
+        launch::{development_mnemonic, ValidatorName, FORBIDDEN_KEYS, NEXT_NETWORK_PARENT_KEY},
+        substrate::{query_chain, submit_transaction},
+        tests::{
+            initialize_test_logger, run_to_block, sign_ocw_message, spawn_testing_validators,
+            unsafe_get,
+        },
         validator::get_signer_and_x25519_secret_from_mnemonic,
     },
     validator::errors::ValidatorErr,
 };
 use entropy_kvdb::clean_tests;
-use entropy_shared::{OcwMessageReshare, EVE_VERIFYING_KEY, MIN_BALANCE, NETWORK_PARENT_KEY};
+use entropy_protocol::{KeyParams, PartyId};
+use entropy_shared::{
+    OcwMessageReshare, OcwMessageSignerRotation, EVE_VERIFYING_KEY, MIN_BALANCE,
+    NETWORK_PARENT_KEY, OCW_SIGNATURE_HEADER, TEST_RESHARE_BLOCK_NUMBER,
+};
 use entropy_testing_utils::{
     constants::{ALICE_STASH_ADDRESS, RANDOM_ACCOUNT},
+    create_test_keyshares::{create_test_keyshares, reshare_test_keyshares},
     substrate_context::{test_node_process_testing_state, testing_context},
 };
 use futures::future::join_all;
-use parity_scale_codec::Encode;
+use parity_scale_codec::{Decode, Encode};
 use serial_test::serial;
 use sp_keyring::AccountKeyring;
+use std::collections::BTreeSet;
 use subxt::{
-    backend::legacy::LegacyRpcMethods, ext::sp_core::sr25519, tx::PairSigner, OnlineClient,
+    backend::legacy::LegacyRpcMethods,
+    ext::sp_core::{sr25519, Pair},
+    tx::PairSigner,
+    OnlineClient,
 };
 
 #[tokio::test]
@@ -46,8 +58,6 @@ async fn test_reshare() {
     initialize_test_logger().await;
     clean_tests();
 
-    let alice = AccountKeyring::Alice;
-
     let cxt = test_node_process_testing_state(true).await;
     let (_validator_ips, _validator_ids) = spawn_testing_validators(true).await;
     let validator_ports = vec![3001, 3002, 3003];
@@ -55,22 +65,66 @@ async fn test_reshare() {
     let rpc = get_rpc(&cxt.ws_url).await.unwrap();
 
     let client = reqwest::Client::new();
-    let block_number = rpc.chain_get_header(None).await.unwrap().unwrap().number + 1;
     let mut key_shares_before = vec![];
     for port in &validator_ports {
         key_shares_before.push(unsafe_get(&client, hex::encode(NETWORK_PARENT_KEY), *port).await);
     }
 
-    let onchain_reshare_request =
-        OcwMessageReshare { new_signer: alice.public().encode(), block_number };
     setup_for_reshare(&api, &rpc).await;
 
+    // The genesis config schedules a reshare, which the chain node sends to the threshold server
+    // on port 3001 itself
+    let onchain_reshare_request =
+        OcwMessageReshare { new_signer: vec![], block_number: TEST_RESHARE_BLOCK_NUMBER - 1 };
+    run_to_block(&rpc, TEST_RESHARE_BLOCK_NUMBER).await;
+
+    // fails without a signature from the validator's off-chain worker
+    let response = client
+        .post("http://127.0.0.1:3002/validator/reshare")
+        .body(onchain_reshare_request.clone().encode())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.text().await.unwrap(),
+        "User Error: Invalid Signature: No off-chain worker signature"
+    );
+
+    // fails when signed by another validator's off-chain worker
+    let response = client
+        .post("http://127.0.0.1:3002/validator/reshare")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3003, &onchain_reshare_request.encode()))
+        .body(onchain_reshare_request.clone().encode())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(
+        response.text().await.unwrap(),
+        "User Error: Invalid Signature: Off-chain worker signature is not from our validator"
+    );
+
+    // fails with data which is not on chain
+    let unverifiable_reshare_request =
+        OcwMessageReshare { new_signer: vec![1], ..onchain_reshare_request.clone() }.encode();
+    let response = client
+        .post("http://127.0.0.1:3002/validator/reshare")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3002, &unverifiable_reshare_request))
+        .body(unverifiable_reshare_request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.text().await.unwrap(), "Data is not verifiable");
+
     let response_results = join_all(
-        validator_ports
+        [3002, 3003]
             .iter()
             .map(|port| {
                 client
                     .post(format!("http://127.0.0.1:{}/validator/reshare", port))
+                    .header(
+                        OCW_SIGNATURE_HEADER,
+                        sign_ocw_message(*port, &onchain_reshare_request.encode()),
+                    )
                     .body(onchain_reshare_request.clone().encode())
                     .send()
             })
@@ -80,11 +134,93 @@ async fn test_reshare() {
     for response_result in response_results {
         assert_eq!(response_result.unwrap().text().await.unwrap(), "");
     }
-    for i in 0..validator_ports.len() {
-        assert_ne!(
+
+    // fails with repeated data
+    let response = client
+        .post("http://127.0.0.1:3002/validator/reshare")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3002, &onchain_reshare_request.encode()))
+        .body(onchain_reshare_request.clone().encode())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.text().await.unwrap(), "Data is repeated");
+
+    // The old key shares are kept until the rotation is final
+    let mut next_key_shares = vec![];
+    for i in 1..validator_ports.len() {
+        assert_eq!(
             key_shares_before[i],
             unsafe_get(&client, hex::encode(NETWORK_PARENT_KEY), validator_ports[i]).await
         );
+        let (reshare_block_number, next_key_share) = <(u32, Vec<u8>)>::decode(
+            &mut unsafe_get(&client, NEXT_NETWORK_PARENT_KEY.to_string(), validator_ports[i])
+                .await
+                .as_ref(),
+        )
+        .unwrap();
+        assert_eq!(reshare_block_number, TEST_RESHARE_BLOCK_NUMBER);
+        assert_ne!(key_shares_before[i], next_key_share);
+        next_key_shares.push(next_key_share);
+    }
+
+    // Wait for every signer to confirm the reshare
+    for _ in 0..30 {
+        let next_signers_query = entropy::storage().staking_extension().next_signers();
+        if query_chain(&api, &rpc, next_signers_query, None).await.unwrap().is_none() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
+    let finished_rotation_query =
+        entropy::storage().staking_extension().finished_rotations(TEST_RESHARE_BLOCK_NUMBER);
+    let finished_rotation = query_chain(&api, &rpc, finished_rotation_query, None)
+        .await
+        .unwrap()
+        .expect("Rotation should have finished");
+    let signer_rotation_message =
+        OcwMessageSignerRotation { block_number: finished_rotation.block_number, rotated: true };
+
+    // A message which does not match the chain is rejected
+    let mismatched_rotation_message =
+        OcwMessageSignerRotation { rotated: false, ..signer_rotation_message.clone() };
+    let response = client
+        .post("http://127.0.0.1:3002/validator/rotate_network_key")
+        .header(OCW_SIGNATURE_HEADER, sign_ocw_message(3002, &mismatched_rotation_message.encode()))
+        .body(mismatched_rotation_message.encode())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.text().await.unwrap(), "Data is not verifiable");
+
+    // Telling a server about the rotation more than once does no harm, so this is also sent to the
+    // server on port 3001
+    let response_results = join_all(
+        validator_ports
+            .iter()
+            .map(|port| {
+                client
+                    .post(format!("http://127.0.0.1:{}/validator/rotate_network_key", port))
+                    .header(
+                        OCW_SIGNATURE_HEADER,
+                        sign_ocw_message(*port, &signer_rotation_message.encode()),
+                    )
+                    .body(signer_rotation_message.clone().encode())
+                    .send()
+            })
+            .collect::<Vec<_>>(),
+    )
+    .await;
+    for response_result in response_results {
+        assert_eq!(response_result.unwrap().text().await.unwrap(), "");
+    }
+    for i in 0..validator_ports.len() {
+        let key_share =
+            unsafe_get(&client, hex::encode(NETWORK_PARENT_KEY), validator_ports[i]).await;
+        assert_ne!(key_shares_before[i], key_share);
+        if i > 0 {
+            assert_eq!(next_key_shares[i - 1], key_share);
+        }
     }
 
     clean_tests();
@@ -112,6 +248,82 @@ async fn setup_for_reshare(
         submit_transaction(api, rpc, &tss_signer, &jump_start_confirm_request, None).await.unwrap();
     }
 }
+
+#[tokio::test]
+async fn test_reshare_between_committees() {
+    let pairs: Vec<sr25519::Pair> =
+        [AccountKeyring::Alice, AccountKeyring::Bob, AccountKeyring::Charlie, AccountKeyring::Dave]
+            .iter()
+            .map(|keyring| keyring.pair())
+            .collect();
+    let party_ids: Vec<PartyId> = pairs.iter().map(|pair| PartyId::from(pair.public())).collect();
+
+    // Alice, Bob and Charlie start with a 2 of 3 key
+    let initial_key_shares = create_test_keyshares::<KeyParams>(
+        [1; 32],
+        pairs[0].clone(),
+        pairs[1].clone(),
+        pairs[2].clone(),
+    )
+    .await;
+    let verifying_key = initial_key_shares[0].0.verifying_key();
+
+    // Dave joins, making it a 3 of 4 key
+    let old_holders: BTreeSet<PartyId> = party_ids[..3].iter().cloned().collect();
+    let new_holders: BTreeSet<PartyId> = party_ids.iter().cloned().collect();
+    let parties = pairs
+        .iter()
+        .enumerate()
+        .map(|(i, pair)| {
+            let inputs = make_reshare_inputs(
+                &party_ids[i],
+                initial_key_shares.get(i).map(|(key_share, _)| key_share.clone()),
+                verifying_key,
+                old_holders.clone(),
+                2,
+                new_holders.clone(),
+                3,
+            );
+            (pair.clone(), inputs)
+        })
+        .collect();
+    let grown_key_shares: Vec<_> = reshare_test_keyshares::<KeyParams>(parties)
+        .await
+        .into_iter()
+        .map(|key_share| key_share.expect("All parties are new holders"))
+        .collect();
+    for key_share in &grown_key_shares {
+        assert_eq!(key_share.verifying_key(), verifying_key);
+    }
+
+    // Alice leaves, making it a 2 of 3 key again
+    let old_holders = new_holders;
+    let new_holders: BTreeSet<PartyId> = party_ids[1..].iter().cloned().collect();
+    let parties = pairs
+        .iter()
+        .enumerate()
+        .map(|(i, pair)| {
+            let inputs = make_reshare_inputs(
+                &party_ids[i],
+                Some(grown_key_shares[i].clone()),
+                verifying_key,
+                old_holders.clone(),
+                3,
+                new_holders.clone(),
+                2,
+            );
+            (pair.clone(), inputs)
+        })
+        .collect();
+    let shrunk_key_shares = reshare_test_keyshares::<KeyParams>(parties).await;
+
+    // Alice gets no new key share
+    assert!(shrunk_key_shares[0].is_none());
+    for key_share in &shrunk_key_shares[1..] {
+        assert_eq!(key_share.as_ref().unwrap().verifying_key(), verifying_key);
+    }
+}
+
 #[tokio::test]
 #[should_panic = "Account does not exist, add balance"]
 async fn test_check_balance_for_fees() {
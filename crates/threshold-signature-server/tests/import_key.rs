@@ -0,0 +1,193 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use entropy_client::{
+    chain_api::{
+        entropy::runtime_types::bounded_collections::bounded_vec::BoundedVec,
+        entropy::runtime_types::pallet_registry::pallet::ProgramInstance, get_api, get_rpc,
+    },
+    client as test_client, Hasher,
+};
+use entropy_kvdb::clean_tests;
+use entropy_protocol::ThresholdSignature;
+use entropy_shared::{HashingAlgorithm, SigningMode, SIGNER_THRESHOLD, TOTAL_SIGNERS};
+use entropy_testing_utils::{
+    constants::{
+        AUXILARY_DATA_SHOULD_SUCCEED, PREIMAGE_SHOULD_SUCCEED, TEST_PROGRAM_WASM_BYTECODE,
+    },
+    spawn_testing_validators,
+    substrate_context::test_context_stationary,
+};
+use serial_test::serial;
+use sp_keyring::AccountKeyring;
+use synedrion::k256::ecdsa::{SigningKey, VerifyingKey};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+#[tokio::test]
+#[serial]
+async fn integration_test_import_key_and_sign() {
+    clean_tests();
+    let dave = AccountKeyring::Dave;
+    let request_author = AccountKeyring::One;
+
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+
+    let substrate_context = test_context_stationary().await;
+    let api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+
+    let program_pointer = test_client::store_program(
+        &api,
+        &rpc,
+        &dave.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let signing_key = SigningKey::from_slice(&[7; 32]).unwrap();
+    let (verifying_key, _registered_info) = test_client::import_key(
+        &api,
+        &rpc,
+        dave.pair(),
+        dave.to_account_id().into(),
+        BoundedVec(vec![ProgramInstance { program_pointer, program_config: vec![], fuel: None }]),
+        signing_key.clone(),
+        SIGNER_THRESHOLD,
+        TOTAL_SIGNERS,
+    )
+    .await
+    .unwrap();
+
+    // The account is registered with the verifying key of the imported key
+    assert_eq!(
+        verifying_key,
+        signing_key.verifying_key().to_encoded_point(true).as_bytes().to_vec()
+    );
+
+    // And the TSS servers can sign with it
+    let signing_report = test_client::sign(
+        &api,
+        &rpc,
+        request_author.pair(),
+        verifying_key,
+        PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Keccak,
+        Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Ecdsa,
+        None,
+    )
+    .await
+    .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Ecdsa(recoverable_signature) = signing_report.signature else {
+        panic!("Expected an ECDSA signature");
+    };
+
+    let recovery_key_from_sig = VerifyingKey::recover_from_prehash(
+        &Hasher::keccak(PREIMAGE_SHOULD_SUCCEED),
+        &recoverable_signature.signature,
+        recoverable_signature.recovery_id,
+    )
+    .unwrap();
+    assert_eq!(signing_key.verifying_key(), &recovery_key_from_sig);
+    clean_tests();
+}
+
+#[tokio::test]
+#[serial]
+async fn integration_test_export_keyshares_and_recover_key() {
+    clean_tests();
+    let dave = AccountKeyring::Dave;
+
+    let (_validator_ips, _validator_ids) = spawn_testing_validators(false).await;
+
+    let substrate_context = test_context_stationary().await;
+    let api = get_api(&substrate_context.node_proc.ws_url).await.unwrap();
+    let rpc = get_rpc(&substrate_context.node_proc.ws_url).await.unwrap();
+
+    let program_pointer = test_client::store_program(
+        &api,
+        &rpc,
+        &dave.pair(),
+        TEST_PROGRAM_WASM_BYTECODE.to_owned(),
+        vec![],
+        vec![],
+        vec![],
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let signing_key = SigningKey::from_slice(&[8; 32]).unwrap();
+    let (verifying_key, _registered_info) = test_client::import_key(
+        &api,
+        &rpc,
+        dave.pair(),
+        dave.to_account_id().into(),
+        BoundedVec(vec![ProgramInstance { program_pointer, program_config: vec![], fuel: None }]),
+        signing_key.clone(),
+        SIGNER_THRESHOLD,
+        TOTAL_SIGNERS,
+    )
+    .await
+    .unwrap();
+
+    // Keyshares cannot be exported until a recovery key is set
+    assert!(test_client::export_keyshares(&api, &rpc, verifying_key.clone(), &dave.pair())
+        .await
+        .is_err());
+
+    let recovery_secret_key = StaticSecret::from([9; 32]);
+    test_client::set_recovery_key(
+        &api,
+        &rpc,
+        verifying_key.clone(),
+        &dave.pair(),
+        Some(PublicKey::from(&recovery_secret_key).to_bytes()),
+    )
+    .await
+    .unwrap();
+
+    // Only the program modification account can export keyshares
+    let eve = AccountKeyring::Eve;
+    assert!(test_client::export_keyshares(&api, &rpc, verifying_key.clone(), &eve.pair())
+        .await
+        .is_err());
+
+    // The recovery key can only be used after the delay in the chainspec, which is a few blocks
+    let mut exported_keyshares = None;
+    for _ in 0..30 {
+        if let Ok(keyshares) =
+            test_client::export_keyshares(&api, &rpc, verifying_key.clone(), &dave.pair()).await
+        {
+            exported_keyshares = Some(keyshares);
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+    let exported_keyshares = exported_keyshares.expect("Keyshares were not exported");
+    let key_shares =
+        test_client::decrypt_exported_keyshares(&exported_keyshares, &recovery_secret_key).unwrap();
+    let recovered_key = test_client::recover_signing_key(&key_shares).unwrap();
+    assert_eq!(recovered_key, signing_key);
+    clean_tests();
+}
@@ -21,7 +21,8 @@ use entropy_client::{
     client as test_client, Hasher,
 };
 use entropy_kvdb::clean_tests;
-use entropy_shared::EVE_VERIFYING_KEY;
+use entropy_protocol::ThresholdSignature;
+use entropy_shared::{HashingAlgorithm, SigningMode, EVE_VERIFYING_KEY};
 use entropy_testing_utils::{
     constants::{
         AUXILARY_DATA_SHOULD_SUCCEED, PREIMAGE_SHOULD_SUCCEED, TEST_PROGRAM_WASM_BYTECODE,
@@ -31,7 +32,10 @@ use entropy_testing_utils::{
 };
 use serial_test::serial;
 use sp_keyring::AccountKeyring;
-use synedrion::k256::ecdsa::VerifyingKey;
+use synedrion::{
+    k256::{ecdsa::VerifyingKey, schnorr},
+    signature::hazmat::PrehashVerifier,
+};
 
 #[tokio::test]
 #[serial]
@@ -54,6 +58,8 @@ async fn integration_test_sign_public() {
         vec![],
         vec![],
         vec![],
+        false,
+        true,
     )
     .await
     .unwrap();
@@ -61,25 +67,32 @@ async fn integration_test_sign_public() {
     test_client::update_programs(
         &api,
         &rpc,
-        EVE_VERIFYING_KEY,
+        EVE_VERIFYING_KEY.to_vec(),
         &eve.pair(),
-        BoundedVec(vec![ProgramInstance { program_pointer, program_config: vec![] }]),
+        BoundedVec(vec![ProgramInstance { program_pointer, program_config: vec![], fuel: None }]),
     )
     .await
     .unwrap();
 
     let message_should_succeed_hash = Hasher::keccak(PREIMAGE_SHOULD_SUCCEED);
 
-    let recoverable_signature = test_client::sign(
+    let signing_report = test_client::sign(
         &api,
         &rpc,
         request_author.pair(),
-        EVE_VERIFYING_KEY,
+        EVE_VERIFYING_KEY.to_vec(),
         PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Keccak,
         Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Ecdsa,
+        None,
     )
     .await
     .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Ecdsa(recoverable_signature) = signing_report.signature else {
+        panic!("Expected an ECDSA signature");
+    };
 
     let recovery_key_from_sig = VerifyingKey::recover_from_prehash(
         &message_should_succeed_hash,
@@ -91,4 +104,112 @@ async fn integration_test_sign_public() {
         EVE_VERIFYING_KEY.to_vec(),
         recovery_key_from_sig.to_encoded_point(true).to_bytes().to_vec()
     );
+
+    // The same key can also be used to make a BIP-340 Schnorr signature
+    let signing_report = test_client::sign(
+        &api,
+        &rpc,
+        request_author.pair(),
+        EVE_VERIFYING_KEY.to_vec(),
+        PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Keccak,
+        Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Bip340,
+        None,
+    )
+    .await
+    .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Bip340(signature) = signing_report.signature else {
+        panic!("Expected a BIP-340 signature");
+    };
+    assert_eq!(signature.to_bytes().len(), 64);
+
+    let x_only_verifying_key = schnorr::VerifyingKey::from_bytes(&EVE_VERIFYING_KEY[1..]).unwrap();
+    assert!(x_only_verifying_key.verify_prehash(&message_should_succeed_hash, &signature).is_ok());
+
+    // Once the signing committee has made presignatures, BIP-340 signing uses one of them
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    let signing_report = test_client::sign(
+        &api,
+        &rpc,
+        request_author.pair(),
+        EVE_VERIFYING_KEY.to_vec(),
+        PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Keccak,
+        Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Bip340,
+        None,
+    )
+    .await
+    .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Bip340(presigned_signature) = signing_report.signature else {
+        panic!("Expected a BIP-340 signature");
+    };
+    assert_ne!(presigned_signature, signature);
+    assert!(x_only_verifying_key
+        .verify_prehash(&message_should_succeed_hash, &presigned_signature)
+        .is_ok());
+
+    // Sign with a child key, which should match the key derived by the client
+    let signing_report = test_client::sign(
+        &api,
+        &rpc,
+        request_author.pair(),
+        EVE_VERIFYING_KEY.to_vec(),
+        PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Keccak,
+        Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Ecdsa,
+        Some("m/0/1".to_string()),
+    )
+    .await
+    .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Ecdsa(recoverable_signature) = signing_report.signature else {
+        panic!("Expected an ECDSA signature");
+    };
+
+    let recovery_key_from_sig = VerifyingKey::recover_from_prehash(
+        &message_should_succeed_hash,
+        &recoverable_signature.signature,
+        recoverable_signature.recovery_id,
+    )
+    .unwrap();
+    let parent_verifying_key = VerifyingKey::from_sec1_bytes(&EVE_VERIFYING_KEY).unwrap();
+    assert_eq!(
+        test_client::derive_verifying_key(&parent_verifying_key, "m/0/1").unwrap(),
+        recovery_key_from_sig
+    );
+
+    // Sign with another hashing algorithm
+    let signing_report = test_client::sign(
+        &api,
+        &rpc,
+        request_author.pair(),
+        EVE_VERIFYING_KEY.to_vec(),
+        PREIMAGE_SHOULD_SUCCEED.to_vec(),
+        HashingAlgorithm::Sha2,
+        Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Ecdsa,
+        None,
+    )
+    .await
+    .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Ecdsa(recoverable_signature) = signing_report.signature else {
+        panic!("Expected an ECDSA signature");
+    };
+
+    let recovery_key_from_sig = VerifyingKey::recover_from_prehash(
+        &<sha2::Sha256 as sha2::Digest>::digest(PREIMAGE_SHOULD_SUCCEED),
+        &recoverable_signature.signature,
+        recoverable_signature.recovery_id,
+    )
+    .unwrap();
+    assert_eq!(
+        EVE_VERIFYING_KEY.to_vec(),
+        recovery_key_from_sig.to_encoded_point(true).to_bytes().to_vec()
+    );
 }
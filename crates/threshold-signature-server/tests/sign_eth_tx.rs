@@ -21,8 +21,8 @@ use entropy_client::{
     client as test_client, Hasher,
 };
 use entropy_kvdb::clean_tests;
-use entropy_protocol::{decode_verifying_key, RecoverableSignature};
-use entropy_shared::EVE_VERIFYING_KEY;
+use entropy_protocol::{decode_verifying_key, RecoverableSignature, ThresholdSignature};
+use entropy_shared::{HashingAlgorithm, SigningMode, EVE_VERIFYING_KEY};
 use entropy_testing_utils::{
     constants::{AUXILARY_DATA_SHOULD_SUCCEED, TEST_PROGRAM_WASM_BYTECODE},
     spawn_testing_validators,
@@ -61,6 +61,8 @@ async fn integration_test_sign_eth_tx() {
         vec![],
         vec![],
         vec![],
+        false,
+        false,
     )
     .await
     .unwrap();
@@ -68,9 +70,9 @@ async fn integration_test_sign_eth_tx() {
     test_client::update_programs(
         &api,
         &rpc,
-        EVE_VERIFYING_KEY,
+        EVE_VERIFYING_KEY.to_vec(),
         &pre_registered_user.pair(),
-        BoundedVec(vec![ProgramInstance { program_pointer, program_config: vec![] }]),
+        BoundedVec(vec![ProgramInstance { program_pointer, program_config: vec![], fuel: None }]),
     )
     .await
     .unwrap();
@@ -84,16 +86,23 @@ async fn integration_test_sign_eth_tx() {
 
     let message_hash = Hasher::keccak(&message);
 
-    let recoverable_signature = test_client::sign(
+    let signing_report = test_client::sign(
         &api,
         &rpc,
         pre_registered_user.pair(),
-        EVE_VERIFYING_KEY,
+        EVE_VERIFYING_KEY.to_vec(),
         message,
+        HashingAlgorithm::Keccak,
         Some(AUXILARY_DATA_SHOULD_SUCCEED.to_vec()),
+        SigningMode::Ecdsa,
+        None,
     )
     .await
     .unwrap();
+    assert!(signing_report.is_unanimous());
+    let ThresholdSignature::Ecdsa(recoverable_signature) = signing_report.signature else {
+        panic!("Expected an ECDSA signature");
+    };
 
     let recovery_key_from_sig = VerifyingKey::recover_from_prehash(
         &message_hash,
@@ -26,7 +26,8 @@ use entropy_runtime::{AccountId, Balance};
 use entropy_shared::{
     DAVE_VERIFYING_KEY, DEVICE_KEY_AUX_DATA_TYPE, DEVICE_KEY_CONFIG_TYPE, DEVICE_KEY_HASH,
     DEVICE_KEY_PROXY, EVE_VERIFYING_KEY, FERDIE_VERIFYING_KEY,
-    INITIAL_MAX_INSTRUCTIONS_PER_PROGRAM, SIGNER_THRESHOLD, TOTAL_SIGNERS,
+    INITIAL_MAX_INSTRUCTIONS_PER_PROGRAM, INITIAL_MAX_RATE_LIMIT_REQUESTS, SIGNER_THRESHOLD,
+    TOTAL_SIGNERS,
 };
 use grandpa_primitives::AuthorityId as GrandpaId;
 use itertools::Itertools;
@@ -258,6 +259,9 @@ pub fn integration_tests_genesis_config(
         "parameters": ParametersConfig {
             request_limit: 20,
             max_instructions_per_programs: INITIAL_MAX_INSTRUCTIONS_PER_PROGRAM,
+            max_rate_limit_requests: INITIAL_MAX_RATE_LIMIT_REQUESTS,
+            // Short enough that tests can wait for a recovery key to be usable
+            recovery_key_delay: 2,
             total_signers: TOTAL_SIGNERS,
             threshold: SIGNER_THRESHOLD,
             ..Default::default()
@@ -155,7 +155,10 @@ fn session_keys(
     im_online: ImOnlineId,
     authority_discovery: AuthorityDiscoveryId,
 ) -> SessionKeys {
-    SessionKeys { grandpa, babe, im_online, authority_discovery }
+    // Until a validator sets new session keys, their `im_online` key is used as their propagation
+    // key, as after the runtime upgrade which added it
+    let propagation = sr25519::Public::from(im_online.clone()).into();
+    SessionKeys { grandpa, babe, im_online, authority_discovery, propagation }
 }
 
 /// Helper function to generate a crypto pair from seed
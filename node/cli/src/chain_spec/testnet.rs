@@ -25,8 +25,8 @@ use entropy_runtime::{
 use entropy_runtime::{AccountId, Balance};
 use entropy_shared::{
     X25519PublicKey as TssX25519PublicKey, DEVICE_KEY_AUX_DATA_TYPE, DEVICE_KEY_CONFIG_TYPE,
-    DEVICE_KEY_HASH, DEVICE_KEY_PROXY, INITIAL_MAX_INSTRUCTIONS_PER_PROGRAM, SIGNER_THRESHOLD,
-    TOTAL_SIGNERS,
+    DEVICE_KEY_HASH, DEVICE_KEY_PROXY, INITIAL_MAX_INSTRUCTIONS_PER_PROGRAM,
+    INITIAL_MAX_RATE_LIMIT_REQUESTS, INITIAL_RECOVERY_KEY_DELAY, SIGNER_THRESHOLD, TOTAL_SIGNERS,
 };
 use grandpa_primitives::AuthorityId as GrandpaId;
 use hex_literal::hex;
@@ -448,6 +448,8 @@ pub fn testnet_genesis_config(
         "parameters": ParametersConfig {
             request_limit: 20,
             max_instructions_per_programs: INITIAL_MAX_INSTRUCTIONS_PER_PROGRAM,
+            max_rate_limit_requests: INITIAL_MAX_RATE_LIMIT_REQUESTS,
+            recovery_key_delay: INITIAL_RECOVERY_KEY_DELAY,
             total_signers: TOTAL_SIGNERS,
             threshold: SIGNER_THRESHOLD,
             ..Default::default()
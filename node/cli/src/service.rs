@@ -362,6 +362,11 @@ pub fn new_full_base(
                 b"registration",
                 &format!("{}/user/new", endpoint).into_bytes(),
             );
+            offchain_db.local_storage_set(
+                sp_core::offchain::StorageKind::PERSISTENT,
+                b"deregistration",
+                &format!("{}/user/deregister", endpoint).into_bytes(),
+            );
             offchain_db.local_storage_set(
                 sp_core::offchain::StorageKind::PERSISTENT,
                 b"refresh",
@@ -372,6 +377,16 @@ pub fn new_full_base(
                 b"reshare_validators",
                 &format!("{}/validator/reshare", endpoint).into_bytes(),
             );
+            offchain_db.local_storage_set(
+                sp_core::offchain::StorageKind::PERSISTENT,
+                b"signer_rotation",
+                &format!("{}/validator/rotate_network_key", endpoint).into_bytes(),
+            );
+            offchain_db.local_storage_set(
+                sp_core::offchain::StorageKind::PERSISTENT,
+                b"program_change",
+                &format!("{}/user/check_program_change", endpoint).into_bytes(),
+            );
             log::info!("Threshold Signing Sever (TSS) location changed to {}", endpoint);
         }
     }
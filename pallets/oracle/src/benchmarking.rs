@@ -0,0 +1,69 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use frame_benchmarking::{account, benchmarks};
+use frame_support::assert_ok;
+use frame_system::{EventRecord, RawOrigin};
+use sp_std::vec;
+
+use super::*;
+#[allow(unused)]
+use crate::Pallet as Oracle;
+
+fn assert_last_event<T: Config>(generic_event: <T as Config>::RuntimeEvent) {
+    let events = frame_system::Pallet::<T>::events();
+    let system_event: <T as frame_system::Config>::RuntimeEvent = generic_event.into();
+    // compare to the last event record
+    let EventRecord { event, .. } = &events[events.len() - 1];
+    assert_eq!(event, &system_event);
+}
+
+benchmarks! {
+  set_feeder {
+    let origin = T::UpdateOrigin::try_successful_origin().unwrap();
+    let feeder: T::AccountId = account("feeder", 0, 0);
+    let oracle_key = vec![1; T::MaxOracleKeyLength::get() as usize];
+  }: {
+    assert_ok!(
+      <Oracle<T>>::set_feeder(origin, oracle_key.clone(), Some(feeder.clone()))
+    );
+  }
+  verify {
+    assert_last_event::<T>(Event::FeederChanged { oracle_key, feeder: Some(feeder) }.into());
+  }
+
+  publish {
+    let l in 0 .. T::MaxOracleValueLength::get();
+    let feeder: T::AccountId = account("feeder", 0, 0);
+    let oracle_key = vec![1; T::MaxOracleKeyLength::get() as usize];
+    let bounded_oracle_key: OracleKey<T> = oracle_key.clone().try_into().unwrap();
+    Feeders::<T>::insert(bounded_oracle_key, feeder.clone());
+  }: _(RawOrigin::Signed(feeder.clone()), oracle_key.clone(), vec![0; l as usize])
+  verify {
+    assert_last_event::<T>(Event::OracleDataPublished { oracle_key, feeder }.into());
+  }
+
+  on_initialize {
+    let block_number: BlockNumberFor<T> = 1u32.into();
+  }: {
+    <Oracle<T>>::on_initialize(block_number);
+  }
+  verify {
+    let oracle_key: OracleKey<T> = BLOCK_NUMBER_ORACLE_KEY.to_vec().try_into().unwrap();
+    assert_eq!(OracleData::<T>::get(oracle_key).map(|value| value.to_vec()), Some(block_number.encode()));
+  }
+
+  impl_benchmark_test_suite!(Oracle, crate::mock::new_test_ext(), crate::mock::Runtime);
+}
@@ -0,0 +1,176 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! # Oracle Pallet
+//!
+//! ## Overview
+//!
+//! A pallet which stores named data on-chain for programs to use when they are evaluated.
+//!
+//! A program gives the name of the data it needs as its `oracle_data_pointer`. When evaluating the
+//! program the threshold servers read the data stored under that name at the block of the
+//! signature request, so that they all give the program the same data.
+//!
+//! Data can be published by the feeder which governance has authorized for that name, for example
+//! to provide prices or allow-lists. The current block number is always published under
+//! [BLOCK_NUMBER_ORACLE_KEY].
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! `set_feeder` - Allows governance to choose which account may publish data under a name.
+//! `publish` - Allows a feeder to publish data under a name it is authorized for.
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(clippy::unused_unit)]
+
+use entropy_shared::BLOCK_NUMBER_ORACLE_KEY;
+use frame_support::pallet_prelude::*;
+use frame_system::pallet_prelude::*;
+use sp_runtime::DispatchResult;
+use sp_std::vec::Vec;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+
+pub mod weights;
+
+pub use module::*;
+pub use weights::WeightInfo;
+
+#[frame_support::pallet]
+pub mod module {
+    use super::*;
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// The origin which may authorize feeders.
+        type UpdateOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+        /// The maximum length of the name of a piece of oracle data.
+        type MaxOracleKeyLength: Get<u32>;
+
+        /// The maximum length of a piece of oracle data.
+        type MaxOracleValueLength: Get<u32>;
+
+        /// Weight information for the extrinsics in this module.
+        type WeightInfo: WeightInfo;
+    }
+
+    pub type OracleKey<T> = BoundedVec<u8, <T as Config>::MaxOracleKeyLength>;
+    pub type OracleValue<T> = BoundedVec<u8, <T as Config>::MaxOracleValueLength>;
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The name of the oracle data is too long
+        OracleKeyTooLong,
+        /// The oracle data is too long
+        OracleValueTooLong,
+        /// The account is not the feeder for this oracle data
+        NotFeeder,
+        /// This oracle data is published by the chain itself
+        ReservedOracleKey,
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// The feeder for some oracle data changed
+        FeederChanged { oracle_key: Vec<u8>, feeder: Option<T::AccountId> },
+        /// Oracle data was published
+        OracleDataPublished { oracle_key: Vec<u8>, feeder: T::AccountId },
+    }
+
+    /// Oracle data, stored by its name
+    #[pallet::storage]
+    #[pallet::getter(fn oracle_data)]
+    pub type OracleData<T: Config> =
+        StorageMap<_, Blake2_128Concat, OracleKey<T>, OracleValue<T>, OptionQuery>;
+
+    /// The account allowed to publish each piece of oracle data
+    #[pallet::storage]
+    #[pallet::getter(fn feeders)]
+    pub type Feeders<T: Config> =
+        StorageMap<_, Blake2_128Concat, OracleKey<T>, T::AccountId, OptionQuery>;
+
+    #[pallet::pallet]
+    #[pallet::without_storage_info]
+    pub struct Pallet<T>(_);
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(block_number: BlockNumberFor<T>) -> Weight {
+            let oracle_key: Result<OracleKey<T>, _> = BLOCK_NUMBER_ORACLE_KEY.to_vec().try_into();
+            let oracle_value: Result<OracleValue<T>, _> = block_number.encode().try_into();
+            if let (Ok(oracle_key), Ok(oracle_value)) = (oracle_key, oracle_value) {
+                OracleData::<T>::insert(oracle_key, oracle_value);
+            }
+            T::WeightInfo::on_initialize()
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Sets the account which may publish the oracle data with the given name, or removes the
+        /// feeder if `None` is given.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::WeightInfo::set_feeder())]
+        pub fn set_feeder(
+            origin: OriginFor<T>,
+            oracle_key: Vec<u8>,
+            feeder: Option<T::AccountId>,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+            ensure!(oracle_key != BLOCK_NUMBER_ORACLE_KEY, Error::<T>::ReservedOracleKey);
+            let bounded_oracle_key: OracleKey<T> =
+                oracle_key.clone().try_into().map_err(|_| Error::<T>::OracleKeyTooLong)?;
+            Feeders::<T>::set(bounded_oracle_key, feeder.clone());
+            Self::deposit_event(Event::FeederChanged { oracle_key, feeder });
+            Ok(())
+        }
+
+        /// Publishes oracle data under the given name.
+        ///
+        /// The caller must be the feeder for that name.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::WeightInfo::publish(T::MaxOracleValueLength::get()))]
+        pub fn publish(
+            origin: OriginFor<T>,
+            oracle_key: Vec<u8>,
+            oracle_value: Vec<u8>,
+        ) -> DispatchResult {
+            let feeder = ensure_signed(origin)?;
+            let bounded_oracle_key: OracleKey<T> =
+                oracle_key.clone().try_into().map_err(|_| Error::<T>::OracleKeyTooLong)?;
+            let bounded_oracle_value: OracleValue<T> =
+                oracle_value.try_into().map_err(|_| Error::<T>::OracleValueTooLong)?;
+            ensure!(
+                Feeders::<T>::get(&bounded_oracle_key).as_ref() == Some(&feeder),
+                Error::<T>::NotFeeder
+            );
+            OracleData::<T>::insert(bounded_oracle_key, bounded_oracle_value);
+            Self::deposit_event(Event::OracleDataPublished { oracle_key, feeder });
+            Ok(())
+        }
+    }
+}
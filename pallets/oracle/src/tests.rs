@@ -0,0 +1,85 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Unit tests for the oracle pallet.
+
+#![cfg(test)]
+
+use frame_support::{assert_noop, assert_ok};
+use mock::*;
+use sp_runtime::traits::BadOrigin;
+
+use super::*;
+
+fn oracle_key(key: &[u8]) -> OracleKey<Runtime> {
+    key.to_vec().try_into().unwrap()
+}
+
+#[test]
+fn block_number_is_published() {
+    new_test_ext().execute_with(|| {
+        Oracle::on_initialize(50);
+        assert_eq!(
+            Oracle::oracle_data(oracle_key(BLOCK_NUMBER_ORACLE_KEY)).unwrap().to_vec(),
+            50u64.encode()
+        );
+    });
+}
+
+#[test]
+fn feeder_publishes_oracle_data() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        // Only governance can set a feeder
+        assert_noop!(
+            Oracle::set_feeder(RuntimeOrigin::signed(1), b"price".to_vec(), Some(1)),
+            BadOrigin
+        );
+        // The block number is published by the chain itself
+        assert_noop!(
+            Oracle::set_feeder(RuntimeOrigin::root(), BLOCK_NUMBER_ORACLE_KEY.to_vec(), Some(1)),
+            Error::<Runtime>::ReservedOracleKey
+        );
+        assert_noop!(
+            Oracle::set_feeder(RuntimeOrigin::root(), vec![1; 101], Some(1)),
+            Error::<Runtime>::OracleKeyTooLong
+        );
+
+        assert_ok!(Oracle::set_feeder(RuntimeOrigin::root(), b"price".to_vec(), Some(1)));
+        assert_eq!(Oracle::feeders(oracle_key(b"price")), Some(1));
+
+        // Only the feeder can publish
+        assert_noop!(
+            Oracle::publish(RuntimeOrigin::signed(2), b"price".to_vec(), vec![10]),
+            Error::<Runtime>::NotFeeder
+        );
+        assert_noop!(
+            Oracle::publish(RuntimeOrigin::signed(1), b"price".to_vec(), vec![10; 101]),
+            Error::<Runtime>::OracleValueTooLong
+        );
+
+        assert_ok!(Oracle::publish(RuntimeOrigin::signed(1), b"price".to_vec(), vec![10]));
+        assert_eq!(Oracle::oracle_data(oracle_key(b"price")).unwrap().to_vec(), vec![10]);
+
+        // A removed feeder can no longer publish, but the data it published is kept
+        assert_ok!(Oracle::set_feeder(RuntimeOrigin::root(), b"price".to_vec(), None));
+        assert_noop!(
+            Oracle::publish(RuntimeOrigin::signed(1), b"price".to_vec(), vec![11]),
+            Error::<Runtime>::NotFeeder
+        );
+        assert_eq!(Oracle::oracle_data(oracle_key(b"price")).unwrap().to_vec(), vec![10]);
+    });
+}
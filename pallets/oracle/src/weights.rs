@@ -0,0 +1,126 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Autogenerated weights for pallet_oracle
+//!
+//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 33.0.0
+//! DATE: 2024-08-05, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
+//! WORST CASE MAP SIZE: `1000000`
+//! HOSTNAME: `Jesses-MacBook-Pro.local`, CPU: `<UNKNOWN>`
+//! WASM-EXECUTION: `Compiled`, CHAIN: `Some("dev")`, DB CACHE: 1024
+
+// Executed Command:
+// ./target/release/entropy
+// benchmark
+// pallet
+// --chain
+// dev
+// --wasm-execution=compiled
+// --pallet
+// pallet_oracle
+// --extrinsic
+// *
+// --steps
+// 50
+// --repeat
+// 20
+// --template
+// .maintain/frame-weight-template.hbs
+// --output
+// pallets/oracle/src/weights.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for pallet_oracle.
+pub trait WeightInfo {
+	fn set_feeder() -> Weight;
+	fn publish(l: u32, ) -> Weight;
+	fn on_initialize() -> Weight;
+}
+
+/// Weights for pallet_oracle using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `Oracle::Feeders` (r:0 w:1)
+	/// Proof: `Oracle::Feeders` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn set_feeder() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 5_000_000 picoseconds.
+		Weight::from_parts(6_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `Oracle::Feeders` (r:1 w:0)
+	/// Proof: `Oracle::Feeders` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Oracle::OracleData` (r:0 w:1)
+	/// Proof: `Oracle::OracleData` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// The range of component `l` is `[0, 100000]`.
+	fn publish(l: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `155`
+		//  Estimated: `3620`
+		// Minimum execution time: 9_000_000 picoseconds.
+		Weight::from_parts(9_520_000, 3620)
+			// Standard Error: 12
+			.saturating_add(Weight::from_parts(1_105, 0).saturating_mul(l.into()))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	fn on_initialize() -> Weight {
+		T::DbWeight::get().writes(1)
+	}
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+	/// Storage: `Oracle::Feeders` (r:0 w:1)
+	/// Proof: `Oracle::Feeders` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn set_feeder() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 5_000_000 picoseconds.
+		Weight::from_parts(6_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	/// Storage: `Oracle::Feeders` (r:1 w:0)
+	/// Proof: `Oracle::Feeders` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Oracle::OracleData` (r:0 w:1)
+	/// Proof: `Oracle::OracleData` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// The range of component `l` is `[0, 100000]`.
+	fn publish(l: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `155`
+		//  Estimated: `3620`
+		// Minimum execution time: 9_000_000 picoseconds.
+		Weight::from_parts(9_520_000, 3620)
+			// Standard Error: 12
+			.saturating_add(Weight::from_parts(1_105, 0).saturating_mul(l.into()))
+			.saturating_add(RocksDbWeight::get().reads(1))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	fn on_initialize() -> Weight {
+		RocksDbWeight::get().writes(1)
+	}
+}
@@ -64,6 +64,27 @@ benchmarks! {
     assert_last_event::<T>(Event::SignerInfoChanged{ signer_info }.into());
   }
 
+  change_max_rate_limit_requests {
+    let origin = T::UpdateOrigin::try_successful_origin().unwrap();
+  }: {
+    assert_ok!(
+      <Parameters<T>>::change_max_rate_limit_requests(origin, 15)
+    );
+  }
+  verify {
+    assert_last_event::<T>(Event::MaxRateLimitRequestsChanged{ max_rate_limit_requests: 15}.into());
+  }
+
+  change_recovery_key_delay {
+    let origin = T::UpdateOrigin::try_successful_origin().unwrap();
+  }: {
+    assert_ok!(
+      <Parameters<T>>::change_recovery_key_delay(origin, 15)
+    );
+  }
+  verify {
+    assert_last_event::<T>(Event::RecoveryKeyDelayChanged{ recovery_key_delay: 15}.into());
+  }
 
   impl_benchmark_test_suite!(Parameters, crate::mock::new_test_ext(), crate::mock::Runtime);
 }
@@ -30,6 +30,10 @@
 //! ### Dispatchable Functions
 //!
 //! `change_request_limit` - Allows governance to change the request limit.
+//! `change_max_rate_limit_requests` - Allows governance to change the most requests an account's
+//! own rate limit may allow in a window.
+//! `change_recovery_key_delay` - Allows governance to change how many blocks a newly set recovery
+//! key must wait before keyshares can be exported to it.
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::unused_unit)]
 
@@ -46,6 +50,7 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod migrations;
 pub mod weights;
 
 pub use module::*;
@@ -71,6 +76,8 @@ pub mod module {
     pub struct GenesisConfig<T: Config> {
         pub request_limit: u32,
         pub max_instructions_per_programs: u64,
+        pub max_rate_limit_requests: u32,
+        pub recovery_key_delay: u32,
         pub threshold: u8,
         pub total_signers: u8,
         #[serde(skip)]
@@ -84,6 +91,8 @@ pub mod module {
             assert!(self.total_signers >= self.threshold, "Threshold is larger then signer");
             RequestLimit::<T>::put(self.request_limit);
             MaxInstructionsPerPrograms::<T>::put(self.max_instructions_per_programs);
+            MaxRateLimitRequests::<T>::put(self.max_rate_limit_requests);
+            RecoveryKeyDelay::<T>::put(self.recovery_key_delay);
             let signer_info =
                 SignersSize { total_signers: self.total_signers, threshold: self.threshold };
             SignersInfo::<T>::put(signer_info);
@@ -113,6 +122,10 @@ pub mod module {
         MaxInstructionsPerProgramsChanged { max_instructions_per_programs: u64 },
         /// Signer Info changed
         SignerInfoChanged { signer_info: SignersSize },
+        /// Max requests allowed by an account's rate limit changed
+        MaxRateLimitRequestsChanged { max_rate_limit_requests: u32 },
+        /// Delay before a recovery key can be used changed
+        RecoveryKeyDelayChanged { recovery_key_delay: u32 },
     }
 
     /// The request limit a user can ask to a specific set of TSS in a block
@@ -130,7 +143,21 @@ pub mod module {
     #[pallet::getter(fn signers_info)]
     pub type SignersInfo<T: Config> = StorageValue<_, SignersSize, ValueQuery>;
 
+    /// The most signature requests an account's own rate limit may allow in a single window
+    #[pallet::storage]
+    #[pallet::getter(fn max_rate_limit_requests)]
+    pub type MaxRateLimitRequests<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// The number of blocks after a recovery key is set before keyshares can be exported to it
+    #[pallet::storage]
+    #[pallet::getter(fn recovery_key_delay)]
+    pub type RecoveryKeyDelay<T: Config> = StorageValue<_, u32, ValueQuery>;
+
+    /// The current storage version
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -175,5 +202,31 @@ pub mod module {
             Self::deposit_event(Event::SignerInfoChanged { signer_info });
             Ok(())
         }
+
+        /// Changes the most signature requests an account's rate limit may allow in a window.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::change_max_rate_limit_requests())]
+        pub fn change_max_rate_limit_requests(
+            origin: OriginFor<T>,
+            max_rate_limit_requests: u32,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+            MaxRateLimitRequests::<T>::put(max_rate_limit_requests);
+            Self::deposit_event(Event::MaxRateLimitRequestsChanged { max_rate_limit_requests });
+            Ok(())
+        }
+
+        /// Changes how many blocks a newly set recovery key must wait before it can be used.
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::change_recovery_key_delay())]
+        pub fn change_recovery_key_delay(
+            origin: OriginFor<T>,
+            recovery_key_delay: u32,
+        ) -> DispatchResult {
+            T::UpdateOrigin::ensure_origin(origin)?;
+            RecoveryKeyDelay::<T>::put(recovery_key_delay);
+            Self::deposit_event(Event::RecoveryKeyDelayChanged { recovery_key_delay });
+            Ok(())
+        }
     }
 }
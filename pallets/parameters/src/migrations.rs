@@ -0,0 +1,88 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage migrations for the parameters pallet
+//!
+//! Each change to the pallet's storage has its own storage version, and the runtime runs the
+//! migrations from one version to the next in order.
+
+/// Sets the maximum number of requests an account's own rate limit may allow.
+pub mod v1 {
+    use crate::{Config, MaxRateLimitRequests, Pallet};
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use sp_std::marker::PhantomData;
+
+    /// Migrates the parameters pallet from storage version 0 to 1.
+    ///
+    /// Without this, `MaxRateLimitRequests` would be zero, so no account could set a rate limit.
+    /// It is set to the value given, which governance can then change.
+    pub struct MigrateToV1<T, InitialMaxRateLimitRequests>(
+        PhantomData<(T, InitialMaxRateLimitRequests)>,
+    );
+
+    impl<T, InitialMaxRateLimitRequests> OnRuntimeUpgrade
+        for MigrateToV1<T, InitialMaxRateLimitRequests>
+    where
+        T: Config,
+        InitialMaxRateLimitRequests: Get<u32>,
+    {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            MaxRateLimitRequests::<T>::put(InitialMaxRateLimitRequests::get());
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(1, 2)
+        }
+    }
+}
+
+/// Sets how long a recovery key must have been set before keyshares can be exported to it.
+pub mod v2 {
+    use crate::{Config, Pallet, RecoveryKeyDelay};
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use sp_std::marker::PhantomData;
+
+    /// Migrates the parameters pallet from storage version 1 to 2.
+    ///
+    /// Without this, `RecoveryKeyDelay` would be zero, so recovery keys could be used as soon as
+    /// they are set. It is set to the value given, which governance can then change.
+    pub struct MigrateToV2<T, InitialRecoveryKeyDelay>(PhantomData<(T, InitialRecoveryKeyDelay)>);
+
+    impl<T, InitialRecoveryKeyDelay> OnRuntimeUpgrade for MigrateToV2<T, InitialRecoveryKeyDelay>
+    where
+        T: Config,
+        InitialRecoveryKeyDelay: Get<u32>,
+    {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 1 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            RecoveryKeyDelay::<T>::put(InitialRecoveryKeyDelay::get());
+
+            StorageVersion::new(2).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(1, 2)
+        }
+    }
+}
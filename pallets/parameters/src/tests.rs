@@ -17,7 +17,10 @@
 
 #![cfg(test)]
 
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{ConstU32, GetStorageVersion, OnRuntimeUpgrade},
+};
 use mock::*;
 use sp_runtime::traits::BadOrigin;
 
@@ -62,6 +65,44 @@ fn max_instructions_per_programs_changed() {
     });
 }
 
+#[test]
+fn max_rate_limit_requests_changed() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Parameters::max_rate_limit_requests(), 5, "Inital max rate limit requests set");
+
+        assert_ok!(Parameters::change_max_rate_limit_requests(RuntimeOrigin::root(), 10));
+
+        assert_eq!(
+            Parameters::max_rate_limit_requests(),
+            10,
+            "Inital max rate limit requests changed"
+        );
+
+        // Fails not root
+        assert_noop!(
+            Parameters::change_max_rate_limit_requests(RuntimeOrigin::signed(2), 15),
+            BadOrigin,
+        );
+    });
+}
+
+#[test]
+fn recovery_key_delay_changed() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Parameters::recovery_key_delay(), 5, "Inital recovery key delay set");
+
+        assert_ok!(Parameters::change_recovery_key_delay(RuntimeOrigin::root(), 10));
+
+        assert_eq!(Parameters::recovery_key_delay(), 10, "Inital recovery key delay changed");
+
+        // Fails not root
+        assert_noop!(
+            Parameters::change_recovery_key_delay(RuntimeOrigin::signed(2), 15),
+            BadOrigin,
+        );
+    });
+}
+
 #[test]
 fn signer_info_changed() {
     new_test_ext().execute_with(|| {
@@ -101,3 +142,39 @@ fn signer_info_changed() {
         );
     });
 }
+
+#[test]
+fn it_migrates_to_v1() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Parameters>();
+        MaxRateLimitRequests::<Runtime>::kill();
+
+        migrations::v1::MigrateToV1::<Runtime, ConstU32<100>>::on_runtime_upgrade();
+
+        assert_eq!(Parameters::on_chain_storage_version(), 1);
+        assert_eq!(Parameters::max_rate_limit_requests(), 100);
+
+        // Values changed by governance are kept if the migration is run again
+        MaxRateLimitRequests::<Runtime>::put(20);
+        migrations::v1::MigrateToV1::<Runtime, ConstU32<100>>::on_runtime_upgrade();
+        assert_eq!(Parameters::max_rate_limit_requests(), 20);
+    });
+}
+
+#[test]
+fn it_migrates_to_v2() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(1).put::<Parameters>();
+        RecoveryKeyDelay::<Runtime>::kill();
+
+        migrations::v2::MigrateToV2::<Runtime, ConstU32<10>>::on_runtime_upgrade();
+
+        assert_eq!(Parameters::on_chain_storage_version(), 2);
+        assert_eq!(Parameters::recovery_key_delay(), 10);
+
+        // Values changed by governance are kept if the migration is run again
+        RecoveryKeyDelay::<Runtime>::put(20);
+        migrations::v2::MigrateToV2::<Runtime, ConstU32<10>>::on_runtime_upgrade();
+        assert_eq!(Parameters::recovery_key_delay(), 20);
+    });
+}
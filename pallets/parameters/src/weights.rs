@@ -54,6 +54,8 @@ pub trait WeightInfo {
 	fn change_request_limit() -> Weight;
 	fn max_instructions_per_programs() -> Weight;
 	fn change_signers_info() -> Weight;
+	fn change_max_rate_limit_requests() -> Weight;
+	fn change_recovery_key_delay() -> Weight;
 }
 
 /// Weights for pallet_transaction_pause using the Substrate node and recommended hardware.
@@ -92,6 +94,28 @@ impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
 			.saturating_add(Weight::from_parts(0, 0))
 			.saturating_add(T::DbWeight::get().writes(1))
 	}
+	/// Storage: `Parameters::MaxRateLimitRequests` (r:0 w:1)
+	/// Proof: `Parameters::MaxRateLimitRequests` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	fn change_max_rate_limit_requests() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_000_000 picoseconds.
+		Weight::from_parts(4_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `Parameters::RecoveryKeyDelay` (r:0 w:1)
+	/// Proof: `Parameters::RecoveryKeyDelay` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	fn change_recovery_key_delay() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_000_000 picoseconds.
+		Weight::from_parts(4_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
 }
 
 // For backwards compatibility and tests
@@ -129,4 +153,26 @@ impl WeightInfo for () {
 			.saturating_add(Weight::from_parts(0, 0))
 			.saturating_add(RocksDbWeight::get().writes(1))
 	}
+	/// Storage: `Parameters::MaxRateLimitRequests` (r:0 w:1)
+	/// Proof: `Parameters::MaxRateLimitRequests` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	fn change_max_rate_limit_requests() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_000_000 picoseconds.
+		Weight::from_parts(4_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
+	/// Storage: `Parameters::RecoveryKeyDelay` (r:0 w:1)
+	/// Proof: `Parameters::RecoveryKeyDelay` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	fn change_recovery_key_delay() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_000_000 picoseconds.
+		Weight::from_parts(4_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(RocksDbWeight::get().writes(1))
+	}
 }
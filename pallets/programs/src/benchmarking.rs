@@ -17,6 +17,7 @@
 
 use frame_benchmarking::{benchmarks, impl_benchmark_test_suite, whitelisted_caller};
 use frame_support::{
+    assert_ok,
     traits::{Currency, Get},
     BoundedVec,
 };
@@ -38,27 +39,52 @@ fn assert_last_event<T: Config>(generic_event: <T as Config>::RuntimeEvent) {
     assert_eq!(event, &system_event);
 }
 
+fn insert_program<T: Config>(program: Vec<u8>, deployer: &T::AccountId) -> T::Hash {
+    let program_hash = T::Hashing::hash(&program);
+    <Programs<T>>::insert(
+        program_hash,
+        ProgramInfo {
+            bytecode: program,
+            configuration_schema: vec![],
+            auxiliary_data_schema: vec![],
+            oracle_data_pointer: vec![],
+            accepts_prehashed: false,
+            accepts_derivation_path: false,
+            deployer: deployer.clone(),
+            ref_counter: 0u128,
+        },
+    );
+    program_hash
+}
+
+fn fund_owner<T: Config>(owner: &T::AccountId) {
+    let value = CurrencyOf::<T>::minimum_balance().saturating_mul(1_000_000_000u32.into());
+    let _ = CurrencyOf::<T>::make_free_balance_be(owner, value);
+}
+
 benchmarks! {
 
   set_program {
     let program = vec![10];
-    let configuration_schema = vec![11];
-    let auxiliary_data_schema = vec![12];
+    // Schemas must be well-formed JSON
+    let configuration_schema = b"1".to_vec();
+    let auxiliary_data_schema = b"2".to_vec();
     let oracle_data_pointer = vec![13];
-    let mut hash_input: Vec<u8> = vec![];
-    hash_input.extend(&program);
-    hash_input.extend(&configuration_schema);
-    hash_input.extend(&auxiliary_data_schema);
-    hash_input.extend(&oracle_data_pointer);
-
-    let program_hash = T::Hashing::hash(&hash_input);
+    let program_hash = Pallet::<T>::program_hash(
+        &program,
+        &configuration_schema,
+        &auxiliary_data_schema,
+        &oracle_data_pointer,
+        false,
+        false,
+    );
     let deployer: T::AccountId = whitelisted_caller();
     let sig_req_account: T::AccountId = whitelisted_caller();
 
     let value = CurrencyOf::<T>::minimum_balance().saturating_mul(1_000_000_000u32.into());
     let _ = CurrencyOf::<T>::make_free_balance_be(&deployer, value);
 
-  }: _(RawOrigin::Signed(deployer.clone()), program.clone(), configuration_schema.clone(), auxiliary_data_schema.clone(), oracle_data_pointer.clone())
+  }: _(RawOrigin::Signed(deployer.clone()), program.clone(), configuration_schema.clone(), auxiliary_data_schema.clone(), oracle_data_pointer.clone(), false, false)
   verify {
     assert_last_event::<T>(
         Event::<T>::ProgramCreated {
@@ -66,7 +92,9 @@ benchmarks! {
             program_hash,
             configuration_schema,
             auxiliary_data_schema,
-            oracle_data_pointer
+            oracle_data_pointer,
+            accepts_prehashed: false,
+            accepts_derivation_path: false,
         }.into()
     );
   }
@@ -77,20 +105,21 @@ benchmarks! {
     let configuration_schema = vec![11];
     let auxiliary_data_schema = vec![12];
     let oracle_data_pointer = vec![13];
-    let mut hash_input: Vec<u8> = vec![];
-    hash_input.extend(&program);
-    hash_input.extend(&configuration_schema);
-    hash_input.extend(&auxiliary_data_schema);
-    hash_input.extend(&oracle_data_pointer);
-
-    let program_hash = T::Hashing::hash(&hash_input);
+    let program_hash = Pallet::<T>::program_hash(
+        &program,
+        &configuration_schema,
+        &auxiliary_data_schema,
+        &oracle_data_pointer,
+        false,
+        false,
+    );
     let random_program = vec![11];
     let random_hash =  T::Hashing::hash(&random_program);
     let deployer: T::AccountId = whitelisted_caller();
 
     let value = CurrencyOf::<T>::minimum_balance().saturating_mul(1_000_000_000u32.into());
     let _ = CurrencyOf::<T>::make_free_balance_be(&deployer, value);
-    <Programs<T>>::insert(program_hash.clone(), ProgramInfo {bytecode: program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, deployer: deployer.clone(), ref_counter: 0u128});
+    <Programs<T>>::insert(program_hash.clone(), ProgramInfo {bytecode: program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, accepts_prehashed: false, accepts_derivation_path: false, deployer: deployer.clone(), ref_counter: 0u128});
     let mut program_hashes = vec![random_hash.clone(); p as usize];
     // remove one to make room for the targetted removal program hash
     program_hashes.pop();
@@ -107,6 +136,58 @@ benchmarks! {
         }.into()
     );
   }
+  create_program_family {
+    let owner: T::AccountId = whitelisted_caller();
+    fund_owner::<T>(&owner);
+    let program_hash = insert_program::<T>(vec![10], &owner);
+    let family_id = T::Hashing::hash_of(&(b"program_family", 0u64));
+  }: _(RawOrigin::Signed(owner.clone()), program_hash)
+  verify {
+    assert_last_event::<T>(
+        Event::<T>::ProgramFamilyCreated { owner, family_id, program_hash }.into()
+    );
+  }
+
+  schedule_program_upgrade {
+    let owner: T::AccountId = whitelisted_caller();
+    fund_owner::<T>(&owner);
+    let program_hash = insert_program::<T>(vec![10], &owner);
+    let pending_program_hash = insert_program::<T>(vec![11], &owner);
+    let new_program_hash = insert_program::<T>(vec![12], &owner);
+    let family_id = T::Hashing::hash_of(&(b"program_family", 0u64));
+    assert_ok!(<ProgramsPallet<T>>::create_program_family(RawOrigin::Signed(owner.clone()).into(), program_hash));
+    // Worst case is replacing an upgrade which was already scheduled
+    assert_ok!(<ProgramsPallet<T>>::schedule_program_upgrade(RawOrigin::Signed(owner.clone()).into(), family_id, pending_program_hash));
+    // And the block the upgrade takes effect in is otherwise full
+    let upgrade_at = <frame_system::Pallet<T>>::block_number().saturating_add(T::ProgramUpgradeDelay::get());
+    let other_family_ids = (1..T::MaxScheduledProgramUpgrades::get()).map(|i| T::Hashing::hash_of(&i));
+    ScheduledProgramUpgrades::<T>::mutate(upgrade_at, |family_ids| {
+        for other_family_id in other_family_ids {
+            let _ = family_ids.try_push(other_family_id);
+        }
+    });
+  }: _(RawOrigin::Signed(owner.clone()), family_id, new_program_hash)
+  verify {
+    let upgrade_at = <frame_system::Pallet<T>>::block_number().saturating_add(T::ProgramUpgradeDelay::get());
+    assert_last_event::<T>(
+        Event::<T>::ProgramUpgradeScheduled { family_id, program_hash: new_program_hash, upgrade_at }.into()
+    );
+  }
+
+  cancel_program_upgrade {
+    let owner: T::AccountId = whitelisted_caller();
+    fund_owner::<T>(&owner);
+    let program_hash = insert_program::<T>(vec![10], &owner);
+    let new_program_hash = insert_program::<T>(vec![11], &owner);
+    let family_id = T::Hashing::hash_of(&(b"program_family", 0u64));
+    assert_ok!(<ProgramsPallet<T>>::create_program_family(RawOrigin::Signed(owner.clone()).into(), program_hash));
+    assert_ok!(<ProgramsPallet<T>>::schedule_program_upgrade(RawOrigin::Signed(owner.clone()).into(), family_id, new_program_hash));
+  }: _(RawOrigin::Signed(owner.clone()), family_id)
+  verify {
+    assert_last_event::<T>(
+        Event::<T>::ProgramUpgradeCancelled { family_id, program_hash: new_program_hash }.into()
+    );
+  }
 }
 
 impl_benchmark_test_suite!(ProgramsPallet, crate::mock::new_test_ext(), crate::mock::Test);
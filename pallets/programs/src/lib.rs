@@ -33,6 +33,32 @@
 //!
 //! `set_program` - Allows a deployer account to create a program.
 //! `remove_program` - Allows a deployer to remove a program if not in use.
+//! `create_program_family` - Allows a deployer to give a program a stable id which can be upgraded.
+//! `schedule_program_upgrade` - Allows the owner of a program family to upgrade it after a delay.
+//! `cancel_program_upgrade` - Allows the owner of a program family to cancel a scheduled upgrade.
+//!
+//! ### Program Families
+//!
+//! Programs are stored at the hash of their contents, so changing a program gives it a new hash.
+//! A program family has a stable id which points to the current version of a program. Accounts
+//! may use the id of a family wherever a program hash is expected, in which case the current
+//! version of the program is evaluated.
+//!
+//! Upgrades to a family only take effect [`Config::ProgramUpgradeDelay`] blocks after they are
+//! scheduled, giving accounts which use the family time to react. At most
+//! [`Config::MaxScheduledProgramUpgrades`] upgrades take effect in any one block, so scheduling an
+//! upgrade fails when that block is full.
+//!
+//! Creating a family reserves [`Config::ProgramFamilyDeposit`] from its owner for as long as the
+//! family exists.
+//!
+//! ### Schemas and Configuration
+//!
+//! A program's configuration and auxiliary data schemas, when given, must be well-formed JSON, as
+//! must an account's configuration for a program which has a configuration schema. Checking a
+//! configuration against the schema itself is too costly to do on-chain, so that is left to the
+//! client and the threshold servers, which refuse to generate a key for a registration whose
+//! configurations do not match.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 pub use pallet::*;
@@ -46,6 +72,7 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 pub mod benchmarking;
 
+pub mod migrations;
 pub mod weights;
 
 #[frame_support::pallet]
@@ -80,6 +107,16 @@ pub mod pallet {
 
         /// The currency mechanism, used to take storage deposits for example.
         type Currency: ReservableCurrency<Self::AccountId>;
+
+        /// The number of blocks between an upgrade to a program family being scheduled and it
+        /// taking effect.
+        type ProgramUpgradeDelay: Get<BlockNumberFor<Self>>;
+
+        /// The maximum number of program family upgrades which can take effect in one block.
+        type MaxScheduledProgramUpgrades: Get<u32>;
+
+        /// The amount reserved from the owner of a program family when it is created.
+        type ProgramFamilyDeposit: Get<BalanceOf<Self>>;
     }
 
     type BalanceOf<T> =
@@ -109,6 +146,8 @@ pub mod pallet {
                         auxiliary_data_schema: program_info.3.clone(),
                         deployer: program_info.4.clone(),
                         oracle_data_pointer: vec![],
+                        accepts_prehashed: false,
+                        accepts_derivation_path: false,
                         ref_counter: program_info.5,
                     },
                 );
@@ -116,7 +155,11 @@ pub mod pallet {
         }
     }
 
+    /// The current storage version
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -139,12 +182,50 @@ pub mod pallet {
         pub auxiliary_data_schema: Vec<u8>,
         /// The location of the oracle data needed for this program
         pub oracle_data_pointer: Vec<u8>,
+        /// Whether the program can evaluate a prehashed message, in which case it is given the
+        /// 32 byte hash rather than the message itself.
+        ///
+        /// A signature request may only use prehashed messages if every program on the account
+        /// accepts them.
+        pub accepts_prehashed: bool,
+        /// Whether the program can evaluate a request to sign with a child key of the account's
+        /// verifying key, in which case its auxiliary data is always a SCALE encoded
+        /// `VersionedAuxilaryData` holding any derivation path and the auxiliary data from the
+        /// request.
+        ///
+        /// A signature request may only give a derivation path if every program on the account
+        /// accepts them.
+        pub accepts_derivation_path: bool,
         /// Deployer of the program
         pub deployer: AccountId,
         /// Accounts that use this program
         pub ref_counter: u128,
     }
 
+    /// An upgrade to a program family which has not taken effect yet
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+    pub struct ProgramUpgrade<Hash, BlockNumber> {
+        /// The hash of the new version of the program.
+        pub program_hash: Hash,
+        /// The block in which the upgrade takes effect.
+        pub upgrade_at: BlockNumber,
+    }
+
+    /// A program with a stable id, which can be upgraded by its owner
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+    pub struct ProgramFamily<AccountId, Hash, BlockNumber> {
+        /// The account which may upgrade the program.
+        pub owner: AccountId,
+        /// The hash of the current version of the program.
+        pub program_hash: Hash,
+        /// The number of the current version, starting from 0.
+        pub version: u32,
+        /// An upgrade which has been scheduled but has not taken effect yet.
+        pub pending_upgrade: Option<ProgramUpgrade<Hash, BlockNumber>>,
+        /// Accounts that use this program family
+        pub ref_counter: u128,
+    }
+
     /// Stores the program info for a given program hash.
     /// A program hash is a combination of the bytecode and configuration_schema and auxiliary_data_schema
     #[pallet::storage]
@@ -163,6 +244,39 @@ pub mod pallet {
         ValueQuery,
     >;
 
+    /// Stores program families by their id.
+    #[pallet::storage]
+    #[pallet::getter(fn program_families)]
+    pub type ProgramFamilies<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        T::Hash,
+        ProgramFamily<T::AccountId, T::Hash, BlockNumberFor<T>>,
+        OptionQuery,
+    >;
+
+    /// The hash of each version of a program family, by family id and version number.
+    #[pallet::storage]
+    #[pallet::getter(fn program_family_versions)]
+    pub type ProgramFamilyVersions<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::Hash, Twox64Concat, u32, T::Hash, OptionQuery>;
+
+    /// The number of program families created, used to give each family a unique id.
+    #[pallet::storage]
+    #[pallet::getter(fn program_family_count)]
+    pub type ProgramFamilyCount<T: Config> = StorageValue<_, u64, ValueQuery>;
+
+    /// The program families which are upgraded in a given block.
+    #[pallet::storage]
+    #[pallet::getter(fn scheduled_program_upgrades)]
+    pub type ScheduledProgramUpgrades<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        BlockNumberFor<T>,
+        BoundedVec<T::Hash, T::MaxScheduledProgramUpgrades>,
+        ValueQuery,
+    >;
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
@@ -182,6 +296,12 @@ pub mod pallet {
 
             /// The oracle data location needed for the program
             oracle_data_pointer: Vec<u8>,
+
+            /// Whether the program accepts prehashed messages.
+            accepts_prehashed: bool,
+
+            /// Whether the program accepts requests to sign with a child key.
+            accepts_derivation_path: bool,
         },
         /// The bytecode of a program was removed.
         ProgramRemoved {
@@ -191,6 +311,40 @@ pub mod pallet {
             /// The hash of the removed program.
             old_program_hash: T::Hash,
         },
+        /// A program family was created.
+        ProgramFamilyCreated {
+            /// The account which owns the family.
+            owner: T::AccountId,
+            /// The id of the new family.
+            family_id: T::Hash,
+            /// The hash of the first version of the program.
+            program_hash: T::Hash,
+        },
+        /// An upgrade to a program family was scheduled.
+        ProgramUpgradeScheduled {
+            /// The id of the family.
+            family_id: T::Hash,
+            /// The hash of the new version of the program.
+            program_hash: T::Hash,
+            /// The block in which the upgrade takes effect.
+            upgrade_at: BlockNumberFor<T>,
+        },
+        /// A scheduled upgrade to a program family was cancelled.
+        ProgramUpgradeCancelled {
+            /// The id of the family.
+            family_id: T::Hash,
+            /// The hash of the version of the program which is no longer being upgraded to.
+            program_hash: T::Hash,
+        },
+        /// A program family was upgraded to a new version.
+        ProgramUpgraded {
+            /// The id of the family.
+            family_id: T::Hash,
+            /// The number of the new version.
+            version: u32,
+            /// The hash of the new version of the program.
+            program_hash: T::Hash,
+        },
     }
 
     #[pallet::error]
@@ -209,12 +363,49 @@ pub mod pallet {
         ProgramInUse,
         /// Arithmetic overflow error
         ArithmeticError,
+        /// No program family defined at id.
+        NoProgramFamilyDefined,
+        /// No upgrade is scheduled for this program family.
+        NoUpgradeScheduled,
+        /// The program family already uses this program.
+        ProgramAlreadyCurrent,
+        /// The configuration schema is not well-formed JSON.
+        InvalidConfigurationSchema,
+        /// The auxiliary data schema is not well-formed JSON.
+        InvalidAuxiliaryDataSchema,
+        /// The program configuration is not well-formed JSON, but the program has a configuration
+        /// schema.
+        InvalidProgramConfig,
+        /// The block in which the upgrade would take effect already has the maximum number of
+        /// upgrades scheduled.
+        TooManyUpgradesScheduled,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(block_number: BlockNumberFor<T>) -> Weight {
+            let family_ids = ScheduledProgramUpgrades::<T>::take(block_number);
+            for family_id in &family_ids {
+                Self::upgrade_program_family(*family_id);
+            }
+            let upgrades = family_ids.len() as u64;
+            // Each upgrade changes the family, its version history and the reference counter of
+            // the old program
+            T::DbWeight::get()
+                .reads_writes(1 + upgrades.saturating_mul(2), 1 + upgrades.saturating_mul(3))
+        }
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         /// Sets the program and uses hash of program config and aux description as key.
         ///
+        /// `accepts_prehashed` declares that the program can evaluate a prehashed message, which
+        /// allows accounts using it to sign 32 byte hashes directly.
+        ///
+        /// `accepts_derivation_path` declares that the program can evaluate a request to sign with
+        /// a child key, which allows accounts using it to sign with BIP-32 derivation paths.
+        ///
         /// Note that the caller becomes the deployer account.
         #[pallet::call_index(0)]
         #[pallet::weight({<T as Config>::WeightInfo::set_program()})]
@@ -224,14 +415,18 @@ pub mod pallet {
             configuration_schema: Vec<u8>,
             auxiliary_data_schema: Vec<u8>,
             oracle_data_pointer: Vec<u8>,
+            accepts_prehashed: bool,
+            accepts_derivation_path: bool,
         ) -> DispatchResult {
             let deployer = ensure_signed(origin)?;
-            let mut hash_input = vec![];
-            hash_input.extend(&new_program);
-            hash_input.extend(&configuration_schema);
-            hash_input.extend(&auxiliary_data_schema);
-            hash_input.extend(&oracle_data_pointer);
-            let program_hash = T::Hashing::hash(&hash_input);
+            let program_hash = Self::program_hash(
+                &new_program,
+                &configuration_schema,
+                &auxiliary_data_schema,
+                &oracle_data_pointer,
+                accepts_prehashed,
+                accepts_derivation_path,
+            );
             let new_program_length = new_program
                 .len()
                 .checked_add(configuration_schema.len())
@@ -244,6 +439,14 @@ pub mod pallet {
                 new_program_length as u32 <= T::MaxBytecodeLength::get(),
                 Error::<T>::ProgramLengthExceeded
             );
+            ensure!(
+                configuration_schema.is_empty() || Self::is_json(&configuration_schema),
+                Error::<T>::InvalidConfigurationSchema
+            );
+            ensure!(
+                auxiliary_data_schema.is_empty() || Self::is_json(&auxiliary_data_schema),
+                Error::<T>::InvalidAuxiliaryDataSchema
+            );
             ensure!(!Programs::<T>::contains_key(program_hash), Error::<T>::ProgramAlreadySet);
 
             Self::reserve_program_deposit(&deployer, new_program_length)?;
@@ -255,6 +458,8 @@ pub mod pallet {
                     configuration_schema: configuration_schema.clone(),
                     auxiliary_data_schema: auxiliary_data_schema.clone(),
                     oracle_data_pointer: oracle_data_pointer.clone(),
+                    accepts_prehashed,
+                    accepts_derivation_path,
                     deployer: deployer.clone(),
                     ref_counter: 0u128,
                 },
@@ -274,6 +479,8 @@ pub mod pallet {
                 configuration_schema,
                 auxiliary_data_schema,
                 oracle_data_pointer,
+                accepts_prehashed,
+                accepts_derivation_path,
             });
             Ok(())
         }
@@ -316,9 +523,231 @@ pub mod pallet {
             Self::deposit_event(Event::ProgramRemoved { deployer, old_program_hash: program_hash });
             Ok(Some(<T as Config>::WeightInfo::remove_program(owned_programs_length as u32)).into())
         }
+
+        /// Creates a program family with the given program as its first version.
+        ///
+        /// The caller becomes the owner of the family, and is the only account which can upgrade
+        /// it. The id of the new family is given in the [`Event::ProgramFamilyCreated`] event.
+        ///
+        /// [`Config::ProgramFamilyDeposit`] is reserved from the caller.
+        #[pallet::call_index(2)]
+        #[pallet::weight({<T as Config>::WeightInfo::create_program_family()})]
+        pub fn create_program_family(
+            origin: OriginFor<T>,
+            program_hash: T::Hash,
+        ) -> DispatchResult {
+            let owner = ensure_signed(origin)?;
+            ensure!(Programs::<T>::contains_key(program_hash), Error::<T>::NoProgramDefined);
+            T::Currency::reserve(&owner, T::ProgramFamilyDeposit::get())?;
+            // The family holds a reference to its current version so that it cannot be removed
+            Self::add_program_reference(program_hash)?;
+
+            let family_count = Self::program_family_count();
+            let family_id = T::Hashing::hash_of(&(b"program_family", family_count));
+            ProgramFamilyCount::<T>::put(family_count.saturating_add(1));
+
+            ProgramFamilies::<T>::insert(
+                family_id,
+                ProgramFamily {
+                    owner: owner.clone(),
+                    program_hash,
+                    version: 0,
+                    pending_upgrade: None,
+                    ref_counter: 0u128,
+                },
+            );
+            ProgramFamilyVersions::<T>::insert(family_id, 0, program_hash);
+            Self::deposit_event(Event::ProgramFamilyCreated { owner, family_id, program_hash });
+            Ok(())
+        }
+
+        /// Schedules an upgrade of a program family to a new program.
+        ///
+        /// The upgrade takes effect after [`Config::ProgramUpgradeDelay`] blocks, and replaces any
+        /// upgrade which was already scheduled. Caller must be the owner of the family.
+        ///
+        /// Fails if [`Config::MaxScheduledProgramUpgrades`] upgrades are already scheduled to take
+        /// effect in that block.
+        #[pallet::call_index(3)]
+        #[pallet::weight({<T as Config>::WeightInfo::schedule_program_upgrade()})]
+        pub fn schedule_program_upgrade(
+            origin: OriginFor<T>,
+            family_id: T::Hash,
+            program_hash: T::Hash,
+        ) -> DispatchResult {
+            let owner = ensure_signed(origin)?;
+            let mut family =
+                Self::program_families(family_id).ok_or(Error::<T>::NoProgramFamilyDefined)?;
+            ensure!(family.owner == owner, Error::<T>::NotAuthorized);
+            ensure!(family.program_hash != program_hash, Error::<T>::ProgramAlreadyCurrent);
+            ensure!(Programs::<T>::contains_key(program_hash), Error::<T>::NoProgramDefined);
+            // The new version must not be removed before the upgrade takes effect
+            Self::add_program_reference(program_hash)?;
+
+            if let Some(pending_upgrade) = family.pending_upgrade.take() {
+                Self::unschedule_program_upgrade(family_id, &pending_upgrade);
+            }
+
+            let upgrade_at = <frame_system::Pallet<T>>::block_number()
+                .saturating_add(T::ProgramUpgradeDelay::get());
+            ScheduledProgramUpgrades::<T>::try_append(upgrade_at, family_id)
+                .map_err(|_| Error::<T>::TooManyUpgradesScheduled)?;
+            family.pending_upgrade = Some(ProgramUpgrade { program_hash, upgrade_at });
+            ProgramFamilies::<T>::insert(family_id, family);
+
+            Self::deposit_event(Event::ProgramUpgradeScheduled {
+                family_id,
+                program_hash,
+                upgrade_at,
+            });
+            Ok(())
+        }
+
+        /// Cancels the scheduled upgrade of a program family.
+        ///
+        /// Caller must be the owner of the family.
+        #[pallet::call_index(4)]
+        #[pallet::weight({<T as Config>::WeightInfo::cancel_program_upgrade()})]
+        pub fn cancel_program_upgrade(origin: OriginFor<T>, family_id: T::Hash) -> DispatchResult {
+            let owner = ensure_signed(origin)?;
+            let mut family =
+                Self::program_families(family_id).ok_or(Error::<T>::NoProgramFamilyDefined)?;
+            ensure!(family.owner == owner, Error::<T>::NotAuthorized);
+            let pending_upgrade =
+                family.pending_upgrade.take().ok_or(Error::<T>::NoUpgradeScheduled)?;
+
+            Self::unschedule_program_upgrade(family_id, &pending_upgrade);
+            ProgramFamilies::<T>::insert(family_id, family);
+
+            Self::deposit_event(Event::ProgramUpgradeCancelled {
+                family_id,
+                program_hash: pending_upgrade.program_hash,
+            });
+            Ok(())
+        }
     }
 
     impl<T: Config> Pallet<T> {
+        /// The hash a program is stored under, which is the hash of its SCALE encoded fields.
+        ///
+        /// Encoding the fields, rather than concatenating them, means that no two different
+        /// programs can have the same hash input.
+        pub fn program_hash(
+            bytecode: &[u8],
+            configuration_schema: &[u8],
+            auxiliary_data_schema: &[u8],
+            oracle_data_pointer: &[u8],
+            accepts_prehashed: bool,
+            accepts_derivation_path: bool,
+        ) -> T::Hash {
+            T::Hashing::hash_of(&(
+                bytecode,
+                configuration_schema,
+                auxiliary_data_schema,
+                oracle_data_pointer,
+                accepts_prehashed,
+                accepts_derivation_path,
+            ))
+        }
+
+        /// Adds a reference from an account to a program, so that it cannot be removed while in
+        /// use.
+        ///
+        /// The pointer may be either the hash of a program or the id of a program family.
+        pub fn add_program_reference(program_pointer: T::Hash) -> DispatchResult {
+            if let Some(mut program_info) = Self::programs(program_pointer) {
+                program_info.ref_counter = program_info.ref_counter.saturating_add(1);
+                Programs::<T>::insert(program_pointer, program_info);
+            } else {
+                let mut family =
+                    Self::program_families(program_pointer).ok_or(Error::<T>::NoProgramDefined)?;
+                family.ref_counter = family.ref_counter.saturating_add(1);
+                ProgramFamilies::<T>::insert(program_pointer, family);
+            }
+            Ok(())
+        }
+
+        /// Removes a reference added with [`Self::add_program_reference`].
+        pub fn remove_program_reference(program_pointer: T::Hash) {
+            if Programs::<T>::contains_key(program_pointer) {
+                Programs::<T>::mutate(program_pointer, |maybe_program_info| {
+                    if let Some(program_info) = maybe_program_info {
+                        program_info.ref_counter = program_info.ref_counter.saturating_sub(1);
+                    }
+                });
+            } else {
+                ProgramFamilies::<T>::mutate(program_pointer, |maybe_family| {
+                    if let Some(family) = maybe_family {
+                        family.ref_counter = family.ref_counter.saturating_sub(1);
+                    }
+                });
+            }
+        }
+
+        /// Gives the hash of the program which a pointer refers to.
+        ///
+        /// For a program family, this is the hash of its current version.
+        pub fn resolve_program_pointer(program_pointer: T::Hash) -> Option<T::Hash> {
+            if Programs::<T>::contains_key(program_pointer) {
+                Some(program_pointer)
+            } else {
+                Self::program_families(program_pointer).map(|family| family.program_hash)
+            }
+        }
+
+        /// Checks that an account's configuration for a program is well-formed JSON, if the program
+        /// has a configuration schema.
+        ///
+        /// The pointer may be either the hash of a program or the id of a program family.
+        pub fn check_program_config(
+            program_pointer: T::Hash,
+            program_config: &[u8],
+        ) -> DispatchResult {
+            let program_hash = Self::resolve_program_pointer(program_pointer)
+                .ok_or(Error::<T>::NoProgramDefined)?;
+            let program_info = Self::programs(program_hash).ok_or(Error::<T>::NoProgramDefined)?;
+            ensure!(
+                program_info.configuration_schema.is_empty() || Self::is_json(program_config),
+                Error::<T>::InvalidProgramConfig
+            );
+            Ok(())
+        }
+
+        fn is_json(bytes: &[u8]) -> bool {
+            serde_json::from_slice::<serde_json::Value>(bytes).is_ok()
+        }
+
+        /// Makes the pending upgrade of a program family its current version.
+        fn upgrade_program_family(family_id: T::Hash) {
+            let Some(mut family) = Self::program_families(family_id) else { return };
+            let Some(pending_upgrade) = family.pending_upgrade.take() else { return };
+
+            // The reference taken when the upgrade was scheduled is now held for the current
+            // version
+            Self::remove_program_reference(family.program_hash);
+            family.program_hash = pending_upgrade.program_hash;
+            family.version = family.version.saturating_add(1);
+            ProgramFamilyVersions::<T>::insert(family_id, family.version, family.program_hash);
+
+            Self::deposit_event(Event::ProgramUpgraded {
+                family_id,
+                version: family.version,
+                program_hash: family.program_hash,
+            });
+            ProgramFamilies::<T>::insert(family_id, family);
+        }
+
+        /// Removes an upgrade from the schedule, releasing the new version of the program.
+        fn unschedule_program_upgrade(
+            family_id: T::Hash,
+            pending_upgrade: &ProgramUpgrade<T::Hash, BlockNumberFor<T>>,
+        ) {
+            ScheduledProgramUpgrades::<T>::mutate(pending_upgrade.upgrade_at, |family_ids| {
+                family_ids.retain(|id| *id != family_id)
+            });
+            Self::remove_program_reference(pending_upgrade.program_hash);
+        }
+
         /// Takes some balance from an account as a storage deposit based off the length of the
         /// program they wish to store on-chain.
         ///
@@ -0,0 +1,140 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage migrations for the programs pallet
+//!
+//! Each change to the layout of the pallet's storage has its own storage version, and the runtime
+//! runs the migrations from one version to the next in order.
+
+/// The layout of programs before storage versions were tracked.
+pub mod v0 {
+    use crate::{Config, Pallet};
+    use frame_support::pallet_prelude::*;
+    use sp_std::vec::Vec;
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+    pub struct ProgramInfo<AccountId> {
+        pub bytecode: Vec<u8>,
+        pub configuration_schema: Vec<u8>,
+        pub auxiliary_data_schema: Vec<u8>,
+        pub oracle_data_pointer: Vec<u8>,
+        pub deployer: AccountId,
+        pub ref_counter: u128,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Programs<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::Hash, ProgramInfo<T::AccountId>, OptionQuery>;
+}
+
+/// Adds whether each program accepts prehashed messages.
+pub mod v1 {
+    use super::v0;
+    use crate::{Config, Pallet};
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use sp_std::{marker::PhantomData, vec::Vec};
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+    pub struct ProgramInfo<AccountId> {
+        pub bytecode: Vec<u8>,
+        pub configuration_schema: Vec<u8>,
+        pub auxiliary_data_schema: Vec<u8>,
+        pub oracle_data_pointer: Vec<u8>,
+        pub accepts_prehashed: bool,
+        pub deployer: AccountId,
+        pub ref_counter: u128,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Programs<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::Hash, ProgramInfo<T::AccountId>, OptionQuery>;
+
+    /// Migrates the programs pallet from storage version 0 to 1.
+    ///
+    /// Existing programs do not accept prehashed messages, as they were written to be given the
+    /// message itself.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Programs::<T>::translate::<v0::ProgramInfo<T::AccountId>, _>(|_program_hash, old| {
+                translated += 1;
+                Some(ProgramInfo {
+                    bytecode: old.bytecode,
+                    configuration_schema: old.configuration_schema,
+                    auxiliary_data_schema: old.auxiliary_data_schema,
+                    oracle_data_pointer: old.oracle_data_pointer,
+                    accepts_prehashed: false,
+                    deployer: old.deployer,
+                    ref_counter: old.ref_counter,
+                })
+            });
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
+
+/// Adds whether each program accepts derivation paths.
+pub mod v2 {
+    use super::v1;
+    use crate::{Config, Pallet, ProgramInfo, Programs};
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use sp_std::marker::PhantomData;
+
+    /// Migrates the programs pallet from storage version 1 to 2.
+    ///
+    /// Existing programs do not accept derivation paths, as they were written to be given the
+    /// auxiliary data from the request as it is.
+    pub struct MigrateToV2<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 1 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Programs::<T>::translate::<v1::ProgramInfo<T::AccountId>, _>(|_program_hash, old| {
+                translated += 1;
+                Some(ProgramInfo {
+                    bytecode: old.bytecode,
+                    configuration_schema: old.configuration_schema,
+                    auxiliary_data_schema: old.auxiliary_data_schema,
+                    oracle_data_pointer: old.oracle_data_pointer,
+                    accepts_prehashed: old.accepts_prehashed,
+                    accepts_derivation_path: false,
+                    deployer: old.deployer,
+                    ref_counter: old.ref_counter,
+                })
+            });
+
+            StorageVersion::new(2).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
@@ -71,6 +71,9 @@ parameter_types! {
   pub const MaxBytecodeLength: u32 = 5;
   pub const ProgramDepositPerByte: u32 = 5;
   pub const MaxOwnedPrograms: u32 = 1;
+  pub const ProgramUpgradeDelay: u64 = 5;
+  pub const MaxScheduledProgramUpgrades: u32 = 1;
+  pub const ProgramFamilyDeposit: u32 = 10;
 }
 
 parameter_types! {
@@ -99,6 +102,9 @@ impl pallet_programs::Config for Test {
     type MaxBytecodeLength = MaxBytecodeLength;
     type ProgramDepositPerByte = ProgramDepositPerByte;
     type MaxOwnedPrograms = MaxOwnedPrograms;
+    type ProgramUpgradeDelay = ProgramUpgradeDelay;
+    type MaxScheduledProgramUpgrades = MaxScheduledProgramUpgrades;
+    type ProgramFamilyDeposit = ProgramFamilyDeposit;
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = ();
 }
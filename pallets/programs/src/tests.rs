@@ -13,11 +13,18 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use frame_support::{assert_noop, assert_ok, traits::Currency};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{Currency, GetStorageVersion, OnInitialize, OnRuntimeUpgrade, StorageVersion},
+};
 use pallet_balances::Error as BalancesError;
 use sp_runtime::traits::Hash;
 
-use crate::{mock::*, Error, ProgramInfo, Programs};
+use crate::{
+    migrations::{v0, v1, v2},
+    mock::*,
+    Error, ProgramFamilies, ProgramInfo, ProgramUpgrade, Programs,
+};
 
 /// consts used for testing
 const PROGRAM_MODIFICATION_ACCOUNT: u64 = 1u64;
@@ -27,17 +34,18 @@ fn set_program() {
     new_test_ext().execute_with(|| {
         let program = vec![10u8, 11u8];
         let program_2 = vec![12u8, 13u8];
-        let configuration_schema = vec![14u8];
-        let auxiliary_data_schema = vec![15u8];
+        let configuration_schema = b"4".to_vec();
+        let auxiliary_data_schema = b"5".to_vec();
         let oracle_data_pointer = vec![16u8];
         let too_long = vec![1u8, 2u8, 3u8, 4u8, 5u8];
-        let mut hash_input: Vec<u8> = vec![];
-        hash_input.extend(&program);
-        hash_input.extend(&configuration_schema);
-        hash_input.extend(&auxiliary_data_schema);
-        hash_input.extend(&oracle_data_pointer);
-
-        let program_hash = <Test as frame_system::Config>::Hashing::hash(&hash_input);
+        let program_hash = ProgramsPallet::program_hash(
+            &program,
+            &configuration_schema,
+            &auxiliary_data_schema,
+            &oracle_data_pointer,
+            false,
+            false,
+        );
         // can't pay deposit
         assert_noop!(
             ProgramsPallet::set_program(
@@ -45,7 +53,9 @@ fn set_program() {
                 program.clone(),
                 configuration_schema.clone(),
                 auxiliary_data_schema.clone(),
-                oracle_data_pointer.clone()
+                oracle_data_pointer.clone(),
+                false,
+                false
             ),
             BalancesError::<Test>::InsufficientBalance
         );
@@ -57,13 +67,17 @@ fn set_program() {
             program.clone(),
             configuration_schema.clone(),
             auxiliary_data_schema.clone(),
-            oracle_data_pointer.clone()
+            oracle_data_pointer.clone(),
+            false,
+            false
         ));
         let program_result = ProgramInfo {
             bytecode: program.clone(),
             configuration_schema: configuration_schema.clone(),
             auxiliary_data_schema: auxiliary_data_schema.clone(),
             oracle_data_pointer: oracle_data_pointer.clone(),
+            accepts_prehashed: false,
+            accepts_derivation_path: false,
             deployer: PROGRAM_MODIFICATION_ACCOUNT,
             ref_counter: 0u128,
         };
@@ -88,10 +102,38 @@ fn set_program() {
                 configuration_schema.clone(),
                 auxiliary_data_schema.clone(),
                 oracle_data_pointer.clone(),
+                false,
+                false,
             ),
             Error::<Test>::ProgramAlreadySet
         );
 
+        // schemas must be well-formed JSON
+        assert_noop!(
+            ProgramsPallet::set_program(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                program_2.clone(),
+                vec![14u8],
+                auxiliary_data_schema.clone(),
+                oracle_data_pointer.clone(),
+                false,
+                false,
+            ),
+            Error::<Test>::InvalidConfigurationSchema
+        );
+        assert_noop!(
+            ProgramsPallet::set_program(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                program_2.clone(),
+                configuration_schema.clone(),
+                vec![15u8],
+                oracle_data_pointer.clone(),
+                false,
+                false,
+            ),
+            Error::<Test>::InvalidAuxiliaryDataSchema
+        );
+
         // Too many programs set
         assert_noop!(
             ProgramsPallet::set_program(
@@ -100,6 +142,8 @@ fn set_program() {
                 configuration_schema.clone(),
                 auxiliary_data_schema.clone(),
                 oracle_data_pointer.clone(),
+                false,
+                false,
             ),
             Error::<Test>::TooManyProgramsOwned
         );
@@ -111,25 +155,112 @@ fn set_program() {
                 configuration_schema,
                 auxiliary_data_schema.clone(),
                 oracle_data_pointer.clone(),
+                false,
+                false,
             ),
             Error::<Test>::ProgramLengthExceeded
         );
     });
 }
 
+#[test]
+fn set_program_accepting_prehashed() {
+    new_test_ext().execute_with(|| {
+        let program = vec![10u8, 11u8];
+        let configuration_schema = b"4".to_vec();
+        let auxiliary_data_schema = b"5".to_vec();
+        let oracle_data_pointer = vec![16u8];
+        let program_hash = ProgramsPallet::program_hash(
+            &program,
+            &configuration_schema,
+            &auxiliary_data_schema,
+            &oracle_data_pointer,
+            true,
+            false,
+        );
+
+        Balances::make_free_balance_be(&PROGRAM_MODIFICATION_ACCOUNT, 100);
+        assert_ok!(ProgramsPallet::set_program(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            program,
+            configuration_schema,
+            auxiliary_data_schema,
+            oracle_data_pointer,
+            true,
+            false
+        ));
+        assert!(
+            ProgramsPallet::programs(program_hash).unwrap().accepts_prehashed,
+            "Program accepting prehashed messages gets its own hash"
+        );
+    });
+}
+
+#[test]
+fn set_program_accepting_derivation_paths() {
+    new_test_ext().execute_with(|| {
+        let program = vec![10u8, 11u8];
+        let configuration_schema = b"4".to_vec();
+        let auxiliary_data_schema = b"5".to_vec();
+        let oracle_data_pointer = vec![16u8];
+        let program_hash = ProgramsPallet::program_hash(
+            &program,
+            &configuration_schema,
+            &auxiliary_data_schema,
+            &oracle_data_pointer,
+            false,
+            true,
+        );
+
+        Balances::make_free_balance_be(&PROGRAM_MODIFICATION_ACCOUNT, 100);
+        assert_ok!(ProgramsPallet::set_program(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            program,
+            configuration_schema,
+            auxiliary_data_schema,
+            oracle_data_pointer,
+            false,
+            true
+        ));
+        let program_info = ProgramsPallet::programs(program_hash).unwrap();
+        assert!(program_info.accepts_derivation_path);
+        assert!(!program_info.accepts_prehashed);
+    });
+}
+
+#[test]
+fn program_hash_separates_fields() {
+    new_test_ext().execute_with(|| {
+        assert_ne!(
+            ProgramsPallet::program_hash(&[10u8, 11u8], &[14u8], &[], &[], false, false),
+            ProgramsPallet::program_hash(&[10u8], &[11u8, 14u8], &[], &[], false, false),
+        );
+        assert_ne!(
+            ProgramsPallet::program_hash(&[10u8], &[], &[], &[], true, false),
+            ProgramsPallet::program_hash(&[10u8], &[], &[], &[1u8], false, false),
+        );
+        assert_ne!(
+            ProgramsPallet::program_hash(&[10u8], &[], &[], &[], true, false),
+            ProgramsPallet::program_hash(&[10u8], &[], &[], &[], false, true),
+        );
+    });
+}
+
 #[test]
 fn remove_program() {
     new_test_ext().execute_with(|| {
         let program = vec![10u8, 11u8];
-        let configuration_schema = vec![14u8];
-        let auxiliary_data_schema = vec![15u8];
+        let configuration_schema = b"4".to_vec();
+        let auxiliary_data_schema = b"5".to_vec();
         let oracle_data_pointer = vec![16u8];
-        let mut hash_input: Vec<u8> = vec![];
-        hash_input.extend(&program);
-        hash_input.extend(&configuration_schema);
-        hash_input.extend(&auxiliary_data_schema);
-        hash_input.extend(&oracle_data_pointer);
-        let program_hash = <Test as frame_system::Config>::Hashing::hash(&hash_input);
+        let program_hash = ProgramsPallet::program_hash(
+            &program,
+            &configuration_schema,
+            &auxiliary_data_schema,
+            &oracle_data_pointer,
+            false,
+            false,
+        );
 
         // no program
         assert_noop!(
@@ -147,7 +278,9 @@ fn remove_program() {
             program.clone(),
             configuration_schema.clone(),
             auxiliary_data_schema.clone(),
-            oracle_data_pointer.clone()
+            oracle_data_pointer.clone(),
+            false,
+            false
         ));
         assert_eq!(
             ProgramsPallet::owned_programs(PROGRAM_MODIFICATION_ACCOUNT),
@@ -193,8 +326,8 @@ fn remove_program_fails_ref_count() {
     new_test_ext().execute_with(|| {
         let program = vec![10u8, 11u8];
         let program_hash = <Test as frame_system::Config>::Hashing::hash(&program);
-        let configuration_schema = vec![14u8];
-        let auxiliary_data_schema = vec![15u8];
+        let configuration_schema = b"4".to_vec();
+        let auxiliary_data_schema = b"5".to_vec();
         let oracle_data_pointer = vec![16u8];
 
         Programs::<Test>::insert(
@@ -204,6 +337,8 @@ fn remove_program_fails_ref_count() {
                 configuration_schema,
                 auxiliary_data_schema,
                 oracle_data_pointer,
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: PROGRAM_MODIFICATION_ACCOUNT,
                 ref_counter: 1u128,
             },
@@ -218,3 +353,316 @@ fn remove_program_fails_ref_count() {
         );
     });
 }
+
+fn insert_program(program: Vec<u8>) -> <Test as frame_system::Config>::Hash {
+    let program_hash = <Test as frame_system::Config>::Hashing::hash(&program);
+    Programs::<Test>::insert(
+        program_hash,
+        ProgramInfo {
+            bytecode: program,
+            configuration_schema: vec![],
+            auxiliary_data_schema: vec![],
+            oracle_data_pointer: vec![],
+            accepts_prehashed: false,
+            accepts_derivation_path: false,
+            deployer: PROGRAM_MODIFICATION_ACCOUNT,
+            ref_counter: 0u128,
+        },
+    );
+    program_hash
+}
+
+#[test]
+fn upgrade_program_family() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&PROGRAM_MODIFICATION_ACCOUNT, 100);
+        System::set_block_number(1);
+        let program_hash = insert_program(vec![10u8]);
+        let new_program_hash = insert_program(vec![11u8]);
+        let random_hash = <Test as frame_system::Config>::Hashing::hash(&[12u8]);
+
+        assert_noop!(
+            ProgramsPallet::create_program_family(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                random_hash
+            ),
+            Error::<Test>::NoProgramDefined
+        );
+        assert_ok!(ProgramsPallet::create_program_family(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            program_hash
+        ));
+        assert_eq!(Balances::free_balance(PROGRAM_MODIFICATION_ACCOUNT), 90, "Deposit charged");
+        let family_id =
+            <Test as frame_system::Config>::Hashing::hash_of(&(b"program_family", 0u64));
+        let family = ProgramsPallet::program_families(family_id).unwrap();
+        assert_eq!(family.program_hash, program_hash);
+        assert_eq!(family.version, 0);
+        assert_eq!(ProgramsPallet::program_family_versions(family_id, 0), Some(program_hash));
+        // The family keeps the current version in use
+        assert_eq!(ProgramsPallet::programs(program_hash).unwrap().ref_counter, 1);
+        assert_eq!(ProgramsPallet::resolve_program_pointer(family_id), Some(program_hash));
+        assert_eq!(ProgramsPallet::resolve_program_pointer(program_hash), Some(program_hash));
+
+        // Only the owner can upgrade the family
+        assert_noop!(
+            ProgramsPallet::schedule_program_upgrade(
+                RuntimeOrigin::signed(2),
+                family_id,
+                new_program_hash
+            ),
+            Error::<Test>::NotAuthorized
+        );
+        assert_noop!(
+            ProgramsPallet::schedule_program_upgrade(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                family_id,
+                program_hash
+            ),
+            Error::<Test>::ProgramAlreadyCurrent
+        );
+        assert_ok!(ProgramsPallet::schedule_program_upgrade(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            family_id,
+            new_program_hash
+        ));
+        assert_eq!(
+            ProgramsPallet::program_families(family_id).unwrap().pending_upgrade,
+            Some(ProgramUpgrade { program_hash: new_program_hash, upgrade_at: 6 })
+        );
+        assert_eq!(ProgramsPallet::scheduled_program_upgrades(6).into_inner(), vec![family_id]);
+        assert_eq!(ProgramsPallet::programs(new_program_hash).unwrap().ref_counter, 1);
+
+        // Nothing changes until the delay has passed
+        ProgramsPallet::on_initialize(5);
+        assert_eq!(ProgramsPallet::resolve_program_pointer(family_id), Some(program_hash));
+
+        ProgramsPallet::on_initialize(6);
+        let family = ProgramsPallet::program_families(family_id).unwrap();
+        assert_eq!(family.program_hash, new_program_hash);
+        assert_eq!(family.version, 1);
+        assert_eq!(family.pending_upgrade, None);
+        assert_eq!(ProgramsPallet::program_family_versions(family_id, 1), Some(new_program_hash));
+        assert_eq!(ProgramsPallet::resolve_program_pointer(family_id), Some(new_program_hash));
+        // The old version is released
+        assert_eq!(ProgramsPallet::programs(program_hash).unwrap().ref_counter, 0);
+        assert_eq!(ProgramsPallet::programs(new_program_hash).unwrap().ref_counter, 1);
+        assert!(ProgramsPallet::scheduled_program_upgrades(6).is_empty());
+    });
+}
+
+#[test]
+fn cancel_program_upgrade() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&PROGRAM_MODIFICATION_ACCOUNT, 100);
+        System::set_block_number(1);
+        let program_hash = insert_program(vec![10u8]);
+        let new_program_hash = insert_program(vec![11u8]);
+        assert_ok!(ProgramsPallet::create_program_family(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            program_hash
+        ));
+        let family_id =
+            <Test as frame_system::Config>::Hashing::hash_of(&(b"program_family", 0u64));
+
+        assert_noop!(
+            ProgramsPallet::cancel_program_upgrade(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                family_id
+            ),
+            Error::<Test>::NoUpgradeScheduled
+        );
+        assert_ok!(ProgramsPallet::schedule_program_upgrade(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            family_id,
+            new_program_hash
+        ));
+        assert_noop!(
+            ProgramsPallet::cancel_program_upgrade(RuntimeOrigin::signed(2), family_id),
+            Error::<Test>::NotAuthorized
+        );
+        assert_ok!(ProgramsPallet::cancel_program_upgrade(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            family_id
+        ));
+        assert_eq!(ProgramsPallet::program_families(family_id).unwrap().pending_upgrade, None);
+        assert!(ProgramsPallet::scheduled_program_upgrades(6).is_empty());
+        assert_eq!(ProgramsPallet::programs(new_program_hash).unwrap().ref_counter, 0);
+
+        ProgramsPallet::on_initialize(6);
+        assert_eq!(ProgramsPallet::resolve_program_pointer(family_id), Some(program_hash));
+    });
+}
+
+#[test]
+fn program_family_deposit() {
+    new_test_ext().execute_with(|| {
+        let program_hash = insert_program(vec![10u8]);
+        Balances::make_free_balance_be(&PROGRAM_MODIFICATION_ACCOUNT, 5);
+        assert_noop!(
+            ProgramsPallet::create_program_family(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                program_hash
+            ),
+            BalancesError::<Test>::InsufficientBalance
+        );
+    });
+}
+
+#[test]
+fn scheduled_program_upgrades_are_capped() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        Balances::make_free_balance_be(&PROGRAM_MODIFICATION_ACCOUNT, 100);
+        let program_hash = insert_program(vec![10u8]);
+        let new_program_hash = insert_program(vec![11u8]);
+        let other_program_hash = insert_program(vec![12u8]);
+        for _ in 0..2 {
+            assert_ok!(ProgramsPallet::create_program_family(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                program_hash
+            ));
+        }
+        let family_id =
+            <Test as frame_system::Config>::Hashing::hash_of(&(b"program_family", 0u64));
+        let other_family_id =
+            <Test as frame_system::Config>::Hashing::hash_of(&(b"program_family", 1u64));
+
+        assert_ok!(ProgramsPallet::schedule_program_upgrade(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            family_id,
+            new_program_hash
+        ));
+        // Only one upgrade may take effect in each block
+        assert_noop!(
+            ProgramsPallet::schedule_program_upgrade(
+                RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+                other_family_id,
+                new_program_hash
+            ),
+            Error::<Test>::TooManyUpgradesScheduled
+        );
+        // Replacing the upgrade already scheduled in that block frees its place
+        assert_ok!(ProgramsPallet::schedule_program_upgrade(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            family_id,
+            other_program_hash
+        ));
+        assert_eq!(ProgramsPallet::scheduled_program_upgrades(6).into_inner(), vec![family_id]);
+
+        // The next block has room again
+        System::set_block_number(2);
+        assert_ok!(ProgramsPallet::schedule_program_upgrade(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            other_family_id,
+            new_program_hash
+        ));
+        assert_eq!(
+            ProgramsPallet::scheduled_program_upgrades(7).into_inner(),
+            vec![other_family_id]
+        );
+    });
+}
+
+#[test]
+fn program_family_references() {
+    new_test_ext().execute_with(|| {
+        Balances::make_free_balance_be(&PROGRAM_MODIFICATION_ACCOUNT, 100);
+        let program_hash = insert_program(vec![10u8]);
+        assert_ok!(ProgramsPallet::create_program_family(
+            RuntimeOrigin::signed(PROGRAM_MODIFICATION_ACCOUNT),
+            program_hash
+        ));
+        let family_id =
+            <Test as frame_system::Config>::Hashing::hash_of(&(b"program_family", 0u64));
+
+        // Accounts can use either the program or the family
+        assert_ok!(ProgramsPallet::add_program_reference(family_id));
+        assert_ok!(ProgramsPallet::add_program_reference(program_hash));
+        assert_eq!(ProgramFamilies::<Test>::get(family_id).unwrap().ref_counter, 1);
+        assert_eq!(ProgramsPallet::programs(program_hash).unwrap().ref_counter, 2);
+
+        ProgramsPallet::remove_program_reference(family_id);
+        assert_eq!(ProgramFamilies::<Test>::get(family_id).unwrap().ref_counter, 0);
+
+        let random_hash = <Test as frame_system::Config>::Hashing::hash(&[12u8]);
+        assert_noop!(
+            ProgramsPallet::add_program_reference(random_hash),
+            Error::<Test>::NoProgramDefined
+        );
+    });
+}
+
+#[test]
+fn it_migrates_programs_to_v1() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<ProgramsPallet>();
+
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&[1]);
+        v0::Programs::<Test>::insert(
+            program_hash,
+            v0::ProgramInfo {
+                bytecode: vec![1],
+                configuration_schema: vec![2],
+                auxiliary_data_schema: vec![3],
+                oracle_data_pointer: vec![4],
+                deployer: PROGRAM_MODIFICATION_ACCOUNT,
+                ref_counter: 5,
+            },
+        );
+
+        v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(ProgramsPallet::on_chain_storage_version(), 1);
+        assert_eq!(
+            v1::Programs::<Test>::get(program_hash).unwrap(),
+            v1::ProgramInfo {
+                bytecode: vec![1],
+                configuration_schema: vec![2],
+                auxiliary_data_schema: vec![3],
+                oracle_data_pointer: vec![4],
+                accepts_prehashed: false,
+                deployer: PROGRAM_MODIFICATION_ACCOUNT,
+                ref_counter: 5,
+            }
+        );
+    });
+}
+
+#[test]
+fn it_migrates_programs_to_v2() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(1).put::<ProgramsPallet>();
+
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&[1]);
+        v1::Programs::<Test>::insert(
+            program_hash,
+            v1::ProgramInfo {
+                bytecode: vec![1],
+                configuration_schema: vec![2],
+                auxiliary_data_schema: vec![3],
+                oracle_data_pointer: vec![4],
+                accepts_prehashed: true,
+                deployer: PROGRAM_MODIFICATION_ACCOUNT,
+                ref_counter: 5,
+            },
+        );
+
+        v2::MigrateToV2::<Test>::on_runtime_upgrade();
+
+        assert_eq!(ProgramsPallet::on_chain_storage_version(), 2);
+        assert_eq!(
+            ProgramsPallet::programs(program_hash).unwrap(),
+            ProgramInfo {
+                bytecode: vec![1],
+                configuration_schema: vec![2],
+                auxiliary_data_schema: vec![3],
+                oracle_data_pointer: vec![4],
+                accepts_prehashed: true,
+                accepts_derivation_path: false,
+                deployer: PROGRAM_MODIFICATION_ACCOUNT,
+                ref_counter: 5,
+            }
+        );
+    });
+}
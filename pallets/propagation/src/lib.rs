@@ -19,7 +19,11 @@
 //! ## Overview
 //!
 //! Propgates messages to signing client through offchain worker
+//!
+//! Each message is signed with the validator's propagation session key, so that the threshold
+//! server can check that it came from its own node.
 pub use pallet::*;
+use sp_runtime::KeyTypeId;
 
 #[cfg(test)]
 mod mock;
@@ -27,20 +31,34 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+/// Key type of the session key the offchain worker signs its messages with
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"prop");
+
+/// The session key the offchain worker signs its messages with
+pub mod crypto {
+    use sp_application_crypto::{app_crypto, sr25519};
+    app_crypto!(sr25519, super::KEY_TYPE);
+}
+
 #[frame_support::pallet]
 pub mod pallet {
     use codec::Encode;
     use entropy_shared::{
-        OcwMessageDkg, OcwMessageProactiveRefresh, OcwMessageReshare, ValidatorInfo,
+        OcwMessageDeregister, OcwMessageDkg, OcwMessageProactiveRefresh, OcwMessageProgramChange,
+        OcwMessageReshare, OcwMessageSignerRotation, ValidatorInfo, OCW_SIGNATURE_HEADER,
     };
     use frame_support::{pallet_prelude::*, sp_runtime::traits::Saturating};
     use frame_system::pallet_prelude::*;
+    use pallet_staking_extension::RotationOutcome;
     use sp_runtime::{
         offchain::{http, Duration},
         sp_std::vec,
-        sp_std::{str, vec::Vec},
+        sp_std::{str, string::String, vec::Vec},
+        RuntimeAppPublic,
     };
 
+    use crate::{crypto, KEY_TYPE};
+
     #[pallet::config]
     pub trait Config:
         frame_system::Config
@@ -61,12 +79,17 @@ pub mod pallet {
             let _ = Self::post_reshare(block_number);
             let _ = Self::post_user_registration(block_number);
             let _ = Self::post_proactive_refresh(block_number);
+            let _ = Self::post_deregistration(block_number);
+            let _ = Self::post_program_changes(block_number);
+            let _ = Self::post_signer_rotation(block_number);
         }
 
         fn on_initialize(block_number: BlockNumberFor<T>) -> Weight {
             pallet_registry::Dkg::<T>::remove(block_number.saturating_sub(2u32.into()));
+            pallet_registry::Deregistrations::<T>::remove(block_number.saturating_sub(2u32.into()));
+            pallet_registry::ProgramChanges::<T>::remove(block_number.saturating_sub(2u32.into()));
             pallet_staking_extension::ProactiveRefresh::<T>::take();
-            T::DbWeight::get().writes(2)
+            T::DbWeight::get().writes(4)
         }
     }
 
@@ -84,12 +107,38 @@ pub mod pallet {
         /// Proactive Refresh Message passed to validators
         /// parameters. [OcwMessageReshare]
         KeyReshareMessagePassed(OcwMessageReshare),
+
+        /// Deregistration Message passed to validators
+        /// parameters. [OcwMessageDeregister]
+        DeregistrationMessagePassed(OcwMessageDeregister),
+
+        /// Signer rotation outcome passed to validators
+        /// parameters. [OcwMessageSignerRotation]
+        SignerRotationMessagePassed(OcwMessageSignerRotation),
+
+        /// Program change Message passed to validators
+        /// parameters. [OcwMessageProgramChange]
+        ProgramChangeMessagePassed(OcwMessageProgramChange),
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {}
 
     impl<T: Config> Pallet<T> {
+        /// Signs a message to the threshold server with this node's propagation session key, giving
+        /// the hex encoded signature.
+        ///
+        /// Only a key which a validator has set as their session key is used, so that the threshold
+        /// server can look it up on-chain. Without one, messages are not posted at all, as the
+        /// threshold server refuses unsigned messages.
+        pub fn sign_message(message: &[u8]) -> Option<String> {
+            let key = crypto::Public::all().into_iter().find(|key| {
+                pallet_session::KeyOwner::<T>::contains_key((KEY_TYPE, key.to_raw_vec()))
+            })?;
+            let signature = key.sign(&message)?;
+            Some(hex::encode(signature))
+        }
+
         /// Submits a distributed key generation request to jumpstart the network to the threshold
         /// servers.
         pub fn post_dkg(block_number: BlockNumberFor<T>) -> Result<(), http::Error> {
@@ -129,7 +178,12 @@ pub mod pallet {
             // We construct the request
             // important: the header->Content-Type must be added and match that of the receiving
             // party!!
+            let Some(signature) = Self::sign_message(&req_body.encode()) else {
+                log::warn!("propagation::post: no propagation session key, so not posting");
+                return Ok(());
+            };
             let pending = http::Request::post(url, vec![req_body.encode()])
+                .add_header(OCW_SIGNATURE_HEADER, &signature)
                 .deadline(deadline)
                 .send()
                 .map_err(|_| http::Error::IoError)?;
@@ -187,7 +241,12 @@ pub mod pallet {
             // We construct the request
             // important: the header->Content-Type must be added and match that of the receiving
             // party!!
+            let Some(signature) = Self::sign_message(&req_body.encode()) else {
+                log::warn!("propagation::post: no propagation session key, so not posting");
+                return Ok(());
+            };
             let pending = http::Request::post(url, vec![req_body.encode()])
+                .add_header(OCW_SIGNATURE_HEADER, &signature)
                 .deadline(deadline)
                 .send()
                 .map_err(|_| http::Error::IoError)?;
@@ -235,7 +294,12 @@ pub mod pallet {
             // We construct the request
             // important: the header->Content-Type must be added and match that of the receiving
             // party!!
+            let Some(signature) = Self::sign_message(&req_body.encode()) else {
+                log::warn!("propagation::post: no propagation session key, so not posting");
+                return Ok(());
+            };
             let pending = http::Request::post(url, vec![req_body.encode()])
+                .add_header(OCW_SIGNATURE_HEADER, &signature)
                 .deadline(deadline)
                 .send()
                 .map_err(|_| http::Error::IoError)?;
@@ -284,7 +348,12 @@ pub mod pallet {
             // We construct the request
             // important: the header->Content-Type must be added and match that of the receiving
             // party!!
+            let Some(signature) = Self::sign_message(&req_body.encode()) else {
+                log::warn!("propagation::post: no propagation session key, so not posting");
+                return Ok(());
+            };
             let pending = http::Request::post(url, vec![req_body.encode()])
+                .add_header(OCW_SIGNATURE_HEADER, &signature)
                 .deadline(deadline)
                 .send()
                 .map_err(|_| http::Error::IoError)?;
@@ -304,5 +373,194 @@ pub mod pallet {
 
             Ok(())
         }
+
+        /// Submits a request to delete the keyshares of deregistered accounts to the threshold
+        /// servers.
+        pub fn post_deregistration(block_number: BlockNumberFor<T>) -> Result<(), http::Error> {
+            let verifying_keys = pallet_registry::Pallet::<T>::deregistrations(
+                block_number.saturating_sub(1u32.into()),
+            );
+            if verifying_keys.is_empty() {
+                return Ok(());
+            }
+
+            let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(2_000));
+            let kind = sp_core::offchain::StorageKind::PERSISTENT;
+            let from_local = sp_io::offchain::local_storage_get(kind, b"deregistration")
+                .unwrap_or_else(|| b"http://localhost:3001/user/deregister".to_vec());
+            let url =
+                str::from_utf8(&from_local).unwrap_or("http://localhost:3001/user/deregister");
+
+            let converted_block_number: u32 =
+                BlockNumberFor::<T>::try_into(block_number).unwrap_or_default();
+
+            let req_body = OcwMessageDeregister {
+                // subtract 1 from blocknumber since the request is from the last block
+                block_number: converted_block_number.saturating_sub(1),
+                verifying_keys: verifying_keys.into_iter().map(|key| key.into_inner()).collect(),
+            };
+            log::warn!("propagation::post deregistration: {:?}", &[req_body.encode()]);
+
+            // We construct the request
+            // important: the header->Content-Type must be added and match that of the receiving
+            // party!!
+            let Some(signature) = Self::sign_message(&req_body.encode()) else {
+                log::warn!("propagation::post: no propagation session key, so not posting");
+                return Ok(());
+            };
+            let pending = http::Request::post(url, vec![req_body.encode()])
+                .add_header(OCW_SIGNATURE_HEADER, &signature)
+                .deadline(deadline)
+                .send()
+                .map_err(|_| http::Error::IoError)?;
+
+            // We await response, same as in fn get()
+            let response =
+                pending.try_wait(deadline).map_err(|_| http::Error::DeadlineReached)??;
+
+            // check response code
+            if response.code != 200 {
+                log::warn!("Unexpected status code: {}", response.code);
+                return Err(http::Error::Unknown);
+            }
+            let _res_body = response.body().collect::<Vec<u8>>();
+
+            Self::deposit_event(Event::DeregistrationMessagePassed(req_body));
+
+            Ok(())
+        }
+
+        /// Asks the threshold servers to check the program configurations of changes to the
+        /// programs of verifying keys which were requested in the previous block.
+        pub fn post_program_changes(block_number: BlockNumberFor<T>) -> Result<(), http::Error> {
+            let verifying_keys = pallet_registry::Pallet::<T>::program_changes(
+                block_number.saturating_sub(1u32.into()),
+            );
+            if verifying_keys.is_empty() {
+                return Ok(());
+            }
+
+            let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(2_000));
+            let kind = sp_core::offchain::StorageKind::PERSISTENT;
+            let from_local = sp_io::offchain::local_storage_get(kind, b"program_change")
+                .unwrap_or_else(|| b"http://localhost:3001/user/check_program_change".to_vec());
+            let url = str::from_utf8(&from_local)
+                .unwrap_or("http://localhost:3001/user/check_program_change");
+
+            let converted_block_number: u32 =
+                BlockNumberFor::<T>::try_into(block_number).unwrap_or_default();
+
+            let req_body = OcwMessageProgramChange {
+                // subtract 1 from blocknumber since the request is from the last block
+                block_number: converted_block_number.saturating_sub(1),
+                verifying_keys: verifying_keys.into_iter().map(|key| key.into_inner()).collect(),
+            };
+            log::warn!("propagation::post program change: {:?}", &[req_body.encode()]);
+
+            let Some(signature) = Self::sign_message(&req_body.encode()) else {
+                log::warn!("propagation::post: no propagation session key, so not posting");
+                return Ok(());
+            };
+            let pending = http::Request::post(url, vec![req_body.encode()])
+                .add_header(OCW_SIGNATURE_HEADER, &signature)
+                .deadline(deadline)
+                .send()
+                .map_err(|_| http::Error::IoError)?;
+
+            let response =
+                pending.try_wait(deadline).map_err(|_| http::Error::DeadlineReached)??;
+
+            if response.code != 200 {
+                log::warn!("Unexpected status code: {}", response.code);
+                return Err(http::Error::Unknown);
+            }
+            let _res_body = response.body().collect::<Vec<u8>>();
+
+            Self::deposit_event(Event::ProgramChangeMessagePassed(req_body));
+
+            Ok(())
+        }
+
+        /// Tells the threshold servers whether a signer rotation which finished in the previous
+        /// block was completed or rolled back, so they know which network parent key share to keep.
+        pub fn post_signer_rotation(block_number: BlockNumberFor<T>) -> Result<(), http::Error> {
+            let reshare_data = pallet_staking_extension::Pallet::<T>::reshare_data();
+            let Some(finished_rotation) = pallet_staking_extension::Pallet::<T>::finished_rotations(
+                reshare_data.block_number,
+            ) else {
+                return Ok(());
+            };
+            if finished_rotation.block_number != block_number.saturating_sub(1u32.into()) {
+                return Ok(());
+            }
+
+            let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(2_000));
+            let kind = sp_core::offchain::StorageKind::PERSISTENT;
+            let from_local = sp_io::offchain::local_storage_get(kind, b"signer_rotation")
+                .unwrap_or_else(|| b"http://localhost:3001/validator/rotate_network_key".to_vec());
+            let url = str::from_utf8(&from_local)
+                .unwrap_or("http://localhost:3001/validator/rotate_network_key");
+
+            let converted_block_number: u32 =
+                BlockNumberFor::<T>::try_into(block_number).unwrap_or_default();
+
+            let req_body = OcwMessageSignerRotation {
+                // subtract 1 from blocknumber since the rotation finished in the last block
+                block_number: converted_block_number.saturating_sub(1),
+                rotated: finished_rotation.outcome == RotationOutcome::Rotated,
+            };
+            log::warn!("propagation::post signer rotation: {:?}", &[req_body.encode()]);
+
+            // We construct the request
+            // important: the header->Content-Type must be added and match that of the receiving
+            // party!!
+            let Some(signature) = Self::sign_message(&req_body.encode()) else {
+                log::warn!("propagation::post: no propagation session key, so not posting");
+                return Ok(());
+            };
+            let pending = http::Request::post(url, vec![req_body.encode()])
+                .add_header(OCW_SIGNATURE_HEADER, &signature)
+                .deadline(deadline)
+                .send()
+                .map_err(|_| http::Error::IoError)?;
+
+            // We await response, same as in fn get()
+            let response =
+                pending.try_wait(deadline).map_err(|_| http::Error::DeadlineReached)??;
+
+            // check response code
+            if response.code != 200 {
+                log::warn!("Unexpected status code: {}", response.code);
+                return Err(http::Error::Unknown);
+            }
+            let _res_body = response.body().collect::<Vec<u8>>();
+
+            Self::deposit_event(Event::SignerRotationMessagePassed(req_body));
+
+            Ok(())
+        }
+    }
+}
+
+impl<T: Config> sp_runtime::BoundToRuntimeAppPublic for Pallet<T> {
+    type Public = crypto::Public;
+}
+
+impl<T: Config> frame_support::traits::OneSessionHandler<T::AccountId> for Pallet<T> {
+    type Key = crypto::Public;
+
+    fn on_genesis_session<'a, I>(_validators: I)
+    where
+        I: 'a + Iterator<Item = (&'a T::AccountId, crypto::Public)>,
+    {
+        // The keys are only used offchain, and are looked up from the session pallet
     }
+
+    fn on_new_session<'a, I>(_changed: bool, _validators: I, _queued_validators: I)
+    where
+        I: 'a + Iterator<Item = (&'a T::AccountId, crypto::Public)>,
+    {
+    }
+
+    fn on_disabled(_validator_index: u32) {}
 }
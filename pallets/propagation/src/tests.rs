@@ -16,72 +16,26 @@
 use std::sync::Arc;
 
 use codec::Encode;
-use entropy_shared::ValidatorInfo;
+use entropy_shared::{SignatureScheme, ValidatorInfo};
 use frame_support::{assert_ok, traits::OnInitialize, BoundedVec};
 use pallet_programs::ProgramInfo;
 use pallet_registry::ProgramInstance;
-use pallet_staking_extension::{RefreshInfo, ReshareInfo};
-use sp_core::offchain::{testing, OffchainDbExt, OffchainWorkerExt, TransactionPoolExt};
+use pallet_staking_extension::{FinishedRotation, RefreshInfo, ReshareInfo, RotationOutcome};
+use sp_core::{
+    offchain::{testing, OffchainDbExt, OffchainWorkerExt, TransactionPoolExt},
+    sr25519, Pair,
+};
 use sp_io::TestExternalities;
 use sp_keystore::{testing::MemoryKeystore, KeystoreExt};
+use sp_runtime::RuntimeAppPublic;
 
-use crate::mock::*;
+use crate::{crypto, mock::*, KEY_TYPE};
 
 #[test]
-fn knows_how_to_mock_several_http_calls() {
-    let mut t = offchain_worker_env(|state| {
-        state.expect_request(testing::PendingRequest {
-            method: "POST".into(),
-            uri: "http://localhost:3001/generate_network_key".into(),
-            sent: true,
-            response: Some([].to_vec()),
-            body: [
-                0, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 10, 32, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4,
-                11, 32, 4, 0, 0, 0, 0, 0, 0, 0,
-            ]
-            .to_vec(),
-            ..Default::default()
-        });
-
-        state.expect_request(testing::PendingRequest {
-            method: "POST".into(),
-            uri: "http://localhost:3001/generate_network_key".into(),
-            sent: true,
-            response: Some([].to_vec()),
-            body: [
-                3, 0, 0, 0, 0, 8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 10, 32, 3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4,
-                11, 32, 4, 0, 0, 0, 0, 0, 0, 0,
-            ]
-            .to_vec(),
-            ..Default::default()
-        });
-
-        state.expect_request(testing::PendingRequest {
-            method: "POST".into(),
-            uri: "http://localhost:3001/signer/proactive_refresh".into(),
-            sent: true,
-            response: Some([].to_vec()),
-            body: [
-                5, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-                0, 0, 0, 0, 0, 0, 0, 0, 0, 20, 16, 116, 101, 115, 116, 20, 16, 116, 101, 115, 116,
-                8, 16, 1, 0, 0, 0, 16, 2, 0, 0, 0,
-            ]
-            .to_vec(),
-            ..Default::default()
-        });
-        state.expect_request(testing::PendingRequest {
-            method: "POST".into(),
-            uri: "http://localhost:3001/validator/reshare".into(),
-            sent: true,
-            response: Some([].to_vec()),
-            body: [32, 1, 0, 0, 0, 0, 0, 0, 0, 6, 0, 0, 0].to_vec(),
-            ..Default::default()
-        });
-    });
+fn skips_posts_without_a_propagation_session_key() {
+    // Without a propagation session key in the keystore, nothing is posted to the threshold
+    // server, so no requests are expected
+    let mut t = offchain_worker_env(|_| {});
 
     t.execute_with(|| {
         Propagation::post_dkg(1).unwrap();
@@ -94,6 +48,8 @@ fn knows_how_to_mock_several_http_calls() {
                 configuration_schema: vec![],
                 auxiliary_data_schema: vec![],
                 oracle_data_pointer: vec![],
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 0,
             },
@@ -102,10 +58,25 @@ fn knows_how_to_mock_several_http_calls() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: <Test as frame_system::Config>::Hash::default(),
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
-        assert_ok!(Registry::register(RuntimeOrigin::signed(1), 2, programs_info.clone(),));
-        assert_ok!(Registry::register(RuntimeOrigin::signed(2), 3, programs_info,));
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info.clone(),
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(2),
+            3,
+            programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
 
         // full send
         Propagation::post_dkg(4).unwrap();
@@ -134,9 +105,70 @@ fn knows_how_to_mock_several_http_calls() {
         pallet_staking_extension::ReshareData::<Test>::put(ReshareInfo {
             block_number: 7,
             new_signer: 1u64.encode(),
+            ..Default::default()
         });
         // now triggers
         Propagation::post_reshare(7).unwrap();
+
+        // doesn't trigger with no deregistrations
+        Propagation::post_deregistration(9).unwrap();
+        pallet_registry::Deregistrations::<Test>::insert(
+            8,
+            vec![BoundedVec::try_from(vec![1, 2, 3]).unwrap()],
+        );
+        // now triggers
+        Propagation::post_deregistration(9).unwrap();
+
+        // doesn't trigger with no program changes
+        Propagation::post_program_changes(9).unwrap();
+        pallet_registry::ProgramChanges::<Test>::insert(
+            8,
+            vec![BoundedVec::try_from(vec![4, 5, 6]).unwrap()],
+        );
+        // now triggers
+        Propagation::post_program_changes(9).unwrap();
+
+        // test pruning
+        Propagation::on_initialize(10);
+        assert_eq!(Registry::deregistrations(8).len(), 0);
+        assert_eq!(Registry::program_changes(8).len(), 0);
+
+        // doesn't trigger with no finished rotation
+        Propagation::post_signer_rotation(11).unwrap();
+        pallet_staking_extension::FinishedRotations::<Test>::insert(
+            7,
+            FinishedRotation { block_number: 10, outcome: RotationOutcome::Rotated },
+        );
+        // now triggers
+        Propagation::post_signer_rotation(11).unwrap();
+
+        // the outcome is kept for the threshold servers to reconcile against
+        Propagation::on_initialize(12);
+        assert!(Staking::finished_rotations(7).is_some());
+
+        // none of the messages were passed on
+        assert!(!System::events()
+            .iter()
+            .any(|record| matches!(record.event, RuntimeEvent::Propagation(_))));
+    })
+}
+
+#[test]
+fn signs_messages_with_the_propagation_session_key() {
+    let mut t = offchain_worker_env(|_| {});
+    t.execute_with(|| {
+        // There is no key in the keystore
+        assert_eq!(Propagation::sign_message(b"message"), None);
+
+        // A key which is not a validator's session key is not used
+        let key = crypto::Public::generate_pair(None);
+        assert_eq!(Propagation::sign_message(b"message"), None);
+
+        pallet_session::KeyOwner::<Test>::insert((KEY_TYPE, key.to_raw_vec()), 1);
+        let signature = Propagation::sign_message(b"message").unwrap();
+        let signature =
+            sr25519::Signature::from_raw(hex::decode(signature).unwrap().try_into().unwrap());
+        assert!(sr25519::Pair::verify(&signature, b"message", &key.into()));
     })
 }
 
@@ -14,17 +14,21 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 //! Benchmarking setup for pallet-propgation
-use entropy_shared::{SIGNING_PARTY_SIZE, VERIFICATION_KEY_LENGTH};
+use entropy_shared::{
+    RateLimit, SignatureScheme, SIGNER_THRESHOLD, SIGNING_PARTY_SIZE, TOTAL_SIGNERS,
+    VERIFICATION_KEY_LENGTH,
+};
 use frame_benchmarking::{account, benchmarks, impl_benchmark_test_suite, whitelisted_caller};
 use frame_support::{
     traits::{Currency, Get},
     BoundedVec,
 };
 use frame_system::{EventRecord, RawOrigin};
+use pallet_parameters::{SignersInfo, SignersSize};
 use pallet_programs::{ProgramInfo, Programs};
 use pallet_session::Validators;
 use pallet_staking_extension::{
-    benchmarking::create_validators, IsValidatorSynced, ServerInfo, ThresholdServers,
+    benchmarking::create_validators, IsValidatorSynced, ServerInfo, Signers, ThresholdServers,
     ThresholdToStash,
 };
 use sp_runtime::traits::Hash;
@@ -66,6 +70,18 @@ pub fn add_non_syncing_validators<T: Config>(
     validators
 }
 
+/// Sets up enough validators and signer bounds for an account to register with the default
+/// threshold and committee size
+fn set_up_signing_committee<T: Config>() {
+    let validators = add_non_syncing_validators::<T>(TOTAL_SIGNERS as u32, 0);
+    <Validators<T>>::set(validators.clone());
+    <Signers<T>>::put(validators);
+    <SignersInfo<T>>::put(SignersSize {
+        threshold: SIGNER_THRESHOLD,
+        total_signers: TOTAL_SIGNERS,
+    });
+}
+
 benchmarks! {
   jump_start_network {
 
@@ -142,49 +158,89 @@ benchmarks! {
   register {
     let p in 1 .. T::MaxProgramHashes::get();
     let program = vec![0u8];
-    let configuration_schema = vec![1u8];
+    let configuration_schema = b"{}".to_vec();
     let auxiliary_data_schema = vec![2u8];
     let oracle_data_pointer = vec![3u8];
     let program_hash = T::Hashing::hash(&program);
     let programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   };  p as usize])
   .unwrap();
 
   let program_modification_account: T::AccountId = whitelisted_caller();
-    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, deployer: program_modification_account.clone(), ref_counter: 0});
+    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: 0});
     let sig_req_account: T::AccountId = whitelisted_caller();
     let balance = <T as pallet_staking_extension::Config>::Currency::minimum_balance() * 100u32.into();
     let _ = <T as pallet_staking_extension::Config>::Currency::make_free_balance_be(&sig_req_account, balance);
-  }: _(RawOrigin::Signed(sig_req_account.clone()), program_modification_account, programs_info)
+    set_up_signing_committee::<T>();
+  }: _(RawOrigin::Signed(sig_req_account.clone()), program_modification_account, programs_info, SignatureScheme::Ecdsa, SIGNER_THRESHOLD, TOTAL_SIGNERS)
   verify {
     assert_last_event::<T>(Event::SignalRegister(sig_req_account.clone()).into());
     assert!(Registering::<T>::contains_key(sig_req_account));
   }
 
+  register_with_imported_key {
+    let p in 1 .. T::MaxProgramHashes::get();
+    let program = vec![0u8];
+    let configuration_schema = b"{}".to_vec();
+    let auxiliary_data_schema = vec![2u8];
+    let oracle_data_pointer = vec![3u8];
+    let program_hash = T::Hashing::hash(&program);
+    let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+      program_pointer: program_hash,
+      program_config: b"{}".to_vec(),
+      fuel: None,
+  };  p as usize])
+  .unwrap();
+    let verifying_key: VerifyingKey = BoundedVec::try_from(vec![3; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+
+  let program_modification_account: T::AccountId = whitelisted_caller();
+    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: 0});
+    let sig_req_account: T::AccountId = whitelisted_caller();
+    let balance = <T as pallet_staking_extension::Config>::Currency::minimum_balance() * 100u32.into();
+    let _ = <T as pallet_staking_extension::Config>::Currency::make_free_balance_be(&sig_req_account, balance);
+    set_up_signing_committee::<T>();
+  }: _(RawOrigin::Signed(sig_req_account.clone()), program_modification_account, programs_info, verifying_key.clone(), SIGNER_THRESHOLD, TOTAL_SIGNERS)
+  verify {
+    assert_last_event::<T>(Event::SignalKeyImport(sig_req_account.clone(), verifying_key).into());
+    assert!(Registering::<T>::contains_key(sig_req_account));
+  }
+
   prune_registration {
     let p in 1 .. T::MaxProgramHashes::get();
     let program_modification_account: T::AccountId = whitelisted_caller();
     let program = vec![0u8];
-    let configuration_schema = vec![1u8];
+    let configuration_schema = b"{}".to_vec();
     let auxiliary_data_schema = vec![2u8];
     let oracle_data_pointer = vec![3u8];
     let program_hash = T::Hashing::hash(&program);
     let programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   }]).unwrap();
-    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, deployer: program_modification_account.clone(), ref_counter: 1});
+    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: 1});
     let sig_req_account: T::AccountId = whitelisted_caller();
     let balance = <T as pallet_staking_extension::Config>::Currency::minimum_balance() * 100u32.into();
     let _ = <T as pallet_staking_extension::Config>::Currency::make_free_balance_be(&sig_req_account, balance);
       <Registering<T>>::insert(&sig_req_account, RegisteringDetails::<T> {
         program_modification_account: sig_req_account.clone(),
         confirmations: vec![],
+        refusals: vec![],
         programs_data: programs_info,
         verifying_key: Some(BoundedVec::default()),
-        version_number: T::KeyVersionNumber::get()
+        version_number: T::KeyVersionNumber::get(),
+        signature_scheme: SignatureScheme::Ecdsa,
+        status: RegistrationStatus::Submitted,
+        deposit: 0u32.into(),
+        dkg_attempts: 1,
+        dkg_deadline: 0u32.into(),
+        imported_key: false,
+        threshold: SIGNER_THRESHOLD,
+        total_signers: TOTAL_SIGNERS,
+        committee: vec![],
     });
   }: _(RawOrigin::Signed(sig_req_account.clone()))
   verify {
@@ -197,25 +253,27 @@ benchmarks! {
 
     let program_modification_account: T::AccountId = whitelisted_caller();
     let program = vec![0u8];
-    let configuration_schema = vec![1u8];
+    let configuration_schema = b"{}".to_vec();
     let auxiliary_data_schema = vec![2u8];
     let oracle_data_pointer = vec![3u8];
     let program_hash = T::Hashing::hash(&program);
     let programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   };  o as usize])
   .unwrap();
     let new_program = vec![1u8];
     let new_program_hash = T::Hashing::hash(&new_program);
     let new_programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: new_program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   };  n as usize])
   .unwrap();
   let sig_req_account: T::AccountId = whitelisted_caller();
-    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema: configuration_schema.clone(), auxiliary_data_schema: auxiliary_data_schema.clone(), oracle_data_pointer: oracle_data_pointer.clone(), deployer: program_modification_account.clone(), ref_counter: 0});
-    Programs::<T>::insert(new_program_hash, ProgramInfo {bytecode: new_program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, deployer: program_modification_account.clone(), ref_counter: o as u128});
+    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema: configuration_schema.clone(), auxiliary_data_schema: auxiliary_data_schema.clone(), oracle_data_pointer: oracle_data_pointer.clone(), accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: 0});
+    Programs::<T>::insert(new_program_hash, ProgramInfo {bytecode: new_program, configuration_schema, auxiliary_data_schema, oracle_data_pointer, accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: o as u128});
     let balance = <T as pallet_staking_extension::Config>::Currency::minimum_balance() * 100u32.into();
     let _ = <T as pallet_staking_extension::Config>::Currency::make_free_balance_be(&sig_req_account, balance);
     <Registered<T>>::insert(
@@ -223,12 +281,16 @@ benchmarks! {
         RegisteredInfo {
             program_modification_account: sig_req_account.clone(),
             programs_data: programs_info,
-            version_number: T::KeyVersionNumber::get()
+            version_number: T::KeyVersionNumber::get(),
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: SIGNER_THRESHOLD,
+            total_signers: TOTAL_SIGNERS,
+            committee: vec![],
         },
     );
   }: _(RawOrigin::Signed(sig_req_account.clone()), BoundedVec::default(), new_programs_info.clone())
   verify {
-    assert_last_event::<T>(Event::ProgramInfoChanged(sig_req_account.clone(), new_programs_info).into());
+    assert_last_event::<T>(Event::ProgramChangeRequested(sig_req_account.clone(), BoundedVec::default(), new_programs_info).into());
   }
 
   change_program_modification_account {
@@ -236,13 +298,14 @@ benchmarks! {
 
     let program_modification_account: T::AccountId = whitelisted_caller();
     let program = vec![0u8];
-    let configuration_schema = vec![1u8];
+    let configuration_schema = b"{}".to_vec();
     let auxiliary_data_schema = vec![2u8];
     let oracle_data_pointer = vec![3u8];
     let program_hash = T::Hashing::hash(&program);
     let programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   }]).unwrap();
 
   let sig_req_account: T::AccountId = whitelisted_caller();
@@ -257,7 +320,11 @@ benchmarks! {
         RegisteredInfo {
             program_modification_account: sig_req_account.clone(),
             programs_data: programs_info,
-            version_number: T::KeyVersionNumber::get()
+            version_number: T::KeyVersionNumber::get(),
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: SIGNER_THRESHOLD,
+            total_signers: TOTAL_SIGNERS,
+            committee: vec![],
         },
     );
   }: _(RawOrigin::Signed(sig_req_account.clone()), BoundedVec::default(), sig_req_account.clone())
@@ -266,16 +333,17 @@ benchmarks! {
   }
 
   confirm_register_registering {
-    let c in 1 .. MaxValidators::<T>::get();
+    let c in 1 .. MaxValidators::<T>::get().min(u8::MAX as u32 - 1);
     let program = vec![0u8];
-    let configuration_schema = vec![1u8];
+    let configuration_schema = b"{}".to_vec();
     let auxiliary_data_schema = vec![2u8];
     let oracle_data_pointer = vec![3u8];
 
     let program_hash = T::Hashing::hash(&program);
     let programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   }]).unwrap();
     let sig_req_account: T::AccountId = whitelisted_caller();
     let validator_account: T::AccountId = whitelisted_caller();
@@ -286,14 +354,25 @@ benchmarks! {
     let validators = add_non_syncing_validators::<T>(c + 1, 0);
     <ThresholdToStash<T>>::insert(&threshold_account, &validators[(c -1) as usize]);
 
-    <Validators<T>>::set(validators);
+    <Validators<T>>::set(validators.clone());
+    <Signers<T>>::put(validators.clone());
 
     <Registering<T>>::insert(&sig_req_account, RegisteringDetails::<T> {
         program_modification_account: sig_req_account.clone(),
         confirmations: vec![],
+        refusals: vec![],
         programs_data: programs_info,
         verifying_key: None,
-        version_number: T::KeyVersionNumber::get()
+        version_number: T::KeyVersionNumber::get(),
+        signature_scheme: SignatureScheme::Ecdsa,
+        status: RegistrationStatus::Submitted,
+        deposit: 0u32.into(),
+        dkg_attempts: 1,
+        dkg_deadline: 0u32.into(),
+        imported_key: false,
+        threshold: SIGNER_THRESHOLD,
+        total_signers: (c + 1) as u8,
+        committee: validators,
     });
     let balance = <T as pallet_staking_extension::Config>::Currency::minimum_balance() * 100u32.into();
     let _ = <T as pallet_staking_extension::Config>::Currency::make_free_balance_be(&threshold_account, balance);
@@ -303,17 +382,18 @@ benchmarks! {
   }
 
   confirm_register_failed_registering {
-    let c in 1 .. MaxValidators::<T>::get();
+    let c in 1 .. MaxValidators::<T>::get().min(u8::MAX as u32 - 1);
 
     let program = vec![0u8];
-    let configuration_schema = vec![1u8];
+    let configuration_schema = b"{}".to_vec();
     let auxiliary_data_schema = vec![2u8];
     let oracle_data_pointer = vec![3u8];
 
     let program_hash = T::Hashing::hash(&program);
     let programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   }]).unwrap();
     let sig_req_account: T::AccountId = whitelisted_caller();
     let validator_account: T::AccountId = whitelisted_caller();
@@ -325,34 +405,46 @@ benchmarks! {
     <ThresholdToStash<T>>::insert(&threshold_account, &validators[(c -1) as usize]);
     let confirmations = vec![random_account.clone(); (c -1).try_into().unwrap()];
 
-        <Validators<T>>::set(validators);
+        <Validators<T>>::set(validators.clone());
+        <Signers<T>>::put(validators.clone());
 
     <Registering<T>>::insert(&sig_req_account, RegisteringDetails::<T> {
         program_modification_account: sig_req_account.clone(),
         confirmations,
+        refusals: vec![],
         programs_data: programs_info,
         verifying_key: Some(BoundedVec::default()),
-        version_number: T::KeyVersionNumber::get()
+        version_number: T::KeyVersionNumber::get(),
+        signature_scheme: SignatureScheme::Ecdsa,
+        status: RegistrationStatus::Submitted,
+        deposit: 0u32.into(),
+        dkg_attempts: 1,
+        dkg_deadline: 0u32.into(),
+        imported_key: false,
+        threshold: SIGNER_THRESHOLD,
+        total_signers: c as u8,
+        committee: validators,
     });
     let balance = <T as pallet_staking_extension::Config>::Currency::minimum_balance() * 100u32.into();
     let _ = <T as pallet_staking_extension::Config>::Currency::make_free_balance_be(&threshold_account, balance);
   }: confirm_register(RawOrigin::Signed(threshold_account), sig_req_account.clone(), invalid_verifying_key)
   verify {
-    assert_last_event::<T>(Event::<T>::FailedRegistration(sig_req_account).into());
+    assert_last_event::<T>(Event::<T>::FailedRegistration(sig_req_account, RegistrationFailure::MismatchedVerifyingKeys).into());
   }
 
 
 confirm_register_registered {
-    let c in 1 .. MaxValidators::<T>::get();
+    let c in 1 .. MaxValidators::<T>::get().min(u8::MAX as u32 - 1);
 
     let program = vec![0u8];
-    let configuration_schema = vec![1u8];
+    let configuration_schema = b"{}".to_vec();
     let auxiliary_data_schema = vec![2u8];
     let oracle_data_pointer = vec![3u8];
     let program_hash = T::Hashing::hash(&program);
     let programs_info = BoundedVec::try_from(vec![ProgramInstance {
       program_pointer: program_hash,
-      program_config: vec![],
+      program_config: b"{}".to_vec(),
+      fuel: None,
   }]).unwrap();
     let sig_req_account: T::AccountId = whitelisted_caller();
     let validator_account: T::AccountId = whitelisted_caller();
@@ -364,14 +456,25 @@ confirm_register_registered {
     let confirmations = vec![random_account.clone(); (c -1).try_into().unwrap()];
 
 
-    <Validators<T>>::set(validators);
+    <Validators<T>>::set(validators.clone());
+    <Signers<T>>::put(validators.clone());
 
     <Registering<T>>::insert(&sig_req_account, RegisteringDetails::<T> {
         program_modification_account: sig_req_account.clone(),
         confirmations,
+        refusals: vec![],
         programs_data: programs_info,
         verifying_key: None,
-        version_number: T::KeyVersionNumber::get()
+        version_number: T::KeyVersionNumber::get(),
+        signature_scheme: SignatureScheme::Ecdsa,
+        status: RegistrationStatus::Submitted,
+        deposit: 0u32.into(),
+        dkg_attempts: 1,
+        dkg_deadline: 0u32.into(),
+        imported_key: false,
+        threshold: SIGNER_THRESHOLD,
+        total_signers: c as u8,
+        committee: validators,
     });
     let balance = <T as pallet_staking_extension::Config>::Currency::minimum_balance() * 100u32.into();
     let _ = <T as pallet_staking_extension::Config>::Currency::make_free_balance_be(&threshold_account, balance);
@@ -379,6 +482,256 @@ confirm_register_registered {
   verify {
     assert_last_event::<T>(Event::<T>::AccountRegistered(sig_req_account, BoundedVec::try_from(vec![3; VERIFICATION_KEY_LENGTH as usize]).unwrap()).into());
   }
+
+  set_rate_limit {
+    let program_modification_account: T::AccountId = whitelisted_caller();
+    let rate_limit = RateLimit { max_requests: 10, window_blocks: 5 };
+    pallet_parameters::MaxRateLimitRequests::<T>::put(10);
+    <Registered<T>>::insert(
+        &BoundedVec::default(),
+        RegisteredInfo {
+            program_modification_account: program_modification_account.clone(),
+            programs_data: BoundedVec::default(),
+            version_number: T::KeyVersionNumber::get(),
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: SIGNER_THRESHOLD,
+            total_signers: TOTAL_SIGNERS,
+            committee: vec![],
+        },
+    );
+  }: _(RawOrigin::Signed(program_modification_account), BoundedVec::default(), Some(rate_limit))
+  verify {
+    assert_last_event::<T>(Event::RateLimitChanged(BoundedVec::default(), Some(rate_limit)).into());
+  }
+
+  set_recovery_key {
+    let program_modification_account: T::AccountId = whitelisted_caller();
+    let recovery_key = [1; 32];
+    <Registered<T>>::insert(
+        &BoundedVec::default(),
+        RegisteredInfo {
+            program_modification_account: program_modification_account.clone(),
+            programs_data: BoundedVec::default(),
+            version_number: T::KeyVersionNumber::get(),
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: SIGNER_THRESHOLD,
+            total_signers: TOTAL_SIGNERS,
+            committee: vec![],
+        },
+    );
+  }: _(RawOrigin::Signed(program_modification_account), BoundedVec::default(), Some(recovery_key))
+  verify {
+    let recovery_key_info = RecoveryKeyInfo::<T> {
+      recovery_key,
+      active_from: <frame_system::Pallet<T>>::block_number()
+        + pallet_parameters::Pallet::<T>::recovery_key_delay().into(),
+    };
+    assert_last_event::<T>(Event::RecoveryKeyChanged(BoundedVec::default(), Some(recovery_key_info)).into());
+  }
+
+  refuse_register {
+    let program = vec![0u8];
+    let program_hash = T::Hashing::hash(&program);
+    let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+      program_pointer: program_hash,
+      program_config: b"{}".to_vec(),
+      fuel: None,
+  }]).unwrap();
+    let sig_req_account: T::AccountId = whitelisted_caller();
+    let threshold_account: T::AccountId = whitelisted_caller();
+    // The refusing validator is the last member of the committee
+    let validators = add_non_syncing_validators::<T>(TOTAL_SIGNERS as u32, 0);
+    <ThresholdToStash<T>>::insert(&threshold_account, &validators[(TOTAL_SIGNERS - 1) as usize]);
+    // The other refusals are already in, so this one fails the registration
+    let refusals = (0..SIGNER_THRESHOLD - 1)
+      .map(|i| account::<T::AccountId>("refuser", i.into(), SEED))
+      .collect::<Vec<_>>();
+
+    <Registering<T>>::insert(&sig_req_account, RegisteringDetails::<T> {
+        program_modification_account: sig_req_account.clone(),
+        confirmations: vec![],
+        refusals,
+        programs_data: programs_info,
+        verifying_key: None,
+        version_number: T::KeyVersionNumber::get(),
+        signature_scheme: SignatureScheme::Ecdsa,
+        status: RegistrationStatus::DkgStarted(0),
+        deposit: 0u32.into(),
+        dkg_attempts: 1,
+        dkg_deadline: 0u32.into(),
+        imported_key: false,
+        threshold: SIGNER_THRESHOLD,
+        total_signers: TOTAL_SIGNERS,
+        committee: validators,
+    });
+  }: _(RawOrigin::Signed(threshold_account), sig_req_account.clone())
+  verify {
+    assert_last_event::<T>(Event::<T>::FailedRegistration(sig_req_account, RegistrationFailure::InvalidProgramConfig).into());
+  }
+
+  deregister {
+    let p in 1 .. T::MaxProgramHashes::get();
+
+    let program_modification_account: T::AccountId = whitelisted_caller();
+    let program = vec![0u8];
+    let program_hash = T::Hashing::hash(&program);
+    let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+      program_pointer: program_hash,
+      program_config: b"{}".to_vec(),
+      fuel: None,
+  };  p as usize])
+  .unwrap();
+    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema: vec![], auxiliary_data_schema: vec![], oracle_data_pointer: vec![], accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: p as u128});
+    <ModifiableKeys<T>>::insert(
+        program_modification_account.clone(),
+        BoundedVec::try_from(vec![BoundedVec::default()]).unwrap()
+    );
+    <Registered<T>>::insert(
+        &BoundedVec::default(),
+        RegisteredInfo {
+            program_modification_account: program_modification_account.clone(),
+            programs_data: programs_info,
+            version_number: T::KeyVersionNumber::get(),
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: SIGNER_THRESHOLD,
+            total_signers: TOTAL_SIGNERS,
+            committee: vec![],
+        },
+    );
+  }: _(RawOrigin::Signed(program_modification_account.clone()), BoundedVec::default())
+  verify {
+    assert_last_event::<T>(Event::AccountDeregistered(program_modification_account, BoundedVec::default()).into());
+  }
+
+  confirm_deregistration {
+    let c in 1 .. MaxValidators::<T>::get();
+
+    let threshold_account: T::AccountId = whitelisted_caller();
+    let random_account = account::<T::AccountId>("ts_account", 10, SEED);
+
+    // all other validators have already confirmed, so this confirmation finishes deregistration
+    let validators = add_non_syncing_validators::<T>(c, 0);
+    <ThresholdToStash<T>>::insert(&threshold_account, &validators[(c -1) as usize]);
+    <Validators<T>>::set(validators.clone());
+    <Deregistering<T>>::insert(
+        &BoundedVec::default(),
+        DeregisteringDetails::<T> {
+            committee: validators,
+            confirmations: vec![random_account; (c -1).try_into().unwrap()],
+            attempts: 1,
+            deadline: 0u32.into(),
+        }
+    );
+  }: _(RawOrigin::Signed(threshold_account), BoundedVec::default())
+  verify {
+    assert_last_event::<T>(Event::<T>::KeysharesDeleted(BoundedVec::default()).into());
+  }
+
+  confirm_program_change {
+    let p in 0 .. T::MaxProgramHashes::get();
+
+    let program_modification_account: T::AccountId = whitelisted_caller();
+    let program = vec![0u8];
+    let program_hash = T::Hashing::hash(&program);
+    let new_program = vec![1u8];
+    let new_program_hash = T::Hashing::hash(&new_program);
+    let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+      program_pointer: program_hash,
+      program_config: b"{}".to_vec(),
+      fuel: None,
+  };  p as usize])
+  .unwrap();
+    let new_programs_info = BoundedVec::try_from(vec![ProgramInstance {
+      program_pointer: new_program_hash,
+      program_config: b"{}".to_vec(),
+      fuel: None,
+  }])
+  .unwrap();
+    Programs::<T>::insert(program_hash, ProgramInfo {bytecode: program, configuration_schema: vec![], auxiliary_data_schema: vec![], oracle_data_pointer: vec![], accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: p as u128});
+
+    // The other confirmations are already in, so this one applies the change
+    let threshold_account: T::AccountId = whitelisted_caller();
+    let validators = add_non_syncing_validators::<T>(TOTAL_SIGNERS as u32, 0);
+    <ThresholdToStash<T>>::insert(&threshold_account, &validators[(TOTAL_SIGNERS - 1) as usize]);
+    let confirmations = (0..SIGNER_THRESHOLD - 1)
+      .map(|i| account::<T::AccountId>("confirmer", i.into(), SEED))
+      .collect::<Vec<_>>();
+
+    <Registered<T>>::insert(
+        &BoundedVec::default(),
+        RegisteredInfo {
+            program_modification_account: program_modification_account.clone(),
+            programs_data: programs_info,
+            version_number: T::KeyVersionNumber::get(),
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: SIGNER_THRESHOLD,
+            total_signers: TOTAL_SIGNERS,
+            committee: validators,
+        },
+    );
+    <PendingProgramChanges<T>>::insert(
+        &BoundedVec::default(),
+        ProgramChangeDetails::<T> {
+            program_modification_account: program_modification_account.clone(),
+            programs_data: new_programs_info.clone(),
+            confirmations,
+            refusals: vec![],
+            deadline: 0u32.into(),
+        },
+    );
+  }: _(RawOrigin::Signed(threshold_account), BoundedVec::default())
+  verify {
+    assert_last_event::<T>(Event::ProgramInfoChanged(program_modification_account, new_programs_info).into());
+  }
+
+  refuse_program_change {
+    let p in 0 .. T::MaxProgramHashes::get();
+
+    let program_modification_account: T::AccountId = whitelisted_caller();
+    let new_program = vec![1u8];
+    let new_program_hash = T::Hashing::hash(&new_program);
+    let new_programs_info = BoundedVec::try_from(vec![ProgramInstance {
+      program_pointer: new_program_hash,
+      program_config: b"{}".to_vec(),
+      fuel: None,
+  };  p as usize])
+  .unwrap();
+    Programs::<T>::insert(new_program_hash, ProgramInfo {bytecode: new_program, configuration_schema: vec![], auxiliary_data_schema: vec![], oracle_data_pointer: vec![], accepts_prehashed: false, accepts_derivation_path: false, deployer: program_modification_account.clone(), ref_counter: p as u128});
+
+    // The other refusals are already in, so this one drops the change
+    let threshold_account: T::AccountId = whitelisted_caller();
+    let validators = add_non_syncing_validators::<T>(TOTAL_SIGNERS as u32, 0);
+    <ThresholdToStash<T>>::insert(&threshold_account, &validators[(TOTAL_SIGNERS - 1) as usize]);
+    let refusals = (0..SIGNER_THRESHOLD - 1)
+      .map(|i| account::<T::AccountId>("refuser", i.into(), SEED))
+      .collect::<Vec<_>>();
+
+    <Registered<T>>::insert(
+        &BoundedVec::default(),
+        RegisteredInfo {
+            program_modification_account: program_modification_account.clone(),
+            programs_data: BoundedVec::default(),
+            version_number: T::KeyVersionNumber::get(),
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: SIGNER_THRESHOLD,
+            total_signers: TOTAL_SIGNERS,
+            committee: validators,
+        },
+    );
+    <PendingProgramChanges<T>>::insert(
+        &BoundedVec::default(),
+        ProgramChangeDetails::<T> {
+            program_modification_account,
+            programs_data: new_programs_info,
+            confirmations: vec![],
+            refusals,
+            deadline: 0u32.into(),
+        },
+    );
+  }: _(RawOrigin::Signed(threshold_account), BoundedVec::default())
+  verify {
+    assert_last_event::<T>(Event::<T>::ProgramChangeRefused(BoundedVec::default()).into());
+  }
 }
 
 impl_benchmark_test_suite!(Registry, crate::mock::new_test_ext(), crate::mock::Test);
@@ -33,6 +33,45 @@
 //! `confirm_register` - Allows validator nodes to confirm that they have recieved a user's
 //! key-share. After enough succesful confirmations from validators that user will be succesfully
 //! registered.
+//!
+//! If not every validator confirms a registration within `DkgTimeout` blocks, the validators which
+//! did not confirm are reported to the slashing pallet and distributed key generation is started
+//! again. After `MaxDkgAttempts` the registration fails and its deposit is refunded. An imported
+//! key is not retried, and as the user may not have sent the keyshares, no validator is reported.
+//! `register_with_imported_key` - Allows a user to register an existing secp256k1 key, which they
+//! split into shares and send to the threshold servers themselves.
+//!
+//! When registering, a user chooses the size of the committee of validators which will hold their
+//! keyshares, and how many of them are needed to sign. These must be within the bounds set by
+//! governance in the parameters pallet's `SignersInfo`: the committee can be no larger than
+//! `total_signers`, and the threshold no lower than `threshold`. The committee is made up of the
+//! first `total_signers` of the network's signers, held in the staking extension pallet, when the
+//! registration starts, and is stored with the account so that it is unaffected by later rotations.
+//! `deregister` - Allows a user's program modification account to retire their verifying key. The
+//! threshold servers in the account's signing committee then delete their keyshares and confirm
+//! this with `confirm_deregistration`. If they have not all confirmed within
+//! `DeregistrationTimeout` blocks the deletion is requested again, up to
+//! `MaxDeregistrationAttempts` times.
+//! `change_program_instance` - Allows a user's program modification account to change the programs
+//! of their verifying key. The threshold servers in the account's signing committee check each
+//! program configuration against its schema, and the change is applied once `threshold` of them
+//! have confirmed it with `confirm_program_change`. If `threshold` of them refuse it with
+//! `refuse_program_change`, or it is not applied within `ProgramChangeTimeout` blocks, the change
+//! is dropped.
+//! `set_rate_limit` - Allows a user's program modification account to limit how many signature
+//! requests can be made with their verifying key.
+//! `set_recovery_key` - Allows a user's program modification account to register an X25519 key,
+//! which the threshold servers will encrypt their keyshares to when asked to export them. A new
+//! recovery key can only be used once the parameters pallet's `RecoveryKeyDelay` has passed, so
+//! that the user has time to notice the `RecoveryKeyChanged` event and respond.
+//!
+//! ### Runtime API
+//!
+//! `RegistryApi::registration_status` - The [RegistrationStatus] of an account's registration,
+//! including why it failed.
+//! `RegistryApi::signers` - Which members of an account's signing committee should sign a message
+//! at a given block. Clients and threshold servers both ask the chain for this, so that they always
+//! agree on the signers.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![allow(clippy::new_without_default)]
@@ -49,20 +88,35 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+pub mod migrations;
 pub mod weights;
 
+sp_api::decl_runtime_apis! {
+    /// Lets clients follow the progress of a registration
+    pub trait RegistryApi<AccountId> where AccountId: codec::Codec {
+        /// The status of the most recent registration of the given account, if there is one
+        fn registration_status(who: AccountId) -> Option<RegistrationStatus>;
+        /// The validators which should sign a message with the given verifying key at this block,
+        /// in signing order, if the key is registered
+        fn signers(verifying_key: Vec<u8>) -> Option<Vec<AccountId>>;
+    }
+}
+
 #[frame_support::pallet]
 pub mod pallet {
-    use entropy_shared::{NETWORK_PARENT_KEY, SIGNING_PARTY_SIZE, VERIFICATION_KEY_LENGTH};
+    use entropy_shared::{
+        RateLimit, SignatureScheme, X25519PublicKey, NETWORK_PARENT_KEY, SIGNER_THRESHOLD,
+        SIGNING_PARTY_SIZE, TOTAL_SIGNERS, VERIFICATION_KEY_LENGTH,
+    };
     use frame_support::{
         dispatch::{DispatchResultWithPostInfo, Pays},
         pallet_prelude::*,
-        traits::{ConstU32, IsSubType},
+        traits::{ConstU32, Currency, IsSubType, ReservableCurrency},
     };
     use frame_system::pallet_prelude::*;
     use pallet_staking_extension::ServerInfo;
     use scale_info::TypeInfo;
-    use sp_runtime::traits::{DispatchInfoOf, SignedExtension};
+    use sp_runtime::traits::{DispatchInfoOf, Saturating, SignedExtension, Zero};
     use sp_std::vec;
     use sp_std::{fmt::Debug, vec::Vec};
 
@@ -77,11 +131,13 @@ pub mod pallet {
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
     pub trait Config:
-        pallet_session::Config
+        pallet_session::Config<ValidatorId = <Self as frame_system::Config>::AccountId>
         + frame_system::Config
         + pallet_authorship::Config
         + pallet_staking_extension::Config
         + pallet_programs::Config
+        + pallet_slashing::Config
+        + pallet_parameters::Config
     {
         /// Because this pallet emits events, it depends on the runtime's definition of an event.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
@@ -91,7 +147,28 @@ pub mod pallet {
         type KeyVersionNumber: Get<u8>;
         /// The weight information of this pallet.
         type WeightInfo: WeightInfo;
+        /// The amount reserved from an account while it is registering. It is refunded once the
+        /// registration is done, has failed or is pruned
+        type RegistrationDeposit: Get<BalanceOf<Self>>;
+        /// The number of blocks the threshold servers have to confirm a distributed key generation
+        /// for a registration before it is retried
+        type DkgTimeout: Get<BlockNumberFor<Self>>;
+        /// The number of times distributed key generation is attempted for a registration before
+        /// the registration fails
+        type MaxDkgAttempts: Get<u32>;
+        /// The number of blocks the threshold servers have to confirm deleting their keyshares for
+        /// a deregistered verifying key before they are asked again
+        type DeregistrationTimeout: Get<BlockNumberFor<Self>>;
+        /// The number of times the threshold servers are asked to delete their keyshares for a
+        /// deregistered verifying key before it is no longer tracked
+        type MaxDeregistrationAttempts: Get<u32>;
+        /// The number of blocks the threshold servers have to confirm a change to the programs of
+        /// a verifying key before it is dropped
+        type ProgramChangeTimeout: Get<BlockNumberFor<Self>>;
     }
+    pub type BalanceOf<T> = <<T as pallet_programs::Config>::Currency as Currency<
+        <T as frame_system::Config>::AccountId,
+    >>::Balance;
     pub type ProgramPointers<Hash, MaxProgramHashes> = BoundedVec<Hash, MaxProgramHashes>;
     pub type VerifyingKey = BoundedVec<u8, ConstU32<VERIFICATION_KEY_LENGTH>>;
 
@@ -100,6 +177,9 @@ pub mod pallet {
     pub struct ProgramInstance<T: Config> {
         pub program_pointer: T::Hash,
         pub program_config: Vec<u8>,
+        /// The most fuel the program may use when evaluated. If not given the network's maximum
+        /// is used, and the network's maximum always applies if it is later lowered below this
+        pub fuel: Option<u64>,
     }
 
     #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug, TypeInfo)]
@@ -107,9 +187,61 @@ pub mod pallet {
     pub struct RegisteringDetails<T: Config> {
         pub program_modification_account: T::AccountId,
         pub confirmations: Vec<T::AccountId>,
+        /// The threshold servers which have refused the registration, as its program
+        /// configurations do not match their schemas
+        pub refusals: Vec<T::AccountId>,
         pub programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
         pub verifying_key: Option<VerifyingKey>,
         pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        pub status: RegistrationStatus,
+        /// The amount reserved from the registering account, which is zero once refunded
+        pub deposit: BalanceOf<T>,
+        /// The number of distributed key generations which have been started for this registration
+        pub dkg_attempts: u32,
+        /// The block by which the current distributed key generation must be confirmed
+        pub dkg_deadline: BlockNumberFor<T>,
+        /// Whether the account is importing an existing key, in which case `verifying_key` is the
+        /// key which the threshold servers must confirm
+        pub imported_key: bool,
+        /// The number of keyshares needed to sign
+        pub threshold: u8,
+        /// The number of validators which will hold a keyshare
+        pub total_signers: u8,
+        /// The validators which will hold a keyshare, chosen when the distributed key generation
+        /// is requested
+        pub committee: Vec<T::ValidatorId>,
+    }
+
+    /// Where an account is in the registration process
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+    pub enum RegistrationStatus {
+        /// The registration has been submitted, and is waiting for the threshold servers to be
+        /// asked to generate a key
+        Submitted,
+        /// The threshold servers were asked to start distributed key generation at this block
+        DkgStarted(u32),
+        /// Some of the threshold servers have confirmed that they hold a keyshare
+        Confirming { confirmations: u32, required: u32 },
+        /// The registration failed. The account should call `prune_registration` before
+        /// registering again
+        Failed(RegistrationFailure),
+        /// The account was registered with this verifying key. Finished registrations are not
+        /// kept in [Registering], this is only given by [Pallet::registration_status]
+        Done(VerifyingKey),
+    }
+
+    /// Why a registration failed
+    #[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+    pub enum RegistrationFailure {
+        /// The threshold servers did not all generate the same verifying key
+        MismatchedVerifyingKeys,
+        /// Not every threshold server confirmed a distributed key generation in time, for the
+        /// maximum number of attempts
+        DkgTimedOut,
+        /// A threshold server in the signing committee found that a program configuration does
+        /// not match the configuration schema of its program
+        InvalidProgramConfig,
     }
 
     #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug, TypeInfo)]
@@ -118,6 +250,49 @@ pub mod pallet {
         pub programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
         pub program_modification_account: T::AccountId,
         pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        /// The number of keyshares needed to sign
+        pub threshold: u8,
+        /// The number of validators which hold a keyshare
+        pub total_signers: u8,
+        /// The validators which hold a keyshare
+        pub committee: Vec<T::ValidatorId>,
+    }
+    /// A change to the programs of a registered verifying key, which is waiting for the threshold
+    /// servers in its signing committee to check the program configurations against their schemas
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug, TypeInfo)]
+    #[scale_info(skip_type_params(T))]
+    pub struct ProgramChangeDetails<T: Config> {
+        /// The program modification account which requested the change
+        pub program_modification_account: T::AccountId,
+        pub programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+        /// The threshold servers which have confirmed the change
+        pub confirmations: Vec<T::AccountId>,
+        /// The threshold servers which have refused the change
+        pub refusals: Vec<T::AccountId>,
+        /// The block by which the change must have been confirmed, after which it is dropped
+        pub deadline: BlockNumberFor<T>,
+    }
+    /// The progress of deleting the keyshares of a deregistered verifying key
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug, TypeInfo)]
+    #[scale_info(skip_type_params(T))]
+    pub struct DeregisteringDetails<T: Config> {
+        /// The validators which hold a keyshare, and so must confirm deleting it
+        pub committee: Vec<T::ValidatorId>,
+        /// The threshold servers which have confirmed deleting their keyshare
+        pub confirmations: Vec<T::AccountId>,
+        /// The number of times the threshold servers have been asked to delete their keyshares
+        pub attempts: u32,
+        /// The block by which every member of the committee must have confirmed
+        pub deadline: BlockNumberFor<T>,
+    }
+    /// A key which exported keyshares are encrypted to
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebugNoBound, TypeInfo)]
+    #[scale_info(skip_type_params(T))]
+    pub struct RecoveryKeyInfo<T: Config> {
+        pub recovery_key: X25519PublicKey,
+        /// The first block at which keyshares can be exported to the recovery key
+        pub active_from: BlockNumberFor<T>,
     }
     /// Details of status of jump starting the network
     #[derive(
@@ -166,21 +341,70 @@ pub mod pallet {
                         programs_data: BoundedVec::default(),
                         program_modification_account: account_info.0.clone(),
                         version_number: T::KeyVersionNumber::get(),
+                        signature_scheme: SignatureScheme::Ecdsa,
+                        threshold: SIGNER_THRESHOLD,
+                        total_signers: TOTAL_SIGNERS,
+                        // Every signer holds a keyshare of the accounts set up at genesis
+                        committee: pallet_staking_extension::Pallet::<T>::signers(),
                     },
                 );
             }
         }
     }
 
+    /// The current storage version
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(7);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(block_number: BlockNumberFor<T>) -> Weight {
+            // The offchain worker of this block asks the threshold servers to start distributed key
+            // generation for the accounts which registered in the previous block
+            let sig_request_accounts = Self::dkg(block_number.saturating_sub(1u32.into()));
+            let converted_block_number: u32 =
+                BlockNumberFor::<T>::try_into(block_number).unwrap_or_default();
+
+            for encoded_sig_req_account in &sig_request_accounts {
+                let Ok(sig_req_account) = T::AccountId::decode(&mut &encoded_sig_req_account[..])
+                else {
+                    continue;
+                };
+                Registering::<T>::mutate(&sig_req_account, |maybe_registering_details| {
+                    if let Some(registering_details) = maybe_registering_details {
+                        if registering_details.status == RegistrationStatus::Submitted {
+                            registering_details.status =
+                                RegistrationStatus::DkgStarted(converted_block_number);
+                        }
+                    }
+                });
+            }
+
+            let accounts = sig_request_accounts.len() as u64;
+            T::DbWeight::get()
+                .reads_writes(accounts.saturating_add(1), accounts)
+                .saturating_add(Self::expire_dkg_attempts(block_number))
+                .saturating_add(Self::expire_deregistrations(block_number))
+                .saturating_add(Self::expire_program_changes(block_number))
+        }
+    }
+
     #[pallet::storage]
     #[pallet::getter(fn registering)]
     pub type Registering<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
 
+    /// The verifying key of the most recent registration of an account which has finished
+    /// registering, so that its registration status can still be queried.
+    #[pallet::storage]
+    #[pallet::getter(fn last_registered)]
+    pub type LastRegistered<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, VerifyingKey, OptionQuery>;
+
     /// Used for triggering a network wide distributed key generation request via an offchain
     /// worker.
     #[pallet::storage]
@@ -195,11 +419,67 @@ pub mod pallet {
     pub type Dkg<T: Config> =
         StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<Vec<u8>>, ValueQuery>;
 
+    /// Accounts whose distributed key generation must be confirmed by the given block, after which
+    /// it is retried or the registration fails.
+    #[pallet::storage]
+    #[pallet::getter(fn dkg_deadlines)]
+    pub type DkgDeadlines<T: Config> =
+        StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<T::AccountId>, ValueQuery>;
+
     #[pallet::storage]
     #[pallet::getter(fn registered)]
     pub type Registered<T: Config> =
         StorageMap<_, Blake2_128Concat, VerifyingKey, RegisteredInfo<T>, OptionQuery>;
 
+    /// The account which most recently started importing a verifying key, so that two accounts
+    /// cannot import the same key at once. The entry is only removed once the import finishes or
+    /// the registration is pruned, so [Pallet::key_import_in_progress] checks that it is ongoing.
+    #[pallet::storage]
+    #[pallet::getter(fn key_imports)]
+    pub type KeyImports<T: Config> =
+        StorageMap<_, Blake2_128Concat, VerifyingKey, T::AccountId, OptionQuery>;
+
+    /// Used to trigger keyshare deletion for deregistered verifying keys via an offchain worker.
+    #[pallet::storage]
+    #[pallet::getter(fn deregistrations)]
+    pub type Deregistrations<T: Config> =
+        StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<VerifyingKey>, ValueQuery>;
+
+    /// Verifying keys which have been deregistered, but which not every threshold server in the
+    /// signing committee has yet confirmed deleting its keyshare for.
+    #[pallet::storage]
+    #[pallet::getter(fn deregistering)]
+    pub type Deregistering<T: Config> =
+        StorageMap<_, Blake2_128Concat, VerifyingKey, DeregisteringDetails<T>, OptionQuery>;
+
+    /// Deregistered verifying keys whose keyshare deletion must be confirmed by the given block,
+    /// after which it is requested again or no longer tracked.
+    #[pallet::storage]
+    #[pallet::getter(fn deregistration_deadlines)]
+    pub type DeregistrationDeadlines<T: Config> =
+        StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<VerifyingKey>, ValueQuery>;
+
+    /// Used to ask the threshold servers to check changes to the programs of verifying keys via an
+    /// offchain worker.
+    #[pallet::storage]
+    #[pallet::getter(fn program_changes)]
+    pub type ProgramChanges<T: Config> =
+        StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<VerifyingKey>, ValueQuery>;
+
+    /// Changes to the programs of verifying keys which have not yet been confirmed by `threshold`
+    /// of the signing committee.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_program_changes)]
+    pub type PendingProgramChanges<T: Config> =
+        StorageMap<_, Blake2_128Concat, VerifyingKey, ProgramChangeDetails<T>, OptionQuery>;
+
+    /// Verifying keys whose program change must be confirmed by the given block, after which it is
+    /// dropped.
+    #[pallet::storage]
+    #[pallet::getter(fn program_change_deadlines)]
+    pub type ProgramChangeDeadlines<T: Config> =
+        StorageMap<_, Blake2_128Concat, BlockNumberFor<T>, Vec<VerifyingKey>, ValueQuery>;
+
     /// Mapping of program_modification accounts to verifying keys they can control
     #[pallet::storage]
     #[pallet::getter(fn modifiable_keys)]
@@ -211,6 +491,20 @@ pub mod pallet {
         ValueQuery,
     >;
 
+    /// Signature request rate limits for verifying keys. Keys without an entry here are limited by
+    /// the `RequestLimit` in the parameters pallet, with a window of a single block.
+    #[pallet::storage]
+    #[pallet::getter(fn rate_limits)]
+    pub type RateLimits<T: Config> =
+        StorageMap<_, Blake2_128Concat, VerifyingKey, RateLimit, OptionQuery>;
+
+    /// X25519 public keys which the threshold servers encrypt keyshares to when exporting them for
+    /// recovery. Keyshares of verifying keys without an entry here cannot be exported.
+    #[pallet::storage]
+    #[pallet::getter(fn recovery_keys)]
+    pub type RecoveryKeys<T: Config> =
+        StorageMap<_, Blake2_128Concat, VerifyingKey, RecoveryKeyInfo<T>, OptionQuery>;
+
     /// A concept of what progress status the jumpstart is
     #[pallet::storage]
     #[pallet::getter(fn jump_start_progress)]
@@ -229,12 +523,18 @@ pub mod pallet {
         JumpStartConfirmation(T::ValidatorId, u8),
         /// An account has signaled to be registered. [signature request account]
         SignalRegister(T::AccountId),
+        /// An account has signaled to be registered with an existing key. [signature request
+        /// account, verifying_key]
+        SignalKeyImport(T::AccountId, VerifyingKey),
         /// An account has been registered. [who, verifying_key]
         RecievedConfirmation(T::AccountId, VerifyingKey),
         /// An account has been registered. \[who, verifying_key]
         AccountRegistered(T::AccountId, VerifyingKey),
-        /// An account registration has failed
-        FailedRegistration(T::AccountId),
+        /// An account registration has failed [who, reason]
+        FailedRegistration(T::AccountId, RegistrationFailure),
+        /// Distributed key generation for an account timed out and has been started again [who,
+        /// attempt]
+        DkgRetried(T::AccountId, u32),
         /// An account cancelled their registration
         RegistrationCancelled(T::AccountId),
         /// An account hash changed their program info [who, new_program_instance]
@@ -243,6 +543,42 @@ pub mod pallet {
         ProgramModificationAccountChanged(T::AccountId, T::AccountId, VerifyingKey),
         /// An account has been registered. [who, block_number, failures]
         ConfirmedDone(T::AccountId, BlockNumberFor<T>, Vec<u32>),
+        /// An account has been deregistered. [who, verifying_key]
+        AccountDeregistered(T::AccountId, VerifyingKey),
+        /// A threshold server has deleted its keyshare for a deregistered account.
+        /// [threshold_account, verifying_key]
+        KeyshareDeletionConfirmed(T::AccountId, VerifyingKey),
+        /// Every threshold server has deleted its keyshare for a deregistered account, so the key
+        /// can no longer be used. [verifying_key]
+        KeysharesDeleted(VerifyingKey),
+        /// Not every threshold server confirmed deleting its keyshare for a deregistered account
+        /// in time, so they have been asked again. [verifying_key, attempt]
+        DeregistrationRetried(VerifyingKey, u32),
+        /// Not every threshold server confirmed deleting its keyshare for a deregistered account
+        /// after `MaxDeregistrationAttempts`, so it is no longer tracked. [verifying_key,
+        /// unconfirmed_validators]
+        DeregistrationExpired(VerifyingKey, Vec<T::ValidatorId>),
+        /// The rate limit of a verifying key has changed [verifying_key, rate_limit]
+        RateLimitChanged(VerifyingKey, Option<RateLimit>),
+        /// The recovery key of a verifying key has changed [verifying_key, recovery_key_info]
+        RecoveryKeyChanged(VerifyingKey, Option<RecoveryKeyInfo<T>>),
+        /// A threshold server has refused a registration, which fails once enough of the signing
+        /// committee have refused it [threshold_account, who]
+        RegistrationRefused(T::AccountId, T::AccountId),
+        /// An account has asked to change the programs of a verifying key, which the threshold
+        /// servers in its signing committee must confirm [who, verifying_key,
+        /// new_program_instance]
+        ProgramChangeRequested(
+            T::AccountId,
+            VerifyingKey,
+            BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+        ),
+        /// `threshold` of the signing committee refused a change to the programs of a verifying
+        /// key, so it has been dropped [verifying_key]
+        ProgramChangeRefused(VerifyingKey),
+        /// Not enough of the signing committee confirmed a change to the programs of a verifying
+        /// key in time, so it has been dropped [verifying_key]
+        ProgramChangeExpired(VerifyingKey),
     }
 
     // Errors inform users that something went wrong.
@@ -267,6 +603,30 @@ pub mod pallet {
         JumpStartProgressNotReady,
         JumpStartNotInProgress,
         NoRegisteringFromParentKey,
+        InvalidRateLimit,
+        /// The rate limit allows more requests than the network's maximum
+        RateLimitTooHigh,
+        NotDeregistering,
+        RegistrationNotInProgress,
+        AlreadyRegistered,
+        /// The threshold is lower than the network's minimum
+        ThresholdTooLow,
+        /// The threshold is greater than the number of signers
+        ThresholdGreaterThanSigners,
+        /// There are more signers than the network's maximum, or the number of validators
+        TooManySigners,
+        /// The fuel given for a program is zero or greater than the network's maximum
+        InvalidProgramFuel,
+        /// The verifying key was deregistered, and not every keyshare has been deleted yet
+        KeyDeregistering,
+        /// Another account is already importing the verifying key
+        KeyImportInProgress,
+        /// The threshold server has already refused the registration
+        AlreadyRefused,
+        /// The programs of the verifying key are already being changed
+        ProgramChangeInProgress,
+        /// There is no pending change to the programs of the verifying key
+        NoProgramChange,
     }
 
     /// Allows anyone to create a parent key for the network if the network is read and a parent key
@@ -367,6 +727,10 @@ pub mod pallet {
                     confirmations: vec![],
                     verifying_key: jump_start_info.verifying_key,
                 });
+                // The threshold servers generate the network parent key with the current threshold
+                pallet_staking_extension::ParentKeyThreshold::<T>::put(
+                    pallet_parameters::Pallet::<T>::signers_info().threshold,
+                );
 
                 Self::deposit_event(Event::FinishedNetworkJumpStart());
 
@@ -397,6 +761,12 @@ pub mod pallet {
         ///
         /// The caller provides an initial program pointer.
         ///
+        /// The `signature_scheme` decides which kind of threshold key is generated for the account,
+        /// and so which kind of signatures it will get back when signing.
+        ///
+        /// The key is shared amongst a committee of `total_signers` validators, `threshold` of
+        /// which are needed to sign.
+        ///
         /// Note that a user needs to be confirmed by validators through the
         /// [`Self::confirm_register`] extrinsic before they can be considered as registered on the
         /// network.
@@ -408,50 +778,20 @@ pub mod pallet {
             origin: OriginFor<T>,
             program_modification_account: T::AccountId,
             programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+            signature_scheme: SignatureScheme,
+            threshold: u8,
+            total_signers: u8,
         ) -> DispatchResultWithPostInfo {
             let sig_req_account = ensure_signed(origin)?;
-            let encoded_sig_req_account = sig_req_account.encode();
-            ensure!(
-                encoded_sig_req_account != NETWORK_PARENT_KEY.encode(),
-                Error::<T>::NoRegisteringFromParentKey
-            );
-            ensure!(
-                !Registering::<T>::contains_key(&sig_req_account),
-                Error::<T>::AlreadySubmitted
-            );
-            ensure!(!programs_data.is_empty(), Error::<T>::NoProgramSet);
-            let block_number = <frame_system::Pallet<T>>::block_number();
-            // Change program ref counter
-            for program_instance in &programs_data {
-                pallet_programs::Programs::<T>::try_mutate(
-                    program_instance.program_pointer,
-                    |maybe_program_info| {
-                        if let Some(program_info) = maybe_program_info {
-                            program_info.ref_counter = program_info.ref_counter.saturating_add(1);
-                            Ok(())
-                        } else {
-                            Err(Error::<T>::NoProgramSet)
-                        }
-                    },
-                )?;
-            }
-
-            Dkg::<T>::try_mutate(block_number, |messages| -> Result<_, DispatchError> {
-                messages.push(encoded_sig_req_account);
-                Ok(())
-            })?;
-
-            // Put account into a registering state
-            Registering::<T>::insert(
+            Self::start_registration(
                 &sig_req_account,
-                RegisteringDetails::<T> {
-                    program_modification_account,
-                    confirmations: vec![],
-                    programs_data: programs_data.clone(),
-                    verifying_key: None,
-                    version_number: T::KeyVersionNumber::get(),
-                },
-            );
+                program_modification_account,
+                &programs_data,
+                signature_scheme,
+                threshold,
+                total_signers,
+                None,
+            )?;
             Self::deposit_event(Event::SignalRegister(sig_req_account));
 
             Ok(Some(<T as Config>::WeightInfo::register(programs_data.len() as u32)).into())
@@ -466,22 +806,29 @@ pub mod pallet {
             let who = ensure_signed(origin)?;
             let registering_info = Self::registering(&who).ok_or(Error::<T>::NotRegistering)?;
             for program_instance in &registering_info.programs_data {
-                pallet_programs::Programs::<T>::mutate(
+                pallet_programs::Pallet::<T>::remove_program_reference(
                     program_instance.program_pointer,
-                    |maybe_program_info| {
-                        if let Some(program_info) = maybe_program_info {
-                            program_info.ref_counter = program_info.ref_counter.saturating_sub(1);
-                        }
-                    },
                 );
             }
+            <T as pallet_programs::Config>::Currency::unreserve(&who, registering_info.deposit);
             let program_length = registering_info.programs_data.len();
+            if let Some(verifying_key) =
+                registering_info.verifying_key.filter(|_| registering_info.imported_key)
+            {
+                if Self::key_imports(&verifying_key).as_ref() == Some(&who) {
+                    KeyImports::<T>::remove(&verifying_key);
+                }
+            }
             Registering::<T>::remove(&who);
             Self::deposit_event(Event::RegistrationCancelled(who));
             Ok(Some(<T as Config>::WeightInfo::register(program_length as u32)).into())
         }
 
         /// Allows a user's program modification account to change their program pointer
+        ///
+        /// Checking a configuration against its schema is too costly to do on-chain, so the change
+        /// only takes effect once `threshold` of the threshold servers in the account's signing
+        /// committee have checked and confirmed it with [`Self::confirm_program_change`].
         #[pallet::call_index(4)]
         #[pallet::weight({
              <T as Config>::WeightInfo::change_program_instance(<T as Config>::MaxProgramHashes::get(), <T as Config>::MaxProgramHashes::get())
@@ -493,51 +840,57 @@ pub mod pallet {
         ) -> DispatchResultWithPostInfo {
             let who = ensure_signed(origin)?;
             ensure!(!new_program_instance.is_empty(), Error::<T>::NoProgramSet);
-            // change program ref counter
+            Self::check_program_fuel(&new_program_instance)?;
+            let registered_details =
+                Self::registered(&verifying_key).ok_or(Error::<T>::NotRegistered)?;
+            ensure!(
+                who == registered_details.program_modification_account,
+                Error::<T>::NotAuthorized
+            );
+            ensure!(
+                !PendingProgramChanges::<T>::contains_key(&verifying_key),
+                Error::<T>::ProgramChangeInProgress
+            );
+            // The new programs are referenced while the change is pending, so that they cannot be
+            // removed before it is applied
             for program_instance in &new_program_instance {
-                pallet_programs::Programs::<T>::try_mutate(
+                pallet_programs::Pallet::<T>::add_program_reference(
                     program_instance.program_pointer,
-                    |maybe_program_info| {
-                        if let Some(program_info) = maybe_program_info {
-                            program_info.ref_counter = program_info.ref_counter.saturating_add(1);
-                            Ok(())
-                        } else {
-                            Err(Error::<T>::NoProgramSet)
-                        }
-                    },
+                )
+                .map_err(|_| Error::<T>::NoProgramSet)?;
+                pallet_programs::Pallet::<T>::check_program_config(
+                    program_instance.program_pointer,
+                    &program_instance.program_config,
                 )?;
             }
-            let mut old_programs_length = 0;
-            let programs_data =
-                Registered::<T>::try_mutate(&verifying_key, |maybe_registered_details| {
-                    if let Some(registered_details) = maybe_registered_details {
-                        ensure!(
-                            who == registered_details.program_modification_account,
-                            Error::<T>::NotAuthorized
-                        );
-                        // decrement ref counter of not used programs
-                        for program_instance in &registered_details.programs_data {
-                            pallet_programs::Programs::<T>::mutate(
-                                program_instance.program_pointer,
-                                |maybe_program_info| {
-                                    if let Some(program_info) = maybe_program_info {
-                                        program_info.ref_counter =
-                                            program_info.ref_counter.saturating_sub(1);
-                                    }
-                                },
-                            );
-                        }
-                        old_programs_length = registered_details.programs_data.len();
-                        registered_details.programs_data = new_program_instance.clone();
-                        Ok(new_program_instance)
-                    } else {
-                        Err(Error::<T>::NotRegistered)
-                    }
-                })?;
-            Self::deposit_event(Event::ProgramInfoChanged(who, programs_data.clone()));
+
+            let block_number = <frame_system::Pallet<T>>::block_number();
+            let deadline = Self::program_change_deadline(block_number);
+            PendingProgramChanges::<T>::insert(
+                &verifying_key,
+                ProgramChangeDetails::<T> {
+                    program_modification_account: who.clone(),
+                    programs_data: new_program_instance.clone(),
+                    confirmations: vec![],
+                    refusals: vec![],
+                    deadline,
+                },
+            );
+            ProgramChanges::<T>::mutate(block_number, |verifying_keys| {
+                verifying_keys.push(verifying_key.clone())
+            });
+            ProgramChangeDeadlines::<T>::mutate(deadline, |verifying_keys| {
+                verifying_keys.push(verifying_key.clone())
+            });
+
+            Self::deposit_event(Event::ProgramChangeRequested(
+                who,
+                verifying_key,
+                new_program_instance.clone(),
+            ));
             Ok(Some(<T as Config>::WeightInfo::change_program_instance(
-                programs_data.len() as u32,
-                old_programs_length as u32,
+                new_program_instance.len() as u32,
+                registered_details.programs_data.len() as u32,
             ))
             .into())
         }
@@ -600,8 +953,8 @@ pub mod pallet {
         /// Allows validators to confirm that they have received a key-share from a user that is
         /// in the process of registering.
         ///
-        /// After a validator from each partition confirms they have a keyshare the user will be
-        /// considered as registered on the network.
+        /// After every validator in the account's signing committee confirms they have a keyshare
+        /// the user will be considered as registered on the network.
         #[pallet::call_index(6)]
         #[pallet::weight({
             let weight =
@@ -616,24 +969,42 @@ pub mod pallet {
             verifying_key: BoundedVec<u8, ConstU32<VERIFICATION_KEY_LENGTH>>,
         ) -> DispatchResultWithPostInfo {
             let ts_server_account = ensure_signed(origin)?;
-            ensure!(
-                verifying_key.len() as u32 == VERIFICATION_KEY_LENGTH,
-                Error::<T>::MismatchedVerifyingKeyLength
-            );
             let validator_stash =
                 pallet_staking_extension::Pallet::<T>::threshold_to_stash(&ts_server_account)
                     .ok_or(Error::<T>::NoThresholdKey)?;
 
             let mut registering_info =
                 Self::registering(&sig_req_account).ok_or(Error::<T>::NotRegistering)?;
+            ensure!(
+                !matches!(registering_info.status, RegistrationStatus::Failed(_)),
+                Error::<T>::RegistrationNotInProgress
+            );
+            ensure!(
+                verifying_key.len() as u32
+                    == registering_info.signature_scheme.verifying_key_length(),
+                Error::<T>::MismatchedVerifyingKeyLength
+            );
+            ensure!(!Registered::<T>::contains_key(&verifying_key), Error::<T>::AlreadyRegistered);
 
-            let validators = pallet_session::Pallet::<T>::validators();
-            ensure!(validators.contains(&validator_stash), Error::<T>::NotValidator);
+            ensure!(
+                registering_info.committee.contains(&validator_stash),
+                Error::<T>::NotValidator
+            );
             let confirmation_length = registering_info.confirmations.len() as u32;
             ensure!(
                 !registering_info.confirmations.contains(&ts_server_account),
                 Error::<T>::AlreadyConfirmed
             );
+            ensure!(
+                !registering_info.refusals.contains(&ts_server_account),
+                Error::<T>::AlreadyRefused
+            );
+            // An imported key is known in advance, so any other key is rejected outright
+            ensure!(
+                !registering_info.imported_key
+                    || registering_info.verifying_key.as_ref() == Some(&verifying_key),
+                Error::<T>::MismatchedVerifyingKey
+            );
 
             // if no one has sent in a verifying key yet, use current
             if registering_info.verifying_key.is_none() {
@@ -643,11 +1014,14 @@ pub mod pallet {
             let registering_info_verifying_key =
                 registering_info.verifying_key.clone().ok_or(Error::<T>::NoVerifyingKey)?;
 
-            if registering_info.confirmations.len() == validators.len() - 1 {
+            if registering_info.confirmations.len() == registering_info.committee.len() - 1 {
                 // If verifying key does not match for everyone, registration failed
                 if registering_info_verifying_key != verifying_key {
-                    Registering::<T>::remove(&sig_req_account);
-                    Self::deposit_event(Event::FailedRegistration(sig_req_account));
+                    let failure = RegistrationFailure::MismatchedVerifyingKeys;
+                    Self::refund_registration_deposit(&sig_req_account, &mut registering_info);
+                    registering_info.status = RegistrationStatus::Failed(failure.clone());
+                    Registering::<T>::insert(&sig_req_account, registering_info);
+                    Self::deposit_event(Event::FailedRegistration(sig_req_account, failure));
                     return Ok(Some(
                         <T as Config>::WeightInfo::confirm_register_failed_registering(
                             confirmation_length,
@@ -667,12 +1041,23 @@ pub mod pallet {
                 Registered::<T>::insert(
                     &verifying_key,
                     RegisteredInfo {
-                        programs_data: registering_info.programs_data,
-                        program_modification_account: registering_info.program_modification_account,
+                        programs_data: registering_info.programs_data.clone(),
+                        program_modification_account: registering_info
+                            .program_modification_account
+                            .clone(),
                         version_number: registering_info.version_number,
+                        signature_scheme: registering_info.signature_scheme,
+                        threshold: registering_info.threshold,
+                        total_signers: registering_info.total_signers,
+                        committee: registering_info.committee.clone(),
                     },
                 );
+                Self::refund_registration_deposit(&sig_req_account, &mut registering_info);
                 Registering::<T>::remove(&sig_req_account);
+                if registering_info.imported_key {
+                    KeyImports::<T>::remove(&verifying_key);
+                }
+                LastRegistered::<T>::insert(&sig_req_account, &verifying_key);
 
                 let weight =
                     <T as Config>::WeightInfo::confirm_register_registered(confirmation_length);
@@ -682,8 +1067,11 @@ pub mod pallet {
             } else {
                 // If verifying key does not match for everyone, registration failed
                 if registering_info_verifying_key != verifying_key {
-                    Registering::<T>::remove(&sig_req_account);
-                    Self::deposit_event(Event::FailedRegistration(sig_req_account));
+                    let failure = RegistrationFailure::MismatchedVerifyingKeys;
+                    Self::refund_registration_deposit(&sig_req_account, &mut registering_info);
+                    registering_info.status = RegistrationStatus::Failed(failure.clone());
+                    Registering::<T>::insert(&sig_req_account, registering_info);
+                    Self::deposit_event(Event::FailedRegistration(sig_req_account, failure));
                     return Ok(Some(
                         <T as Config>::WeightInfo::confirm_register_failed_registering(
                             confirmation_length,
@@ -692,6 +1080,10 @@ pub mod pallet {
                     .into());
                 }
                 registering_info.confirmations.push(ts_server_account);
+                registering_info.status = RegistrationStatus::Confirming {
+                    confirmations: registering_info.confirmations.len() as u32,
+                    required: registering_info.committee.len() as u32,
+                };
                 Registering::<T>::insert(&sig_req_account, registering_info);
                 Self::deposit_event(Event::RecievedConfirmation(
                     sig_req_account,
@@ -703,6 +1095,394 @@ pub mod pallet {
                 .into())
             }
         }
+
+        /// Allows a user's program modification account to set how many signature requests can
+        /// be made with their verifying key over a window of blocks.
+        ///
+        /// Setting `None` removes the limit, so the network wide `RequestLimit` applies again. A
+        /// limit may allow no more than the parameters pallet's `MaxRateLimitRequests` in a window.
+        #[pallet::call_index(7)]
+        #[pallet::weight({
+            <T as Config>::WeightInfo::set_rate_limit()
+        })]
+        pub fn set_rate_limit(
+            origin: OriginFor<T>,
+            verifying_key: VerifyingKey,
+            rate_limit: Option<RateLimit>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let registered_details =
+                Self::registered(&verifying_key).ok_or(Error::<T>::NotRegistered)?;
+            ensure!(
+                who == registered_details.program_modification_account,
+                Error::<T>::NotAuthorized
+            );
+
+            match rate_limit {
+                Some(rate_limit) => {
+                    ensure!(rate_limit.window_blocks > 0, Error::<T>::InvalidRateLimit);
+                    ensure!(
+                        rate_limit.max_requests
+                            <= pallet_parameters::Pallet::<T>::max_rate_limit_requests(),
+                        Error::<T>::RateLimitTooHigh
+                    );
+                    RateLimits::<T>::insert(&verifying_key, rate_limit);
+                },
+                None => RateLimits::<T>::remove(&verifying_key),
+            }
+
+            Self::deposit_event(Event::RateLimitChanged(verifying_key, rate_limit));
+            Ok(())
+        }
+
+        /// Allows a user's program modification account to deregister their verifying key.
+        ///
+        /// The key is removed from the registry straight away, and the threshold servers are
+        /// asked to delete their keyshares via an offchain worker. Each one in the account's
+        /// signing committee confirms the deletion with `confirm_deregistration`.
+        #[pallet::call_index(8)]
+        #[pallet::weight({
+            <T as Config>::WeightInfo::deregister(<T as Config>::MaxProgramHashes::get())
+        })]
+        pub fn deregister(
+            origin: OriginFor<T>,
+            verifying_key: VerifyingKey,
+        ) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+            let registered_details =
+                Self::registered(&verifying_key).ok_or(Error::<T>::NotRegistered)?;
+            ensure!(
+                who == registered_details.program_modification_account,
+                Error::<T>::NotAuthorized
+            );
+
+            // decrement ref counter of the programs the account was using
+            for program_instance in &registered_details.programs_data {
+                pallet_programs::Pallet::<T>::remove_program_reference(
+                    program_instance.program_pointer,
+                );
+            }
+            // and of those it was changing to, as the change is dropped with the key
+            if let Some(program_change) = PendingProgramChanges::<T>::take(&verifying_key) {
+                for program_instance in &program_change.programs_data {
+                    pallet_programs::Pallet::<T>::remove_program_reference(
+                        program_instance.program_pointer,
+                    );
+                }
+            }
+
+            ModifiableKeys::<T>::mutate(&who, |verifying_keys| {
+                verifying_keys.retain(|k| *k != verifying_key)
+            });
+            Registered::<T>::remove(&verifying_key);
+            RateLimits::<T>::remove(&verifying_key);
+            RecoveryKeys::<T>::remove(&verifying_key);
+
+            let block_number = <frame_system::Pallet<T>>::block_number();
+            let deadline = Self::deregistration_deadline(block_number);
+            Deregistering::<T>::insert(
+                &verifying_key,
+                DeregisteringDetails::<T> {
+                    committee: registered_details.committee.clone(),
+                    confirmations: vec![],
+                    attempts: 1,
+                    deadline,
+                },
+            );
+            Deregistrations::<T>::mutate(block_number, |verifying_keys| {
+                verifying_keys.push(verifying_key.clone())
+            });
+            DeregistrationDeadlines::<T>::mutate(deadline, |verifying_keys| {
+                verifying_keys.push(verifying_key.clone())
+            });
+
+            Self::deposit_event(Event::AccountDeregistered(who, verifying_key));
+            Ok(Some(<T as Config>::WeightInfo::deregister(
+                registered_details.programs_data.len() as u32
+            ))
+            .into())
+        }
+
+        /// Allows validators to confirm that they have deleted their keyshare for a deregistered
+        /// verifying key.
+        ///
+        /// Once every member of the account's signing committee has confirmed, the key is no
+        /// longer tracked as deregistering.
+        #[pallet::call_index(9)]
+        #[pallet::weight({
+            let weight =
+                <T as Config>::WeightInfo::confirm_deregistration(pallet_session::Pallet::<T>::validators().len() as u32);
+            (weight, DispatchClass::Operational, Pays::No)
+        })]
+        pub fn confirm_deregistration(
+            origin: OriginFor<T>,
+            verifying_key: VerifyingKey,
+        ) -> DispatchResultWithPostInfo {
+            let ts_server_account = ensure_signed(origin)?;
+            let validator_stash =
+                pallet_staking_extension::Pallet::<T>::threshold_to_stash(&ts_server_account)
+                    .ok_or(Error::<T>::NoThresholdKey)?;
+
+            let mut deregistering_details =
+                Self::deregistering(&verifying_key).ok_or(Error::<T>::NotDeregistering)?;
+            ensure!(
+                deregistering_details.committee.contains(&validator_stash),
+                Error::<T>::NotValidator
+            );
+            ensure!(
+                !deregistering_details.confirmations.contains(&ts_server_account),
+                Error::<T>::AlreadyConfirmed
+            );
+            let confirmation_length = deregistering_details.confirmations.len() as u32;
+
+            deregistering_details.confirmations.push(ts_server_account.clone());
+            Self::deposit_event(Event::KeyshareDeletionConfirmed(
+                ts_server_account,
+                verifying_key.clone(),
+            ));
+
+            if deregistering_details.confirmations.len() >= deregistering_details.committee.len() {
+                Deregistering::<T>::remove(&verifying_key);
+                Self::deposit_event(Event::KeysharesDeleted(verifying_key));
+            } else {
+                Deregistering::<T>::insert(&verifying_key, deregistering_details);
+            }
+
+            Ok(Some(<T as Config>::WeightInfo::confirm_deregistration(confirmation_length)).into())
+        }
+
+        /// Allows a user to register an existing secp256k1 key with the Entropy network, rather
+        /// than having the threshold servers generate a new one.
+        ///
+        /// Once this has been included in a block, the user splits the private key into shares
+        /// and sends one to each threshold server, which then reshare it among themselves. The
+        /// threshold servers can only confirm the registration with the given `verifying_key`,
+        /// which is the compressed public key of the imported private key.
+        ///
+        /// As with [`Self::register`], the key is reshared amongst a committee of `total_signers`
+        /// validators, `threshold` of which are needed to sign.
+        #[pallet::call_index(10)]
+        #[pallet::weight({
+            <T as Config>::WeightInfo::register_with_imported_key(<T as Config>::MaxProgramHashes::get())
+        })]
+        pub fn register_with_imported_key(
+            origin: OriginFor<T>,
+            program_modification_account: T::AccountId,
+            programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+            verifying_key: VerifyingKey,
+            threshold: u8,
+            total_signers: u8,
+        ) -> DispatchResultWithPostInfo {
+            let sig_req_account = ensure_signed(origin)?;
+            ensure!(
+                verifying_key.len() as u32 == SignatureScheme::Ecdsa.verifying_key_length(),
+                Error::<T>::MismatchedVerifyingKeyLength
+            );
+            ensure!(!Registered::<T>::contains_key(&verifying_key), Error::<T>::AlreadyRegistered);
+            ensure!(
+                !Deregistering::<T>::contains_key(&verifying_key),
+                Error::<T>::KeyDeregistering
+            );
+            ensure!(!Self::key_import_in_progress(&verifying_key), Error::<T>::KeyImportInProgress);
+            Self::start_registration(
+                &sig_req_account,
+                program_modification_account,
+                &programs_data,
+                SignatureScheme::Ecdsa,
+                threshold,
+                total_signers,
+                Some(verifying_key.clone()),
+            )?;
+            KeyImports::<T>::insert(&verifying_key, &sig_req_account);
+            Self::deposit_event(Event::SignalKeyImport(sig_req_account, verifying_key));
+
+            Ok(Some(<T as Config>::WeightInfo::register_with_imported_key(
+                programs_data.len() as u32
+            ))
+            .into())
+        }
+
+        /// Allows a user's program modification account to set the X25519 public key which the
+        /// threshold servers encrypt their keyshares to when asked to export them, so that the
+        /// user can recover their private key should the network become unavailable.
+        ///
+        /// A new recovery key can only be used after the parameters pallet's `RecoveryKeyDelay`,
+        /// so that a recovery key set by someone else can be noticed and removed before any
+        /// keyshares are exported to it. Setting `None` removes the recovery key straight away, so
+        /// keyshares can no longer be exported.
+        #[pallet::call_index(11)]
+        #[pallet::weight({
+            <T as Config>::WeightInfo::set_recovery_key()
+        })]
+        pub fn set_recovery_key(
+            origin: OriginFor<T>,
+            verifying_key: VerifyingKey,
+            recovery_key: Option<X25519PublicKey>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            let registered_details =
+                Self::registered(&verifying_key).ok_or(Error::<T>::NotRegistered)?;
+            ensure!(
+                who == registered_details.program_modification_account,
+                Error::<T>::NotAuthorized
+            );
+
+            let recovery_key_info = recovery_key.map(|recovery_key| {
+                let block_number = <frame_system::Pallet<T>>::block_number();
+                let delay = pallet_parameters::Pallet::<T>::recovery_key_delay();
+                RecoveryKeyInfo::<T> {
+                    recovery_key,
+                    active_from: block_number.saturating_add(delay.into()),
+                }
+            });
+            match &recovery_key_info {
+                Some(recovery_key_info) => {
+                    RecoveryKeys::<T>::insert(&verifying_key, recovery_key_info)
+                },
+                None => RecoveryKeys::<T>::remove(&verifying_key),
+            }
+
+            Self::deposit_event(Event::RecoveryKeyChanged(verifying_key, recovery_key_info));
+            Ok(())
+        }
+
+        /// Allows validators to refuse a registration whose program configurations do not match
+        /// the configuration schemas of their programs.
+        ///
+        /// Checking a configuration against its schema is too costly to do on-chain, so the
+        /// threshold servers in the account's signing committee check them before generating or
+        /// importing a key. Once `threshold` members of the committee have refused, the
+        /// registration fails and the deposit is refunded, so that a single validator cannot fail
+        /// a registration.
+        #[pallet::call_index(12)]
+        #[pallet::weight({
+            let weight = <T as Config>::WeightInfo::refuse_register();
+            (weight, DispatchClass::Operational, Pays::No)
+        })]
+        pub fn refuse_register(
+            origin: OriginFor<T>,
+            sig_req_account: T::AccountId,
+        ) -> DispatchResult {
+            let ts_server_account = ensure_signed(origin)?;
+            let validator_stash =
+                pallet_staking_extension::Pallet::<T>::threshold_to_stash(&ts_server_account)
+                    .ok_or(Error::<T>::NoThresholdKey)?;
+
+            let mut registering_info =
+                Self::registering(&sig_req_account).ok_or(Error::<T>::NotRegistering)?;
+            ensure!(
+                !matches!(registering_info.status, RegistrationStatus::Failed(_)),
+                Error::<T>::RegistrationNotInProgress
+            );
+            ensure!(
+                registering_info.committee.contains(&validator_stash),
+                Error::<T>::NotValidator
+            );
+            ensure!(
+                !registering_info.refusals.contains(&ts_server_account),
+                Error::<T>::AlreadyRefused
+            );
+            ensure!(
+                !registering_info.confirmations.contains(&ts_server_account),
+                Error::<T>::AlreadyConfirmed
+            );
+
+            registering_info.refusals.push(ts_server_account.clone());
+            if registering_info.refusals.len() >= registering_info.threshold as usize {
+                let failure = RegistrationFailure::InvalidProgramConfig;
+                Self::refund_registration_deposit(&sig_req_account, &mut registering_info);
+                registering_info.status = RegistrationStatus::Failed(failure.clone());
+                Self::deposit_event(Event::FailedRegistration(sig_req_account.clone(), failure));
+            } else {
+                Self::deposit_event(Event::RegistrationRefused(
+                    ts_server_account,
+                    sig_req_account.clone(),
+                ));
+            }
+            Registering::<T>::insert(&sig_req_account, registering_info);
+            Ok(())
+        }
+
+        /// Allows validators to confirm that the program configurations of a change requested
+        /// with [`Self::change_program_instance`] match the configuration schemas of their
+        /// programs.
+        ///
+        /// Once `threshold` members of the account's signing committee have confirmed, the change
+        /// is applied.
+        #[pallet::call_index(13)]
+        #[pallet::weight({
+            let weight = <T as Config>::WeightInfo::confirm_program_change(<T as Config>::MaxProgramHashes::get());
+            (weight, DispatchClass::Operational, Pays::No)
+        })]
+        pub fn confirm_program_change(
+            origin: OriginFor<T>,
+            verifying_key: VerifyingKey,
+        ) -> DispatchResultWithPostInfo {
+            let ts_server_account = ensure_signed(origin)?;
+            let (mut program_change, mut registered_details) =
+                Self::check_program_change_response(&ts_server_account, &verifying_key)?;
+
+            program_change.confirmations.push(ts_server_account);
+            if program_change.confirmations.len() < registered_details.threshold as usize {
+                PendingProgramChanges::<T>::insert(&verifying_key, program_change);
+                return Ok(Some(<T as Config>::WeightInfo::confirm_program_change(0)).into());
+            }
+
+            // decrement ref counter of not used programs
+            for program_instance in &registered_details.programs_data {
+                pallet_programs::Pallet::<T>::remove_program_reference(
+                    program_instance.program_pointer,
+                );
+            }
+            let old_programs_length = registered_details.programs_data.len() as u32;
+            registered_details.programs_data = program_change.programs_data.clone();
+            Registered::<T>::insert(&verifying_key, registered_details);
+            PendingProgramChanges::<T>::remove(&verifying_key);
+
+            Self::deposit_event(Event::ProgramInfoChanged(
+                program_change.program_modification_account,
+                program_change.programs_data,
+            ));
+            Ok(Some(<T as Config>::WeightInfo::confirm_program_change(old_programs_length)).into())
+        }
+
+        /// Allows validators to refuse a change requested with [`Self::change_program_instance`],
+        /// as its program configurations do not match the configuration schemas of their programs.
+        ///
+        /// Once `threshold` members of the account's signing committee have refused, the change is
+        /// dropped and the programs of the verifying key are left as they were.
+        #[pallet::call_index(14)]
+        #[pallet::weight({
+            let weight = <T as Config>::WeightInfo::refuse_program_change(<T as Config>::MaxProgramHashes::get());
+            (weight, DispatchClass::Operational, Pays::No)
+        })]
+        pub fn refuse_program_change(
+            origin: OriginFor<T>,
+            verifying_key: VerifyingKey,
+        ) -> DispatchResultWithPostInfo {
+            let ts_server_account = ensure_signed(origin)?;
+            let (mut program_change, registered_details) =
+                Self::check_program_change_response(&ts_server_account, &verifying_key)?;
+
+            program_change.refusals.push(ts_server_account);
+            if program_change.refusals.len() < registered_details.threshold as usize {
+                PendingProgramChanges::<T>::insert(&verifying_key, program_change);
+                return Ok(Some(<T as Config>::WeightInfo::refuse_program_change(0)).into());
+            }
+
+            for program_instance in &program_change.programs_data {
+                pallet_programs::Pallet::<T>::remove_program_reference(
+                    program_instance.program_pointer,
+                );
+            }
+            PendingProgramChanges::<T>::remove(&verifying_key);
+
+            Self::deposit_event(Event::ProgramChangeRefused(verifying_key));
+            Ok(Some(<T as Config>::WeightInfo::refuse_program_change(
+                program_change.programs_data.len() as u32,
+            ))
+            .into())
+        }
     }
 
     impl<T: Config> Pallet<T> {
@@ -720,6 +1500,443 @@ pub mod pallet {
 
             Ok(validators_info)
         }
+
+        /// The status of the most recent registration of an account, if there is one.
+        ///
+        /// This is the status of its registration in progress or which failed, or otherwise the
+        /// verifying key it last registered, as long as that is still registered.
+        pub fn registration_status(who: &T::AccountId) -> Option<RegistrationStatus> {
+            if let Some(registering_info) = Self::registering(who) {
+                return Some(registering_info.status);
+            }
+            Self::last_registered(who)
+                .filter(|verifying_key| Registered::<T>::contains_key(verifying_key))
+                .map(RegistrationStatus::Done)
+        }
+
+        /// Whether an account is importing the given verifying key, and its registration has not
+        /// failed
+        pub fn key_import_in_progress(verifying_key: &VerifyingKey) -> bool {
+            Self::key_imports(verifying_key)
+                .and_then(|sig_req_account| Self::registering(sig_req_account))
+                .is_some_and(|registering_info| {
+                    registering_info.imported_key
+                        && registering_info.verifying_key.as_ref() == Some(verifying_key)
+                        && !matches!(registering_info.status, RegistrationStatus::Failed(_))
+                })
+        }
+
+        /// The validators which will hold the keyshares of an account whose distributed key
+        /// generation is requested in the given block, with the given committee size.
+        ///
+        /// These are selected from the network's signers by stake and reliability, using the
+        /// account and block as a seed, so that keyshares are spread across the signers and a
+        /// retried registration gets a fresh committee. Once chosen, the committee is stored with
+        /// the registration, since the network's signers change as they are rotated.
+        pub fn signing_committee(
+            sig_req_account: &T::AccountId,
+            block_number: BlockNumberFor<T>,
+            total_signers: u8,
+        ) -> Vec<T::ValidatorId> {
+            let signers = pallet_staking_extension::Pallet::<T>::signers();
+            pallet_staking_extension::Pallet::<T>::select_signers(
+                &signers,
+                total_signers as usize,
+                &(sig_req_account, block_number).encode(),
+            )
+        }
+
+        /// The validators which should sign a message with a registered verifying key at the
+        /// current block, or `None` if the key is not registered.
+        ///
+        /// These are `threshold` members of the account's signing committee, selected by stake and
+        /// reliability using the verifying key as a seed, so that signature requests for
+        /// different accounts are spread across the committee. As stake and reliability change,
+        /// the signers for a request are those selected at the block it was made.
+        pub fn select_signers(verifying_key: Vec<u8>) -> Option<Vec<T::ValidatorId>> {
+            let verifying_key = VerifyingKey::try_from(verifying_key).ok()?;
+            let registered_info = Registered::<T>::get(&verifying_key)?;
+            Some(pallet_staking_extension::Pallet::<T>::select_signers(
+                &registered_info.committee,
+                registered_info.threshold as usize,
+                &verifying_key.encode(),
+            ))
+        }
+
+        /// Checks that a threshold and committee size chosen when registering are within the
+        /// bounds set by governance, and that there are enough signers for the committee
+        pub fn check_signers_size(threshold: u8, total_signers: u8) -> DispatchResult {
+            let signers_info = pallet_parameters::Pallet::<T>::signers_info();
+            ensure!(threshold >= signers_info.threshold.max(1), Error::<T>::ThresholdTooLow);
+            ensure!(threshold <= total_signers, Error::<T>::ThresholdGreaterThanSigners);
+            ensure!(
+                total_signers <= signers_info.total_signers
+                    && total_signers as usize
+                        <= pallet_staking_extension::Pallet::<T>::signers().len(),
+                Error::<T>::TooManySigners
+            );
+            Ok(())
+        }
+
+        /// Checks that the fuel set for each program is not zero and does not exceed the
+        /// network's maximum
+        pub fn check_program_fuel(
+            programs_data: &BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+        ) -> DispatchResult {
+            let max_fuel = pallet_parameters::Pallet::<T>::max_instructions_per_programs();
+            for fuel in programs_data.iter().filter_map(|program_instance| program_instance.fuel) {
+                ensure!(fuel > 0 && fuel <= max_fuel, Error::<T>::InvalidProgramFuel);
+            }
+            Ok(())
+        }
+
+        /// Checks and records a registration request, reserving the deposit and incrementing the
+        /// program reference counters.
+        ///
+        /// If a verifying key is given, the account is importing an existing key. Otherwise
+        /// distributed key generation is requested from the threshold servers.
+        #[allow(clippy::too_many_arguments)]
+        pub fn start_registration(
+            sig_req_account: &T::AccountId,
+            program_modification_account: T::AccountId,
+            programs_data: &BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+            signature_scheme: SignatureScheme,
+            threshold: u8,
+            total_signers: u8,
+            imported_verifying_key: Option<VerifyingKey>,
+        ) -> DispatchResult {
+            let encoded_sig_req_account = sig_req_account.encode();
+            ensure!(
+                encoded_sig_req_account != NETWORK_PARENT_KEY.encode(),
+                Error::<T>::NoRegisteringFromParentKey
+            );
+            ensure!(!Registering::<T>::contains_key(sig_req_account), Error::<T>::AlreadySubmitted);
+            ensure!(!programs_data.is_empty(), Error::<T>::NoProgramSet);
+            Self::check_program_fuel(programs_data)?;
+            Self::check_signers_size(threshold, total_signers)?;
+            let block_number = <frame_system::Pallet<T>>::block_number();
+            // Change program ref counter
+            for program_instance in programs_data {
+                pallet_programs::Pallet::<T>::add_program_reference(
+                    program_instance.program_pointer,
+                )
+                .map_err(|_| Error::<T>::NoProgramSet)?;
+                pallet_programs::Pallet::<T>::check_program_config(
+                    program_instance.program_pointer,
+                    &program_instance.program_config,
+                )?;
+            }
+
+            let deposit = T::RegistrationDeposit::get();
+            <T as pallet_programs::Config>::Currency::reserve(sig_req_account, deposit)?;
+
+            // An imported key is sent to the threshold servers by the user
+            if imported_verifying_key.is_none() {
+                Dkg::<T>::try_mutate(block_number, |messages| -> Result<_, DispatchError> {
+                    messages.push(encoded_sig_req_account);
+                    Ok(())
+                })?;
+            }
+            let dkg_deadline = Self::dkg_deadline(block_number);
+            DkgDeadlines::<T>::mutate(dkg_deadline, |accounts| {
+                accounts.push(sig_req_account.clone())
+            });
+
+            // Put account into a registering state
+            let imported_key = imported_verifying_key.is_some();
+            Registering::<T>::insert(
+                sig_req_account,
+                RegisteringDetails::<T> {
+                    program_modification_account,
+                    confirmations: vec![],
+                    refusals: vec![],
+                    programs_data: programs_data.clone(),
+                    verifying_key: imported_verifying_key,
+                    version_number: T::KeyVersionNumber::get(),
+                    signature_scheme,
+                    status: RegistrationStatus::Submitted,
+                    deposit,
+                    dkg_attempts: 1,
+                    dkg_deadline,
+                    imported_key,
+                    threshold,
+                    total_signers,
+                    committee: Self::signing_committee(
+                        sig_req_account,
+                        block_number,
+                        total_signers,
+                    ),
+                },
+            );
+            Ok(())
+        }
+
+        /// The block by which a distributed key generation requested in the given block must be
+        /// confirmed. The request is only sent to the threshold servers in the following block.
+        pub fn dkg_deadline(block_number: BlockNumberFor<T>) -> BlockNumberFor<T> {
+            block_number.saturating_add(1u32.into()).saturating_add(T::DkgTimeout::get())
+        }
+
+        /// Releases whatever is left of the deposit reserved for a registration
+        pub fn refund_registration_deposit(
+            sig_req_account: &T::AccountId,
+            registering_info: &mut RegisteringDetails<T>,
+        ) {
+            <T as pallet_programs::Config>::Currency::unreserve(
+                sig_req_account,
+                registering_info.deposit,
+            );
+            registering_info.deposit = Zero::zero();
+        }
+
+        /// Handles registrations whose distributed key generation was not confirmed by every
+        /// threshold server by the given block.
+        ///
+        /// The validators in the account's signing committee which did not confirm are reported to
+        /// the slashing pallet. The distributed key generation is then started again, unless the
+        /// registration has used up all of its attempts, in which case it fails and the deposit is
+        /// refunded.
+        pub fn expire_dkg_attempts(block_number: BlockNumberFor<T>) -> Weight {
+            let expired_accounts = DkgDeadlines::<T>::take(block_number);
+            if expired_accounts.is_empty() {
+                return T::DbWeight::get().reads_writes(1, 1);
+            }
+
+            let mut reads: u64 = 2;
+            let mut writes: u64 = 1;
+
+            for sig_req_account in expired_accounts {
+                reads = reads.saturating_add(1);
+                let Some(mut registering_info) = Self::registering(&sig_req_account) else {
+                    continue;
+                };
+                // The account may have pruned this registration and registered again since
+                if registering_info.dkg_deadline != block_number
+                    || matches!(registering_info.status, RegistrationStatus::Failed(_))
+                {
+                    continue;
+                }
+
+                // A threshold server can only confirm an imported key once the user has sent it a
+                // keyshare, so a stalled import is not necessarily the fault of the validators
+                if !registering_info.imported_key {
+                    // A validator which refused the registration has responded, so is not at fault
+                    let confirmed_validators: Vec<T::AccountId> = registering_info
+                        .confirmations
+                        .iter()
+                        .chain(registering_info.refusals.iter())
+                        .filter_map(|ts_server_account| {
+                            pallet_staking_extension::Pallet::<T>::threshold_to_stash(
+                                ts_server_account,
+                            )
+                        })
+                        .collect();
+                    reads = reads.saturating_add(confirmed_validators.len() as u64);
+                    for offender in registering_info
+                        .committee
+                        .iter()
+                        .filter(|v| !confirmed_validators.contains(v))
+                    {
+                        let _ = pallet_slashing::Pallet::<T>::note_report(
+                            sig_req_account.clone(),
+                            offender.clone(),
+                        );
+                        writes = writes.saturating_add(1);
+                    }
+                }
+
+                // Only the user can restart the import of an existing key
+                if !registering_info.imported_key
+                    && registering_info.dkg_attempts < T::MaxDkgAttempts::get()
+                {
+                    registering_info.dkg_attempts = registering_info.dkg_attempts.saturating_add(1);
+                    registering_info.confirmations = vec![];
+                    registering_info.refusals = vec![];
+                    registering_info.verifying_key = None;
+                    registering_info.status = RegistrationStatus::Submitted;
+                    registering_info.dkg_deadline = Self::dkg_deadline(block_number);
+                    // Retry with whichever validators are signers now, in case some of the
+                    // previous committee have since been rotated out
+                    registering_info.committee = Self::signing_committee(
+                        &sig_req_account,
+                        block_number,
+                        registering_info.total_signers,
+                    );
+
+                    Dkg::<T>::mutate(block_number, |messages| {
+                        messages.push(sig_req_account.encode())
+                    });
+                    DkgDeadlines::<T>::mutate(registering_info.dkg_deadline, |accounts| {
+                        accounts.push(sig_req_account.clone())
+                    });
+                    writes = writes.saturating_add(2);
+                    Self::deposit_event(Event::DkgRetried(
+                        sig_req_account.clone(),
+                        registering_info.dkg_attempts,
+                    ));
+                } else {
+                    let failure = RegistrationFailure::DkgTimedOut;
+                    Self::refund_registration_deposit(&sig_req_account, &mut registering_info);
+                    registering_info.status = RegistrationStatus::Failed(failure.clone());
+                    writes = writes.saturating_add(1);
+                    Self::deposit_event(Event::FailedRegistration(
+                        sig_req_account.clone(),
+                        failure,
+                    ));
+                }
+                Registering::<T>::insert(&sig_req_account, registering_info);
+                writes = writes.saturating_add(1);
+            }
+
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+
+        /// The block by which the keyshare deletion for a verifying key deregistered in the given
+        /// block must be confirmed. The request is only sent to the threshold servers in the
+        /// following block.
+        pub fn deregistration_deadline(block_number: BlockNumberFor<T>) -> BlockNumberFor<T> {
+            block_number.saturating_add(1u32.into()).saturating_add(T::DeregistrationTimeout::get())
+        }
+
+        /// Asks the threshold servers again to delete their keyshares for deregistered verifying
+        /// keys which not every member of the signing committee confirmed deleting by this block.
+        /// After `MaxDeregistrationAttempts` the keys are no longer tracked.
+        pub fn expire_deregistrations(block_number: BlockNumberFor<T>) -> Weight {
+            let expired_keys = DeregistrationDeadlines::<T>::take(block_number);
+            if expired_keys.is_empty() {
+                return T::DbWeight::get().reads_writes(1, 1);
+            }
+
+            let mut reads: u64 = 1;
+            let mut writes: u64 = 1;
+
+            for verifying_key in expired_keys {
+                reads = reads.saturating_add(1);
+                let Some(mut deregistering_details) = Self::deregistering(&verifying_key) else {
+                    continue;
+                };
+                // The key may have been registered and deregistered again since
+                if deregistering_details.deadline != block_number {
+                    continue;
+                }
+
+                if deregistering_details.attempts < T::MaxDeregistrationAttempts::get() {
+                    deregistering_details.attempts =
+                        deregistering_details.attempts.saturating_add(1);
+                    deregistering_details.deadline = Self::deregistration_deadline(block_number);
+
+                    Deregistrations::<T>::mutate(block_number, |verifying_keys| {
+                        verifying_keys.push(verifying_key.clone())
+                    });
+                    DeregistrationDeadlines::<T>::mutate(
+                        deregistering_details.deadline,
+                        |verifying_keys| verifying_keys.push(verifying_key.clone()),
+                    );
+                    writes = writes.saturating_add(3);
+                    Self::deposit_event(Event::DeregistrationRetried(
+                        verifying_key.clone(),
+                        deregistering_details.attempts,
+                    ));
+                    Deregistering::<T>::insert(&verifying_key, deregistering_details);
+                } else {
+                    let confirmed_validators: Vec<T::ValidatorId> = deregistering_details
+                        .confirmations
+                        .iter()
+                        .filter_map(|ts_server_account| {
+                            pallet_staking_extension::Pallet::<T>::threshold_to_stash(
+                                ts_server_account,
+                            )
+                        })
+                        .collect();
+                    reads = reads.saturating_add(confirmed_validators.len() as u64);
+                    let unconfirmed_validators = deregistering_details
+                        .committee
+                        .into_iter()
+                        .filter(|validator| !confirmed_validators.contains(validator))
+                        .collect();
+
+                    Deregistering::<T>::remove(&verifying_key);
+                    writes = writes.saturating_add(1);
+                    Self::deposit_event(Event::DeregistrationExpired(
+                        verifying_key,
+                        unconfirmed_validators,
+                    ));
+                }
+            }
+
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+
+        /// Checks that a threshold server is in the signing committee of a verifying key with a
+        /// pending program change, and has not yet confirmed or refused it.
+        #[allow(clippy::type_complexity)]
+        fn check_program_change_response(
+            ts_server_account: &T::AccountId,
+            verifying_key: &VerifyingKey,
+        ) -> Result<(ProgramChangeDetails<T>, RegisteredInfo<T>), Error<T>> {
+            let validator_stash =
+                pallet_staking_extension::Pallet::<T>::threshold_to_stash(ts_server_account)
+                    .ok_or(Error::<T>::NoThresholdKey)?;
+            let program_change =
+                Self::pending_program_changes(verifying_key).ok_or(Error::<T>::NoProgramChange)?;
+            let registered_details =
+                Self::registered(verifying_key).ok_or(Error::<T>::NotRegistered)?;
+            ensure!(
+                registered_details.committee.contains(&validator_stash),
+                Error::<T>::NotValidator
+            );
+            ensure!(
+                !program_change.confirmations.contains(ts_server_account),
+                Error::<T>::AlreadyConfirmed
+            );
+            ensure!(
+                !program_change.refusals.contains(ts_server_account),
+                Error::<T>::AlreadyRefused
+            );
+            Ok((program_change, registered_details))
+        }
+
+        /// The block by which a program change requested in the given block must be confirmed.
+        /// The request is only sent to the threshold servers in the following block.
+        pub fn program_change_deadline(block_number: BlockNumberFor<T>) -> BlockNumberFor<T> {
+            block_number.saturating_add(1u32.into()).saturating_add(T::ProgramChangeTimeout::get())
+        }
+
+        /// Drops program changes which not enough of the signing committee confirmed by this
+        /// block, leaving the programs of the verifying keys as they were.
+        pub fn expire_program_changes(block_number: BlockNumberFor<T>) -> Weight {
+            let expired_keys = ProgramChangeDeadlines::<T>::take(block_number);
+            if expired_keys.is_empty() {
+                return T::DbWeight::get().reads_writes(1, 1);
+            }
+
+            let mut reads: u64 = 1;
+            let mut writes: u64 = 1;
+
+            for verifying_key in expired_keys {
+                reads = reads.saturating_add(1);
+                let Some(program_change) = Self::pending_program_changes(&verifying_key) else {
+                    continue;
+                };
+                // The programs may have been changed again since
+                if program_change.deadline != block_number {
+                    continue;
+                }
+
+                for program_instance in &program_change.programs_data {
+                    pallet_programs::Pallet::<T>::remove_program_reference(
+                        program_instance.program_pointer,
+                    );
+                }
+                PendingProgramChanges::<T>::remove(&verifying_key);
+                writes = writes
+                    .saturating_add(1)
+                    .saturating_add(program_change.programs_data.len() as u64);
+                Self::deposit_event(Event::ProgramChangeExpired(verifying_key));
+            }
+
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
     }
 
     #[allow(clippy::derive_partial_eq_without_eq)]
@@ -794,12 +2011,79 @@ pub mod pallet {
                 let registering_info =
                     Registering::<T>::get(sig_req_account).ok_or(InvalidTransaction::Custom(2))?;
                 ensure!(
-                    !registering_info.confirmations.contains(who),
+                    !registering_info.confirmations.contains(who)
+                        && !registering_info.refusals.contains(who),
+                    InvalidTransaction::Custom(3)
+                );
+                ensure!(
+                    !matches!(registering_info.status, RegistrationStatus::Failed(_)),
+                    InvalidTransaction::Custom(5)
+                );
+
+                ensure!(
+                    registering_info.committee.contains(&validator_stash),
+                    InvalidTransaction::Custom(4)
+                );
+            }
+            if let Some(Call::refuse_register { sig_req_account }) = call.is_sub_type() {
+                let validator_stash =
+                    pallet_staking_extension::Pallet::<T>::threshold_to_stash(who)
+                        .ok_or(InvalidTransaction::Custom(1))?;
+
+                let registering_info =
+                    Registering::<T>::get(sig_req_account).ok_or(InvalidTransaction::Custom(2))?;
+                ensure!(
+                    !registering_info.confirmations.contains(who)
+                        && !registering_info.refusals.contains(who),
                     InvalidTransaction::Custom(3)
                 );
+                ensure!(
+                    !matches!(registering_info.status, RegistrationStatus::Failed(_)),
+                    InvalidTransaction::Custom(5)
+                );
+                ensure!(
+                    registering_info.committee.contains(&validator_stash),
+                    InvalidTransaction::Custom(4)
+                );
+            }
+            if let Some(
+                Call::confirm_program_change { verifying_key }
+                | Call::refuse_program_change { verifying_key },
+            ) = call.is_sub_type()
+            {
+                let validator_stash =
+                    pallet_staking_extension::Pallet::<T>::threshold_to_stash(who)
+                        .ok_or(InvalidTransaction::Custom(1))?;
 
-                let validators = pallet_session::Pallet::<T>::validators();
-                ensure!(validators.contains(&validator_stash), InvalidTransaction::Custom(4));
+                let program_change = PendingProgramChanges::<T>::get(verifying_key)
+                    .ok_or(InvalidTransaction::Custom(2))?;
+                let registered_details =
+                    Registered::<T>::get(verifying_key).ok_or(InvalidTransaction::Custom(2))?;
+                ensure!(
+                    !program_change.confirmations.contains(who)
+                        && !program_change.refusals.contains(who),
+                    InvalidTransaction::Custom(3)
+                );
+                ensure!(
+                    registered_details.committee.contains(&validator_stash),
+                    InvalidTransaction::Custom(4)
+                );
+            }
+            if let Some(Call::confirm_deregistration { verifying_key }) = call.is_sub_type() {
+                let validator_stash =
+                    pallet_staking_extension::Pallet::<T>::threshold_to_stash(who)
+                        .ok_or(InvalidTransaction::Custom(1))?;
+
+                let deregistering_details =
+                    Deregistering::<T>::get(verifying_key).ok_or(InvalidTransaction::Custom(2))?;
+                ensure!(
+                    !deregistering_details.confirmations.contains(who),
+                    InvalidTransaction::Custom(3)
+                );
+                ensure!(
+                    deregistering_details.committee.contains(&validator_stash),
+                    InvalidTransaction::Custom(4)
+                );
             }
             Ok(ValidTransaction::default())
         }
@@ -0,0 +1,629 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage migrations for the registry pallet
+//!
+//! Each change to the layout of the pallet's storage has its own storage version, and the runtime
+//! runs the migrations from one version to the next in order.
+
+/// The layout of registrations before storage versions were tracked.
+pub mod v0 {
+    use crate::{Config, Pallet, VerifyingKey};
+    use frame_support::pallet_prelude::*;
+    use sp_std::vec::Vec;
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct ProgramInstance<T: Config> {
+        pub program_pointer: T::Hash,
+        pub program_config: Vec<u8>,
+    }
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteringDetails<T: Config> {
+        pub program_modification_account: T::AccountId,
+        pub confirmations: Vec<T::AccountId>,
+        pub programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+        pub verifying_key: Option<VerifyingKey>,
+        pub version_number: u8,
+    }
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteredInfo<T: Config> {
+        pub programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+        pub program_modification_account: T::AccountId,
+        pub version_number: u8,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Registering<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
+
+    #[frame_support::storage_alias]
+    pub type Registered<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, VerifyingKey, RegisteredInfo<T>, OptionQuery>;
+}
+
+/// Adds the signature scheme of each account.
+pub mod v1 {
+    use super::v0;
+    use crate::{Config, Pallet, VerifyingKey};
+    use entropy_shared::SignatureScheme;
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use sp_std::{marker::PhantomData, vec::Vec};
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteringDetails<T: Config> {
+        pub program_modification_account: T::AccountId,
+        pub confirmations: Vec<T::AccountId>,
+        pub programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+        pub verifying_key: Option<VerifyingKey>,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+    }
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteredInfo<T: Config> {
+        pub programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+        pub program_modification_account: T::AccountId,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Registering<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
+
+    #[frame_support::storage_alias]
+    pub type Registered<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, VerifyingKey, RegisteredInfo<T>, OptionQuery>;
+
+    /// Migrates the registry pallet from storage version 0 to 1.
+    ///
+    /// Only ECDSA keys could be registered before accounts chose a signature scheme.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Registered::<T>::translate::<v0::RegisteredInfo<T>, _>(|_verifying_key, old| {
+                translated += 1;
+                Some(RegisteredInfo {
+                    programs_data: old.programs_data,
+                    program_modification_account: old.program_modification_account,
+                    version_number: old.version_number,
+                    signature_scheme: SignatureScheme::Ecdsa,
+                })
+            });
+            Registering::<T>::translate::<v0::RegisteringDetails<T>, _>(|_account, old| {
+                translated += 1;
+                Some(RegisteringDetails {
+                    program_modification_account: old.program_modification_account,
+                    confirmations: old.confirmations,
+                    programs_data: old.programs_data,
+                    verifying_key: old.verifying_key,
+                    version_number: old.version_number,
+                    signature_scheme: SignatureScheme::Ecdsa,
+                })
+            });
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
+
+/// Adds the status of each registration in progress.
+pub mod v2 {
+    use super::{v0, v1};
+    use crate::{Config, Pallet, RegistrationStatus, VerifyingKey};
+    use entropy_shared::SignatureScheme;
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_std::{marker::PhantomData, vec::Vec};
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteringDetails<T: Config> {
+        pub program_modification_account: T::AccountId,
+        pub confirmations: Vec<T::AccountId>,
+        pub programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+        pub verifying_key: Option<VerifyingKey>,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        pub status: RegistrationStatus,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Registering<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
+
+    /// Migrates the registry pallet from storage version 1 to 2.
+    ///
+    /// Registrations are removed from [Registering] once they finish, so every registration is
+    /// still waiting for keyshares to be confirmed by the signers, who all took part in the
+    /// distributed key generation.
+    pub struct MigrateToV2<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV2<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 1 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let block_number: u32 =
+                BlockNumberFor::<T>::try_into(frame_system::Pallet::<T>::block_number())
+                    .unwrap_or_default();
+            let required = pallet_staking_extension::Pallet::<T>::signers().len() as u32;
+            let mut translated = 0u64;
+            Registering::<T>::translate::<v1::RegisteringDetails<T>, _>(|_account, old| {
+                translated += 1;
+                let status = if old.confirmations.is_empty() {
+                    RegistrationStatus::DkgStarted(block_number)
+                } else {
+                    RegistrationStatus::Confirming {
+                        confirmations: old.confirmations.len() as u32,
+                        required,
+                    }
+                };
+                Some(RegisteringDetails {
+                    program_modification_account: old.program_modification_account,
+                    confirmations: old.confirmations,
+                    programs_data: old.programs_data,
+                    verifying_key: old.verifying_key,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    status,
+                })
+            });
+
+            StorageVersion::new(2).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 3, translated + 1)
+        }
+    }
+}
+
+/// Adds the deposit and distributed key generation attempts of each registration in progress.
+pub mod v3 {
+    use super::{v0, v2};
+    use crate::{BalanceOf, Config, Dkg, DkgDeadlines, Pallet, RegistrationStatus, VerifyingKey};
+    use codec::Encode;
+    use entropy_shared::SignatureScheme;
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_runtime::traits::{Saturating, Zero};
+    use sp_std::{marker::PhantomData, vec, vec::Vec};
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteringDetails<T: Config> {
+        pub program_modification_account: T::AccountId,
+        pub confirmations: Vec<T::AccountId>,
+        pub programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+        pub verifying_key: Option<VerifyingKey>,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        pub status: RegistrationStatus,
+        pub deposit: BalanceOf<T>,
+        pub dkg_attempts: u32,
+        pub dkg_deadline: BlockNumberFor<T>,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Registering<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
+
+    /// Migrates the registry pallet from storage version 2 to 3.
+    ///
+    /// Registrations which are still in progress have their distributed key generation restarted,
+    /// as it was not tracked and may never finish, and validators should not be reported for a
+    /// deadline they were not given. Failed registrations are kept as they are. Neither are charged
+    /// a deposit, as none was taken when they registered.
+    pub struct MigrateToV3<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV3<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 2 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let block_number = frame_system::Pallet::<T>::block_number();
+            let dkg_deadline = Pallet::<T>::dkg_deadline(block_number);
+            let mut translated = 0u64;
+            let mut restarted = Vec::new();
+            Registering::<T>::translate::<v2::RegisteringDetails<T>, _>(|account, old| {
+                translated += 1;
+                let mut registering_details = RegisteringDetails {
+                    program_modification_account: old.program_modification_account,
+                    confirmations: old.confirmations,
+                    programs_data: old.programs_data,
+                    verifying_key: old.verifying_key,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    status: old.status,
+                    deposit: Zero::zero(),
+                    dkg_attempts: 1,
+                    dkg_deadline: block_number,
+                };
+                if !matches!(registering_details.status, RegistrationStatus::Failed(_)) {
+                    registering_details.confirmations = vec![];
+                    registering_details.verifying_key = None;
+                    registering_details.status = RegistrationStatus::Submitted;
+                    registering_details.dkg_deadline = dkg_deadline;
+                    restarted.push(account);
+                }
+                Some(registering_details)
+            });
+
+            let mut reads = translated + 1;
+            let mut writes = translated + 1;
+            if !restarted.is_empty() {
+                // Registrations made in the previous block are already being sent to the threshold
+                // servers in this block
+                let requested = Dkg::<T>::get(block_number.saturating_sub(1u32.into()));
+                Dkg::<T>::mutate(block_number, |messages| {
+                    messages.extend(
+                        restarted
+                            .iter()
+                            .map(|account| account.encode())
+                            .filter(|account| !requested.contains(account)),
+                    )
+                });
+                DkgDeadlines::<T>::mutate(dkg_deadline, |accounts| accounts.extend(restarted));
+                reads += 3;
+                writes += 2;
+            }
+
+            StorageVersion::new(3).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(reads, writes)
+        }
+    }
+}
+
+/// Adds whether each registration in progress is importing an existing key.
+pub mod v4 {
+    use super::{v0, v3};
+    use crate::{BalanceOf, Config, Pallet, RegistrationStatus, VerifyingKey};
+    use entropy_shared::SignatureScheme;
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_std::{marker::PhantomData, vec::Vec};
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteringDetails<T: Config> {
+        pub program_modification_account: T::AccountId,
+        pub confirmations: Vec<T::AccountId>,
+        pub programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+        pub verifying_key: Option<VerifyingKey>,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        pub status: RegistrationStatus,
+        pub deposit: BalanceOf<T>,
+        pub dkg_attempts: u32,
+        pub dkg_deadline: BlockNumberFor<T>,
+        pub imported_key: bool,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Registering<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
+
+    /// Migrates the registry pallet from storage version 3 to 4.
+    ///
+    /// Keys could only be generated by the network before existing keys could be imported.
+    pub struct MigrateToV4<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV4<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 3 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Registering::<T>::translate::<v3::RegisteringDetails<T>, _>(|_account, old| {
+                translated += 1;
+                Some(RegisteringDetails {
+                    program_modification_account: old.program_modification_account,
+                    confirmations: old.confirmations,
+                    programs_data: old.programs_data,
+                    verifying_key: old.verifying_key,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    status: old.status,
+                    deposit: old.deposit,
+                    dkg_attempts: old.dkg_attempts,
+                    dkg_deadline: old.dkg_deadline,
+                    imported_key: false,
+                })
+            });
+
+            StorageVersion::new(4).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
+
+/// Adds the threshold, committee size and signing committee of each account.
+pub mod v5 {
+    use super::{v0, v1, v4};
+    use crate::{BalanceOf, Config, Pallet, RegistrationStatus, VerifyingKey};
+    use entropy_shared::{SignatureScheme, SIGNER_THRESHOLD, TOTAL_SIGNERS};
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_std::{marker::PhantomData, vec::Vec};
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteringDetails<T: Config> {
+        pub program_modification_account: T::AccountId,
+        pub confirmations: Vec<T::AccountId>,
+        pub programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+        pub verifying_key: Option<VerifyingKey>,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        pub status: RegistrationStatus,
+        pub deposit: BalanceOf<T>,
+        pub dkg_attempts: u32,
+        pub dkg_deadline: BlockNumberFor<T>,
+        pub imported_key: bool,
+        pub threshold: u8,
+        pub total_signers: u8,
+        pub committee: Vec<T::ValidatorId>,
+    }
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteredInfo<T: Config> {
+        pub programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+        pub program_modification_account: T::AccountId,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        pub threshold: u8,
+        pub total_signers: u8,
+        pub committee: Vec<T::ValidatorId>,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Registering<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
+
+    #[frame_support::storage_alias]
+    pub type Registered<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, VerifyingKey, RegisteredInfo<T>, OptionQuery>;
+
+    /// Migrates the registry pallet from storage version 4 to 5.
+    ///
+    /// Every signer held a keyshare before committees were chosen per account, so registered
+    /// accounts are given the network's default threshold and committee size, with the current
+    /// signers as their committee. So are registrations whose key generation has already been
+    /// started, while those which are waiting for it to start are given a newly selected committee.
+    pub struct MigrateToV5<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV5<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 4 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let block_number = frame_system::Pallet::<T>::block_number();
+            let signers = pallet_staking_extension::Pallet::<T>::signers();
+            let mut translated = 0u64;
+            Registered::<T>::translate::<v1::RegisteredInfo<T>, _>(|_verifying_key, old| {
+                translated += 1;
+                Some(RegisteredInfo {
+                    programs_data: old.programs_data,
+                    program_modification_account: old.program_modification_account,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    threshold: SIGNER_THRESHOLD,
+                    total_signers: TOTAL_SIGNERS,
+                    committee: signers.clone(),
+                })
+            });
+            Registering::<T>::translate::<v4::RegisteringDetails<T>, _>(|account, old| {
+                translated += 1;
+                let committee = if old.status == RegistrationStatus::Submitted {
+                    Pallet::<T>::signing_committee(&account, block_number, TOTAL_SIGNERS)
+                } else {
+                    signers.clone()
+                };
+                Some(RegisteringDetails {
+                    program_modification_account: old.program_modification_account,
+                    confirmations: old.confirmations,
+                    programs_data: old.programs_data,
+                    verifying_key: old.verifying_key,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    status: old.status,
+                    deposit: old.deposit,
+                    dkg_attempts: old.dkg_attempts,
+                    dkg_deadline: old.dkg_deadline,
+                    imported_key: old.imported_key,
+                    threshold: SIGNER_THRESHOLD,
+                    total_signers: TOTAL_SIGNERS,
+                    committee,
+                })
+            });
+
+            StorageVersion::new(5).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 2, translated + 1)
+        }
+    }
+}
+
+/// Adds the fuel cap of each program instance.
+pub mod v6 {
+    use super::{v0, v5};
+    use crate::{
+        BalanceOf, Config, Pallet, ProgramInstance, Registered, RegisteredInfo, RegistrationStatus,
+        VerifyingKey,
+    };
+    use entropy_shared::SignatureScheme;
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_std::{marker::PhantomData, vec::Vec};
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEqNoBound, RuntimeDebug)]
+    pub struct RegisteringDetails<T: Config> {
+        pub program_modification_account: T::AccountId,
+        pub confirmations: Vec<T::AccountId>,
+        pub programs_data: BoundedVec<ProgramInstance<T>, T::MaxProgramHashes>,
+        pub verifying_key: Option<VerifyingKey>,
+        pub version_number: u8,
+        pub signature_scheme: SignatureScheme,
+        pub status: RegistrationStatus,
+        pub deposit: BalanceOf<T>,
+        pub dkg_attempts: u32,
+        pub dkg_deadline: BlockNumberFor<T>,
+        pub imported_key: bool,
+        pub threshold: u8,
+        pub total_signers: u8,
+        pub committee: Vec<T::ValidatorId>,
+    }
+
+    #[frame_support::storage_alias]
+    pub type Registering<T: Config> =
+        StorageMap<Pallet<T>, Blake2_128Concat, T::AccountId, RegisteringDetails<T>, OptionQuery>;
+
+    fn migrate_programs<T: Config>(
+        programs_data: BoundedVec<v0::ProgramInstance<T>, T::MaxProgramHashes>,
+    ) -> BoundedVec<ProgramInstance<T>, T::MaxProgramHashes> {
+        BoundedVec::truncate_from(
+            programs_data
+                .into_iter()
+                .map(|program| ProgramInstance {
+                    program_pointer: program.program_pointer,
+                    program_config: program.program_config,
+                    fuel: None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Migrates the registry pallet from storage version 5 to 6.
+    ///
+    /// Existing programs are given no fuel cap of their own, so the network's maximum applies.
+    pub struct MigrateToV6<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV6<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 5 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Registered::<T>::translate::<v5::RegisteredInfo<T>, _>(|_verifying_key, old| {
+                translated += 1;
+                Some(RegisteredInfo {
+                    programs_data: migrate_programs::<T>(old.programs_data),
+                    program_modification_account: old.program_modification_account,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    threshold: old.threshold,
+                    total_signers: old.total_signers,
+                    committee: old.committee,
+                })
+            });
+            Registering::<T>::translate::<v5::RegisteringDetails<T>, _>(|_account, old| {
+                translated += 1;
+                Some(RegisteringDetails {
+                    program_modification_account: old.program_modification_account,
+                    confirmations: old.confirmations,
+                    programs_data: migrate_programs::<T>(old.programs_data),
+                    verifying_key: old.verifying_key,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    status: old.status,
+                    deposit: old.deposit,
+                    dkg_attempts: old.dkg_attempts,
+                    dkg_deadline: old.dkg_deadline,
+                    imported_key: old.imported_key,
+                    threshold: old.threshold,
+                    total_signers: old.total_signers,
+                    committee: old.committee,
+                })
+            });
+
+            StorageVersion::new(6).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
+
+/// Adds the threshold servers which have refused a registration.
+pub mod v7 {
+    use super::v6;
+    use crate::{Config, Pallet, Registering, RegisteringDetails};
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use sp_std::{marker::PhantomData, vec};
+
+    /// Migrates the registry pallet from storage version 6 to 7.
+    ///
+    /// Until now a single refusal failed a registration, so those still in progress have none.
+    pub struct MigrateToV7<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV7<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 6 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let mut translated = 0u64;
+            Registering::<T>::translate::<v6::RegisteringDetails<T>, _>(|_account, old| {
+                translated += 1;
+                Some(RegisteringDetails {
+                    program_modification_account: old.program_modification_account,
+                    confirmations: old.confirmations,
+                    refusals: vec![],
+                    programs_data: old.programs_data,
+                    verifying_key: old.verifying_key,
+                    version_number: old.version_number,
+                    signature_scheme: old.signature_scheme,
+                    status: old.status,
+                    deposit: old.deposit,
+                    dkg_attempts: old.dkg_attempts,
+                    dkg_deadline: old.dkg_deadline,
+                    imported_key: old.imported_key,
+                    threshold: old.threshold,
+                    total_signers: old.total_signers,
+                    committee: old.committee,
+                })
+            });
+
+            StorageVersion::new(7).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+        }
+    }
+}
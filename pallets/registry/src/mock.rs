@@ -54,7 +54,9 @@ frame_support::construct_runtime!(
     FrameStaking: pallet_staking,
     Session: pallet_session,
     Historical: pallet_session_historical,
+    Slashing: pallet_slashing,
     BagsList: pallet_bags_list,
+    Parameters: pallet_parameters,
     Programs: pallet_programs,
   }
 );
@@ -296,11 +298,20 @@ impl Randomness<H256, BlockNumber> for TestPastRandomness {
 }
 parameter_types! {
   pub const MaxEndpointLength: u32 = 3;
+  pub const ReshareTimeout: u64 = 5;
+}
+pub struct SlashingReliability;
+impl pallet_staking_extension::ValidatorReliability<AccountId> for SlashingReliability {
+    fn unresponsive_reports(validator: &AccountId) -> u32 {
+        Slashing::failed_registrations(validator)
+    }
 }
 impl pallet_staking_extension::Config for Test {
     type Currency = Balances;
     type MaxEndpointLength = MaxEndpointLength;
     type Randomness = TestPastRandomness;
+    type Reliability = SlashingReliability;
+    type ReshareTimeout = ReshareTimeout;
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = ();
 }
@@ -328,6 +339,13 @@ impl pallet_authorship::Config for Test {
 parameter_types! {
   pub const MaxProgramHashes: u32 = 5u32;
   pub const KeyVersionNumber: u8 = 1;
+  pub const RegistrationDeposit: u64 = 10;
+  pub const DkgTimeout: u64 = 5;
+  pub const MaxDkgAttempts: u32 = 2;
+  pub const DeregistrationTimeout: u64 = 5;
+  pub const MaxDeregistrationAttempts: u32 = 2;
+  pub const ProgramChangeTimeout: u64 = 5;
+  pub const ReportThreshold: u32 = 5;
 }
 
 impl pallet_registry::Config for Test {
@@ -335,12 +353,35 @@ impl pallet_registry::Config for Test {
     type MaxProgramHashes = MaxProgramHashes;
     type KeyVersionNumber = KeyVersionNumber;
     type WeightInfo = ();
+    type RegistrationDeposit = RegistrationDeposit;
+    type DkgTimeout = DkgTimeout;
+    type MaxDkgAttempts = MaxDkgAttempts;
+    type DeregistrationTimeout = DeregistrationTimeout;
+    type MaxDeregistrationAttempts = MaxDeregistrationAttempts;
+    type ProgramChangeTimeout = ProgramChangeTimeout;
+}
+
+impl pallet_parameters::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type UpdateOrigin = frame_system::EnsureRoot<Self::AccountId>;
+    type WeightInfo = ();
+}
+
+impl pallet_slashing::Config for Test {
+    type AuthorityId = UintAuthorityId;
+    type ReportThreshold = ReportThreshold;
+    type ReportUnresponsiveness = ();
+    type RuntimeEvent = RuntimeEvent;
+    type ValidatorSet = Historical;
 }
 
 parameter_types! {
   pub const MaxBytecodeLength: u32 = 3;
   pub const ProgramDepositPerByte: u32 = 5;
   pub const MaxOwnedPrograms: u32 = 5;
+  pub const ProgramUpgradeDelay: u64 = 5;
+  pub const MaxScheduledProgramUpgrades: u32 = 5;
+  pub const ProgramFamilyDeposit: u32 = 10;
 }
 
 impl pallet_programs::Config for Test {
@@ -348,6 +389,9 @@ impl pallet_programs::Config for Test {
     type MaxBytecodeLength = MaxBytecodeLength;
     type ProgramDepositPerByte = ProgramDepositPerByte;
     type MaxOwnedPrograms = MaxOwnedPrograms;
+    type ProgramUpgradeDelay = ProgramUpgradeDelay;
+    type MaxScheduledProgramUpgrades = MaxScheduledProgramUpgrades;
+    type ProgramFamilyDeposit = ProgramFamilyDeposit;
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = ();
 }
@@ -366,11 +410,27 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
         ],
         proactive_refresh_data: (vec![], vec![]),
         mock_signer_rotate: false,
-        inital_signers: vec![5, 6],
+        inital_signers: vec![1, 2],
     };
 
     pallet_staking_extension.assimilate_storage(&mut t).unwrap();
 
+    pallet_parameters::GenesisConfig::<Test> {
+        request_limit: 5u32,
+        max_instructions_per_programs: 5u64,
+        max_rate_limit_requests: 100u32,
+        recovery_key_delay: 5u32,
+        total_signers: 2u8,
+        threshold: 1u8,
+        _config: Default::default(),
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    pallet_balances::GenesisConfig::<Test> { balances: vec![(1, 100), (2, 100), (5, 100)] }
+        .assimilate_storage(&mut t)
+        .unwrap();
+
     let stakers = vec![1, 2];
     let keys: Vec<_> = stakers.iter().cloned().map(|i| (i, i, UintAuthorityId(i).into())).collect();
 
@@ -14,11 +14,16 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use codec::Encode;
-use entropy_shared::{NETWORK_PARENT_KEY, VERIFICATION_KEY_LENGTH};
+use entropy_shared::{
+    RateLimit, SignatureScheme, ED25519_VERIFICATION_KEY_LENGTH, NETWORK_PARENT_KEY,
+    SIGNER_THRESHOLD, TOTAL_SIGNERS, VERIFICATION_KEY_LENGTH,
+};
 use frame_support::{
     assert_noop, assert_ok,
     dispatch::{GetDispatchInfo, Pays},
-    traits::Currency,
+    traits::{
+        ConstU32, Currency, GetStorageVersion, OnInitialize, OnRuntimeUpgrade, StorageVersion,
+    },
     BoundedVec,
 };
 use pallet_programs::ProgramInfo;
@@ -31,8 +36,12 @@ use sp_runtime::{
 
 use crate as pallet_registry;
 use crate::{
-    mock::*, Error, JumpStartDetails, JumpStartStatus, ModifiableKeys, ProgramInstance, Registered,
-    RegisteredInfo, RegisteringDetails, ValidateConfirmRegistered,
+    migrations::{v0, v1, v2, v3, v4, v5, v6, v7},
+    mock::*,
+    Deregistering, DeregisteringDetails, Dkg, Error, JumpStartDetails, JumpStartStatus,
+    ModifiableKeys, ProgramInstance, RecoveryKeyInfo, Registered, RegisteredInfo,
+    RegisteringDetails, RegistrationFailure, RegistrationStatus, ValidateConfirmRegistered,
+    VerifyingKey,
 };
 
 const NULL_ARR: [u8; 32] = [0; 32];
@@ -58,6 +67,7 @@ fn it_registers_a_user() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
         pallet_programs::Programs::<Test>::insert(
@@ -67,6 +77,8 @@ fn it_registers_a_user() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 0,
             },
@@ -76,6 +88,9 @@ fn it_registers_a_user() {
             RuntimeOrigin::signed(1),
             2 as <Test as frame_system::Config>::AccountId,
             programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
         ));
         assert_eq!(Registry::dkg(0), vec![1u64.encode()]);
         assert_eq!(
@@ -197,6 +212,7 @@ fn it_tests_jump_start_result() {
             },
             "Jump start in done status after all confirmations"
         );
+        assert_eq!(Staking::parent_key_threshold(), 1, "Parent key has the network's threshold");
     });
 }
 
@@ -222,6 +238,7 @@ fn it_confirms_registers_a_user() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
         pallet_programs::Programs::<Test>::insert(
@@ -231,6 +248,8 @@ fn it_confirms_registers_a_user() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 0,
             },
@@ -240,6 +259,9 @@ fn it_confirms_registers_a_user() {
             RuntimeOrigin::signed(1),
             2 as <Test as frame_system::Config>::AccountId,
             programs_info.clone(),
+            SignatureScheme::Ecdsa,
+            1,
+            2
         ));
 
         pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
@@ -264,10 +286,20 @@ fn it_confirms_registers_a_user() {
 
         let registering_info = RegisteringDetails::<Test> {
             confirmations: vec![1],
+            refusals: vec![],
             programs_data: programs_info.clone(),
             verifying_key: Some(expected_verifying_key.clone()),
             program_modification_account: 2,
             version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+            status: RegistrationStatus::Confirming { confirmations: 1, required: 2 },
+            deposit: RegistrationDeposit::get(),
+            dkg_attempts: 1,
+            dkg_deadline: 1 + DkgTimeout::get(),
+            imported_key: false,
+            threshold: 1,
+            total_signers: 2,
+            committee: vec![1, 2],
         };
 
         assert_eq!(Registry::registering(1), Some(registering_info));
@@ -279,19 +311,199 @@ fn it_confirms_registers_a_user() {
         ));
 
         assert_eq!(Registry::registering(1), None);
+        assert_eq!(
+            Registry::registration_status(&1),
+            Some(RegistrationStatus::Done(expected_verifying_key.clone()))
+        );
         assert_eq!(
             Registry::registered(expected_verifying_key.clone()).unwrap(),
             RegisteredInfo {
                 programs_data: programs_info.clone(),
                 program_modification_account: 2,
                 version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: 1,
+                total_signers: 2,
+                committee: vec![1, 2],
             }
         );
         assert_eq!(
             Registry::modifiable_keys(2),
-            vec![expected_verifying_key],
+            vec![expected_verifying_key.clone()],
             "list of modifable keys exist"
         );
+
+        // a key which is already registered cannot be confirmed for another account
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(5),
+            2 as <Test as frame_system::Config>::AccountId,
+            programs_info.clone(),
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
+        assert_noop!(
+            Registry::confirm_register(RuntimeOrigin::signed(1), 5, expected_verifying_key.clone()),
+            Error::<Test>::AlreadyRegistered
+        );
+
+        // once the key is deregistered the account has no registration to report
+        assert_ok!(Registry::deregister(RuntimeOrigin::signed(2), expected_verifying_key));
+        assert_eq!(Registry::registration_status(&1), None);
+    });
+}
+
+#[test]
+fn it_registers_with_a_chosen_signing_committee() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+
+        // The threshold and committee size must be within the bounds in `SignersInfo`, which are
+        // a threshold of at least 1 and at most 2 signers
+        assert_noop!(
+            Registry::register(
+                RuntimeOrigin::signed(1),
+                2,
+                programs_info.clone(),
+                SignatureScheme::Ecdsa,
+                0,
+                2
+            ),
+            Error::<Test>::ThresholdTooLow
+        );
+        assert_noop!(
+            Registry::register(
+                RuntimeOrigin::signed(1),
+                2,
+                programs_info.clone(),
+                SignatureScheme::Ecdsa,
+                2,
+                1
+            ),
+            Error::<Test>::ThresholdGreaterThanSigners
+        );
+        assert_noop!(
+            Registry::register(
+                RuntimeOrigin::signed(1),
+                2,
+                programs_info.clone(),
+                SignatureScheme::Ecdsa,
+                1,
+                3
+            ),
+            Error::<Test>::TooManySigners
+        );
+
+        // One of the network's signers is selected for a committee of one, the same one for the
+        // same account and block
+        let committee = Registry::signing_committee(&1, 1, 1);
+        assert_eq!(committee.len(), 1);
+        assert_eq!(Registry::signing_committee(&1, 1, 1), committee);
+        let (member, non_member) = if committee == vec![1] { (1, 2) } else { (2, 1) };
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info.clone(),
+            SignatureScheme::Ecdsa,
+            1,
+            1
+        ));
+
+        let expected_verifying_key =
+            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+
+        assert_noop!(
+            Registry::confirm_register(
+                RuntimeOrigin::signed(non_member),
+                1,
+                expected_verifying_key.clone()
+            ),
+            Error::<Test>::NotValidator
+        );
+
+        // A single confirmation from the committee completes the registration
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(member),
+            1,
+            expected_verifying_key.clone()
+        ));
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::AccountRegistered(
+            1,
+            expected_verifying_key.clone(),
+        )));
+        let registered_info = Registry::registered(expected_verifying_key).unwrap();
+        assert_eq!(registered_info.threshold, 1);
+        assert_eq!(registered_info.total_signers, 1);
+        assert_eq!(registered_info.committee, committee);
+    });
+}
+
+#[test]
+fn it_selects_signers_from_the_signing_committee() {
+    new_test_ext().execute_with(|| {
+        let register = |verifying_key: &Vec<u8>| {
+            Registered::<Test>::insert(
+                BoundedVec::try_from(verifying_key.clone()).unwrap(),
+                RegisteredInfo {
+                    programs_data: BoundedVec::default(),
+                    program_modification_account: 2,
+                    version_number: 1,
+                    signature_scheme: SignatureScheme::Ecdsa,
+                    threshold: 1,
+                    total_signers: 2,
+                    committee: vec![1, 2],
+                },
+            );
+        };
+
+        let verifying_key = vec![1; VERIFICATION_KEY_LENGTH as usize];
+        assert_eq!(Registry::select_signers(verifying_key.clone()), None);
+        register(&verifying_key);
+
+        // One member of the committee signs, and the same one is selected every time
+        let signers = Registry::select_signers(verifying_key.clone()).unwrap();
+        assert_eq!(signers.len(), 1);
+        assert!(vec![1, 2].contains(&signers[0]));
+        assert_eq!(Registry::select_signers(verifying_key.clone()).unwrap(), signers);
+
+        // The committee is the one stored when the account registered, even once the network's
+        // signers have been rotated
+        pallet_staking_extension::Signers::<Test>::put(vec![3, 4]);
+        assert_eq!(Registry::select_signers(verifying_key).unwrap(), signers);
+        pallet_staking_extension::Signers::<Test>::put(vec![1, 2]);
+
+        // Signing for different accounts is spread across the committee
+        let mut all_signers = vec![];
+        for i in 1..10 {
+            let verifying_key = vec![i; VERIFICATION_KEY_LENGTH as usize];
+            register(&verifying_key);
+            all_signers.extend(Registry::select_signers(verifying_key).unwrap());
+        }
+        assert!(all_signers.contains(&1));
+        assert!(all_signers.contains(&2));
     });
 }
 
@@ -303,6 +515,7 @@ fn it_changes_a_program_pointer() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -313,6 +526,8 @@ fn it_changes_a_program_pointer() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 1,
             },
@@ -323,6 +538,7 @@ fn it_changes_a_program_pointer() {
         let new_programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: new_program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -333,6 +549,8 @@ fn it_changes_a_program_pointer() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 1,
             },
@@ -344,6 +562,10 @@ fn it_changes_a_program_pointer() {
             programs_data: programs_info,
             program_modification_account: 2,
             version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: 1,
+            total_signers: 2,
+            committee: vec![1, 2],
         };
 
         Registered::<Test>::insert(expected_verifying_key.clone(), &registered_info);
@@ -354,8 +576,34 @@ fn it_changes_a_program_pointer() {
             expected_verifying_key.clone(),
             new_programs_info.clone(),
         ));
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::ProgramChangeRequested(
+            2,
+            expected_verifying_key.clone(),
+            new_programs_info.clone(),
+        )));
+        // The change only takes effect once the signing committee has checked it
+        assert_eq!(Registry::registered(expected_verifying_key.clone()).unwrap(), registered_info);
+        assert_eq!(
+            Registry::program_changes(System::block_number()),
+            vec![expected_verifying_key.clone()]
+        );
+        assert_noop!(
+            Registry::change_program_instance(
+                RuntimeOrigin::signed(2),
+                expected_verifying_key.clone(),
+                new_programs_info.clone(),
+            ),
+            Error::<Test>::ProgramChangeInProgress
+        );
+
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        assert_ok!(Registry::confirm_program_change(
+            RuntimeOrigin::signed(1),
+            expected_verifying_key.clone()
+        ));
         registered_info.programs_data = new_programs_info;
         assert_eq!(Registry::registered(expected_verifying_key.clone()).unwrap(), registered_info);
+        assert_eq!(Registry::pending_program_changes(expected_verifying_key.clone()), None);
         assert_eq!(
             pallet_programs::Programs::<Test>::get(program_hash).unwrap().ref_counter,
             0,
@@ -371,8 +619,16 @@ fn it_changes_a_program_pointer() {
         let unreigistered_program_hash =
             <Test as frame_system::Config>::Hashing::hash(&unreigistered_program);
         let unregistered_programs_info = BoundedVec::try_from(vec![
-            ProgramInstance { program_pointer: new_program_hash, program_config: vec![] },
-            ProgramInstance { program_pointer: unreigistered_program_hash, program_config: vec![] },
+            ProgramInstance {
+                program_pointer: new_program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
+            ProgramInstance {
+                program_pointer: unreigistered_program_hash,
+                program_config: vec![],
+                fuel: None,
+            },
         ])
         .unwrap();
         assert_noop!(
@@ -392,6 +648,208 @@ fn it_changes_a_program_pointer() {
             ),
             Error::<Test>::NoProgramSet
         );
+
+        // The fuel for a program must be within the network's maximum, which is 5 in the mock
+        for invalid_fuel in [0, 6] {
+            assert_noop!(
+                Registry::change_program_instance(
+                    RuntimeOrigin::signed(2),
+                    expected_verifying_key.clone(),
+                    BoundedVec::try_from(vec![ProgramInstance {
+                        program_pointer: new_program_hash,
+                        program_config: vec![],
+                        fuel: Some(invalid_fuel),
+                    }])
+                    .unwrap(),
+                ),
+                Error::<Test>::InvalidProgramFuel
+            );
+        }
+
+        // The configuration for a program with a configuration schema must be well-formed JSON
+        let schema_program = vec![11];
+        let schema_program_hash = <Test as frame_system::Config>::Hashing::hash(&schema_program);
+        pallet_programs::Programs::<Test>::insert(
+            schema_program_hash,
+            ProgramInfo {
+                bytecode: schema_program,
+                configuration_schema: b"{}".to_vec(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+        assert_noop!(
+            Registry::change_program_instance(
+                RuntimeOrigin::signed(2),
+                expected_verifying_key.clone(),
+                BoundedVec::try_from(vec![ProgramInstance {
+                    program_pointer: schema_program_hash,
+                    program_config: vec![14],
+                    fuel: None,
+                }])
+                .unwrap(),
+            ),
+            pallet_programs::Error::<Test>::InvalidProgramConfig
+        );
+        assert_ok!(Registry::change_program_instance(
+            RuntimeOrigin::signed(2),
+            expected_verifying_key.clone(),
+            BoundedVec::try_from(vec![ProgramInstance {
+                program_pointer: schema_program_hash,
+                program_config: b"{}".to_vec(),
+                fuel: None,
+            }])
+            .unwrap(),
+        ));
+        assert_eq!(
+            pallet_programs::Programs::<Test>::get(schema_program_hash).unwrap().ref_counter,
+            1
+        );
+
+        // A refused change is dropped, and the programs are left as they were
+        assert_ok!(Registry::refuse_program_change(
+            RuntimeOrigin::signed(1),
+            expected_verifying_key.clone()
+        ));
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::ProgramChangeRefused(
+            expected_verifying_key.clone(),
+        )));
+        assert_eq!(Registry::registered(expected_verifying_key.clone()).unwrap(), registered_info);
+        assert_eq!(Registry::pending_program_changes(expected_verifying_key.clone()), None);
+        assert_eq!(
+            pallet_programs::Programs::<Test>::get(schema_program_hash).unwrap().ref_counter,
+            0,
+            "ref counter is released"
+        );
+        assert_noop!(
+            Registry::refuse_program_change(
+                RuntimeOrigin::signed(1),
+                expected_verifying_key.clone()
+            ),
+            Error::<Test>::NoProgramChange
+        );
+
+        let fuel_limited_programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: new_program_hash,
+            program_config: vec![],
+            fuel: Some(5),
+        }])
+        .unwrap();
+        assert_ok!(Registry::change_program_instance(
+            RuntimeOrigin::signed(2),
+            expected_verifying_key.clone(),
+            fuel_limited_programs_info.clone(),
+        ));
+        assert_ok!(Registry::confirm_program_change(
+            RuntimeOrigin::signed(1),
+            expected_verifying_key.clone()
+        ));
+        assert_eq!(
+            Registry::registered(expected_verifying_key).unwrap().programs_data,
+            fuel_limited_programs_info
+        );
+    });
+}
+
+#[test]
+fn it_needs_a_threshold_of_the_committee_to_change_programs() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 1,
+            },
+        );
+
+        let verifying_key: VerifyingKey = BoundedVec::default();
+        let registered_info = RegisteredInfo {
+            programs_data: programs_info.clone(),
+            program_modification_account: 2,
+            version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: 2,
+            total_signers: 2,
+            committee: vec![1, 2],
+        };
+        Registered::<Test>::insert(&verifying_key, &registered_info);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(7, 7);
+
+        assert_ok!(Registry::change_program_instance(
+            RuntimeOrigin::signed(2),
+            verifying_key.clone(),
+            programs_info.clone(),
+        ));
+        assert_eq!(pallet_programs::Programs::<Test>::get(program_hash).unwrap().ref_counter, 2);
+
+        // Only threshold servers of the signing committee can respond, and only once each
+        assert_noop!(
+            Registry::confirm_program_change(RuntimeOrigin::signed(7), verifying_key.clone()),
+            Error::<Test>::NotValidator
+        );
+        assert_ok!(Registry::confirm_program_change(
+            RuntimeOrigin::signed(1),
+            verifying_key.clone()
+        ));
+        assert_noop!(
+            Registry::confirm_program_change(RuntimeOrigin::signed(1), verifying_key.clone()),
+            Error::<Test>::AlreadyConfirmed
+        );
+        assert_noop!(
+            Registry::refuse_program_change(RuntimeOrigin::signed(1), verifying_key.clone()),
+            Error::<Test>::AlreadyConfirmed
+        );
+
+        // A single refusal does not drop the change either
+        assert_ok!(Registry::refuse_program_change(
+            RuntimeOrigin::signed(2),
+            verifying_key.clone()
+        ));
+        let program_change = Registry::pending_program_changes(&verifying_key).unwrap();
+        assert_eq!(program_change.confirmations, vec![1]);
+        assert_eq!(program_change.refusals, vec![2]);
+
+        // Without `threshold` confirmations by the deadline, the change is dropped
+        let deadline = Registry::program_change_deadline(1);
+        assert_eq!(program_change.deadline, deadline);
+        Registry::on_initialize(deadline);
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::ProgramChangeExpired(
+            verifying_key.clone(),
+        )));
+        assert_eq!(Registry::pending_program_changes(&verifying_key), None);
+        assert_eq!(Registry::registered(&verifying_key).unwrap(), registered_info);
+        assert_eq!(pallet_programs::Programs::<Test>::get(program_hash).unwrap().ref_counter, 1);
+
+        // A pending change is dropped along with a deregistered key
+        assert_ok!(Registry::change_program_instance(
+            RuntimeOrigin::signed(2),
+            verifying_key.clone(),
+            programs_info,
+        ));
+        assert_ok!(Registry::deregister(RuntimeOrigin::signed(2), verifying_key.clone()));
+        assert_eq!(Registry::pending_program_changes(&verifying_key), None);
+        assert_eq!(pallet_programs::Programs::<Test>::get(program_hash).unwrap().ref_counter, 0);
     });
 }
 
@@ -403,6 +861,7 @@ fn it_changes_a_program_mod_account() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -413,6 +872,8 @@ fn it_changes_a_program_mod_account() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 1,
             },
@@ -424,6 +885,10 @@ fn it_changes_a_program_mod_account() {
             programs_data: programs_info,
             program_modification_account: 2,
             version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+            threshold: 1,
+            total_signers: 2,
+            committee: vec![1, 2],
         };
 
         Registered::<Test>::insert(expected_verifying_key.clone(), &registered_info);
@@ -476,59 +941,943 @@ fn it_changes_a_program_mod_account() {
 }
 
 #[test]
-fn it_fails_on_non_matching_verifying_keys() {
+fn it_sets_a_rate_limit() {
     new_test_ext().execute_with(|| {
-        let empty_program = vec![];
-        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
-        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
-            program_pointer: program_hash,
-            program_config: vec![],
-        }])
-        .unwrap();
+        let verifying_key = BoundedVec::default();
+        let rate_limit = RateLimit { max_requests: 10, window_blocks: 5 };
 
-        pallet_programs::Programs::<Test>::insert(
-            program_hash,
-            ProgramInfo {
-                bytecode: empty_program.clone(),
-                configuration_schema: empty_program.clone(),
-                auxiliary_data_schema: empty_program.clone(),
-                oracle_data_pointer: empty_program.clone(),
-                deployer: 1,
-                ref_counter: 0,
-            },
+        assert_noop!(
+            Registry::set_rate_limit(
+                RuntimeOrigin::signed(2),
+                verifying_key.clone(),
+                Some(rate_limit)
+            ),
+            Error::<Test>::NotRegistered
         );
 
-        let expected_verifying_key =
-            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
-        let unexpected_verifying_key =
-            BoundedVec::try_from(vec![1; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        Registered::<Test>::insert(
+            verifying_key.clone(),
+            RegisteredInfo {
+                programs_data: BoundedVec::default(),
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: 1,
+                total_signers: 2,
+                committee: vec![1, 2],
+            },
+        );
 
-        assert_ok!(Registry::register(
-            RuntimeOrigin::signed(1),
-            2 as <Test as frame_system::Config>::AccountId,
-            programs_info,
-        ));
-        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
-        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+        assert_noop!(
+            Registry::set_rate_limit(
+                RuntimeOrigin::signed(3),
+                verifying_key.clone(),
+                Some(rate_limit)
+            ),
+            Error::<Test>::NotAuthorized
+        );
+        assert_noop!(
+            Registry::set_rate_limit(
+                RuntimeOrigin::signed(2),
+                verifying_key.clone(),
+                Some(RateLimit { max_requests: 10, window_blocks: 0 })
+            ),
+            Error::<Test>::InvalidRateLimit
+        );
+        assert_noop!(
+            Registry::set_rate_limit(
+                RuntimeOrigin::signed(2),
+                verifying_key.clone(),
+                Some(RateLimit {
+                    max_requests: pallet_parameters::Pallet::<Test>::max_rate_limit_requests() + 1,
+                    window_blocks: 5
+                })
+            ),
+            Error::<Test>::RateLimitTooHigh
+        );
 
-        assert_ok!(Registry::confirm_register(
-            RuntimeOrigin::signed(1),
-            1,
-            expected_verifying_key.clone()
+        assert_ok!(Registry::set_rate_limit(
+            RuntimeOrigin::signed(2),
+            verifying_key.clone(),
+            Some(rate_limit)
+        ));
+        assert_eq!(Registry::rate_limits(verifying_key.clone()), Some(rate_limit));
+
+        assert_ok!(Registry::set_rate_limit(RuntimeOrigin::signed(2), verifying_key.clone(), None));
+        assert_eq!(Registry::rate_limits(verifying_key), None, "rate limit removed");
+    })
+}
+
+#[test]
+fn it_sets_a_recovery_key() {
+    new_test_ext().execute_with(|| {
+        let verifying_key = BoundedVec::default();
+        let recovery_key = [1; 32];
+
+        assert_noop!(
+            Registry::set_recovery_key(
+                RuntimeOrigin::signed(2),
+                verifying_key.clone(),
+                Some(recovery_key)
+            ),
+            Error::<Test>::NotRegistered
+        );
+
+        Registered::<Test>::insert(
+            verifying_key.clone(),
+            RegisteredInfo {
+                programs_data: BoundedVec::default(),
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: 1,
+                total_signers: 2,
+                committee: vec![1, 2],
+            },
+        );
+
+        assert_noop!(
+            Registry::set_recovery_key(
+                RuntimeOrigin::signed(3),
+                verifying_key.clone(),
+                Some(recovery_key)
+            ),
+            Error::<Test>::NotAuthorized
+        );
+
+        // The recovery key can only be used once the delay set in the parameters pallet is over
+        System::set_block_number(3);
+        assert_ok!(Registry::set_recovery_key(
+            RuntimeOrigin::signed(2),
+            verifying_key.clone(),
+            Some(recovery_key)
+        ));
+        let recovery_key_info = RecoveryKeyInfo {
+            recovery_key,
+            active_from: 3 + pallet_parameters::Pallet::<Test>::recovery_key_delay() as u64,
+        };
+        assert_eq!(Registry::recovery_keys(verifying_key.clone()), Some(recovery_key_info.clone()));
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::RecoveryKeyChanged(
+            verifying_key.clone(),
+            Some(recovery_key_info),
+        )));
+
+        assert_ok!(Registry::set_recovery_key(
+            RuntimeOrigin::signed(2),
+            verifying_key.clone(),
+            None
+        ));
+        assert_eq!(Registry::recovery_keys(verifying_key), None, "recovery key removed");
+    })
+}
+
+#[test]
+fn it_deregisters_a_user() {
+    new_test_ext().execute_with(|| {
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 1,
+            },
+        );
+
+        let verifying_key =
+            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        assert_noop!(
+            Registry::deregister(RuntimeOrigin::signed(2), verifying_key.clone()),
+            Error::<Test>::NotRegistered
+        );
+
+        Registered::<Test>::insert(
+            verifying_key.clone(),
+            RegisteredInfo {
+                programs_data: programs_info,
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: 1,
+                total_signers: 2,
+                committee: vec![1, 2],
+            },
+        );
+        ModifiableKeys::<Test>::insert(
+            2,
+            BoundedVec::try_from(vec![verifying_key.clone()]).unwrap(),
+        );
+
+        assert_noop!(
+            Registry::deregister(RuntimeOrigin::signed(3), verifying_key.clone()),
+            Error::<Test>::NotAuthorized
+        );
+        assert_ok!(Registry::deregister(RuntimeOrigin::signed(2), verifying_key.clone()));
+
+        assert_eq!(Registry::registered(verifying_key.clone()), None);
+        assert_eq!(Registry::modifiable_keys(2), vec![], "account 2 no longer has the key");
+        assert_eq!(
+            pallet_programs::Programs::<Test>::get(program_hash).unwrap().ref_counter,
+            0,
+            "ref counter is decremented"
+        );
+        assert_eq!(Registry::deregistrations(System::block_number()), vec![verifying_key.clone()]);
+        let deadline = Registry::deregistration_deadline(System::block_number());
+        assert_eq!(
+            Registry::deregistering(verifying_key.clone()),
+            Some(DeregisteringDetails {
+                committee: vec![1, 2],
+                confirmations: vec![],
+                attempts: 1,
+                deadline
+            })
+        );
+        assert_eq!(Registry::deregistration_deadlines(deadline), vec![verifying_key.clone()]);
+
+        // keyshares have to be deleted by every member of the signing committee
+        assert_noop!(
+            Registry::confirm_deregistration(RuntimeOrigin::signed(1), verifying_key.clone()),
+            Error::<Test>::NoThresholdKey
+        );
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+        assert_noop!(
+            Registry::confirm_deregistration(RuntimeOrigin::signed(7), verifying_key.clone()),
+            Error::<Test>::NotValidator
+        );
+        // a validator outside the committee has no keyshare to delete
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(5, 5);
+        assert_noop!(
+            Registry::confirm_deregistration(RuntimeOrigin::signed(5), verifying_key.clone()),
+            Error::<Test>::NotValidator
+        );
+
+        assert_ok!(Registry::confirm_deregistration(
+            RuntimeOrigin::signed(1),
+            verifying_key.clone()
+        ));
+        assert_eq!(Registry::deregistering(verifying_key.clone()).unwrap().confirmations, vec![1]);
+        assert_noop!(
+            Registry::confirm_deregistration(RuntimeOrigin::signed(1), verifying_key.clone()),
+            Error::<Test>::AlreadyConfirmed
+        );
+
+        assert_ok!(Registry::confirm_deregistration(
+            RuntimeOrigin::signed(2),
+            verifying_key.clone()
+        ));
+        assert_eq!(Registry::deregistering(verifying_key.clone()), None);
+        assert_noop!(
+            Registry::confirm_deregistration(RuntimeOrigin::signed(2), verifying_key),
+            Error::<Test>::NotDeregistering
+        );
+    })
+}
+
+#[test]
+fn it_retries_keyshare_deletion_until_it_expires() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let verifying_key =
+            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        Registered::<Test>::insert(
+            verifying_key.clone(),
+            RegisteredInfo {
+                programs_data: BoundedVec::default(),
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: 1,
+                total_signers: 2,
+                committee: vec![1, 2],
+            },
+        );
+        assert_ok!(Registry::deregister(RuntimeOrigin::signed(2), verifying_key.clone()));
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        assert_ok!(Registry::confirm_deregistration(
+            RuntimeOrigin::signed(1),
+            verifying_key.clone()
+        ));
+
+        // Validator 2 does not confirm in time, so the deletion is requested again
+        let first_deadline = 2 + DeregistrationTimeout::get();
+        System::set_block_number(first_deadline);
+        Registry::on_initialize(first_deadline);
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::DeregistrationRetried(
+            verifying_key.clone(),
+            2,
+        )));
+        assert_eq!(Registry::deregistrations(first_deadline), vec![verifying_key.clone()]);
+        let deregistering_details = Registry::deregistering(verifying_key.clone()).unwrap();
+        assert_eq!(deregistering_details.attempts, 2);
+        assert_eq!(deregistering_details.confirmations, vec![1]);
+
+        // After the last attempt the key is no longer tracked
+        let second_deadline = first_deadline + 1 + DeregistrationTimeout::get();
+        assert_eq!(deregistering_details.deadline, second_deadline);
+        System::set_block_number(second_deadline);
+        Registry::on_initialize(second_deadline);
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::DeregistrationExpired(
+            verifying_key.clone(),
+            vec![2],
+        )));
+        assert_eq!(Registry::deregistering(verifying_key), None);
+    })
+}
+
+#[test]
+fn it_refuses_registrations_with_invalid_program_configs() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2 as <Test as frame_system::Config>::AccountId,
+            programs_info,
+            SignatureScheme::Ecdsa,
+            2,
+            2
+        ));
+        assert_eq!(Balances::reserved_balance(1), RegistrationDeposit::get());
+
+        // Only threshold servers of the signing committee can refuse a registration
+        assert_noop!(
+            Registry::refuse_register(RuntimeOrigin::signed(1), 1),
+            Error::<Test>::NoThresholdKey
+        );
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        assert_noop!(
+            Registry::refuse_register(RuntimeOrigin::signed(1), 3),
+            Error::<Test>::NotRegistering
+        );
+
+        // A single refusal does not fail the registration
+        assert_ok!(Registry::refuse_register(RuntimeOrigin::signed(1), 1));
+        let registering_info = Registry::registering(1).unwrap();
+        assert_eq!(registering_info.refusals, vec![1]);
+        assert!(!matches!(registering_info.status, RegistrationStatus::Failed(_)));
+        assert_eq!(Balances::reserved_balance(1), RegistrationDeposit::get());
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::RegistrationRefused(1, 1)));
+
+        // A threshold server cannot refuse twice, or confirm once it has refused
+        assert_noop!(
+            Registry::refuse_register(RuntimeOrigin::signed(1), 1),
+            Error::<Test>::AlreadyRefused
+        );
+        assert_noop!(
+            Registry::confirm_register(
+                RuntimeOrigin::signed(1),
+                1,
+                BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap()
+            ),
+            Error::<Test>::AlreadyRefused
+        );
+
+        // The registration fails once `threshold` of the committee have refused it
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+        assert_ok!(Registry::refuse_register(RuntimeOrigin::signed(2), 1));
+        assert_eq!(
+            Registry::registering(1).unwrap().status,
+            RegistrationStatus::Failed(RegistrationFailure::InvalidProgramConfig)
+        );
+        assert_eq!(Balances::reserved_balance(1), 0, "the deposit is refunded");
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::FailedRegistration(
+            1,
+            RegistrationFailure::InvalidProgramConfig,
+        )));
+
+        // A failed registration cannot be refused again or confirmed
+        assert_noop!(
+            Registry::refuse_register(RuntimeOrigin::signed(1), 1),
+            Error::<Test>::RegistrationNotInProgress
+        );
+        assert_noop!(
+            Registry::confirm_register(
+                RuntimeOrigin::signed(1),
+                1,
+                BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap()
+            ),
+            Error::<Test>::RegistrationNotInProgress
+        );
+    })
+}
+
+#[test]
+fn it_fails_on_non_matching_verifying_keys() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+
+        let expected_verifying_key =
+            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        let unexpected_verifying_key =
+            BoundedVec::try_from(vec![1; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2 as <Test as frame_system::Config>::AccountId,
+            programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(1),
+            1,
+            expected_verifying_key.clone()
         ));
 
         // uses different verifying key
         assert_ok!(Registry::confirm_register(
             RuntimeOrigin::signed(2),
             1,
-            unexpected_verifying_key.try_into().unwrap()
+            unexpected_verifying_key.try_into().unwrap()
+        ));
+
+        // not registered, and the failure reason is kept
+        assert_eq!(
+            Registry::registering(1).unwrap().status,
+            RegistrationStatus::Failed(RegistrationFailure::MismatchedVerifyingKeys)
+        );
+        assert_eq!(Registry::registered(expected_verifying_key.clone()), None);
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::FailedRegistration(
+            1,
+            RegistrationFailure::MismatchedVerifyingKeys,
+        )));
+
+        // no more confirmations are accepted for a failed registration
+        assert_noop!(
+            Registry::confirm_register(RuntimeOrigin::signed(1), 1, expected_verifying_key),
+            Error::<Test>::RegistrationNotInProgress
+        );
+
+        // the account can try again after pruning the failed registration
+        assert_noop!(
+            Registry::register(
+                RuntimeOrigin::signed(1),
+                2,
+                Registry::registering(1).unwrap().programs_data,
+                SignatureScheme::Ecdsa,
+                1,
+                2
+            ),
+            Error::<Test>::AlreadySubmitted
+        );
+        assert_ok!(Registry::prune_registration(RuntimeOrigin::signed(1)));
+        assert_eq!(Registry::registering(1), None);
+    })
+}
+#[test]
+fn it_tracks_the_registration_status() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+        let expected_verifying_key =
+            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info.clone(),
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
+        assert_eq!(Registry::registering(1).unwrap().status, RegistrationStatus::Submitted);
+        assert_eq!(Balances::reserved_balance(1), RegistrationDeposit::get());
+
+        // the offchain worker asks for a dkg in the block after registering
+        System::set_block_number(2);
+        Registry::on_initialize(2);
+        assert_eq!(Registry::registering(1).unwrap().status, RegistrationStatus::DkgStarted(2));
+
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(1),
+            1,
+            expected_verifying_key.clone()
+        ));
+        assert_eq!(
+            Registry::registering(1).unwrap().status,
+            RegistrationStatus::Confirming { confirmations: 1, required: 2 }
+        );
+
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(2),
+            1,
+            expected_verifying_key.clone()
+        ));
+        assert_eq!(Registry::registering(1), None);
+        assert_eq!(
+            Registry::registration_status(&1),
+            Some(RegistrationStatus::Done(expected_verifying_key.clone()))
+        );
+        assert_eq!(Balances::reserved_balance(1), 0, "the deposit is refunded");
+
+        // a finished registration is no longer kept, so there is nothing to prune and the account
+        // can register again
+        assert_noop!(
+            Registry::prune_registration(RuntimeOrigin::signed(1)),
+            Error::<Test>::NotRegistering
+        );
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
+        assert_eq!(Registry::registering(1).unwrap().status, RegistrationStatus::Submitted);
+    })
+}
+
+#[test]
+fn it_retries_a_stalled_dkg_and_then_fails() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        for validator in [1, 2] {
+            assert_ok!(FrameStaking::bond(
+                RuntimeOrigin::signed(validator),
+                50,
+                pallet_staking::RewardDestination::Stash
+            ));
+        }
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+        let expected_verifying_key =
+            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
+        let first_deadline = 2 + DkgTimeout::get();
+        assert_eq!(Registry::registering(1).unwrap().dkg_deadline, first_deadline);
+        assert_eq!(Registry::dkg_deadlines(first_deadline), vec![1]);
+
+        // only one of the validators confirms
+        assert_ok!(Registry::confirm_register(RuntimeOrigin::signed(1), 1, expected_verifying_key));
+
+        System::set_block_number(first_deadline);
+        Registry::on_initialize(first_deadline);
+
+        // the validator which did not confirm is reported, and the dkg is started again
+        assert_eq!(Slashing::failed_registrations(1), 0);
+        assert_eq!(Slashing::failed_registrations(2), 1);
+        // which makes it less likely to be selected as a signer
+        assert_eq!(Staking::signer_weight(&1), 50);
+        assert_eq!(Staking::signer_weight(&2), 25);
+        let registering_info = Registry::registering(1).unwrap();
+        assert_eq!(registering_info.status, RegistrationStatus::Submitted);
+        assert_eq!(registering_info.dkg_attempts, 2);
+        assert!(registering_info.confirmations.is_empty());
+        assert_eq!(registering_info.verifying_key, None);
+        assert_eq!(Registry::dkg(first_deadline), vec![1u64.encode()]);
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::DkgRetried(1, 2)));
+        assert_eq!(Balances::reserved_balance(1), RegistrationDeposit::get());
+
+        // after the last attempt the registration fails and the deposit is refunded
+        let second_deadline = first_deadline + 1 + DkgTimeout::get();
+        assert_eq!(Registry::registering(1).unwrap().dkg_deadline, second_deadline);
+        System::set_block_number(second_deadline);
+        Registry::on_initialize(second_deadline);
+
+        assert_eq!(Slashing::failed_registrations(1), 1);
+        assert_eq!(Slashing::failed_registrations(2), 2);
+        assert_eq!(
+            Registry::registering(1).unwrap().status,
+            RegistrationStatus::Failed(RegistrationFailure::DkgTimedOut)
+        );
+        System::assert_last_event(RuntimeEvent::Registry(crate::Event::FailedRegistration(
+            1,
+            RegistrationFailure::DkgTimedOut,
+        )));
+        assert_eq!(Balances::reserved_balance(1), 0);
+
+        assert_ok!(Registry::prune_registration(RuntimeOrigin::signed(1)));
+        assert_eq!(Registry::registering(1), None);
+        assert_eq!(Balances::free_balance(1), 100);
+    })
+}
+
+#[test]
+fn it_registers_an_imported_key() {
+    new_test_ext().execute_with(|| {
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+        let imported_verifying_key: BoundedVec<u8, ConstU32<VERIFICATION_KEY_LENGTH>> =
+            BoundedVec::try_from(vec![2; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        let other_verifying_key =
+            BoundedVec::try_from(vec![3; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+
+        // only secp256k1 keys can be imported
+        assert_noop!(
+            Registry::register_with_imported_key(
+                RuntimeOrigin::signed(1),
+                2,
+                programs_info.clone(),
+                BoundedVec::try_from(vec![2; ED25519_VERIFICATION_KEY_LENGTH as usize]).unwrap(),
+                1,
+                2
+            ),
+            Error::<Test>::MismatchedVerifyingKeyLength
+        );
+
+        assert_ok!(Registry::register_with_imported_key(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info.clone(),
+            imported_verifying_key.clone(),
+            1,
+            2
+        ));
+        let registering_info = Registry::registering(1).unwrap();
+        assert!(registering_info.imported_key);
+        assert_eq!(registering_info.verifying_key, Some(imported_verifying_key.clone()));
+        assert!(Registry::dkg(0).is_empty(), "No distributed key generation is requested");
+        assert_eq!(Registry::key_imports(imported_verifying_key.clone()), Some(1));
+
+        // another account cannot import the same key while this import is in progress
+        assert_noop!(
+            Registry::register_with_imported_key(
+                RuntimeOrigin::signed(2),
+                2,
+                programs_info.clone(),
+                imported_verifying_key.clone(),
+                1,
+                2
+            ),
+            Error::<Test>::KeyImportInProgress
+        );
+
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+
+        // a threshold server cannot confirm any other key
+        assert_noop!(
+            Registry::confirm_register(RuntimeOrigin::signed(1), 1, other_verifying_key),
+            Error::<Test>::MismatchedVerifyingKey
+        );
+
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(1),
+            1,
+            imported_verifying_key.clone()
+        ));
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(2),
+            1,
+            imported_verifying_key.clone()
+        ));
+        assert_eq!(Registry::registering(1), None);
+        assert_eq!(
+            Registry::registration_status(&1),
+            Some(RegistrationStatus::Done(imported_verifying_key.clone()))
+        );
+        assert!(Registry::registered(imported_verifying_key.clone()).is_some());
+        assert_eq!(Registry::key_imports(imported_verifying_key.clone()), None);
+
+        // the same key cannot be imported twice
+        assert_noop!(
+            Registry::register_with_imported_key(
+                RuntimeOrigin::signed(2),
+                2,
+                programs_info,
+                imported_verifying_key,
+                1,
+                2
+            ),
+            Error::<Test>::AlreadyRegistered
+        );
+
+        // nor can a key whose keyshares are still being deleted
+        Deregistering::<Test>::insert(
+            &other_verifying_key,
+            DeregisteringDetails::<Test> {
+                committee: vec![1, 2],
+                confirmations: vec![],
+                attempts: 1,
+                deadline: 10,
+            },
+        );
+        assert_noop!(
+            Registry::register_with_imported_key(
+                RuntimeOrigin::signed(2),
+                2,
+                programs_info,
+                other_verifying_key,
+                1,
+                2
+            ),
+            Error::<Test>::KeyDeregistering
+        );
+    })
+}
+
+#[test]
+fn it_fails_a_stalled_key_import_without_reporting_validators() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+        let imported_verifying_key =
+            BoundedVec::try_from(vec![2; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        assert_ok!(Registry::register_with_imported_key(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info.clone(),
+            imported_verifying_key.clone(),
+            1,
+            2
+        ));
+
+        // The user may never have sent the keyshares, so no validator is reported
+        let deadline = 2 + DkgTimeout::get();
+        System::set_block_number(deadline);
+        Registry::on_initialize(deadline);
+        assert_eq!(Slashing::failed_registrations(1), 0);
+        assert_eq!(Slashing::failed_registrations(2), 0);
+        assert_eq!(
+            Registry::registering(1).unwrap().status,
+            RegistrationStatus::Failed(RegistrationFailure::DkgTimedOut)
+        );
+
+        // Once the import has failed another account may import the key
+        assert!(!Registry::key_import_in_progress(&imported_verifying_key));
+        assert_ok!(Registry::register_with_imported_key(
+            RuntimeOrigin::signed(2),
+            2,
+            programs_info,
+            imported_verifying_key.clone(),
+            1,
+            2
+        ));
+        assert_eq!(Registry::key_imports(imported_verifying_key), Some(2));
+    })
+}
+
+#[test]
+fn it_registers_an_ed25519_account() {
+    new_test_ext().execute_with(|| {
+        let empty_program = vec![];
+        let program_hash = <Test as frame_system::Config>::Hashing::hash(&empty_program);
+        let programs_info = BoundedVec::try_from(vec![ProgramInstance {
+            program_pointer: program_hash,
+            program_config: vec![],
+            fuel: None,
+        }])
+        .unwrap();
+        pallet_programs::Programs::<Test>::insert(
+            program_hash,
+            ProgramInfo {
+                bytecode: empty_program.clone(),
+                configuration_schema: empty_program.clone(),
+                auxiliary_data_schema: empty_program.clone(),
+                oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
+                deployer: 1,
+                ref_counter: 0,
+            },
+        );
+
+        let ecdsa_verifying_key =
+            BoundedVec::try_from(vec![0; VERIFICATION_KEY_LENGTH as usize]).unwrap();
+        let expected_verifying_key: BoundedVec<u8, ConstU32<VERIFICATION_KEY_LENGTH>> =
+            BoundedVec::try_from(vec![0; ED25519_VERIFICATION_KEY_LENGTH as usize]).unwrap();
+
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2 as <Test as frame_system::Config>::AccountId,
+            programs_info.clone(),
+            SignatureScheme::Ed25519,
+            1,
+            2
+        ));
+        assert_eq!(Registry::registering(1).unwrap().signature_scheme, SignatureScheme::Ed25519);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(1, 1);
+        pallet_staking_extension::ThresholdToStash::<Test>::insert(2, 2);
+
+        // a secp256k1 key cannot be confirmed for an ed25519 account
+        assert_noop!(
+            Registry::confirm_register(RuntimeOrigin::signed(1), 1, ecdsa_verifying_key),
+            Error::<Test>::MismatchedVerifyingKeyLength
+        );
+
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(1),
+            1,
+            expected_verifying_key.clone()
+        ));
+        assert_ok!(Registry::confirm_register(
+            RuntimeOrigin::signed(2),
+            1,
+            expected_verifying_key.clone()
         ));
 
-        // not registered or registering
         assert_eq!(Registry::registering(1), None);
-        assert_eq!(Registry::registered(expected_verifying_key.clone()), None);
+        assert_eq!(
+            Registry::registration_status(&1),
+            Some(RegistrationStatus::Done(expected_verifying_key.clone()))
+        );
+        assert_eq!(
+            Registry::registered(expected_verifying_key).unwrap(),
+            RegisteredInfo {
+                programs_data: programs_info,
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ed25519,
+                threshold: 1,
+                total_signers: 2,
+                committee: vec![1, 2],
+            }
+        );
     })
 }
+
 #[test]
 fn it_doesnt_allow_double_registering() {
     new_test_ext().execute_with(|| {
@@ -538,6 +1887,7 @@ fn it_doesnt_allow_double_registering() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -548,16 +1898,32 @@ fn it_doesnt_allow_double_registering() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 0,
             },
         );
 
-        assert_ok!(Registry::register(RuntimeOrigin::signed(1), 2, programs_info.clone(),));
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info.clone(),
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
 
         // error if they try to submit another request, even with a different program key
         assert_noop!(
-            Registry::register(RuntimeOrigin::signed(1), 2, programs_info),
+            Registry::register(
+                RuntimeOrigin::signed(1),
+                2,
+                programs_info,
+                SignatureScheme::Ecdsa,
+                1,
+                2
+            ),
             Error::<Test>::AlreadySubmitted
         );
     });
@@ -572,11 +1938,19 @@ fn it_fails_no_program() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
         assert_noop!(
-            Registry::register(RuntimeOrigin::signed(1), 2, programs_info),
+            Registry::register(
+                RuntimeOrigin::signed(1),
+                2,
+                programs_info,
+                SignatureScheme::Ecdsa,
+                1,
+                2
+            ),
             Error::<Test>::NoProgramSet
         );
     });
@@ -586,7 +1960,14 @@ fn it_fails_no_program() {
 fn it_fails_empty_program_list() {
     new_test_ext().execute_with(|| {
         assert_noop!(
-            Registry::register(RuntimeOrigin::signed(1), 2, BoundedVec::try_from(vec![]).unwrap(),),
+            Registry::register(
+                RuntimeOrigin::signed(1),
+                2,
+                BoundedVec::try_from(vec![]).unwrap(),
+                SignatureScheme::Ecdsa,
+                1,
+                2
+            ),
             Error::<Test>::NoProgramSet
         );
     });
@@ -600,6 +1981,7 @@ fn it_tests_prune_registration() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -607,9 +1989,11 @@ fn it_tests_prune_registration() {
             program_hash,
             ProgramInfo {
                 bytecode: inital_program.clone(),
-                configuration_schema: inital_program.clone(),
+                configuration_schema: vec![],
                 auxiliary_data_schema: inital_program.clone(),
                 oracle_data_pointer: inital_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 1,
             },
@@ -617,7 +2001,14 @@ fn it_tests_prune_registration() {
 
         Balances::make_free_balance_be(&2, 100);
         // register a user
-        assert_ok!(Registry::register(RuntimeOrigin::signed(1), 2, programs_info,));
+        assert_ok!(Registry::register(
+            RuntimeOrigin::signed(1),
+            2,
+            programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
+        ));
         assert_eq!(
             pallet_programs::Programs::<Test>::get(program_hash).unwrap().ref_counter,
             2,
@@ -641,6 +2032,7 @@ fn it_provides_free_txs_confirm_done() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -651,6 +2043,8 @@ fn it_provides_free_txs_confirm_done() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 0,
             },
@@ -661,6 +2055,9 @@ fn it_provides_free_txs_confirm_done() {
             RuntimeOrigin::signed(5),
             2 as <Test as frame_system::Config>::AccountId,
             programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
         ));
         let p = ValidateConfirmRegistered::<Test>::new();
         let c = RuntimeCall::Registry(RegistryCall::confirm_register {
@@ -717,6 +2114,7 @@ fn it_provides_free_txs_confirm_done_fails_3() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -727,6 +2125,8 @@ fn it_provides_free_txs_confirm_done_fails_3() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 0,
             },
@@ -738,6 +2138,9 @@ fn it_provides_free_txs_confirm_done_fails_3() {
             RuntimeOrigin::signed(5),
             2 as <Test as frame_system::Config>::AccountId,
             programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
         ));
 
         assert_ok!(Registry::confirm_register(
@@ -766,6 +2169,7 @@ fn it_provides_free_txs_confirm_done_fails_4() {
         let programs_info = BoundedVec::try_from(vec![ProgramInstance {
             program_pointer: program_hash,
             program_config: vec![],
+            fuel: None,
         }])
         .unwrap();
 
@@ -776,6 +2180,8 @@ fn it_provides_free_txs_confirm_done_fails_4() {
                 configuration_schema: empty_program.clone(),
                 auxiliary_data_schema: empty_program.clone(),
                 oracle_data_pointer: empty_program.clone(),
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
                 deployer: 1,
                 ref_counter: 0,
             },
@@ -786,6 +2192,9 @@ fn it_provides_free_txs_confirm_done_fails_4() {
             RuntimeOrigin::signed(5),
             2 as <Test as frame_system::Config>::AccountId,
             programs_info,
+            SignatureScheme::Ecdsa,
+            1,
+            2
         ));
         let p = ValidateConfirmRegistered::<Test>::new();
         let c = RuntimeCall::Registry(RegistryCall::confirm_register {
@@ -798,3 +2207,421 @@ fn it_provides_free_txs_confirm_done_fails_4() {
         assert_eq!(r, TransactionValidity::Ok(ValidTransaction::default()));
     });
 }
+
+#[test]
+fn it_migrates_registrations_to_v1() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Registry>();
+
+        let programs_data: BoundedVec<_, <Test as pallet_registry::Config>::MaxProgramHashes> =
+            BoundedVec::truncate_from(vec![v0::ProgramInstance::<Test> {
+                program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+                program_config: vec![1],
+            }]);
+        let verifying_key: VerifyingKey = BoundedVec::try_from(vec![10; 33]).unwrap();
+
+        v0::Registered::<Test>::insert(
+            &verifying_key,
+            v0::RegisteredInfo {
+                programs_data: programs_data.clone(),
+                program_modification_account: 2,
+                version_number: 1,
+            },
+        );
+        v0::Registering::<Test>::insert(
+            1,
+            v0::RegisteringDetails {
+                program_modification_account: 2,
+                confirmations: vec![3],
+                programs_data: programs_data.clone(),
+                verifying_key: None,
+                version_number: 1,
+            },
+        );
+
+        v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Registry::on_chain_storage_version(), 1);
+        assert_eq!(
+            v1::Registered::<Test>::get(&verifying_key).unwrap(),
+            v1::RegisteredInfo {
+                programs_data: programs_data.clone(),
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+            }
+        );
+        assert_eq!(
+            v1::Registering::<Test>::get(1).unwrap(),
+            v1::RegisteringDetails {
+                program_modification_account: 2,
+                confirmations: vec![3],
+                programs_data,
+                verifying_key: None,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+            }
+        );
+    });
+}
+
+#[test]
+fn it_migrates_registrations_to_v2() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(1).put::<Registry>();
+        System::set_block_number(3);
+
+        let programs_data: BoundedVec<_, <Test as pallet_registry::Config>::MaxProgramHashes> =
+            BoundedVec::truncate_from(vec![v0::ProgramInstance::<Test> {
+                program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+                program_config: vec![1],
+            }]);
+        let verifying_key: VerifyingKey = BoundedVec::try_from(vec![10; 33]).unwrap();
+        // A registration which no signer has confirmed yet, and one which has a confirmation
+        let unconfirmed = v1::RegisteringDetails::<Test> {
+            program_modification_account: 2,
+            confirmations: vec![],
+            programs_data,
+            verifying_key: None,
+            version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+        };
+        let confirming = v1::RegisteringDetails {
+            confirmations: vec![3],
+            verifying_key: Some(verifying_key.clone()),
+            ..unconfirmed.clone()
+        };
+        v1::Registering::<Test>::insert(1, unconfirmed);
+        v1::Registering::<Test>::insert(5, confirming);
+
+        v2::MigrateToV2::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Registry::on_chain_storage_version(), 2);
+        assert_eq!(
+            v2::Registering::<Test>::get(1).unwrap().status,
+            RegistrationStatus::DkgStarted(3)
+        );
+        let confirming = v2::Registering::<Test>::get(5).unwrap();
+        assert_eq!(confirming.confirmations, vec![3]);
+        assert_eq!(confirming.verifying_key, Some(verifying_key));
+        assert_eq!(
+            confirming.status,
+            RegistrationStatus::Confirming {
+                confirmations: 1,
+                required: pallet_staking_extension::Signers::<Test>::get().len() as u32,
+            }
+        );
+    });
+}
+
+#[test]
+fn it_migrates_registrations_to_v3() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(2).put::<Registry>();
+        System::set_block_number(3);
+
+        let programs_data: BoundedVec<_, <Test as pallet_registry::Config>::MaxProgramHashes> =
+            BoundedVec::truncate_from(vec![v0::ProgramInstance::<Test> {
+                program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+                program_config: vec![1],
+            }]);
+        let verifying_key: VerifyingKey = BoundedVec::try_from(vec![10; 33]).unwrap();
+        // A registration which is still waiting for keyshares, and one which has failed
+        let confirming = v2::RegisteringDetails::<Test> {
+            program_modification_account: 2,
+            confirmations: vec![3],
+            programs_data,
+            verifying_key: Some(verifying_key.clone()),
+            version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+            status: RegistrationStatus::Confirming { confirmations: 1, required: 2 },
+        };
+        let failed = v2::RegisteringDetails {
+            status: RegistrationStatus::Failed(RegistrationFailure::MismatchedVerifyingKeys),
+            ..confirming.clone()
+        };
+        v2::Registering::<Test>::insert(1, confirming);
+        v2::Registering::<Test>::insert(5, failed);
+
+        v3::MigrateToV3::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Registry::on_chain_storage_version(), 3);
+        let restarted = v3::Registering::<Test>::get(1).unwrap();
+        assert_eq!(restarted.confirmations, Vec::<u64>::new());
+        assert_eq!(restarted.verifying_key, None);
+        assert_eq!(restarted.status, RegistrationStatus::Submitted);
+        assert_eq!(restarted.deposit, 0);
+        assert_eq!(restarted.dkg_attempts, 1);
+        assert_eq!(restarted.dkg_deadline, Registry::dkg_deadline(3));
+        assert_eq!(Dkg::<Test>::get(3), vec![1u64.encode()]);
+        assert_eq!(Registry::dkg_deadlines(Registry::dkg_deadline(3)), vec![1]);
+
+        let failed = v3::Registering::<Test>::get(5).unwrap();
+        assert_eq!(failed.confirmations, vec![3]);
+        assert_eq!(failed.verifying_key, Some(verifying_key));
+        assert_eq!(
+            failed.status,
+            RegistrationStatus::Failed(RegistrationFailure::MismatchedVerifyingKeys)
+        );
+        assert_eq!(failed.deposit, 0);
+
+        // Running the migration again does nothing
+        v3::MigrateToV3::<Test>::on_runtime_upgrade();
+        assert_eq!(Dkg::<Test>::get(3), vec![1u64.encode()]);
+    });
+}
+
+#[test]
+fn it_migrates_registrations_to_v4() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(3).put::<Registry>();
+
+        let registering_details = v3::RegisteringDetails::<Test> {
+            program_modification_account: 2,
+            confirmations: vec![],
+            programs_data: BoundedVec::truncate_from(vec![v0::ProgramInstance {
+                program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+                program_config: vec![1],
+            }]),
+            verifying_key: None,
+            version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+            status: RegistrationStatus::DkgStarted(3),
+            deposit: 10,
+            dkg_attempts: 2,
+            dkg_deadline: 8,
+        };
+        v3::Registering::<Test>::insert(1, registering_details.clone());
+
+        v4::MigrateToV4::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Registry::on_chain_storage_version(), 4);
+        assert_eq!(
+            v4::Registering::<Test>::get(1).unwrap(),
+            v4::RegisteringDetails {
+                program_modification_account: registering_details.program_modification_account,
+                confirmations: registering_details.confirmations,
+                programs_data: registering_details.programs_data,
+                verifying_key: registering_details.verifying_key,
+                version_number: registering_details.version_number,
+                signature_scheme: registering_details.signature_scheme,
+                status: registering_details.status,
+                deposit: registering_details.deposit,
+                dkg_attempts: registering_details.dkg_attempts,
+                dkg_deadline: registering_details.dkg_deadline,
+                imported_key: false,
+            }
+        );
+    });
+}
+
+#[test]
+fn it_migrates_registrations_to_v5() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(4).put::<Registry>();
+        System::set_block_number(3);
+
+        let programs_data: BoundedVec<_, <Test as pallet_registry::Config>::MaxProgramHashes> =
+            BoundedVec::truncate_from(vec![v0::ProgramInstance::<Test> {
+                program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+                program_config: vec![1],
+            }]);
+        let verifying_key: VerifyingKey = BoundedVec::try_from(vec![10; 33]).unwrap();
+        let signers = pallet_staking_extension::Signers::<Test>::get();
+
+        v1::Registered::<Test>::insert(
+            &verifying_key,
+            v1::RegisteredInfo {
+                programs_data: programs_data.clone(),
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+            },
+        );
+        // A registration whose key generation has started, and one waiting for it to start
+        let started = v4::RegisteringDetails::<Test> {
+            program_modification_account: 2,
+            confirmations: vec![],
+            programs_data: programs_data.clone(),
+            verifying_key: None,
+            version_number: 1,
+            signature_scheme: SignatureScheme::Ecdsa,
+            status: RegistrationStatus::DkgStarted(2),
+            deposit: 0,
+            dkg_attempts: 1,
+            dkg_deadline: 8,
+            imported_key: false,
+        };
+        let submitted =
+            v4::RegisteringDetails { status: RegistrationStatus::Submitted, ..started.clone() };
+        v4::Registering::<Test>::insert(1, started);
+        v4::Registering::<Test>::insert(5, submitted);
+
+        v5::MigrateToV5::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Registry::on_chain_storage_version(), 5);
+        assert_eq!(
+            v5::Registered::<Test>::get(&verifying_key).unwrap(),
+            v5::RegisteredInfo {
+                programs_data,
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: SIGNER_THRESHOLD,
+                total_signers: TOTAL_SIGNERS,
+                committee: signers.clone(),
+            }
+        );
+
+        let started = v5::Registering::<Test>::get(1).unwrap();
+        assert_eq!(started.threshold, SIGNER_THRESHOLD);
+        assert_eq!(started.total_signers, TOTAL_SIGNERS);
+        assert_eq!(started.committee, signers);
+        let submitted = v5::Registering::<Test>::get(5).unwrap();
+        assert_eq!(submitted.committee, Registry::signing_committee(&5, 3, TOTAL_SIGNERS));
+    });
+}
+
+#[test]
+fn it_migrates_registrations_to_v6() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(5).put::<Registry>();
+
+        let old_programs: BoundedVec<_, <Test as pallet_registry::Config>::MaxProgramHashes> =
+            BoundedVec::truncate_from(vec![v0::ProgramInstance::<Test> {
+                program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+                program_config: vec![1],
+            }]);
+        let migrated_programs = BoundedVec::try_from(vec![ProgramInstance::<Test> {
+            program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+            program_config: vec![1],
+            fuel: None,
+        }])
+        .unwrap();
+        let verifying_key: VerifyingKey = BoundedVec::try_from(vec![10; 33]).unwrap();
+
+        v5::Registered::<Test>::insert(
+            &verifying_key,
+            v5::RegisteredInfo {
+                programs_data: old_programs.clone(),
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: 2,
+                total_signers: 3,
+                committee: vec![5, 6, 7],
+            },
+        );
+        v5::Registering::<Test>::insert(
+            1,
+            v5::RegisteringDetails {
+                program_modification_account: 2,
+                confirmations: vec![],
+                programs_data: old_programs,
+                verifying_key: None,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                status: RegistrationStatus::Submitted,
+                deposit: 10,
+                dkg_attempts: 1,
+                dkg_deadline: 8,
+                imported_key: false,
+                threshold: 2,
+                total_signers: 3,
+                committee: vec![5, 6, 7],
+            },
+        );
+
+        v6::MigrateToV6::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Registry::on_chain_storage_version(), 6);
+        assert_eq!(
+            Registry::registered(&verifying_key).unwrap(),
+            RegisteredInfo {
+                programs_data: migrated_programs.clone(),
+                program_modification_account: 2,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                threshold: 2,
+                total_signers: 3,
+                committee: vec![5, 6, 7],
+            }
+        );
+        assert_eq!(
+            v6::Registering::<Test>::get(1).unwrap(),
+            v6::RegisteringDetails {
+                program_modification_account: 2,
+                confirmations: vec![],
+                programs_data: migrated_programs,
+                verifying_key: None,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                status: RegistrationStatus::Submitted,
+                deposit: 10,
+                dkg_attempts: 1,
+                dkg_deadline: 8,
+                imported_key: false,
+                threshold: 2,
+                total_signers: 3,
+                committee: vec![5, 6, 7],
+            }
+        );
+    });
+}
+
+#[test]
+fn it_migrates_registrations_to_v7() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(6).put::<Registry>();
+
+        let programs_data = BoundedVec::try_from(vec![ProgramInstance::<Test> {
+            program_pointer: <Test as frame_system::Config>::Hashing::hash(&[1]),
+            program_config: vec![1],
+            fuel: None,
+        }])
+        .unwrap();
+        v6::Registering::<Test>::insert(
+            1,
+            v6::RegisteringDetails {
+                program_modification_account: 2,
+                confirmations: vec![3],
+                programs_data: programs_data.clone(),
+                verifying_key: None,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                status: RegistrationStatus::DkgStarted(4),
+                deposit: 10,
+                dkg_attempts: 1,
+                dkg_deadline: 8,
+                imported_key: false,
+                threshold: 2,
+                total_signers: 3,
+                committee: vec![5, 6, 7],
+            },
+        );
+
+        v7::MigrateToV7::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Registry::on_chain_storage_version(), 7);
+        assert_eq!(
+            Registry::registering(1).unwrap(),
+            RegisteringDetails {
+                program_modification_account: 2,
+                confirmations: vec![3],
+                refusals: vec![],
+                programs_data,
+                verifying_key: None,
+                version_number: 1,
+                signature_scheme: SignatureScheme::Ecdsa,
+                status: RegistrationStatus::DkgStarted(4),
+                deposit: 10,
+                dkg_attempts: 1,
+                dkg_deadline: 8,
+                imported_key: false,
+                threshold: 2,
+                total_signers: 3,
+                committee: vec![5, 6, 7],
+            }
+        );
+    });
+}
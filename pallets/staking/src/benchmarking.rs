@@ -45,16 +45,7 @@ pub fn create_validators<T: Config>(
     count: u32,
     seed: u32,
 ) -> Vec<<T as pallet_session::Config>::ValidatorId> {
-    let candidates =
-        (0..count).map(|c| account::<T::AccountId>("validator", c, seed)).collect::<Vec<_>>();
-    let mut validators = vec![];
-    for who in candidates {
-        let validator_id_res = <T as pallet_session::Config>::ValidatorId::try_from(who.clone())
-            .or(Err(Error::<T>::InvalidValidatorId))
-            .unwrap();
-        validators.push(validator_id_res);
-    }
-    validators
+    (0..count).map(|c| account::<T::AccountId>("validator", c, seed)).collect::<Vec<_>>()
 }
 
 fn prep_bond_and_validate<T: Config>(
@@ -106,8 +97,7 @@ benchmarks! {
   change_threshold_accounts {
     let caller: T::AccountId = whitelisted_caller();
     let _bonder: T::AccountId = account("bond", 0, SEED);
-    let validator_id_res = <T as pallet_session::Config>::ValidatorId::try_from(_bonder.clone()).or(Err(Error::<T>::InvalidValidatorId));
-    let bonder: T::ValidatorId = validator_id_res.expect("Issue converting account id into validator id");
+    let bonder: T::ValidatorId = _bonder.clone();
     let threshold: T::AccountId = account("threshold", 0, SEED);
     let x25519_public_key: [u8; 32] = NULL_ARR;
     prep_bond_and_validate::<T>(true, caller.clone(), _bonder.clone(), threshold, NULL_ARR);
@@ -169,7 +159,7 @@ benchmarks! {
 
   declare_synced {
     let caller: T::AccountId = whitelisted_caller();
-    let validator_id_res = <T as pallet_session::Config>::ValidatorId::try_from(caller.clone()).or(Err(Error::<T>::InvalidValidatorId)).unwrap();
+    let validator_id_res = caller.clone();
     ThresholdToStash::<T>::insert(caller.clone(), validator_id_res.clone());
 
   }:  _(RawOrigin::Signed(caller.clone()), true)
@@ -183,9 +173,9 @@ benchmarks! {
     let confirmation_num = c.checked_sub(2).unwrap_or(0);
     let signer_num =  SIGNING_PARTY_SIZE - 1;
     let caller: T::AccountId = whitelisted_caller();
-    let validator_id_res = <T as pallet_session::Config>::ValidatorId::try_from(caller.clone()).or(Err(Error::<T>::InvalidValidatorId)).unwrap();
+    let validator_id_res = caller.clone();
     let second_signer: T::AccountId = account("second_signer", 0, SEED);
-    let second_signer_id = <T as pallet_session::Config>::ValidatorId::try_from(second_signer.clone()).or(Err(Error::<T>::InvalidValidatorId)).unwrap();
+    let second_signer_id = second_signer.clone();
     ThresholdToStash::<T>::insert(caller.clone(), validator_id_res.clone());
 
     // full signer list leaving room for one extra validator
@@ -208,9 +198,9 @@ benchmarks! {
     let confirmation_num = SIGNING_PARTY_SIZE - 1;
 
     let caller: T::AccountId = whitelisted_caller();
-    let validator_id_res = <T as pallet_session::Config>::ValidatorId::try_from(caller.clone()).or(Err(Error::<T>::InvalidValidatorId)).unwrap();
+    let validator_id_res = caller.clone();
     let second_signer: T::AccountId = account("second_signer", 0, SEED);
-    let second_signer_id = <T as pallet_session::Config>::ValidatorId::try_from(second_signer.clone()).or(Err(Error::<T>::InvalidValidatorId)).unwrap();
+    let second_signer_id = second_signer.clone();
     ThresholdToStash::<T>::insert(caller.clone(), validator_id_res.clone());
     // full signer list leaving room for one extra validator
     let mut signers = vec![second_signer_id.clone(); confirmation_num as usize];
@@ -31,6 +31,24 @@
 //! (needed so comms manager can confirm done) withdraw_unbonded - wraps substrate's call but clears
 //! endpoint and threshold key if all is unbonded validate - wraps substrate's call but forces a
 //! threshold key and endpoint
+//!
+//! ### Signer Selection
+//!
+//! `select_signers` - the one algorithm used to choose signers, both when rotating the network's
+//! signers and when choosing who signs a message. Validators are picked deterministically from a
+//! seed, with a chance proportional to their stake and how reliably they have taken part in the
+//! protocol this session.
+//!
+//! ### Signer Rotation
+//!
+//! Each session one signer is swapped for a newly selected validator and the network parent key is
+//! reshared between the next signers. `ReshareData` records both the old and new signers and the
+//! threshold the key is held with before and after, which the threshold servers reshare with. Once
+//! they have all called `confirm_key_reshare` they become the signers. If they have not all
+//! confirmed within `ReshareTimeout` blocks the rotation is rolled back, leaving the signers
+//! unchanged. Either way the outcome is recorded in `FinishedRotations` under the block the reshare
+//! started in, so that the threshold servers know whether to keep their new key shares. Outcomes
+//! are kept until a later rotation goes through, by which point every signer has acted on them.
 
 use core::convert::TryInto;
 
@@ -50,14 +68,30 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 pub mod benchmarking;
 
+pub mod migrations;
 pub mod weights;
-use core::convert::TryFrom;
 
 use sp_staking::SessionIndex;
 
+/// Something which can tell how reliably a validator has taken part in the protocol recently,
+/// used along with its stake to weight signer selection
+pub trait ValidatorReliability<AccountId> {
+    /// How many times the validator has been reported as unresponsive this session
+    fn unresponsive_reports(validator: &AccountId) -> u32;
+}
+
+impl<AccountId> ValidatorReliability<AccountId> for () {
+    fn unresponsive_reports(_validator: &AccountId) -> u32 {
+        0
+    }
+}
+
 #[frame_support::pallet]
 pub mod pallet {
-    use entropy_shared::{ValidatorInfo, X25519PublicKey, SIGNING_PARTY_SIZE};
+    use entropy_shared::{
+        ValidatorInfo, X25519PublicKey, SIGNER_THRESHOLD, SIGNING_PARTY_SIZE,
+        TEST_RESHARE_BLOCK_NUMBER,
+    };
     use frame_support::{
         dispatch::{DispatchResult, DispatchResultWithPostInfo},
         pallet_prelude::*,
@@ -65,11 +99,10 @@ pub mod pallet {
         DefaultNoBound,
     };
     use frame_system::pallet_prelude::*;
-    use rand_chacha::{
-        rand_core::{RngCore, SeedableRng},
-        ChaCha20Rng, ChaChaRng,
+    use sp_runtime::{
+        traits::{Hash, Saturating, TrailingZeroInput},
+        SaturatedConversion,
     };
-    use sp_runtime::traits::TrailingZeroInput;
     use sp_staking::StakingAccount;
     use sp_std::vec;
     use sp_std::vec::Vec;
@@ -78,13 +111,22 @@ pub mod pallet {
 
     #[pallet::config]
     pub trait Config:
-        pallet_session::Config + frame_system::Config + pallet_staking::Config
+        pallet_session::Config<ValidatorId = <Self as frame_system::Config>::AccountId>
+        + frame_system::Config
+        + pallet_staking::Config
+        + pallet_parameters::Config
     {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
         /// Something that provides randomness in the runtime.
         type Randomness: Randomness<Self::Hash, BlockNumberFor<Self>>;
         type Currency: Currency<Self::AccountId>;
         type MaxEndpointLength: Get<u32>;
+        /// How reliably validators have taken part in the protocol, used to weight signer
+        /// selection.
+        type Reliability: ValidatorReliability<Self::AccountId>;
+        /// The number of blocks the next signers have to confirm a reshare before the rotation is
+        /// abandoned and the current signers are kept
+        type ReshareTimeout: Get<BlockNumberFor<Self>>;
         /// The weight information of this pallet.
         type WeightInfo: WeightInfo;
     }
@@ -115,10 +157,32 @@ pub mod pallet {
         pub proactive_refresh_keys: Vec<Vec<u8>>,
     }
 
-    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo, Default)]
-    pub struct ReshareInfo<BlockNumber> {
+    /// The reshare of the network parent key which the threshold servers should do
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+    pub struct ReshareInfo<ValidatorId, BlockNumber> {
         pub new_signer: Vec<u8>,
         pub block_number: BlockNumber,
+        /// The signers which hold the network parent key before the reshare
+        pub old_signers: Vec<ValidatorId>,
+        /// The threshold the network parent key is held with before the reshare
+        pub old_threshold: u8,
+        /// The signers which will hold the network parent key after the reshare
+        pub new_signers: Vec<ValidatorId>,
+        /// The threshold the network parent key will be held with after the reshare
+        pub new_threshold: u8,
+    }
+
+    impl<ValidatorId, BlockNumber: Default> Default for ReshareInfo<ValidatorId, BlockNumber> {
+        fn default() -> Self {
+            Self {
+                new_signer: Vec::new(),
+                block_number: BlockNumber::default(),
+                old_signers: Vec::new(),
+                old_threshold: 0,
+                new_signers: Vec::new(),
+                new_threshold: 0,
+            }
+        }
     }
 
     #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
@@ -126,7 +190,29 @@ pub mod pallet {
         pub next_signers: Vec<ValidatorId>,
         pub confirmations: Vec<ValidatorId>,
     }
+
+    /// How a signer rotation finished
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+    pub enum RotationOutcome {
+        /// Every next signer confirmed the reshare and they are now the signers
+        Rotated,
+        /// Not every next signer confirmed the reshare in time, so the signers were not changed
+        RolledBack,
+    }
+
+    /// A signer rotation which has finished
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+    pub struct FinishedRotation<BlockNumber> {
+        /// The block the rotation finished in
+        pub block_number: BlockNumber,
+        pub outcome: RotationOutcome,
+    }
+
+    /// The current storage version
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -185,7 +271,27 @@ pub mod pallet {
     /// The next time a reshare should happen
     #[pallet::storage]
     #[pallet::getter(fn reshare_data)]
-    pub type ReshareData<T: Config> = StorageValue<_, ReshareInfo<BlockNumberFor<T>>, ValueQuery>;
+    pub type ReshareData<T: Config> =
+        StorageValue<_, ReshareInfo<T::ValidatorId, BlockNumberFor<T>>, ValueQuery>;
+
+    /// The threshold with which the signers hold the network parent key
+    #[pallet::storage]
+    #[pallet::getter(fn parent_key_threshold)]
+    pub type ParentKeyThreshold<T: Config> = StorageValue<_, u8, ValueQuery>;
+
+    /// Signer rotations which have finished, keyed by the block their reshare started in.
+    ///
+    /// The threshold servers reconcile the network parent key shares they got from a reshare
+    /// against this, so entries are only pruned once a later rotation has gone through.
+    #[pallet::storage]
+    #[pallet::getter(fn finished_rotations)]
+    pub type FinishedRotations<T: Config> = StorageMap<
+        _,
+        Blake2_128Concat,
+        BlockNumberFor<T>,
+        FinishedRotation<BlockNumberFor<T>>,
+        OptionQuery,
+    >;
 
     /// A type used to simplify the genesis configuration definition.
     pub type ThresholdServersConfig<T> = (
@@ -200,7 +306,8 @@ pub mod pallet {
         pub inital_signers: Vec<T::ValidatorId>,
         /// validator info and accounts to take part in proactive refresh
         pub proactive_refresh_data: (Vec<ValidatorInfo>, Vec<Vec<u8>>),
-        /// validator info and account to take part in a reshare
+        /// Whether to start a rotation to the initial signers, with the network parent key
+        /// reshared in block [TEST_RESHARE_BLOCK_NUMBER]
         pub mock_signer_rotate: bool,
     }
 
@@ -237,6 +344,16 @@ pub mod pallet {
                     next_signers: self.inital_signers.clone(),
                     confirmations: vec![],
                 });
+                // Nobody joins in the mocked rotation, the next signers are the current ones
+                ReshareData::<T>::put(ReshareInfo {
+                    block_number: TEST_RESHARE_BLOCK_NUMBER.into(),
+                    new_signer: vec![],
+                    old_signers: self.inital_signers.clone(),
+                    old_threshold: SIGNER_THRESHOLD,
+                    new_signers: self.inital_signers.clone(),
+                    new_threshold: SIGNER_THRESHOLD,
+                });
+                ParentKeyThreshold::<T>::put(SIGNER_THRESHOLD);
             }
         }
     }
@@ -280,6 +397,15 @@ pub mod pallet {
         SignerConfirmed(<T as pallet_session::Config>::ValidatorId),
         /// Validators subgroups rotated [old, new]
         SignersRotation(Vec<<T as pallet_session::Config>::ValidatorId>),
+        /// A reshare was not confirmed in time so the signers were not rotated [unconfirmed]
+        SignersRotationFailed(Vec<<T as pallet_session::Config>::ValidatorId>),
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(block_number: BlockNumberFor<T>) -> Weight {
+            Self::expire_reshare(block_number)
+        }
     }
 
     #[pallet::call]
@@ -297,8 +423,7 @@ pub mod pallet {
 
             let ledger = pallet_staking::Pallet::<T>::ledger(StakingAccount::Stash(who.clone()))
                 .map_err(|_| Error::<T>::NoBond)?;
-            let validator_id = <T as pallet_session::Config>::ValidatorId::try_from(ledger.stash)
-                .or(Err(Error::<T>::InvalidValidatorId))?;
+            let validator_id = ledger.stash;
 
             ThresholdServers::<T>::try_mutate(&validator_id, |maybe_server_info| {
                 if let Some(server_info) = maybe_server_info {
@@ -328,8 +453,7 @@ pub mod pallet {
 
             let who = ensure_signed(origin)?;
             let stash = Self::get_stash(&who)?;
-            let validator_id = <T as pallet_session::Config>::ValidatorId::try_from(stash)
-                .or(Err(Error::<T>::InvalidValidatorId))?;
+            let validator_id = stash;
 
             let new_server_info: ServerInfo<T::AccountId> =
                 ThresholdServers::<T>::try_mutate(&validator_id, |maybe_server_info| {
@@ -358,8 +482,7 @@ pub mod pallet {
                 pallet_staking::Pallet::<T>::ledger(StakingAccount::Controller(controller.clone()))
                     .map_err(|_| Error::<T>::NoThresholdKey)?;
 
-            let validator_id = <T as pallet_session::Config>::ValidatorId::try_from(ledger.stash)
-                .or(Err(Error::<T>::InvalidValidatorId))?;
+            let validator_id = ledger.stash;
 
             pallet_staking::Pallet::<T>::withdraw_unbonded(origin, num_slashing_spans)?;
             // TODO: do not allow unbonding of validator if not enough validators https://github.com/entropyxyz/entropy-core/issues/942
@@ -400,8 +523,7 @@ pub mod pallet {
             pallet_staking::Pallet::<T>::validate(origin, prefs)?;
 
             let stash = Self::get_stash(&who)?;
-            let validator_id =
-                T::ValidatorId::try_from(stash).or(Err(Error::<T>::InvalidValidatorId))?;
+            let validator_id = stash;
 
             ThresholdServers::<T>::insert(&validator_id, server_info.clone());
             ThresholdToStash::<T>::insert(&server_info.tss_account, validator_id);
@@ -454,6 +576,8 @@ pub mod pallet {
             let current_signer_length = signers_info.next_signers.len();
             if signers_info.confirmations.len() == (current_signer_length - 1) {
                 Signers::<T>::put(signers_info.next_signers.clone());
+                ParentKeyThreshold::<T>::put(Self::reshare_data().new_threshold);
+                Self::finish_rotation(RotationOutcome::Rotated);
                 Self::deposit_event(Event::SignersRotation(signers_info.next_signers));
                 Ok(Pays::No.into())
             } else {
@@ -473,15 +597,71 @@ pub mod pallet {
             Ok(ledger.stash)
         }
 
-        pub fn get_randomness() -> ChaCha20Rng {
+        /// A random seed for selecting the next signer when rotating signers
+        pub fn get_randomness() -> [u8; 32] {
             let phrase = b"signer_rotation";
             // TODO: Is randomness freshness an issue here
             // https://github.com/paritytech/substrate/issues/8312
             let (seed, _) = T::Randomness::random(phrase);
             // seed needs to be guaranteed to be 32 bytes.
-            let seed = <[u8; 32]>::decode(&mut TrailingZeroInput::new(seed.as_ref()))
-                .expect("input is padded with zeroes; qed");
-            ChaChaRng::from_seed(seed)
+            <[u8; 32]>::decode(&mut TrailingZeroInput::new(seed.as_ref()))
+                .expect("input is padded with zeroes; qed")
+        }
+
+        /// The weight a validator is given when selecting signers.
+        ///
+        /// This is its active stake, divided by one more than the number of times it has been
+        /// reported as unresponsive this session. Every validator has a weight of at least one, so
+        /// that it can still be selected when nothing is staked.
+        pub fn signer_weight(validator: &T::ValidatorId) -> u128 {
+            let stake: u128 =
+                pallet_staking::Pallet::<T>::slashable_balance_of(validator).saturated_into();
+            let unresponsive_reports = T::Reliability::unresponsive_reports(validator);
+            (stake / (unresponsive_reports as u128).saturating_add(1)).max(1)
+        }
+
+        /// Deterministically selects up to `count` different validators from `candidates`, with
+        /// the chance of each being picked proportional to its [Self::signer_weight].
+        ///
+        /// Anyone selecting with the same candidates and seed against the same chain state gets
+        /// the same signers, in the same order.
+        pub fn select_signers(
+            candidates: &[T::ValidatorId],
+            count: usize,
+            seed: &[u8],
+        ) -> Vec<T::ValidatorId> {
+            let mut weighted_candidates = candidates
+                .iter()
+                .map(|candidate| (candidate.clone(), Self::signer_weight(candidate)))
+                .collect::<Vec<_>>();
+            let mut selected = Vec::with_capacity(count.min(candidates.len()));
+
+            let mut round: u32 = 0;
+            while selected.len() < count && !weighted_candidates.is_empty() {
+                let total_weight = weighted_candidates
+                    .iter()
+                    .fold(0u128, |total, (_, weight)| total.saturating_add(*weight));
+                let hash = T::Hashing::hash_of(&(seed, round));
+                let mut point = u128::decode(&mut TrailingZeroInput::new(hash.as_ref()))
+                    .expect("input is padded with zeroes; qed")
+                    % total_weight;
+                // Walk along the candidates until we find the one whose share of the total weight
+                // the point falls in
+                let index = weighted_candidates
+                    .iter()
+                    .position(|(_, weight)| {
+                        if point < *weight {
+                            true
+                        } else {
+                            point -= weight;
+                            false
+                        }
+                    })
+                    .unwrap_or(weighted_candidates.len() - 1);
+                selected.push(weighted_candidates.remove(index).0);
+                round += 1;
+            }
+            selected
         }
 
         pub fn new_session_handler(
@@ -490,24 +670,30 @@ pub mod pallet {
             let mut current_signers = Self::signers();
             // Since not enough validators do not allow rotation
             // TODO: https://github.com/entropyxyz/entropy-core/issues/943
-            if validators.len() <= current_signers.len() {
+            if current_signers.is_empty()
+                || validators.len() <= current_signers.len()
+                || NextSigners::<T>::exists()
+            {
                 return Ok(());
             }
-            let mut randomness = Self::get_randomness();
-            // grab a current signer to initiate value
-            let mut next_signer_up = &current_signers[0].clone();
-            let mut index;
-            // loops to find signer in validator that is not already signer
-            while current_signers.contains(next_signer_up) {
-                index = randomness.next_u32() % validators.len() as u32;
-                next_signer_up = &validators[index as usize];
-            }
+            // picks a validator which is not already a signer
+            let candidates = validators
+                .iter()
+                .filter(|validator| !current_signers.contains(validator))
+                .cloned()
+                .collect::<Vec<_>>();
+            let Some(next_signer_up) =
+                Self::select_signers(&candidates, 1, &Self::get_randomness()).pop()
+            else {
+                return Ok(());
+            };
 
             // removes first signer and pushes new signer to back
+            let old_signers = current_signers.clone();
             current_signers.remove(0);
             current_signers.push(next_signer_up.clone());
             NextSigners::<T>::put(NextSignerInfo {
-                next_signers: current_signers,
+                next_signers: current_signers.clone(),
                 confirmations: vec![],
             });
             // trigger reshare at next block
@@ -515,27 +701,69 @@ pub mod pallet {
             let reshare_info = ReshareInfo {
                 block_number: current_block_number + sp_runtime::traits::One::one(),
                 new_signer: next_signer_up.encode(),
+                old_signers,
+                old_threshold: Self::parent_key_threshold(),
+                new_signers: current_signers,
+                new_threshold: pallet_parameters::Pallet::<T>::signers_info().threshold,
             };
             ReshareData::<T>::put(reshare_info);
 
-            // for next PR
-            // confirm action has taken place
-
             Ok(())
         }
+
+        /// Abandons a signer rotation if the next signers have not all confirmed the reshare within
+        /// `ReshareTimeout` blocks of it starting.
+        ///
+        /// The current signers are kept, and the next signers which did not confirm are recorded in
+        /// a [Event::SignersRotationFailed] event.
+        pub fn expire_reshare(block_number: BlockNumberFor<T>) -> Weight {
+            let Some(signers_info) = Self::next_signers() else {
+                return T::DbWeight::get().reads(1);
+            };
+            let deadline =
+                Self::reshare_data().block_number.saturating_add(T::ReshareTimeout::get());
+            if block_number < deadline {
+                return T::DbWeight::get().reads(2);
+            }
+
+            NextSigners::<T>::kill();
+            Self::finish_rotation(RotationOutcome::RolledBack);
+            let unconfirmed = signers_info
+                .next_signers
+                .into_iter()
+                .filter(|signer| !signers_info.confirmations.contains(signer))
+                .collect();
+            Self::deposit_event(Event::SignersRotationFailed(unconfirmed));
+
+            T::DbWeight::get().reads_writes(2, 2)
+        }
+
+        /// Records how the signer rotation in progress finished.
+        ///
+        /// Every next signer reconciles its key shares against earlier outcomes before confirming a
+        /// reshare, so once a rotation has gone through the earlier outcomes are no longer needed.
+        pub fn finish_rotation(outcome: RotationOutcome) {
+            if outcome == RotationOutcome::Rotated {
+                let _ = FinishedRotations::<T>::clear(u32::MAX, None);
+            }
+            FinishedRotations::<T>::insert(
+                Self::reshare_data().block_number,
+                FinishedRotation {
+                    block_number: <frame_system::Pallet<T>>::block_number(),
+                    outcome,
+                },
+            );
+        }
     }
 
     pub struct SessionManager<I, T: Config>(
         sp_std::marker::PhantomData<I>,
         sp_std::marker::PhantomData<T>,
     );
-    impl<
-            I: pallet_session::SessionManager<ValidatorId>,
-            ValidatorId,
-            T: Config + pallet::Config<ValidatorId = ValidatorId>,
-        > pallet_session::SessionManager<ValidatorId> for SessionManager<I, T>
+    impl<I: pallet_session::SessionManager<T::AccountId>, T: Config>
+        pallet_session::SessionManager<T::AccountId> for SessionManager<I, T>
     {
-        fn new_session(new_index: SessionIndex) -> Option<Vec<ValidatorId>> {
+        fn new_session(new_index: SessionIndex) -> Option<Vec<T::AccountId>> {
             let new_session = I::new_session(new_index);
             if let Some(validators) = &new_session {
                 let result = Pallet::<T>::new_session_handler(validators);
@@ -546,7 +774,7 @@ pub mod pallet {
             new_session
         }
 
-        fn new_session_genesis(new_index: SessionIndex) -> Option<Vec<ValidatorId>> {
+        fn new_session_genesis(new_index: SessionIndex) -> Option<Vec<T::AccountId>> {
             I::new_session_genesis(new_index)
         }
 
@@ -0,0 +1,82 @@
+// Copyright (C) 2023 Entropy Cryptography Inc.
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage migrations for the staking extension pallet
+//!
+//! Each change to the layout of the pallet's storage has its own storage version, and the runtime
+//! runs the migrations from one version to the next in order.
+
+/// The layout of reshare data before storage versions were tracked.
+pub mod v0 {
+    use frame_support::pallet_prelude::*;
+    use sp_std::vec::Vec;
+
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug)]
+    pub struct ReshareInfo<BlockNumber> {
+        pub new_signer: Vec<u8>,
+        pub block_number: BlockNumber,
+    }
+}
+
+/// Records the signers and threshold of the network parent key before and after a reshare.
+pub mod v1 {
+    use super::v0;
+    use crate::{Config, Pallet, ParentKeyThreshold, ReshareData, ReshareInfo};
+    use frame_support::{
+        pallet_prelude::*,
+        traits::{GetStorageVersion, OnRuntimeUpgrade},
+    };
+    use frame_system::pallet_prelude::BlockNumberFor;
+    use sp_std::marker::PhantomData;
+
+    /// Migrates the staking extension pallet from storage version 0 to 1.
+    ///
+    /// The network parent key was held with the threshold from the parameters pallet's
+    /// `SignersInfo`, which is recorded as [ParentKeyThreshold]. If a rotation is in progress, its
+    /// reshare is from the current signers to the next signers, both with that threshold.
+    pub struct MigrateToV1<T>(PhantomData<T>);
+
+    impl<T: Config> OnRuntimeUpgrade for MigrateToV1<T> {
+        fn on_runtime_upgrade() -> Weight {
+            if Pallet::<T>::on_chain_storage_version() != 0 {
+                return T::DbWeight::get().reads(1);
+            }
+
+            let threshold = pallet_parameters::Pallet::<T>::signers_info().threshold;
+            ParentKeyThreshold::<T>::put(threshold);
+
+            let old_signers = Pallet::<T>::signers();
+            let new_signers = Pallet::<T>::next_signers()
+                .map_or_else(|| old_signers.clone(), |next_signers| next_signers.next_signers);
+            let translated =
+                ReshareData::<T>::translate::<v0::ReshareInfo<BlockNumberFor<T>>, _>(|old| {
+                    old.map(|old| ReshareInfo {
+                        new_signer: old.new_signer,
+                        block_number: old.block_number,
+                        old_signers,
+                        old_threshold: threshold,
+                        new_signers,
+                        new_threshold: threshold,
+                    })
+                });
+            if translated.is_err() {
+                ReshareData::<T>::kill();
+            }
+
+            StorageVersion::new(1).put::<Pallet<T>>();
+            T::DbWeight::get().reads_writes(5, 3)
+        }
+    }
+}
@@ -58,6 +58,7 @@ frame_support::construct_runtime!(
     Session: pallet_session,
     Historical: pallet_session_historical,
     BagsList: pallet_bags_list,
+    Parameters: pallet_parameters,
   }
 );
 
@@ -359,6 +360,7 @@ impl pallet_session::historical::Config for Test {
 
 thread_local! {
     pub static LAST_RANDOM: RefCell<Option<(H256, u64)>> = RefCell::new(None);
+    pub static UNRESPONSIVE_REPORTS: RefCell<Vec<(AccountId, u32)>> = RefCell::new(vec![]);
 }
 
 pub struct TestPastRandomness;
@@ -374,14 +376,37 @@ impl Randomness<H256, BlockNumber> for TestPastRandomness {
     }
 }
 
+pub struct TestReliability;
+impl pallet_staking_extension::ValidatorReliability<AccountId> for TestReliability {
+    fn unresponsive_reports(validator: &AccountId) -> u32 {
+        UNRESPONSIVE_REPORTS.with(|reports| {
+            reports
+                .borrow()
+                .iter()
+                .find(|(reported, _)| reported == validator)
+                .map(|(_, count)| *count)
+                .unwrap_or_default()
+        })
+    }
+}
+
 parameter_types! {
   pub const MaxEndpointLength: u32 = 3;
+  pub const ReshareTimeout: u64 = 5;
 }
 impl pallet_staking_extension::Config for Test {
     type Currency = Balances;
     type MaxEndpointLength = MaxEndpointLength;
     type Randomness = TestPastRandomness;
+    type Reliability = TestReliability;
+    type ReshareTimeout = ReshareTimeout;
+    type RuntimeEvent = RuntimeEvent;
+    type WeightInfo = ();
+}
+
+impl pallet_parameters::Config for Test {
     type RuntimeEvent = RuntimeEvent;
+    type UpdateOrigin = frame_system::EnsureRoot<Self::AccountId>;
     type WeightInfo = ();
 }
 
@@ -402,6 +427,18 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
     pallet_balances.assimilate_storage(&mut t).unwrap();
     pallet_staking_extension.assimilate_storage(&mut t).unwrap();
 
+    pallet_parameters::GenesisConfig::<Test> {
+        request_limit: 5u32,
+        max_instructions_per_programs: 5u64,
+        max_rate_limit_requests: 100u32,
+        recovery_key_delay: 5u32,
+        total_signers: 2u8,
+        threshold: 2u8,
+        _config: Default::default(),
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
     t.into()
 }
 
@@ -14,11 +14,17 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use crate::{
-    mock::*, tests::RuntimeEvent, Error, IsValidatorSynced, NextSignerInfo, NextSigners,
-    ServerInfo, ThresholdToStash,
+    migrations::{v0, v1},
+    mock::*,
+    tests::RuntimeEvent,
+    Error, FinishedRotation, IsValidatorSynced, NextSignerInfo, NextSigners, ParentKeyThreshold,
+    ReshareData, ReshareInfo, RotationOutcome, ServerInfo, ThresholdToStash,
 };
 use codec::Encode;
-use frame_support::{assert_noop, assert_ok};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{GetStorageVersion, OnInitialize, OnRuntimeUpgrade, StorageVersion},
+};
 use frame_system::{EventRecord, Phase};
 use pallet_session::SessionManager;
 const NULL_ARR: [u8; 32] = [0; 32];
@@ -336,10 +342,18 @@ fn it_tests_new_session_handler() {
         assert_eq!(Staking::next_signers(), None);
         assert_eq!(Staking::reshare_data().block_number, 0, "Check reshare block start at zero");
         System::set_block_number(100);
+        ParentKeyThreshold::<Test>::put(1);
 
         assert_ok!(Staking::new_session_handler(&[1, 2, 3]));
-        // takes signers original (5,6) pops off first 5, adds (fake randomness in mock so adds 1)
-        assert_eq!(Staking::next_signers().unwrap().next_signers, vec![6, 1]);
+        // takes signers original (5,6) pops off first 5, adds (fake randomness in mock so adds 3)
+        assert_eq!(Staking::next_signers().unwrap().next_signers, vec![6, 3]);
+
+        // The reshare is between the old and new signers, from the parent key's current threshold
+        // to the one set in the parameters pallet
+        assert_eq!(Staking::reshare_data().old_signers, vec![5, 6]);
+        assert_eq!(Staking::reshare_data().old_threshold, 1);
+        assert_eq!(Staking::reshare_data().new_signers, vec![6, 3]);
+        assert_eq!(Staking::reshare_data().new_threshold, 2);
 
         assert_eq!(
             Staking::reshare_data().block_number,
@@ -348,8 +362,8 @@ fn it_tests_new_session_handler() {
         );
         assert_eq!(
             Staking::reshare_data().new_signer,
-            1u64.encode(),
-            "Check reshare next signer up is 1"
+            3u64.encode(),
+            "Check reshare next signer up is 3"
         );
 
         assert_eq!(
@@ -359,10 +373,15 @@ fn it_tests_new_session_handler() {
         );
         assert_eq!(
             Staking::reshare_data().new_signer,
-            1u64.encode(),
-            "Check reshare next signer up is 1"
+            3u64.encode(),
+            "Check reshare next signer up is 3"
         );
 
+        assert_ok!(Staking::new_session_handler(&[6, 5, 4]));
+        // does nothing while a rotation is already in progress
+        assert_eq!(Staking::next_signers().unwrap().next_signers, vec![6, 3]);
+
+        NextSigners::<Test>::kill();
         assert_ok!(Staking::new_session_handler(&[6, 5, 3]));
         // takes 3 and leaves 5 and 6 since already in signer group
         assert_eq!(Staking::next_signers().unwrap().next_signers, vec![6, 3]);
@@ -373,6 +392,38 @@ fn it_tests_new_session_handler() {
     });
 }
 
+#[test]
+fn it_selects_signers_weighted_by_stake_and_reliability() {
+    new_test_ext().execute_with(|| {
+        // Without any stake every validator has the same weight
+        assert_eq!(Staking::signer_weight(&1), 1);
+        assert_eq!(Staking::select_signers(&[1, 2, 3], 3, &[0; 32]), vec![3, 1, 2]);
+
+        for validator in [1, 2, 3] {
+            assert_ok!(FrameStaking::bond(
+                RuntimeOrigin::signed(validator),
+                100u64,
+                pallet_staking::RewardDestination::Account(validator),
+            ));
+        }
+        assert_eq!(Staking::signer_weight(&1), 100);
+
+        // Changing the weights changes who is selected, but selection is deterministic
+        let signers = Staking::select_signers(&[1, 2, 3], 3, &[0; 32]);
+        assert_eq!(signers, vec![2, 1, 3]);
+        assert_eq!(Staking::select_signers(&[1, 2, 3], 3, &[0; 32]), signers);
+
+        // Validators which have been reported as unresponsive are weighted down
+        UNRESPONSIVE_REPORTS.with(|reports| *reports.borrow_mut() = vec![(2, 1)]);
+        assert_eq!(Staking::signer_weight(&2), 50);
+        assert_eq!(Staking::select_signers(&[1, 2, 3], 1, &[0; 32]), vec![1]);
+
+        // Only as many signers as there are candidates can be selected
+        assert_eq!(Staking::select_signers(&[1, 2], 3, &[0; 32]).len(), 2);
+        assert!(Staking::select_signers(&[], 1, &[0; 32]).is_empty());
+    });
+}
+
 #[test]
 fn it_confirms_keyshare() {
     new_test_ext().execute_with(|| {
@@ -405,6 +456,7 @@ fn it_confirms_keyshare() {
             next_signers: vec![6, 5],
             confirmations: vec![],
         });
+        ReshareData::<Test>::put(ReshareInfo { new_threshold: 2, ..Default::default() });
 
         let mock_next_signer_info =
             NextSignerInfo { next_signers: vec![6, 5], confirmations: vec![5] };
@@ -416,5 +468,89 @@ fn it_confirms_keyshare() {
         assert_ok!(Staking::confirm_key_reshare(RuntimeOrigin::signed(8)));
         assert_eq!(Staking::next_signers(), None, "Next Signers cleared");
         assert_eq!(Staking::signers(), [6, 5], "next signers rotated into current signers");
+        assert_eq!(
+            Staking::finished_rotations(0),
+            Some(FinishedRotation { block_number: 0, outcome: RotationOutcome::Rotated })
+        );
+        assert_eq!(Staking::parent_key_threshold(), 2, "parent key is held with the new threshold");
+    });
+}
+
+#[test]
+fn it_rolls_back_an_unconfirmed_reshare() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(100);
+        assert_ok!(Staking::new_session_handler(&[1, 2, 3]));
+        assert_ok!(Staking::confirm_key_reshare(RuntimeOrigin::signed(8)));
+
+        // Nothing happens before the timeout
+        let reshare_block_number = Staking::reshare_data().block_number;
+        let deadline = reshare_block_number + ReshareTimeout::get();
+        Staking::on_initialize(deadline - 1);
+        assert_eq!(Staking::next_signers().unwrap().confirmations, vec![6]);
+        assert_eq!(Staking::finished_rotations(reshare_block_number), None);
+
+        System::set_block_number(deadline);
+        Staking::on_initialize(deadline);
+        assert_eq!(Staking::next_signers(), None, "Next Signers cleared");
+        assert_eq!(Staking::signers(), [5, 6], "current signers are kept");
+        assert_eq!(
+            Staking::finished_rotations(reshare_block_number),
+            Some(FinishedRotation { block_number: deadline, outcome: RotationOutcome::RolledBack })
+        );
+        System::assert_last_event(RuntimeEvent::Staking(crate::Event::SignersRotationFailed(
+            vec![3],
+        )));
+
+        // The rotation can no longer be confirmed
+        assert_noop!(
+            Staking::confirm_key_reshare(RuntimeOrigin::signed(7)),
+            Error::<Test>::ReshareNotInProgress
+        );
+
+        // The outcome is kept until a later rotation goes through
+        ReshareData::<Test>::put(ReshareInfo { block_number: deadline + 10, ..Default::default() });
+        System::set_block_number(deadline + 20);
+        Staking::finish_rotation(RotationOutcome::Rotated);
+        assert_eq!(Staking::finished_rotations(reshare_block_number), None);
+        assert_eq!(
+            Staking::finished_rotations(deadline + 10),
+            Some(FinishedRotation {
+                block_number: deadline + 20,
+                outcome: RotationOutcome::Rotated
+            })
+        );
+    });
+}
+
+#[test]
+fn it_migrates_reshare_data_to_v1() {
+    new_test_ext().execute_with(|| {
+        StorageVersion::new(0).put::<Staking>();
+        ParentKeyThreshold::<Test>::kill();
+        NextSigners::<Test>::put(NextSignerInfo {
+            next_signers: vec![6, 7],
+            confirmations: vec![],
+        });
+        frame_support::storage::unhashed::put(
+            &ReshareData::<Test>::hashed_key(),
+            &v0::ReshareInfo::<u64> { new_signer: 7u64.encode(), block_number: 10 },
+        );
+
+        v1::MigrateToV1::<Test>::on_runtime_upgrade();
+
+        assert_eq!(Staking::on_chain_storage_version(), 1);
+        assert_eq!(Staking::parent_key_threshold(), 2);
+        assert_eq!(
+            Staking::reshare_data(),
+            ReshareInfo {
+                new_signer: 7u64.encode(),
+                block_number: 10,
+                old_signers: vec![5, 6],
+                old_threshold: 2,
+                new_signers: vec![6, 7],
+                new_threshold: 2,
+            }
+        );
     });
 }
@@ -86,6 +86,9 @@ parameter_types! {
   pub const MaxBytecodeLength: u32 = 3;
   pub const ProgramDepositPerByte: u32 = 5;
   pub const MaxOwnedPrograms: u32 = 5;
+  pub const ProgramUpgradeDelay: u64 = 5;
+  pub const MaxScheduledProgramUpgrades: u32 = 5;
+  pub const ProgramFamilyDeposit: u32 = 10;
 }
 
 impl pallet_programs::Config for Runtime {
@@ -93,6 +96,9 @@ impl pallet_programs::Config for Runtime {
     type MaxBytecodeLength = MaxBytecodeLength;
     type ProgramDepositPerByte = ProgramDepositPerByte;
     type MaxOwnedPrograms = MaxOwnedPrograms;
+    type ProgramUpgradeDelay = ProgramUpgradeDelay;
+    type MaxScheduledProgramUpgrades = MaxScheduledProgramUpgrades;
+    type ProgramFamilyDeposit = ProgramFamilyDeposit;
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = ();
 }
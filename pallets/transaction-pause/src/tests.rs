@@ -153,6 +153,8 @@ fn paused_transaction_filter_work() {
                 configuration_schema: vec![],
                 auxiliary_data_schema: vec![],
                 oracle_data_pointer: vec![],
+                accepts_prehashed: false,
+                accepts_derivation_path: false,
             });
         assert!(!PausedTransactionFilter::<Runtime>::contains(BALANCE_TRANSFER));
         assert!(!PausedTransactionFilter::<Runtime>::contains(whitelist_address_call));
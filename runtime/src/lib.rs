@@ -184,6 +184,7 @@ pub mod opaque {
             pub babe: Babe,
             pub im_online: ImOnline,
             pub authority_discovery: AuthorityDiscovery,
+            pub propagation: Propagation,
         }
     }
 }
@@ -208,7 +209,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // We update this if the runtime behaviour has changed. When this happens we set the
     // `impl_version` to `0`.
     #[allow(clippy::zero_prefixed_literal)]
-    spec_version: 00_02_00,
+    spec_version: 00_03_00,
 
     // We only bump this if the runtime behaviour remains unchanged, but the implementations details
     // have changed.
@@ -222,7 +223,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     // call index, parameter changes, etc.).
     //
     // The `spec_version` also needs to be bumped in this case.
-    transaction_version: 7,
+    transaction_version: 8,
 
     // Version of the state implementation to use.
     //
@@ -586,9 +587,38 @@ impl_opaque_keys! {
     pub babe: Babe,
     pub im_online: ImOnline,
     pub authority_discovery: AuthorityDiscovery,
+    pub propagation: Propagation,
   }
 }
 
+impl_opaque_keys! {
+  /// The session keys from before validators had a propagation key
+  pub struct SessionKeysWithoutPropagation {
+    pub grandpa: Grandpa,
+    pub babe: Babe,
+    pub im_online: ImOnline,
+    pub authority_discovery: AuthorityDiscovery,
+  }
+}
+
+/// Adds a propagation key to the session keys of each validator.
+///
+/// Until a validator sets new session keys, their `im_online` key is used as their propagation
+/// key.
+pub struct UpgradeSessionKeys;
+impl frame_support::traits::OnRuntimeUpgrade for UpgradeSessionKeys {
+    fn on_runtime_upgrade() -> Weight {
+        Session::upgrade_keys::<SessionKeysWithoutPropagation, _>(|_, old| SessionKeys {
+            grandpa: old.grandpa,
+            babe: old.babe,
+            propagation: sp_core::sr25519::Public::from(old.im_online.clone()).into(),
+            im_online: old.im_online,
+            authority_discovery: old.authority_discovery,
+        });
+        RuntimeBlockWeights::get().max_block
+    }
+}
+
 impl pallet_session::Config for Runtime {
     type Keys = SessionKeys;
     type NextSessionRotation = Babe;
@@ -710,11 +740,23 @@ impl pallet_staking::Config for Runtime {
 
 parameter_types! {
   pub const MaxEndpointLength: u32 = 100;
+  pub const ReshareTimeout: BlockNumber = 10 * MINUTES;
+}
+/// Validators which have been reported for failing to take part in distributed key generation are
+/// less likely to be selected as signers
+pub struct SlashingReliability;
+impl pallet_staking_extension::ValidatorReliability<AccountId> for SlashingReliability {
+    fn unresponsive_reports(validator: &AccountId) -> u32 {
+        Slashing::failed_registrations(validator)
+    }
 }
+
 impl pallet_staking_extension::Config for Runtime {
     type Currency = Balances;
     type MaxEndpointLength = MaxEndpointLength;
     type Randomness = pallet_babe::RandomnessFromOneEpochAgo<Runtime>;
+    type Reliability = SlashingReliability;
+    type ReshareTimeout = ReshareTimeout;
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = weights::pallet_staking_extension::WeightInfo<Runtime>;
 }
@@ -1449,6 +1491,12 @@ impl pallet_slashing::Config for Runtime {
 parameter_types! {
   pub const MaxProgramHashes: u32 = 5;
   pub const KeyVersionNumber: u8 = 1;
+  pub const RegistrationDeposit: Balance = DOLLARS;
+  pub const DkgTimeout: BlockNumber = 10 * MINUTES;
+  pub const MaxDkgAttempts: u32 = 3;
+  pub const DeregistrationTimeout: BlockNumber = 10 * MINUTES;
+  pub const MaxDeregistrationAttempts: u32 = 3;
+  pub const ProgramChangeTimeout: BlockNumber = 10 * MINUTES;
 }
 
 impl pallet_registry::Config for Runtime {
@@ -1456,6 +1504,12 @@ impl pallet_registry::Config for Runtime {
     type MaxProgramHashes = MaxProgramHashes;
     type KeyVersionNumber = KeyVersionNumber;
     type WeightInfo = weights::pallet_registry::WeightInfo<Runtime>;
+    type RegistrationDeposit = RegistrationDeposit;
+    type DkgTimeout = DkgTimeout;
+    type MaxDkgAttempts = MaxDkgAttempts;
+    type DeregistrationTimeout = DeregistrationTimeout;
+    type MaxDeregistrationAttempts = MaxDeregistrationAttempts;
+    type ProgramChangeTimeout = ProgramChangeTimeout;
 }
 
 parameter_types! {
@@ -1463,6 +1517,9 @@ parameter_types! {
   pub const MaxBytecodeLength: u32 = 1_000_000;
   pub const ProgramDepositPerByte: Balance = MILLICENTS;
   pub const MaxOwnedPrograms: u32 = 250;
+  pub const ProgramUpgradeDelay: BlockNumber = 7 * DAYS;
+  pub const MaxScheduledProgramUpgrades: u32 = 50;
+  pub const ProgramFamilyDeposit: Balance = DOLLARS;
 }
 
 impl pallet_programs::Config for Runtime {
@@ -1470,6 +1527,9 @@ impl pallet_programs::Config for Runtime {
     type MaxBytecodeLength = MaxBytecodeLength;
     type ProgramDepositPerByte = ProgramDepositPerByte;
     type MaxOwnedPrograms = MaxOwnedPrograms;
+    type ProgramUpgradeDelay = ProgramUpgradeDelay;
+    type MaxScheduledProgramUpgrades = MaxScheduledProgramUpgrades;
+    type ProgramFamilyDeposit = ProgramFamilyDeposit;
     type RuntimeEvent = RuntimeEvent;
     type WeightInfo = weights::pallet_programs::WeightInfo<Runtime>;
 }
@@ -1493,6 +1553,19 @@ impl pallet_parameters::Config for Runtime {
     type WeightInfo = weights::pallet_parameters::WeightInfo<Runtime>;
 }
 
+parameter_types! {
+  pub const MaxOracleKeyLength: u32 = 256;
+  pub const MaxOracleValueLength: u32 = 100_000;
+}
+
+impl pallet_oracle::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type UpdateOrigin = EnsureRoot<AccountId>;
+    type MaxOracleKeyLength = MaxOracleKeyLength;
+    type MaxOracleValueLength = MaxOracleValueLength;
+    type WeightInfo = weights::pallet_oracle::WeightInfo<Runtime>;
+}
+
 construct_runtime!(
   pub enum Runtime
   {
@@ -1545,6 +1618,7 @@ construct_runtime!(
     TransactionPause: pallet_transaction_pause = 54,
     Propagation: pallet_propagation = 55,
     Parameters: pallet_parameters = 56,
+    Oracle: pallet_oracle = 57,
   }
 );
 
@@ -1592,7 +1666,28 @@ pub type Executive = frame_executive::Executive<
     Migrations,
 >;
 
-type Migrations = (pallet_nomination_pools::migration::v2::MigrateToV2<Runtime>,);
+type Migrations = (
+    pallet_nomination_pools::migration::v2::MigrateToV2<Runtime>,
+    UpgradeSessionKeys,
+    pallet_parameters::migrations::v1::MigrateToV1<
+        Runtime,
+        ConstU32<{ entropy_shared::INITIAL_MAX_RATE_LIMIT_REQUESTS }>,
+    >,
+    pallet_parameters::migrations::v2::MigrateToV2<
+        Runtime,
+        ConstU32<{ entropy_shared::INITIAL_RECOVERY_KEY_DELAY }>,
+    >,
+    pallet_programs::migrations::v1::MigrateToV1<Runtime>,
+    pallet_programs::migrations::v2::MigrateToV2<Runtime>,
+    pallet_staking_extension::migrations::v1::MigrateToV1<Runtime>,
+    pallet_registry::migrations::v1::MigrateToV1<Runtime>,
+    pallet_registry::migrations::v2::MigrateToV2<Runtime>,
+    pallet_registry::migrations::v3::MigrateToV3<Runtime>,
+    pallet_registry::migrations::v4::MigrateToV4<Runtime>,
+    pallet_registry::migrations::v5::MigrateToV5<Runtime>,
+    pallet_registry::migrations::v6::MigrateToV6<Runtime>,
+    pallet_registry::migrations::v7::MigrateToV7<Runtime>,
+);
 
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
@@ -1621,6 +1716,7 @@ mod benches {
       [pallet_nomination_pools, NominationPoolsBench::<Runtime>]
       [pallet_multisig, Multisig]
       [pallet_offences, OffencesBench::<Runtime>]
+      [pallet_oracle, Oracle]
       [pallet_preimage, Preimage]
       [pallet_parameters, Parameters]
       [pallet_proxy, Proxy]
@@ -1801,6 +1897,16 @@ impl_runtime_apis! {
     }
   }
 
+  impl pallet_registry::RegistryApi<Block, AccountId> for Runtime {
+    fn registration_status(who: AccountId) -> Option<pallet_registry::RegistrationStatus> {
+      Registry::registration_status(&who)
+    }
+
+    fn signers(verifying_key: Vec<u8>) -> Option<Vec<AccountId>> {
+      Registry::select_signers(verifying_key)
+    }
+  }
+
   impl sp_genesis_builder::GenesisBuilder<Block> for Runtime {
     fn create_default_config() -> Vec<u8> {
         create_default_config::<RuntimeGenesisConfig>()
@@ -78,4 +78,26 @@ impl<T: frame_system::Config> pallet_parameters::WeightInfo for WeightInfo<T> {
 			.saturating_add(Weight::from_parts(0, 0))
 			.saturating_add(T::DbWeight::get().writes(1))
 	}
+	/// Storage: `Parameters::MaxRateLimitRequests` (r:0 w:1)
+	/// Proof: `Parameters::MaxRateLimitRequests` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	fn change_max_rate_limit_requests() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_000_000 picoseconds.
+		Weight::from_parts(4_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `Parameters::RecoveryKeyDelay` (r:0 w:1)
+	/// Proof: `Parameters::RecoveryKeyDelay` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	fn change_recovery_key_delay() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0`
+		//  Estimated: `0`
+		// Minimum execution time: 4_000_000 picoseconds.
+		Weight::from_parts(4_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 0))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
 }
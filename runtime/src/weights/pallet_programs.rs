@@ -80,4 +80,56 @@ impl<T: frame_system::Config> pallet_programs::WeightInfo for WeightInfo<T> {
 			.saturating_add(T::DbWeight::get().writes(2))
 			.saturating_add(Weight::from_parts(0, 32).saturating_mul(p.into()))
 	}
+	/// Storage: `Programs::Programs` (r:1 w:1)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `Programs::ProgramFamilyCount` (r:1 w:1)
+	/// Proof: `Programs::ProgramFamilyCount` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::ProgramFamilies` (r:0 w:1)
+	/// Proof: `Programs::ProgramFamilies` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::ProgramFamilyVersions` (r:0 w:1)
+	/// Proof: `Programs::ProgramFamilyVersions` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn create_program_family() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `328`
+		//  Estimated: `3793`
+		// Minimum execution time: 34_000_000 picoseconds.
+		Weight::from_parts(35_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 3793))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(5))
+	}
+	/// Storage: `Programs::ProgramFamilies` (r:1 w:1)
+	/// Proof: `Programs::ProgramFamilies` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::Programs` (r:2 w:2)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::ScheduledProgramUpgrades` (r:2 w:2)
+	/// Proof: `Programs::ScheduledProgramUpgrades` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn schedule_program_upgrade() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `2145`
+		//  Estimated: `8085`
+		// Minimum execution time: 34_000_000 picoseconds.
+		Weight::from_parts(35_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 8085))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(5))
+	}
+	/// Storage: `Programs::ProgramFamilies` (r:1 w:1)
+	/// Proof: `Programs::ProgramFamilies` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::ScheduledProgramUpgrades` (r:1 w:1)
+	/// Proof: `Programs::ScheduledProgramUpgrades` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::Programs` (r:1 w:1)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn cancel_program_upgrade() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `498`
+		//  Estimated: `3963`
+		// Minimum execution time: 24_000_000 picoseconds.
+		Weight::from_parts(25_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 3963))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
 }
\ No newline at end of file
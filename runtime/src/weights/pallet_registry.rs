@@ -127,10 +127,16 @@ impl<T: frame_system::Config> pallet_registry::WeightInfo for WeightInfo<T> {
 			.saturating_add(T::DbWeight::get().reads(2))
 			.saturating_add(T::DbWeight::get().writes(2))
 	}
-	/// Storage: `Programs::Programs` (r:2 w:2)
-	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
-	/// Storage: `Registry::Registered` (r:1 w:1)
+	/// Storage: `Registry::Registered` (r:1 w:0)
 	/// Proof: `Registry::Registered` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::PendingProgramChanges` (r:1 w:1)
+	/// Proof: `Registry::PendingProgramChanges` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::Programs` (r:1 w:1)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::ProgramChanges` (r:1 w:1)
+	/// Proof: `Registry::ProgramChanges` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::ProgramChangeDeadlines` (r:1 w:1)
+	/// Proof: `Registry::ProgramChangeDeadlines` (`max_values`: None, `max_size`: None, mode: `Measured`)
 	/// The range of component `n` is `[1, 5]`.
 	/// The range of component `o` is `[1, 5]`.
 	fn change_program_instance(n: u32, o: u32, ) -> Weight {
@@ -144,8 +150,8 @@ impl<T: frame_system::Config> pallet_registry::WeightInfo for WeightInfo<T> {
 			.saturating_add(Weight::from_parts(2_204_761, 0).saturating_mul(n.into()))
 			// Standard Error: 526_434
 			.saturating_add(Weight::from_parts(2_088_095, 0).saturating_mul(o.into()))
-			.saturating_add(T::DbWeight::get().reads(3))
-			.saturating_add(T::DbWeight::get().writes(3))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(4))
 			.saturating_add(Weight::from_parts(0, 33).saturating_mul(o.into()))
 	}
 	/// Storage: `Registry::Registered` (r:1 w:1)
@@ -223,4 +229,167 @@ impl<T: frame_system::Config> pallet_registry::WeightInfo for WeightInfo<T> {
 			.saturating_add(T::DbWeight::get().reads(4))
 			.saturating_add(T::DbWeight::get().writes(3))
 	}
+	/// Storage: `Registry::Registered` (r:1 w:0)
+	/// Proof: `Registry::Registered` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Parameters::MaxRateLimitRequests` (r:1 w:0)
+	/// Proof: `Parameters::MaxRateLimitRequests` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::RateLimits` (r:0 w:1)
+	/// Proof: `Registry::RateLimits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn set_rate_limit() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `328`
+		//  Estimated: `3793`
+		// Minimum execution time: 11_000_000 picoseconds.
+		Weight::from_parts(12_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 3793))
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `Registry::Registered` (r:1 w:1)
+	/// Proof: `Registry::Registered` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::Programs` (r:1 w:1)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::ModifiableKeys` (r:1 w:1)
+	/// Proof: `Registry::ModifiableKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::Deregistrations` (r:1 w:1)
+	/// Proof: `Registry::Deregistrations` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::RateLimits` (r:0 w:1)
+	/// Proof: `Registry::RateLimits` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::Deregistering` (r:0 w:1)
+	/// Proof: `Registry::Deregistering` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// The range of component `p` is `[1, 5]`.
+	fn deregister(p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `565 + p * (32 ±0)`
+		//  Estimated: `4030 + p * (2507 ±0)`
+		// Minimum execution time: 22_000_000 picoseconds.
+		Weight::from_parts(23_250_000, 0)
+			.saturating_add(Weight::from_parts(0, 4030))
+			// Standard Error: 41_432
+			.saturating_add(Weight::from_parts(2_458_333, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(p.into())))
+			.saturating_add(T::DbWeight::get().writes(5))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(p.into())))
+			.saturating_add(Weight::from_parts(0, 2507).saturating_mul(p.into()))
+	}
+	/// Storage: `StakingExtension::ThresholdToStash` (r:1 w:0)
+	/// Proof: `StakingExtension::ThresholdToStash` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Session::Validators` (r:1 w:0)
+	/// Proof: `Session::Validators` (`max_values`: Some(1), `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::Deregistering` (r:1 w:1)
+	/// Proof: `Registry::Deregistering` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// The range of component `c` is `[0, 2]`.
+	fn confirm_deregistration(c: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1290 + c * (32 ±0)`
+		//  Estimated: `4755 + c * (32 ±0)`
+		// Minimum execution time: 17_000_000 picoseconds.
+		Weight::from_parts(18_166_666, 0)
+			.saturating_add(Weight::from_parts(0, 4755))
+			// Standard Error: 57_735
+			.saturating_add(Weight::from_parts(250_000, 0).saturating_mul(c.into()))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(Weight::from_parts(0, 32).saturating_mul(c.into()))
+	}
+	/// Storage: `Registry::Registered` (r:1 w:0)
+	/// Proof: `Registry::Registered` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::Registering` (r:1 w:1)
+	/// Proof: `Registry::Registering` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::Programs` (r:1 w:1)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::DkgDeadlines` (r:1 w:1)
+	/// Proof: `Registry::DkgDeadlines` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// The range of component `p` is `[1, 5]`.
+	fn register_with_imported_key(p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `401`
+		//  Estimated: `3866`
+		// Minimum execution time: 21_000_000 picoseconds.
+		Weight::from_parts(19_600_000, 0)
+			.saturating_add(Weight::from_parts(0, 3866))
+			// Standard Error: 134_629
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	/// Storage: `Registry::Registered` (r:1 w:0)
+	/// Proof: `Registry::Registered` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::RecoveryKeys` (r:0 w:1)
+	/// Proof: `Registry::RecoveryKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	fn set_recovery_key() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `328`
+		//  Estimated: `3793`
+		// Minimum execution time: 11_000_000 picoseconds.
+		Weight::from_parts(12_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 3793))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `StakingExtension::ThresholdToStash` (r:1 w:0)
+	/// Proof: `StakingExtension::ThresholdToStash` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::Registering` (r:1 w:1)
+	/// Proof: `Registry::Registering` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	fn refuse_register() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1412`
+		//  Estimated: `4877`
+		// Minimum execution time: 27_000_000 picoseconds.
+		Weight::from_parts(28_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 4877))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	/// Storage: `StakingExtension::ThresholdToStash` (r:1 w:0)
+	/// Proof: `StakingExtension::ThresholdToStash` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::PendingProgramChanges` (r:1 w:1)
+	/// Proof: `Registry::PendingProgramChanges` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::Registered` (r:1 w:1)
+	/// Proof: `Registry::Registered` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::Programs` (r:5 w:5)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// The range of component `p` is `[0, 5]`.
+	fn confirm_program_change(p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1010 + p * (33 ±0)`
+		//  Estimated: `4475 + p * (2508 ±0)`
+		// Minimum execution time: 22_000_000 picoseconds.
+		Weight::from_parts(23_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 4475))
+			// Standard Error: 120_000
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(p.into())))
+			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(p.into())))
+			.saturating_add(Weight::from_parts(0, 2508).saturating_mul(p.into()))
+	}
+	/// Storage: `StakingExtension::ThresholdToStash` (r:1 w:0)
+	/// Proof: `StakingExtension::ThresholdToStash` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::PendingProgramChanges` (r:1 w:1)
+	/// Proof: `Registry::PendingProgramChanges` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Registry::Registered` (r:1 w:0)
+	/// Proof: `Registry::Registered` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Programs::Programs` (r:5 w:5)
+	/// Proof: `Programs::Programs` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// The range of component `p` is `[0, 5]`.
+	fn refuse_program_change(p: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `1010 + p * (33 ±0)`
+		//  Estimated: `4475 + p * (2508 ±0)`
+		// Minimum execution time: 21_000_000 picoseconds.
+		Weight::from_parts(22_000_000, 0)
+			.saturating_add(Weight::from_parts(0, 4475))
+			// Standard Error: 120_000
+			.saturating_add(Weight::from_parts(2_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(p.into())))
+			.saturating_add(T::DbWeight::get().writes(1))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(p.into())))
+			.saturating_add(Weight::from_parts(0, 2508).saturating_mul(p.into()))
+	}
 }
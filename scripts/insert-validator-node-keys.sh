@@ -33,3 +33,9 @@ for name in "${!keyTypes[@]}"; do
     --suri "$secretPhrase//$name" \
     --key-type $name
 done
+
+# Until new session keys are set, the propagation key is the same as the im online key
+$keyInsert \
+  --scheme Sr25519 \
+  --suri "$secretPhrase//imon" \
+  --key-type prop